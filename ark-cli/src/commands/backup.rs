@@ -1,25 +1,43 @@
-use std::{io::Write, path::PathBuf};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
 
 use crate::{
-    create_dir_all, dir, discover_roots, home_dir, storages_exists, timestamp,
-    AppError, CopyOptions, File, ARK_BACKUPS_PATH, ARK_FOLDER,
+    create_dir_all, discover_roots, home_dir, parse_roots, storages_exists,
+    timestamp, AppError, File, ARK_BACKUPS_PATH, ARK_FOLDER,
     ROOTS_CFG_FILENAME,
 };
 
+/// File of a backup containing the timestamp of the backup it extends
+const BASE_FILENAME: &str = "base";
+const MANIFEST_EXTENSION: &str = "manifest";
+
+/// Size and modification time of every file of a backed up `.ark` folder,
+/// keyed by paths relative to it
+pub type Manifest = BTreeMap<PathBuf, (u64, u64)>;
+
 #[derive(Clone, Debug, clap::Args)]
 #[clap(name = "backup", about = "Backup the ark managed folder")]
 pub struct Backup {
     #[clap(value_parser, help = "Path to the root directory")]
     roots_cfg: Option<PathBuf>,
+    #[clap(
+        long,
+        action,
+        help = "Only copy files changed since the latest backup"
+    )]
+    incremental: bool,
 }
 
 impl Backup {
     pub fn run(&self) -> Result<(), AppError> {
         let timestamp = timestamp().as_secs();
-        let backup_dir = home_dir()
-            .ok_or(AppError::HomeDirNotFound)?
-            .join(ARK_BACKUPS_PATH)
-            .join(timestamp.to_string());
+        let backups = backups_dir()?;
+        let backup_dir = backups.join(timestamp.to_string());
 
         if backup_dir.is_dir() {
             println!("Wait at least 1 second, please!");
@@ -45,6 +63,17 @@ impl Backup {
             std::process::exit(0)
         }
 
+        let base = if self.incremental {
+            let base = list_backups(&backups)?.pop();
+            match &base {
+                Some(base) => println!("Incremental backup on top of {}", base),
+                None => println!("No previous backup, creating a full one"),
+            }
+            base
+        } else {
+            None
+        };
+
         create_dir_all(&backup_dir).map_err(|_| {
             AppError::BackupCreationError(
                 "Couldn't create backup directory!".to_owned(),
@@ -61,6 +90,10 @@ impl Backup {
             }
         });
 
+        if let Some(base) = base {
+            fs::write(backup_dir.join(BASE_FILENAME), base.to_string())?;
+        }
+
         println!("Performing backups:");
         valid
             .into_iter()
@@ -68,13 +101,30 @@ impl Backup {
             .for_each(|(i, root)| {
                 println!("\tRoot {}", root.display());
                 let storage_backup = backup_dir.join(i.to_string());
+                let ark = root.join(ARK_FOLDER);
 
-                let mut options = CopyOptions::new();
-                options.overwrite = true;
-                options.copy_inside = true;
-
-                let result =
-                    dir::copy(root.join(ARK_FOLDER), storage_backup, &options);
+                let previous = base.and_then(|base| {
+                    resolve_manifest(&backups, base, &root)
+                        .ok()
+                        .flatten()
+                });
+                // the manifest lists exactly the files the copy step saw,
+                // so files changed during the backup are copied next time
+                let result = scan(&ark).and_then(|manifest| {
+                    match previous {
+                        Some(previous) => copy_changed(
+                            &ark,
+                            &storage_backup,
+                            &manifest,
+                            &previous,
+                        )?,
+                        None => copy_all(&ark, &storage_backup, &manifest)?,
+                    }
+                    write_manifest(
+                        &storage_backup.with_extension(MANIFEST_EXTENSION),
+                        &manifest,
+                    )
+                });
 
                 if let Err(e) = result {
                     println!("\t\tFailed to copy storages!\n\t\t{}", e);
@@ -86,3 +136,167 @@ impl Backup {
         Ok(())
     }
 }
+
+pub fn backups_dir() -> Result<PathBuf, AppError> {
+    Ok(home_dir()
+        .ok_or(AppError::HomeDirNotFound)?
+        .join(ARK_BACKUPS_PATH))
+}
+
+/// Timestamps of all backups, oldest first
+pub fn list_backups(backups: &Path) -> Result<Vec<u64>, AppError> {
+    if !backups.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut timestamps: Vec<u64> = fs::read_dir(backups)?
+        .flatten()
+        .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+        .collect();
+    timestamps.sort();
+    Ok(timestamps)
+}
+
+/// Timestamp of the backup the incremental backup extends
+pub fn backup_base(backup_dir: &Path) -> Option<u64> {
+    fs::read_to_string(backup_dir.join(BASE_FILENAME))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Folder of the root in the backup, roots are numbered in order
+/// of the roots file of the backup
+pub fn root_backup(backup_dir: &Path, root: &Path) -> Option<PathBuf> {
+    let canonical =
+        |path: &Path| fs::canonicalize(path).unwrap_or(path.to_path_buf());
+    let root = canonical(root);
+    let roots =
+        parse_roots(File::open(backup_dir.join(ROOTS_CFG_FILENAME)).ok()?);
+    let index = roots
+        .iter()
+        .position(|backed_up| canonical(backed_up) == root)?;
+    Some(backup_dir.join(index.to_string()))
+}
+
+/// Manifest of the root in the backup. Backups made before manifests
+/// were introduced are full copies, so their manifest is built from files.
+pub fn resolve_manifest(
+    backups: &Path,
+    timestamp: u64,
+    root: &Path,
+) -> Result<Option<Manifest>, AppError> {
+    let backup_dir = backups.join(timestamp.to_string());
+    let Some(folder) = root_backup(&backup_dir, root) else {
+        return Ok(None);
+    };
+    let path = folder.with_extension(MANIFEST_EXTENSION);
+    if path.is_file() {
+        return Ok(Some(read_manifest(&path)?));
+    }
+    Ok(Some(scan(&folder)?))
+}
+
+fn read_manifest(path: &Path) -> Result<Manifest, AppError> {
+    let mut manifest = Manifest::new();
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        let mut parts = line.rsplitn(3, '\t');
+        let (Some(modified), Some(size), Some(file)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+        if let (Ok(size), Ok(modified)) = (size.parse(), modified.parse()) {
+            manifest.insert(PathBuf::from(file), (size, modified));
+        }
+    }
+    Ok(manifest)
+}
+
+fn write_manifest(path: &Path, manifest: &Manifest) -> Result<(), AppError> {
+    let mut output = File::create(path)?;
+    for (file, (size, modified)) in manifest {
+        writeln!(output, "{}\t{}\t{}", file.display(), size, modified)?;
+    }
+    Ok(())
+}
+
+/// Files of the folder with their sizes and modification times
+pub fn scan(folder: &Path) -> Result<Manifest, AppError> {
+    fn walk(
+        folder: &Path,
+        base: &Path,
+        manifest: &mut Manifest,
+    ) -> Result<(), AppError> {
+        for entry in fs::read_dir(folder)?.flatten() {
+            let path = entry.path();
+            let metadata = entry.metadata()?;
+            if metadata.is_dir() {
+                walk(&path, base, manifest)?;
+                continue;
+            }
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |time| time.as_secs());
+            // SAFETY: walked paths are always inside the base folder
+            let relative = path.strip_prefix(base).unwrap().to_path_buf();
+            manifest.insert(relative, (metadata.len(), modified));
+        }
+        Ok(())
+    }
+
+    let mut manifest = Manifest::new();
+    if folder.is_dir() {
+        walk(folder, folder, &mut manifest)?;
+    }
+    Ok(manifest)
+}
+
+/// Copy all files of the manifest
+fn copy_all(
+    ark: &Path,
+    destination: &Path,
+    manifest: &Manifest,
+) -> Result<(), AppError> {
+    create_dir_all(destination)?;
+    for file in manifest.keys() {
+        copy_file(ark, destination, file)?;
+    }
+    Ok(())
+}
+
+/// Copy files of the manifest which are new or changed since the previous
+/// backup
+fn copy_changed(
+    ark: &Path,
+    destination: &Path,
+    manifest: &Manifest,
+    previous: &Manifest,
+) -> Result<(), AppError> {
+    let mut copied = 0;
+    for (file, state) in manifest {
+        if previous.get(file) == Some(state) {
+            continue;
+        }
+        copy_file(ark, destination, file)?;
+        copied += 1;
+    }
+    println!("\t\t{} files changed since the previous backup", copied);
+    Ok(())
+}
+
+fn copy_file(
+    ark: &Path,
+    destination: &Path,
+    file: &Path,
+) -> Result<(), AppError> {
+    let target = destination.join(file);
+    if let Some(parent) = target.parent() {
+        create_dir_all(parent)?;
+    }
+    fs::copy(ark.join(file), target)?;
+    Ok(())
+}
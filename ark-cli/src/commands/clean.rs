@@ -0,0 +1,227 @@
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    provide_root, util::provide_index, AppError, ResourceId, ARK_FOLDER,
+};
+
+const CACHE_FOLDER: &str = "cache";
+
+/// Temporary files of atomic writes are removed on drop,
+/// younger ones may still be in use by another process
+const ORPHAN_AGE: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Clone, Debug, clap::Args)]
+#[clap(
+    name = "clean",
+    about = "Remove stale cache entries, old versions and temporary files"
+)]
+pub struct Clean {
+    #[clap(value_parser, help = "Path to the root directory")]
+    root_dir: Option<PathBuf>,
+    #[clap(
+        long,
+        default_value = "1",
+        value_parser = clap::value_parser!(usize).range(1..),
+        help = "Number of latest versions of atomic files to keep"
+    )]
+    keep_versions: usize,
+    #[clap(long, action, help = "Only print what would be removed")]
+    dry_run: bool,
+}
+
+impl Clean {
+    pub fn run(&self) -> Result<(), AppError> {
+        let root = provide_root(&self.root_dir)?;
+        let ark = root.join(ARK_FOLDER);
+        if !ark.is_dir() {
+            println!("Nothing to clean in {}", root.display());
+            return Ok(());
+        }
+
+        let ids: HashSet<ResourceId> = provide_index(&root)
+            .resources()
+            .iter()
+            .map(|resource| resource.id().clone())
+            .collect();
+
+        let mut cleanup = Cleanup {
+            dry_run: self.dry_run,
+            entries: 0,
+            bytes: 0,
+            removed_folders: vec![],
+        };
+
+        println!("Cache of missing resources:");
+        let cache = ark.join(CACHE_FOLDER);
+        if cache.is_dir() {
+            clean_cache(&cache, &ids, &mut cleanup)?;
+        }
+        let removed = cleanup.entries;
+
+        println!("Old versions and temporary files:");
+        prune_versions(&ark, self.keep_versions, &mut cleanup)?;
+
+        let verb = if self.dry_run {
+            "Would reclaim"
+        } else {
+            "Reclaimed"
+        };
+        println!(
+            "{} {} bytes: {} cache entries, {} files",
+            verb,
+            cleanup.bytes,
+            removed,
+            cleanup.entries - removed
+        );
+        Ok(())
+    }
+}
+
+struct Cleanup {
+    dry_run: bool,
+    entries: usize,
+    bytes: u64,
+    /// Removed folders are still present during a dry run,
+    /// they are remembered to not count their files twice
+    removed_folders: Vec<PathBuf>,
+}
+
+impl Cleanup {
+    fn remove(&mut self, path: &Path) -> io::Result<()> {
+        println!("\t{}", path.display());
+        self.entries += 1;
+        self.bytes += size(path);
+        if path.is_dir() {
+            self.removed_folders.push(path.to_path_buf());
+        }
+        if self.dry_run {
+            return Ok(());
+        }
+        if path.is_dir() {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_file(path)
+        }
+    }
+
+    fn is_removed(&self, path: &Path) -> bool {
+        self.removed_folders
+            .iter()
+            .any(|folder| path.starts_with(folder))
+    }
+}
+
+/// Cache entries are folders named by resource ids, possibly grouped
+/// into folders by parameters like thumbnail sizes
+fn clean_cache(
+    dir: &Path,
+    ids: &HashSet<ResourceId>,
+    cleanup: &mut Cleanup,
+) -> io::Result<()> {
+    for entry in fs::read_dir(dir)?.flatten() {
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
+        }
+        if has_subfolders(&path)? {
+            clean_cache(&path, ids, cleanup)?;
+            continue;
+        }
+        let id = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| ResourceId::from_str(name).ok());
+        if let Some(id) = id {
+            if !ids.contains(&id) {
+                cleanup.remove(&path)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Walk all atomic files of the `.ark` folder, removing versions older
+/// than the kept ones and temporary files left by interrupted writes
+fn prune_versions(
+    dir: &Path,
+    keep: usize,
+    cleanup: &mut Cleanup,
+) -> io::Result<()> {
+    let mut versions = vec![];
+    let mut others = vec![];
+    for entry in fs::read_dir(dir)?.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if !cleanup.is_removed(&path) {
+                prune_versions(&path, keep, cleanup)?;
+            }
+        } else {
+            match parse_version(dir, &path) {
+                Some(version) => versions.push((version, path)),
+                None => others.push(path),
+            }
+        }
+    }
+
+    // Only folders with versions are atomic files
+    let Some(latest) = versions.iter().map(|(version, _)| *version).max()
+    else {
+        return Ok(());
+    };
+    for (version, path) in versions {
+        if version + keep <= latest {
+            cleanup.remove(&path)?;
+        }
+    }
+    let now = SystemTime::now();
+    for path in others {
+        let age = fs::metadata(&path)?
+            .modified()
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok());
+        if age.is_some_and(|age| age > ORPHAN_AGE) {
+            cleanup.remove(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Versions are named `<folder>_<app id>.<version>`
+fn parse_version(dir: &Path, path: &Path) -> Option<usize> {
+    let folder = dir.file_name()?.to_str()?;
+    let name = path.file_name()?.to_str()?;
+    let (prefix, version) = name.rsplit_once('.')?;
+    if !prefix.starts_with(&format!("{}_", folder)) {
+        return None;
+    }
+    version.parse().ok()
+}
+
+fn has_subfolders(dir: &Path) -> io::Result<bool> {
+    Ok(fs::read_dir(dir)?
+        .flatten()
+        .any(|entry| entry.path().is_dir()))
+}
+
+fn size(path: &Path) -> u64 {
+    if path.is_dir() {
+        fs::read_dir(path)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|entry| size(&entry.path()))
+                    .sum()
+            })
+            .unwrap_or(0)
+    } else {
+        fs::metadata(path)
+            .map(|metadata| metadata.len())
+            .unwrap_or(0)
+    }
+}
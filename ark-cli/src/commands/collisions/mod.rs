@@ -0,0 +1,241 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    config::output_format, monitor_index, provide_root, util::provide_index,
+    AppError, Format, ResourceId,
+};
+
+pub mod resolution;
+
+use resolution::{rehash, Resolution, Resolutions};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Action {
+    /// Keep the resources sharing the id
+    Ignore,
+    /// Tell the resources apart by Blake3 ids
+    Rehash,
+    /// Tell the resources apart by ids given with `--map`
+    Override,
+}
+
+#[derive(Clone, Debug, clap::Args)]
+#[clap(
+    name = "collisions",
+    about = "Find and resolve collisions in the ark managed folder"
+)]
+pub struct Collisions {
+    #[clap(value_parser, help = "Path to the root directory")]
+    root_dir: Option<PathBuf>,
+    #[clap(
+        long,
+        value_enum,
+        help = "Output format, raw unless configured otherwise"
+    )]
+    format: Option<Format>,
+    #[clap(
+        long,
+        value_enum,
+        conflicts_with = "interactive",
+        help = "Resolve unresolved collisions with this action"
+    )]
+    resolve: Option<Action>,
+    #[clap(
+        short,
+        long,
+        action,
+        help = "Ask how to resolve every unresolved collision"
+    )]
+    interactive: bool,
+    #[clap(long, help = "Only resolve the collision of this id")]
+    id: Option<String>,
+    #[clap(
+        long,
+        value_name = "PATH=ID",
+        help = "Id of a colliding resource, used by the override action"
+    )]
+    map: Vec<String>,
+    #[clap(
+        long,
+        value_name = "ID",
+        conflicts_with_all = ["resolve", "interactive"],
+        help = "Forget the decision about the collision of this id"
+    )]
+    forget: Option<String>,
+}
+
+impl Collisions {
+    pub fn run(&self) -> Result<(), AppError> {
+        if let Some(id) = &self.forget {
+            let root = provide_root(&self.root_dir)?;
+            let mut resolutions = Resolutions::load(&root)?;
+            if resolutions.forget(id) {
+                resolutions.store(&root)?;
+                println!("Collision of {} is reported again", id);
+            } else {
+                println!("No decision about {}", id);
+            }
+            return Ok(());
+        }
+        if self.resolve.is_none() && !self.interactive {
+            return monitor_index(
+                &self.root_dir,
+                None,
+                output_format(self.format),
+            );
+        }
+
+        let root = provide_root(&self.root_dir)?;
+        let index = provide_index(&root);
+        let mut resolutions = Resolutions::load(&root)?;
+
+        let mut collisions: Vec<(ResourceId, HashSet<PathBuf>)> = index
+            .collisions()
+            .into_iter()
+            .filter(|(id, paths)| match &self.id {
+                // An explicitly chosen collision can be resolved again
+                Some(chosen) => id.to_string() == *chosen,
+                None => !resolutions.is_resolved(id, paths),
+            })
+            .collect();
+        collisions.sort_by_key(|(id, _)| id.to_string());
+
+        if collisions.is_empty() {
+            println!("No unresolved collisions");
+            return Ok(());
+        }
+
+        let mut resolved = 0;
+        for (id, paths) in collisions {
+            let mut sorted: Vec<&PathBuf> = paths.iter().collect();
+            sorted.sort();
+            println!("Id {} calculated {} times:", id, paths.len());
+            for path in &sorted {
+                println!("\t{}", path.display());
+            }
+
+            let action = match self.resolve {
+                Some(action) => Some(action),
+                None => ask_action()?,
+            };
+            let Some(action) = action else {
+                println!("\tSkipped");
+                continue;
+            };
+
+            let resolution = match action {
+                Action::Ignore => Some(Resolution::Ignore),
+                Action::Rehash => {
+                    rehash(&root, &paths)?.map(|ids| Resolution::Rehash { ids })
+                }
+                Action::Override => self
+                    .override_ids(&sorted)?
+                    .map(|ids| Resolution::Override { ids }),
+            };
+            match resolution {
+                Some(resolution) => {
+                    print_resolution(&resolution);
+                    resolutions.resolve(&id, &paths, resolution);
+                    resolved += 1;
+                }
+                None if action == Action::Rehash => println!(
+                    "\tContents are identical, consider `ark-cli dedupe`"
+                ),
+                None => println!("\tSkipped"),
+            }
+        }
+
+        resolutions.store(&root)?;
+        println!("Resolved {} collisions", resolved);
+        Ok(())
+    }
+
+    /// Ids of the colliding resources given by `--map`, or asked for
+    /// interactively. `None` if some of them are missing or not unique.
+    fn override_ids(
+        &self,
+        paths: &[&PathBuf],
+    ) -> Result<Option<BTreeMap<PathBuf, String>>, AppError> {
+        let mut mapping = BTreeMap::new();
+        for entry in &self.map {
+            let (path, id) = entry.rsplit_once('=').ok_or_else(|| {
+                AppError::FileOperationError(format!(
+                    "Mapping {} must be given as PATH=ID",
+                    entry
+                ))
+            })?;
+            mapping.insert(PathBuf::from(path), id.to_owned());
+        }
+
+        let mut ids = BTreeMap::new();
+        for &path in paths {
+            let id = match mapping.get(path) {
+                Some(id) => id.clone(),
+                None if self.interactive => ask_id(path)?,
+                None => {
+                    println!("\tNo id given for {}", path.display());
+                    return Ok(None);
+                }
+            };
+            if id.is_empty() {
+                return Ok(None);
+            }
+            ids.insert(path.clone(), id);
+        }
+
+        let unique: HashSet<&String> = ids.values().collect();
+        if unique.len() != ids.len() {
+            println!("\tOverridden ids must be unique");
+            return Ok(None);
+        }
+        Ok(Some(ids))
+    }
+}
+
+fn print_resolution(resolution: &Resolution) {
+    match resolution {
+        Resolution::Ignore => println!("\tIgnored"),
+        Resolution::Rehash { ids } | Resolution::Override { ids } => {
+            for (path, id) in ids {
+                println!("\t{} -> {}", path.display(), id);
+            }
+        }
+    }
+}
+
+fn read_answer(prompt: &str) -> Result<Option<String>, AppError> {
+    print!("{}", prompt);
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(answer.trim().to_owned()))
+}
+
+/// Ask what to do with the collision, `None` means it is skipped
+fn ask_action() -> Result<Option<Action>, AppError> {
+    loop {
+        let prompt = "[i]gnore, [r]ehash, [o]verride or [s]kip: ";
+        let Some(answer) = read_answer(prompt)? else {
+            return Ok(None);
+        };
+        match answer.to_lowercase().as_str() {
+            "i" => return Ok(Some(Action::Ignore)),
+            "r" => return Ok(Some(Action::Rehash)),
+            "o" => return Ok(Some(Action::Override)),
+            "s" => return Ok(None),
+            _ => println!("Please enter i, r, o or s"),
+        }
+    }
+}
+
+fn ask_id(path: &Path) -> Result<String, AppError> {
+    let prompt = format!("Id of {}: ", path.display());
+    Ok(read_answer(&prompt)?.unwrap_or_default())
+}
@@ -0,0 +1,123 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use data_resource::ResourceId as _;
+use dev_hash::Blake3;
+use serde::{Deserialize, Serialize};
+
+use crate::{AppError, ResourceId, ARK_FOLDER};
+
+/// Decisions about collisions, relative to the `.ark` folder
+pub const RESOLUTIONS_FILE: &str = "user/collisions.json";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Resolution {
+    /// Resources are known to share the id
+    Ignore,
+    /// Resources are told apart by ids of a cryptographic hash
+    Rehash { ids: BTreeMap<PathBuf, String> },
+    /// Resources are told apart by ids chosen by the user
+    Override { ids: BTreeMap<PathBuf, String> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Decision {
+    /// Colliding paths at the moment of the decision. The decision
+    /// no longer applies once the colliding paths change.
+    pub paths: BTreeSet<PathBuf>,
+    #[serde(flatten)]
+    pub resolution: Resolution,
+}
+
+/// Decisions about collisions of a root, keyed by the colliding id
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Resolutions(BTreeMap<String, Decision>);
+
+impl Resolutions {
+    fn path(root: &Path) -> PathBuf {
+        root.join(ARK_FOLDER).join(RESOLUTIONS_FILE)
+    }
+
+    pub fn load(root: &Path) -> Result<Self, AppError> {
+        let path = Self::path(root);
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        serde_json::from_slice(&fs::read(&path)?).map_err(|e| {
+            AppError::FileOperationError(format!(
+                "Couldn't parse {}: {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+
+    pub fn store(&self, root: &Path) -> Result<(), AppError> {
+        let path = Self::path(root);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_vec_pretty(self)
+            .map_err(|e| AppError::FileOperationError(e.to_string()))?;
+
+        // Written next to the file and renamed, so an interrupted
+        // write doesn't lose earlier decisions
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, content)?;
+        fs::rename(tmp, path)?;
+        Ok(())
+    }
+
+    pub fn get(&self, id: &ResourceId) -> Option<&Decision> {
+        self.0.get(&id.to_string())
+    }
+
+    pub fn is_resolved(
+        &self,
+        id: &ResourceId,
+        paths: &HashSet<PathBuf>,
+    ) -> bool {
+        self.get(id).is_some_and(|decision| {
+            decision.paths.len() == paths.len()
+                && paths
+                    .iter()
+                    .all(|path| decision.paths.contains(path))
+        })
+    }
+
+    pub fn resolve(
+        &mut self,
+        id: &ResourceId,
+        paths: &HashSet<PathBuf>,
+        resolution: Resolution,
+    ) {
+        let decision = Decision {
+            paths: paths.iter().cloned().collect(),
+            resolution,
+        };
+        self.0.insert(id.to_string(), decision);
+    }
+
+    pub fn forget(&mut self, id: &str) -> bool {
+        self.0.remove(id).is_some()
+    }
+}
+
+/// Blake3 ids of the colliding resources, `None` if some of them
+/// still collide, i.e. their contents are identical
+pub fn rehash(
+    root: &Path,
+    paths: &HashSet<PathBuf>,
+) -> Result<Option<BTreeMap<PathBuf, String>>, AppError> {
+    let mut ids = BTreeMap::new();
+    for path in paths {
+        let id = Blake3::from_path(root.join(path))?;
+        ids.insert(path.clone(), id.to_string());
+    }
+    let unique: HashSet<&String> = ids.values().collect();
+    Ok((unique.len() == ids.len()).then_some(ids))
+}
@@ -0,0 +1,50 @@
+use std::io;
+
+use clap::{builder::PossibleValuesParser, Command, CommandFactory};
+use clap_complete::{generate, Shell};
+
+use crate::{cli::Cli, registered_roots, AppError, STORAGE_NAMES};
+
+#[derive(Clone, Debug, clap::Args)]
+#[clap(
+    name = "completions",
+    about = "Print the shell completion script",
+    long_about = "Print the shell completion script. Registered roots and \
+                  storage names are suggested as they are at the moment \
+                  of generation, regenerate the script after changing \
+                  the roots config."
+)]
+pub struct Completions {
+    #[clap(value_enum, help = "Shell to generate the script for")]
+    shell: Shell,
+}
+
+impl Completions {
+    pub fn run(&self) -> Result<(), AppError> {
+        let roots: Vec<String> = registered_roots()
+            .iter()
+            .map(|root| root.display().to_string())
+            .collect();
+
+        // The command is only used to generate the script,
+        // so suggestions don't restrict values accepted by ark-cli
+        let mut command = with_suggestions(Cli::command(), &roots);
+        let name = command.get_name().to_owned();
+        generate(self.shell, &mut command, name, &mut io::stdout());
+        Ok(())
+    }
+}
+
+fn with_suggestions(command: Command, roots: &[String]) -> Command {
+    command
+        .mut_args(|arg| match arg.get_id().as_str() {
+            "root_dir" if !roots.is_empty() => {
+                arg.value_parser(PossibleValuesParser::new(roots.to_vec()))
+            }
+            "storage" => {
+                arg.value_parser(PossibleValuesParser::new(STORAGE_NAMES))
+            }
+            _ => arg,
+        })
+        .mut_subcommands(|subcommand| with_suggestions(subcommand, roots))
+}
@@ -0,0 +1,211 @@
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, Read, Write},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::{provide_index, provide_root, AppError};
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum KeepPolicy {
+    /// Keep the file modified first
+    Oldest,
+    /// Keep the file modified last
+    Newest,
+    /// Keep the file with the shortest path
+    Shortest,
+}
+
+#[derive(Clone, Debug, clap::Args)]
+#[clap(
+    name = "dedupe",
+    about = "Delete or hard-link duplicate files in the ark managed folder"
+)]
+pub struct Dedupe {
+    #[clap(value_parser, help = "Path to the root directory")]
+    root_dir: Option<PathBuf>,
+    #[clap(
+        long,
+        value_enum,
+        help = "Choose the file to keep automatically instead of asking"
+    )]
+    auto: Option<KeepPolicy>,
+    #[clap(
+        long,
+        action,
+        help = "Replace duplicates with hard links instead of deleting them"
+    )]
+    hard_link: bool,
+    #[clap(long, action, help = "Only print what would be done")]
+    dry_run: bool,
+}
+
+impl Dedupe {
+    pub fn run(&self) -> Result<(), AppError> {
+        let root = provide_root(&self.root_dir)?;
+        let index = provide_index(&root).map_err(|_| {
+            AppError::IndexError("Could not provide index".to_owned())
+        })?;
+        let collisions = index
+            .read()
+            .map_err(|_| {
+                AppError::IndexError("Could not read index".to_owned())
+            })?
+            .collisions();
+
+        let mut removed = 0;
+        let mut reclaimed = 0;
+        for (id, paths) in collisions {
+            let mut paths: Vec<PathBuf> = paths
+                .into_iter()
+                .map(|path| root.join(path))
+                .collect();
+            paths.sort();
+
+            println!("Duplicates of {}:", id);
+            for (i, path) in paths.iter().enumerate() {
+                println!("\t[{}] {}", i + 1, path.display());
+            }
+
+            let keep = match self.auto {
+                Some(policy) => Some(select(&paths, policy)?),
+                None => ask(paths.len())?,
+            };
+            let Some(keep) = keep else {
+                println!("\tSkipped");
+                continue;
+            };
+            let kept = &paths[keep];
+
+            for (i, duplicate) in paths.iter().enumerate() {
+                if i == keep {
+                    continue;
+                }
+                // Ids of non-cryptographic hashes can collide
+                if !same_content(kept, duplicate)? {
+                    println!(
+                        "\tContent of {} differs, keeping it",
+                        duplicate.display()
+                    );
+                    continue;
+                }
+
+                let size = fs::metadata(duplicate)?.len();
+                let action = if self.hard_link {
+                    "Linking"
+                } else {
+                    "Deleting"
+                };
+                println!("\t{} {}", action, duplicate.display());
+                if !self.dry_run {
+                    if self.hard_link {
+                        replace_with_link(kept, duplicate)?;
+                    } else {
+                        fs::remove_file(duplicate)?;
+                    }
+                }
+                removed += 1;
+                reclaimed += size;
+            }
+        }
+
+        if !self.dry_run && removed > 0 {
+            let mut index = index.write().map_err(|_| {
+                AppError::IndexError("Could not write index".to_owned())
+            })?;
+            index.update_all()?;
+            index.store()?;
+        }
+
+        let verb = if self.dry_run {
+            "Would reclaim"
+        } else {
+            "Reclaimed"
+        };
+        println!(
+            "{} {} bytes from {} duplicate files",
+            verb, reclaimed, removed
+        );
+        Ok(())
+    }
+}
+
+fn select(paths: &[PathBuf], policy: KeepPolicy) -> Result<usize, AppError> {
+    let modified = |path: &PathBuf| -> Result<SystemTime, AppError> {
+        Ok(fs::metadata(path)?.modified()?)
+    };
+
+    let mut selected = 0;
+    for (i, path) in paths.iter().enumerate().skip(1) {
+        let current = &paths[selected];
+        let better = match policy {
+            KeepPolicy::Oldest => modified(path)? < modified(current)?,
+            KeepPolicy::Newest => modified(path)? > modified(current)?,
+            KeepPolicy::Shortest => {
+                path.as_os_str().len() < current.as_os_str().len()
+            }
+        };
+        if better {
+            selected = i;
+        }
+    }
+    Ok(selected)
+}
+
+/// Ask which file to keep, `None` means the group is skipped
+fn ask(count: usize) -> Result<Option<usize>, AppError> {
+    loop {
+        print!("Number of the file to keep, or 's' to skip: ");
+        io::stdout().flush()?;
+
+        let mut answer = String::new();
+        if io::stdin().lock().read_line(&mut answer)? == 0 {
+            return Ok(None);
+        }
+        let answer = answer.trim();
+        if answer.eq_ignore_ascii_case("s") {
+            return Ok(None);
+        }
+        match answer.parse::<usize>() {
+            Ok(number) if (1..=count).contains(&number) => {
+                return Ok(Some(number - 1))
+            }
+            _ => println!("Please enter a number from 1 to {}", count),
+        }
+    }
+}
+
+fn same_content(a: &Path, b: &Path) -> io::Result<bool> {
+    if fs::metadata(a)?.len() != fs::metadata(b)?.len() {
+        return Ok(false);
+    }
+
+    let mut a = BufReader::new(fs::File::open(a)?);
+    let mut b = BufReader::new(fs::File::open(b)?);
+    let mut buf_a = [0; 8192];
+    let mut buf_b = [0; 8192];
+    loop {
+        let read = a.read(&mut buf_a)?;
+        if read == 0 {
+            return Ok(true);
+        }
+        b.read_exact(&mut buf_b[..read])?;
+        if buf_a[..read] != buf_b[..read] {
+            return Ok(false);
+        }
+    }
+}
+
+/// Link is created next to the duplicate first,
+/// so the duplicate is never lost if linking fails
+fn replace_with_link(original: &Path, duplicate: &Path) -> io::Result<()> {
+    let mut name = duplicate
+        .file_name()
+        .unwrap_or_default()
+        .to_os_string();
+    name.push(".ark-dedupe");
+    let tmp = duplicate.with_file_name(name);
+    fs::hard_link(original, &tmp)?;
+    fs::rename(&tmp, duplicate)
+}
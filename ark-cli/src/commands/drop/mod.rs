@@ -0,0 +1,19 @@
+use clap::Subcommand;
+
+pub(crate) mod query;
+mod receive;
+mod send;
+pub mod sync;
+mod user_data;
+
+/// Available commands for the `drop` subcommand
+#[derive(Subcommand, Debug)]
+pub enum Drop {
+    Send(send::SendFiles),
+    Receive(receive::ReceiveFiles),
+    #[command(about = "Keep ark managed folders in sync with paired devices")]
+    Sync {
+        #[clap(subcommand)]
+        subcommand: sync::Sync,
+    },
+}
@@ -0,0 +1,204 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    commands::tag::utils::{parse_tags, tag_storage},
+    parse_since,
+    util::provide_index,
+    AppError, ResourceId,
+};
+
+/// How far in the future modification times of selected resources can be
+const FUTURE_MODIFICATIONS: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+/// Resource selected by a query
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Selected {
+    /// Path in the root, with `/` as separator
+    pub name: String,
+    pub path: PathBuf,
+    pub id: ResourceId,
+}
+
+/// Selection of resources of an index, e.g. to send them over drop
+#[derive(Clone, Debug, clap::Args)]
+pub struct ResourceQuery {
+    #[clap(long, value_name = "ID", help = "Select the resource with this id")]
+    id: Vec<String>,
+    #[clap(
+        long,
+        value_name = "TAG",
+        help = "Select all resources tagged with this tag"
+    )]
+    tag: Vec<String>,
+    #[clap(
+        long,
+        value_name = "PATTERN",
+        help = "Select resources whose path in the root matches this pattern, \
+                e.g. '*.pdf' or 'photos/**/*.jpg'"
+    )]
+    glob: Vec<String>,
+    #[clap(
+        long,
+        value_parser = parse_since,
+        help = "Select resources modified since a date (2024-05-01) \
+                or a period ago (12h, 7d, 4w)"
+    )]
+    modified_since: Option<SystemTime>,
+}
+
+impl ResourceQuery {
+    pub fn is_empty(&self) -> bool {
+        self.id.is_empty()
+            && self.tag.is_empty()
+            && self.glob.is_empty()
+            && self.modified_since.is_none()
+    }
+
+    /// Resources matching the query with their files, named by their path
+    /// in the root so the receiver recreates the tree
+    ///
+    /// Ids and tags select resources, all of them if none are given, which
+    /// are then filtered by modification time and patterns.
+    pub fn select(&self, root: &Path) -> Result<Vec<Selected>, AppError> {
+        let index = provide_index(&root.to_path_buf());
+
+        let ids = self.selected_ids(root)?;
+        if let Some(ids) = &ids {
+            for id in ids {
+                // Only given ids must be indexed, as tags of deleted
+                // resources stay in the storage
+                let explicit = self.id.contains(&id.to_string());
+                if explicit && index.get_resources_by_id(id).is_none() {
+                    return Err(AppError::ResourceNotFound(format!(
+                        "{} is not indexed",
+                        id
+                    )));
+                }
+            }
+        }
+
+        // Recently modified resources are looked up without scanning the
+        // whole index. Files modified in the future, e.g. copied from a
+        // device with a skewed clock, are selected too.
+        let resources = match self.modified_since {
+            Some(since) => index.resources_modified_between(
+                since,
+                SystemTime::now() + FUTURE_MODIFICATIONS,
+            ),
+            None => index.resources(),
+        };
+
+        let mut files = vec![];
+        let mut seen = BTreeSet::new();
+        for resource in resources {
+            let id = resource.id();
+            if ids.as_ref().is_some_and(|ids| !ids.contains(id)) {
+                continue;
+            }
+            // Resources with the same id have the same content,
+            // so sending one of them is enough
+            if seen.contains(id) || !self.matches(resource.path()) {
+                continue;
+            }
+            seen.insert(id.clone());
+            files.push(Selected {
+                name: resource_name(resource.path()),
+                path: root.join(resource.path()),
+                id: id.clone(),
+            });
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    /// Ids selected by ids and tags, `None` to select all resources
+    fn selected_ids(
+        &self,
+        root: &Path,
+    ) -> Result<Option<BTreeSet<ResourceId>>, AppError> {
+        if self.id.is_empty() && self.tag.is_empty() {
+            return Ok(None);
+        }
+
+        let mut ids = BTreeSet::new();
+        for id in &self.id {
+            let id = ResourceId::from_str(id).map_err(|_| {
+                AppError::ResourceNotFound(format!("Invalid id {}", id))
+            })?;
+            ids.insert(id);
+        }
+        if !self.tag.is_empty() {
+            for (id, value) in tag_storage(&root.to_path_buf())?.entries()? {
+                let tags = parse_tags(&[value]);
+                if self.tag.iter().any(|tag| tags.contains(tag)) {
+                    ids.insert(id);
+                }
+            }
+        }
+        Ok(Some(ids))
+    }
+
+    fn matches(&self, path: &Path) -> bool {
+        if self.glob.is_empty() {
+            return true;
+        }
+        let name = resource_name(path);
+        self.glob
+            .iter()
+            .any(|pattern| glob_matches(pattern, &name))
+    }
+}
+
+/// Path of a resource in the root, with `/` as separator
+pub(super) fn resource_name(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Matches a path against a pattern where `?` is any character, `*` any
+/// characters within a directory and `**` any characters across
+/// directories. A pattern without `/` is matched against the file name.
+fn glob_matches(pattern: &str, path: &str) -> bool {
+    let path = if pattern.contains('/') {
+        path
+    } else {
+        path.rsplit('/').next().unwrap_or(path)
+    };
+    let pattern: Vec<char> = pattern.chars().collect();
+    let path: Vec<char> = path.chars().collect();
+    matches_from(&pattern, &path)
+}
+
+fn matches_from(pattern: &[char], path: &[char]) -> bool {
+    match pattern {
+        [] => path.is_empty(),
+        ['*', '*', '/', rest @ ..] => {
+            // `**/` also matches no directory at all
+            matches_from(rest, path)
+                || (0..path.len()).any(|i| {
+                    path[i] == '/' && matches_from(rest, &path[i + 1..])
+                })
+        }
+        ['*', '*', rest @ ..] => {
+            (0..=path.len()).any(|i| matches_from(rest, &path[i..]))
+        }
+        ['*', rest @ ..] => (0..=path.len())
+            .take_while(|&i| i == 0 || path[i - 1] != '/')
+            .any(|i| matches_from(rest, &path[i..])),
+        ['?', rest @ ..] => match path {
+            [c, path @ ..] if *c != '/' => matches_from(rest, path),
+            _ => false,
+        },
+        [c, rest @ ..] => match path {
+            [p, path @ ..] if p == c => matches_from(rest, path),
+            _ => false,
+        },
+    }
+}
@@ -0,0 +1,163 @@
+use std::path::{Path, PathBuf};
+
+use arkdrop_cli::run_receive_files;
+use arkdrop_common::{LinkItem, Profile};
+use data_link::Link;
+use url::Url;
+
+use super::user_data::merge_received;
+use crate::{util::registered_roots, AppError, ResourceId};
+
+#[derive(Clone, Debug, clap::Args)]
+#[clap(
+    name = "receive",
+    about = "Receive files from another device, indexing them and saving \
+             the links and user data sent if they are received in an ark \
+             managed folder"
+)]
+pub struct ReceiveFiles {
+    #[clap(help = "Ticket given by the sender")]
+    ticket: String,
+    #[clap(help = "Confirmation code given by the sender")]
+    confirmation: String,
+    #[clap(
+        long,
+        short,
+        help = "Directory to save the files in, the configured one by default"
+    )]
+    output: Option<PathBuf>,
+    #[clap(
+        long,
+        help = "Root directory of the ark managed folder to index the files \
+                in, the registered roots by default"
+    )]
+    root_dir: Option<PathBuf>,
+    #[clap(long, short, default_value = "ark-cli", help = "Your display name")]
+    name: String,
+    #[clap(long, short, action, help = "Enable verbose logging")]
+    verbose: bool,
+}
+
+impl ReceiveFiles {
+    pub async fn run(&self) -> Result<(), AppError> {
+        let session = run_receive_files(
+            self.output.clone(),
+            self.ticket.clone(),
+            self.confirmation.clone(),
+            Profile::new(self.name.clone(), None),
+            self.verbose,
+            false,
+            None,
+        )
+        .await
+        .map_err(|e| AppError::DropError(e.to_string()))?;
+
+        let Some(session) = session else {
+            return Ok(());
+        };
+        let session_dir = session.dir.canonicalize()?;
+        match self.find_root(&session_dir) {
+            Some(root) => {
+                merge_received(&root, &session.user_data)?;
+                let links = save_links(&root, &session.links)?;
+                index_received(&root, &session_dir, &links)
+            }
+            None => {
+                if let Some(root) = &self.root_dir {
+                    println!(
+                        "{} is not in {}, files were not indexed",
+                        session_dir.display(),
+                        root.display()
+                    );
+                }
+                if !session.links.is_empty() {
+                    println!(
+                        "{} links were kept as files, receive them in an ark \
+                         managed folder to save them",
+                        session.links.len()
+                    );
+                }
+                if !session.user_data.is_empty() {
+                    println!(
+                        "User data was kept as a file, receive it in an ark \
+                         managed folder to merge it"
+                    );
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Root containing the directory, among the given or registered ones
+    fn find_root(&self, dir: &Path) -> Option<PathBuf> {
+        let roots = match &self.root_dir {
+            Some(root) => vec![root.clone()],
+            None => registered_roots(),
+        };
+        roots
+            .into_iter()
+            .filter_map(|root| root.canonicalize().ok())
+            .find(|root| dir.starts_with(root))
+    }
+}
+
+/// Saves the received link items into the links of the root, removing
+/// their files. Returns the paths of the saved links in the root.
+///
+/// Files which aren't link items are kept as they are.
+fn save_links(
+    root: &Path,
+    files: &[PathBuf],
+) -> Result<Vec<PathBuf>, AppError> {
+    let mut saved = vec![];
+    for file in files {
+        let bytes = std::fs::read(file)?;
+        let item = match LinkItem::from_bytes(&bytes) {
+            Ok(item) => item,
+            Err(e) => {
+                println!("Keeping {} as a file: {}", file.display(), e);
+                continue;
+            }
+        };
+        let Ok(url) = Url::parse(&item.url) else {
+            println!("Keeping {} as a file: invalid url", file.display());
+            continue;
+        };
+        let link: Link<ResourceId> = Link::new(url, item.title, item.desc);
+        link.save_received(root, item.preview.as_deref())?;
+        std::fs::remove_file(file)?;
+        saved.push(PathBuf::from(link.id()?.to_string()));
+    }
+    if !saved.is_empty() {
+        println!("Saved {} links into {}", saved.len(), root.display());
+    }
+    Ok(saved)
+}
+
+/// Indexes the files received into a root and the links saved from them,
+/// so they get ids right away instead of on the next update of the whole
+/// root
+fn index_received(
+    root: &Path,
+    dir: &Path,
+    links: &[PathBuf],
+) -> Result<(), AppError> {
+    let relative = dir
+        .strip_prefix(root)
+        .expect("Directory must be in the root");
+
+    let index = crate::provide_index(root).map_err(|_| {
+        AppError::IndexError("Could not provide index".to_owned())
+    })?;
+    let mut index = index.write().map_err(|_| {
+        AppError::IndexError("Could not write index".to_owned())
+    })?;
+    let mut added = index.update_subtree(relative)?.added().len();
+    for link in links {
+        added += index.update_one(link)?.added().len();
+    }
+    index.store()?;
+
+    println!("Indexed {} new resources in {}", added, root.display());
+    Ok(())
+}
@@ -0,0 +1,127 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+};
+
+use arkdrop_cli::run_send_items;
+use arkdrop_common::{collect_files, LinkItem, Profile};
+use data_link::Link;
+
+use super::{query::ResourceQuery, user_data::export_resources};
+use crate::{
+    commands::link::utils::load_link, provide_root, AppError, ResourceId,
+};
+
+#[derive(Clone, Debug, clap::Args)]
+#[clap(
+    name = "send",
+    about = "Send files or resources of the index to another device"
+)]
+pub struct SendFiles {
+    #[clap(value_parser, help = "Files to send")]
+    files: Vec<PathBuf>,
+    #[clap(flatten)]
+    query: ResourceQuery,
+    #[clap(
+        long,
+        value_name = "ID",
+        help = "Send the link with this id, saved into the links of the \
+                receiver along with its preview"
+    )]
+    link: Vec<ResourceId>,
+    #[clap(
+        long,
+        action,
+        help = "Also send tags, scores and properties of the resources \
+                selected by a query and of the links, merged into the root \
+                of the receiver"
+    )]
+    with_user_data: bool,
+    #[clap(long, help = "Root directory of the ark managed folder")]
+    root_dir: Option<PathBuf>,
+    #[clap(long, short, default_value = "ark-cli", help = "Your display name")]
+    name: String,
+    #[clap(long, short, action, help = "Enable verbose logging")]
+    verbose: bool,
+}
+
+impl SendFiles {
+    pub async fn run(&self) -> Result<(), AppError> {
+        // Files are keyed by the name they are sent under,
+        // the receiver can't save two files under the same name
+        let mut files: BTreeMap<String, PathBuf> = collect_files(&self.files)
+            .map_err(|e| AppError::DropError(e.to_string()))?
+            .into_iter()
+            .collect();
+        // Resources whose user data is sent along
+        let mut ids: BTreeSet<String> = self
+            .link
+            .iter()
+            .map(|id| id.to_string())
+            .collect();
+        if !self.query.is_empty() {
+            let root = provide_root(&self.root_dir)?;
+            for selected in self.query.select(&root)? {
+                ids.insert(selected.id.to_string());
+                files
+                    .entry(selected.name)
+                    .or_insert(selected.path);
+            }
+        }
+        let links = self.links()?;
+        let user_data = if self.with_user_data {
+            export_resources(&provide_root(&self.root_dir)?, &ids)?
+        } else {
+            None
+        };
+        if files.is_empty() && links.is_empty() {
+            return Err(AppError::ResourceNotFound(
+                "Nothing to send, give files, links or a query of resources"
+                    .to_owned(),
+            ));
+        }
+
+        println!("Sending {} files:", files.len() + links.len());
+        for name in files.keys() {
+            println!("\t{}", name);
+        }
+        for (name, link) in &links {
+            println!("\t{} ({})", name, link.url);
+        }
+        if user_data.is_some() {
+            println!("With user data of the resources");
+        }
+        run_send_items(
+            files.into_iter().collect(),
+            links,
+            user_data,
+            Profile::new(self.name.clone(), None),
+            self.verbose,
+        )
+        .await
+        .map_err(|e| AppError::DropError(e.to_string()))
+    }
+
+    /// Links to send with their preview, named by their id
+    fn links(&self) -> Result<Vec<(String, LinkItem)>, AppError> {
+        if self.link.is_empty() {
+            return Ok(vec![]);
+        }
+        let root = provide_root(&self.root_dir)?;
+        let mut links = vec![];
+        for id in &self.link {
+            let link = load_link(&root, &None, &Some(id.clone()))?;
+            let preview = Link::<ResourceId>::load_preview(&root, id)?;
+            links.push((
+                format!("{}.link.json", id),
+                LinkItem {
+                    url: link.url.to_string(),
+                    title: link.prop.title,
+                    desc: link.prop.desc,
+                    preview,
+                },
+            ));
+        }
+        Ok(links)
+    }
+}
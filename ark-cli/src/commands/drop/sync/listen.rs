@@ -0,0 +1,95 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use arkdrop_cli::{run_sync_listener, ReceivedSession};
+use arkdrop_common::{collect_files, Profile};
+use fs_storage::ARK_FOLDER;
+
+use super::{super::user_data::merge_received, SYNC_FOLDER};
+use crate::{provide_root, AppError};
+
+/// Folder receiving the sessions before their files are moved into the root
+const INCOMING_FOLDER: &str = "incoming";
+
+#[derive(Clone, Debug, clap::Args)]
+#[clap(
+    name = "listen",
+    about = "Receive the files pushed by trusted devices into an ark managed \
+             folder"
+)]
+pub struct Listen {
+    #[clap(long, help = "Root directory of the ark managed folder")]
+    root_dir: Option<PathBuf>,
+    #[clap(long, short, default_value = "ark-cli", help = "Your display name")]
+    name: String,
+    #[clap(long, short, action, help = "Enable verbose logging")]
+    verbose: bool,
+}
+
+impl Listen {
+    pub async fn run(&self) -> Result<(), AppError> {
+        let root = provide_root(&self.root_dir)?.canonicalize()?;
+        let incoming = root
+            .join(ARK_FOLDER)
+            .join(SYNC_FOLDER)
+            .join(INCOMING_FOLDER);
+
+        run_sync_listener(
+            incoming,
+            Profile::new(self.name.clone(), None),
+            self.verbose,
+            |session| {
+                if let Err(e) = apply_received(&root, session) {
+                    eprintln!("Failed to sync received files: {}", e);
+                }
+            },
+        )
+        .await
+        .map_err(|e| AppError::DropError(e.to_string()))
+    }
+}
+
+/// Merges the user data of a received session into the root, then moves
+/// its files to their paths in the root, replacing older versions, and
+/// indexes them
+fn apply_received(
+    root: &Path,
+    session: &ReceivedSession,
+) -> Result<(), AppError> {
+    merge_received(root, &session.user_data)?;
+
+    let files = collect_files(&[session.dir.clone()])
+        .map_err(|e| AppError::FileOperationError(e.to_string()))?;
+
+    let mut moved = vec![];
+    for (name, path) in files {
+        // Names start with the folder of the session
+        let Some((_, name)) = name.split_once('/') else {
+            continue;
+        };
+        let relative = PathBuf::from(name);
+        let target = root.join(&relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::rename(&path, &target)?;
+        moved.push(relative);
+    }
+    fs::remove_dir_all(&session.dir)?;
+
+    let index = crate::provide_index(root).map_err(|_| {
+        AppError::IndexError("Could not provide index".to_owned())
+    })?;
+    let mut index = index.write().map_err(|_| {
+        AppError::IndexError("Could not write index".to_owned())
+    })?;
+    for relative in &moved {
+        index.update_one(relative)?;
+    }
+    index.store()?;
+
+    println!("Synced {} files into {}", moved.len(), root.display());
+    Ok(())
+}
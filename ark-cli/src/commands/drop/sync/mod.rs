@@ -0,0 +1,19 @@
+use clap::Subcommand;
+
+mod listen;
+mod push;
+mod state;
+mod trust;
+
+/// Available commands for the `drop sync` subcommand
+#[derive(Subcommand, Debug)]
+pub enum Sync {
+    Id(trust::Id),
+    Trust(trust::Trust),
+    Untrust(trust::Untrust),
+    Listen(listen::Listen),
+    Push(push::Push),
+}
+
+/// Folder of the sync data in the `.ark` folder of a root
+const SYNC_FOLDER: &str = "sync";
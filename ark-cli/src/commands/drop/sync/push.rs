@@ -0,0 +1,192 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use arkdrop_cli::run_sync_send;
+use arkdrop_common::{ticket_node_id, Profile};
+use data_resource::ResourceId as _;
+use fs_index::{load_or_build_index, watch_index, IndexUpdate, WatchEvent};
+use futures::{pin_mut, StreamExt};
+
+use super::{
+    super::{query::resource_name, user_data::export_resources},
+    state::SyncState,
+};
+use crate::{provide_root, AppError, ResourceId};
+
+/// Interval at which pushes that failed, e.g. because the paired device was
+/// offline, are retried without waiting for another change. Changes of user
+/// data, which are outside of the watched files, are pushed then too.
+const RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Debug, clap::Args)]
+#[clap(
+    name = "push",
+    about = "Push the changes of an ark managed folder to a paired device \
+             as they happen"
+)]
+pub struct Push {
+    #[clap(help = "Ticket shown by `drop sync listen` on the paired device")]
+    ticket: String,
+    #[clap(help = "Confirmation code shown by `drop sync listen`")]
+    confirmation: u8,
+    #[clap(long, help = "Root directory of the ark managed folder")]
+    root_dir: Option<PathBuf>,
+    #[clap(long, short, default_value = "ark-cli", help = "Your display name")]
+    name: String,
+    #[clap(long, short, action, help = "Enable verbose logging")]
+    verbose: bool,
+}
+
+impl Push {
+    pub async fn run(&self) -> Result<(), AppError> {
+        let root = provide_root(&self.root_dir)?.canonicalize()?;
+        let peer = ticket_node_id(&self.ticket)
+            .map_err(|e| AppError::DropError(e.to_string()))?;
+        let state = SyncState::load(&root, &peer)?;
+
+        println!("Pushing changes of {} to {}", root.display(), peer);
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {
+                println!("Sync stopped");
+                Ok(())
+            }
+            result = self.sync(root, state) => result,
+        }
+    }
+
+    /// Pushes the resources of the root not sent yet, then the changes
+    async fn sync(
+        &self,
+        root: PathBuf,
+        mut state: SyncState,
+    ) -> Result<(), AppError> {
+        // Ids of the resources by path, kept up to date with the changes
+        let index = load_or_build_index::<_, ResourceId>(&root, true)?;
+        let mut resources: BTreeMap<PathBuf, ResourceId> = index
+            .resources()
+            .into_iter()
+            .map(|resource| {
+                (resource.path().to_path_buf(), resource.id().clone())
+            })
+            .collect();
+        self.push(&root, &resources, &mut state).await;
+
+        let stream = watch_index::<_, ResourceId>(&root);
+        pin_mut!(stream);
+        let mut retry = tokio::time::interval(RETRY_INTERVAL);
+        loop {
+            tokio::select! {
+                event = stream.next() => {
+                    let Some(event) = event else {
+                        return Ok(());
+                    };
+                    let update = match &event {
+                        WatchEvent::UpdatedOne(update) => update,
+                        WatchEvent::UpdatedAll(update) => update,
+                    };
+                    apply_update(&mut resources, update);
+                }
+                _ = retry.tick() => {}
+            }
+            self.push(&root, &resources, &mut state).await;
+        }
+    }
+
+    /// Sends the resources whose content the peer doesn't have yet at their
+    /// path, and their user data if it changed, and records them once the
+    /// peer received them
+    async fn push(
+        &self,
+        root: &Path,
+        resources: &BTreeMap<PathBuf, ResourceId>,
+        state: &mut SyncState,
+    ) {
+        // Files removed since the last update are skipped
+        let pending: Vec<(String, &ResourceId)> = resources
+            .iter()
+            .filter(|(path, _)| root.join(path).is_file())
+            .map(|(path, id)| (resource_name(path), id))
+            .filter(|(name, id)| !state.is_sent(name, id))
+            .collect();
+        let (checksum, user_data) =
+            changed_user_data(root, resources, state).unzip();
+        if pending.is_empty() && user_data.is_none() {
+            return;
+        }
+
+        println!("Pushing {} files", pending.len());
+        if user_data.is_some() {
+            println!("Pushing user data of the resources");
+        }
+        let files = pending
+            .iter()
+            .map(|(name, _)| (name.clone(), root.join(name)))
+            .collect();
+        let sent = run_sync_send(
+            files,
+            user_data,
+            self.ticket.clone(),
+            self.confirmation,
+            Profile::new(self.name.clone(), None),
+            self.verbose,
+        )
+        .await;
+        if let Err(e) = sent {
+            eprintln!("Failed to push changes, will retry: {}", e);
+            return;
+        }
+
+        for (name, id) in pending {
+            state.record(name, id);
+        }
+        if let Some(checksum) = checksum {
+            state.record_user_data(checksum);
+        }
+        if let Err(e) = state.save() {
+            eprintln!("Failed to save sync state: {}", e);
+        }
+    }
+}
+
+/// Tags, scores and properties of the resources with their checksum, if they
+/// changed since they were last sent
+fn changed_user_data(
+    root: &Path,
+    resources: &BTreeMap<PathBuf, ResourceId>,
+    state: &SyncState,
+) -> Option<(String, Vec<u8>)> {
+    let ids = resources
+        .values()
+        .map(|id| id.to_string())
+        .collect();
+    let user_data = match export_resources(root, &ids) {
+        Ok(user_data) => user_data?,
+        Err(e) => {
+            eprintln!("Failed to read user data, will retry: {}", e);
+            return None;
+        }
+    };
+    let checksum = ResourceId::from_bytes(&user_data)
+        .ok()?
+        .to_string();
+    if state.is_user_data_sent(&checksum) {
+        return None;
+    }
+    Some((checksum, user_data))
+}
+
+/// Applies an update of the index to the ids of the resources by path
+fn apply_update(
+    resources: &mut BTreeMap<PathBuf, ResourceId>,
+    update: &IndexUpdate<ResourceId>,
+) {
+    resources.retain(|_, id| !update.removed().contains(id));
+    for (id, paths) in update.added() {
+        for path in paths {
+            resources.insert(path.item().clone(), id.clone());
+        }
+    }
+}
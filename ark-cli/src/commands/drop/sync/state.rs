@@ -0,0 +1,81 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use fs_storage::ARK_FOLDER;
+use serde::{Deserialize, Serialize};
+
+use super::SYNC_FOLDER;
+use crate::{AppError, ResourceId};
+
+/// Contents pushed to a paired device, so unchanged resources are not sent
+/// again, e.g. after a restart or when only their metadata changed
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SyncState {
+    #[serde(skip)]
+    path: PathBuf,
+    /// Ids of the resources sent, by path in the root
+    sent: BTreeMap<String, String>,
+    /// Checksum of the user data of the resources last sent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    user_data: Option<String>,
+}
+
+impl SyncState {
+    /// Loads the state of the pushes of a root to a peer, empty if none
+    /// were done yet
+    pub fn load(root: &Path, peer: &str) -> Result<Self, AppError> {
+        let path = root
+            .join(ARK_FOLDER)
+            .join(SYNC_FOLDER)
+            .join(format!("{}.json", peer));
+        let mut state: Self = if path.exists() {
+            let content = fs::read_to_string(&path)?;
+            serde_json::from_str(&content).map_err(|e| {
+                AppError::FileOperationError(format!(
+                    "Invalid sync state {}: {}",
+                    path.display(),
+                    e
+                ))
+            })?
+        } else {
+            Self::default()
+        };
+        state.path = path;
+        Ok(state)
+    }
+
+    pub fn is_sent(&self, name: &str, id: &ResourceId) -> bool {
+        self.sent
+            .get(name)
+            .is_some_and(|sent| *sent == id.to_string())
+    }
+
+    pub fn record(&mut self, name: String, id: &ResourceId) {
+        self.sent.insert(name, id.to_string());
+    }
+
+    pub fn is_user_data_sent(&self, checksum: &str) -> bool {
+        self.user_data.as_deref() == Some(checksum)
+    }
+
+    pub fn record_user_data(&mut self, checksum: String) {
+        self.user_data = Some(checksum);
+    }
+
+    pub fn save(&self) -> Result<(), AppError> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let content = serde_json::to_string_pretty(self).map_err(|e| {
+            AppError::FileOperationError(format!(
+                "Could not serialize sync state: {}",
+                e
+            ))
+        })?;
+        fs::write(&self.path, content)?;
+        Ok(())
+    }
+}
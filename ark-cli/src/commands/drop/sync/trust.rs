@@ -0,0 +1,63 @@
+use arkdrop_common::{identity, AppConfig};
+
+use crate::AppError;
+
+#[derive(Clone, Debug, clap::Args)]
+#[clap(
+    name = "id",
+    about = "Show the node id of this device, to be trusted by paired devices"
+)]
+pub struct Id {}
+
+impl Id {
+    pub fn run(&self) -> Result<(), AppError> {
+        let node_id = identity::node_id()
+            .map_err(|e| AppError::ConfigError(e.to_string()))?;
+        println!("{}", node_id);
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, clap::Args)]
+#[clap(name = "trust", about = "Allow a device to push files to this one")]
+pub struct Trust {
+    #[clap(help = "Node id of the device, shown by `drop sync id` on it")]
+    node_id: String,
+}
+
+impl Trust {
+    pub fn run(&self) -> Result<(), AppError> {
+        let mut config = load_config()?;
+        config
+            .trust_peer(self.node_id.clone())
+            .map_err(|e| AppError::ConfigError(e.to_string()))?;
+        println!("Trusted {}", self.node_id);
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, clap::Args)]
+#[clap(name = "untrust", about = "Stop allowing a device to push files")]
+pub struct Untrust {
+    #[clap(help = "Node id of the device")]
+    node_id: String,
+}
+
+impl Untrust {
+    pub fn run(&self) -> Result<(), AppError> {
+        let mut config = load_config()?;
+        let removed = config
+            .untrust_peer(&self.node_id)
+            .map_err(|e| AppError::ConfigError(e.to_string()))?;
+        if removed {
+            println!("{} is no longer trusted", self.node_id);
+        } else {
+            println!("{} was not trusted", self.node_id);
+        }
+        Ok(())
+    }
+}
+
+fn load_config() -> Result<AppConfig, AppError> {
+    AppConfig::load().map_err(|e| AppError::ConfigError(e.to_string()))
+}
@@ -0,0 +1,59 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use fs_properties::archive::{
+    export_user_data, import_user_data, UserDataArchive,
+};
+
+use crate::AppError;
+
+/// Tags, scores and properties of the given resources, serialized to be
+/// sent over drop, `None` if they have none
+pub fn export_resources(
+    root: &Path,
+    ids: &BTreeSet<String>,
+) -> Result<Option<Vec<u8>>, AppError> {
+    let mut archive = export_user_data(root)?;
+    archive.retain_resources(ids);
+    if archive.is_empty() {
+        return Ok(None);
+    }
+    let bytes = serde_json::to_vec(&archive).map_err(|e| {
+        AppError::FileOperationError(format!(
+            "Could not serialize user data: {}",
+            e
+        ))
+    })?;
+    Ok(Some(bytes))
+}
+
+/// Merges the received user data into the root, removing the files it was
+/// received in
+///
+/// Tags are united, received scores replace the current ones and properties
+/// are merged like `import` does, so nothing is removed from the root. Files
+/// which aren't user data are kept as they are.
+pub fn merge_received(root: &Path, files: &[PathBuf]) -> Result<(), AppError> {
+    for file in files {
+        let content = fs::read(file)?;
+        let archive: UserDataArchive = match serde_json::from_slice(&content) {
+            Ok(archive) => archive,
+            Err(e) => {
+                println!("Keeping {} as a file: {}", file.display(), e);
+                continue;
+            }
+        };
+        import_user_data(root, &archive)?;
+        fs::remove_file(file)?;
+        println!(
+            "Merged tags of {}, scores of {} and properties of {} resources",
+            archive.tags.len(),
+            archive.scores.len(),
+            archive.properties.len()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use std::{fs, path::PathBuf};
+
+use fs_properties::archive::export_user_data_to;
+
+use crate::{
+    dir, provide_root, AppError, CopyOptions, ARCHIVE_CACHE_FOLDER,
+    ARCHIVE_USER_DATA_FILE, ARK_FOLDER,
+};
+
+#[derive(Clone, Debug, clap::Args)]
+#[clap(
+    name = "export",
+    about = "Export user data into an archive keyed by resource ids"
+)]
+pub struct Export {
+    #[clap(value_parser, help = "Path to the archive directory to create")]
+    archive: PathBuf,
+    #[clap(long, help = "Root directory of the ark managed folder")]
+    root_dir: Option<PathBuf>,
+    #[clap(long, action, help = "Also export previews and thumbnails")]
+    with_cache: bool,
+}
+
+impl Export {
+    pub fn run(&self) -> Result<(), AppError> {
+        let root = provide_root(&self.root_dir)?;
+
+        if self.archive.exists() {
+            return Err(AppError::FileOperationError(format!(
+                "{} already exists",
+                self.archive.display()
+            )));
+        }
+        fs::create_dir_all(&self.archive)?;
+
+        export_user_data_to(&root, self.archive.join(ARCHIVE_USER_DATA_FILE))?;
+
+        let cache = root.join(ARK_FOLDER).join(ARCHIVE_CACHE_FOLDER);
+        if self.with_cache && cache.is_dir() {
+            let destination = self.archive.join(ARCHIVE_CACHE_FOLDER);
+            fs::create_dir_all(&destination)?;
+
+            let mut options = CopyOptions::new();
+            options.content_only = true;
+            dir::copy(cache, destination, &options).map_err(|e| {
+                AppError::FileOperationError(format!(
+                    "Failed to copy cache: {}",
+                    e
+                ))
+            })?;
+        }
+
+        println!("Archive created:\n\t{}", self.archive.display());
+        Ok(())
+    }
+}
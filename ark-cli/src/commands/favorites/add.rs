@@ -0,0 +1,35 @@
+use std::path::PathBuf;
+
+use crate::{
+    commands::favorites::utils::{favorites_storage, indexed_path},
+    models::ResourceSelector,
+    provide_root, resolve_resource,
+    util::provide_index,
+    AppError,
+};
+
+#[derive(Clone, Debug, clap::Args)]
+#[clap(name = "add", about = "Add a resource to favorites")]
+pub struct Add {
+    #[clap(flatten)]
+    selector: ResourceSelector,
+    #[clap(long, help = "Root directory of the ark managed folder")]
+    root_dir: Option<PathBuf>,
+}
+
+impl Add {
+    pub fn run(&self) -> Result<(), AppError> {
+        let root = provide_root(&self.root_dir)?;
+        let id = resolve_resource(&root, &self.selector)?;
+        let path =
+            indexed_path(&provide_index(&root), &id).ok_or_else(|| {
+                AppError::ResourceNotFound(format!("{} is not indexed", id))
+            })?;
+
+        // The path is kept to list favorites which are not indexed anymore
+        let mut storage = favorites_storage(&root)?;
+        storage.update(&id, |_| Some(path.display().to_string()))?;
+
+        Ok(())
+    }
+}
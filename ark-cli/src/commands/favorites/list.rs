@@ -0,0 +1,41 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{
+    commands::favorites::utils::{favorites_storage, indexed_path},
+    provide_root,
+    util::provide_index,
+    AppError,
+};
+
+#[derive(Clone, Debug, clap::Args)]
+#[clap(name = "list", about = "List favorite resources with their paths")]
+pub struct List {
+    #[clap(value_parser, help = "Root directory of the ark managed folder")]
+    root_dir: Option<PathBuf>,
+}
+
+impl List {
+    pub fn run(&self) -> Result<(), AppError> {
+        let root = provide_root(&self.root_dir)?;
+        let storage = favorites_storage(&root)?;
+        let index = provide_index(&root);
+
+        // The latest line of a resource wins
+        let favorites: BTreeMap<_, _> =
+            storage.entries()?.into_iter().collect();
+
+        let longest = favorites
+            .keys()
+            .map(|id| id.to_string().len())
+            .max()
+            .unwrap_or(0);
+        for (id, stored_path) in favorites {
+            let path = match indexed_path(&index, &id) {
+                Some(path) => path.display().to_string(),
+                None => format!("{} (not indexed)", stored_path),
+            };
+            println!("{:width$} {}", id.to_string(), path, width = longest);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,14 @@
+use clap::Subcommand;
+
+mod add;
+mod list;
+mod remove;
+pub mod utils;
+
+/// Available commands for the `favorites` subcommand
+#[derive(Subcommand, Debug)]
+pub enum Favorites {
+    Add(add::Add),
+    Remove(remove::Remove),
+    List(list::List),
+}
@@ -0,0 +1,27 @@
+use std::path::PathBuf;
+
+use crate::{
+    commands::favorites::utils::favorites_storage, models::ResourceSelector,
+    provide_root, resolve_resource, AppError,
+};
+
+#[derive(Clone, Debug, clap::Args)]
+#[clap(name = "remove", about = "Remove a resource from favorites")]
+pub struct Remove {
+    #[clap(flatten)]
+    selector: ResourceSelector,
+    #[clap(long, help = "Root directory of the ark managed folder")]
+    root_dir: Option<PathBuf>,
+}
+
+impl Remove {
+    pub fn run(&self) -> Result<(), AppError> {
+        let root = provide_root(&self.root_dir)?;
+        let id = resolve_resource(&root, &self.selector)?;
+
+        let mut storage = favorites_storage(&root)?;
+        storage.update(&id, |_| None)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,26 @@
+use std::path::PathBuf;
+
+use fs_index::ResourceIndex;
+
+use crate::{
+    models::storage::{Storage, StorageType},
+    translate_storage, AppError, ResourceId,
+};
+
+pub fn favorites_storage(root: &PathBuf) -> Result<Storage, AppError> {
+    let (path, _) = translate_storage(&Some(root.to_owned()), "favorites")
+        .ok_or(AppError::StorageNotFound("favorites".to_owned()))?;
+    Storage::new(path, StorageType::File)
+}
+
+/// Current path of the resource relative to the root, the first one
+/// if several files have the same content
+pub fn indexed_path(
+    index: &ResourceIndex<ResourceId>,
+    id: &ResourceId,
+) -> Option<PathBuf> {
+    index
+        .get_resources_by_id(id)?
+        .first()
+        .map(|resource| resource.path().to_owned())
+}
@@ -0,0 +1,142 @@
+use std::path::PathBuf;
+
+use data_resource::ResourceId as _;
+use serde_json::{json, Value};
+
+use crate::{
+    commands::{
+        score::utils::{parse_score, score_storage},
+        tag::utils::{parse_tags, tag_storage},
+    },
+    config::output_format,
+    models::ResourceSelector,
+    print_json, provide_root, read_storage_value, resolve_resource,
+    util::provide_index,
+    AppError, DateTime, Format, ResourceId, Utc,
+};
+
+#[derive(Clone, Debug, clap::Args)]
+#[clap(
+    name = "meta",
+    about = "Show the id, index entry and stored data of a resource"
+)]
+pub struct Meta {
+    #[clap(flatten)]
+    selector: ResourceSelector,
+    #[clap(long, help = "Root directory of the ark managed folder")]
+    root_dir: Option<PathBuf>,
+    #[clap(
+        long,
+        value_enum,
+        help = "Output format, raw unless configured otherwise"
+    )]
+    format: Option<Format>,
+}
+
+impl Meta {
+    pub fn run(&self) -> Result<(), AppError> {
+        let root = provide_root(&self.root_dir)?;
+        let id = resolve_resource(&root, &self.selector)?;
+
+        // Id of the current content, which differs from the indexed
+        // one if the file was changed since the index was updated
+        let computed = if self.selector.is_path() {
+            Some(ResourceId::from_path(&self.selector.resource)?)
+        } else {
+            None
+        };
+
+        let entries: Vec<(PathBuf, String)> = provide_index(&root)
+            .get_resources_by_id(&id)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|resource| {
+                let modified = DateTime::<Utc>::from(resource.last_modified())
+                    .format("%b %e %H:%M %Y")
+                    .to_string();
+                (resource.path().to_path_buf(), modified)
+            })
+            .collect();
+        let tags: Vec<String> = parse_tags(&tag_storage(&root)?.values(&id)?)
+            .into_iter()
+            .collect();
+        let score = parse_score(&score_storage(&root)?.values(&id)?);
+        let read = |storage: &str| {
+            read_storage_value(&root, storage, &id.to_string(), &None).ok()
+        };
+        let metadata = read("metadata");
+        let properties = read("properties");
+
+        if output_format(self.format) == Format::KeyValue {
+            // Stored JSON is embedded as is, other values as strings
+            let embed = |value: &Option<String>| {
+                value.as_ref().map(|value| {
+                    serde_json::from_str(value)
+                        .unwrap_or_else(|_| Value::from(value.clone()))
+                })
+            };
+            let entries: Vec<Value> = entries
+                .iter()
+                .map(|(path, modified)| {
+                    json!({ "path": path, "modified": modified })
+                })
+                .collect();
+            return print_json(&json!({
+                "id": id.to_string(),
+                "computed_id": computed.as_ref().map(|id| id.to_string()),
+                "entries": entries,
+                "tags": tags,
+                "score": score,
+                "metadata": embed(&metadata),
+                "properties": embed(&properties),
+            }));
+        }
+
+        println!("{:<12}{}", "Id", id);
+        if let Some(computed) = &computed {
+            if computed == &id {
+                println!("{:<12}{}", "Computed", computed);
+            } else {
+                println!(
+                    "{:<12}{} (content changed since the index was updated)",
+                    "Computed", computed
+                );
+            }
+        }
+        if entries.is_empty() {
+            println!("{:<12}NOT_INDEXED", "Paths");
+        }
+        for (i, (path, modified)) in entries.iter().enumerate() {
+            let title = if i == 0 {
+                "Paths"
+            } else {
+                ""
+            };
+            println!("{:<12}{} ({})", title, path.display(), modified);
+        }
+        if tags.is_empty() {
+            println!("{:<12}NO_TAGS", "Tags");
+        } else {
+            println!("{:<12}{}", "Tags", tags.join(", "));
+        }
+        match score {
+            Some(score) => println!("{:<12}{}", "Score", score),
+            None => println!("{:<12}NO_SCORE", "Score"),
+        }
+        for (title, content) in
+            [("Metadata", &metadata), ("Properties", &properties)]
+        {
+            match content {
+                Some(content) => {
+                    println!("{}:", title);
+                    content
+                        .lines()
+                        .for_each(|line| println!("\t{}", line));
+                }
+                None => println!("{:<12}NONE", title),
+            }
+        }
+
+        Ok(())
+    }
+}
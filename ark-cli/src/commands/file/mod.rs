@@ -2,6 +2,7 @@ use clap::Subcommand;
 
 mod append;
 mod insert;
+mod meta;
 mod read;
 mod utils;
 
@@ -10,7 +11,10 @@ mod utils;
 pub enum File {
     Append(append::Append),
     Insert(insert::Insert),
+    Meta(meta::Meta),
     Read(read::Read),
 }
 
-pub use utils::{file_append, file_insert, format_file, format_line};
+pub use utils::{
+    file_append, file_insert, format_file, format_line, version_info,
+};
@@ -4,6 +4,7 @@ use crate::{
 };
 use data_error::Result as ArklibResult;
 use fs_atomic_versions::atomic::{modify, modify_json, AtomicFile};
+use std::path::PathBuf;
 
 pub fn file_append(
     atomic_file: &AtomicFile,
@@ -117,6 +118,14 @@ where
 }
 
 pub fn format_file(file: &AtomicFile) -> Option<String> {
+    let (version, name, machine, path) = version_info(file)?;
+    Some(format_line(version, name, machine, path.display()))
+}
+
+/// Version, name, machine and path of the latest version of the file
+pub fn version_info(
+    file: &AtomicFile,
+) -> Option<(usize, String, String, PathBuf)> {
     let current = file.load().ok()?;
 
     if current.version == 0 {
@@ -131,15 +140,10 @@ pub fn format_file(file: &AtomicFile) -> Option<String> {
         .unwrap()
         .split('_');
 
-    let name = split.next().unwrap();
+    let name = split.next().unwrap().to_owned();
 
     let machine = split.next().unwrap();
-    let machine = &machine[..machine.len() - 2];
-
-    Some(format_line(
-        current.version,
-        name,
-        machine,
-        current.path.display(),
-    ))
+    let machine = machine[..machine.len() - 2].to_owned();
+
+    Some((current.version, name, machine, current.path))
 }
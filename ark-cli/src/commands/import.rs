@@ -0,0 +1,73 @@
+use std::{fs, path::PathBuf};
+
+use fs_properties::archive::{import_user_data, UserDataArchive};
+
+use crate::{
+    dir, provide_root, AppError, CopyOptions, ARCHIVE_CACHE_FOLDER,
+    ARCHIVE_USER_DATA_FILE, ARK_FOLDER,
+};
+
+#[derive(Clone, Debug, clap::Args)]
+#[clap(
+    name = "import",
+    about = "Import user data from an archive created by `export`"
+)]
+pub struct Import {
+    #[clap(value_parser, help = "Path to the archive directory")]
+    archive: PathBuf,
+    #[clap(long, help = "Root directory of the ark managed folder")]
+    root_dir: Option<PathBuf>,
+}
+
+impl Import {
+    pub fn run(&self) -> Result<(), AppError> {
+        let root = provide_root(&self.root_dir)?;
+
+        let source = self.archive.join(ARCHIVE_USER_DATA_FILE);
+        if !source.is_file() {
+            return Err(AppError::FileOperationError(format!(
+                "{} is not an ark archive",
+                self.archive.display()
+            )));
+        }
+        let content = fs::read(source)?;
+        let archive: UserDataArchive = serde_json::from_slice(&content)
+            .map_err(|e| {
+                AppError::FileOperationError(format!(
+                    "Couldn't parse archive: {}",
+                    e
+                ))
+            })?;
+
+        // Data is keyed by resource ids, so it is attached to the same
+        // resources regardless of where they are located in this root
+        import_user_data(&root, &archive)?;
+        println!(
+            "Imported tags of {}, scores of {} and properties of {} resources",
+            archive.tags.len(),
+            archive.scores.len(),
+            archive.properties.len()
+        );
+
+        let cache = self.archive.join(ARCHIVE_CACHE_FOLDER);
+        if cache.is_dir() {
+            let destination = root.join(ARK_FOLDER).join(ARCHIVE_CACHE_FOLDER);
+            fs::create_dir_all(&destination)?;
+
+            // Cached files of this root are kept, they are
+            // generated from the same content anyway
+            let mut options = CopyOptions::new();
+            options.content_only = true;
+            options.skip_exist = true;
+            dir::copy(cache, destination, &options).map_err(|e| {
+                AppError::FileOperationError(format!(
+                    "Failed to copy cache: {}",
+                    e
+                ))
+            })?;
+            println!("Imported cache");
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,86 @@
+use std::{path::PathBuf, time::SystemTime};
+
+use data_link::{Link, LinkFetchConfig};
+use serde_json::json;
+
+use crate::{
+    commands::link::utils::stale_links, config::output_format, parse_since,
+    print_json, provide_root, AppError, Format,
+};
+
+#[derive(Clone, Debug, clap::Args)]
+#[clap(name = "check", about = "Check stored links and report dead ones")]
+pub struct Check {
+    #[clap(value_parser, help = "Root directory of the ark managed folder")]
+    root_dir: Option<PathBuf>,
+    #[clap(
+        long,
+        value_name = "DATE|PERIOD",
+        value_parser = parse_since,
+        help = "Only check links not checked since a date (2024-05-01) \
+                or for a period (12h, 7d, 4w)"
+    )]
+    older_than: Option<SystemTime>,
+    #[clap(
+        long,
+        value_enum,
+        help = "Output format, raw unless configured otherwise"
+    )]
+    format: Option<Format>,
+}
+
+impl Check {
+    pub async fn run(&self) -> Result<(), AppError> {
+        let root = provide_root(&self.root_dir)?;
+        let links = stale_links(&root, self.older_than)?;
+        let json = output_format(self.format) == Format::KeyValue;
+
+        if !json {
+            println!("Checking {} links...", links.len());
+        }
+        let report =
+            Link::check_all(&root, &links, &LinkFetchConfig::default()).await?;
+
+        if json {
+            let dead: Vec<_> = report
+                .dead
+                .iter()
+                .map(|(id, health)| {
+                    let url = links
+                        .iter()
+                        .find(|link| link.id().ok().as_ref() == Some(id))
+                        .map(|link| link.url.to_string());
+                    json!({
+                        "id": id.to_string(),
+                        "link": url,
+                        "status": health.status,
+                        "error": health.error,
+                    })
+                })
+                .collect();
+            return print_json(&json!({
+                "checked": report.checked,
+                "dead": dead,
+            }));
+        }
+
+        for (id, health) in &report.dead {
+            let url = links
+                .iter()
+                .find(|link| link.id().ok().as_ref() == Some(id))
+                .map_or(String::new(), |link| link.url.to_string());
+            let reason = match (&health.status, &health.error) {
+                (Some(status), _) => format!("HTTP {}", status),
+                (None, Some(error)) => error.clone(),
+                (None, None) => "unreachable".to_owned(),
+            };
+            println!("{} {} ({})", id, url, reason);
+        }
+        println!(
+            "Checked {} links, {} dead",
+            report.checked,
+            report.dead.len()
+        );
+        Ok(())
+    }
+}
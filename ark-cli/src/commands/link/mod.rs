@@ -1,12 +1,16 @@
 use clap::Subcommand;
 
+mod check;
 pub mod create;
 mod load;
-mod utils;
+mod refresh;
+pub mod utils;
 
 /// Available commands for the `link` subcommand
 #[derive(Subcommand, Debug)]
 pub enum Link {
+    Check(check::Check),
     Create(create::Create),
     Load(load::Load),
+    Refresh(refresh::Refresh),
 }
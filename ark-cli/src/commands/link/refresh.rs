@@ -0,0 +1,66 @@
+use std::{path::PathBuf, time::SystemTime};
+
+use data_link::LinkFetchConfig;
+
+use crate::{
+    commands::link::utils::{last_health, restore_health, stale_links},
+    parse_since, provide_root, AppError,
+};
+
+#[derive(Clone, Debug, clap::Args)]
+#[clap(
+    name = "refresh",
+    about = "Fetch previews of stored links again, skipping unchanged pages"
+)]
+pub struct Refresh {
+    #[clap(value_parser, help = "Root directory of the ark managed folder")]
+    root_dir: Option<PathBuf>,
+    #[clap(
+        long,
+        value_name = "DATE|PERIOD",
+        value_parser = parse_since,
+        help = "Only refresh links not checked since a date (2024-05-01) \
+                or for a period (12h, 7d, 4w)"
+    )]
+    older_than: Option<SystemTime>,
+    #[clap(long, action, help = "Don't fetch preview images")]
+    no_preview: bool,
+}
+
+impl Refresh {
+    pub async fn run(&self) -> Result<(), AppError> {
+        let root = provide_root(&self.root_dir)?;
+        let links = stale_links(&root, self.older_than)?;
+        let config = LinkFetchConfig::default();
+
+        println!("Refreshing {} links...", links.len());
+        let mut failed = 0;
+        for link in &links {
+            let id = link.id()?;
+            let health = last_health(&root, &id);
+
+            // Validators of the previous responses are sent along,
+            // so unchanged pages are not downloaded again
+            match link
+                .refresh(&root, !self.no_preview, &config)
+                .await
+            {
+                Ok(()) => println!("\t{} {}", id, link.url),
+                Err(e) => {
+                    println!("\t{} {} failed: {}", id, link.url, e);
+                    failed += 1;
+                }
+            }
+            if let Some(health) = health {
+                restore_health(&root, &id, &health)?;
+            }
+        }
+
+        println!(
+            "Refreshed {} links, {} failed",
+            links.len() - failed,
+            failed
+        );
+        Ok(())
+    }
+}
@@ -1,10 +1,18 @@
 use crate::ResourceId;
-use data_link::Link;
-use std::path::PathBuf;
+use data_link::{load_links, Link, LinkHealth};
+use fs_metadata::{load_raw_metadata, store_metadata};
+use serde_json::Value;
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
 use url::Url;
 
 use crate::{error::AppError, util::provide_index}; // Import your custom AppError type
 
+/// Key of the link metadata holding the result of the last check
+const HEALTH_KEY: &str = "health";
+
 pub async fn create_link(
     root: &PathBuf,
     url: &str,
@@ -13,8 +21,12 @@ pub async fn create_link(
 ) -> Result<(), AppError> {
     let url = Url::parse(url)
         .map_err(|_| AppError::LinkCreationError("Invalid url".to_owned()))?;
-    let link: Link<ResourceId> =
+    let mut link: Link<ResourceId> =
         Link::new(url, title.to_owned(), desc.to_owned());
+    // Shortened links are saved under the id of the page they point to
+    if let Err(e) = link.resolve(&Default::default()).await {
+        log::debug!("Couldn't resolve {}: {}", link.url, e);
+    }
     link.save(root, true)
         .await
         .map_err(|e| AppError::LinkCreationError(e.to_string()))
@@ -62,3 +74,55 @@ pub fn load_link(
 
     Ok(Link::load(root, &path)?)
 }
+
+/// Links stored under the root, which weren't checked since `since`
+pub fn stale_links(
+    root: &Path,
+    since: Option<SystemTime>,
+) -> Result<Vec<Link<ResourceId>>, AppError> {
+    let links: Vec<Link<ResourceId>> = load_links(root)?;
+    let Some(since) = since else {
+        return Ok(links);
+    };
+    let since = since
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+
+    let mut stale = vec![];
+    for link in links {
+        let checked_at =
+            last_health(root, &link.id()?).map(|health| health.checked_at);
+        // Links which were never checked are always stale
+        if checked_at.map_or(true, |checked_at| checked_at < since) {
+            stale.push(link);
+        }
+    }
+    Ok(stale)
+}
+
+/// Result of the last check of the link, stored in its metadata
+pub fn last_health(root: &Path, id: &ResourceId) -> Option<LinkHealth> {
+    let bytes = load_raw_metadata(root, id.clone()).ok()?;
+    let metadata: Value = serde_json::from_slice(&bytes).ok()?;
+    serde_json::from_value(metadata.get(HEALTH_KEY)?.clone()).ok()
+}
+
+/// Put the result of the last check back into the metadata,
+/// refreshing a link replaces its metadata with the fetched one
+pub fn restore_health(
+    root: &Path,
+    id: &ResourceId,
+    health: &LinkHealth,
+) -> Result<(), AppError> {
+    let mut metadata: Value = load_raw_metadata(root, id.clone())
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_else(|| Value::Object(Default::default()));
+    if let Value::Object(map) = &mut metadata {
+        let health = serde_json::to_value(health)
+            .map_err(|e| AppError::FileOperationError(e.to_string()))?;
+        map.insert(HEALTH_KEY.to_owned(), health);
+    }
+    store_metadata(root, id.clone(), &metadata)?;
+    Ok(())
+}
@@ -1,8 +1,18 @@
-use std::{io::Read, path::PathBuf};
+use std::{
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
 
 use crate::{
-    provide_index, provide_root, read_storage_value, AppError, DateTime,
-    EntryOutput, File, Sort, StorageEntry, Utc,
+    commands::{
+        score::utils::{parse_score, score_storage},
+        tag::utils::{parse_tags, tag_storage},
+    },
+    config::output_format,
+    parse_since, print_json, provide_index, provide_root, AppError, DateTime,
+    EntryOutput, File, Format, ResourceId, Sort, StorageEntry, Utc,
 };
 
 #[derive(Clone, Debug, clap::Args)]
@@ -38,6 +48,46 @@ pub struct List {
     sort: Option<Sort>,
     #[clap(long, help = "Filter the entries by tag")]
     filter: Option<String>,
+    #[clap(
+        long,
+        value_delimiter = ',',
+        help = "Only list entries having all of these tags"
+    )]
+    tag: Vec<String>,
+    #[clap(
+        long,
+        action,
+        conflicts_with_all = ["tag", "filter"],
+        help = "Only list entries without tags"
+    )]
+    untagged: bool,
+    #[clap(long, help = "Only list entries with at least this score")]
+    min_score: Option<i64>,
+    #[clap(
+        long,
+        value_parser = parse_since,
+        help = "Only list entries modified since a date (2024-05-01) \
+                or a period ago (12h, 7d, 4w)"
+    )]
+    modified_since: Option<SystemTime>,
+    #[clap(
+        long,
+        value_delimiter = ',',
+        help = "Only list entries with these extensions, e.g. pdf,png"
+    )]
+    ext: Vec<String>,
+    #[clap(
+        long,
+        value_parser = parse_size,
+        help = "Only list entries larger than a size (500, 10K, 2M, 1G)"
+    )]
+    larger_than: Option<u64>,
+    #[clap(
+        long,
+        value_enum,
+        help = "Output format, raw unless configured otherwise"
+    )]
+    format: Option<Format>,
 }
 
 impl List {
@@ -63,6 +113,58 @@ impl List {
         }
     }
 
+    /// Check the resource against the filters, cheapest checks first
+    fn matches(
+        &self,
+        root: &Path,
+        id: &ResourceId,
+        path: &Path,
+        modified: SystemTime,
+    ) -> bool {
+        if !self.ext.is_empty() {
+            let extension = path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .map(|extension| extension.to_lowercase());
+            let matches = extension.is_some_and(|extension| {
+                self.ext.iter().any(|wanted| {
+                    wanted.trim_start_matches('.').to_lowercase() == extension
+                })
+            });
+            if !matches {
+                return false;
+            }
+        }
+        if self
+            .modified_since
+            .is_some_and(|since| modified < since)
+        {
+            return false;
+        }
+        if let Some(size) = self.larger_than {
+            let len = fs::metadata(root.join(path))
+                .map(|metadata| metadata.len())
+                .unwrap_or(0);
+            if len <= size {
+                return false;
+            }
+        }
+        if self
+            .min_score
+            .is_some_and(|min| read_score(root, id) < min)
+        {
+            return false;
+        }
+        if !self.tag.is_empty() || self.untagged {
+            let tags = read_tags(root, id);
+            if self.untagged {
+                return tags.is_empty();
+            }
+            return self.tag.iter().all(|tag| tags.contains(tag));
+        }
+        true
+    }
+
     pub fn run(&self) -> Result<(), AppError> {
         let root = provide_root(&self.root_dir)?;
         let entry_output = self.entry()?;
@@ -80,34 +182,19 @@ impl List {
             .filter_map(|indexed_resource| {
                 let path = indexed_resource.path();
                 let id = indexed_resource.id();
+                let modified = indexed_resource.last_modified();
+                if !self.matches(&root, id, path, modified) {
+                    return None;
+                }
+
                 let tags = if self.tags {
-                    Some(
-                        read_storage_value(
-                            &root,
-                            "tags",
-                            &id.to_string(),
-                            &None,
-                        )
-                        .map_or(vec![], |s| {
-                            s.split(',')
-                                .map(|s| s.trim().to_string())
-                                .collect::<Vec<_>>()
-                        }),
-                    )
+                    Some(read_tags(&root, id))
                 } else {
                     None
                 };
 
                 let scores = if self.scores {
-                    Some(
-                        read_storage_value(
-                            &root,
-                            "scores",
-                            &id.to_string(),
-                            &None,
-                        )
-                        .map_or(0, |s| s.parse::<u32>().unwrap_or(0)),
-                    )
+                    Some(read_score(&root, id).max(0) as u32)
                 } else {
                     None
                 };
@@ -115,7 +202,7 @@ impl List {
                 let datetime = if self.modified {
                     let format = "%b %e %H:%M %Y";
                     Some(
-                        DateTime::<Utc>::from(indexed_resource.last_modified())
+                        DateTime::<Utc>::from(modified)
                             .format(format)
                             .to_string(),
                     )
@@ -184,6 +271,14 @@ impl List {
             });
         }
 
+        if output_format(self.format) == Format::KeyValue {
+            let entries: Vec<_> = storage_entries
+                .iter()
+                .map(StorageEntry::to_json)
+                .collect();
+            return print_json(&entries.into());
+        }
+
         let no_tags = "NO_TAGS";
         let no_scores = "NO_SCORE";
 
@@ -344,3 +439,36 @@ impl List {
         Ok(())
     }
 }
+
+/// Tags of all lines of the resource, like the `tag` command reads them
+fn read_tags(root: &Path, id: &ResourceId) -> Vec<String> {
+    tag_storage(&root.to_path_buf())
+        .and_then(|storage| storage.values(id))
+        .map(|values| parse_tags(&values).into_iter().collect())
+        .unwrap_or_default()
+}
+
+/// Latest score of the resource, like the `score` command reads it
+fn read_score(root: &Path, id: &ResourceId) -> i64 {
+    score_storage(&root.to_path_buf())
+        .ok()
+        .and_then(|storage| storage.values(id).ok())
+        .and_then(|values| parse_score(&values))
+        .map_or(0, i64::from)
+}
+
+/// Parse a size like `500`, `10K` or `2M` into bytes
+fn parse_size(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let (number, multiplier) = match value.chars().last() {
+        Some('K' | 'k') => (&value[..value.len() - 1], 1 << 10),
+        Some('M' | 'm') => (&value[..value.len() - 1], 1 << 20),
+        Some('G' | 'g') => (&value[..value.len() - 1], 1 << 30),
+        _ => (value, 1),
+    };
+    number
+        .parse::<u64>()
+        .map_err(|_| format!("Invalid size {}", value))?
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("Size {} is too large", value))
+}
@@ -1,38 +1,88 @@
 use clap::Subcommand;
 
 mod backup;
-mod collisions;
+mod clean;
+pub mod collisions;
+mod completions;
+mod dedupe;
+pub mod drop;
+mod export;
+pub mod favorites;
 pub mod file;
+mod import;
 pub mod link;
 mod list;
-mod monitor;
+pub mod monitor;
 mod render;
+mod restore;
+pub mod score;
+mod search;
+mod stats;
 pub mod storage;
+pub mod tag;
+pub mod thumbnails;
+mod tui;
 mod watch;
 
-pub use file::{file_append, file_insert, format_file, format_line};
+pub use file::{
+    file_append, file_insert, format_file, format_line, version_info,
+};
 
 #[derive(Debug, Subcommand)]
 pub enum Commands {
     Backup(backup::Backup),
+    Clean(clean::Clean),
     Collisions(collisions::Collisions),
+    Completions(completions::Completions),
+    Dedupe(dedupe::Dedupe),
+    Export(export::Export),
+    Import(import::Import),
     Monitor(monitor::Monitor),
     Render(render::Render),
+    Restore(restore::Restore),
     List(list::List),
+    Search(search::Search),
+    Stats(stats::Stats),
+    Tui(tui::Tui),
     Watch(watch::Watch),
     #[command(about = "Manage links")]
     Link {
         #[clap(subcommand)]
         subcommand: link::Link,
     },
+    #[command(about = "Share files with other devices")]
+    Drop {
+        #[clap(subcommand)]
+        subcommand: drop::Drop,
+    },
+    #[command(about = "Manage favorite resources")]
+    Favorites {
+        #[clap(subcommand)]
+        subcommand: favorites::Favorites,
+    },
     #[command(about = "Manage files")]
     File {
         #[clap(subcommand)]
         subcommand: file::File,
     },
+    #[command(about = "Manage scores")]
+    Score {
+        #[clap(subcommand)]
+        subcommand: score::Score,
+    },
     #[command(about = "Manage storage")]
     Storage {
         #[clap(subcommand)]
         subcommand: storage::Storage,
     },
+    #[command(about = "Manage tags")]
+    Tag {
+        #[clap(subcommand)]
+        subcommand: tag::Tag,
+    },
+    #[command(about = "Manage thumbnails")]
+    Thumbnails {
+        #[clap(subcommand)]
+        subcommand: thumbnails::Thumbnails,
+    },
 }
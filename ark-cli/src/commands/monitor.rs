@@ -1,23 +1,184 @@
-use std::path::PathBuf;
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use crate::{monitor_index, AppError};
+use chrono::{Local, NaiveTime};
+use fs_index::IndexUpdate;
+use serde_json::{json, Value};
+
+use crate::{
+    config::{config, output_format},
+    monitor_index,
+    util::shell,
+    AppError, Format, ResourceId,
+};
+
+/// Interval used if neither the flag nor the config sets one
+const DEFAULT_INTERVAL: u64 = 1000;
 
 #[derive(Clone, Debug, clap::Args)]
 #[clap(name = "monitor", about = "Monitor the ark managed folder")]
 pub struct Monitor {
     #[clap(value_parser, help = "Path to the root directory")]
     root_dir: Option<PathBuf>,
+    #[clap(help = "Interval to check for changes in milliseconds \
+                   [default: 1000]")]
+    interval: Option<u64>,
     #[clap(
-        default_value = "1000",
-        help = "Interval to check for changes in milliseconds"
+        long,
+        value_enum,
+        help = "Output format, raw unless configured otherwise"
     )]
-    interval: Option<u64>,
+    format: Option<Format>,
+    #[clap(
+        long,
+        value_name = "HH:MM-HH:MM",
+        value_parser = QuietHours::parse,
+        help = "Local time range without checks, e.g. 22:00-07:00"
+    )]
+    quiet_hours: Option<QuietHours>,
+    #[clap(
+        long,
+        value_name = "URL",
+        help = "POST changes of the index as JSON to this url"
+    )]
+    webhook: Option<String>,
+    #[clap(
+        long,
+        value_name = "COMMAND",
+        help = "Run a command when the index changes, changed ids are \
+                given in ARK_ADDED and ARK_REMOVED"
+    )]
+    exec: Option<String>,
 }
 
 impl Monitor {
     pub fn run(&self) -> Result<(), AppError> {
-        // SAFETY: interval is always Some since it has a default value in clap
-        let millis = self.interval.unwrap();
-        monitor_index(&self.root_dir, Some(millis))
+        let settings = &config().monitor;
+        let millis = self
+            .interval
+            .or(settings.interval)
+            .unwrap_or(DEFAULT_INTERVAL);
+        let quiet_hours = match (&self.quiet_hours, &settings.quiet_hours) {
+            (Some(hours), _) => Some(hours.clone()),
+            (None, Some(hours)) => {
+                Some(QuietHours::parse(hours).map_err(AppError::ConfigError)?)
+            }
+            (None, None) => None,
+        };
+
+        let options = MonitorOptions {
+            interval: Duration::from_millis(millis),
+            quiet_hours,
+            webhook: self.webhook.clone().or(settings.webhook.clone()),
+            exec: self.exec.clone().or(settings.exec.clone()),
+        };
+        monitor_index(&self.root_dir, Some(options), output_format(self.format))
+    }
+}
+
+/// Settings of continuous monitoring
+#[derive(Debug, Clone)]
+pub struct MonitorOptions {
+    pub interval: Duration,
+    pub quiet_hours: Option<QuietHours>,
+    pub webhook: Option<String>,
+    pub exec: Option<String>,
+}
+
+impl MonitorOptions {
+    /// Tell the webhook and the command about changes of the index.
+    /// Failures are reported without stopping the monitoring.
+    pub fn notify(&self, root: &Path, diff: &IndexUpdate<ResourceId>) {
+        if diff.added().is_empty() && diff.removed().is_empty() {
+            return;
+        }
+        let added: Vec<String> = diff
+            .added()
+            .keys()
+            .map(|id| id.to_string())
+            .collect();
+        let removed: Vec<String> = diff
+            .removed()
+            .iter()
+            .map(|id| id.to_string())
+            .collect();
+
+        if let Some(url) = &self.webhook {
+            let payload = json!({
+                "root": root,
+                "added": added,
+                "removed": removed,
+            });
+            if let Err(e) = post(url, &payload) {
+                eprintln!("Webhook {} failed: {}", url, e);
+            }
+        }
+        if let Some(command) = &self.exec {
+            let status = shell(command)
+                .env("ARK_ROOT", root)
+                .env("ARK_ADDED", added.join(" "))
+                .env("ARK_REMOVED", removed.join(" "))
+                .status();
+            match status {
+                Ok(status) if !status.success() => {
+                    eprintln!("Command `{}` failed with {}", command, status)
+                }
+                Ok(_) => {}
+                Err(e) => eprintln!("Failed to run `{}`: {}", command, e),
+            }
+        }
+    }
+}
+
+/// Range of local time, which may span midnight
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl QuietHours {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let (start, end) = value
+            .split_once('-')
+            .ok_or_else(|| format!("Expected HH:MM-HH:MM, got {}", value))?;
+        let time = |time: &str| {
+            NaiveTime::parse_from_str(time.trim(), "%H:%M")
+                .map_err(|_| format!("Invalid time {}", time))
+        };
+        Ok(Self {
+            start: time(start)?,
+            end: time(end)?,
+        })
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.contains(Local::now().time())
     }
+
+    fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+fn post(url: &str, payload: &Value) -> Result<(), reqwest::Error> {
+    // Monitoring loop runs inside of the runtime of `main`
+    tokio::task::block_in_place(|| {
+        tokio::runtime::Handle::current().block_on(async {
+            reqwest::Client::new()
+                .post(url)
+                .json(payload)
+                .timeout(Duration::from_secs(10))
+                .send()
+                .await?
+                .error_for_status()
+                .map(|_| ())
+        })
+    })
 }
@@ -1,26 +1,92 @@
-use std::path::PathBuf;
+use std::{
+    io::{Cursor, Write},
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+};
 
-use crate::{render_preview_page, AppError, File, PDFQuality};
+use data_pdf::render_pages;
+use data_preview::ResourceKind;
+use fs_atomic_versions::atomic::AtomicFile;
+use fs_storage::{ARK_FOLDER, PREVIEWS_STORAGE_FOLDER};
+use image::ImageFormat;
+
+use crate::{
+    commands::drop::query::{ResourceQuery, Selected},
+    provide_root, render_preview_page, AppError, File, PDFQuality, ResourceId,
+};
 
 #[derive(Clone, Debug, clap::Args)]
-#[clap(name = "render", about = "Render a PDF file to an image")]
+#[clap(
+    name = "render",
+    about = "Render a PDF file to an image, or previews of all PDFs of a root"
+)]
 pub struct Render {
-    #[clap(value_parser, help = "The path to the file to render")]
-    path: PathBuf,
-    #[clap(help = "The quality of the rendering")]
+    #[clap(
+        value_parser,
+        help = "The PDF file to render next to it, or the root directory \
+                whose PDFs are rendered into the previews cache"
+    )]
+    path: Option<PathBuf>,
+    #[clap(help = "The quality of the rendering: high, medium or low")]
     quality: Option<String>,
+    #[clap(flatten)]
+    query: ResourceQuery,
+    #[clap(
+        long,
+        action,
+        help = "Only render previews which are not cached yet"
+    )]
+    missing_only: bool,
+    #[clap(
+        short,
+        long,
+        help = "Number of documents rendered in parallel, \
+                all available cores by default"
+    )]
+    jobs: Option<NonZeroUsize>,
+}
+
+/// Outcome of rendering the preview of a single document
+enum Rendered {
+    Done,
+    Skipped(&'static str),
 }
 
 impl Render {
     pub fn run(&self) -> Result<(), AppError> {
-        let filepath = self.path.to_owned();
-        let quality = match self.quality.to_owned().unwrap().as_str() {
+        // Validate the option before anything gets rendered
+        self.quality()?;
+        match &self.path {
+            Some(path) if path.is_file() => {
+                if !self.query.is_empty() {
+                    return Err(AppError::FileOperationError(
+                        "Queries select PDFs of a root directory, not a file"
+                            .to_owned(),
+                    ));
+                }
+                self.render_file(path)
+            }
+            path => self.render_root(&provide_root(path)?),
+        }
+    }
+
+    fn quality(&self) -> Result<PDFQuality, AppError> {
+        match self.quality.as_deref().unwrap_or("medium") {
             "high" => Ok(PDFQuality::High),
             "medium" => Ok(PDFQuality::Medium),
             "low" => Ok(PDFQuality::Low),
             _ => Err(AppError::InvalidRenderOption),
-        }?;
-        let buf = File::open(&filepath).map_err(|e| {
+        }
+    }
+
+    /// Render the first page of the file into a PNG image next to it
+    fn render_file(&self, filepath: &Path) -> Result<(), AppError> {
+        let buf = File::open(filepath).map_err(|e| {
             AppError::FileOperationError(format!("Failed to open file: {}", e))
         })?;
         let dest_path = filepath.with_file_name(
@@ -34,10 +100,138 @@ impl Render {
                 .to_owned()
                 + ".png",
         );
-        let img = render_preview_page(buf, quality);
+        let img = render_preview_page(buf, self.quality()?);
         img.save(dest_path).map_err(|e| {
             AppError::FileOperationError(format!("Failed to save image: {}", e))
         })?;
         Ok(())
     }
+
+    /// Render previews of the PDFs of the root selected by the query,
+    /// stored in `.ark/cache/previews/<id>`
+    fn render_root(&self, root: &Path) -> Result<(), AppError> {
+        let documents: Vec<Selected> = self
+            .query
+            .select(root)?
+            .into_iter()
+            .filter(|selected| {
+                ResourceKind::from_path(&selected.path)
+                    == Some(ResourceKind::Pdf)
+            })
+            .collect();
+
+        let total = documents.len();
+        if total == 0 {
+            println!("No PDFs found in {}", root.display());
+            return Ok(());
+        }
+
+        let jobs = self
+            .jobs
+            .or_else(|| thread::available_parallelism().ok())
+            .map_or(1, NonZeroUsize::get)
+            .min(total);
+        let next = AtomicUsize::new(0);
+        let done = AtomicUsize::new(0);
+        let skipped = Mutex::new(vec![]);
+        let failed = Mutex::new(vec![]);
+
+        thread::scope(|scope| {
+            for _ in 0..jobs {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::SeqCst);
+                    let Some(document) = documents.get(index) else {
+                        break;
+                    };
+
+                    let result = self.render_preview(root, document);
+                    let count = done.fetch_add(1, Ordering::SeqCst) + 1;
+                    match result {
+                        Ok(Rendered::Done) => {
+                            println!("[{}/{}] {}", count, total, document.name)
+                        }
+                        Ok(Rendered::Skipped(reason)) => {
+                            println!(
+                                "[{}/{}] {} skipped: {}",
+                                count, total, document.name, reason
+                            );
+                            skipped
+                                .lock()
+                                .unwrap()
+                                .push(document.name.clone());
+                        }
+                        Err(e) => {
+                            println!(
+                                "[{}/{}] {} failed: {}",
+                                count, total, document.name, e
+                            );
+                            failed.lock().unwrap().push(document.name.clone());
+                        }
+                    }
+                });
+            }
+        });
+
+        let mut skipped = skipped.into_inner().unwrap();
+        let mut failed = failed.into_inner().unwrap();
+        skipped.sort();
+        failed.sort();
+        println!(
+            "Rendered previews of {} PDFs, {} skipped, {} failed",
+            total - skipped.len() - failed.len(),
+            skipped.len(),
+            failed.len()
+        );
+        for name in &skipped {
+            println!("Skipped: {}", name);
+        }
+        for name in &failed {
+            println!("Failed: {}", name);
+        }
+        Ok(())
+    }
+
+    fn render_preview(
+        &self,
+        root: &Path,
+        document: &Selected,
+    ) -> Result<Rendered, AppError> {
+        let file = AtomicFile::new(preview_path(root, &document.id))?;
+        let current = file.load()?;
+        if self.missing_only && current.version > 0 {
+            return Ok(Rendered::Skipped("already cached"));
+        }
+
+        let Some(page) = render_pages(&document.path, 0..1, self.quality()?)
+            .map_err(|e| {
+                AppError::FileOperationError(format!(
+                    "Failed to render PDF: {:?}",
+                    e
+                ))
+            })?
+            .into_iter()
+            .next()
+        else {
+            return Ok(Rendered::Skipped("no pages"));
+        };
+
+        let mut bytes = Cursor::new(vec![]);
+        page.write_to(&mut bytes, ImageFormat::Png)
+            .map_err(|e| {
+                AppError::FileOperationError(format!(
+                    "Failed to encode image: {}",
+                    e
+                ))
+            })?;
+        let tmp = file.make_temp()?;
+        (&tmp).write_all(bytes.get_ref())?;
+        file.compare_and_swap(&current, tmp)?;
+        Ok(Rendered::Done)
+    }
+}
+
+fn preview_path(root: &Path, id: &ResourceId) -> PathBuf {
+    root.join(ARK_FOLDER)
+        .join(PREVIEWS_STORAGE_FOLDER)
+        .join(id.to_string())
 }
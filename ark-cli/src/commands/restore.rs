@@ -0,0 +1,113 @@
+use std::{fs, path::PathBuf};
+
+use crate::{provide_root, AppError, ARK_FOLDER};
+
+use super::backup::{
+    backup_base, backups_dir, list_backups, resolve_manifest, root_backup,
+};
+
+const RESTORING_FOLDER: &str = ".ark-restoring";
+const PREVIOUS_FOLDER: &str = ".ark-before-restore";
+
+#[derive(Clone, Debug, clap::Args)]
+#[clap(
+    name = "restore",
+    about = "Roll back the ark managed folder to a backup"
+)]
+pub struct Restore {
+    #[clap(
+        value_parser,
+        help = "Timestamp of the backup, the latest one by default"
+    )]
+    timestamp: Option<u64>,
+    #[clap(long, help = "Root directory of the ark managed folder")]
+    root_dir: Option<PathBuf>,
+}
+
+impl Restore {
+    pub fn run(&self) -> Result<(), AppError> {
+        let root = provide_root(&self.root_dir)?;
+        let backups = backups_dir()?;
+
+        let timestamp = match self.timestamp {
+            Some(timestamp) => timestamp,
+            None => list_backups(&backups)?
+                .into_iter()
+                .rev()
+                .find(|timestamp| {
+                    let backup = backups.join(timestamp.to_string());
+                    root_backup(&backup, &root).is_some()
+                })
+                .ok_or_else(|| {
+                    AppError::FileOperationError(format!(
+                        "No backups of {}",
+                        root.display()
+                    ))
+                })?,
+        };
+
+        let manifest = resolve_manifest(&backups, timestamp, &root)?
+            .ok_or_else(|| {
+                AppError::FileOperationError(format!(
+                    "Backup {} doesn't contain {}",
+                    timestamp,
+                    root.display()
+                ))
+            })?;
+
+        // Incremental backups contain only changed files,
+        // the rest is taken from the backups they are based on
+        let mut chain = vec![];
+        let mut next = Some(timestamp);
+        while let Some(timestamp) = next {
+            if chain.contains(&timestamp) {
+                break;
+            }
+            let backup = backups.join(timestamp.to_string());
+            if let Some(folder) = root_backup(&backup, &root) {
+                chain.push(folder);
+            }
+            next = backup_base(&backup);
+        }
+
+        println!("Restoring {} from backup {}", root.display(), timestamp);
+        let restoring = root.join(RESTORING_FOLDER);
+        if restoring.exists() {
+            fs::remove_dir_all(&restoring)?;
+        }
+        for file in manifest.keys() {
+            let source = chain
+                .iter()
+                .map(|folder| folder.join(file))
+                .find(|source| source.is_file())
+                .ok_or_else(|| {
+                    AppError::FileOperationError(format!(
+                        "{} is missing in backup {} and its bases",
+                        file.display(),
+                        timestamp
+                    ))
+                })?;
+            let target = restoring.join(file);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(source, target)?;
+        }
+        fs::create_dir_all(&restoring)?;
+
+        // Current data is kept aside until the next restore
+        let ark = root.join(ARK_FOLDER);
+        let previous = root.join(PREVIOUS_FOLDER);
+        if ark.exists() {
+            if previous.exists() {
+                fs::remove_dir_all(&previous)?;
+            }
+            fs::rename(&ark, &previous)?;
+            println!("Previous data moved to:\n\t{}", previous.display());
+        }
+        fs::rename(&restoring, &ark)?;
+
+        println!("Restored {} files", manifest.len());
+        Ok(())
+    }
+}
@@ -0,0 +1,27 @@
+use std::path::PathBuf;
+
+use crate::{
+    commands::score::utils::score_storage, models::ResourceSelector,
+    provide_root, resolve_resource, AppError,
+};
+
+#[derive(Clone, Debug, clap::Args)]
+#[clap(name = "clear", about = "Remove score of a resource")]
+pub struct Clear {
+    #[clap(flatten)]
+    selector: ResourceSelector,
+    #[clap(long, help = "Root directory of the ark managed folder")]
+    root_dir: Option<PathBuf>,
+}
+
+impl Clear {
+    pub fn run(&self) -> Result<(), AppError> {
+        let root = provide_root(&self.root_dir)?;
+        let id = resolve_resource(&root, &self.selector)?;
+
+        let mut storage = score_storage(&root)?;
+        storage.update(&id, |_| None)?;
+
+        Ok(())
+    }
+}
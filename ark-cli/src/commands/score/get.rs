@@ -0,0 +1,31 @@
+use std::path::PathBuf;
+
+use crate::{
+    commands::score::utils::{parse_score, score_storage},
+    models::ResourceSelector,
+    provide_root, resolve_resource, AppError,
+};
+
+#[derive(Clone, Debug, clap::Args)]
+#[clap(name = "get", about = "Print score of a resource")]
+pub struct Get {
+    #[clap(flatten)]
+    selector: ResourceSelector,
+    #[clap(long, help = "Root directory of the ark managed folder")]
+    root_dir: Option<PathBuf>,
+}
+
+impl Get {
+    pub fn run(&self) -> Result<(), AppError> {
+        let root = provide_root(&self.root_dir)?;
+        let id = resolve_resource(&root, &self.selector)?;
+
+        let storage = score_storage(&root)?;
+        match parse_score(&storage.values(&id)?) {
+            Some(score) => println!("{}", score),
+            None => println!("NO_SCORE"),
+        }
+
+        Ok(())
+    }
+}
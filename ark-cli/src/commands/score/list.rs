@@ -0,0 +1,49 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{
+    commands::score::utils::{parse_score, score_storage},
+    provide_root, AppError,
+};
+
+#[derive(Clone, Debug, clap::Args)]
+#[clap(name = "list", about = "List scores of resources")]
+pub struct List {
+    #[clap(value_parser, help = "Root directory of the ark managed folder")]
+    root_dir: Option<PathBuf>,
+    #[clap(long, action, help = "Show top-scored resources first")]
+    sorted: bool,
+    #[clap(long, short = 'n', help = "Show only this many resources")]
+    limit: Option<usize>,
+}
+
+impl List {
+    pub fn run(&self) -> Result<(), AppError> {
+        let root = provide_root(&self.root_dir)?;
+        let storage = score_storage(&root)?;
+
+        let mut values: BTreeMap<_, Vec<String>> = BTreeMap::new();
+        for (id, value) in storage.entries()? {
+            values.entry(id).or_default().push(value);
+        }
+        let mut scores: Vec<_> = values
+            .into_iter()
+            .filter_map(|(id, values)| Some((id, parse_score(&values)?)))
+            .collect();
+        if self.sorted {
+            scores.sort_by(|(_, a), (_, b)| b.cmp(a));
+        }
+
+        let longest = scores
+            .iter()
+            .map(|(id, _)| id.to_string().len())
+            .max()
+            .unwrap_or(0);
+        for (id, score) in scores
+            .iter()
+            .take(self.limit.unwrap_or(usize::MAX))
+        {
+            println!("{:width$} {}", id.to_string(), score, width = longest);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,16 @@
+use clap::Subcommand;
+
+mod clear;
+mod get;
+mod list;
+mod set;
+pub mod utils;
+
+/// Available commands for the `score` subcommand
+#[derive(Subcommand, Debug)]
+pub enum Score {
+    Set(set::Set),
+    Get(get::Get),
+    Clear(clear::Clear),
+    List(list::List),
+}
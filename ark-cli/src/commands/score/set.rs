@@ -0,0 +1,34 @@
+use std::path::PathBuf;
+
+use crate::{
+    commands::score::utils::{score_storage, MAX_SCORE, MIN_SCORE},
+    models::ResourceSelector,
+    provide_root, resolve_resource, AppError,
+};
+
+#[derive(Clone, Debug, clap::Args)]
+#[clap(name = "set", about = "Set score of a resource")]
+pub struct Set {
+    #[clap(flatten)]
+    selector: ResourceSelector,
+    #[clap(
+        allow_negative_numbers = true,
+        value_parser = clap::value_parser!(i32).range(MIN_SCORE..=MAX_SCORE),
+        help = "Score of the resource"
+    )]
+    score: i32,
+    #[clap(long, help = "Root directory of the ark managed folder")]
+    root_dir: Option<PathBuf>,
+}
+
+impl Set {
+    pub fn run(&self) -> Result<(), AppError> {
+        let root = provide_root(&self.root_dir)?;
+        let id = resolve_resource(&root, &self.selector)?;
+
+        let mut storage = score_storage(&root)?;
+        storage.update(&id, |_| Some(self.score.to_string()))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,24 @@
+use std::path::PathBuf;
+
+use crate::{
+    models::storage::{Storage, StorageType},
+    translate_storage, AppError,
+};
+
+/// Scores are limited to keep them comparable between apps
+pub const MIN_SCORE: i64 = -100;
+pub const MAX_SCORE: i64 = 100;
+
+pub fn score_storage(root: &PathBuf) -> Result<Storage, AppError> {
+    let (path, _) = translate_storage(&Some(root.to_owned()), "scores")
+        .ok_or(AppError::StorageNotFound("scores".to_owned()))?;
+    Storage::new(path, StorageType::File)
+}
+
+/// Score of a resource, the latest appended line wins
+pub fn parse_score(values: &[String]) -> Option<i32> {
+    values
+        .iter()
+        .rev()
+        .find_map(|value| value.trim().parse().ok())
+}
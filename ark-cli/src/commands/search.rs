@@ -0,0 +1,177 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use serde_json::json;
+
+use crate::{
+    commands::{
+        score::utils::{parse_score, score_storage},
+        tag::utils::{parse_tags, tag_storage},
+    },
+    print_json, provide_root, read_storage_value, AppError, ResourceId,
+};
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum SearchOutput {
+    Path,
+    Id,
+    Both,
+    Json,
+}
+
+#[derive(Clone, Debug, clap::Args)]
+#[clap(
+    name = "search",
+    about = "Search resources by tags, extensions, scores and text"
+)]
+pub struct Search {
+    #[clap(value_parser, help = "The path to the root directory")]
+    root_dir: Option<PathBuf>,
+    #[clap(long, help = "Required tag, can be repeated")]
+    tag: Vec<String>,
+    #[clap(long, help = "Allowed file extension, can be repeated")]
+    ext: Vec<String>,
+    #[clap(
+        long,
+        short,
+        help = "Text to find in paths, properties or metadata"
+    )]
+    query: Option<String>,
+    #[clap(long, allow_negative_numbers = true, help = "Minimal score")]
+    min_score: Option<i32>,
+    #[clap(
+        long,
+        short,
+        value_enum,
+        default_value = "path",
+        help = "Output format"
+    )]
+    output: SearchOutput,
+}
+
+impl Search {
+    pub fn run(&self) -> Result<(), AppError> {
+        let root = provide_root(&self.root_dir)?;
+
+        let tags = group_entries(tag_storage(&root)?.entries()?);
+        let scores = group_entries(score_storage(&root)?.entries()?);
+        let query = self
+            .query
+            .as_ref()
+            .map(|query| query.to_lowercase());
+        let extensions: Vec<String> = self
+            .ext
+            .iter()
+            .map(|ext| ext.to_lowercase())
+            .collect();
+
+        let mut results = vec![];
+        for resource in crate::util::provide_index(&root).resources() {
+            let id = resource.id();
+            let path = resource.path();
+
+            if !extensions.is_empty() {
+                let extension = path
+                    .extension()
+                    .map(|ext| ext.to_string_lossy().to_lowercase());
+                match extension {
+                    Some(ext) if extensions.contains(&ext) => {}
+                    _ => continue,
+                }
+            }
+
+            let resource_tags = tags
+                .get(id)
+                .map(|values| parse_tags(values))
+                .unwrap_or_default();
+            if !self
+                .tag
+                .iter()
+                .all(|tag| resource_tags.contains(tag.trim()))
+            {
+                continue;
+            }
+
+            let score = scores
+                .get(id)
+                .and_then(|values| parse_score(values));
+            if let Some(min_score) = self.min_score {
+                if score.unwrap_or(0) < min_score {
+                    continue;
+                }
+            }
+
+            if let Some(query) = &query {
+                if !self.matches_text(
+                    &root,
+                    id,
+                    &path.display().to_string(),
+                    query,
+                ) {
+                    continue;
+                }
+            }
+
+            results.push((id.clone(), path.to_owned(), resource_tags, score));
+        }
+
+        match self.output {
+            SearchOutput::Json => {
+                let results: Vec<_> = results
+                    .iter()
+                    .map(|(id, path, tags, score)| {
+                        json!({
+                            "id": id.to_string(),
+                            "path": path,
+                            "tags": tags,
+                            "score": score,
+                        })
+                    })
+                    .collect();
+                print_json(&results.into())?;
+            }
+            output => {
+                for (id, path, _, _) in results {
+                    match output {
+                        SearchOutput::Id => println!("{}", id),
+                        SearchOutput::Both => {
+                            println!("{} {}", id, path.display())
+                        }
+                        _ => println!("{}", path.display()),
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Case-insensitive match in the path, properties and metadata,
+    /// including metadata authored outside of ark
+    fn matches_text(
+        &self,
+        root: &PathBuf,
+        id: &ResourceId,
+        path: &str,
+        query: &str,
+    ) -> bool {
+        if path.to_lowercase().contains(query) {
+            return true;
+        }
+        ["properties", "metadata", "external"]
+            .iter()
+            .any(|storage| {
+                read_storage_value(root, storage, &id.to_string(), &None)
+                    .map(|text| text.to_lowercase().contains(query))
+                    .unwrap_or(false)
+            })
+    }
+}
+
+fn group_entries(
+    entries: Vec<(ResourceId, String)>,
+) -> BTreeMap<ResourceId, Vec<String>> {
+    let mut grouped: BTreeMap<_, Vec<String>> = BTreeMap::new();
+    for (id, value) in entries {
+        grouped.entry(id).or_default().push(value);
+    }
+    grouped
+}
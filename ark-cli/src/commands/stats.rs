@@ -0,0 +1,194 @@
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use fs_atomic_versions::app_id;
+use fs_index::load_or_build_index;
+use fs_storage::INDEX_PATH;
+use serde_json::json;
+
+use crate::{
+    commands::tag::utils::{parse_tags, tag_storage},
+    config::output_format,
+    print_json, provide_root, AppError, DateTime, Format, ResourceId, Utc,
+    ARK_FOLDER,
+};
+
+const NO_EXTENSION: &str = "<none>";
+
+#[derive(Clone, Debug, clap::Args)]
+#[clap(name = "stats", about = "Summarize the ark managed folder")]
+pub struct Stats {
+    #[clap(value_parser, help = "Path to the root directory")]
+    root_dir: Option<PathBuf>,
+    #[clap(
+        long,
+        value_enum,
+        help = "Output format, raw unless configured otherwise"
+    )]
+    format: Option<Format>,
+    #[clap(
+        short = 'n',
+        long,
+        default_value = "10",
+        help = "Number of extensions and tags to show in the table"
+    )]
+    limit: usize,
+}
+
+impl Stats {
+    pub fn run(&self) -> Result<(), AppError> {
+        let root = provide_root(&self.root_dir)?;
+
+        // Stored index is loaded as is, so it can be
+        // compared with the current state of the folder
+        let mut index = load_or_build_index::<_, ResourceId>(&root, false)?;
+        let stored_at = fs::metadata(root.join(ARK_FOLDER).join(INDEX_PATH))
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .map(|time| DateTime::<Utc>::from(time).to_rfc3339());
+        let update = index.update_all()?;
+        let pending = update.added().len() + update.removed().len();
+
+        let mut total_size = 0;
+        let mut extensions: BTreeMap<String, usize> = BTreeMap::new();
+        let mut kinds: BTreeMap<&str, usize> = BTreeMap::new();
+        let resources = index.resources();
+        for resource in &resources {
+            total_size += index
+                .get_size_by_path(resource.path())
+                .unwrap_or(0);
+            let extension = resource
+                .path()
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+                .unwrap_or_else(|| NO_EXTENSION.to_owned());
+            *kinds.entry(kind(&extension)).or_default() += 1;
+            *extensions.entry(extension).or_default() += 1;
+        }
+
+        let mut tags: BTreeMap<String, usize> = BTreeMap::new();
+        let mut values: BTreeMap<ResourceId, Vec<String>> = BTreeMap::new();
+        for (id, value) in tag_storage(&root)?.entries()? {
+            values.entry(id).or_default().push(value);
+        }
+        for resource_values in values.values() {
+            for tag in parse_tags(resource_values) {
+                *tags.entry(tag).or_default() += 1;
+            }
+        }
+
+        // Every copy except one is wasted space
+        let collisions = index.collisions();
+        let duplicates: usize = collisions
+            .values()
+            .map(|paths| paths.len() - 1)
+            .sum();
+        let wasted: u64 = collisions
+            .values()
+            .flat_map(|paths| paths.iter().skip(1))
+            .filter_map(|path| index.get_size_by_path(path))
+            .sum();
+
+        // Reported only if loaded, stats don't depend on it
+        let device = app_id::identity().ok();
+
+        if output_format(self.format) == Format::KeyValue {
+            return print_json(&json!({
+                "root": root,
+                "device": device,
+                "resources": resources.len(),
+                "size": total_size,
+                "extensions": extensions,
+                "kinds": kinds,
+                "tags": tags,
+                "duplicates": {
+                    "groups": collisions.len(),
+                    "files": duplicates,
+                    "wasted": wasted,
+                },
+                "index": {
+                    "stored_at": stored_at,
+                    "pending_changes": pending,
+                },
+            }));
+        }
+
+        println!("Root:\t\t{}", root.display());
+        if let Some(device) = &device {
+            println!("Device:\t\t{} ({})", device.name, device.id);
+        }
+        println!("Resources:\t{}", resources.len());
+        println!("Total size:\t{}", human_size(total_size));
+        println!(
+            "Duplicates:\t{} files in {} groups, {} wasted",
+            duplicates,
+            collisions.len(),
+            human_size(wasted)
+        );
+        match &stored_at {
+            Some(time) => println!(
+                "Index:\t\tstored at {}, {} changes since",
+                time, pending
+            ),
+            None => println!("Index:\t\tnot stored yet"),
+        }
+
+        print_table("Kinds", kinds.into_iter().collect(), self.limit);
+        print_table("Extensions", extensions.into_iter().collect(), self.limit);
+        print_table("Tags", tags.into_iter().collect(), self.limit);
+
+        Ok(())
+    }
+}
+
+/// Most frequent entries first
+fn print_table<K: std::fmt::Display>(
+    title: &str,
+    mut rows: Vec<(K, usize)>,
+    limit: usize,
+) {
+    println!("\n{}:", title);
+    if rows.is_empty() {
+        println!("\tnone");
+        return;
+    }
+    rows.sort_by(|a, b| b.1.cmp(&a.1));
+    let width = rows
+        .iter()
+        .map(|(key, _)| key.to_string().len())
+        .max()
+        .unwrap_or(0);
+    for (key, count) in rows.iter().take(limit) {
+        println!("\t{:width$} {}", key.to_string(), count, width = width);
+    }
+    if rows.len() > limit {
+        println!("\t... and {} more", rows.len() - limit);
+    }
+}
+
+fn kind(extension: &str) -> &'static str {
+    match extension {
+        "png" | "jpg" | "jpeg" | "gif" | "webp" | "bmp" | "tif" | "tiff"
+        | "ico" | "svg" | "heic" => "image",
+        "mp4" | "mkv" | "avi" | "mov" | "webm" => "video",
+        "mp3" | "flac" | "ogg" | "wav" | "m4a" => "audio",
+        "pdf" | "txt" | "md" | "doc" | "docx" | "odt" | "epub" => "document",
+        "zip" | "tar" | "gz" | "xz" | "7z" | "rar" => "archive",
+        "link" => "link",
+        _ => "other",
+    }
+}
+
+fn human_size(bytes: u64) -> String {
+    let units = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < units.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, units[0])
+    } else {
+        format!("{:.1} {}", size, units[unit])
+    }
+}
@@ -1,8 +1,9 @@
 use std::path::PathBuf;
 
 use crate::{
+    config::output_format,
     models::storage::{Storage, StorageType},
-    translate_storage, AppError,
+    print_json, translate_storage, AppError, Format,
 };
 
 #[derive(Clone, Debug, clap::Args)]
@@ -16,6 +17,12 @@ pub struct List {
     versions: bool,
     #[clap(short, long, value_enum, help = "Storage kind of the resource")]
     kind: Option<StorageType>,
+    #[clap(
+        long,
+        value_enum,
+        help = "Output format, raw unless configured otherwise"
+    )]
+    format: Option<Format>,
 }
 
 impl List {
@@ -42,6 +49,10 @@ impl List {
 
         storage.load()?;
 
+        if output_format(self.format) == Format::KeyValue {
+            return print_json(&storage.list_json(versions)?);
+        }
+
         let output = storage.list(versions)?;
 
         println!("{}", output);
@@ -0,0 +1,35 @@
+use std::path::PathBuf;
+
+use crate::{
+    commands::tag::utils::{parse_tags, tag_storage, validate_tags},
+    models::ResourceSelector,
+    provide_root, resolve_resource, AppError,
+};
+
+#[derive(Clone, Debug, clap::Args)]
+#[clap(name = "add", about = "Add tags to a resource")]
+pub struct Add {
+    #[clap(flatten)]
+    selector: ResourceSelector,
+    #[clap(required = true, help = "Tags to add")]
+    tags: Vec<String>,
+    #[clap(long, help = "Root directory of the ark managed folder")]
+    root_dir: Option<PathBuf>,
+}
+
+impl Add {
+    pub fn run(&self) -> Result<(), AppError> {
+        let root = provide_root(&self.root_dir)?;
+        let id = resolve_resource(&root, &self.selector)?;
+        let new_tags = validate_tags(&self.tags)?;
+
+        let mut storage = tag_storage(&root)?;
+        storage.update(&id, |values| {
+            let mut tags = parse_tags(&values);
+            tags.extend(new_tags.iter().cloned());
+            Some(tags.into_iter().collect::<Vec<_>>().join(","))
+        })?;
+
+        Ok(())
+    }
+}
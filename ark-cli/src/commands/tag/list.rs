@@ -0,0 +1,69 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{
+    commands::tag::utils::{parse_tags, tag_storage},
+    models::ResourceSelector,
+    provide_root, resolve_resource, AppError,
+};
+
+#[derive(Clone, Debug, clap::Args)]
+#[clap(
+    name = "list",
+    about = "List tags of a resource, or all tags with their usage counts"
+)]
+pub struct List {
+    #[clap(help = "Path or ID of the resource")]
+    resource: Option<String>,
+    #[clap(
+        long,
+        action,
+        conflicts_with = "by_id",
+        help = "Resource is a path"
+    )]
+    by_path: bool,
+    #[clap(long, action, help = "Resource is an ID")]
+    by_id: bool,
+    #[clap(long, help = "Root directory of the ark managed folder")]
+    root_dir: Option<PathBuf>,
+}
+
+impl List {
+    pub fn run(&self) -> Result<(), AppError> {
+        let root = provide_root(&self.root_dir)?;
+        let storage = tag_storage(&root)?;
+
+        if let Some(resource) = &self.resource {
+            let selector = ResourceSelector {
+                resource: resource.to_owned(),
+                by_path: self.by_path,
+                by_id: self.by_id,
+            };
+            let id = resolve_resource(&root, &selector)?;
+            for tag in parse_tags(&storage.values(&id)?) {
+                println!("{}", tag);
+            }
+            return Ok(());
+        }
+
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+        let mut resources: BTreeMap<_, Vec<String>> = BTreeMap::new();
+        for (id, value) in storage.entries()? {
+            resources.entry(id).or_default().push(value);
+        }
+        for values in resources.values() {
+            for tag in parse_tags(values) {
+                *counts.entry(tag).or_default() += 1;
+            }
+        }
+
+        let longest = counts
+            .keys()
+            .map(|tag| tag.len())
+            .max()
+            .unwrap_or(0);
+        for (tag, count) in counts {
+            println!("{:width$} {}", tag, count, width = longest);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,41 @@
+use std::path::PathBuf;
+
+use crate::{
+    commands::tag::utils::{parse_tags, tag_storage, validate_tags},
+    models::ResourceSelector,
+    provide_root, resolve_resource, AppError,
+};
+
+#[derive(Clone, Debug, clap::Args)]
+#[clap(name = "remove", about = "Remove tags from a resource")]
+pub struct Remove {
+    #[clap(flatten)]
+    selector: ResourceSelector,
+    #[clap(help = "Tags to remove, all tags are removed if none given")]
+    tags: Vec<String>,
+    #[clap(long, help = "Root directory of the ark managed folder")]
+    root_dir: Option<PathBuf>,
+}
+
+impl Remove {
+    pub fn run(&self) -> Result<(), AppError> {
+        let root = provide_root(&self.root_dir)?;
+        let id = resolve_resource(&root, &self.selector)?;
+        let removed = validate_tags(&self.tags)?;
+
+        let mut storage = tag_storage(&root)?;
+        storage.update(&id, |values| {
+            let mut tags = parse_tags(&values);
+            if removed.is_empty() {
+                tags.clear();
+            } else {
+                tags.retain(|tag| !removed.contains(tag));
+            }
+            // Resources without tags are dropped from the storage
+            (!tags.is_empty())
+                .then(|| tags.into_iter().collect::<Vec<_>>().join(","))
+        })?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,36 @@
+use std::{collections::BTreeSet, path::PathBuf};
+
+use crate::{
+    models::storage::{Storage, StorageType},
+    translate_storage, AppError,
+};
+
+pub fn tag_storage(root: &PathBuf) -> Result<Storage, AppError> {
+    let (path, _) = translate_storage(&Some(root.to_owned()), "tags")
+        .ok_or(AppError::StorageNotFound("tags".to_owned()))?;
+    Storage::new(path, StorageType::File)
+}
+
+/// Tags of all lines of a resource, lines are comma-separated lists
+pub fn parse_tags(values: &[String]) -> BTreeSet<String> {
+    values
+        .iter()
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim().to_owned())
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+/// Tags are stored as comma-separated lines of `id:tags`
+pub fn validate_tags(tags: &[String]) -> Result<Vec<String>, AppError> {
+    tags.iter()
+        .map(|tag| {
+            let tag = tag.trim();
+            if tag.is_empty() || tag.contains([',', ':', '\n', '\r']) {
+                Err(AppError::InvalidTag(tag.to_owned()))
+            } else {
+                Ok(tag.to_owned())
+            }
+        })
+        .collect()
+}
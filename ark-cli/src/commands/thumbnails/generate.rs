@@ -0,0 +1,157 @@
+use std::{
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+use data_preview::{
+    invalidate_thumbnails, provide_thumbnail, ResourceKind, ThumbnailSize,
+};
+
+use crate::{provide_root, util::provide_index, AppError, ResourceId};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Size {
+    /// 128x128 pixels
+    Small,
+    /// 256x256 pixels
+    Medium,
+    /// 512x512 pixels
+    Large,
+}
+
+impl From<Size> for ThumbnailSize {
+    fn from(size: Size) -> Self {
+        match size {
+            Size::Small => ThumbnailSize::Small,
+            Size::Medium => ThumbnailSize::Medium,
+            Size::Large => ThumbnailSize::Large,
+        }
+    }
+}
+
+#[derive(Clone, Debug, clap::Args)]
+#[clap(
+    name = "generate",
+    about = "Generate thumbnails of all images and PDFs in the index"
+)]
+pub struct Generate {
+    #[clap(value_parser, help = "Path to the root directory")]
+    root_dir: Option<PathBuf>,
+    #[clap(
+        long,
+        action,
+        help = "Only generate thumbnails which are not cached yet"
+    )]
+    missing_only: bool,
+    #[clap(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "medium",
+        help = "Sizes of thumbnails to generate"
+    )]
+    sizes: Vec<Size>,
+    #[clap(
+        short,
+        long,
+        help = "Number of thumbnails generated in parallel, \
+                all available cores by default"
+    )]
+    jobs: Option<NonZeroUsize>,
+}
+
+impl Generate {
+    pub fn run(&self) -> Result<(), AppError> {
+        let root = provide_root(&self.root_dir)?;
+
+        let mut resources: Vec<(ResourceId, PathBuf, ResourceKind)> =
+            provide_index(&root)
+                .resources()
+                .iter()
+                .filter_map(|resource| {
+                    let kind = ResourceKind::from_path(resource.path())?;
+                    Some((
+                        resource.id().clone(),
+                        resource.path().to_path_buf(),
+                        kind,
+                    ))
+                })
+                .collect();
+        // Duplicates share the same thumbnails
+        resources.sort_by(|a, b| a.0.cmp(&b.0));
+        resources.dedup_by(|a, b| a.0 == b.0);
+
+        let total = resources.len();
+        if total == 0 {
+            println!("No images or PDFs found in {}", root.display());
+            return Ok(());
+        }
+
+        let jobs = self
+            .jobs
+            .or_else(|| thread::available_parallelism().ok())
+            .map_or(1, NonZeroUsize::get)
+            .min(total);
+        let next = AtomicUsize::new(0);
+        let done = AtomicUsize::new(0);
+        let failed = Mutex::new(vec![]);
+
+        thread::scope(|scope| {
+            for _ in 0..jobs {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::SeqCst);
+                    let Some((id, path, kind)) = resources.get(index) else {
+                        break;
+                    };
+
+                    let result = self.generate(&root, id, path, *kind);
+                    let count = done.fetch_add(1, Ordering::SeqCst) + 1;
+                    match result {
+                        Ok(()) => {
+                            println!("[{}/{}] {}", count, total, path.display())
+                        }
+                        Err(e) => {
+                            println!(
+                                "[{}/{}] {} failed: {}",
+                                count,
+                                total,
+                                path.display(),
+                                e
+                            );
+                            failed.lock().unwrap().push(path.clone());
+                        }
+                    }
+                });
+            }
+        });
+
+        let failed = failed.into_inner().unwrap();
+        println!(
+            "Generated thumbnails of {} resources, {} failed",
+            total - failed.len(),
+            failed.len()
+        );
+        Ok(())
+    }
+
+    fn generate(
+        &self,
+        root: &Path,
+        id: &ResourceId,
+        path: &Path,
+        kind: ResourceKind,
+    ) -> Result<(), AppError> {
+        if !self.missing_only {
+            invalidate_thumbnails(root, id)?;
+        }
+        for size in &self.sizes {
+            provide_thumbnail(root, id, root.join(path), kind, (*size).into())?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,9 @@
+use clap::Subcommand;
+
+mod generate;
+
+/// Available commands for the `thumbnails` subcommand
+#[derive(Subcommand, Debug)]
+pub enum Thumbnails {
+    Generate(generate::Generate),
+}
@@ -0,0 +1,333 @@
+use std::{fs, path::PathBuf, time::SystemTime};
+
+use data_preview::{provide_thumbnail, ResourceKind, ThumbnailSize};
+use ratatui::{
+    crossterm::event::{KeyCode, KeyEvent},
+    widgets::ListState,
+};
+
+use crate::{
+    commands::{
+        score::utils::{parse_score, score_storage, MAX_SCORE, MIN_SCORE},
+        tag::utils::{parse_tags, tag_storage, validate_tags},
+    },
+    read_storage_value, AppError, ResourceId,
+};
+
+pub struct Entry {
+    pub id: ResourceId,
+    pub path: PathBuf,
+}
+
+/// Details of the selected resource, loaded when it gets selected
+#[derive(Default)]
+pub struct Details {
+    pub size: Option<u64>,
+    pub modified: Option<SystemTime>,
+    pub tags: Vec<String>,
+    pub score: Option<i32>,
+    pub metadata: Option<String>,
+    pub properties: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Browse,
+    Filter,
+    AddTag,
+    RemoveTag,
+}
+
+pub struct Browser {
+    pub root: PathBuf,
+    pub entries: Vec<Entry>,
+    /// Indices of entries matching the filter
+    pub visible: Vec<usize>,
+    pub list: ListState,
+    pub filter: String,
+    pub mode: Mode,
+    pub input: String,
+    pub details: Details,
+    pub status: Option<String>,
+    finished: bool,
+}
+
+impl Browser {
+    pub fn new(root: PathBuf) -> Result<Self, AppError> {
+        let mut browser = Self {
+            root,
+            entries: vec![],
+            visible: vec![],
+            list: ListState::default(),
+            filter: String::new(),
+            mode: Mode::Browse,
+            input: String::new(),
+            details: Details::default(),
+            status: None,
+            finished: false,
+        };
+        browser.reload(false)?;
+        Ok(browser)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn selected(&self) -> Option<&Entry> {
+        let index = *self.visible.get(self.list.selected()?)?;
+        self.entries.get(index)
+    }
+
+    /// Read resources from the index, updating it first if requested
+    fn reload(&mut self, update: bool) -> Result<(), AppError> {
+        let index = crate::provide_index(&self.root).map_err(|_| {
+            AppError::IndexError("Could not provide index".to_owned())
+        })?;
+        if update {
+            let mut index = index.write().map_err(|_| {
+                AppError::IndexError("Could not write index".to_owned())
+            })?;
+            index.update_all()?;
+            index.store()?;
+        }
+
+        let index = index.read().map_err(|_| {
+            AppError::IndexError("Could not read index".to_owned())
+        })?;
+        self.entries = index
+            .resources()
+            .iter()
+            .map(|resource| Entry {
+                id: resource.id().clone(),
+                path: resource.path().to_path_buf(),
+            })
+            .collect();
+        self.entries.sort_by(|a, b| a.path.cmp(&b.path));
+        self.apply_filter();
+        Ok(())
+    }
+
+    fn apply_filter(&mut self) {
+        let filter = self.filter.to_lowercase();
+        self.visible = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| {
+                filter.is_empty()
+                    || entry
+                        .path
+                        .display()
+                        .to_string()
+                        .to_lowercase()
+                        .contains(&filter)
+                    || entry.id.to_string().contains(&filter)
+            })
+            .map(|(index, _)| index)
+            .collect();
+        let selected = match self.visible.len() {
+            0 => None,
+            len => Some(self.list.selected().unwrap_or(0).min(len - 1)),
+        };
+        self.list.select(selected);
+        self.load_details();
+    }
+
+    fn load_details(&mut self) {
+        let Some(entry) = self.selected() else {
+            self.details = Details::default();
+            return;
+        };
+        let id = entry.id.clone();
+        let metadata = fs::metadata(self.root.join(&entry.path)).ok();
+
+        let tags = tag_storage(&self.root)
+            .and_then(|storage| storage.values(&id))
+            .map(|values| parse_tags(&values).into_iter().collect())
+            .unwrap_or_default();
+        let score = score_storage(&self.root)
+            .and_then(|storage| storage.values(&id))
+            .ok()
+            .and_then(|values| parse_score(&values));
+        let read = |storage: &str| {
+            read_storage_value(&self.root, storage, &id.to_string(), &None).ok()
+        };
+
+        self.details = Details {
+            size: metadata.as_ref().map(|metadata| metadata.len()),
+            modified: metadata.and_then(|metadata| metadata.modified().ok()),
+            tags,
+            score,
+            metadata: read("metadata"),
+            properties: read("properties"),
+        };
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        match self.mode {
+            Mode::Browse => self.handle_browse_key(key),
+            _ => self.handle_input_key(key),
+        }
+    }
+
+    fn handle_browse_key(&mut self, key: KeyEvent) {
+        self.status = None;
+        let result = match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => {
+                self.finished = true;
+                Ok(())
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.move_selection(-1);
+                Ok(())
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.move_selection(1);
+                Ok(())
+            }
+            KeyCode::PageUp => {
+                self.move_selection(-10);
+                Ok(())
+            }
+            KeyCode::PageDown => {
+                self.move_selection(10);
+                Ok(())
+            }
+            KeyCode::Char('/') => {
+                self.start_input(Mode::Filter);
+                self.input = self.filter.clone();
+                Ok(())
+            }
+            KeyCode::Char('t') => {
+                self.start_input(Mode::AddTag);
+                Ok(())
+            }
+            KeyCode::Char('d') => {
+                self.start_input(Mode::RemoveTag);
+                Ok(())
+            }
+            KeyCode::Char('+') => self.change_score(1),
+            KeyCode::Char('-') => self.change_score(-1),
+            KeyCode::Char('p') => self.generate_thumbnail(),
+            KeyCode::Char('r') => self.reload(true).map(|_| {
+                self.status = Some("Index updated".to_owned());
+            }),
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            self.status = Some(format!("Error: {}", e));
+        }
+    }
+
+    fn handle_input_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc => self.mode = Mode::Browse,
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Char(c) => self.input.push(c),
+            KeyCode::Enter => {
+                let input = std::mem::take(&mut self.input);
+                let mode = std::mem::replace(&mut self.mode, Mode::Browse);
+                let result = match mode {
+                    Mode::Filter => {
+                        self.filter = input;
+                        self.apply_filter();
+                        Ok(())
+                    }
+                    Mode::AddTag => self.update_tags(&input, true),
+                    Mode::RemoveTag => self.update_tags(&input, false),
+                    Mode::Browse => Ok(()),
+                };
+                if let Err(e) = result {
+                    self.status = Some(format!("Error: {}", e));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn start_input(&mut self, mode: Mode) {
+        self.mode = mode;
+        self.input.clear();
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        if self.visible.is_empty() {
+            return;
+        }
+        let current = self.list.selected().unwrap_or(0) as isize;
+        let last = self.visible.len() as isize - 1;
+        self.list
+            .select(Some((current + delta).clamp(0, last) as usize));
+        self.load_details();
+    }
+
+    fn update_tags(&mut self, input: &str, add: bool) -> Result<(), AppError> {
+        let Some(id) = self.selected().map(|entry| entry.id.clone()) else {
+            return Ok(());
+        };
+        let tags: Vec<String> = input
+            .split(',')
+            .map(|tag| tag.to_owned())
+            .collect();
+        let tags = validate_tags(&tags)?;
+
+        tag_storage(&self.root)?.update(&id, |values| {
+            let mut current = parse_tags(&values);
+            for tag in &tags {
+                if add {
+                    current.insert(tag.clone());
+                } else {
+                    current.remove(tag);
+                }
+            }
+            if current.is_empty() {
+                return None;
+            }
+            Some(current.into_iter().collect::<Vec<_>>().join(","))
+        })?;
+        self.load_details();
+        Ok(())
+    }
+
+    fn change_score(&mut self, delta: i32) -> Result<(), AppError> {
+        let Some(id) = self.selected().map(|entry| entry.id.clone()) else {
+            return Ok(());
+        };
+        score_storage(&self.root)?.update(&id, |values| {
+            let score = parse_score(&values).unwrap_or(0) + delta;
+            let score = score.clamp(MIN_SCORE as i32, MAX_SCORE as i32);
+            Some(score.to_string())
+        })?;
+        self.load_details();
+        Ok(())
+    }
+
+    fn generate_thumbnail(&mut self) -> Result<(), AppError> {
+        let Some(entry) = self.selected() else {
+            return Ok(());
+        };
+        let (id, path) = (entry.id.clone(), self.root.join(&entry.path));
+        let Some(kind) = ResourceKind::from_path(&path) else {
+            self.status =
+                Some("Only images and PDFs have thumbnails".to_owned());
+            return Ok(());
+        };
+        let thumbnail = provide_thumbnail(
+            &self.root,
+            &id,
+            path,
+            kind,
+            ThumbnailSize::Medium,
+        )?;
+        self.status = Some(format!("Thumbnail: {}", thumbnail.display()));
+        Ok(())
+    }
+}
+
+pub fn format_time(time: SystemTime) -> String {
+    let datetime: chrono::DateTime<chrono::Local> = time.into();
+    datetime.format("%Y-%m-%d %H:%M:%S").to_string()
+}
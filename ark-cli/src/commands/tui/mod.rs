@@ -0,0 +1,71 @@
+use std::{io, path::PathBuf, time::Duration};
+
+use ratatui::{
+    backend::CrosstermBackend,
+    crossterm::{
+        event::{self, Event, KeyEventKind},
+        execute,
+        terminal::{
+            disable_raw_mode, enable_raw_mode, EnterAlternateScreen,
+            LeaveAlternateScreen,
+        },
+    },
+    Terminal,
+};
+
+use crate::{provide_root, AppError};
+
+mod browser;
+mod ui;
+
+use browser::Browser;
+
+#[derive(Clone, Debug, clap::Args)]
+#[clap(
+    name = "tui",
+    about = "Browse and manage resources of the ark managed folder interactively"
+)]
+pub struct Tui {
+    #[clap(value_parser, help = "Path to the root directory")]
+    root_dir: Option<PathBuf>,
+}
+
+impl Tui {
+    pub fn run(&self) -> Result<(), AppError> {
+        let root = provide_root(&self.root_dir)?;
+        let mut browser = Browser::new(root)?;
+
+        enable_raw_mode()?;
+        let mut stdout = io::stdout();
+        execute!(stdout, EnterAlternateScreen)?;
+        let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+
+        let result = event_loop(&mut terminal, &mut browser);
+
+        // Terminal is restored even if the loop failed
+        disable_raw_mode()?;
+        execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+        terminal.show_cursor()?;
+
+        result
+    }
+}
+
+fn event_loop(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    browser: &mut Browser,
+) -> Result<(), AppError> {
+    while !browser.is_finished() {
+        terminal.draw(|f| ui::draw(f, browser))?;
+
+        if event::poll(Duration::from_millis(100))? {
+            if let Event::Key(key) = event::read()? {
+                // Releases are reported on some platforms as well
+                if key.kind == KeyEventKind::Press {
+                    browser.handle_key(key);
+                }
+            }
+        }
+    }
+    Ok(())
+}
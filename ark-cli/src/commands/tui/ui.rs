@@ -0,0 +1,208 @@
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    symbols::border,
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
+    Frame,
+};
+
+use super::browser::{format_time, Browser, Mode};
+
+pub fn draw(f: &mut Frame, browser: &mut Browser) {
+    let blocks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(5),
+            Constraint::Length(3),
+        ])
+        .split(f.area());
+    let body = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(blocks[1]);
+
+    draw_header(f, blocks[0], browser);
+    draw_resources(f, body[0], browser);
+    draw_details(f, body[1], browser);
+    draw_footer(f, blocks[2], browser);
+}
+
+fn block(title: &str) -> Block<'_> {
+    Block::default()
+        .borders(Borders::ALL)
+        .border_set(border::ROUNDED)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(format!(" {} ", title))
+        .title_style(Style::default().fg(Color::White).bold())
+}
+
+fn draw_header(f: &mut Frame, area: Rect, browser: &Browser) {
+    let mut spans = vec![
+        Span::styled("📂 ", Style::default().fg(Color::Yellow)),
+        Span::styled(
+            browser.root.display().to_string(),
+            Style::default().fg(Color::White).bold(),
+        ),
+        Span::raw(format!(
+            "  {} of {} resources",
+            browser.visible.len(),
+            browser.entries.len()
+        )),
+    ];
+    if !browser.filter.is_empty() {
+        spans.push(Span::styled(
+            format!("  filter: {}", browser.filter),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+    f.render_widget(
+        Paragraph::new(Line::from(spans)).block(block("ARK")),
+        area,
+    );
+}
+
+fn draw_resources(f: &mut Frame, area: Rect, browser: &mut Browser) {
+    let items: Vec<ListItem> = browser
+        .visible
+        .iter()
+        .map(|&index| {
+            let entry = &browser.entries[index];
+            ListItem::new(Line::from(vec![
+                Span::raw(entry.path.display().to_string()),
+                Span::styled(
+                    format!("  {}", entry.id),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(block("Resources"))
+        .highlight_style(
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol("▶ ");
+    f.render_stateful_widget(list, area, &mut browser.list);
+}
+
+fn draw_details(f: &mut Frame, area: Rect, browser: &Browser) {
+    let Some(entry) = browser.selected() else {
+        f.render_widget(
+            Paragraph::new("No resources").block(block("Details")),
+            area,
+        );
+        return;
+    };
+    let details = &browser.details;
+    let field = |name: &str, value: String| {
+        Line::from(vec![
+            Span::styled(
+                format!("{:<10}", name),
+                Style::default().fg(Color::Cyan),
+            ),
+            Span::raw(value),
+        ])
+    };
+
+    let mut lines = vec![
+        field("Id", entry.id.to_string()),
+        field("Path", entry.path.display().to_string()),
+        field(
+            "Size",
+            details
+                .size
+                .map_or("-".to_owned(), |size| format!("{} bytes", size)),
+        ),
+        field(
+            "Modified",
+            details
+                .modified
+                .map_or("-".to_owned(), format_time),
+        ),
+        field(
+            "Tags",
+            if details.tags.is_empty() {
+                "-".to_owned()
+            } else {
+                details.tags.join(", ")
+            },
+        ),
+        field(
+            "Score",
+            details
+                .score
+                .map_or("-".to_owned(), |score| score.to_string()),
+        ),
+    ];
+    for (title, content) in [
+        ("Metadata", &details.metadata),
+        ("Properties", &details.properties),
+    ] {
+        if let Some(content) = content {
+            lines.push(Line::from(""));
+            lines.push(Line::styled(
+                title,
+                Style::default().fg(Color::Cyan).bold(),
+            ));
+            lines.extend(
+                content
+                    .lines()
+                    .map(|line| Line::raw(line.to_owned())),
+            );
+        }
+    }
+
+    f.render_widget(
+        Paragraph::new(lines)
+            .block(block("Details"))
+            .wrap(Wrap { trim: false }),
+        area,
+    );
+}
+
+fn draw_footer(f: &mut Frame, area: Rect, browser: &Browser) {
+    let prompt = match browser.mode {
+        Mode::Browse => None,
+        Mode::Filter => Some("Filter"),
+        Mode::AddTag => Some("Add tags"),
+        Mode::RemoveTag => Some("Remove tags"),
+    };
+
+    let line = if let Some(prompt) = prompt {
+        Line::from(vec![
+            Span::styled(
+                format!("{}: ", prompt),
+                Style::default().fg(Color::Yellow),
+            ),
+            Span::raw(browser.input.clone()),
+            Span::styled("█", Style::default().fg(Color::White)),
+            Span::styled(
+                "  Enter confirm • Esc cancel",
+                Style::default().fg(Color::DarkGray),
+            ),
+        ])
+    } else if let Some(status) = &browser.status {
+        Line::styled(status.clone(), Style::default().fg(Color::Yellow))
+    } else {
+        Line::from(vec![
+            Span::styled("💡 ", Style::default().fg(Color::Yellow)),
+            Span::raw(
+                "↑↓ move • / filter • t tag • d untag • +/- score • \
+                 p thumbnail • r refresh • q quit",
+            ),
+        ])
+    };
+
+    f.render_widget(
+        Paragraph::new(line)
+            .block(block("Controls"))
+            .alignment(Alignment::Center),
+        area,
+    );
+}
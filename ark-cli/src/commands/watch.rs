@@ -1,10 +1,37 @@
-use std::path::PathBuf;
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
 
 use futures::{pin_mut, StreamExt};
 
-use fs_index::{watch_index, WatchEvent};
+use fs_index::{load_or_build_index, watch_index, IndexUpdate, WatchEvent};
 
-use crate::{AppError, DateTime, ResourceId, Utc};
+use crate::{util::shell, AppError, DateTime, ResourceId, Utc};
+
+/// Kind of change of a resource, passed to hooks as `{event}`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum ChangeKind {
+    Added,
+    Modified,
+    Removed,
+}
+
+impl ChangeKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ChangeKind::Added => "added",
+            ChangeKind::Modified => "modified",
+            ChangeKind::Removed => "removed",
+        }
+    }
+}
+
+struct Change {
+    kind: ChangeKind,
+    path: PathBuf,
+    id: ResourceId,
+}
 
 #[derive(Clone, Debug, clap::Args)]
 #[clap(
@@ -18,14 +45,53 @@ pub struct Watch {
         value_parser
     )]
     path: PathBuf,
+    #[clap(
+        long,
+        help = "Command to run for every changed resource, \
+                `{path}`, `{id}` and `{event}` are substituted"
+    )]
+    exec: Option<String>,
+    #[clap(
+        long,
+        help = "Command to run once per index update, `{paths}` and `{ids}` \
+                are substituted, paths are appended if neither is used"
+    )]
+    exec_batch: Option<String>,
+    #[clap(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        help = "Changes triggering the commands, all of them by default"
+    )]
+    on: Vec<ChangeKind>,
 }
 
 impl Watch {
     pub async fn run(&self) -> Result<(), AppError> {
+        // Removed resources are reported by id only,
+        // so their paths are remembered here
+        let mut known: HashMap<ResourceId, HashSet<PathBuf>> = HashMap::new();
+        if self.exec.is_some() || self.exec_batch.is_some() {
+            let index =
+                load_or_build_index::<_, ResourceId>(&self.path, false)?;
+            for resource in index.resources() {
+                known
+                    .entry(resource.id().clone())
+                    .or_default()
+                    .insert(resource.path().to_path_buf());
+            }
+        }
+
         let stream = watch_index::<_, ResourceId>(&self.path);
         pin_mut!(stream);
 
         while let Some(value) = stream.next().await {
+            let update = match &value {
+                WatchEvent::UpdatedOne(update) => update,
+                WatchEvent::UpdatedAll(update) => update,
+            };
+            let changes = classify_changes(update, &mut known);
+
             match value {
                 WatchEvent::UpdatedOne(update) => {
                     println!("Index updated with a single file change");
@@ -67,8 +133,174 @@ impl Watch {
                     }
                 }
             }
+
+            if self.exec.is_some() || self.exec_batch.is_some() {
+                self.run_hooks(&changes).await;
+            }
         }
 
         Ok(())
     }
+
+    async fn run_hooks(&self, changes: &[Change]) {
+        let changes: Vec<&Change> = changes
+            .iter()
+            .filter(|change| {
+                self.on.is_empty() || self.on.contains(&change.kind)
+            })
+            .collect();
+        if changes.is_empty() {
+            return;
+        }
+
+        if let Some(template) = &self.exec {
+            for change in &changes {
+                let path = self.path.join(&change.path);
+                let path = quote(&path.display().to_string());
+                let id = quote(&change.id.to_string());
+                let command = substitute(
+                    template,
+                    &[
+                        ("path", path.as_str()),
+                        ("id", id.as_str()),
+                        ("event", change.kind.as_str()),
+                    ],
+                );
+                execute(&command).await;
+            }
+        }
+
+        if let Some(template) = &self.exec_batch {
+            let paths: Vec<String> = changes
+                .iter()
+                .map(|change| {
+                    quote(&self.path.join(&change.path).display().to_string())
+                })
+                .collect();
+            let ids: Vec<String> = changes
+                .iter()
+                .map(|change| quote(&change.id.to_string()))
+                .collect();
+
+            let command =
+                if template.contains("{paths}") || template.contains("{ids}") {
+                    let (paths, ids) = (paths.join(" "), ids.join(" "));
+                    substitute(
+                        template,
+                        &[("paths", paths.as_str()), ("ids", ids.as_str())],
+                    )
+                } else {
+                    format!("{} {}", template, paths.join(" "))
+                };
+            execute(&command).await;
+        }
+    }
+}
+
+/// Classify the update, a modified file is reported by the index
+/// as its old id being removed and its new id being added
+fn classify_changes(
+    update: &IndexUpdate<ResourceId>,
+    known: &mut HashMap<ResourceId, HashSet<PathBuf>>,
+) -> Vec<Change> {
+    let mut removed: HashMap<PathBuf, ResourceId> = HashMap::new();
+    for id in update.removed() {
+        for path in known.remove(id).unwrap_or_default() {
+            removed.insert(path, id.clone());
+        }
+    }
+
+    let mut changes = vec![];
+    for (id, paths) in update.added() {
+        for path in paths {
+            let path = path.item().to_path_buf();
+            let kind = match removed.remove(&path) {
+                Some(_) => ChangeKind::Modified,
+                None => ChangeKind::Added,
+            };
+            known
+                .entry(id.clone())
+                .or_default()
+                .insert(path.clone());
+            changes.push(Change {
+                kind,
+                path,
+                id: id.clone(),
+            });
+        }
+    }
+    for (path, id) in removed {
+        changes.push(Change {
+            kind: ChangeKind::Removed,
+            path,
+            id,
+        });
+    }
+    changes
+}
+
+/// Replace the `{name}` placeholders of the template with their values in
+/// one pass, so placeholders inside the values, e.g. in file names, are kept
+fn substitute(template: &str, values: &[(&str, &str)]) -> String {
+    let mut command = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        command.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+        let value = values.iter().find_map(|&(name, value)| {
+            rest.strip_prefix(name)?
+                .strip_prefix('}')
+                .map(|after| (value, after))
+        });
+        match value {
+            Some((value, after)) => {
+                command.push_str(value);
+                rest = after;
+            }
+            None => command.push('{'),
+        }
+    }
+    command.push_str(rest);
+    command
+}
+
+/// Run the command through the shell, failures don't stop the watcher
+async fn execute(command: &str) {
+    let mut process = tokio::process::Command::from(shell(command));
+    match process.status().await {
+        Ok(status) if !status.success() => {
+            eprintln!("Command `{}` failed with {}", command, status)
+        }
+        Ok(_) => {}
+        Err(e) => eprintln!("Failed to run `{}`: {}", command, e),
+    }
+}
+
+/// Quote the value so it is passed to the shell as a single argument
+#[cfg(unix)]
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+#[cfg(windows)]
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn substitutes_placeholders_once() {
+        let command = substitute(
+            "echo {event} {path} {id} {unknown}",
+            &[
+                ("path", "'a{id}{event}.txt'"),
+                ("id", "42"),
+                ("event", "added"),
+            ],
+        );
+        assert_eq!(command, "echo added 'a{id}{event}.txt' 42 {unknown}");
+    }
 }
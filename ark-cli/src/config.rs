@@ -0,0 +1,123 @@
+use std::{fs, path::PathBuf, sync::OnceLock};
+
+use home::home_dir;
+use serde::Deserialize;
+
+use crate::{error::AppError, models::Format, ARK_CONFIG, ROOTS_CFG_FILENAME};
+
+const CONFIG_FILENAME: &str = "config.toml";
+
+/// Hash function of resource ids this build of ark-cli uses
+pub const RESOURCE_ID_TYPE: &str = "crc32";
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Folder of the ark-cli configs, `~/.config/ark`
+pub fn config_dir() -> Option<PathBuf> {
+    home_dir().map(|home| home.join(ARK_CONFIG))
+}
+
+/// Roots config, `~/.config/ark/roots`. Older versions stored the roots
+/// in `~/.config/ark` itself, which is still read when it is a file.
+pub fn roots_path() -> Option<PathBuf> {
+    let dir = config_dir()?;
+    if dir.is_file() {
+        Some(dir)
+    } else {
+        Some(dir.join(ROOTS_CFG_FILENAME))
+    }
+}
+
+/// Persistent defaults of ark-cli, read from `~/.config/ark/config.toml`.
+/// Flags given to commands always take precedence.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Root used by commands when no root is given
+    pub root: Option<PathBuf>,
+    /// Hash function of resource ids, must match the one of the build
+    pub resource_id: Option<String>,
+    /// Output format of commands supporting `--format`
+    pub format: Option<Format>,
+    pub index: IndexConfig,
+    pub monitor: MonitorConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IndexConfig {
+    /// Update the stored index with changes of the folder when loading it
+    pub update_on_load: bool,
+    /// Read extended attributes and `.xmp`/`.json` sidecars of resources
+    /// into their external metadata when loading the index
+    pub ingest_external_metadata: bool,
+}
+
+impl Default for IndexConfig {
+    fn default() -> Self {
+        Self {
+            update_on_load: true,
+            ingest_external_metadata: false,
+        }
+    }
+}
+
+/// Defaults of `ark-cli monitor`, see its flags
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MonitorConfig {
+    /// Polling interval in milliseconds
+    pub interval: Option<u64>,
+    /// Local time range without polling, e.g. `22:00-07:00`
+    pub quiet_hours: Option<String>,
+    /// Url receiving a POST request with changes of the index
+    pub webhook: Option<String>,
+    /// Command run when the index changes
+    pub exec: Option<String>,
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        Some(config_dir()?.join(CONFIG_FILENAME))
+    }
+
+    /// Read the config file, default config is used if there is none
+    pub fn load() -> Result<Self, AppError> {
+        let Some(path) = Self::path().filter(|path| path.is_file()) else {
+            return Ok(Self::default());
+        };
+
+        let content = fs::read_to_string(&path)?;
+        let config: Config = toml::from_str(&content).map_err(|e| {
+            AppError::ConfigError(format!("{}: {}", path.display(), e))
+        })?;
+
+        if let Some(id_type) = &config.resource_id {
+            if !id_type.eq_ignore_ascii_case(RESOURCE_ID_TYPE) {
+                return Err(AppError::ConfigError(format!(
+                    "resource ids of type {} are not supported, \
+                     this build uses {}",
+                    id_type, RESOURCE_ID_TYPE
+                )));
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// Load the config once at startup
+pub fn init() -> Result<(), AppError> {
+    let config = Config::load()?;
+    let _ = CONFIG.set(config);
+    Ok(())
+}
+
+/// Config loaded at startup, or the default one
+pub fn config() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+
+/// Format given by the flag, or the preferred one
+pub fn output_format(flag: Option<Format>) -> Format {
+    flag.or(config().format).unwrap_or(Format::Raw)
+}
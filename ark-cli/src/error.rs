@@ -46,6 +46,18 @@ pub enum AppError {
     #[error("Invalid entry option")]
     InvalidEntryOption,
 
+    #[error("Resource not found: {0}")]
+    ResourceNotFound(String),
+
+    #[error("Invalid tag: {0}")]
+    InvalidTag(String),
+
+    #[error("Invalid config: {0}")]
+    ConfigError(String),
+
+    #[error("Failed to transfer files: {0}")]
+    DropError(String),
+
     #[error(transparent)]
     IoError(#[from] io::Error),
 
@@ -2,7 +2,9 @@ use lazy_static::lazy_static;
 extern crate canonical_path;
 
 use data_error::{ArklibError, Result};
-use fs_index::{load_or_build_index, ResourceIndex};
+use fs_index::{
+    load_or_build_index, sidecar::ingest_external_metadata, ResourceIndex,
+};
 
 use std::{
     collections::HashMap,
@@ -10,7 +12,7 @@ use std::{
     sync::{Arc, RwLock},
 };
 
-use crate::ResourceId;
+use crate::{config::config, ResourceId};
 
 use canonical_path::CanonicalPathBuf;
 
@@ -38,8 +40,20 @@ pub fn provide_index<P: AsRef<Path>>(
     log::info!("Index has not been registered before");
     // If the index has not been registered before,
     // we need to load it, update it and register it
-    match load_or_build_index(&root_path, true) {
+    match load_or_build_index(&root_path, config().index.update_on_load) {
         Ok(index) => {
+            if config().index.ingest_external_metadata {
+                match ingest_external_metadata(&index) {
+                    Ok(count) => log::info!(
+                        "Ingested external metadata of {} resources",
+                        count
+                    ),
+                    Err(e) => {
+                        log::warn!("Failed to ingest external metadata: {}", e)
+                    }
+                }
+            }
+
             let mut registrar = REGISTRAR.write().map_err(|_| {
                 ArklibError::Other(anyhow::anyhow!("Failed to lock registrar"))
             })?;
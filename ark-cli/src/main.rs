@@ -28,8 +28,8 @@ use home::home_dir;
 use crate::{
     cli::Cli,
     commands::{
-        file::File::{Append, Insert, Read},
-        link::Link::{Create, Load},
+        file::File::{Append, Insert, Meta, Read},
+        link::Link::{Check, Create, Load, Refresh},
         Commands::{Link, Storage, *},
     },
     models::{EntryOutput, Format, Sort},
@@ -38,12 +38,14 @@ use crate::{
 use crate::error::AppError;
 
 use util::{
-    discover_roots, monitor_index, provide_root, read_storage_value,
-    storages_exists, timestamp, translate_storage,
+    discover_roots, monitor_index, parse_roots, parse_since, print_json,
+    provide_root, read_storage_value, registered_roots, resolve_resource,
+    storages_exists, timestamp, translate_storage, STORAGE_NAMES,
 };
 
 mod cli;
 mod commands;
+mod config;
 mod error;
 mod index_registrar;
 mod models;
@@ -52,6 +54,8 @@ mod util;
 const ARK_CONFIG: &str = ".config/ark";
 const ARK_BACKUPS_PATH: &str = ".ark-backups";
 const ROOTS_CFG_FILENAME: &str = "roots";
+const ARCHIVE_USER_DATA_FILE: &str = "user-data.json";
+const ARCHIVE_CACHE_FOLDER: &str = "cache";
 
 struct StorageEntry {
     path: Option<PathBuf>,
@@ -62,28 +66,112 @@ struct StorageEntry {
     datetime: Option<String>,
 }
 
+impl StorageEntry {
+    /// Only the requested fields are present in the JSON object
+    fn to_json(&self) -> serde_json::Value {
+        let mut object = serde_json::Map::new();
+        if let Some(resource) = &self.resource {
+            object.insert("id".to_owned(), resource.to_string().into());
+        }
+        if let Some(path) = &self.path {
+            object.insert("path".to_owned(), path.display().to_string().into());
+        }
+        if let Some(content) = &self.content {
+            object.insert("link".to_owned(), content.clone().into());
+        }
+        if let Some(tags) = &self.tags {
+            object.insert("tags".to_owned(), tags.clone().into());
+        }
+        if let Some(scores) = self.scores {
+            object.insert("score".to_owned(), scores.into());
+        }
+        if let Some(datetime) = &self.datetime {
+            object.insert("modified".to_owned(), datetime.clone().into());
+        }
+        object.into()
+    }
+}
+
 async fn run() -> Result<()> {
     let matches = Cli::command().get_matches();
     let cli = Cli::from_arg_matches(&matches)?;
     match cli.command {
         Backup(backup) => backup.run()?,
+        Clean(clean) => clean.run()?,
         Collisions(collisions) => collisions.run()?,
+        Completions(completions) => completions.run()?,
+        Dedupe(dedupe) => dedupe.run()?,
+        Export(export) => export.run()?,
+        Import(import) => import.run()?,
         Monitor(monitor) => monitor.run()?,
         Render(render) => render.run()?,
+        Restore(restore) => restore.run()?,
         List(list) => list.run()?,
+        Search(search) => search.run()?,
+        Stats(stats) => stats.run()?,
+        Tui(tui) => tui.run()?,
         Watch(watch) => watch.run().await?,
         Link { subcommand } => match subcommand {
+            Check(check) => check.run().await?,
             Create(create) => create.run().await?,
             Load(load) => load.run()?,
+            Refresh(refresh) => refresh.run().await?,
+        },
+        crate::commands::Commands::Drop { subcommand } => match subcommand {
+            crate::commands::drop::Drop::Send(send) => send.run().await?,
+            crate::commands::drop::Drop::Receive(receive) => {
+                receive.run().await?
+            }
+            crate::commands::drop::Drop::Sync { subcommand } => {
+                match subcommand {
+                    crate::commands::drop::sync::Sync::Id(id) => id.run()?,
+                    crate::commands::drop::sync::Sync::Trust(trust) => {
+                        trust.run()?
+                    }
+                    crate::commands::drop::sync::Sync::Untrust(untrust) => {
+                        untrust.run()?
+                    }
+                    crate::commands::drop::sync::Sync::Listen(listen) => {
+                        listen.run().await?
+                    }
+                    crate::commands::drop::sync::Sync::Push(push) => {
+                        push.run().await?
+                    }
+                }
+            }
+        },
+        Favorites { subcommand } => match subcommand {
+            crate::commands::favorites::Favorites::Add(add) => add.run()?,
+            crate::commands::favorites::Favorites::Remove(remove) => {
+                remove.run()?
+            }
+            crate::commands::favorites::Favorites::List(list) => list.run()?,
         },
         crate::commands::Commands::File { subcommand } => match subcommand {
             Append(append) => append.run()?,
             Insert(insert) => insert.run()?,
+            Meta(meta) => meta.run()?,
             Read(read) => read.run()?,
         },
+        Score { subcommand } => match subcommand {
+            crate::commands::score::Score::Set(set) => set.run()?,
+            crate::commands::score::Score::Get(get) => get.run()?,
+            crate::commands::score::Score::Clear(clear) => clear.run()?,
+            crate::commands::score::Score::List(list) => list.run()?,
+        },
         Storage { subcommand } => match subcommand {
             crate::commands::storage::Storage::List(list) => list.run()?,
         },
+        Tag { subcommand } => match subcommand {
+            crate::commands::tag::Tag::Add(add) => add.run()?,
+            crate::commands::tag::Tag::Remove(remove) => remove.run()?,
+            crate::commands::tag::Tag::List(list) => list.run()?,
+        },
+        Thumbnails { subcommand } => match subcommand {
+            crate::commands::thumbnails::Thumbnails::Generate(generate) => {
+                generate.run()?
+            }
+        },
     };
 
     Ok(())
@@ -95,6 +183,8 @@ async fn main() -> anyhow::Result<()> {
         env_logger::Env::default().default_filter_or("info"),
     );
 
+    config::init()?;
+
     let app_id_dir = home_dir().ok_or(AppError::HomeDirNotFound)?;
     let ark_dir = app_id_dir.join(".ark");
     if !ark_dir.exists() {
@@ -102,8 +192,9 @@ async fn main() -> anyhow::Result<()> {
             .map_err(|e| AppError::ArkDirectoryCreationError(e.to_string()))?;
     }
 
-    println!("Loading app id at {}...", ark_dir.display());
-    let _ = app_id::load(ark_dir)
+    // Stdout is kept for command output, like JSON or completion scripts
+    eprintln!("Loading device identity at {}...", ark_dir.display());
+    let _ = app_id::load_identity(app_id_dir)
         .map_err(|e| AppError::AppIdLoadError(e.to_string()))?;
 
     // Having a separate function for the main logic allows for easier
@@ -18,11 +18,43 @@ pub enum Sort {
     Desc,
 }
 
-#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+/// Resource argument which can be given either as a path or as an id
+#[derive(Debug, Clone, clap::Args)]
+pub struct ResourceSelector {
+    #[clap(help = "Path or ID of the resource")]
+    pub resource: String,
+    #[clap(
+        long,
+        action,
+        conflicts_with = "by_id",
+        help = "Resource is a path"
+    )]
+    pub by_path: bool,
+    #[clap(long, action, help = "Resource is an ID")]
+    pub by_id: bool,
+}
+
+impl ResourceSelector {
+    /// Without explicit `--by-path` or `--by-id`,
+    /// existing paths take precedence over ids
+    pub fn is_path(&self) -> bool {
+        self.by_path
+            || (!self.by_id && std::path::Path::new(&self.resource).exists())
+    }
+}
+
+/// Format of values given to storages and of the commands output.
+/// JSON output uses the same field names in every command:
+/// `id`, `path`, `link`, `tags`, `score`, `modified`, `value`.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum, serde::Deserialize,
+)]
 pub enum Format {
     #[clap(name = "json")]
+    #[serde(rename = "json")]
     KeyValue,
     #[clap(name = "raw")]
+    #[serde(rename = "raw")]
     Raw,
 }
 
@@ -1,9 +1,12 @@
 use crate::ResourceId;
-use fs_atomic_versions::atomic::AtomicFile;
+use fs_atomic_versions::atomic::{modify, AtomicFile};
+use serde_json::{json, Value};
 use std::{fmt::Write, path::PathBuf};
 
 use crate::{
-    commands::{file_append, file_insert, format_file, format_line},
+    commands::{
+        file_append, file_insert, format_file, format_line, version_info,
+    },
     error::AppError,
     models::Format,
 };
@@ -301,6 +304,73 @@ impl Storage {
         }
     }
 
+    /// Collect values of all lines of the resource in a file storage.
+    /// Appending creates a new line, so a resource can have many of them.
+    pub fn values(&self, id: &ResourceId) -> Result<Vec<String>, AppError> {
+        Ok(self
+            .entries()?
+            .into_iter()
+            .filter(|(line_id, _)| line_id == id)
+            .map(|(_, value)| value)
+            .collect())
+    }
+
+    /// All entries of a file storage, in order of lines
+    pub fn entries(&self) -> Result<Vec<(ResourceId, String)>, AppError> {
+        let StorageType::File = self.storage_type else {
+            return Err(AppError::StorageCreationError(
+                "Only file storages consist of entries".to_owned(),
+            ));
+        };
+
+        let atomic_file = AtomicFile::new(&self.path)?;
+        let atomic_file_data = atomic_file.load()?;
+        if atomic_file_data.version == 0 {
+            return Ok(vec![]);
+        }
+        let data = atomic_file_data.read_to_string()?;
+        Ok(parse_entries(&data))
+    }
+
+    /// Atomically replace all lines of the resource in a file storage
+    /// with a single one. The `operator` receives current values of the
+    /// resource, returning `None` removes the resource from the storage.
+    pub fn update(
+        &mut self,
+        id: &ResourceId,
+        mut operator: impl FnMut(Vec<String>) -> Option<String>,
+    ) -> Result<(), AppError> {
+        let StorageType::File = self.storage_type else {
+            return Err(AppError::StorageCreationError(
+                "Only file storages can be updated by entry".to_owned(),
+            ));
+        };
+
+        let atomic_file = AtomicFile::new(&self.path)?;
+        modify(&atomic_file, |current| {
+            let current = String::from_utf8_lossy(current);
+            // Lines of other resources are kept untouched
+            let mut values = vec![];
+            let mut output = String::new();
+            for line in current.lines() {
+                match parse_entry(line) {
+                    Some((line_id, value)) if &line_id == id => {
+                        values.push(value)
+                    }
+                    _ => {
+                        output.push_str(line);
+                        output.push('\n');
+                    }
+                }
+            }
+            if let Some(value) = operator(values) {
+                output.push_str(&format!("{}:{}\n", id, value));
+            }
+            output.into_bytes()
+        })?;
+        Ok(())
+    }
+
     pub fn list(&self, versions: bool) -> Result<String, AppError> {
         let mut output = String::new();
 
@@ -404,4 +474,51 @@ impl Storage {
 
         Ok(output)
     }
+
+    /// Same content as [`Storage::list`], as a JSON array of objects
+    pub fn list_json(&self, versions: bool) -> Result<Value, AppError> {
+        if !versions {
+            return Ok(self
+                .files
+                .iter()
+                .map(|id| json!({ "id": id.to_string() }))
+                .collect());
+        }
+
+        match self.storage_type {
+            StorageType::File => Ok(self
+                .entries()?
+                .into_iter()
+                .map(|(id, value)| {
+                    json!({ "id": id.to_string(), "value": value })
+                })
+                .collect()),
+            StorageType::Folder => {
+                let entries = std::fs::read_dir(&self.path)?
+                    .filter_map(|entry| entry.ok())
+                    .filter(|entry| entry.path().is_dir())
+                    .filter_map(|entry| AtomicFile::new(entry.path()).ok())
+                    .filter_map(|file| version_info(&file))
+                    .map(|(version, name, machine, path)| {
+                        json!({
+                            "version": version,
+                            "name": name,
+                            "machine": machine,
+                            "path": path,
+                        })
+                    })
+                    .collect();
+                Ok(entries)
+            }
+        }
+    }
+}
+
+fn parse_entries(data: &str) -> Vec<(ResourceId, String)> {
+    data.lines().filter_map(parse_entry).collect()
+}
+
+fn parse_entry(line: &str) -> Option<(ResourceId, String)> {
+    let (id, value) = line.split_once(':')?;
+    Some((id.parse::<ResourceId>().ok()?, value.to_string()))
 }
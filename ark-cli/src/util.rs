@@ -1,25 +1,33 @@
 use crate::ResourceId;
+use chrono::{Local, NaiveDate, TimeZone};
 use fs_index::ResourceIndex;
-use fs_metadata::METADATA_STORAGE_FOLDER;
+use fs_metadata::{EXTERNAL_METADATA_STORAGE_FOLDER, METADATA_STORAGE_FOLDER};
 use fs_properties::PROPERTIES_STORAGE_FOLDER;
 use fs_storage::{
-    ARK_FOLDER, PREVIEWS_STORAGE_FOLDER, SCORE_STORAGE_FILE, STATS_FOLDER,
+    ARK_FOLDER, FAVICONS_STORAGE_FOLDER, FAVORITES_FILE,
+    PREVIEWS_STORAGE_FOLDER, SCORE_STORAGE_FILE, STATS_FOLDER,
     TAG_STORAGE_FILE, THUMBNAILS_STORAGE_FOLDER,
 };
+use serde_json::{json, Value};
 use std::{
     env::current_dir,
     fs::{canonicalize, metadata, File},
     io::{BufRead, BufReader},
     path::{Path, PathBuf},
+    process::Command,
     str::FromStr,
     thread,
     time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use crate::{
+    commands::{collisions::resolution::Resolutions, monitor::MonitorOptions},
+    config::{config, roots_path},
     error::AppError,
-    models::storage::{Storage, StorageType},
-    ARK_CONFIG,
+    models::{
+        storage::{Storage, StorageType},
+        Format, ResourceSelector,
+    },
 };
 
 pub fn discover_roots(
@@ -33,11 +41,12 @@ pub fn discover_roots(
         let config = File::open(path)?;
 
         Ok(parse_roots(config))
-    } else if let Ok(config) = File::open(ARK_CONFIG) {
+    } else if let Some(path) = roots_path().filter(|path| path.is_file()) {
         println!(
             "\tRoots config was found automatically:\n\t\t{}",
-            &ARK_CONFIG
+            path.display()
         );
+        let config = File::open(path)?;
 
         Ok(parse_roots(config))
     } else {
@@ -63,8 +72,10 @@ pub fn discover_roots(
     }
 }
 
+/// Root given by the flag, or the configured one,
+/// or the current directory
 pub fn provide_root(root_dir: &Option<PathBuf>) -> Result<PathBuf, AppError> {
-    if let Some(path) = root_dir {
+    if let Some(path) = root_dir.as_ref().or(config().root.as_ref()) {
         Ok(path.clone())
     } else {
         Ok(current_dir()?)
@@ -81,36 +92,80 @@ pub fn provide_index(root_dir: &PathBuf) -> ResourceIndex<ResourceId> {
 
 pub fn monitor_index(
     root_dir: &Option<PathBuf>,
-    interval: Option<u64>,
+    monitor: Option<MonitorOptions>,
+    format: Format,
 ) -> Result<(), AppError> {
     let dir_path = provide_root(root_dir)?;
+    let json = format == Format::KeyValue;
 
-    println!("Building index of folder {}", dir_path.display());
+    if !json {
+        println!("Building index of folder {}", dir_path.display());
+    }
     let start = Instant::now();
 
-    let result = crate::provide_index(dir_path);
+    let result = crate::provide_index(&dir_path);
     let duration = start.elapsed();
 
     match result {
         Ok(rwlock) => {
-            println!("Build succeeded in {:?}\n", duration);
+            if !json {
+                println!("Build succeeded in {:?}\n", duration);
+            }
 
-            if let Some(millis) = interval {
+            if let Some(monitor) = monitor {
                 let mut index = rwlock.write().map_err(|_| {
                     AppError::StorageCreationError(
                         "Failed to write lock index".to_owned(),
                     )
                 })?;
                 loop {
-                    let pause = Duration::from_millis(millis);
-                    thread::sleep(pause);
+                    thread::sleep(monitor.interval);
+
+                    // Changes made during quiet hours are
+                    // picked up by the first check after them
+                    if monitor
+                        .quiet_hours
+                        .as_ref()
+                        .is_some_and(|hours| hours.is_active())
+                    {
+                        continue;
+                    }
 
                     let start = Instant::now();
                     match index.update_all() {
+                        Err(msg) if json => {
+                            println!("{}", json!({ "error": msg.to_string() }))
+                        }
                         Err(msg) => println!("Oops! {}", msg),
                         Ok(diff) => {
                             index.store().expect("Could not store index");
                             let duration = start.elapsed();
+                            monitor.notify(&dir_path, &diff);
+
+                            if json {
+                                // One line per update, so the output
+                                // can be consumed while monitoring
+                                let added: Vec<String> = diff
+                                    .added()
+                                    .keys()
+                                    .map(|id| id.to_string())
+                                    .collect();
+                                let removed: Vec<String> = diff
+                                    .removed()
+                                    .iter()
+                                    .map(|id| id.to_string())
+                                    .collect();
+                                println!(
+                                    "{}",
+                                    json!({
+                                        "duration_ms": duration.as_millis(),
+                                        "added": added,
+                                        "removed": removed,
+                                    })
+                                );
+                                continue;
+                            }
+
                             println!("Updating succeeded in {:?}\n", duration);
 
                             if !diff.removed().is_empty() {
@@ -119,6 +174,19 @@ pub fn monitor_index(
                             if !diff.added().is_empty() {
                                 println!("Added: {:?}", diff.added());
                             }
+
+                            let resolutions = Resolutions::load(&dir_path)?;
+                            for (id, paths) in index.collisions() {
+                                if diff.added().contains_key(&id)
+                                    && !resolutions.is_resolved(&id, &paths)
+                                {
+                                    println!(
+                                        "Collision: id {} calculated {} times",
+                                        id,
+                                        paths.len()
+                                    );
+                                }
+                            }
                         }
                     }
                 }
@@ -129,23 +197,107 @@ pub fn monitor_index(
                     )
                 })?;
 
+                // Collisions resolved with `ark-cli collisions` are hidden
+                let resolutions = Resolutions::load(&dir_path)?;
+                let (resolved, collisions): (Vec<_>, Vec<_>) = index
+                    .collisions()
+                    .into_iter()
+                    .partition(|(id, paths)| {
+                        resolutions.is_resolved(id, paths)
+                    });
+
+                if json {
+                    let collisions: Vec<Value> = collisions
+                        .iter()
+                        .map(|(id, paths)| {
+                            json!({
+                                "id": id.to_string(),
+                                "paths": paths,
+                            })
+                        })
+                        .collect();
+                    return print_json(&json!({
+                        "root": dir_path,
+                        "entries": index.len(),
+                        "duration_ms": duration.as_millis(),
+                        "collisions": collisions,
+                        "resolved": resolved.len(),
+                    }));
+                }
+
                 println!("Here are {} entries in the index", index.len());
 
-                for (key, resources) in index.collisions().iter() {
+                for (key, resources) in collisions.iter() {
                     println!(
                         "Id {:?} calculated {} times",
                         key,
                         resources.len()
                     );
                 }
+                if !resolved.is_empty() {
+                    println!("{} resolved collisions hidden", resolved.len());
+                }
             }
         }
+        Err(err) if json => {
+            print_json(&json!({ "error": format!("{:?}", err) }))?
+        }
         Err(err) => println!("Failure: {:?}", err),
     }
 
     Ok(())
 }
 
+/// Parse a date like `2024-05-01` or a period like `7d`
+/// into the moment it refers to
+pub fn parse_since(value: &str) -> Result<SystemTime, String> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let midnight = date
+            .and_hms_opt(0, 0, 0)
+            .and_then(|time| Local.from_local_datetime(&time).earliest())
+            .ok_or_else(|| format!("Invalid date {}", value))?;
+        return Ok(midnight.into());
+    }
+
+    let split = value
+        .char_indices()
+        .last()
+        .map_or(0, |(index, _)| index);
+    let (amount, unit) = value.split_at(split);
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| format!("Expected a date or a period, got {}", value))?;
+    let seconds = match unit {
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(format!("Unknown period unit {}", unit)),
+    };
+    amount
+        .checked_mul(seconds)
+        .and_then(|seconds| {
+            SystemTime::now().checked_sub(Duration::from_secs(seconds))
+        })
+        .ok_or_else(|| format!("Period {} is too long", value))
+}
+
+/// Print the value as pretty JSON, used by commands with `--format json`
+pub fn print_json(value: &Value) -> Result<(), AppError> {
+    let output = serde_json::to_string_pretty(value)
+        .map_err(|e| AppError::FileOperationError(e.to_string()))?;
+    println!("{}", output);
+    Ok(())
+}
+
+/// Roots listed in the roots config, empty if there is no config
+pub fn registered_roots() -> Vec<PathBuf> {
+    roots_path()
+        .and_then(|path| File::open(path).ok())
+        .map(parse_roots)
+        .unwrap_or_default()
+}
+
 pub fn storages_exists(path: &Path) -> bool {
     let meta = metadata(path.join(ARK_FOLDER));
     if let Ok(meta) = meta {
@@ -168,6 +320,21 @@ pub fn parse_roots(config: File) -> Vec<PathBuf> {
         .collect()
 }
 
+/// Build a process running the command through the shell, `sh` or `cmd`
+pub fn shell(command: &str) -> Command {
+    #[cfg(unix)]
+    let mut process = Command::new("sh");
+    #[cfg(unix)]
+    process.arg("-c");
+    #[cfg(windows)]
+    let mut process = Command::new("cmd");
+    #[cfg(windows)]
+    process.arg("/C");
+
+    process.arg(command);
+    process
+}
+
 pub fn timestamp() -> Duration {
     let start = SystemTime::now();
     start
@@ -175,6 +342,20 @@ pub fn timestamp() -> Duration {
         .expect("Time went backwards!")
 }
 
+/// Storages which can be referred to by name
+pub const STORAGE_NAMES: [&str; 10] = [
+    "tags",
+    "scores",
+    "favorites",
+    "stats",
+    "properties",
+    "metadata",
+    "external",
+    "previews",
+    "thumbnails",
+    "favicons",
+];
+
 pub fn translate_storage(
     root: &Option<PathBuf>,
     storage: &str,
@@ -199,6 +380,13 @@ pub fn translate_storage(
                 .join(SCORE_STORAGE_FILE),
             Some(StorageType::File),
         )),
+        "favorites" => Some((
+            provide_root(root)
+                .ok()?
+                .join(ARK_FOLDER)
+                .join(FAVORITES_FILE),
+            Some(StorageType::File),
+        )),
         "stats" => Some((
             provide_root(root)
                 .ok()?
@@ -220,6 +408,13 @@ pub fn translate_storage(
                 .join(METADATA_STORAGE_FOLDER),
             Some(StorageType::Folder),
         )),
+        "external" => Some((
+            provide_root(root)
+                .ok()?
+                .join(ARK_FOLDER)
+                .join(EXTERNAL_METADATA_STORAGE_FOLDER),
+            Some(StorageType::Folder),
+        )),
         "previews" => Some((
             provide_root(root)
                 .ok()?
@@ -234,18 +429,25 @@ pub fn translate_storage(
                 .join(THUMBNAILS_STORAGE_FOLDER),
             Some(StorageType::Folder),
         )),
+        "favicons" => Some((
+            provide_root(root)
+                .ok()?
+                .join(ARK_FOLDER)
+                .join(FAVICONS_STORAGE_FOLDER),
+            Some(StorageType::Folder),
+        )),
         _ => None,
     }
 }
 
 pub fn read_storage_value(
-    root_dir: &PathBuf,
+    root_dir: &Path,
     storage: &str,
     id: &str,
     type_: &Option<String>,
 ) -> Result<String, AppError> {
     let (file_path, storage_type) =
-        translate_storage(&Some(root_dir.to_owned()), storage)
+        translate_storage(&Some(root_dir.to_path_buf()), storage)
             .ok_or(AppError::StorageNotFound(storage.to_owned()))?;
 
     let storage_type = storage_type.unwrap_or(match type_ {
@@ -263,3 +465,34 @@ pub fn read_storage_value(
         ResourceId::from_str(id).map_err(|_| AppError::InvalidEntryOption)?;
     storage.read(resource_id)
 }
+
+/// Find the id of the selected resource. Without explicit `--by-path`
+/// or `--by-id`, existing paths take precedence over ids.
+pub fn resolve_resource(
+    root: &PathBuf,
+    selector: &ResourceSelector,
+) -> Result<ResourceId, AppError> {
+    let resource = &selector.resource;
+    if !selector.is_path() {
+        return ResourceId::from_str(resource).map_err(|_| {
+            AppError::ResourceNotFound(format!("Invalid id {}", resource))
+        });
+    }
+
+    let path = canonicalize(resource)?;
+    let relative = path
+        .strip_prefix(canonicalize(root)?)
+        .map_err(|_| {
+            AppError::ResourceNotFound(format!(
+                "{} is outside of root {}",
+                resource,
+                root.display()
+            ))
+        })?;
+    provide_index(root)
+        .get_resource_by_path(relative)
+        .map(|resource| resource.id().clone())
+        .ok_or_else(|| {
+            AppError::ResourceNotFound(format!("{} is not indexed", resource))
+        })
+}
@@ -1,9 +1,74 @@
 use serde_json::{json, map::Entry, Map, Value};
 
+mod patch;
+mod pointer;
+
+pub use patch::{apply, diff, Operation, Patch, PatchError};
+pub use pointer::{extract, extract_all, merge_at, pointer, replace_at};
+
+/// How to resolve two different values found at the same place
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Conflict {
+    /// Keep both values in an array, if they have the same type
+    #[default]
+    Collect,
+    /// Replace old value with the new one
+    NewWins,
+    /// Ignore the new value
+    KeepOld,
+}
+
+/// How to combine two arrays when conflicts are collected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Arrays {
+    /// Append only new values missing from the old array
+    #[default]
+    Union,
+    /// Append all new values, keeping duplicates
+    Concat,
+}
+
+/// Policy of [`merge_with`]. Default options are used by [`merge`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MergeOptions {
+    pub conflict: Conflict,
+    pub arrays: Arrays,
+    /// Number of nested object levels merged key by key. Deeper objects
+    /// are treated as plain values, `0` merges only the top-level keys.
+    pub max_depth: usize,
+}
+
 pub fn merge(origin: Value, new_data: Value) -> Value {
+    merge_with(origin, new_data, &MergeOptions::default())
+}
+
+/// Merge `new_data` into `origin` according to `options`
+pub fn merge_with(
+    origin: Value,
+    new_data: Value,
+    options: &MergeOptions,
+) -> Value {
+    match (origin, new_data) {
+        (Value::Object(old), Value::Object(new)) => {
+            merge_object(old, new, options, 0)
+        }
+        (old, new) => resolve(old, new, options),
+    }
+}
+
+fn resolve(origin: Value, new_data: Value, options: &MergeOptions) -> Value {
+    match options.conflict {
+        Conflict::Collect => collect(origin, new_data, options),
+        Conflict::NewWins if new_data.is_null() => origin,
+        Conflict::NewWins => new_data,
+        Conflict::KeepOld if origin.is_null() => new_data,
+        Conflict::KeepOld => origin,
+    }
+}
+
+fn collect(origin: Value, new_data: Value, options: &MergeOptions) -> Value {
     match (origin, new_data) {
-        (Value::Object(old), Value::Object(new)) => merge_object(old, new),
-        (Value::Array(old), Value::Array(new)) => merge_vec(old, new),
+        (Value::Array(old), Value::Array(new)) => merge_vec(old, new, options),
         (Value::Array(mut old), new) => {
             if !old.is_empty()
                 && std::mem::discriminant(&old[0])
@@ -47,6 +112,8 @@ pub fn merge(origin: Value, new_data: Value) -> Value {
 fn merge_object(
     mut origin: Map<String, Value>,
     new_data: Map<String, Value>,
+    options: &MergeOptions,
+    depth: usize,
 ) -> Value {
     for (key, value) in new_data.into_iter() {
         match origin.entry(&key) {
@@ -57,8 +124,18 @@ fn merge_object(
                 // Extract entry to manipulate it
                 let prev = prev.remove();
                 match (prev, value) {
+                    (Value::Object(old), Value::Object(new))
+                        if depth < options.max_depth =>
+                    {
+                        let updated =
+                            merge_object(old, new, options, depth + 1);
+                        origin.insert(key, updated);
+                    }
+                    (prev, value) if options.conflict != Conflict::Collect => {
+                        origin.insert(key, resolve(prev, value, options));
+                    }
                     (Value::Array(old_data), Value::Array(new_data)) => {
-                        let updated = merge_vec(old_data, new_data);
+                        let updated = merge_vec(old_data, new_data, options);
                         origin.insert(key, updated);
                     }
                     (Value::Array(d), Value::Null) => {
@@ -104,7 +181,11 @@ fn merge_object(
     Value::Object(origin)
 }
 
-fn merge_vec(original: Vec<Value>, new_data: Vec<Value>) -> Value {
+fn merge_vec(
+    original: Vec<Value>,
+    new_data: Vec<Value>,
+    options: &MergeOptions,
+) -> Value {
     if original.is_empty() {
         Value::Array(new_data)
     } else if new_data.is_empty() {
@@ -120,7 +201,8 @@ fn merge_vec(original: Vec<Value>, new_data: Vec<Value>) -> Value {
             .into_iter()
             .filter(|v| {
                 std::mem::discriminant(v) == discriminant
-                    && filtered.iter().all(|val| val != v)
+                    && (options.arrays == Arrays::Concat
+                        || filtered.iter().all(|val| val != v))
             })
             .collect();
         filtered.extend(new);
@@ -151,4 +233,39 @@ mod tests {
         let merged = merge(old, new);
         assert_eq!(merged, expected);
     }
+
+    #[rstest]
+    #[case(Conflict::NewWins, 0, json ! ({"a": 2, "b": {"c": 2}, "d": [1, 2]}))]
+    #[case(Conflict::KeepOld, 0, json ! ({"a": 1, "b": {"c": 1, "e": 1}, "d": [1, 2]}))]
+    #[case(Conflict::NewWins, 1, json ! ({"a": 2, "b": {"c": 2, "e": 1}, "d": [1, 2]}))]
+    #[case(Conflict::Collect, 1, json ! ({"a": [1, 2], "b": {"c": [1, 2], "e": 1}, "d": [1, 2]}))]
+    fn merging_with_options(
+        #[case] conflict: Conflict,
+        #[case] max_depth: usize,
+        #[case] expected: Value,
+    ) {
+        let old = json!({"a": 1, "b": {"c": 1, "e": 1}, "d": [1, 2]});
+        let new = json!({"a": 2, "b": {"c": 2}, "d": [1, 2], "f": null});
+        let options = MergeOptions {
+            conflict,
+            max_depth,
+            ..Default::default()
+        };
+        let mut expected = expected;
+        expected["f"] = Value::Null;
+        assert_eq!(merge_with(old, new, &options), expected);
+    }
+
+    #[test]
+    fn concatenating_arrays() {
+        let options = MergeOptions {
+            arrays: Arrays::Concat,
+            ..Default::default()
+        };
+        assert_eq!(
+            merge_with(json!([1, 2]), json!([2, 3]), &options),
+            json!([1, 2, 2, 3])
+        );
+        assert_eq!(merge(json!([1, 2]), json!([2, 3])), json!([1, 2, 3]));
+    }
 }
@@ -0,0 +1,295 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+/// Single operation of a JSON Patch (RFC 6902)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum Operation {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: Value },
+}
+
+/// Sequence of operations transforming one document into another,
+/// serialized as a JSON Patch document
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Patch(pub Vec<Operation>);
+
+impl Patch {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchError {
+    /// Path doesn't point to an existing location
+    InvalidPointer(String),
+    /// `test` operation failed
+    TestFailed(String),
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatchError::InvalidPointer(path) => {
+                write!(f, "Invalid JSON pointer: {path}")
+            }
+            PatchError::TestFailed(path) => {
+                write!(f, "Test of {path} failed")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+/// Compute the patch transforming `old` into `new`.
+/// Swapping the arguments produces a patch undoing the change.
+pub fn diff(old: &Value, new: &Value) -> Patch {
+    let mut operations = vec![];
+    diff_values(old, new, String::new(), &mut operations);
+    Patch(operations)
+}
+
+fn diff_values(
+    old: &Value,
+    new: &Value,
+    path: String,
+    operations: &mut Vec<Operation>,
+) {
+    match (old, new) {
+        _ if old == new => {}
+        (Value::Object(old), Value::Object(new)) => {
+            for (key, value) in old {
+                let path = format!("{path}/{}", escape(key));
+                match new.get(key) {
+                    Some(new_value) => {
+                        diff_values(value, new_value, path, operations)
+                    }
+                    None => operations.push(Operation::Remove { path }),
+                }
+            }
+            for (key, value) in new {
+                if !old.contains_key(key) {
+                    operations.push(Operation::Add {
+                        path: format!("{path}/{}", escape(key)),
+                        value: value.clone(),
+                    });
+                }
+            }
+        }
+        (Value::Array(old), Value::Array(new)) => {
+            let common = old.len().min(new.len());
+            for index in 0..common {
+                diff_values(
+                    &old[index],
+                    &new[index],
+                    format!("{path}/{index}"),
+                    operations,
+                );
+            }
+            // Removing from the end keeps the indices valid
+            for index in (common..old.len()).rev() {
+                operations.push(Operation::Remove {
+                    path: format!("{path}/{index}"),
+                });
+            }
+            for value in &new[common..] {
+                operations.push(Operation::Add {
+                    path: format!("{path}/-"),
+                    value: value.clone(),
+                });
+            }
+        }
+        _ => operations.push(Operation::Replace {
+            path,
+            value: new.clone(),
+        }),
+    }
+}
+
+/// Apply the patch to the document. The document is left untouched
+/// if any operation fails.
+pub fn apply(document: &mut Value, patch: &Patch) -> Result<(), PatchError> {
+    let mut patched = document.clone();
+    for operation in &patch.0 {
+        apply_operation(&mut patched, operation)?;
+    }
+    *document = patched;
+    Ok(())
+}
+
+fn apply_operation(
+    document: &mut Value,
+    operation: &Operation,
+) -> Result<(), PatchError> {
+    match operation {
+        Operation::Add { path, value } => add(document, path, value.clone()),
+        Operation::Remove { path } => remove(document, path).map(|_| ()),
+        Operation::Replace { path, value } => {
+            let target = document
+                .pointer_mut(path)
+                .ok_or_else(|| PatchError::InvalidPointer(path.clone()))?;
+            *target = value.clone();
+            Ok(())
+        }
+        Operation::Move { from, path } => {
+            if path.starts_with(&format!("{from}/")) {
+                // Location can't be moved into its own child
+                return Err(PatchError::InvalidPointer(path.clone()));
+            }
+            let value = remove(document, from)?;
+            add(document, path, value)
+        }
+        Operation::Copy { from, path } => {
+            let value = document
+                .pointer(from)
+                .cloned()
+                .ok_or_else(|| PatchError::InvalidPointer(from.clone()))?;
+            add(document, path, value)
+        }
+        Operation::Test { path, value } => match document.pointer(path) {
+            Some(current) if current == value => Ok(()),
+            _ => Err(PatchError::TestFailed(path.clone())),
+        },
+    }
+}
+
+fn add(
+    document: &mut Value,
+    path: &str,
+    value: Value,
+) -> Result<(), PatchError> {
+    if path.is_empty() {
+        *document = value;
+        return Ok(());
+    }
+    let (parent, key) = split(path)?;
+    let invalid = || PatchError::InvalidPointer(path.to_string());
+    match document.pointer_mut(parent).ok_or_else(invalid)? {
+        Value::Object(map) => {
+            map.insert(key, value);
+            Ok(())
+        }
+        Value::Array(array) => {
+            if key == "-" {
+                array.push(value);
+                return Ok(());
+            }
+            let index = parse_index(&key).ok_or_else(invalid)?;
+            if index > array.len() {
+                return Err(invalid());
+            }
+            array.insert(index, value);
+            Ok(())
+        }
+        _ => Err(invalid()),
+    }
+}
+
+fn remove(document: &mut Value, path: &str) -> Result<Value, PatchError> {
+    let (parent, key) = split(path)?;
+    let invalid = || PatchError::InvalidPointer(path.to_string());
+    match document.pointer_mut(parent).ok_or_else(invalid)? {
+        Value::Object(map) => map.remove(&key).ok_or_else(invalid),
+        Value::Array(array) => {
+            let index = parse_index(&key).ok_or_else(invalid)?;
+            if index >= array.len() {
+                return Err(invalid());
+            }
+            Ok(array.remove(index))
+        }
+        _ => Err(invalid()),
+    }
+}
+
+/// Split the pointer into the pointer of its parent and the last token
+fn split(path: &str) -> Result<(&str, String), PatchError> {
+    let (parent, key) = path
+        .rsplit_once('/')
+        .ok_or_else(|| PatchError::InvalidPointer(path.to_string()))?;
+    Ok((parent, unescape(key)))
+}
+
+fn parse_index(token: &str) -> Option<usize> {
+    // Leading zeros are not allowed by RFC 6901
+    if token.len() > 1 && token.starts_with('0') {
+        return None;
+    }
+    token.parse().ok()
+}
+
+pub(crate) fn escape(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+pub(crate) fn unescape(token: &str) -> String {
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use serde_json::json;
+
+    #[rstest]
+    #[case(json ! ({"a": 1, "b": "text"}), json ! ({"a": 2, "c": [1]}))]
+    #[case(json ! ({"a/b": {"c~": [1, 2, 3]}}), json ! ({"a/b": {"c~": [1, 4]}}))]
+    #[case(json ! ([1, {"a": null}]), json ! ([1, {"a": true}, "x", "y"]))]
+    #[case(json ! ("old"), json ! ({"new": "object"}))]
+    fn diff_and_apply(#[case] old: Value, #[case] new: Value) {
+        let patch = diff(&old, &new);
+        let mut document = old.clone();
+        apply(&mut document, &patch).unwrap();
+        assert_eq!(document, new);
+
+        // Reverse patch undoes the change
+        apply(&mut document, &diff(&new, &old)).unwrap();
+        assert_eq!(document, old);
+    }
+
+    #[test]
+    fn serialized_as_rfc6902() {
+        let patch: Patch = serde_json::from_value(json!([
+            {"op": "test", "path": "/a", "value": 1},
+            {"op": "move", "from": "/a", "path": "/b"},
+            {"op": "copy", "from": "/b", "path": "/c"},
+        ]))
+        .unwrap();
+        let mut document = json!({"a": 1});
+        apply(&mut document, &patch).unwrap();
+        assert_eq!(document, json!({"b": 1, "c": 1}));
+
+        assert_eq!(
+            serde_json::to_value(diff(&json!({"a": 1}), &json!({}))).unwrap(),
+            json!([{"op": "remove", "path": "/a"}])
+        );
+    }
+
+    #[test]
+    fn failed_patch_is_not_applied() {
+        let mut document = json!({"a": 1});
+        let patch = Patch(vec![
+            Operation::Add {
+                path: "/b".to_string(),
+                value: json!(2),
+            },
+            Operation::Test {
+                path: "/a".to_string(),
+                value: json!(2),
+            },
+        ]);
+        assert_eq!(
+            apply(&mut document, &patch),
+            Err(PatchError::TestFailed("/a".to_string()))
+        );
+        assert_eq!(document, json!({"a": 1}));
+    }
+}
@@ -0,0 +1,127 @@
+use serde_json::{Map, Value};
+
+use crate::{
+    merge_with,
+    patch::{escape, unescape},
+    MergeOptions, PatchError,
+};
+
+/// Get a copy of the value addressed by JSON Pointer (RFC 6901)
+pub fn extract(document: &Value, pointer: &str) -> Option<Value> {
+    document.pointer(pointer).cloned()
+}
+
+/// Build a document containing only values addressed by the pointers,
+/// at the same locations. Missing locations are skipped.
+pub fn extract_all(document: &Value, pointers: &[&str]) -> Value {
+    let mut extracted = Value::Null;
+    for pointer in pointers {
+        if let Some(value) = extract(document, pointer) {
+            // Parents are rebuilt as objects, so array indices
+            // become keys of the extracted document
+            let _ = replace_at(&mut extracted, pointer, value);
+        }
+    }
+    extracted
+}
+
+/// Replace the value addressed by the pointer,
+/// creating missing parent objects
+pub fn replace_at(
+    document: &mut Value,
+    pointer: &str,
+    value: Value,
+) -> Result<(), PatchError> {
+    *location(document, pointer)? = value;
+    Ok(())
+}
+
+/// Merge `new_data` into the value addressed by the pointer, leaving
+/// the rest of the document untouched. Missing parent objects are created.
+pub fn merge_at(
+    document: &mut Value,
+    pointer: &str,
+    new_data: Value,
+    options: &MergeOptions,
+) -> Result<(), PatchError> {
+    let target = location(document, pointer)?;
+    *target = merge_with(target.take(), new_data, options);
+    Ok(())
+}
+
+/// Find the location addressed by the pointer, creating missing
+/// object members along the way
+fn location<'a>(
+    document: &'a mut Value,
+    pointer: &str,
+) -> Result<&'a mut Value, PatchError> {
+    if pointer.is_empty() {
+        return Ok(document);
+    }
+    let invalid = || PatchError::InvalidPointer(pointer.to_string());
+    let tokens = pointer.strip_prefix('/').ok_or_else(invalid)?;
+
+    let mut current = document;
+    for token in tokens.split('/').map(unescape) {
+        if current.is_null() {
+            *current = Value::Object(Map::new());
+        }
+        current = match current {
+            Value::Object(map) => map.entry(token).or_insert(Value::Null),
+            Value::Array(array) => token
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| array.get_mut(index))
+                .ok_or_else(invalid)?,
+            _ => return Err(invalid()),
+        };
+    }
+    Ok(current)
+}
+
+/// Build the pointer from unescaped tokens
+pub fn pointer<'a>(tokens: impl IntoIterator<Item = &'a str>) -> String {
+    tokens
+        .into_iter()
+        .map(|token| format!("/{}", escape(token)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Conflict;
+    use serde_json::json;
+
+    #[test]
+    fn extracting_by_pointer() {
+        let document = json!({"a": {"b": [1, 2], "c": "x"}, "d/e": true});
+        assert_eq!(extract(&document, "/a/b/1"), Some(json!(2)));
+        assert_eq!(extract(&document, "/a/z"), None);
+        assert_eq!(
+            extract_all(&document, &["/a/c", "/d~1e", "/missing"]),
+            json!({"a": {"c": "x"}, "d/e": true})
+        );
+        assert_eq!(pointer(["a", "d/e"]), "/a/d~1e");
+    }
+
+    #[test]
+    fn merging_by_pointer() {
+        let mut document = json!({"a": {"b": 1, "c": 1}, "d": 1});
+        let options = MergeOptions {
+            conflict: Conflict::NewWins,
+            ..Default::default()
+        };
+        merge_at(&mut document, "/a", json!({"b": 2}), &options).unwrap();
+        merge_at(&mut document, "/x/y", json!(3), &options).unwrap();
+        assert_eq!(
+            document,
+            json!({"a": {"b": 2, "c": 1}, "d": 1, "x": {"y": 3}})
+        );
+
+        replace_at(&mut document, "/a", json!([0])).unwrap();
+        assert_eq!(document["a"], json!([0]));
+        assert!(replace_at(&mut document, "/d/e", json!(0)).is_err());
+        assert!(replace_at(&mut document, "/a/5", json!(0)).is_err());
+    }
+}
@@ -0,0 +1,485 @@
+use data_error::{ArklibError, Result};
+use data_resource::ResourceId;
+use fs_properties::archive::{import_user_data, UserDataArchive};
+use fs_storage::ARK_FOLDER;
+use scraper::{ElementRef, Html, Selector};
+use serde_json::{json, Value};
+use std::{fs, path::Path};
+use url::Url;
+
+use crate::{readable::normalize, Link};
+
+/// Bookmark exported from a browser
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bookmark {
+    pub url: Url,
+    pub title: String,
+    pub desc: Option<String>,
+    /// Folders containing the bookmark, from the outermost one
+    pub folders: Vec<String>,
+}
+
+/// Parse bookmarks in Netscape HTML format,
+/// exported by all major browsers
+pub fn parse_netscape_bookmarks(content: &str) -> Vec<Bookmark> {
+    let html = Html::parse_document(content);
+    let anchors = Selector::parse("a[href]").unwrap();
+    let heading = Selector::parse("h3").unwrap();
+
+    html.select(&anchors)
+        .filter_map(|anchor| {
+            let url = Url::parse(anchor.value().attr("href")?).ok()?;
+            let title = normalize(anchor.text());
+
+            // Folder of a bookmark is a `<DL>` preceded by `<H3>` title
+            let mut folders: Vec<String> = anchor
+                .ancestors()
+                .filter_map(ElementRef::wrap)
+                .filter(|element| element.value().name() == "dl")
+                .filter_map(|list| {
+                    list.prev_siblings()
+                        .filter_map(ElementRef::wrap)
+                        .next()
+                        .filter(|sibling| heading.matches(sibling))
+                        .map(|sibling| normalize(sibling.text()))
+                })
+                .collect();
+            folders.reverse();
+
+            // Description is kept in `<DD>` right after the bookmark
+            let desc = anchor
+                .parent()
+                .and_then(ElementRef::wrap)
+                .and_then(|term| {
+                    term.next_siblings()
+                        .filter_map(ElementRef::wrap)
+                        .next()
+                })
+                .filter(|sibling| sibling.value().name() == "dd")
+                .map(|sibling| normalize(sibling.text()))
+                .filter(|desc| !desc.is_empty());
+
+            Some(Bookmark {
+                title: if title.is_empty() {
+                    url.to_string()
+                } else {
+                    title
+                },
+                url,
+                desc,
+                folders,
+            })
+        })
+        .collect()
+}
+
+/// Parse bookmarks exported by Chrome (`Bookmarks` file)
+/// or Firefox (JSON backup)
+pub fn parse_json_bookmarks(content: &str) -> Result<Vec<Bookmark>> {
+    let json: Value = serde_json::from_str(content)?;
+    let mut bookmarks = vec![];
+
+    if let Some(roots) = json.get("roots").and_then(Value::as_object) {
+        // Chrome
+        for root in roots.values() {
+            collect_json(root, &mut vec![], &mut bookmarks, true);
+        }
+    } else if json.get("type").and_then(Value::as_str)
+        == Some("text/x-moz-place-container")
+    {
+        // Firefox
+        collect_json(&json, &mut vec![], &mut bookmarks, true);
+    } else {
+        return Err(ArklibError::Parse);
+    }
+
+    Ok(bookmarks)
+}
+
+fn collect_json(
+    node: &Value,
+    folders: &mut Vec<String>,
+    bookmarks: &mut Vec<Bookmark>,
+    is_root: bool,
+) {
+    let kind = node
+        .get("type")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let title = node
+        .get("name")
+        .or(node.get("title"))
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+
+    match kind {
+        "url" | "text/x-moz-place" => {
+            let Some(url) = node
+                .get("url")
+                .or(node.get("uri"))
+                .and_then(Value::as_str)
+                .and_then(|url| Url::parse(url).ok())
+            else {
+                return;
+            };
+            // Firefox keeps bookmarklets and smart queries as "place:" urls
+            if url.scheme() == "place" {
+                return;
+            }
+            // Written by `export_json_bookmarks`, Firefox uses annotations
+            let desc = node
+                .get("description")
+                .or_else(|| {
+                    node.get("annos")?
+                        .as_array()?
+                        .iter()
+                        .find(|anno| {
+                            anno.get("name").and_then(Value::as_str)
+                                == Some("bookmarkProperties/description")
+                        })?
+                        .get("value")
+                })
+                .and_then(Value::as_str)
+                .map(|desc| desc.to_string());
+            bookmarks.push(Bookmark {
+                title: if title.is_empty() {
+                    url.to_string()
+                } else {
+                    title
+                },
+                url,
+                desc,
+                folders: folders.clone(),
+            });
+        }
+        _ => {
+            let Some(children) = node.get("children").and_then(Value::as_array)
+            else {
+                return;
+            };
+            // Names of the top-level containers like "Bookmarks bar"
+            // or "Other bookmarks" are not meaningful as tags
+            let is_folder =
+                !is_root && node.get("root").is_none() && !title.is_empty();
+            if is_folder {
+                folders.push(title);
+            }
+            for child in children {
+                collect_json(child, folders, bookmarks, false);
+            }
+            if is_folder {
+                folders.pop();
+            }
+        }
+    }
+}
+
+/// Create links for the bookmarks in the root, mapping their folders
+/// to tags. Links are saved offline, their previews are scheduled
+/// to be fetched later with [`Link::fetch_pending`].
+///
+/// Returns ids of the created links.
+pub fn import_bookmarks<Id: ResourceId, P: AsRef<Path>>(
+    root: P,
+    bookmarks: &[Bookmark],
+    with_preview: bool,
+) -> Result<Vec<Id>> {
+    let mut ids = Vec::with_capacity(bookmarks.len());
+    let mut archive = UserDataArchive::default();
+
+    for bookmark in bookmarks {
+        let link: Link<Id> = Link::new(
+            bookmark.url.clone(),
+            bookmark.title.clone(),
+            bookmark.desc.clone(),
+        );
+        link.save_offline(&root, with_preview)?;
+
+        let id = link.id()?;
+        if !bookmark.folders.is_empty() {
+            archive
+                .tags
+                .entry(id.to_string())
+                .or_default()
+                // Tags are stored comma-separated
+                .extend(
+                    bookmark
+                        .folders
+                        .iter()
+                        .map(|f| f.replace(',', " ")),
+                );
+        }
+        ids.push(id);
+    }
+
+    import_user_data(&root, &archive)?;
+    Ok(ids)
+}
+
+/// Import bookmarks from a file, detecting its format by content
+pub fn import_bookmarks_file<Id, P, F>(
+    root: P,
+    file: F,
+    with_preview: bool,
+) -> Result<Vec<Id>>
+where
+    Id: ResourceId,
+    P: AsRef<Path>,
+    F: AsRef<Path>,
+{
+    let content = fs::read_to_string(file)?;
+    let bookmarks = if content.trim_start().starts_with('{') {
+        parse_json_bookmarks(&content)?
+    } else {
+        parse_netscape_bookmarks(&content)
+    };
+    import_bookmarks(root, &bookmarks, with_preview)
+}
+
+/// Load all links stored under the root, skipping the `.ark` folder
+pub fn load_links<Id: ResourceId, P: AsRef<Path>>(
+    root: P,
+) -> Result<Vec<Link<Id>>> {
+    let mut links = vec![];
+    collect_links(root.as_ref(), root.as_ref(), &mut links)?;
+    Ok(links)
+}
+
+fn collect_links<Id: ResourceId>(
+    root: &Path,
+    dir: &Path,
+    links: &mut Vec<Link<Id>>,
+) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            if entry.file_name() != ARK_FOLDER {
+                collect_links(root, &path, links)?;
+            }
+            continue;
+        }
+        if !file_type.is_file() || entry.metadata()?.len() > MAX_LINK_SIZE {
+            continue;
+        }
+
+        // Link files are named after the id of their url
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let Ok(url) = Url::parse(content.trim()) else {
+            continue;
+        };
+        let id = Id::from_bytes(url.as_str().as_bytes())?;
+        if entry.file_name().to_string_lossy() != id.to_string() {
+            continue;
+        }
+        match Link::load(root, path.as_path()) {
+            Ok(link) => links.push(link),
+            Err(e) => log::debug!("Skipping link {}: {e}", path.display()),
+        }
+    }
+    Ok(())
+}
+
+/// Files larger than this can't be links
+const MAX_LINK_SIZE: u64 = 8 * 1024;
+
+/// Render links as Netscape bookmark HTML,
+/// which can be imported by all major browsers
+pub fn export_netscape_bookmarks<Id: ResourceId>(links: &[Link<Id>]) -> String {
+    let mut html = String::from(
+        "<!DOCTYPE NETSCAPE-Bookmark-file-1>\n\
+         <META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">\n\
+         <TITLE>Bookmarks</TITLE>\n\
+         <H1>Bookmarks</H1>\n\
+         <DL><p>\n",
+    );
+    for link in links {
+        html.push_str(&format!(
+            "    <DT><A HREF=\"{}\">{}</A>\n",
+            escape(link.url.as_str()),
+            escape(&link.prop.title)
+        ));
+        if let Some(desc) = &link.prop.desc {
+            html.push_str(&format!("    <DD>{}\n", escape(desc)));
+        }
+    }
+    html.push_str("</DL><p>\n");
+    html
+}
+
+/// Render links in the JSON format of Chrome bookmarks
+pub fn export_json_bookmarks<Id: ResourceId>(
+    links: &[Link<Id>],
+) -> Result<String> {
+    let children: Vec<Value> = links
+        .iter()
+        .map(|link| {
+            let mut bookmark = json!({
+                "type": "url",
+                "name": link.prop.title,
+                "url": link.url.as_str(),
+            });
+            if let Some(desc) = &link.prop.desc {
+                bookmark["description"] = json!(desc);
+            }
+            bookmark
+        })
+        .collect();
+    let bookmarks = json!({
+        "roots": {
+            "bookmark_bar": {
+                "type": "folder",
+                "name": "Bookmarks bar",
+                "children": children,
+            }
+        },
+        "version": 1,
+    });
+    Ok(serde_json::to_string_pretty(&bookmarks)?)
+}
+
+/// Export all links under the root into a file. JSON is written
+/// for `.json` files, Netscape HTML otherwise.
+///
+/// Returns the number of exported links.
+pub fn export_bookmarks_file<Id, P, F>(root: P, file: F) -> Result<usize>
+where
+    Id: ResourceId,
+    P: AsRef<Path>,
+    F: AsRef<Path>,
+{
+    let links: Vec<Link<Id>> = load_links(root)?;
+    let is_json = file
+        .as_ref()
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+    let content = if is_json {
+        export_json_bookmarks(&links)?
+    } else {
+        export_netscape_bookmarks(&links)
+    };
+    fs::write(file, content)?;
+    Ok(links.len())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_netscape() {
+        let content = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=UTF-8">
+<TITLE>Bookmarks</TITLE>
+<H1>Bookmarks</H1>
+<DL><p>
+    <DT><H3>Work</H3>
+    <DL><p>
+        <DT><H3>Rust</H3>
+        <DL><p>
+            <DT><A HREF="https://www.rust-lang.org/" ADD_DATE="1">Rust</A>
+            <DD>The language
+        </DL><p>
+    </DL><p>
+    <DT><A HREF="https://example.com/">Example</A>
+</DL><p>"#;
+
+        let bookmarks = parse_netscape_bookmarks(content);
+        assert_eq!(bookmarks.len(), 2);
+        assert_eq!(bookmarks[0].url.as_str(), "https://www.rust-lang.org/");
+        assert_eq!(bookmarks[0].title, "Rust");
+        assert_eq!(bookmarks[0].desc.as_deref(), Some("The language"));
+        assert_eq!(bookmarks[0].folders, ["Work", "Rust"]);
+        assert_eq!(bookmarks[1].title, "Example");
+        assert!(bookmarks[1].folders.is_empty());
+    }
+
+    #[test]
+    fn test_parse_chrome() {
+        let content = r#"{"roots": {
+            "bookmark_bar": {"type": "folder", "name": "Bookmarks bar",
+                "children": [
+                    {"type": "folder", "name": "News", "children": [
+                        {"type": "url", "name": "Site", "url": "https://news.example/"}
+                    ]},
+                    {"type": "url", "name": "", "url": "https://example.com/"}
+                ]}
+        }}"#;
+
+        let bookmarks = parse_json_bookmarks(content).unwrap();
+        assert_eq!(bookmarks.len(), 2);
+        assert_eq!(bookmarks[0].folders, ["News"]);
+        assert_eq!(bookmarks[1].title, "https://example.com/");
+        assert!(bookmarks[1].folders.is_empty());
+    }
+
+    #[test]
+    fn test_parse_firefox() {
+        let content = r#"{"type": "text/x-moz-place-container", "root": "placesRoot",
+            "children": [
+                {"type": "text/x-moz-place-container", "title": "menu",
+                 "root": "bookmarksMenuFolder", "children": [
+                    {"type": "text/x-moz-place-container", "title": "Docs",
+                     "children": [
+                        {"type": "text/x-moz-place", "title": "MDN",
+                         "uri": "https://developer.mozilla.org/"},
+                        {"type": "text/x-moz-place", "title": "Recent",
+                         "uri": "place:sort=8"}
+                     ]}
+                 ]}
+            ]}"#;
+
+        let bookmarks = parse_json_bookmarks(content).unwrap();
+        assert_eq!(bookmarks.len(), 1);
+        assert_eq!(bookmarks[0].title, "MDN");
+        assert_eq!(bookmarks[0].folders, ["Docs"]);
+    }
+
+    #[test]
+    fn test_export_roundtrip() {
+        fs_atomic_versions::initialize();
+
+        use dev_hash::Crc32;
+        use tempdir::TempDir;
+
+        let dir = TempDir::new("arklib_test").unwrap();
+        let root = dir.path();
+
+        let link: Link<Crc32> = Link::new(
+            Url::parse("https://example.com/?a=1&b=2").unwrap(),
+            String::from("Title <with> \"quotes\""),
+            Some(String::from("Description")),
+        );
+        link.save_offline(root, false).unwrap();
+        std::fs::create_dir(root.join("nested")).unwrap();
+        std::fs::write(root.join("nested").join("notes.txt"), "text").unwrap();
+
+        let links: Vec<Link<Crc32>> = load_links(root).unwrap();
+        assert_eq!(links.len(), 1);
+
+        let html = export_netscape_bookmarks(&links);
+        let bookmarks = parse_netscape_bookmarks(&html);
+        assert_eq!(bookmarks.len(), 1);
+        assert_eq!(bookmarks[0].url, link.url);
+        assert_eq!(bookmarks[0].title, link.prop.title);
+        assert_eq!(bookmarks[0].desc, link.prop.desc);
+
+        let json = export_json_bookmarks(&links).unwrap();
+        let bookmarks = parse_json_bookmarks(&json).unwrap();
+        assert_eq!(bookmarks.len(), 1);
+        assert_eq!(bookmarks[0].url, link.url);
+        assert_eq!(bookmarks[0].title, link.prop.title);
+    }
+}
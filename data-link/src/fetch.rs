@@ -0,0 +1,317 @@
+use data_error::ArklibError;
+use reqwest::{
+    header::{
+        HeaderMap, HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH,
+        LAST_MODIFIED,
+    },
+    redirect::Policy,
+    Client, Proxy, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use std::{io::Write, net::IpAddr, time::Duration};
+use thiserror::Error;
+use tokio::time::timeout;
+use url::Url;
+
+use crate::SnapshotConfig;
+
+const USER_AGENT: &str =
+    "Mozilla/5.0 (X11; Linux x86_64; rv:102.0) Gecko/20100101 Firefox/102.0";
+
+/// Network settings used while fetching previews of links
+#[derive(Debug, Clone)]
+pub struct LinkFetchConfig {
+    /// Maximum time to establish a connection with the host
+    pub connect_timeout: Duration,
+    /// Maximum time to wait for the response headers
+    /// or for the next chunk of the body
+    pub read_timeout: Duration,
+    /// Number of additional attempts after a failed request
+    pub retries: u32,
+    /// Delay before the first retry, doubled on every next attempt
+    pub backoff: Duration,
+    /// Maximum number of bytes accepted from the host
+    pub max_body_size: usize,
+    /// Extract the main text of the page into the metadata
+    pub extract_text: bool,
+    /// Maximum number of redirects to follow
+    pub max_redirects: usize,
+    /// Archive a self-contained copy of the page, disabled by default
+    pub snapshot: Option<SnapshotConfig>,
+    /// HTTP or SOCKS5 proxy for all requests,
+    /// e.g. `socks5://127.0.0.1:1080`
+    pub proxy: Option<String>,
+    /// Local address to send requests from,
+    /// selecting the network interface
+    pub local_address: Option<IpAddr>,
+}
+
+impl Default for LinkFetchConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(10),
+            read_timeout: Duration::from_secs(30),
+            retries: 2,
+            backoff: Duration::from_millis(500),
+            max_body_size: 10 * 1024 * 1024,
+            extract_text: false,
+            max_redirects: 10,
+            snapshot: None,
+            proxy: None,
+            local_address: None,
+        }
+    }
+}
+
+/// Validators of a previously fetched response, sent back to the host
+/// to skip downloading content which hasn't changed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Validators {
+    /// Value of the `ETag` header
+    pub etag: Option<String>,
+    /// Value of the `Last-Modified` header
+    pub last_modified: Option<String>,
+}
+
+/// Errors which may happen while downloading link data
+#[derive(Error, Debug)]
+pub enum FetchError {
+    #[error("Request failed: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("Host responded with status {0}")]
+    Status(u16),
+    #[error("Host didn't respond in time")]
+    Timeout,
+    #[error("Body is larger than {0} bytes")]
+    TooLarge(usize),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("There is nothing to fetch")]
+    Missing,
+}
+
+impl From<FetchError> for ArklibError {
+    fn from(error: FetchError) -> Self {
+        match error {
+            FetchError::Io(e) => Self::Io(e),
+            _ => Self::Network,
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, FetchError>;
+
+/// Outcome of a conditional request, `B` is either the body itself
+/// or the number of bytes streamed into a writer
+pub(crate) enum Fetched<B = Vec<u8>> {
+    Modified {
+        url: Url,
+        body: B,
+        validators: Option<Validators>,
+    },
+    NotModified,
+}
+
+impl LinkFetchConfig {
+    pub(crate) fn client(&self) -> Result<Client> {
+        let mut header = HeaderMap::new();
+        header.insert("User-Agent", HeaderValue::from_static(USER_AGENT));
+        let mut builder = Client::builder()
+            .default_headers(header)
+            .connect_timeout(self.connect_timeout)
+            .redirect(Policy::limited(self.max_redirects))
+            .local_address(self.local_address);
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(Proxy::all(proxy)?);
+        }
+        Ok(builder.build()?)
+    }
+
+    /// Download the body of `url`, retrying with exponential backoff
+    pub(crate) async fn fetch(
+        &self,
+        client: &Client,
+        url: &str,
+    ) -> Result<Vec<u8>> {
+        let (_, body) = self.fetch_page(client, url).await?;
+        Ok(body)
+    }
+
+    /// Same as `fetch`, but also returns the final url after redirects
+    pub(crate) async fn fetch_page(
+        &self,
+        client: &Client,
+        url: &str,
+    ) -> Result<(Url, Vec<u8>)> {
+        match self.fetch_conditional(client, url, None).await? {
+            Fetched::Modified { url, body, .. } => Ok((url, body)),
+            // Can't happen without validators
+            Fetched::NotModified => Err(FetchError::Status(304)),
+        }
+    }
+
+    /// Download the body of `url` unless it didn't change since
+    /// the response described by `validators`
+    pub(crate) async fn fetch_conditional(
+        &self,
+        client: &Client,
+        url: &str,
+        validators: Option<&Validators>,
+    ) -> Result<Fetched> {
+        self.retry(url, || async move {
+            match self.send(client, url, validators).await? {
+                Some(response) => {
+                    let url = response.url().clone();
+                    let validators = validators_of(&response);
+                    let mut body = Vec::new();
+                    self.stream_body(response, &mut body).await?;
+                    Ok(Fetched::Modified {
+                        url,
+                        body,
+                        validators,
+                    })
+                }
+                None => Ok(Fetched::NotModified),
+            }
+        })
+        .await
+    }
+
+    /// Same as `fetch_conditional`, but writes the body into `writer`
+    /// chunk by chunk instead of buffering it in memory.
+    ///
+    /// Only sending of the request is retried, since the writer
+    /// may already contain a part of the body.
+    pub(crate) async fn stream_conditional<W: Write>(
+        &self,
+        client: &Client,
+        url: &str,
+        validators: Option<&Validators>,
+        writer: &mut W,
+    ) -> Result<Fetched<u64>> {
+        let response = self
+            .retry(url, || self.send(client, url, validators))
+            .await?;
+        match response {
+            Some(response) => {
+                let url = response.url().clone();
+                let validators = validators_of(&response);
+                let written = self.stream_body(response, writer).await?;
+                Ok(Fetched::Modified {
+                    url,
+                    body: written,
+                    validators,
+                })
+            }
+            None => Ok(Fetched::NotModified),
+        }
+    }
+
+    async fn retry<T, F, Fut>(&self, url: &str, mut operation: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match operation().await {
+                Ok(result) => return Ok(result),
+                Err(e) if attempt < self.retries && is_transient(&e) => {
+                    let delay = self.backoff * 2u32.pow(attempt);
+                    log::debug!(
+                        "Fetching {url} failed: {e}, retrying in {delay:?}"
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Send the request, returning `None` if the host reports
+    /// that the content wasn't modified
+    async fn send(
+        &self,
+        client: &Client,
+        url: &str,
+        validators: Option<&Validators>,
+    ) -> Result<Option<reqwest::Response>> {
+        let mut request = client.get(url);
+        if let Some(validators) = validators {
+            if let Some(etag) = &validators.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &validators.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let response = timeout(self.read_timeout, request.send())
+            .await
+            .map_err(|_| FetchError::Timeout)??;
+        let status = response.status();
+        if status == StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+        if status.is_client_error() || status.is_server_error() {
+            return Err(FetchError::Status(status.as_u16()));
+        }
+        Ok(Some(response))
+    }
+
+    /// Write the body chunk by chunk, refusing to go over `max_body_size`
+    pub(crate) async fn stream_body<W: Write>(
+        &self,
+        mut response: reqwest::Response,
+        writer: &mut W,
+    ) -> Result<u64> {
+        if let Some(length) = response.content_length() {
+            if length > self.max_body_size as u64 {
+                return Err(FetchError::TooLarge(self.max_body_size));
+            }
+        }
+
+        let mut written = 0;
+        while let Some(chunk) = timeout(self.read_timeout, response.chunk())
+            .await
+            .map_err(|_| FetchError::Timeout)??
+        {
+            if written + chunk.len() > self.max_body_size {
+                return Err(FetchError::TooLarge(self.max_body_size));
+            }
+            writer.write_all(&chunk)?;
+            written += chunk.len();
+        }
+        writer.flush()?;
+        Ok(written as u64)
+    }
+}
+
+fn validators_of(response: &reqwest::Response) -> Option<Validators> {
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value: &HeaderValue| value.to_str().ok())
+            .map(|value| value.to_string())
+    };
+    let validators = Validators {
+        etag: header(ETAG),
+        last_modified: header(LAST_MODIFIED),
+    };
+    if validators == Validators::default() {
+        None
+    } else {
+        Some(validators)
+    }
+}
+
+/// Client errors and oversized bodies won't be fixed by retrying
+fn is_transient(error: &FetchError) -> bool {
+    match error {
+        FetchError::Status(status) => *status >= 500 || *status == 429,
+        FetchError::TooLarge(_) | FetchError::Missing => false,
+        _ => true,
+    }
+}
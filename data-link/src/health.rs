@@ -0,0 +1,151 @@
+use data_error::Result;
+use data_resource::ResourceId;
+use fs_metadata::{load_raw_metadata, store_metadata};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::time::timeout;
+
+use crate::{Link, LinkFetchConfig};
+
+/// Result of the last availability check of a link
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkHealth {
+    /// HTTP status returned by the host, `None` if it wasn't reachable
+    pub status: Option<u16>,
+    /// Seconds since UNIX epoch
+    pub checked_at: u64,
+    /// Description of the network failure, if any
+    pub error: Option<String>,
+}
+
+impl LinkHealth {
+    pub fn is_alive(&self) -> bool {
+        matches!(self.status, Some(status) if status < 400)
+    }
+}
+
+/// Outcome of [`Link::check_all`]
+#[derive(Debug, Clone)]
+pub struct LinkCheckReport<Id: ResourceId> {
+    /// Number of checked links
+    pub checked: usize,
+    /// Links which are unreachable or respond with an error status
+    pub dead: Vec<(Id, LinkHealth)>,
+}
+
+impl<Id: ResourceId> Link<Id> {
+    /// Check if the link is still available and record the result
+    /// in the metadata of the link under the `health` key.
+    ///
+    /// HEAD request is tried first, GET is used if the host doesn't
+    /// support HEAD.
+    pub async fn check<P: AsRef<Path>>(
+        &self,
+        root: P,
+        config: &LinkFetchConfig,
+    ) -> Result<LinkHealth> {
+        let health = match self.request_status(config).await {
+            Ok(status) => LinkHealth {
+                status: Some(status.as_u16()),
+                checked_at: now(),
+                error: None,
+            },
+            Err(e) => LinkHealth {
+                status: None,
+                checked_at: now(),
+                error: Some(e.to_string()),
+            },
+        };
+
+        let id = self.id()?;
+        let mut metadata: Value = load_raw_metadata(&root, id.clone())
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_else(|| Value::Object(Default::default()));
+        if let Value::Object(map) = &mut metadata {
+            map.insert("health".to_string(), serde_json::to_value(&health)?);
+        }
+        store_metadata(&root, id, &metadata)?;
+
+        Ok(health)
+    }
+
+    /// Check all `links` one by one and report the dead ones
+    pub async fn check_all<'a, P, I>(
+        root: P,
+        links: I,
+        config: &LinkFetchConfig,
+    ) -> Result<LinkCheckReport<Id>>
+    where
+        P: AsRef<Path>,
+        I: IntoIterator<Item = &'a Link<Id>>,
+        Id: 'a,
+    {
+        let mut report = LinkCheckReport {
+            checked: 0,
+            dead: vec![],
+        };
+        for link in links {
+            let health = link.check(&root, config).await?;
+            report.checked += 1;
+            if !health.is_alive() {
+                report.dead.push((link.id()?, health));
+            }
+        }
+        Ok(report)
+    }
+
+    async fn request_status(
+        &self,
+        config: &LinkFetchConfig,
+    ) -> Result<StatusCode> {
+        let client = config.client()?;
+        let url = self.url.as_str();
+
+        let head = timeout(config.read_timeout, client.head(url).send())
+            .await
+            .map_err(|_| data_error::ArklibError::Network)??;
+        let status = head.status();
+        if status != StatusCode::METHOD_NOT_ALLOWED
+            && status != StatusCode::NOT_IMPLEMENTED
+        {
+            return Ok(status);
+        }
+
+        // Only headers are needed, the body is dropped unread
+        let get = timeout(config.read_timeout, client.get(url).send())
+            .await
+            .map_err(|_| data_error::ArklibError::Network)??;
+        Ok(get.status())
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_alive() {
+        let health = |status| LinkHealth {
+            status,
+            checked_at: 0,
+            error: None,
+        };
+        assert!(health(Some(200)).is_alive());
+        assert!(health(Some(301)).is_alive());
+        assert!(!health(Some(404)).is_alive());
+        assert!(!health(None).is_alive());
+    }
+}
@@ -1,12 +1,14 @@
-use data_error::Result;
+use data_error::{ArklibError, Result};
 use data_resource::ResourceId;
 use fs_atomic_versions::atomic::AtomicFile;
-use fs_metadata::store_metadata;
+use fs_metadata::{load_raw_metadata, store_metadata};
 use fs_properties::{
     load_raw_properties, store_properties, PROPERTIES_STORAGE_FOLDER,
 };
-use fs_storage::{ARK_FOLDER, PREVIEWS_STORAGE_FOLDER};
-use reqwest::header::HeaderValue;
+use fs_storage::{
+    ARK_FOLDER, FAVICONS_STORAGE_FOLDER, PREVIEWS_STORAGE_FOLDER,
+    SNAPSHOTS_STORAGE_FOLDER,
+};
 use scraper::{Html, Selector};
 use serde::{Deserialize, Serialize};
 use std::{
@@ -18,6 +20,45 @@ use std::{
 };
 use url::Url;
 
+use fetch::{Fetched, Result as FetchResult};
+use oembed::{oembed_endpoint, resolve_oembed};
+use snapshot::{build_snapshot, prune_snapshots};
+use thumbnail::{generate_thumbnail, thumbnails_folder};
+
+mod bookmarks;
+mod fetch;
+mod health;
+mod oembed;
+mod readable;
+mod snapshot;
+mod thumbnail;
+
+pub use bookmarks::{
+    export_bookmarks_file, export_json_bookmarks, export_netscape_bookmarks,
+    import_bookmarks, import_bookmarks_file, load_links, parse_json_bookmarks,
+    parse_netscape_bookmarks, Bookmark,
+};
+pub use fetch::{FetchError, LinkFetchConfig, Validators};
+pub use health::{LinkCheckReport, LinkHealth};
+pub use oembed::OEmbed;
+pub use readable::extract_readable_text;
+pub use snapshot::SnapshotConfig;
+pub use thumbnail::THUMBNAIL_SIZE;
+
+/// Links saved without generated data, waiting to be fetched
+pub const PENDING_PREVIEWS_FOLDER: &str = "cache/pending";
+
+/// Outcome of [`Link::fetch_pending`]
+#[derive(Debug, Clone)]
+pub struct PendingFetchReport<Id: ResourceId> {
+    /// Links whose generated data was fetched and stored
+    pub fetched: Vec<Id>,
+    /// Links which failed and stay scheduled
+    pub failed: Vec<Id>,
+    /// Number of scheduled links which don't exist anymore
+    pub removed: usize,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Link<Id: ResourceId> {
     pub url: Url,
@@ -30,13 +71,30 @@ pub struct Link<Id: ResourceId> {
 pub struct Properties {
     pub title: String,
     pub desc: Option<String>,
+    /// Author, duration and embed code reported by the oEmbed provider
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embed: Option<OEmbed>,
+    /// Url the link was created with, if it was replaced
+    /// by the canonical one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_url: Option<Url>,
+    /// Url of the page after redirects, as declared
+    /// by `rel="canonical"` or "og:url"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canonical_url: Option<Url>,
 }
 
 impl<Id: ResourceId> Link<Id> {
     pub fn new(url: Url, title: String, desc: Option<String>) -> Self {
         Self {
             url,
-            prop: Properties { title, desc },
+            prop: Properties {
+                title,
+                desc,
+                embed: None,
+                original_url: None,
+                canonical_url: None,
+            },
             _marker: PhantomData,
         }
     }
@@ -80,49 +138,407 @@ impl<Id: ResourceId> Link<Id> {
             prop: Properties {
                 title: user_prop.title,
                 desc: description,
+                embed: user_prop.embed,
+                original_url: user_prop.original_url,
+                canonical_url: user_prop.canonical_url,
             },
             _marker: PhantomData,
         })
     }
 
+    /// Persist the link, its user defined properties and the generated data
+    ///
+    /// The link is saved under the id of its url as is: call
+    /// [`Link::resolve`] first so that shortened links and links to the same
+    /// page get the id of the canonical url, and are deduplicated.
     pub async fn save<P: AsRef<Path>>(
         &self,
         root: P,
         with_preview: bool,
+    ) -> Result<()> {
+        self.save_with_config(root, with_preview, &LinkFetchConfig::default())
+            .await
+    }
+
+    /// Same as [`Link::save`], but fetches generated data
+    /// using custom network settings.
+    pub async fn save_with_config<P: AsRef<Path>>(
+        &self,
+        root: P,
+        with_preview: bool,
+        config: &LinkFetchConfig,
+    ) -> Result<()> {
+        let id = self.id()?;
+        self.save_user_data(&root, &id)?;
+
+        // Generated data, only failures of the network are worth retrying
+        match self
+            .save_generated_data(&root, &id, with_preview, config)
+            .await
+        {
+            Err(ArklibError::Network) => {
+                log::debug!("Preview of {id} is not available, fetching later");
+                schedule_preview(&root, &id, with_preview)
+            }
+            result => result,
+        }
+    }
+
+    /// Persist the link and user defined properties without touching
+    /// the network. Fetching of the generated data is scheduled and can be
+    /// performed later with [`Link::fetch_pending`].
+    pub fn save_offline<P: AsRef<Path>>(
+        &self,
+        root: P,
+        with_preview: bool,
+    ) -> Result<()> {
+        let id = self.id()?;
+        self.save_user_data(&root, &id)?;
+        schedule_preview(&root, &id, with_preview)
+    }
+
+    /// Persist a link received from another device, with the preview image
+    /// it was sent with. Like [`Link::save_offline`], the network isn't
+    /// touched: the rest of the generated data, and the preview if none was
+    /// sent, is fetched later by [`Link::fetch_pending`].
+    pub fn save_received<P: AsRef<Path>>(
+        &self,
+        root: P,
+        preview: Option<&[u8]>,
+    ) -> Result<()> {
+        let id = self.id()?;
+        self.save_user_data(&root, &id)?;
+        if let Some(preview) = preview {
+            self.save_generated_file(
+                &root,
+                PREVIEWS_STORAGE_FOLDER,
+                preview.to_vec(),
+                &id,
+            )?;
+            if let Err(e) = self.save_thumbnail(&root, &id) {
+                log::debug!("Thumbnail of {} is not available: {e}", self.url);
+            }
+        }
+        schedule_preview(&root, &id, preview.is_none())
+    }
+
+    /// Load the stored preview image of a link, if there is one
+    pub fn load_preview<P: AsRef<Path>>(
+        root: P,
+        id: &Id,
+    ) -> Result<Option<Vec<u8>>> {
+        let path = root
+            .as_ref()
+            .join(ARK_FOLDER)
+            .join(PREVIEWS_STORAGE_FOLDER)
+            .join(id.to_string());
+        if !path.exists() {
+            return Ok(None);
+        }
+        let current = AtomicFile::new(path)?.load()?;
+        if current.version == 0 {
+            return Ok(None);
+        }
+        Ok(Some(current.read_content()?))
+    }
+
+    /// Fetch generated data of the links saved offline.
+    ///
+    /// Links which fail stay scheduled, scheduled links which were
+    /// deleted in the meantime are dropped.
+    pub async fn fetch_pending<P: AsRef<Path>>(
+        root: P,
+        config: &LinkFetchConfig,
+    ) -> Result<PendingFetchReport<Id>> {
+        let mut report = PendingFetchReport {
+            fetched: vec![],
+            failed: vec![],
+            removed: 0,
+        };
+        let pending = root
+            .as_ref()
+            .join(ARK_FOLDER)
+            .join(PENDING_PREVIEWS_FOLDER);
+        if !pending.exists() {
+            return Ok(report);
+        }
+
+        for entry in std::fs::read_dir(&pending)?.flatten() {
+            let filename = entry.file_name().to_string_lossy().to_string();
+            let Ok(id) = Id::from_str(&filename) else {
+                continue;
+            };
+            let with_preview = match std::fs::read_to_string(entry.path()) {
+                Ok(content) => content.trim().parse::<bool>().unwrap_or(false),
+                Err(e) => {
+                    log::warn!("Failed to read scheduled {filename}: {e}");
+                    report.failed.push(id);
+                    continue;
+                }
+            };
+
+            let path = root.as_ref().join(&filename);
+            if !path.exists() {
+                log::debug!("Link {filename} was deleted, unscheduling it");
+                match std::fs::remove_file(entry.path()) {
+                    Ok(()) => report.removed += 1,
+                    Err(e) => {
+                        log::warn!("Failed to unschedule {filename}: {e}");
+                        report.failed.push(id);
+                    }
+                }
+                continue;
+            }
+            let url = match Self::load_url(path) {
+                Ok(url) => url,
+                Err(e) => {
+                    log::warn!("Failed to load link {filename}: {e}");
+                    report.failed.push(id);
+                    continue;
+                }
+            };
+            let link = Self {
+                url,
+                prop: Properties {
+                    title: String::new(),
+                    desc: None,
+                    embed: None,
+                    original_url: None,
+                    canonical_url: None,
+                },
+                _marker: PhantomData,
+            };
+            match link
+                .save_generated_data(&root, &id, with_preview, config)
+                .await
+            {
+                Ok(()) => {
+                    if let Err(e) = std::fs::remove_file(entry.path()) {
+                        log::warn!("Failed to unschedule {filename}: {e}");
+                    }
+                    report.fetched.push(id);
+                }
+                Err(e) => {
+                    log::debug!("Failed to fetch {filename}: {e}");
+                    report.failed.push(id);
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Fetch generated data of an already saved link again.
+    ///
+    /// Validators of the previous responses are sent to the host,
+    /// so unchanged pages, images and favicons are not downloaded again.
+    pub async fn refresh<P: AsRef<Path>>(
+        &self,
+        root: P,
+        with_preview: bool,
+        config: &LinkFetchConfig,
     ) -> Result<()> {
         let id = self.id()?;
-        let id_string = id.to_string();
+        self.save_generated_data(&root, &id, with_preview, config)
+            .await
+    }
 
+    fn save_user_data<P: AsRef<Path>>(&self, root: P, id: &Id) -> Result<()> {
         // Resources are stored in the folder chosen by user
         let bytes = self.url.as_str().as_bytes();
-        fs_atomic_light::temp_and_move(bytes, root.as_ref(), &id_string)?;
+        fs_atomic_light::temp_and_move(bytes, root.as_ref(), &id.to_string())?;
         //User defined properties
-        store_properties(&root, id.clone(), &self.prop)?;
+        store_properties(&root, id.clone(), &self.prop)
+    }
+
+    async fn save_generated_data<P: AsRef<Path>>(
+        &self,
+        root: P,
+        id: &Id,
+        with_preview: bool,
+        config: &LinkFetchConfig,
+    ) -> Result<()> {
+        let previous: Option<OpenGraph> = load_raw_metadata(&root, id.clone())
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok());
+        let page_validators = previous
+            .as_ref()
+            .and_then(|graph| graph.validators.as_ref());
+
+        let (mut graph, page) = match self
+            .fetch_preview_conditional(config, page_validators)
+            .await?
+        {
+            Some((graph, page_url, page)) => (graph, Some((page_url, page))),
+            None => {
+                log::debug!("{} wasn't modified since last fetch", self.url);
+                // Validators are only sent if there is previous metadata
+                (previous.clone().ok_or(ArklibError::Network)?, None)
+            }
+        };
+        log::debug!("Trying to save: {with_preview} with {graph:?}");
+
+        if let (Some(snapshot), Some((page_url, page))) =
+            (&config.snapshot, &page)
+        {
+            let page = String::from_utf8_lossy(page);
+            // Archiving is best effort, the rest of the data is still saved
+            match build_snapshot(&page, page_url, config, snapshot).await {
+                Ok(Some(archived)) => {
+                    self.save_generated_file(
+                        &root,
+                        SNAPSHOTS_STORAGE_FOLDER,
+                        archived.into_bytes(),
+                        id,
+                    )?;
+                    let folder = root
+                        .as_ref()
+                        .join(ARK_FOLDER)
+                        .join(SNAPSHOTS_STORAGE_FOLDER);
+                    let pruned =
+                        prune_snapshots(&folder, snapshot.max_total_size);
+                    log::debug!("pruned {pruned} old snapshots");
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    log::warn!("Snapshot of {} failed: {e}", self.url);
+                }
+            }
+        }
+
+        let mut preview = None;
+        let mut missing_thumbnail = false;
+        if with_preview && graph.image.is_some() {
+            let file = AtomicFile::new(
+                root.as_ref()
+                    .join(ARK_FOLDER)
+                    .join(PREVIEWS_STORAGE_FOLDER)
+                    .join(id.to_string()),
+            )?;
+            let has_preview = file.load()?.version > 0;
+            // Previous validators are only valid for the same image
+            let image_validators = previous
+                .as_ref()
+                .filter(|previous| has_preview && previous.image == graph.image)
+                .and_then(|previous| previous.image_validators.clone());
+
+            let tmp = file.make_temp()?;
+            let fetched = graph
+                .stream_image_conditional(
+                    config,
+                    image_validators.as_ref(),
+                    &mut &tmp,
+                )
+                .await;
+            match fetched {
+                Ok(Fetched::Modified { validators, .. }) => {
+                    graph.image_validators = validators;
+                    preview = Some((file, tmp));
+                }
+                Ok(Fetched::NotModified) => {
+                    graph.image_validators = image_validators;
+                    missing_thumbnail = !root
+                        .as_ref()
+                        .join(ARK_FOLDER)
+                        .join(thumbnails_folder())
+                        .join(id.to_string())
+                        .exists();
+                }
+                Err(e) => {
+                    log::debug!(
+                        "Preview of {} is not available: {e}",
+                        self.url
+                    );
+                    graph.image_validators = None;
+                }
+            }
+        }
 
-        // Generated data
-        if let Ok(graph) = self.get_preview().await {
-            log::debug!("Trying to save: {with_preview} with {graph:?}");
+        let mut favicon = None;
+        if with_preview && graph.favicon.is_some() {
+            let has_favicon = AtomicFile::new(
+                root.as_ref()
+                    .join(ARK_FOLDER)
+                    .join(FAVICONS_STORAGE_FOLDER)
+                    .join(id.to_string()),
+            )?
+            .load()?
+            .version
+                > 0;
+            // Previous validators are only valid for the same icon
+            let favicon_validators = previous
+                .as_ref()
+                .filter(|previous| {
+                    has_favicon && previous.favicon == graph.favicon
+                })
+                .and_then(|previous| previous.favicon_validators.clone());
 
-            store_metadata(&root, id.clone(), &graph)?;
-            if with_preview {
-                if let Some(preview_data) = graph.fetch_image().await {
-                    self.save_preview(root, preview_data, &id)?;
+            match graph
+                .fetch_favicon_conditional(config, favicon_validators.as_ref())
+                .await
+            {
+                Ok(Fetched::Modified {
+                    body, validators, ..
+                }) => {
+                    graph.favicon_validators = validators;
+                    favicon = Some(body);
+                }
+                Ok(Fetched::NotModified) => {
+                    graph.favicon_validators = favicon_validators;
                 }
+                Err(e) => {
+                    log::debug!(
+                        "Favicon of {} is not available: {e}",
+                        self.url
+                    );
+                    graph.favicon_validators = None;
+                }
+            }
+        }
+
+        store_metadata(&root, id.clone(), &graph)?;
+        if let Some(embed) = &graph.embed {
+            enrich_properties(&root, id, "embed", serde_json::json!(embed))?;
+        }
+        if let Some(canonical) = &graph.canonical {
+            enrich_properties(
+                &root,
+                id,
+                "canonical_url",
+                serde_json::json!(canonical),
+            )?;
+        }
+        if let Some((file, tmp)) = preview {
+            let current_preview = file.load()?;
+            file.compare_and_swap(&current_preview, tmp)?;
+            missing_thumbnail = true;
+        }
+        if missing_thumbnail {
+            if let Err(e) = self.save_thumbnail(&root, id) {
+                log::debug!("Thumbnail of {} is not available: {e}", self.url);
             }
         }
+        if let Some(favicon) = favicon {
+            self.save_generated_file(
+                &root,
+                FAVICONS_STORAGE_FOLDER,
+                favicon,
+                id,
+            )?;
+        }
         Ok(())
     }
 
-    fn save_preview<P: AsRef<Path>>(
+    fn save_generated_file<P: AsRef<Path>>(
         &self,
         root: P,
+        folder: &str,
         image_data: Vec<u8>,
         id: &Id,
     ) -> Result<()> {
         let path = root
             .as_ref()
             .join(ARK_FOLDER)
-            .join(PREVIEWS_STORAGE_FOLDER)
+            .join(folder)
             .join(id.to_string());
         let file = AtomicFile::new(path)?;
         let tmp = file.make_temp()?;
@@ -132,6 +548,20 @@ impl<Id: ResourceId> Link<Id> {
         Ok(())
     }
 
+    /// Generate thumbnail from the stored preview image
+    fn save_thumbnail<P: AsRef<Path>>(&self, root: P, id: &Id) -> Result<()> {
+        let preview = AtomicFile::new(
+            root.as_ref()
+                .join(ARK_FOLDER)
+                .join(PREVIEWS_STORAGE_FOLDER)
+                .join(id.to_string()),
+        )?
+        .load()?
+        .read_content()?;
+        let thumbnail = generate_thumbnail(&preview)?;
+        self.save_generated_file(root, &thumbnails_folder(), thumbnail, id)
+    }
+
     /// Get OGP metadata of the link (synced).
     pub fn get_preview_synced(&self) -> Result<OpenGraph> {
         let runtime =
@@ -141,30 +571,122 @@ impl<Id: ResourceId> Link<Id> {
 
     /// Get OGP metadata of the link.
     pub async fn get_preview(&self) -> Result<OpenGraph> {
-        let mut header = reqwest::header::HeaderMap::new();
-        header.insert(
-            "User-Agent",
-            HeaderValue::from_static(
-                "Mozilla/5.0 (X11; Linux x86_64; rv:102.0) Gecko/20100101 Firefox/102.0",
-            ),
-        );
-        let client = reqwest::Client::builder()
-            .default_headers(header)
-            .build()?;
+        self.get_preview_with_config(&LinkFetchConfig::default())
+            .await
+    }
+
+    /// Get OGP metadata of the link using custom network settings.
+    pub async fn get_preview_with_config(
+        &self,
+        config: &LinkFetchConfig,
+    ) -> Result<OpenGraph> {
+        let (graph, _, _) = self.fetch_preview(config).await?;
+        Ok(graph)
+    }
+
+    /// Fetch the page and parse its metadata, returning also the final
+    /// url and the raw body of the page
+    async fn fetch_preview(
+        &self,
+        config: &LinkFetchConfig,
+    ) -> Result<(OpenGraph, Url, Vec<u8>)> {
+        self.fetch_preview_conditional(config, None)
+            .await?
+            .ok_or(ArklibError::Network)
+    }
+
+    /// Same as `fetch_preview`, but returns `None` if the page didn't change
+    /// since the response described by `validators`
+    async fn fetch_preview_conditional(
+        &self,
+        config: &LinkFetchConfig,
+        validators: Option<&Validators>,
+    ) -> Result<Option<(OpenGraph, Url, Vec<u8>)>> {
+        let client = config.client()?;
         let url = self.url.to_string();
-        let scraper = client.get(url).send().await?.text().await?;
-        let html = Html::parse_document(scraper.as_str());
-        let title =
-            select_og(&html, OpenGraphTag::Title).or(select_title(&html));
-        Ok(OpenGraph {
+        let (final_url, body, validators) = match config
+            .fetch_conditional(&client, &url, validators)
+            .await?
+        {
+            Fetched::Modified {
+                url,
+                body,
+                validators,
+            } => (url, body, validators),
+            Fetched::NotModified => return Ok(None),
+        };
+        let (mut graph, endpoint) = {
+            let scraper = String::from_utf8_lossy(&body);
+            let html = Html::parse_document(&scraper);
+            (
+                self.parse_preview(&html, &final_url, config),
+                oembed_endpoint(&html, &self.url),
+            )
+        };
+
+        if let Some(endpoint) = endpoint {
+            match resolve_oembed(&endpoint, config).await {
+                Ok(embed) => graph.embed = Some(embed),
+                Err(e) => {
+                    log::debug!("oEmbed of {url} is not available: {e}")
+                }
+            }
+        }
+        graph.validators = validators;
+        Ok(Some((graph, final_url, body)))
+    }
+
+    fn parse_preview(
+        &self,
+        html: &Html,
+        final_url: &Url,
+        config: &LinkFetchConfig,
+    ) -> OpenGraph {
+        let title = select_og(html, OpenGraphTag::Title).or(select_title(html));
+        OpenGraph {
             title,
-            description: select_og(&html, OpenGraphTag::Description)
-                .or(select_desc(&html)),
-            url: select_og(&html, OpenGraphTag::Url),
-            image: select_og(&html, OpenGraphTag::Image),
-            object_type: select_og(&html, OpenGraphTag::Type),
-            locale: select_og(&html, OpenGraphTag::Locale),
-        })
+            description: select_og(html, OpenGraphTag::Description)
+                .or(select_desc(html)),
+            url: select_og(html, OpenGraphTag::Url),
+            image: select_og(html, OpenGraphTag::Image),
+            object_type: select_og(html, OpenGraphTag::Type),
+            locale: select_og(html, OpenGraphTag::Locale),
+            favicon: select_favicon(html, final_url),
+            canonical: Some(select_canonical(html, final_url))
+                .filter(|canonical| canonical != self.url.as_str()),
+            text: if config.extract_text {
+                extract_readable_text(html)
+            } else {
+                None
+            },
+            embed: None,
+            validators: None,
+            image_validators: None,
+            favicon_validators: None,
+        }
+    }
+
+    /// Follow redirects of the link and replace its url with the canonical
+    /// one, keeping the original in the properties.
+    ///
+    /// Call it before [`Link::save`] so that shortened links get the id
+    /// of the page they point to.
+    pub async fn resolve(&mut self, config: &LinkFetchConfig) -> Result<()> {
+        let graph = self.get_preview_with_config(config).await?;
+        self.use_canonical(graph.canonical.as_deref());
+        Ok(())
+    }
+
+    /// Replace the url with the canonical one found by [`Link::parse_preview`],
+    /// if it differs, keeping the original in the properties
+    fn use_canonical(&mut self, canonical: Option<&str>) {
+        if let Some(canonical) =
+            canonical.and_then(|canonical| Url::parse(canonical).ok())
+        {
+            let original = std::mem::replace(&mut self.url, canonical.clone());
+            self.prop.original_url.get_or_insert(original);
+            self.prop.canonical_url = Some(canonical);
+        }
     }
 
     fn load_url(path: PathBuf) -> Result<Url> {
@@ -173,6 +695,39 @@ impl<Id: ResourceId> Link<Id> {
     }
 }
 
+/// Add generated `value` to user properties unless `key` is already there
+fn enrich_properties<P: AsRef<Path>, Id: ResourceId>(
+    root: P,
+    id: &Id,
+    key: &str,
+    value: serde_json::Value,
+) -> Result<()> {
+    let current: serde_json::Value = load_raw_properties(&root, id.clone())
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default();
+    if current.get(key).is_some() {
+        return Ok(());
+    }
+    let mut update = serde_json::Map::new();
+    update.insert(key.to_string(), value);
+    store_properties(&root, id.clone(), &serde_json::Value::Object(update))
+}
+
+fn schedule_preview<P: AsRef<Path>, Id: ResourceId>(
+    root: P,
+    id: &Id,
+    with_preview: bool,
+) -> Result<()> {
+    let pending = root
+        .as_ref()
+        .join(ARK_FOLDER)
+        .join(PENDING_PREVIEWS_FOLDER);
+    std::fs::create_dir_all(&pending)?;
+    std::fs::write(pending.join(id.to_string()), with_preview.to_string())?;
+    Ok(())
+}
+
 fn select_og(html: &Html, tag: OpenGraphTag) -> Option<String> {
     let selector =
         Selector::parse(&format!("meta[property=\"og:{}\"]", tag.as_str()))
@@ -199,6 +754,30 @@ fn select_desc(html: &Html) -> Option<String> {
     None
 }
 
+fn select_favicon(html: &Html, base: &Url) -> Option<String> {
+    let selector = Selector::parse("link[rel~=\"icon\"]").unwrap();
+
+    let href = html
+        .select(&selector)
+        .find_map(|element| element.value().attr("href"))
+        .unwrap_or("/favicon.ico");
+
+    base.join(href).ok().map(|url| url.to_string())
+}
+
+/// Url declared by `rel="canonical"` or "og:url", else the url of the page
+/// after redirects
+fn select_canonical(html: &Html, final_url: &Url) -> String {
+    let selector = Selector::parse("link[rel=\"canonical\"]").unwrap();
+
+    html.select(&selector)
+        .find_map(|element| element.value().attr("href"))
+        .or(select_og(html, OpenGraphTag::Url).as_deref())
+        .and_then(|href| final_url.join(href).ok())
+        .unwrap_or_else(|| final_url.clone())
+        .to_string()
+}
+
 fn select_title(html: &Html) -> Option<String> {
     let selector = Selector::parse("title").unwrap();
     if let Some(element) = html.select(&selector).next() {
@@ -228,17 +807,93 @@ pub struct OpenGraph {
     object_type: Option<String>,
     /// Represents the "og:locale" OpenGraph meta tag
     locale: Option<String>,
+    /// Location of the site icon, taken from `<link rel="icon">`
+    /// or `/favicon.ico` of the host otherwise
+    pub favicon: Option<String>,
+    /// Main text of the page, extracted only if requested
+    /// in [`LinkFetchConfig::extract_text`]
+    pub text: Option<String>,
+    /// Response of the oEmbed provider, if the site has one
+    pub embed: Option<OEmbed>,
+    /// Canonical url of the page, if it differs from the link
+    pub canonical: Option<String>,
+    /// Validators of the page response, used to refresh it cheaply
+    pub validators: Option<Validators>,
+    /// Validators of the preview image response
+    pub image_validators: Option<Validators>,
+    /// Validators of the favicon response
+    pub favicon_validators: Option<Validators>,
 }
 
 impl OpenGraph {
-    pub async fn fetch_image(&self) -> Option<Vec<u8>> {
-        if let Some(url) = &self.image {
-            let res = reqwest::get(url).await.unwrap();
-            Some(res.bytes().await.unwrap().to_vec())
-        } else {
-            None
+    pub async fn fetch_image(&self) -> FetchResult<Vec<u8>> {
+        self.fetch_image_with_config(&LinkFetchConfig::default())
+            .await
+    }
+
+    pub async fn fetch_image_with_config(
+        &self,
+        config: &LinkFetchConfig,
+    ) -> FetchResult<Vec<u8>> {
+        let mut body = Vec::new();
+        self.fetch_image_into(config, &mut body).await?;
+        Ok(body)
+    }
+
+    /// Stream the image into `writer` without buffering it in memory,
+    /// returning the number of written bytes
+    pub async fn fetch_image_into<W: Write>(
+        &self,
+        config: &LinkFetchConfig,
+        writer: &mut W,
+    ) -> FetchResult<u64> {
+        match self
+            .stream_image_conditional(config, None, writer)
+            .await?
+        {
+            Fetched::Modified { body, .. } => Ok(body),
+            Fetched::NotModified => Err(FetchError::Status(304)),
         }
     }
+
+    pub(crate) async fn stream_image_conditional<W: Write>(
+        &self,
+        config: &LinkFetchConfig,
+        validators: Option<&Validators>,
+        writer: &mut W,
+    ) -> FetchResult<Fetched<u64>> {
+        let url = self.image.as_ref().ok_or(FetchError::Missing)?;
+        let client = config.client()?;
+        config
+            .stream_conditional(&client, url, validators, writer)
+            .await
+    }
+
+    pub async fn fetch_favicon(&self) -> FetchResult<Vec<u8>> {
+        self.fetch_favicon_with_config(&LinkFetchConfig::default())
+            .await
+    }
+
+    pub async fn fetch_favicon_with_config(
+        &self,
+        config: &LinkFetchConfig,
+    ) -> FetchResult<Vec<u8>> {
+        let url = self.favicon.as_ref().ok_or(FetchError::Missing)?;
+        let client = config.client()?;
+        config.fetch(&client, url).await
+    }
+
+    pub(crate) async fn fetch_favicon_conditional(
+        &self,
+        config: &LinkFetchConfig,
+        validators: Option<&Validators>,
+    ) -> FetchResult<Fetched> {
+        let url = self.favicon.as_ref().ok_or(FetchError::Missing)?;
+        let client = config.client()?;
+        config
+            .fetch_conditional(&client, url, validators)
+            .await
+    }
 }
 
 /// OpenGraphTag meta tags collection
@@ -336,3 +991,292 @@ async fn test_create_link_file() {
         }
     }
 }
+
+#[tokio::test]
+async fn test_save_offline() {
+    fs_atomic_versions::initialize();
+
+    use dev_hash::Crc32;
+    use tempdir::TempDir;
+
+    let dir = TempDir::new("arklib_test").unwrap();
+    let root: &Path = dir.path();
+
+    let url = Url::parse("http://localhost:1/unreachable").unwrap();
+    let link: Link<Crc32> = Link::new(url, String::from("test_title"), None);
+    link.save_offline(root, false).unwrap();
+
+    let id = link.id().unwrap();
+    assert!(root.join(id.to_string()).exists());
+    assert!(root
+        .join(ARK_FOLDER)
+        .join(PENDING_PREVIEWS_FOLDER)
+        .join(id.to_string())
+        .exists());
+
+    // The host is unreachable, so the link stays scheduled
+    let config = LinkFetchConfig {
+        retries: 0,
+        ..Default::default()
+    };
+    let report = Link::<Crc32>::fetch_pending(root, &config)
+        .await
+        .unwrap();
+    assert!(report.fetched.is_empty());
+    assert_eq!(report.failed, vec![id.clone()]);
+
+    // Links deleted in the meantime are unscheduled
+    std::fs::remove_file(root.join(id.to_string())).unwrap();
+    let report = Link::<Crc32>::fetch_pending(root, &config)
+        .await
+        .unwrap();
+    assert!(report.failed.is_empty());
+    assert_eq!(report.removed, 1);
+    assert!(!root
+        .join(ARK_FOLDER)
+        .join(PENDING_PREVIEWS_FOLDER)
+        .join(id.to_string())
+        .exists());
+}
+
+/// Answer requests on a local port with the response built by `respond`
+/// from the raw request, returns the url of `/page` on that port
+#[cfg(test)]
+async fn serve<F>(respond: F) -> Url
+where
+    F: Fn(&str) -> String + Send + 'static,
+{
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut request = [0; 4096];
+            let read = stream.read(&mut request).await.unwrap();
+            let response = respond(&String::from_utf8_lossy(&request[..read]));
+            stream
+                .write_all(response.as_bytes())
+                .await
+                .unwrap();
+        }
+    });
+    Url::parse(&format!("http://{addr}/page")).unwrap()
+}
+
+#[cfg(test)]
+fn http_response(status: &str, headers: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {status}\r\n{headers}Content-Length: {}\r\n\
+         Connection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+#[tokio::test]
+async fn test_save_fails_on_storage_error() {
+    fs_atomic_versions::initialize();
+
+    use dev_hash::Crc32;
+    use fs_metadata::METADATA_STORAGE_FOLDER;
+    use tempdir::TempDir;
+
+    let dir = TempDir::new("arklib_test").unwrap();
+    let root: &Path = dir.path();
+
+    // A file in place of the metadata folder makes storing metadata fail
+    let metadata = root
+        .join(ARK_FOLDER)
+        .join(METADATA_STORAGE_FOLDER);
+    std::fs::create_dir_all(metadata.parent().unwrap()).unwrap();
+    std::fs::write(&metadata, "").unwrap();
+
+    let url = serve(|_| {
+        http_response(
+            "200 OK",
+            "",
+            "<html><head><title>page</title></head></html>",
+        )
+    })
+    .await;
+    let link: Link<Crc32> = Link::new(url, String::from("test_title"), None);
+    let config = LinkFetchConfig {
+        retries: 0,
+        ..Default::default()
+    };
+    assert!(link
+        .save_with_config(root, false, &config)
+        .await
+        .is_err());
+
+    // Fetching again wouldn't help, so the link isn't scheduled
+    assert!(!root
+        .join(ARK_FOLDER)
+        .join(PENDING_PREVIEWS_FOLDER)
+        .join(link.id().unwrap().to_string())
+        .exists());
+}
+
+#[test]
+fn test_save_received() {
+    fs_atomic_versions::initialize();
+
+    use dev_hash::Crc32;
+    use tempdir::TempDir;
+
+    let dir = TempDir::new("arklib_test").unwrap();
+    let root: &Path = dir.path();
+
+    let url = Url::parse("http://localhost:1/received").unwrap();
+    let link: Link<Crc32> = Link::new(url, String::from("test_title"), None);
+    let id = link.id().unwrap();
+    assert!(Link::<Crc32>::load_preview(root, &id)
+        .unwrap()
+        .is_none());
+
+    link.save_received(root, Some(b"preview"))
+        .unwrap();
+
+    let loaded = Link::<Crc32>::load(root, &root.join(id.to_string())).unwrap();
+    assert_eq!(loaded.prop.title, "test_title");
+    assert_eq!(
+        Link::<Crc32>::load_preview(root, &id)
+            .unwrap()
+            .unwrap(),
+        b"preview"
+    );
+    // Only the metadata is left to fetch
+    let pending = root
+        .join(ARK_FOLDER)
+        .join(PENDING_PREVIEWS_FOLDER)
+        .join(id.to_string());
+    assert_eq!(std::fs::read_to_string(pending).unwrap(), "false");
+}
+
+#[test]
+fn test_select_favicon() {
+    let base = Url::parse("https://example.com/blog/post").unwrap();
+
+    let html = Html::parse_document(
+        r#"<html><head><link rel="shortcut icon" href="/static/icon.png"></head></html>"#,
+    );
+    assert_eq!(
+        select_favicon(&html, &base).as_deref(),
+        Some("https://example.com/static/icon.png")
+    );
+
+    let html = Html::parse_document("<html><head></head></html>");
+    assert_eq!(
+        select_favicon(&html, &base).as_deref(),
+        Some("https://example.com/favicon.ico")
+    );
+}
+
+#[test]
+fn test_select_canonical() {
+    let final_url = Url::parse("https://example.com/article?utm=1").unwrap();
+
+    let html = Html::parse_document(
+        r#"<html><head><link rel="canonical" href="/article"></head></html>"#,
+    );
+    assert_eq!(
+        select_canonical(&html, &final_url),
+        "https://example.com/article"
+    );
+
+    let html = Html::parse_document(
+        r#"<html><head><meta property="og:url" content="https://example.com/a"></head></html>"#,
+    );
+    assert_eq!(select_canonical(&html, &final_url), "https://example.com/a");
+
+    let html = Html::parse_document("<html></html>");
+    assert_eq!(select_canonical(&html, &final_url), final_url.as_str());
+}
+
+#[test]
+fn test_shortened_link_resolves_to_canonical() {
+    use dev_hash::Crc32;
+
+    let short = Url::parse("https://sho.rt/abc").unwrap();
+    let mut link: Link<Crc32> = Link::new(short.clone(), "title".into(), None);
+    let same_page: Link<Crc32> = Link::new(
+        Url::parse("https://example.com/article").unwrap(),
+        "title".into(),
+        None,
+    );
+
+    // Without a canonical tag, the page the short link redirects to is used
+    let final_url = Url::parse("https://example.com/article").unwrap();
+    let html = Html::parse_document("<html></html>");
+    let graph = link.parse_preview(&html, &final_url, &Default::default());
+    assert_eq!(graph.canonical.as_deref(), Some(final_url.as_str()));
+
+    // Saved links only get the id of the page once resolved
+    assert_ne!(link.id().unwrap(), same_page.id().unwrap());
+    link.use_canonical(graph.canonical.as_deref());
+    assert_eq!(link.id().unwrap(), same_page.id().unwrap());
+    assert_eq!(link.prop.original_url, Some(short));
+    assert_eq!(link.prop.canonical_url, Some(final_url));
+}
+
+#[tokio::test]
+async fn test_refresh_skips_unchanged_favicon() {
+    fs_atomic_versions::initialize();
+
+    use dev_hash::Crc32;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+    use tempdir::TempDir;
+
+    let dir = TempDir::new("arklib_test").unwrap();
+    let root: &Path = dir.path();
+
+    let downloads = Arc::new(AtomicUsize::new(0));
+    let counter = downloads.clone();
+    let url = serve(move |request| {
+        let page = request.starts_with("GET /page ");
+        let etag = if page { "\"page\"" } else { "\"icon\"" };
+        let request = request.to_ascii_lowercase();
+        if request.contains(&format!("if-none-match: {etag}")) {
+            return http_response("304 Not Modified", "", "");
+        }
+        let headers = format!("ETag: {etag}\r\n");
+        if page {
+            let page = r#"<html><head><link rel="icon" href="/icon.png"></head></html>"#;
+            http_response("200 OK", &headers, page)
+        } else {
+            counter.fetch_add(1, Ordering::SeqCst);
+            http_response("200 OK", &headers, "icon")
+        }
+    })
+    .await;
+
+    let link: Link<Crc32> = Link::new(url, String::from("test_title"), None);
+    let id = link.id().unwrap();
+    let config = LinkFetchConfig {
+        retries: 0,
+        ..Default::default()
+    };
+    link.save_with_config(root, true, &config)
+        .await
+        .unwrap();
+    link.refresh(root, true, &config).await.unwrap();
+
+    assert_eq!(downloads.load(Ordering::SeqCst), 1);
+    let favicon = AtomicFile::new(
+        root.join(ARK_FOLDER)
+            .join(FAVICONS_STORAGE_FOLDER)
+            .join(id.to_string()),
+    )
+    .unwrap()
+    .load()
+    .unwrap()
+    .read_content()
+    .unwrap();
+    assert_eq!(favicon, b"icon");
+}
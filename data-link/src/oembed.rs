@@ -0,0 +1,128 @@
+use data_error::Result;
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::LinkFetchConfig;
+
+/// Providers which don't advertise their oEmbed endpoint in the page,
+/// matched by the host of the link
+const PROVIDERS: [(&[&str], &str); 3] = [
+    (
+        &[
+            "youtube.com",
+            "www.youtube.com",
+            "m.youtube.com",
+            "youtu.be",
+        ],
+        "https://www.youtube.com/oembed",
+    ),
+    (
+        &["vimeo.com", "www.vimeo.com"],
+        "https://vimeo.com/api/oembed.json",
+    ),
+    (
+        &[
+            "twitter.com",
+            "www.twitter.com",
+            "x.com",
+            "mobile.twitter.com",
+        ],
+        "https://publish.twitter.com/oembed",
+    ),
+];
+
+/// Embed information returned by an oEmbed provider
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct OEmbed {
+    /// Resource type: "video", "photo", "rich" or "link"
+    #[serde(rename = "type")]
+    pub object_type: Option<String>,
+    pub title: Option<String>,
+    pub author_name: Option<String>,
+    pub author_url: Option<String>,
+    pub provider_name: Option<String>,
+    /// HTML snippet to embed the resource
+    pub html: Option<String>,
+    pub thumbnail_url: Option<String>,
+    /// Duration of the media in seconds, reported only by some providers
+    pub duration: Option<u64>,
+    pub width: Option<u64>,
+    pub height: Option<u64>,
+}
+
+/// Find the oEmbed endpoint for the link, either advertised in the page
+/// via `<link type="application/json+oembed">` or a known provider
+pub(crate) fn oembed_endpoint(html: &Html, url: &Url) -> Option<Url> {
+    let selector =
+        Selector::parse("link[type=\"application/json+oembed\"]").unwrap();
+    if let Some(href) = html
+        .select(&selector)
+        .find_map(|element| element.value().attr("href"))
+    {
+        return url.join(href).ok();
+    }
+
+    let host = url.host_str()?;
+    let (_, endpoint) = PROVIDERS
+        .iter()
+        .find(|(hosts, _)| hosts.contains(&host))?;
+    let mut endpoint = Url::parse(endpoint).ok()?;
+    endpoint
+        .query_pairs_mut()
+        .append_pair("url", url.as_str())
+        .append_pair("format", "json");
+    Some(endpoint)
+}
+
+/// Query the oEmbed endpoint of the provider
+pub(crate) async fn resolve_oembed(
+    endpoint: &Url,
+    config: &LinkFetchConfig,
+) -> Result<OEmbed> {
+    let client = config.client()?;
+    let body = config.fetch(&client, endpoint.as_str()).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_provider() {
+        let html = Html::parse_document("<html></html>");
+        let url = Url::parse("https://youtu.be/dQw4w9WgXcQ").unwrap();
+        let endpoint = oembed_endpoint(&html, &url).unwrap();
+        assert_eq!(endpoint.host_str(), Some("www.youtube.com"));
+        assert!(endpoint
+            .query_pairs()
+            .any(|(k, v)| k == "url" && v == url.as_str()));
+    }
+
+    #[test]
+    fn test_discovered_endpoint() {
+        let html = Html::parse_document(
+            r#"<html><head><link rel="alternate" type="application/json+oembed" href="/oembed?id=1"></head></html>"#,
+        );
+        let url = Url::parse("https://example.com/post/1").unwrap();
+        assert_eq!(
+            oembed_endpoint(&html, &url).unwrap().as_str(),
+            "https://example.com/oembed?id=1"
+        );
+
+        let html = Html::parse_document("<html></html>");
+        assert_eq!(oembed_endpoint(&html, &url), None);
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let embed: OEmbed = serde_json::from_str(
+            r#"{"type":"video","author_name":"Author","duration":212,"html":"<iframe></iframe>","version":"1.0"}"#,
+        )
+        .unwrap();
+        assert_eq!(embed.object_type.as_deref(), Some("video"));
+        assert_eq!(embed.author_name.as_deref(), Some("Author"));
+        assert_eq!(embed.duration, Some(212));
+    }
+}
@@ -0,0 +1,99 @@
+use scraper::{ElementRef, Html, Selector};
+
+/// Containers which usually hold the main content of a page,
+/// in order of preference
+const CONTENT_CONTAINERS: [&str; 4] =
+    ["article", "main", "[role=\"main\"]", "body"];
+
+/// Elements whose text is considered readable content
+const TEXT_BLOCKS: &str = "h1, h2, h3, h4, h5, h6, p, li, pre, blockquote";
+
+/// Blocks with less words are likely navigation or buttons
+const MIN_BLOCK_WORDS: usize = 4;
+
+/// Extract the main text of the page, dropping menus, scripts and
+/// other boilerplate. Blocks are separated with empty lines.
+pub fn extract_readable_text(html: &Html) -> Option<String> {
+    let blocks = Selector::parse(TEXT_BLOCKS).unwrap();
+
+    let container = CONTENT_CONTAINERS
+        .iter()
+        .filter_map(|container| {
+            let selector = Selector::parse(container).unwrap();
+            html.select(&selector)
+                .max_by_key(|e| words_count(e))
+        })
+        .find(|element| words_count(element) > 0)?;
+
+    let text = container
+        .select(&blocks)
+        // Nested blocks are collected as part of their parent
+        .filter(|block| {
+            !block
+                .ancestors()
+                .filter_map(ElementRef::wrap)
+                .take_while(|ancestor| *ancestor != container)
+                .any(|ancestor| blocks.matches(&ancestor))
+        })
+        .map(|block| normalize(block.text()))
+        .filter(|text| {
+            text.split_whitespace().count() >= MIN_BLOCK_WORDS
+                || (text.starts_with(char::is_alphanumeric)
+                    && text.ends_with(['.', '!', '?']))
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    if text.is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+fn words_count(element: &ElementRef) -> usize {
+    element
+        .text()
+        .map(|text| text.split_whitespace().count())
+        .sum()
+}
+
+pub(crate) fn normalize<'a>(text: impl Iterator<Item = &'a str>) -> String {
+    text.flat_map(str::split_whitespace)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_article() {
+        let html = Html::parse_document(
+            r#"<html><body>
+                <nav><ul><li>Home</li><li>About</li></ul></nav>
+                <article>
+                    <h1>The title of the article</h1>
+                    <p>First paragraph   of the
+                       article text.</p>
+                    <script>var x = 1;</script>
+                    <p>Second paragraph with <b>bold</b> words.</p>
+                </article>
+            </body></html>"#,
+        );
+
+        assert_eq!(
+            extract_readable_text(&html).unwrap(),
+            "The title of the article\n\n\
+             First paragraph of the article text.\n\n\
+             Second paragraph with bold words."
+        );
+    }
+
+    #[test]
+    fn test_empty_page() {
+        let html = Html::parse_document("<html><body></body></html>");
+        assert_eq!(extract_readable_text(&html), None);
+    }
+}
@@ -0,0 +1,223 @@
+use base64::{engine::general_purpose, Engine};
+use data_error::Result;
+use scraper::{Html, Selector};
+use std::{collections::BTreeSet, fs, path::Path, time::SystemTime};
+use url::Url;
+
+use crate::LinkFetchConfig;
+
+/// Limits applied while archiving pages
+#[derive(Debug, Clone)]
+pub struct SnapshotConfig {
+    /// Resources larger than this are left as remote links
+    pub max_resource_size: usize,
+    /// Snapshots larger than this are not stored at all
+    pub max_snapshot_size: usize,
+    /// Oldest snapshots are removed once the folder grows over this size
+    pub max_total_size: u64,
+}
+
+impl Default for SnapshotConfig {
+    fn default() -> Self {
+        Self {
+            max_resource_size: 2 * 1024 * 1024,
+            max_snapshot_size: 20 * 1024 * 1024,
+            max_total_size: 500 * 1024 * 1024,
+        }
+    }
+}
+
+/// Resources inlined into the snapshot together with their mime type
+const RESOURCES: [(&str, &str, &str); 3] = [
+    ("link[rel~=\"stylesheet\"]", "href", "text/css"),
+    ("img", "src", "image"),
+    ("link[rel~=\"icon\"]", "href", "image"),
+];
+
+/// Build a self-contained copy of the page: stylesheets and images
+/// are inlined as `data:` urls and scripts are dropped.
+pub(crate) async fn build_snapshot(
+    page: &str,
+    base: &Url,
+    fetch: &LinkFetchConfig,
+    config: &SnapshotConfig,
+) -> Result<Option<String>> {
+    let resources = collect_resources(page, base);
+    let client = fetch.client()?;
+    let resource_fetch = LinkFetchConfig {
+        max_body_size: config.max_resource_size,
+        retries: 0,
+        ..fetch.clone()
+    };
+
+    let mut snapshot = strip_scripts(page);
+    for (value, url, mime) in resources {
+        let Ok(data) = resource_fetch.fetch(&client, url.as_str()).await else {
+            log::debug!("Leaving {url} as a remote resource");
+            continue;
+        };
+        let mime = if mime == "image" {
+            image_mime(&data)
+        } else {
+            mime
+        };
+        let inlined = format!(
+            "data:{};base64,{}",
+            mime,
+            general_purpose::STANDARD.encode(&data)
+        );
+        for quote in ['"', '\''] {
+            for value in [value.clone(), value.replace('&', "&amp;")] {
+                snapshot = snapshot.replace(
+                    &format!("={quote}{value}{quote}"),
+                    &format!("={quote}{inlined}{quote}"),
+                );
+            }
+        }
+    }
+
+    if snapshot.len() > config.max_snapshot_size {
+        log::debug!("Snapshot of {base} is too large");
+        return Ok(None);
+    }
+    Ok(Some(snapshot))
+}
+
+/// Remove the oldest snapshots until the folder fits into `max_total_size`.
+/// Returns the number of removed snapshots.
+pub(crate) fn prune_snapshots(folder: &Path, max_total_size: u64) -> usize {
+    let Ok(entries) = fs::read_dir(folder) else {
+        return 0;
+    };
+    let mut snapshots: Vec<(SystemTime, u64, std::path::PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let size = dir_size(&entry.path());
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            Some((modified, size, entry.path()))
+        })
+        .collect();
+    snapshots.sort();
+
+    let mut total: u64 = snapshots.iter().map(|(_, size, _)| size).sum();
+    let mut removed = 0;
+    for (_, size, path) in snapshots {
+        if total <= max_total_size {
+            break;
+        }
+        if fs::remove_dir_all(&path).is_ok() {
+            total -= size;
+            removed += 1;
+        }
+    }
+    removed
+}
+
+fn dir_size(path: &Path) -> u64 {
+    fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| entry.metadata().ok())
+                .map(|metadata| metadata.len())
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+fn collect_resources(
+    page: &str,
+    base: &Url,
+) -> Vec<(String, Url, &'static str)> {
+    let html = Html::parse_document(page);
+    let mut seen = BTreeSet::new();
+    let mut resources = vec![];
+    for (selector, attr, mime) in RESOURCES {
+        let selector = Selector::parse(selector).unwrap();
+        for element in html.select(&selector) {
+            let Some(value) = element.value().attr(attr) else {
+                continue;
+            };
+            if value.starts_with("data:") || !seen.insert(value.to_string()) {
+                continue;
+            }
+            if let Ok(url) = base.join(value) {
+                resources.push((value.to_string(), url, mime));
+            }
+        }
+    }
+    resources
+}
+
+fn strip_scripts(page: &str) -> String {
+    let mut result = String::with_capacity(page.len());
+    let mut rest = page;
+    while let Some(start) = find_ignore_case(rest, "<script") {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match find_ignore_case(rest, "</script>") {
+            Some(end) => rest = &rest[end + "</script>".len()..],
+            None => rest = "",
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack
+        .as_bytes()
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+fn image_mime(data: &[u8]) -> &'static str {
+    match data {
+        [0x89, b'P', b'N', b'G', ..] => "image/png",
+        [0xFF, 0xD8, 0xFF, ..] => "image/jpeg",
+        [b'G', b'I', b'F', b'8', ..] => "image/gif",
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => {
+            "image/webp"
+        }
+        [0, 0, 1, 0, ..] => "image/x-icon",
+        _ if data.starts_with(b"<svg") || data.starts_with(b"<?xml") => {
+            "image/svg+xml"
+        }
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_scripts() {
+        let page = "<html><SCRIPT>alert(1)</script><p>text</p>\
+                    <script src=\"a.js\"></script></html>";
+        assert_eq!(strip_scripts(page), "<html><p>text</p></html>");
+    }
+
+    #[test]
+    fn test_collect_resources() {
+        let base = Url::parse("https://example.com/post/").unwrap();
+        let page = r#"<html><head>
+            <link rel="stylesheet" href="/style.css">
+            </head><body>
+            <img src="a.png"><img src="a.png">
+            <img src="data:image/png;base64,AAAA">
+            </body></html>"#;
+        let resources = collect_resources(page, &base);
+        let urls: Vec<&str> = resources
+            .iter()
+            .map(|(_, url, _)| url.as_str())
+            .collect();
+        assert_eq!(
+            urls,
+            [
+                "https://example.com/style.css",
+                "https://example.com/post/a.png"
+            ]
+        );
+    }
+}
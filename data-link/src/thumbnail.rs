@@ -0,0 +1,53 @@
+use data_error::{ArklibError, Result};
+use fs_storage::THUMBNAILS_STORAGE_FOLDER;
+use image::{codecs::jpeg::JpegEncoder, imageops::FilterType};
+
+/// Width and height of link thumbnails in pixels
+pub const THUMBNAIL_SIZE: u32 = 256;
+
+const THUMBNAIL_QUALITY: u8 = 85;
+
+/// Thumbnails are grouped by size, the same way as in `data-preview`
+pub(crate) fn thumbnails_folder() -> String {
+    format!("{THUMBNAILS_STORAGE_FOLDER}/{THUMBNAIL_SIZE}")
+}
+
+/// Decode the preview image and produce a square JPEG thumbnail,
+/// cropping the image to fill the square
+pub(crate) fn generate_thumbnail(data: &[u8]) -> Result<Vec<u8>> {
+    let image = image::load_from_memory(data).map_err(|e| {
+        log::debug!("Can't decode preview image: {e}");
+        ArklibError::Parse
+    })?;
+    let thumbnail = image
+        .resize_to_fill(THUMBNAIL_SIZE, THUMBNAIL_SIZE, FilterType::Triangle)
+        .into_rgb8();
+
+    let mut bytes = vec![];
+    JpegEncoder::new_with_quality(&mut bytes, THUMBNAIL_QUALITY)
+        .encode_image(&thumbnail)
+        .map_err(|e| ArklibError::Other(e.into()))?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, RgbImage};
+    use std::io::Cursor;
+
+    #[test]
+    fn test_generate_thumbnail() {
+        let mut png = vec![];
+        RgbImage::new(800, 400)
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+
+        let thumbnail = generate_thumbnail(&png).unwrap();
+        let thumbnail = image::load_from_memory(&thumbnail).unwrap();
+        assert_eq!(thumbnail.width(), THUMBNAIL_SIZE);
+        assert_eq!(thumbnail.height(), THUMBNAIL_SIZE);
+
+        assert!(generate_thumbnail(b"not an image").is_err());
+    }
+}
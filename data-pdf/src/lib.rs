@@ -1,11 +1,16 @@
 use std::{
     io::{Read, Seek},
-    path::PathBuf,
+    ops::Range,
+    path::{Path, PathBuf},
 };
 
+use data_error::ArklibError;
+use data_resource::ResourceId;
+use fs_metadata::store_metadata;
 use image::DynamicImage;
 use once_cell::sync::OnceCell;
 use pdfium_render::prelude::*;
+use serde::{Deserialize, Serialize};
 
 static PDFIUM: OnceCell<Pdfium> = OnceCell::new(); // static initializers must impl Sync + Send
 
@@ -15,7 +20,7 @@ pub enum PDFQuality {
     Low,
 }
 
-fn initialize_pdfium() {
+fn initialize_pdfium() -> Pdfium {
     let out_path = env!("OUT_DIR");
     let pdfium_lib_path =
         PathBuf::from(&out_path).join(Pdfium::pdfium_platform_library_name());
@@ -27,31 +32,32 @@ fn initialize_pdfium() {
     )
     .or_else(|_| Pdfium::bind_to_system_library())
     .unwrap();
-    PDFIUM
-        .set(Pdfium::new(bindings))
-        .ok()
-        .expect("Can't set PDFIUM"); // Instead of returning the bindings, we
-                                     // cache them in the static initializer
+    Pdfium::new(bindings)
 }
 
-pub fn render_preview_page<R>(data: R, quailty: PDFQuality) -> DynamicImage
-where
-    R: Read + Seek + 'static,
-{
+fn pdfium() -> &'static Pdfium {
+    // Threads rendering at the same time wait for the first one to bind
+    // the library instead of binding it again
+    PDFIUM.get_or_init(initialize_pdfium)
+}
+
+fn render_config(quality: &PDFQuality) -> PdfRenderConfig {
     let render_cfg = PdfRenderConfig::new();
-    let render_cfg = match quailty {
+    match quality {
         PDFQuality::High => render_cfg.set_target_width(2000),
         PDFQuality::Medium => render_cfg,
         PDFQuality::Low => render_cfg.thumbnail(50),
     }
-    .rotate_if_landscape(PdfBitmapRotation::Degrees90, true);
+    .rotate_if_landscape(PdfBitmapRotation::Degrees90, true)
+}
 
-    if PDFIUM.get().is_none() {
-        initialize_pdfium();
-    }
-    PDFIUM
-        .get()
-        .unwrap()
+pub fn render_preview_page<R>(data: R, quailty: PDFQuality) -> DynamicImage
+where
+    R: Read + Seek + 'static,
+{
+    let render_cfg = render_config(&quailty);
+
+    pdfium()
         .load_pdf_from_reader(data, None)
         .unwrap()
         .pages()
@@ -62,6 +68,106 @@ where
         .as_image()
 }
 
+/// Render pages of the document in the `range`, pages out of the document
+/// are skipped.
+///
+/// Pages are rendered one after the other from a single loaded document:
+/// pdfium is not thread safe, every call goes through the same lock, so
+/// threads wouldn't render faster.
+pub fn render_pages<P: AsRef<Path>>(
+    path: P,
+    range: Range<u16>,
+    quality: PDFQuality,
+) -> Result<Vec<DynamicImage>, PdfiumError> {
+    let document = pdfium().load_pdf_from_file(path.as_ref(), None)?;
+    let pages = document.pages();
+    let count = pages.len();
+    let render_cfg = render_config(&quality);
+    range
+        .filter(|index| *index < count)
+        .map(|index| {
+            Ok(pages
+                .get(index)?
+                .render_with_config(&render_cfg)?
+                .as_image())
+        })
+        .collect()
+}
+
+/// Text of a single page of a document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageText {
+    /// Zero-based index of the page
+    pub index: u16,
+    pub text: String,
+}
+
+/// Extract text of all pages of the document, in reading order
+pub fn extract_text<P: AsRef<Path>>(
+    path: P,
+) -> Result<Vec<PageText>, PdfiumError> {
+    let document = pdfium().load_pdf_from_file(path.as_ref(), None)?;
+    document
+        .pages()
+        .iter()
+        .enumerate()
+        .map(|(index, page)| {
+            Ok(PageText {
+                index: index as u16,
+                text: page.text()?.all(),
+            })
+        })
+        .collect()
+}
+
+/// Document information of a PDF file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PdfMetadata {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    pub creator: Option<String>,
+    /// Date in PDF format, e.g. `D:20230101120000+01'00'`
+    pub creation_date: Option<String>,
+    pub page_count: u16,
+}
+
+/// Read document information of the PDF file
+pub fn read_metadata<P: AsRef<Path>>(
+    path: P,
+) -> Result<PdfMetadata, PdfiumError> {
+    let document = pdfium().load_pdf_from_file(path.as_ref(), None)?;
+    let metadata = document.metadata();
+    let tag = |tag| {
+        metadata
+            .get(tag)
+            .map(|tag| tag.value().trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+
+    Ok(PdfMetadata {
+        title: tag(PdfDocumentMetadataTagType::Title),
+        author: tag(PdfDocumentMetadataTagType::Author),
+        subject: tag(PdfDocumentMetadataTagType::Subject),
+        creator: tag(PdfDocumentMetadataTagType::Creator),
+        creation_date: tag(PdfDocumentMetadataTagType::CreationDate),
+        page_count: document.pages().len(),
+    })
+}
+
+/// Read document information of the PDF resource
+/// and store it as metadata of the resource
+pub fn store_pdf_metadata<R: AsRef<Path>, P: AsRef<Path>, Id: ResourceId>(
+    root: R,
+    id: Id,
+    path: P,
+) -> data_error::Result<PdfMetadata> {
+    let metadata = read_metadata(path)
+        .map_err(|e| ArklibError::Other(anyhow::anyhow!("{e:?}")))?;
+    store_metadata(root, id, &metadata)?;
+    Ok(metadata)
+}
+
 #[test]
 fn test_multi_pdf_generate() {
     use tempdir::TempDir;
@@ -79,3 +185,48 @@ fn test_multi_pdf_generate() {
             .expect("cannot save image");
     }
 }
+
+#[test]
+fn test_render_pages() {
+    let path = "../test-assets/test.pdf";
+    let count = render_pages(path, 0..u16::MAX, PDFQuality::Low)
+        .unwrap()
+        .len();
+    assert!(count > 0);
+
+    let images = render_pages(path, 0..1, PDFQuality::Low).unwrap();
+    assert_eq!(images.len(), 1);
+    assert!(render_pages(path, count as u16..u16::MAX, PDFQuality::Low)
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_extract_text() {
+    let pages = extract_text("../test-assets/test.pdf").unwrap();
+    assert!(!pages.is_empty());
+    for (index, page) in pages.iter().enumerate() {
+        assert_eq!(page.index as usize, index);
+    }
+}
+
+#[test]
+fn test_store_pdf_metadata() {
+    use dev_hash::Crc32;
+    use tempdir::TempDir;
+
+    fs_atomic_versions::initialize();
+
+    let dir = TempDir::new("arklib_test").unwrap();
+    let root = dir.path();
+    let path = "../test-assets/test.pdf";
+
+    let metadata = store_pdf_metadata(root, Crc32(0x342a3d4a), path).unwrap();
+    assert_eq!(metadata, read_metadata(path).unwrap());
+    assert!(metadata.page_count > 0);
+
+    let bytes =
+        fs_metadata::load_raw_metadata(root, Crc32(0x342a3d4a)).unwrap();
+    let stored: PdfMetadata = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(stored, metadata);
+}
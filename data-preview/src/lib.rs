@@ -0,0 +1,196 @@
+use data_error::{ArklibError, Result};
+use data_pdf::{render_pages, PDFQuality};
+use data_resource::ResourceId;
+use fs_atomic_versions::atomic::AtomicFile;
+use fs_storage::{ARK_FOLDER, THUMBNAILS_STORAGE_FOLDER};
+use image::{codecs::jpeg::JpegEncoder, imageops::FilterType, DynamicImage};
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+const THUMBNAIL_QUALITY: u8 = 85;
+
+/// Kinds of resources thumbnails can be generated for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceKind {
+    Image,
+    Pdf,
+}
+
+impl ResourceKind {
+    /// Guess the kind of the resource by its extension
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let extension = path
+            .as_ref()
+            .extension()?
+            .to_str()?
+            .to_ascii_lowercase();
+        match extension.as_str() {
+            "pdf" => Some(Self::Pdf),
+            "png" | "jpg" | "jpeg" | "gif" | "webp" | "bmp" | "tif"
+            | "tiff" | "ico" => Some(Self::Image),
+            _ => None,
+        }
+    }
+}
+
+/// Size presets of thumbnails, each of them is cached separately
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThumbnailSize {
+    Small,
+    Medium,
+    Large,
+}
+
+impl ThumbnailSize {
+    pub const ALL: [ThumbnailSize; 3] =
+        [Self::Small, Self::Medium, Self::Large];
+
+    /// Width and height of the thumbnail in pixels
+    pub fn pixels(&self) -> u32 {
+        match self {
+            Self::Small => 128,
+            Self::Medium => 256,
+            Self::Large => 512,
+        }
+    }
+}
+
+/// Location of thumbnails of the given size:
+/// `.ark/cache/thumbnails/<pixels>/<id>`
+pub fn thumbnail_path<P: AsRef<Path>, Id: ResourceId>(
+    root: P,
+    id: &Id,
+    size: ThumbnailSize,
+) -> PathBuf {
+    root.as_ref()
+        .join(ARK_FOLDER)
+        .join(THUMBNAILS_STORAGE_FOLDER)
+        .join(size.pixels().to_string())
+        .join(id.to_string())
+}
+
+/// Get the cached thumbnail of the resource, generating it if needed.
+/// Returns the path to the JPEG file of the thumbnail.
+pub fn provide_thumbnail<R: AsRef<Path>, P: AsRef<Path>, Id: ResourceId>(
+    root: R,
+    id: &Id,
+    path: P,
+    kind: ResourceKind,
+    size: ThumbnailSize,
+) -> Result<PathBuf> {
+    let file = AtomicFile::new(thumbnail_path(&root, id, size))?;
+    let current = file.load()?;
+    if current.version > 0 {
+        return Ok(current.path);
+    }
+
+    let thumbnail = generate_thumbnail(path, kind, size)?;
+    let tmp = file.make_temp()?;
+    (&tmp).write_all(&thumbnail)?;
+    file.compare_and_swap(&current, tmp)?;
+    Ok(file.load()?.path)
+}
+
+/// Render the resource and produce a square JPEG thumbnail,
+/// cropping it to fill the square
+pub fn generate_thumbnail<P: AsRef<Path>>(
+    path: P,
+    kind: ResourceKind,
+    size: ThumbnailSize,
+) -> Result<Vec<u8>> {
+    let image = match kind {
+        // Format is guessed by content, resources may lack extensions
+        ResourceKind::Image => {
+            image::load_from_memory(&fs::read(path)?).map_err(other)?
+        }
+        ResourceKind::Pdf => render_pages(path, 0..1, PDFQuality::Medium)
+            .map_err(|e| other(format!("{e:?}")))?
+            .into_iter()
+            .next()
+            .ok_or_else(|| other("PDF has no pages"))?,
+    };
+    encode_thumbnail(&image, size.pixels())
+}
+
+/// Remove thumbnails of all sizes, e.g. after the resource was modified
+pub fn invalidate_thumbnails<P: AsRef<Path>, Id: ResourceId>(
+    root: P,
+    id: &Id,
+) -> Result<()> {
+    for size in ThumbnailSize::ALL {
+        let path = thumbnail_path(&root, id, size);
+        if path.exists() {
+            fs::remove_dir_all(path)?;
+        }
+    }
+    Ok(())
+}
+
+fn encode_thumbnail(image: &DynamicImage, pixels: u32) -> Result<Vec<u8>> {
+    let thumbnail = image
+        .resize_to_fill(pixels, pixels, FilterType::Triangle)
+        .into_rgb8();
+
+    let mut bytes = vec![];
+    JpegEncoder::new_with_quality(&mut bytes, THUMBNAIL_QUALITY)
+        .encode_image(&thumbnail)
+        .map_err(other)?;
+    Ok(bytes)
+}
+
+fn other<E: std::fmt::Display>(e: E) -> ArklibError {
+    log::debug!("Thumbnail generation failed: {e}");
+    ArklibError::Other(anyhow::anyhow!("{e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dev_hash::Crc32;
+    use tempdir::TempDir;
+
+    #[test]
+    fn test_resource_kind() {
+        assert_eq!(ResourceKind::from_path("a.PDF"), Some(ResourceKind::Pdf));
+        assert_eq!(
+            ResourceKind::from_path("dir/a.jpeg"),
+            Some(ResourceKind::Image)
+        );
+        assert_eq!(ResourceKind::from_path("a.txt"), None);
+        assert_eq!(ResourceKind::from_path("a"), None);
+    }
+
+    #[test]
+    fn test_provide_image_thumbnail() {
+        fs_atomic_versions::initialize();
+
+        let dir = TempDir::new("arklib_test").unwrap();
+        let root = dir.path();
+        let id = Crc32(0x342a3d4a);
+        let source = "../test-assets/lena.jpg";
+
+        for size in ThumbnailSize::ALL {
+            let path =
+                provide_thumbnail(root, &id, source, ResourceKind::Image, size)
+                    .unwrap();
+            let thumbnail =
+                image::load_from_memory(&fs::read(&path).unwrap()).unwrap();
+            assert_eq!(thumbnail.width(), size.pixels());
+            assert_eq!(thumbnail.height(), size.pixels());
+
+            // Cached thumbnail is reused
+            let cached =
+                provide_thumbnail(root, &id, source, ResourceKind::Image, size)
+                    .unwrap();
+            assert_eq!(cached, path);
+        }
+
+        invalidate_thumbnails(root, &id).unwrap();
+        for size in ThumbnailSize::ALL {
+            assert!(!thumbnail_path(root, &id, size).exists());
+        }
+    }
+}
@@ -4,7 +4,30 @@
 use core::{fmt::Display, str::FromStr};
 use data_error::Result;
 use serde::{de::DeserializeOwned, Serialize};
-use std::{fmt::Debug, hash::Hash, path::Path};
+use std::{fmt::Debug, hash::Hash, io::Read, path::Path};
+#[cfg(feature = "async")]
+use std::{future::Future, io};
+
+#[cfg(feature = "async")]
+use futures::{Stream, StreamExt};
+#[cfg(feature = "async")]
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Size of the buffer used to read data when computing identifiers
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+/// This trait defines an incremental hasher computing a resource identifier
+/// from data given in chunks, e.g. as it arrives from the network.
+///
+/// Feeding the same data in any split must give the same identifier as
+/// [`ResourceId::from_bytes`].
+pub trait ResourceIdHasher<Id>: Default + Send {
+    /// Feeds the next chunk of data
+    fn update(&mut self, data: &[u8]);
+
+    /// Computes the resource identifier of all the data fed so far
+    fn finalize(self) -> Id;
+}
 
 /// This trait defines a generic type representing a resource identifier.
 ///
@@ -28,9 +51,65 @@ pub trait ResourceId:
     + Sync
     + Send
 {
+    /// Incremental hasher computing this kind of identifiers
+    type Hasher: ResourceIdHasher<Self>;
+
     /// Computes the resource identifier from the given file path
     fn from_path<P: AsRef<Path>>(file_path: P) -> Result<Self>;
 
     /// Computes the resource identifier from the given bytes
     fn from_bytes(data: &[u8]) -> Result<Self>;
+
+    /// Computes the resource identifier from all the data of the reader
+    fn from_reader<R: Read>(mut reader: R) -> Result<Self> {
+        let mut hasher = Self::Hasher::default();
+        let mut buffer = vec![0; READ_BUFFER_SIZE];
+        loop {
+            let bytes_read = reader.read(&mut buffer)?;
+            if bytes_read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..bytes_read]);
+        }
+        Ok(hasher.finalize())
+    }
+
+    /// Computes the resource identifier from all the data of the async
+    /// reader, while it arrives
+    #[cfg(feature = "async")]
+    fn from_async_reader<R: AsyncRead + Unpin + Send>(
+        mut reader: R,
+    ) -> impl Future<Output = Result<Self>> + Send {
+        async move {
+            let mut hasher = Self::Hasher::default();
+            let mut buffer = vec![0; READ_BUFFER_SIZE];
+            loop {
+                let bytes_read = reader.read(&mut buffer).await?;
+                if bytes_read == 0 {
+                    break;
+                }
+                hasher.update(&buffer[..bytes_read]);
+            }
+            Ok(hasher.finalize())
+        }
+    }
+
+    /// Computes the resource identifier from a stream of chunks of data,
+    /// while they arrive. The first error of the stream is returned.
+    #[cfg(feature = "async")]
+    fn from_stream<S, B>(
+        mut stream: S,
+    ) -> impl Future<Output = Result<Self>> + Send
+    where
+        S: Stream<Item = io::Result<B>> + Unpin + Send,
+        B: AsRef<[u8]> + Send,
+    {
+        async move {
+            let mut hasher = Self::Hasher::default();
+            while let Some(chunk) = stream.next().await {
+                hasher.update(chunk?.as_ref());
+            }
+            Ok(hasher.finalize())
+        }
+    }
 }
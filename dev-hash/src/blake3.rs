@@ -10,7 +10,7 @@ use hex::encode;
 use serde::{Deserialize, Serialize};
 
 use data_error::Result;
-use data_resource::ResourceId;
+use data_resource::{ResourceId, ResourceIdHasher};
 
 /// Represents a resource identifier using the BLAKE3 algorithm.
 ///
@@ -34,7 +34,23 @@ impl Display for Blake3 {
     }
 }
 
+/// Incremental hasher of [`Blake3`] identifiers
+#[derive(Default)]
+pub struct Blake3Hasher(Hasher);
+
+impl ResourceIdHasher<Blake3> for Blake3Hasher {
+    fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    fn finalize(self) -> Blake3 {
+        Blake3(encode(self.0.finalize().as_bytes()))
+    }
+}
+
 impl ResourceId for Blake3 {
+    type Hasher = Blake3Hasher;
+
     fn from_path<P: AsRef<Path>>(file_path: P) -> Result<Self> {
         log::debug!("Computing BLAKE3 hash for file: {:?}", file_path.as_ref());
 
@@ -85,5 +101,35 @@ mod tests {
             id,
             Blake3("172b4bf148e858b13dde0fc6613413bcb7552e5c4e5c45195ac6c80f20eb5ff5".to_string())
         );
+
+        let id = Blake3::from_reader(raw_bytes.as_slice())
+            .expect("Failed to compute resource identifier");
+        assert_eq!(
+            id,
+            Blake3("172b4bf148e858b13dde0fc6613413bcb7552e5c4e5c45195ac6c80f20eb5ff5".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn async_sanity_check() {
+        let raw_bytes =
+            fs::read("../test-assets/lena.jpg").expect("Failed to read file");
+        let expected = Blake3(
+            "172b4bf148e858b13dde0fc6613413bcb7552e5c4e5c45195ac6c80f20eb5ff5"
+                .to_string(),
+        );
+
+        let id = Blake3::from_async_reader(raw_bytes.as_slice())
+            .await
+            .expect("Failed to compute resource identifier");
+        assert_eq!(id, expected);
+
+        let chunks = raw_bytes
+            .chunks(1000)
+            .map(|chunk| Ok(chunk.to_vec()));
+        let id = Blake3::from_stream(futures::stream::iter(chunks))
+            .await
+            .expect("Failed to compute resource identifier");
+        assert_eq!(id, expected);
     }
 }
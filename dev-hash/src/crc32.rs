@@ -9,7 +9,7 @@ use crc32fast::Hasher;
 use serde::{Deserialize, Serialize};
 
 use data_error::Result;
-use data_resource::ResourceId;
+use data_resource::{ResourceId, ResourceIdHasher};
 
 /// Represents a resource identifier using the CRC32 algorithm.
 ///
@@ -33,7 +33,23 @@ impl Display for Crc32 {
     }
 }
 
+/// Incremental hasher of [`Crc32`] identifiers
+#[derive(Default)]
+pub struct Crc32Hasher(Hasher);
+
+impl ResourceIdHasher<Crc32> for Crc32Hasher {
+    fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    fn finalize(self) -> Crc32 {
+        Crc32(self.0.finalize())
+    }
+}
+
 impl ResourceId for Crc32 {
+    type Hasher = Crc32Hasher;
+
     fn from_path<P: AsRef<Path>>(file_path: P) -> Result<Self> {
         log::debug!("Computing CRC32 hash for file: {:?}", file_path.as_ref());
 
@@ -76,5 +92,28 @@ mod tests {
         let id = <Crc32 as ResourceId>::from_bytes(&raw_bytes)
             .expect("Failed to compute resource identifier");
         assert_eq!(id, Crc32(875183434));
+
+        let id = Crc32::from_reader(raw_bytes.as_slice())
+            .expect("Failed to compute resource identifier");
+        assert_eq!(id, Crc32(875183434));
+    }
+
+    #[tokio::test]
+    async fn async_sanity_check() {
+        let raw_bytes =
+            fs::read("../test-assets/lena.jpg").expect("Failed to read file");
+
+        let id = Crc32::from_async_reader(raw_bytes.as_slice())
+            .await
+            .expect("Failed to compute resource identifier");
+        assert_eq!(id, Crc32(875183434));
+
+        let chunks = raw_bytes
+            .chunks(1000)
+            .map(|chunk| Ok(chunk.to_vec()));
+        let id = Crc32::from_stream(futures::stream::iter(chunks))
+            .await
+            .expect("Failed to compute resource identifier");
+        assert_eq!(id, Crc32(875183434));
     }
 }
@@ -1,5 +1,5 @@
 mod blake3;
 mod crc32;
 
-pub use blake3::Blake3;
-pub use crc32::Crc32;
+pub use blake3::{Blake3, Blake3Hasher};
+pub use crc32::{Crc32, Crc32Hasher};
@@ -32,7 +32,12 @@
 //! use arkdrop_common::Profile;
 //! # async fn demo() -> anyhow::Result<()> {
 //! let profile = Profile::new("Alice".into(), None);
-//! run_send_files(vec!["/path/file1.bin".into(), "/path/file2.jpg".into()], profile, true).await?;
+//! run_send_files(
+//!     vec!["/path/file1.bin".into(), "/path/file2.jpg".into()],
+//!     profile,
+//!     true,   // verbose
+//!     false,  // move_sources
+//! ).await?;
 //! # Ok(())
 //! # }
 //! ```
@@ -45,12 +50,13 @@
 //! let profile = Profile::default();
 //! // If you want to persist the directory, set save_out = true
 //! run_receive_files(
-//!     "/tmp/downloads".into(),
+//!     Some("/tmp/downloads".into()),
 //!     "TICKET_STRING".into(),
 //!     "7".into(),
 //!     profile,
 //!     true,   // verbose
 //!     false,  // save_out
+//!     None,   // max_size
 //! ).await?;
 //! # Ok(())
 //! # }
@@ -58,42 +64,103 @@
 use std::{
     collections::HashMap,
     fs,
-    io::Write,
+    net::IpAddr,
     path::PathBuf,
     str::FromStr,
-    sync::{Arc, RwLock, atomic::AtomicBool},
+    sync::{Arc, Mutex, RwLock},
+    time::Duration,
 };
 
 use anyhow::{Context, Result, anyhow};
 use arkdrop_common::{
-    AppConfig, Profile, clear_default_out_dir, get_default_out_dir,
-    set_default_out_dir,
+    AppConfig, InMemoryData, LimitsConfig, PerformancePreset, Profile,
+    clear_default_out_dir, collect_files, get_default_out_dir, identity,
+    journal::{
+        Direction, Journal, JournalEvent, JournalFile, SessionStatus,
+        read_journal, summarize_sessions,
+    },
+    peers::PeerBook,
+    received_file_path, set_default_out_dir,
+    writer::{FileWriters, WriteError},
 };
 use arkdropx_receiver::{
-    ReceiveFilesConnectingEvent, ReceiveFilesFile, ReceiveFilesReceivingEvent,
-    ReceiveFilesRequest, ReceiveFilesSubscriber, ReceiverProfile,
+    EndReason, ReceiveFilesConnectingEvent, ReceiveFilesDiscardedEvent,
+    ReceiveFilesFile, ReceiveFilesReceivedEvent, ReceiveFilesReceivingEvent,
+    ReceiveFilesRequest, ReceiveFilesSubscriber, ReceiverConfig,
+    ReceiverProfile,
     ready_to_receive::{
         ReadyToReceiveBubble, ReadyToReceiveConfig,
-        ReadyToReceiveConnectingEvent, ReadyToReceiveFile,
+        ReadyToReceiveConnectingEvent, ReadyToReceiveDiscardedEvent,
+        ReadyToReceiveFile, ReadyToReceiveReceivedEvent,
         ReadyToReceiveReceivingEvent, ReadyToReceiveRequest,
         ReadyToReceiveSubscriber, ready_to_receive,
     },
     receive_files,
 };
 use arkdropx_sender::{
-    SendFilesBubble, SendFilesConnectingEvent, SendFilesRequest,
-    SendFilesSendingEvent, SendFilesSubscriber, SenderConfig, SenderFile,
-    SenderFileData, SenderProfile, send_files,
+    DropUri, EndpointConfig, ItemKind, LinkItem, SendFilesBubble,
+    SendFilesConnectingEvent, SendFilesErrorEvent, SendFilesReceivedEvent,
+    SendFilesRequest, SendFilesSendingEvent, SendFilesSubscriber, SenderConfig,
+    SenderFile, SenderProfile, send_files,
     send_files_to::{
-        SendFilesToBubble, SendFilesToConnectingEvent, SendFilesToRequest,
-        SendFilesToSendingEvent, SendFilesToSubscriber, send_files_to,
+        SendFilesToBubble, SendFilesToConnectingEvent, SendFilesToErrorEvent,
+        SendFilesToReceivedEvent, SendFilesToRequest, SendFilesToSendingEvent,
+        SendFilesToSubscriber, send_files_to,
     },
 };
 use clap::{Arg, ArgMatches, Command};
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use indicatif::{HumanBytes, MultiProgress, ProgressBar, ProgressStyle};
 use qrcode::QrCode;
 use uuid::Uuid;
 
+pub use arkdrop_common::FileData;
+
+/// Progress bars redraw at most this often, so sending progress coalesces
+/// the events of a file within it.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Name under which the user data of resources is sent, see
+/// [`ItemKind::UserData`].
+const USER_DATA_ITEM_NAME: &str = "user-data.json";
+
+/// Files received by a session.
+#[derive(Clone, Debug)]
+pub struct ReceivedSession {
+    /// Folder the files were saved to.
+    pub dir: PathBuf,
+    /// Received files holding a [`LinkItem`], to save into a library.
+    pub links: Vec<PathBuf>,
+    /// Received files holding user data of resources, to merge into a
+    /// library, see [`ItemKind::UserData`].
+    pub user_data: Vec<PathBuf>,
+}
+
+impl ReceivedSession {
+    /// Sorts out the items among the files received completely, given by
+    /// kind and name.
+    fn new<'a>(
+        dir: PathBuf,
+        files: impl IntoIterator<Item = (ItemKind, &'a str)>,
+    ) -> Self {
+        let mut session = Self {
+            dir,
+            links: Vec::new(),
+            user_data: Vec::new(),
+        };
+        for (kind, name) in files {
+            let items = match kind {
+                ItemKind::File => continue,
+                ItemKind::Link => &mut session.links,
+                ItemKind::UserData => &mut session.user_data,
+            };
+            if let Ok(path) = received_file_path(&session.dir, name) {
+                items.push(path);
+            }
+        }
+        session
+    }
+}
+
 /// File sender with error handling and progress tracking.
 ///
 /// Wraps the lower-level arkdropx_sender API and provides:
@@ -118,76 +185,72 @@ impl FileSender {
     /// - Shows per-file progress bars.
     /// - Cancels cleanly on Ctrl+C.
     ///
+    /// Files are sent under their names, see [`collect_files`]. With a
+    /// `mover`, their sources are deleted as the receiver confirms them.
+    ///
     /// Errors:
-    /// - If any provided path is missing or not a regular file.
+    /// - If the list of files is empty.
     /// - If the underlying sender fails to initialize or run.
     async fn send_files(
         &self,
-        file_paths: Vec<PathBuf>,
+        files: Vec<SenderFile>,
         verbose: bool,
+        mover: Option<Arc<SourceMover>>,
     ) -> Result<()> {
-        if file_paths.is_empty() {
+        if files.is_empty() {
             return Err(anyhow!("Cannot send an empty list of files"));
         }
 
-        // Validate all files exist before starting
-        for path in &file_paths {
-            if !path.exists() {
-                return Err(anyhow!("File does not exist: {}", path.display()));
-            }
-            if !path.is_file() {
-                return Err(anyhow!("Path is not a file: {}", path.display()));
-            }
-        }
+        let journal = start_journal(Direction::Send, journal_files(&files));
+        let total_size: u64 = files.iter().map(|f| f.data.len()).sum();
 
         let request = SendFilesRequest {
-            files: self.create_sender_files(file_paths)?,
+            files,
             profile: self.create_sender_profile(),
-            config: SenderConfig::default(),
+            config: SenderConfig {
+                endpoint: endpoint_config(),
+                max_rate: limits().max_upload_rate,
+                chunk_size: preset().chunk_size(),
+                parallel_streams: preset().parallel_streams(),
+                ..Default::default()
+            },
         };
 
         let bubble = send_files(request)
             .await
-            .context("Failed to initiate file sending")?;
+            .context("Failed to initiate file sending")
+            .inspect_err(|e| record_failure(&journal, e))?;
 
-        let subscriber = FileSendSubscriber::new(verbose);
+        let subscriber =
+            FileSendSubscriber::new(verbose, journal.clone(), mover.clone());
         bubble.subscribe(Arc::new(subscriber));
 
         println!("📦 Ready to send files!");
-        print_qr_to_console(&bubble)?;
+        print_qr_to_console(&bubble, &self.profile.name, total_size)?;
         println!("⏳ Waiting for receiver... (Press Ctrl+C to cancel)");
 
         tokio::select! {
             _ = tokio::signal::ctrl_c() => {
                 println!("Cancelling file transfer...");
                 let _ = bubble.cancel().await;
+                record(&journal, JournalEvent::Finished { cancelled: true });
                 println!("Transfer cancelled");
                 Ok(())
             }
             _ = wait_for_send_completion(&bubble) => {
-                println!("All files sent successfully!");
+                report_end(
+                    &journal,
+                    bubble.get_end_reason(),
+                    "All files sent successfully!",
+                );
+                if let Some(mover) = &mover {
+                    mover.report();
+                }
                 Ok(())
             }
         }
     }
 
-    fn create_sender_files(
-        &self,
-        paths: Vec<PathBuf>,
-    ) -> Result<Vec<SenderFile>> {
-        let mut sender_files = Vec::new();
-
-        for path in paths {
-            let data = FileData::new(path.clone())?;
-            sender_files.push(SenderFile {
-                name: path.to_string_lossy().to_string(),
-                data: Arc::new(data),
-            });
-        }
-
-        Ok(sender_files)
-    }
-
     /// Returns a SenderProfile derived from this FileSender's Profile.
     fn create_sender_profile(&self) -> SenderProfile {
         SenderProfile {
@@ -197,11 +260,350 @@ impl FileSender {
     }
 }
 
-fn print_qr_to_console(bubble: &SendFilesBubble) -> Result<()> {
+/// Deletes the sources of a session sent with `--move`, each one once the
+/// receiver confirmed it got the file in full.
+///
+/// Files the receiver doesn't confirm, e.g. because it doesn't support
+/// acknowledgements or the session failed, are kept.
+struct SourceMover {
+    /// Paths of the sources not deleted yet, by name they are sent under.
+    pending: Mutex<HashMap<String, PathBuf>>,
+    total: usize,
+}
+
+impl SourceMover {
+    fn new(named_files: &[(String, PathBuf)]) -> Self {
+        Self {
+            pending: Mutex::new(named_files.iter().cloned().collect()),
+            total: named_files.len(),
+        }
+    }
+
+    /// Deletes the source sent under `name`, returning a line to print.
+    fn remove(&self, name: &str) -> Option<String> {
+        let path = self.pending.lock().unwrap().remove(name)?;
+        Some(match fs::remove_file(&path) {
+            Ok(()) => format!("[MOVED] {}", path.display()),
+            Err(e) => {
+                // Kept for the summary
+                let line =
+                    format!("[ERROR] Failed to remove {}: {e}", path.display());
+                self.pending
+                    .lock()
+                    .unwrap()
+                    .insert(name.to_string(), path);
+                line
+            }
+        })
+    }
+
+    /// Prints how many sources were deleted and kept.
+    fn report(&self) {
+        let kept = self.pending.lock().unwrap().len();
+        println!("🗑️  Moved {} file(s)", self.total - kept);
+        if kept > 0 {
+            println!(
+                "⚠️  Kept {kept} file(s) the receiver didn't confirm, or which couldn't be removed"
+            );
+        }
+    }
+}
+
+/// Creates the sender files of the given named paths, validating that they
+/// exist.
+fn create_sender_files(
+    named_files: Vec<(String, PathBuf)>,
+) -> Result<Vec<SenderFile>> {
+    let mut sender_files = Vec::new();
+
+    for (name, path) in named_files {
+        let data = FileData::new(path)?;
+        sender_files.push(SenderFile {
+            name,
+            data: Arc::new(data),
+            kind: ItemKind::File,
+        });
+    }
+
+    if sender_files.is_empty() {
+        return Err(anyhow!("No files found to send"));
+    }
+    Ok(sender_files)
+}
+
+/// Creates the sender file of a link item, holding its serialized form.
+fn link_sender_file(name: String, link: &LinkItem) -> Result<SenderFile> {
+    Ok(SenderFile {
+        name,
+        data: Arc::new(InMemoryData::new(link.to_bytes()?)),
+        kind: ItemKind::Link,
+    })
+}
+
+/// Creates the sender file of serialized user data of resources.
+fn user_data_sender_file(user_data: Vec<u8>) -> SenderFile {
+    SenderFile {
+        name: USER_DATA_ITEM_NAME.to_string(),
+        data: Arc::new(InMemoryData::new(user_data)),
+        kind: ItemKind::UserData,
+    }
+}
+
+/// Returns the names and sizes of the files to send, for the journal.
+fn journal_files(files: &[SenderFile]) -> Vec<JournalFile> {
+    files
+        .iter()
+        .map(|file| JournalFile {
+            name: file.name.clone(),
+            len: file.data.len(),
+        })
+        .collect()
+}
+
+/// Directory receiving the files of a session.
+///
+/// It's created once the sender is known, so that files from trusted peers
+/// land in the directory configured for them.
+struct SessionDir {
+    /// Parent directory chosen by the user, if any.
+    out_dir: Option<PathBuf>,
+    path: RwLock<Option<PathBuf>>,
+}
+
+impl SessionDir {
+    fn new(out_dir: Option<PathBuf>) -> Self {
+        Self {
+            out_dir,
+            path: RwLock::new(None),
+        }
+    }
+
+    /// Creates a unique folder for the session inside the chosen directory,
+    /// or else inside the one configured for the sender.
+    fn create(&self, node_id: Option<&str>, name: &str) -> Result<PathBuf> {
+        let out_dir = match &self.out_dir {
+            Some(dir) => dir.clone(),
+            None => AppConfig::load()
+                .unwrap_or_default()
+                .get_peer_out_dir(node_id, name),
+        };
+        let path = out_dir.join(Uuid::new_v4().to_string());
+        fs::create_dir_all(&path).with_context(|| {
+            format!("Failed to create receiving directory: {}", path.display())
+        })?;
+        if let Ok(mut current) = self.path.write() {
+            *current = Some(path.clone());
+        }
+        Ok(path)
+    }
+
+    /// Returns the session folder, once created.
+    fn path(&self) -> Option<PathBuf> {
+        self.path.read().ok()?.clone()
+    }
+}
+
+/// Returns the endpoint options for the network settings in the config.
+fn endpoint_config() -> EndpointConfig {
+    AppConfig::load()
+        .map(|config| config.network.endpoint_config())
+        .unwrap_or_default()
+}
+
+/// Returns the endpoint options of sync sessions: the network settings in
+/// the config with the persistent identity of this device.
+fn sync_endpoint_config() -> Result<EndpointConfig> {
+    Ok(EndpointConfig {
+        secret_key: Some(identity::secret_key()?),
+        ..endpoint_config()
+    })
+}
+
+/// Returns the bandwidth limits from the config, unlimited if it can't be
+/// loaded.
+fn limits() -> LimitsConfig {
+    AppConfig::load()
+        .map(|config| config.limits)
+        .unwrap_or_default()
+}
+
+/// Returns the performance preset from the config, balanced if it can't be
+/// loaded.
+fn preset() -> PerformancePreset {
+    AppConfig::load()
+        .map(|config| config.preset)
+        .unwrap_or_default()
+}
+
+/// Parses a rate in bytes per second, with an optional binary unit such as
+/// `512K`, `2M` or `1GiB/s`.
+fn parse_rate(value: &str) -> Result<u64, String> {
+    let upper = value.trim().to_ascii_uppercase();
+    let number = upper.strip_suffix("/S").unwrap_or(&upper);
+    let rate =
+        parse_bytes(number).ok_or_else(|| format!("Invalid rate: {value}"))?;
+    if rate == 0 {
+        return Err("Rate must be greater than zero".to_string());
+    }
+    Ok(rate)
+}
+
+/// Parses a size in bytes, with an optional binary unit such as `512K`,
+/// `2M` or `10GiB`.
+fn parse_size(value: &str) -> Result<u64, String> {
+    let size = parse_bytes(&value.trim().to_ascii_uppercase())
+        .ok_or_else(|| format!("Invalid size: {value}"))?;
+    if size == 0 {
+        return Err("Size must be greater than zero".to_string());
+    }
+    Ok(size)
+}
+
+/// Parses an upper case number of bytes with an optional binary unit.
+fn parse_bytes(value: &str) -> Option<u64> {
+    let number = value.strip_suffix('B').unwrap_or(value);
+    let number = number.strip_suffix('I').unwrap_or(number);
+    let (number, multiplier) = match number.chars().last() {
+        Some('K') => (&number[..number.len() - 1], 1024),
+        Some('M') => (&number[..number.len() - 1], 1024 * 1024),
+        Some('G') => (&number[..number.len() - 1], 1024 * 1024 * 1024),
+        _ => (number, 1),
+    };
+    number
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+}
+
+/// Formats an optional rate for display.
+fn format_rate(rate: Option<u64>) -> String {
+    match rate {
+        Some(rate) => format!("{}/s", HumanBytes(rate)),
+        None => "unlimited".to_string(),
+    }
+}
+
+/// Formats an optional size limit for display.
+fn format_size(size: Option<u64>) -> String {
+    match size {
+        Some(size) => HumanBytes(size).to_string(),
+        None => "unlimited".to_string(),
+    }
+}
+
+/// Starts recording a session in the journal, if enabled in the config.
+///
+/// Failing to open the journal doesn't prevent the transfer, it's only
+/// reported.
+fn start_journal(
+    direction: Direction,
+    files: Vec<JournalFile>,
+) -> Option<Arc<Journal>> {
+    let enabled = AppConfig::load()
+        .map(|config| config.journal)
+        .unwrap_or(false);
+    if !enabled {
+        return None;
+    }
+
+    match Journal::start(direction, files) {
+        Ok(journal) => Some(Arc::new(journal)),
+        Err(e) => {
+            eprintln!("[WARN] Failed to open the journal: {e:#}");
+            None
+        }
+    }
+}
+
+/// Records an event of the session in the journal, if any.
+fn record(journal: &Option<Arc<Journal>>, event: JournalEvent) {
+    if let Some(journal) = journal
+        && let Err(e) = journal.record(event)
+    {
+        eprintln!("[WARN] Failed to write to the journal: {e:#}");
+    }
+}
+
+/// Records the session with the peer in the address book, and returns a
+/// line telling when it was last seen if it was known.
+fn remember_peer(
+    node_id: Option<&str>,
+    name: &str,
+    avatar_b64: Option<&str>,
+) -> Option<String> {
+    let node_id = node_id?;
+    let result = PeerBook::load().and_then(|mut book| {
+        let previous = book.get(node_id).cloned();
+        book.record(node_id, name, avatar_b64)?;
+        Ok(previous)
+    });
+    match result {
+        Ok(previous) => previous.map(|peer| {
+            format!(
+                "   Last seen: {} ({} sessions)",
+                peer.last_seen.format("%Y-%m-%d %H:%M:%S UTC"),
+                peer.sessions
+            )
+        }),
+        Err(e) => {
+            eprintln!("[WARN] Failed to update the peer address book: {e:#}");
+            None
+        }
+    }
+}
+
+/// Records the error ending the session in the journal, if any.
+fn record_failure(journal: &Option<Arc<Journal>>, error: &anyhow::Error) {
+    record(
+        journal,
+        JournalEvent::Error {
+            message: format!("{error:#}"),
+        },
+    );
+    record(journal, JournalEvent::Finished { cancelled: false });
+}
+
+/// Prints how a finished session ended, `success` if all files were
+/// transferred, and records it in the journal, if any.
+fn report_end(
+    journal: &Option<Arc<Journal>>,
+    reason: Option<EndReason>,
+    success: &str,
+) {
+    match reason {
+        None | Some(EndReason::Completed) => {
+            record(journal, JournalEvent::Finished { cancelled: false });
+            println!("{success}");
+        }
+        Some(EndReason::Cancelled) => {
+            record(journal, JournalEvent::Finished { cancelled: true });
+            println!("Transfer cancelled by the peer");
+        }
+        Some(reason) => {
+            record(
+                journal,
+                JournalEvent::Error {
+                    message: format!("Transfer stopped: {reason}"),
+                },
+            );
+            record(journal, JournalEvent::Finished { cancelled: false });
+            eprintln!("[ERROR] Transfer stopped: {reason}");
+        }
+    }
+}
+
+fn print_qr_to_console(
+    bubble: &SendFilesBubble,
+    name: &str,
+    total_size: u64,
+) -> Result<()> {
     let ticket = bubble.get_ticket();
     let confirmation = bubble.get_confirmation();
-    let data =
-        format!("drop://receive?ticket={ticket}&confirmation={confirmation}");
+    let data = DropUri::receive(&ticket, confirmation)
+        .with_name(name)
+        .with_expected_size(total_size)
+        .to_string();
 
     let code = QrCode::new(&data)?;
     let image = code
@@ -218,9 +620,14 @@ fn print_qr_to_console(bubble: &SendFilesBubble) -> Result<()> {
     Ok(())
 }
 
-fn print_ready_to_receive_qr(ticket: &str, confirmation: u8) -> Result<()> {
-    let data =
-        format!("drop://send?ticket={ticket}&confirmation={confirmation}");
+fn print_ready_to_receive_qr(
+    ticket: &str,
+    confirmation: u8,
+    name: &str,
+) -> Result<()> {
+    let data = DropUri::send(ticket, confirmation)
+        .with_name(name)
+        .to_string();
 
     let code = QrCode::new(&data)?;
     let image = code
@@ -265,7 +672,9 @@ impl FileReceiver {
     /// Receive files into the provided output directory.
     ///
     /// Behavior:
-    /// - Creates a unique subfolder for the session inside `out_dir`.
+    /// - Creates a unique subfolder for the session inside `out_dir`, or
+    ///   inside the sender's directory from the config if None, once the
+    ///   sender is connected.
     /// - Shows per-file progress bars for known file sizes.
     /// - Cancels cleanly on Ctrl+C.
     ///
@@ -275,57 +684,57 @@ impl FileReceiver {
     /// - ticket: The ticket provided by the sender.
     /// - confirmation: The numeric confirmation code.
     /// - verbose: Enables extra logging output.
+    /// - max_size: Largest total size of the session in bytes, the limit
+    ///   from the config if None.
+    ///
+    /// Returns the received session, `None` if the sender never connected.
     ///
     /// Errors:
     /// - If directories cannot be created or written.
     /// - If the underlying receiver fails to initialize or run.
     async fn receive_files(
         &self,
-        out_dir: PathBuf,
+        out_dir: Option<PathBuf>,
         ticket: String,
         confirmation: u8,
         verbose: bool,
-    ) -> Result<()> {
-        // Create output directory if it doesn't exist
-        if !out_dir.exists() {
-            fs::create_dir_all(&out_dir).with_context(|| {
-                format!(
-                    "Failed to create output directory: {}",
-                    out_dir.display()
-                )
-            })?;
-        }
-
-        // Create unique subdirectory for this transfer
-        let receiving_path = out_dir.join(Uuid::new_v4().to_string());
-        fs::create_dir(&receiving_path).with_context(|| {
-            format!(
-                "Failed to create receiving directory: {}",
-                receiving_path.display()
-            )
-        })?;
+        max_size: Option<u64>,
+    ) -> Result<Option<ReceivedSession>> {
+        let journal = start_journal(Direction::Receive, Vec::new());
 
+        let limits = limits();
         let request = ReceiveFilesRequest {
             ticket,
             confirmation,
             profile: self.get_receiver_profile(),
-            config: None,
+            config: Some(ReceiverConfig {
+                endpoint: endpoint_config(),
+                max_rate: limits.max_download_rate,
+                max_total_size: max_size.or(limits.max_receive_size),
+                chunk_size: preset().chunk_size(),
+                parallel_streams: preset().parallel_streams(),
+                ..Default::default()
+            }),
         };
 
         let bubble = receive_files(request)
             .await
-            .context("Failed to initiate file receiving")?;
+            .context("Failed to initiate file receiving")
+            .inspect_err(|e| record_failure(&journal, e))?;
 
-        let subscriber =
-            FileReceiveSubscriber::new(receiving_path.clone(), verbose);
-        bubble.subscribe(Arc::new(subscriber));
+        let subscriber = Arc::new(FileReceiveSubscriber::new(
+            SessionDir::new(out_dir),
+            verbose,
+            journal.clone(),
+        ));
+        bubble.subscribe(subscriber.clone());
 
         println!("Starting file transfer...");
-        println!("Files will be saved to: {}", receiving_path.display());
 
         bubble
             .start()
-            .context("Failed to start file receiving")?;
+            .context("Failed to start file receiving")
+            .inspect_err(|e| record_failure(&journal, e))?;
 
         println!("Receiving files... (Press Ctrl+C to cancel)");
 
@@ -333,12 +742,20 @@ impl FileReceiver {
             _ = tokio::signal::ctrl_c() => {
                 println!("Cancelling file transfer...");
                 bubble.cancel();
+                subscriber.finish_writes();
+                record(&journal, JournalEvent::Finished { cancelled: true });
                 println!("Transfer cancelled");
-                Ok(())
+                Ok(subscriber.session())
             }
-            _ = wait_for_receive_completion(&bubble) => {
-                println!("All files received successfully!");
-                Ok(())
+            _ = wait_for_receive_completion(&bubble, &subscriber) => {
+                subscriber.finish_writes();
+                subscriber.verify();
+                report_end(
+                    &journal,
+                    bubble.get_end_reason(),
+                    "All files received successfully!",
+                );
+                Ok(subscriber.session())
             }
         }
     }
@@ -352,13 +769,37 @@ impl FileReceiver {
     }
 }
 
+/// Prints and records in the journal the files which couldn't be written.
+fn report_write_errors(
+    journal: &Option<Arc<Journal>>,
+    errors: Vec<WriteError>,
+) {
+    for e in errors {
+        eprintln!("[ERROR] {e}");
+        record(
+            journal,
+            JournalEvent::Error {
+                message: e.to_string(),
+            },
+        );
+    }
+}
+
 async fn wait_for_receive_completion(
     bubble: &arkdropx_receiver::ReceiveFilesBubble,
+    subscriber: &FileReceiveSubscriber,
 ) {
     loop {
         if bubble.is_finished() {
             break;
         }
+        report_write_errors(
+            &subscriber.journal,
+            subscriber.writers.take_errors(),
+        );
+        if subscriber.is_disk_full() {
+            bubble.cancel_with(EndReason::DiskFull);
+        }
         tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
     }
 }
@@ -368,15 +809,23 @@ struct FileSendSubscriber {
     verbose: bool,
     mp: MultiProgress,
     bars: RwLock<HashMap<String, ProgressBar>>,
+    journal: Option<Arc<Journal>>,
+    mover: Option<Arc<SourceMover>>,
 }
 
 impl FileSendSubscriber {
-    fn new(verbose: bool) -> Self {
+    fn new(
+        verbose: bool,
+        journal: Option<Arc<Journal>>,
+        mover: Option<Arc<SourceMover>>,
+    ) -> Self {
         Self {
             id: Uuid::new_v4().to_string(),
             verbose,
             mp: MultiProgress::new(),
             bars: RwLock::new(HashMap::new()),
+            journal,
+            mover,
         }
     }
 
@@ -439,12 +888,28 @@ impl SendFilesSubscriber for FileSendSubscriber {
 
         if event.remaining == 0 {
             pb.finish_with_message(format!("[DONE] Sent {}", event.name));
+            record(
+                &self.journal,
+                JournalEvent::FileCompleted {
+                    name: event.name.clone(),
+                    len: event.sent,
+                },
+            );
         } else {
             pb.set_message(format!("Sending {}", event.name));
         }
     }
 
     fn notify_connecting(&self, event: SendFilesConnectingEvent) {
+        record(
+            &self.journal,
+            JournalEvent::Connected {
+                peer_id: event.receiver.id.clone(),
+                peer_name: event.receiver.name.clone(),
+                files: Vec::new(),
+            },
+        );
+
         let _ = self.mp.println("Connected to receiver:");
         let _ = self
             .mp
@@ -452,31 +917,124 @@ impl SendFilesSubscriber for FileSendSubscriber {
         let _ = self
             .mp
             .println(format!("   ID: {}", event.receiver.id));
+        if let Some(line) = remember_peer(
+            event.receiver.node_id.as_deref(),
+            &event.receiver.name,
+            event.receiver.avatar_b64.as_deref(),
+        ) {
+            let _ = self.mp.println(line);
+        }
+    }
+
+    fn notify_error(&self, event: SendFilesErrorEvent) {
+        if let Ok(bars) = self.bars.read()
+            && let Some(pb) = bars.get(&event.name)
+        {
+            pb.abandon_with_message(format!(
+                "[FAILED] {}: {}",
+                event.name, event.error
+            ));
+        }
+        record(
+            &self.journal,
+            JournalEvent::Error {
+                message: format!(
+                    "Failed to send {}: {}",
+                    event.name, event.error
+                ),
+            },
+        );
+    }
+
+    fn notify_received(&self, event: SendFilesReceivedEvent) {
+        if let Some(line) = self
+            .mover
+            .as_ref()
+            .and_then(|mover| mover.remove(&event.name))
+        {
+            let _ = self.mp.println(line);
+        }
+    }
+
+    fn progress_interval(&self) -> Option<Duration> {
+        Some(PROGRESS_INTERVAL)
     }
 }
 
 struct FileReceiveSubscriber {
     id: String,
-    receiving_path: PathBuf,
+    session_dir: SessionDir,
     files: RwLock<Vec<ReceiveFilesFile>>,
     verbose: bool,
     mp: MultiProgress,
     bars: RwLock<HashMap<String, ProgressBar>>,
     received: RwLock<HashMap<String, u64>>,
-    // Cache file handles to avoid reopening on every chunk
-    file_handles: RwLock<HashMap<String, fs::File>>,
+    writers: FileWriters,
+    journal: Option<Arc<Journal>>,
 }
 impl FileReceiveSubscriber {
-    fn new(receiving_path: PathBuf, verbose: bool) -> Self {
+    fn new(
+        session_dir: SessionDir,
+        verbose: bool,
+        journal: Option<Arc<Journal>>,
+    ) -> Self {
         Self {
             id: Uuid::new_v4().to_string(),
-            receiving_path,
+            session_dir,
             files: RwLock::new(Vec::new()),
             verbose,
             mp: MultiProgress::new(),
             bars: RwLock::new(HashMap::new()),
             received: RwLock::new(HashMap::new()),
-            file_handles: RwLock::new(HashMap::new()),
+            writers: FileWriters::new(),
+            journal,
+        }
+    }
+
+    /// Returns true once a file couldn't be written for lack of space.
+    fn is_disk_full(&self) -> bool {
+        self.writers.is_disk_full()
+    }
+
+    /// Returns the session once the sender connected, with the items
+    /// received completely.
+    fn session(&self) -> Option<ReceivedSession> {
+        let dir = self.session_dir.path()?;
+        let (Ok(files), Ok(received)) =
+            (self.files.read(), self.received.read())
+        else {
+            return Some(ReceivedSession::new(dir, []));
+        };
+        let complete = files
+            .iter()
+            .filter(|file| received.get(&file.id) == Some(&file.len))
+            .map(|file| (file.kind, file.name.as_str()));
+        Some(ReceivedSession::new(dir, complete))
+    }
+
+    /// Waits for the received data to be written and synced to disk, and
+    /// reports the files which couldn't be written.
+    fn finish_writes(&self) {
+        report_write_errors(&self.journal, self.writers.finish_all());
+    }
+
+    /// Records in the journal whether every announced file was received
+    /// with its announced size.
+    fn verify(&self) {
+        let (Ok(files), Ok(received)) =
+            (self.files.read(), self.received.read())
+        else {
+            return;
+        };
+        for file in files.iter() {
+            record(
+                &self.journal,
+                JournalEvent::Verified {
+                    name: file.name.clone(),
+                    expected_len: file.len,
+                    received_len: received.get(&file.id).copied().unwrap_or(0),
+                },
+            );
         }
     }
 
@@ -525,15 +1083,9 @@ impl ReceiveFilesSubscriber for FileReceiveSubscriber {
             }
         };
         let pb = bars.entry(event.id.clone()).or_insert_with(|| {
-            // Use spinner for receivers (file size not known initially)
-            let pb = self.mp.add(ProgressBar::new_spinner());
-            pb.set_style(
-                ProgressStyle::with_template(
-                    "{spinner:.green} {msg} {bytes} ({bytes_per_sec})",
-                )
-                .unwrap_or_else(|_| ProgressStyle::default_spinner()),
-            );
-            pb.enable_steady_tick(std::time::Duration::from_millis(100));
+            // The size is known from the handshake file list
+            let pb = self.mp.add(ProgressBar::new(file.len));
+            pb.set_style(Self::bar_style());
             pb.set_message(format!("Receiving {}", file.name));
             pb
         });
@@ -561,69 +1113,92 @@ impl ReceiveFilesSubscriber for FileReceiveSubscriber {
                         "[DONE] Received {}",
                         file.name
                     ));
+                    record(
+                        &self.journal,
+                        JournalEvent::FileCompleted {
+                            name: file.name.clone(),
+                            len: *entry,
+                        },
+                    );
                 }
             } else {
                 pb.inc(event.data.len() as u64);
             }
         }
 
-        let file_path = self.receiving_path.join(&file.name);
-
-        // Get or create cached file handle
-        let mut file_handles = match self.file_handles.write() {
-            Ok(handles) => handles,
+        let Some(receiving_path) = self.session_dir.path() else {
+            return;
+        };
+        let file_path = match received_file_path(&receiving_path, &file.name) {
+            Ok(path) => path,
             Err(e) => {
-                eprintln!("[ERROR] Error accessing file handles: {}", e);
+                eprintln!("[ERROR] {}", e);
                 return;
             }
         };
-        let file_handle = match file_handles.entry(event.id.clone()) {
-            std::collections::hash_map::Entry::Occupied(entry) => {
-                entry.into_mut()
-            }
-            std::collections::hash_map::Entry::Vacant(entry) => {
-                // Create parent directories if they don't exist
-                if let Some(parent) = file_path.parent() {
-                    if !parent.exists() {
-                        if let Err(e) = fs::create_dir_all(parent) {
-                            eprintln!(
-                                "[ERROR] Failed to create directory {}: {}",
-                                parent.display(),
-                                e
-                            );
-                            return;
-                        }
-                    }
-                }
-                match fs::File::options()
-                    .create(true)
-                    .append(true)
-                    .open(&file_path)
-                {
-                    Ok(f) => entry.insert(f),
-                    Err(e) => {
-                        eprintln!(
-                            "[ERROR] Failed to open file {}: {}",
-                            file_path.display(),
-                            e
-                        );
-                        return;
-                    }
-                }
-            }
-        };
 
-        // Write to the cached file handle
-        if let Err(e) = file_handle.write_all(&event.data) {
-            eprintln!("[ERROR] Error writing to file {}: {}", file.name, e);
+        self.writers
+            .write(&event.id, &file_path, event.data);
+    }
+
+    fn notify_received(&self, event: ReceiveFilesReceivedEvent) -> bool {
+        // Confirmed to the sender, which may delete the source, once synced
+        self.writers.sync(&event.id, event.modified)
+    }
+
+    fn notify_discarded(&self, event: ReceiveFilesDiscardedEvent) {
+        let Some(name) = self.files.read().ok().and_then(|files| {
+            files
+                .iter()
+                .find(|f| f.id == event.id)
+                .map(|f| f.name.clone())
+        }) else {
             return;
+        };
+
+        // Not received anymore, so not counted as complete or moved
+        if let Ok(mut received) = self.received.write() {
+            received.remove(&event.id);
+        }
+        if let Some(pb) = self
+            .bars
+            .read()
+            .ok()
+            .and_then(|bars| bars.get(&event.id).cloned())
+        {
+            pb.abandon_with_message(format!("[DISCARDED] {name}"));
         }
-        if let Err(e) = file_handle.flush() {
-            eprintln!("[ERROR] Error flushing file {}: {}", file.name, e);
+        record(
+            &self.journal,
+            JournalEvent::Error {
+                message: format!("Discarded {name}, it failed verification"),
+            },
+        );
+
+        if let Some(dir) = self.session_dir.path()
+            && let Ok(path) = received_file_path(&dir, &name)
+        {
+            self.writers.discard(&event.id, &path);
         }
     }
 
     fn notify_connecting(&self, event: ReceiveFilesConnectingEvent) {
+        record(
+            &self.journal,
+            JournalEvent::Connected {
+                peer_id: event.sender.id.clone(),
+                peer_name: event.sender.name.clone(),
+                files: event
+                    .files
+                    .iter()
+                    .map(|f| JournalFile {
+                        name: f.name.clone(),
+                        len: f.len,
+                    })
+                    .collect(),
+            },
+        );
+
         let _ = self.mp.println("Connected to sender:");
         let _ = self
             .mp
@@ -631,6 +1206,13 @@ impl ReceiveFilesSubscriber for FileReceiveSubscriber {
         let _ = self
             .mp
             .println(format!("   ID: {}", event.sender.id));
+        if let Some(line) = remember_peer(
+            event.sender.node_id.as_deref(),
+            &event.sender.name,
+            event.sender.avatar_b64.as_deref(),
+        ) {
+            let _ = self.mp.println(line);
+        }
         let _ = self
             .mp
             .println(format!("   Files to receive: {}", event.files.len()));
@@ -639,6 +1221,27 @@ impl ReceiveFilesSubscriber for FileReceiveSubscriber {
             let _ = self.mp.println(format!("     - {}", f.name));
         }
 
+        match self
+            .session_dir
+            .create(event.sender.node_id.as_deref(), &event.sender.name)
+        {
+            Ok(path) => {
+                let _ = self.mp.println(format!(
+                    "📁 Files will be saved to: {}",
+                    path.display()
+                ));
+            }
+            Err(e) => {
+                eprintln!("[ERROR] {e:#}");
+                record(
+                    &self.journal,
+                    JournalEvent::Error {
+                        message: format!("{e:#}"),
+                    },
+                );
+            }
+        }
+
         // Keep the list of files and prepare bars if sizes are known
         match self.files.write() {
             Ok(mut files) => {
@@ -668,259 +1271,6 @@ impl ReceiveFilesSubscriber for FileReceiveSubscriber {
     }
 }
 
-/// In-memory, seek-based file data source for the sender.
-///
-/// This implementation:
-/// - Supports both single-byte reads (`read`) and ranged chunk reads
-///   (`read_chunk`).
-/// - Uses atomic counters to coordinate chunked read offsets safely.
-/// - Reports its total length through `len`.
-///
-/// Notes:
-/// - Errors are logged and will mark the stream as finished to prevent
-///   stalling.
-pub struct FileData {
-    is_finished: AtomicBool,
-    path: PathBuf,
-    reader: RwLock<Option<std::fs::File>>,
-    // Dedicated file handle for positioned chunk reads
-    chunk_reader: std::sync::Mutex<Option<std::fs::File>>,
-    size: u64,
-    bytes_read: std::sync::atomic::AtomicU64,
-}
-
-impl FileData {
-    /// Create a new FileData for the given path, capturing size metadata.
-    ///
-    /// Errors:
-    /// - If the file's metadata cannot be read.
-    pub fn new(path: PathBuf) -> Result<Self> {
-        let metadata = fs::metadata(&path).with_context(|| {
-            format!("Failed to get metadata for file: {}", path.display())
-        })?;
-
-        Ok(Self {
-            is_finished: AtomicBool::new(false),
-            path,
-            reader: RwLock::new(None),
-            chunk_reader: std::sync::Mutex::new(None),
-            size: metadata.len(),
-            bytes_read: std::sync::atomic::AtomicU64::new(0),
-        })
-    }
-}
-
-impl SenderFileData for FileData {
-    /// Returns the total file size in bytes.
-    fn len(&self) -> u64 {
-        self.size
-    }
-
-    /// Checks if the data is empty (length is 0).
-    fn is_empty(&self) -> bool {
-        self.size == 0
-    }
-
-    /// Reads a single byte, falling back to EOF (None) at end of file or on
-    /// errors.
-    fn read(&self) -> Option<u8> {
-        use std::io::Read;
-
-        if self
-            .is_finished
-            .load(std::sync::atomic::Ordering::Relaxed)
-        {
-            return None;
-        }
-
-        let is_reader_none = match self.reader.read() {
-            Ok(guard) => guard.is_none(),
-            Err(e) => {
-                eprintln!(
-                    "Error acquiring read lock for file {}: {}",
-                    self.path.display(),
-                    e
-                );
-                self.is_finished
-                    .store(true, std::sync::atomic::Ordering::Relaxed);
-                return None;
-            }
-        };
-
-        if is_reader_none {
-            match std::fs::File::open(&self.path) {
-                Ok(file) => match self.reader.write() {
-                    Ok(mut guard) => *guard = Some(file),
-                    Err(e) => {
-                        eprintln!(
-                            "Error acquiring write lock for file {}: {}",
-                            self.path.display(),
-                            e
-                        );
-                        self.is_finished
-                            .store(true, std::sync::atomic::Ordering::Relaxed);
-                        return None;
-                    }
-                },
-                Err(e) => {
-                    eprintln!(
-                        "[ERROR] Error opening file {}: {}",
-                        self.path.display(),
-                        e
-                    );
-                    self.is_finished
-                        .store(true, std::sync::atomic::Ordering::Relaxed);
-                    return None;
-                }
-            }
-        }
-
-        // Read next byte
-        let mut reader = match self.reader.write() {
-            Ok(guard) => guard,
-            Err(e) => {
-                eprintln!(
-                    "Error acquiring write lock for file {}: {}",
-                    self.path.display(),
-                    e
-                );
-                self.is_finished
-                    .store(true, std::sync::atomic::Ordering::Relaxed);
-                return None;
-            }
-        };
-        if let Some(file) = reader.as_mut() {
-            let mut buffer = [0u8; 1];
-            match file.read(&mut buffer) {
-                Ok(bytes_read) => {
-                    if bytes_read == 0 {
-                        *reader = None;
-                        self.is_finished
-                            .store(true, std::sync::atomic::Ordering::Relaxed);
-                        None
-                    } else {
-                        Some(buffer[0])
-                    }
-                }
-                Err(e) => {
-                    eprintln!(
-                        "[ERROR] Error reading from file {}: {}",
-                        self.path.display(),
-                        e
-                    );
-                    *reader = None;
-                    self.is_finished
-                        .store(true, std::sync::atomic::Ordering::Relaxed);
-                    None
-                }
-            }
-        } else {
-            None
-        }
-    }
-
-    /// Reads up to `size` bytes as a contiguous chunk starting from the next
-    /// claimed position. Returns an empty Vec when the file is fully consumed
-    /// or on errors.
-    fn read_chunk(&self, size: u64) -> Vec<u8> {
-        use std::{
-            io::{Read, Seek, SeekFrom},
-            sync::atomic::Ordering,
-        };
-
-        if self.is_finished.load(Ordering::Acquire) {
-            return Vec::new();
-        }
-
-        // Atomically claim the next chunk position
-        let current_position =
-            self.bytes_read.fetch_add(size, Ordering::AcqRel);
-
-        // Check if we've already passed the end of the file
-        if current_position >= self.size {
-            // Reset the bytes_read counter and mark as finished
-            self.bytes_read
-                .store(self.size, Ordering::Release);
-            self.is_finished.store(true, Ordering::Release);
-            return Vec::new();
-        }
-
-        // Calculate how much to actually read (don't exceed file size)
-        let remaining = self.size - current_position;
-        let to_read = std::cmp::min(size, remaining) as usize;
-
-        // Get or create the cached file handle
-        let mut chunk_reader_guard = match self.chunk_reader.lock() {
-            Ok(guard) => guard,
-            Err(e) => {
-                eprintln!(
-                    "[ERROR] Error acquiring lock for file {}: {}",
-                    self.path.display(),
-                    e
-                );
-                self.is_finished.store(true, Ordering::Release);
-                return Vec::new();
-            }
-        };
-
-        // Open file handle if not already open
-        if chunk_reader_guard.is_none() {
-            match std::fs::File::open(&self.path) {
-                Ok(file) => {
-                    *chunk_reader_guard = Some(file);
-                }
-                Err(e) => {
-                    eprintln!(
-                        "[ERROR] Error opening file {}: {}",
-                        self.path.display(),
-                        e
-                    );
-                    self.is_finished.store(true, Ordering::Release);
-                    return Vec::new();
-                }
-            }
-        }
-
-        let file = chunk_reader_guard
-            .as_mut()
-            .expect("File handle must exist after initialization");
-
-        // Seek to the claimed position
-        if let Err(e) = file.seek(SeekFrom::Start(current_position)) {
-            eprintln!(
-                "[ERROR] Error seeking to position {} in file {}: {}",
-                current_position,
-                self.path.display(),
-                e
-            );
-            self.is_finished.store(true, Ordering::Release);
-            return Vec::new();
-        }
-
-        // Read the chunk
-        let mut buffer = vec![0u8; to_read];
-        match file.read_exact(&mut buffer) {
-            Ok(()) => {
-                // Check if we've finished reading the entire file
-                if current_position + to_read as u64 >= self.size {
-                    self.is_finished.store(true, Ordering::Release);
-                }
-
-                buffer
-            }
-            Err(e) => {
-                eprintln!(
-                    "[ERROR] Error reading chunk from file {}: {}",
-                    self.path.display(),
-                    e
-                );
-                self.is_finished.store(true, Ordering::Release);
-                Vec::new()
-            }
-        }
-    }
-}
-
 /// Run a send operation with the provided list of file paths.
 ///
 /// This is a convenience wrapper used by the CLI. It constructs a FileSender
@@ -930,6 +1280,8 @@ impl SenderFileData for FileData {
 /// - file_paths: Paths to regular files to be sent. Each path must exist.
 /// - profile: The local user profile to present to the receiver.
 /// - verbose: Enables transport logs and extra diagnostics.
+/// - move_sources: If true, deletes each file once the receiver confirmed
+///   it got it in full. Files it doesn't confirm are kept.
 ///
 /// Errors:
 /// - If any path is invalid or if the transport fails to initialize.
@@ -939,7 +1291,7 @@ impl SenderFileData for FileData {
 /// use arkdrop_cli::{run_send_files};
 /// use arkdrop_common::Profile;
 /// # async fn demo() -> anyhow::Result<()> {
-/// run_send_files(vec!["/tmp/a.bin".into()], Profile::default(), false).await?;
+/// run_send_files(vec!["/tmp/a.bin".into()], Profile::default(), false, false).await?;
 /// # Ok(())
 /// # }
 /// ```
@@ -947,18 +1299,72 @@ pub async fn run_send_files(
     file_paths: Vec<String>,
     profile: Profile,
     verbose: bool,
+    move_sources: bool,
 ) -> Result<()> {
     let paths: Vec<PathBuf> = file_paths
         .into_iter()
         .map(PathBuf::from)
         .collect();
+    let named_files = collect_files(&paths)?;
+    let mover = move_sources.then(|| Arc::new(SourceMover::new(&named_files)));
+    let files = create_sender_files(named_files)?;
     let sender = FileSender::new(profile);
-    sender.send_files(paths, verbose).await
+    sender.send_files(files, verbose, mover).await
+}
+
+/// Run a send operation with files named by the caller.
+///
+/// Unlike [`run_send_files`], paths are not expanded: each file is sent
+/// under its given name, which may contain directories separated by `/`.
+/// This lets callers send a selection of files, e.g. resources of an index,
+/// with their path relative to a common root.
+///
+/// Errors:
+/// - If any path is missing or if the transport fails to initialize.
+pub async fn run_send_named_files(
+    named_files: Vec<(String, PathBuf)>,
+    profile: Profile,
+    verbose: bool,
+) -> Result<()> {
+    let files = create_sender_files(named_files)?;
+    let sender = FileSender::new(profile);
+    sender.send_files(files, verbose, None).await
+}
+
+/// Run a send operation with named files and items.
+///
+/// Links are sent as [`ItemKind::Link`] items, so that receivers aware of
+/// them can save them into their library, see [`ReceivedSession::links`].
+/// Other receivers get their serialized form as a file with the given name.
+/// User data of resources is sent the same way as an [`ItemKind::UserData`]
+/// item, see [`ReceivedSession::user_data`].
+///
+/// Errors:
+/// - If there is nothing to send or any path is missing.
+/// - If the transport fails to initialize.
+pub async fn run_send_items(
+    named_files: Vec<(String, PathBuf)>,
+    links: Vec<(String, LinkItem)>,
+    user_data: Option<Vec<u8>>,
+    profile: Profile,
+    verbose: bool,
+) -> Result<()> {
+    let mut files = Vec::new();
+    if !named_files.is_empty() {
+        files = create_sender_files(named_files)?;
+    }
+    for (name, link) in &links {
+        files.push(link_sender_file(name.clone(), link)?);
+    }
+    files.extend(user_data.map(user_data_sender_file));
+    let sender = FileSender::new(profile);
+    sender.send_files(files, verbose, None).await
 }
 
 /// Run a receive operation, optionally persisting the chosen output directory.
 ///
-/// If `out_dir` is None, a previously saved default directory is used.
+/// If `out_dir` is None, the directory configured for the sender is used,
+/// else a previously saved default directory.
 /// If no saved default exists, a sensible fallback is chosen:
 /// - $HOME/Downloads/ARK-Drop if HOME is set
 /// - or the current directory (.) otherwise
@@ -970,6 +1376,11 @@ pub async fn run_send_files(
 /// - profile: The local user profile to present to the sender.
 /// - verbose: Enables transport logs and extra diagnostics.
 /// - save_out: If true and `out_dir` is Some, saves it as the default.
+/// - max_size: Largest total size in bytes accepted from the sender, the
+///   limit from the config if None.
+///
+/// Returns the folder the files were saved to with the link items received,
+/// `None` if the sender never connected.
 ///
 /// Errors:
 /// - If the confirmation code is invalid.
@@ -981,29 +1392,31 @@ pub async fn run_send_files(
 /// use arkdrop_common::Profile;
 /// # async fn demo() -> anyhow::Result<()> {
 /// run_receive_files(
-///     "/tmp/downloads".into(),
+///     Some("/tmp/downloads".into()),
 ///     "TICKET".into(),
 ///     "3".into(),
 ///     Profile::default(),
 ///     false,
-///     true
+///     true,
+///     None,
 /// ).await?;
 /// # Ok(())
 /// # }
 /// ```
 pub async fn run_receive_files(
-    out_dir: PathBuf,
+    out_dir: Option<PathBuf>,
     ticket: String,
     confirmation: String,
     profile: Profile,
     verbose: bool,
     save_out: bool,
-) -> Result<()> {
+    max_size: Option<u64>,
+) -> Result<Option<ReceivedSession>> {
     let confirmation_code = u8::from_str(&confirmation).with_context(|| {
         format!("Invalid confirmation code: {confirmation}")
     })?;
 
-    if save_out {
+    if save_out && let Some(out_dir) = &out_dir {
         let mut config = AppConfig::load()?;
         config.set_out_dir(out_dir.clone()).with_context(
             || "Failed to save default output receive directory",
@@ -1016,7 +1429,7 @@ pub async fn run_receive_files(
 
     let receiver = FileReceiver::new(profile);
     receiver
-        .receive_files(out_dir, ticket, confirmation_code, verbose)
+        .receive_files(out_dir, ticket, confirmation_code, verbose, max_size)
         .await
 }
 
@@ -1071,6 +1484,9 @@ async fn run_cli_subcommand(
         Some(("send-to", sub_matches)) => {
             handle_send_to_command(sub_matches).await
         }
+        Some(("history", sub_matches)) => {
+            handle_history_command(sub_matches).await
+        }
         _ => {
             eprintln!("❌ Invalid command. Use --help for usage information.");
             std::process::exit(1);
@@ -1096,7 +1512,7 @@ pub fn build_cli() -> Command {
                 .about("Send files to another user")
                 .arg(
                     Arg::new("files")
-                        .help("Files to send")
+                        .help("Files or directories to send")
                         .required(true)
                         .num_args(1..)
                         .value_parser(clap::value_parser!(PathBuf))
@@ -1121,6 +1537,12 @@ pub fn build_cli() -> Command {
                         .help("Base64 encoded avatar image (alternative to --avatar)")
                         .conflicts_with("avatar")
                 )
+                .arg(
+                    Arg::new("move")
+                        .long("move")
+                        .help("Delete each file once the receiver confirmed it got it in full")
+                        .action(clap::ArgAction::SetTrue)
+                )
         )
         .subcommand(
             Command::new("receive")
@@ -1173,6 +1595,12 @@ pub fn build_cli() -> Command {
                         .help("Base64 encoded avatar image (alternative to --avatar)")
                         .conflicts_with("avatar")
                 )
+                .arg(
+                    Arg::new("max-size")
+                        .long("max-size")
+                        .help("Refuse sessions larger than this size, e.g. 500M or 10G (defaults to the configured limit)")
+                        .value_parser(parse_size)
+                )
         )
         .subcommand(
             Command::new("config")
@@ -1195,6 +1623,117 @@ pub fn build_cli() -> Command {
                     Command::new("clear-output")
                         .about("Clear default receive directory")
                 )
+                .subcommand(
+                    Command::new("set-peer-output")
+                        .about("Set the receive output directory for files from a peer")
+                        .arg(
+                            Arg::new("peer")
+                                .help("Node id or display name of the peer")
+                                .required(true)
+                        )
+                        .arg(
+                            Arg::new("output")
+                                .help("Output directory path for the peer")
+                                .required(true)
+                                .value_parser(clap::value_parser!(PathBuf))
+                        )
+                )
+                .subcommand(
+                    Command::new("clear-peer-output")
+                        .about("Clear the receive output directory of a peer")
+                        .arg(
+                            Arg::new("peer")
+                                .help("Node id or display name of the peer")
+                                .required(true)
+                        )
+                )
+                .subcommand(
+                    Command::new("enable-journal")
+                        .about("Record transfers in the journal shown by the history command")
+                )
+                .subcommand(
+                    Command::new("disable-journal")
+                        .about("Stop recording transfers in the journal")
+                )
+                .subcommand(
+                    Command::new("set-proxy")
+                        .about("Reach relays through an HTTP or SOCKS5 proxy")
+                        .arg(
+                            Arg::new("url")
+                                .help("Proxy URL, e.g. socks5://127.0.0.1:1080")
+                                .required(true)
+                        )
+                )
+                .subcommand(
+                    Command::new("clear-proxy")
+                        .about("Stop using a proxy")
+                )
+                .subcommand(
+                    Command::new("set-bind-address")
+                        .about("Bind transfers to the interface with this address")
+                        .arg(
+                            Arg::new("address")
+                                .help("IPv4 or IPv6 address of a local interface")
+                                .required(true)
+                                .value_parser(clap::value_parser!(IpAddr))
+                        )
+                )
+                .subcommand(
+                    Command::new("clear-bind-address")
+                        .about("Bind transfers to all interfaces")
+                )
+                .subcommand(
+                    Command::new("set-upload-limit")
+                        .about("Limit the upload rate of transfers")
+                        .arg(
+                            Arg::new("rate")
+                                .help("Bytes per second, e.g. 512K or 2M")
+                                .required(true)
+                                .value_parser(parse_rate)
+                        )
+                )
+                .subcommand(
+                    Command::new("clear-upload-limit")
+                        .about("Upload without a rate limit")
+                )
+                .subcommand(
+                    Command::new("set-download-limit")
+                        .about("Limit the download rate of transfers")
+                        .arg(
+                            Arg::new("rate")
+                                .help("Bytes per second, e.g. 512K or 2M")
+                                .required(true)
+                                .value_parser(parse_rate)
+                        )
+                )
+                .subcommand(
+                    Command::new("clear-download-limit")
+                        .about("Download without a rate limit")
+                )
+                .subcommand(
+                    Command::new("set-max-receive-size")
+                        .about("Refuse received sessions larger than a size")
+                        .arg(
+                            Arg::new("size")
+                                .help("Total size of the files, e.g. 500M or 10G")
+                                .required(true)
+                                .value_parser(parse_size)
+                        )
+                )
+                .subcommand(
+                    Command::new("clear-max-receive-size")
+                        .about("Receive sessions of any size")
+                )
+                .subcommand(
+                    Command::new("set-preset")
+                        .about("Set the chunk size and parallel streams of transfers")
+                        .arg(
+                            Arg::new("preset")
+                                .help("Performance preset")
+                                .required(true)
+                                .value_parser(PerformancePreset::ALL.map(|preset| preset.name()))
+                        )
+                )
         )
         .subcommand(
             Command::new("wait-to-receive")
@@ -1235,6 +1774,12 @@ pub fn build_cli() -> Command {
                         .help("Base64 encoded avatar image (alternative to --avatar)")
                         .conflicts_with("avatar")
                 )
+                .arg(
+                    Arg::new("max-size")
+                        .long("max-size")
+                        .help("Refuse sessions larger than this size, e.g. 500M or 10G (defaults to the configured limit)")
+                        .value_parser(parse_size)
+                )
         )
         .subcommand(
             Command::new("send-to")
@@ -1253,7 +1798,7 @@ pub fn build_cli() -> Command {
                 )
                 .arg(
                     Arg::new("files")
-                        .help("Files to send")
+                        .help("Files or directories to send")
                         .required(true)
                         .index(3)
                         .num_args(1..)
@@ -1279,6 +1824,22 @@ pub fn build_cli() -> Command {
                         .help("Base64 encoded avatar image (alternative to --avatar)")
                         .conflicts_with("avatar")
                 )
+                .arg(
+                    Arg::new("move")
+                        .long("move")
+                        .help("Delete each file once the receiver confirmed it got it in full")
+                        .action(clap::ArgAction::SetTrue)
+                )
+        )
+        .subcommand(
+            Command::new("history")
+                .about("Show past transfers recorded in the journal")
+                .arg(
+                    Arg::new("session")
+                        .long("session")
+                        .short('s')
+                        .help("Print the journal lines of the session with this ID (or ID prefix), e.g. to attach them to a bug report")
+                )
         )
 }
 
@@ -1290,6 +1851,7 @@ async fn handle_send_command(matches: &ArgMatches) -> Result<()> {
         .collect();
 
     let verbose: bool = matches.get_flag("verbose");
+    let move_sources = matches.get_flag("move");
 
     let profile = build_profile(matches)?;
 
@@ -1309,7 +1871,7 @@ async fn handle_send_command(matches: &ArgMatches) -> Result<()> {
         .map(|p| p.to_string_lossy().to_string())
         .collect();
 
-    run_send_files(file_strings, profile, verbose).await
+    run_send_files(file_strings, profile, verbose, move_sources).await
 }
 
 async fn handle_receive_command(matches: &ArgMatches) -> Result<()> {
@@ -1320,16 +1882,11 @@ async fn handle_receive_command(matches: &ArgMatches) -> Result<()> {
     let confirmation = matches.get_one::<String>("confirmation").unwrap();
     let verbose = matches.get_flag("verbose");
     let save_output = matches.get_flag("save-output");
+    let max_size = matches.get_one::<u64>("max-size").copied();
 
     let profile = build_profile(matches)?;
 
     println!("📥 Preparing to receive files...");
-
-    let out_dir = match out_dir {
-        Some(o) => o,
-        None => get_default_out_dir(),
-    };
-
     println!("👤 Receiver name: {}", profile.name);
 
     if profile.avatar_b64.is_some() {
@@ -1343,6 +1900,7 @@ async fn handle_receive_command(matches: &ArgMatches) -> Result<()> {
         profile,
         verbose,
         save_output,
+        max_size,
     )
     .await?;
 
@@ -1357,6 +1915,41 @@ async fn handle_config_command(matches: &ArgMatches) -> Result<()> {
                 "📁 Default receive output directory: {}",
                 out_dir.display()
             );
+            let config = AppConfig::load()?;
+            for (peer, dir) in &config.peer_out_dirs {
+                println!("📁 Receive directory for {peer}: {}", dir.display());
+            }
+            let journal = if config.journal {
+                "enabled"
+            } else {
+                "disabled"
+            };
+            println!(
+                "📓 Journal: {journal} ({})",
+                Journal::default_path()?.display()
+            );
+            let network = AppConfig::load()?.network;
+            println!(
+                "🌐 Proxy: {}",
+                network.proxy.as_deref().unwrap_or("none")
+            );
+            match network.bind_address {
+                Some(address) => println!("🔌 Bind address: {address}"),
+                None => println!("🔌 Bind address: all interfaces"),
+            }
+            println!(
+                "📤 Upload limit: {}",
+                format_rate(config.limits.max_upload_rate)
+            );
+            println!(
+                "📥 Download limit: {}",
+                format_rate(config.limits.max_download_rate)
+            );
+            println!(
+                "📦 Max receive size: {}",
+                format_size(config.limits.max_receive_size)
+            );
+            println!("⚙️  Performance preset: {}", config.preset.name());
         }
 
         Some(("set-output", sub_matches)) => {
@@ -1389,6 +1982,134 @@ async fn handle_config_command(matches: &ArgMatches) -> Result<()> {
             clear_default_out_dir()?;
             println!("✅ Cleared default receive output directory");
         }
+
+        Some(("set-peer-output", sub_matches)) => {
+            let peer = sub_matches.get_one::<String>("peer").unwrap();
+            let out_dir = sub_matches.get_one::<PathBuf>("output").unwrap();
+            AppConfig::load()?
+                .set_peer_out_dir(peer.clone(), out_dir.clone())?;
+            println!(
+                "✅ Files from {peer} will be saved to: {}",
+                out_dir.display()
+            );
+        }
+
+        Some(("clear-peer-output", sub_matches)) => {
+            let peer = sub_matches.get_one::<String>("peer").unwrap();
+            if AppConfig::load()?.remove_peer_out_dir(peer)? {
+                println!("✅ Cleared receive directory of {peer}");
+            } else {
+                println!("ℹ️  No receive directory set for {peer}");
+            }
+        }
+
+        Some(("enable-journal", _)) => {
+            AppConfig::load()?.set_journal(true)?;
+            println!(
+                "✅ Transfers will be recorded in: {}",
+                Journal::default_path()?.display()
+            );
+        }
+
+        Some(("disable-journal", _)) => {
+            AppConfig::load()?.set_journal(false)?;
+            println!("✅ Transfers will no longer be recorded");
+        }
+
+        Some(("set-proxy", sub_matches)) => {
+            let url = sub_matches.get_one::<String>("url").unwrap();
+            let mut config = AppConfig::load()?;
+            let mut network = config.network.clone();
+            network.proxy = Some(url.clone());
+            // Fails early on URLs the endpoint would reject
+            network.endpoint_config().builder()?;
+            config.set_network(network)?;
+            println!("✅ Transfers will reach relays through: {url}");
+        }
+
+        Some(("clear-proxy", _)) => {
+            let mut config = AppConfig::load()?;
+            let mut network = config.network.clone();
+            network.proxy = None;
+            config.set_network(network)?;
+            println!("✅ Cleared proxy");
+        }
+
+        Some(("set-bind-address", sub_matches)) => {
+            let address = *sub_matches.get_one::<IpAddr>("address").unwrap();
+            let mut config = AppConfig::load()?;
+            let mut network = config.network.clone();
+            network.bind_address = Some(address);
+            config.set_network(network)?;
+            println!("✅ Transfers will bind to: {address}");
+        }
+
+        Some(("clear-bind-address", _)) => {
+            let mut config = AppConfig::load()?;
+            let mut network = config.network.clone();
+            network.bind_address = None;
+            config.set_network(network)?;
+            println!("✅ Transfers will bind to all interfaces");
+        }
+
+        Some(("set-upload-limit", sub_matches)) => {
+            let rate = *sub_matches.get_one::<u64>("rate").unwrap();
+            let mut config = AppConfig::load()?;
+            let mut limits = config.limits.clone();
+            limits.max_upload_rate = Some(rate);
+            config.set_limits(limits)?;
+            println!("✅ Upload limit: {}", format_rate(Some(rate)));
+        }
+
+        Some(("clear-upload-limit", _)) => {
+            let mut config = AppConfig::load()?;
+            let mut limits = config.limits.clone();
+            limits.max_upload_rate = None;
+            config.set_limits(limits)?;
+            println!("✅ Cleared upload limit");
+        }
+
+        Some(("set-download-limit", sub_matches)) => {
+            let rate = *sub_matches.get_one::<u64>("rate").unwrap();
+            let mut config = AppConfig::load()?;
+            let mut limits = config.limits.clone();
+            limits.max_download_rate = Some(rate);
+            config.set_limits(limits)?;
+            println!("✅ Download limit: {}", format_rate(Some(rate)));
+        }
+
+        Some(("clear-download-limit", _)) => {
+            let mut config = AppConfig::load()?;
+            let mut limits = config.limits.clone();
+            limits.max_download_rate = None;
+            config.set_limits(limits)?;
+            println!("✅ Cleared download limit");
+        }
+
+        Some(("set-max-receive-size", sub_matches)) => {
+            let size = *sub_matches.get_one::<u64>("size").unwrap();
+            let mut config = AppConfig::load()?;
+            let mut limits = config.limits.clone();
+            limits.max_receive_size = Some(size);
+            config.set_limits(limits)?;
+            println!("✅ Max receive size: {}", format_size(Some(size)));
+        }
+
+        Some(("clear-max-receive-size", _)) => {
+            let mut config = AppConfig::load()?;
+            let mut limits = config.limits.clone();
+            limits.max_receive_size = None;
+            config.set_limits(limits)?;
+            println!("✅ Cleared max receive size");
+        }
+
+        Some(("set-preset", sub_matches)) => {
+            let name = sub_matches.get_one::<String>("preset").unwrap();
+            // Only known names pass the value parser
+            let preset = PerformancePreset::from_name(name).unwrap();
+            AppConfig::load()?.set_preset(preset)?;
+            println!("✅ Performance preset: {name}");
+        }
         _ => {
             eprintln!(
                 "❌ Invalid config command. Use --help for usage information."
@@ -1399,12 +2120,69 @@ async fn handle_config_command(matches: &ArgMatches) -> Result<()> {
     Ok(())
 }
 
+async fn handle_history_command(matches: &ArgMatches) -> Result<()> {
+    let entries = read_journal(&Journal::default_path()?)?;
+
+    if let Some(session_id) = matches.get_one::<String>("session") {
+        let lines = entries
+            .iter()
+            .filter(|entry| entry.session_id.starts_with(session_id.as_str()))
+            .map(serde_json::to_string)
+            .collect::<serde_json::Result<Vec<_>>>()?;
+        if lines.is_empty() {
+            return Err(anyhow!("No session found with ID: {session_id}"));
+        }
+        for line in lines {
+            println!("{line}");
+        }
+        return Ok(());
+    }
+
+    let sessions = summarize_sessions(&entries);
+    if sessions.is_empty() {
+        println!("📭 No transfers recorded");
+        if !AppConfig::load()?.journal {
+            println!(
+                "   Enable the journal with: arkdrop config enable-journal"
+            );
+        }
+        return Ok(());
+    }
+
+    for session in sessions {
+        let direction = match session.direction {
+            Some(Direction::Send) => "📤 Sent",
+            Some(Direction::Receive) => "📥 Received",
+            None => "❔ Unknown",
+        };
+        let status = match session.status {
+            SessionStatus::Completed => "✅ completed",
+            SessionStatus::Cancelled => "🚫 cancelled",
+            SessionStatus::Failed => "❌ failed",
+            SessionStatus::Incomplete => "⏳ incomplete",
+        };
+        println!(
+            "{} {direction} {}/{} file(s), {} bytes, {status}",
+            session.started_at.format("%Y-%m-%d %H:%M:%S UTC"),
+            session.completed_files,
+            session.files,
+            session.bytes,
+        );
+        if let Some(peer_name) = &session.peer_name {
+            println!("   👤 Peer: {peer_name}");
+        }
+        println!("   🆔 Session: {}", session.session_id);
+    }
+    Ok(())
+}
+
 async fn handle_wait_to_receive_command(matches: &ArgMatches) -> Result<()> {
     let out_dir = matches
         .get_one::<PathBuf>("output")
         .map(|p| p.to_string_lossy().to_string());
     let verbose = matches.get_flag("verbose");
     let save_output = matches.get_flag("save-output");
+    let max_size = matches.get_one::<u64>("max-size").copied();
 
     let profile = build_profile(matches)?;
 
@@ -1415,7 +2193,7 @@ async fn handle_wait_to_receive_command(matches: &ArgMatches) -> Result<()> {
         println!("🖼️  Avatar: Set");
     }
 
-    run_ready_to_receive(out_dir, profile, verbose, save_output).await
+    run_ready_to_receive(out_dir, profile, verbose, save_output, max_size).await
 }
 
 async fn handle_send_to_command(matches: &ArgMatches) -> Result<()> {
@@ -1427,6 +2205,7 @@ async fn handle_send_to_command(matches: &ArgMatches) -> Result<()> {
         .cloned()
         .collect();
     let verbose = matches.get_flag("verbose");
+    let move_sources = matches.get_flag("move");
 
     let profile = build_profile(matches)?;
 
@@ -1454,6 +2233,7 @@ async fn handle_send_to_command(matches: &ArgMatches) -> Result<()> {
         confirmation.clone(),
         profile,
         verbose,
+        move_sources,
     )
     .await
 }
@@ -1476,6 +2256,25 @@ mod tests {
         assert_eq!(profile.name, "test-user");
         assert_eq!(profile.avatar_b64, Some("dGVzdA==".to_string()));
     }
+
+    #[test]
+    fn test_parse_rate() {
+        assert_eq!(parse_rate("1000"), Ok(1000));
+        assert_eq!(parse_rate("512K"), Ok(512 * 1024));
+        assert_eq!(parse_rate("2MiB/s"), Ok(2 * 1024 * 1024));
+        assert_eq!(parse_rate("1gb"), Ok(1024 * 1024 * 1024));
+        assert!(parse_rate("0").is_err());
+        assert!(parse_rate("fast").is_err());
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("4096"), Ok(4096));
+        assert_eq!(parse_size("10G"), Ok(10 * 1024 * 1024 * 1024));
+        assert_eq!(parse_size("512kib"), Ok(512 * 1024));
+        assert!(parse_size("0").is_err());
+        assert!(parse_size("2M/s").is_err());
+    }
 }
 
 // QR-to-receive helper functions
@@ -1500,27 +2299,74 @@ async fn wait_for_send_files_to_completion(bubble: &SendFilesToBubble) {
 
 struct ReadyToReceiveSubscriberImpl {
     id: String,
-    receiving_path: PathBuf,
+    session_dir: SessionDir,
     files: RwLock<Vec<ReadyToReceiveFile>>,
     verbose: bool,
     mp: MultiProgress,
     bars: RwLock<HashMap<String, ProgressBar>>,
     received: RwLock<HashMap<String, u64>>,
-    // Cache file handles to avoid reopening on every chunk
-    file_handles: RwLock<HashMap<String, fs::File>>,
+    writers: FileWriters,
+    journal: Option<Arc<Journal>>,
 }
 
 impl ReadyToReceiveSubscriberImpl {
-    fn new(receiving_path: PathBuf, verbose: bool) -> Self {
+    fn new(
+        session_dir: SessionDir,
+        verbose: bool,
+        journal: Option<Arc<Journal>>,
+    ) -> Self {
         Self {
             id: Uuid::new_v4().to_string(),
-            receiving_path,
+            session_dir,
             files: RwLock::new(Vec::new()),
             verbose,
             mp: MultiProgress::new(),
             bars: RwLock::new(HashMap::new()),
             received: RwLock::new(HashMap::new()),
-            file_handles: RwLock::new(HashMap::new()),
+            writers: FileWriters::new(),
+            journal,
+        }
+    }
+
+    /// Returns the session once the sender connected, with the items
+    /// received completely.
+    fn session(&self) -> Option<ReceivedSession> {
+        let dir = self.session_dir.path()?;
+        let (Ok(files), Ok(received)) =
+            (self.files.read(), self.received.read())
+        else {
+            return Some(ReceivedSession::new(dir, []));
+        };
+        let complete = files
+            .iter()
+            .filter(|file| received.get(&file.id) == Some(&file.len))
+            .map(|file| (file.kind, file.name.as_str()));
+        Some(ReceivedSession::new(dir, complete))
+    }
+
+    /// Waits for the received data to be written and synced to disk, and
+    /// reports the files which couldn't be written.
+    fn finish_writes(&self) {
+        report_write_errors(&self.journal, self.writers.finish_all());
+    }
+
+    /// Records in the journal whether every announced file was received
+    /// with its announced size.
+    fn verify(&self) {
+        let (Ok(files), Ok(received)) =
+            (self.files.read(), self.received.read())
+        else {
+            return;
+        };
+        for file in files.iter() {
+            record(
+                &self.journal,
+                JournalEvent::Verified {
+                    name: file.name.clone(),
+                    expected_len: file.len,
+                    received_len: received.get(&file.id).copied().unwrap_or(0),
+                },
+            );
         }
     }
 
@@ -1568,14 +2414,9 @@ impl ReadyToReceiveSubscriber for ReadyToReceiveSubscriberImpl {
             }
         };
         let pb = bars.entry(event.id.clone()).or_insert_with(|| {
-            let pb = self.mp.add(ProgressBar::new_spinner());
-            pb.set_style(
-                ProgressStyle::with_template(
-                    "{spinner:.green} {msg} {bytes} ({bytes_per_sec})",
-                )
-                .unwrap_or_else(|_| ProgressStyle::default_spinner()),
-            );
-            pb.enable_steady_tick(std::time::Duration::from_millis(100));
+            // The size is known from the handshake file list
+            let pb = self.mp.add(ProgressBar::new(file.len));
+            pb.set_style(Self::bar_style());
             pb.set_message(format!("Receiving {}", file.name));
             pb
         });
@@ -1602,69 +2443,92 @@ impl ReadyToReceiveSubscriber for ReadyToReceiveSubscriberImpl {
                         "[DONE] Received {}",
                         file.name
                     ));
+                    record(
+                        &self.journal,
+                        JournalEvent::FileCompleted {
+                            name: file.name.clone(),
+                            len: *entry,
+                        },
+                    );
                 }
             } else {
                 pb.inc(event.data.len() as u64);
             }
         }
 
-        let file_path = self.receiving_path.join(&file.name);
-
-        // Get or create cached file handle
-        let mut file_handles = match self.file_handles.write() {
-            Ok(handles) => handles,
+        let Some(receiving_path) = self.session_dir.path() else {
+            return;
+        };
+        let file_path = match received_file_path(&receiving_path, &file.name) {
+            Ok(path) => path,
             Err(e) => {
-                eprintln!("[ERROR] Error accessing file handles: {}", e);
+                eprintln!("[ERROR] {}", e);
                 return;
             }
         };
-        let file_handle = match file_handles.entry(event.id.clone()) {
-            std::collections::hash_map::Entry::Occupied(entry) => {
-                entry.into_mut()
-            }
-            std::collections::hash_map::Entry::Vacant(entry) => {
-                // Create parent directories if they don't exist
-                if let Some(parent) = file_path.parent() {
-                    if !parent.exists() {
-                        if let Err(e) = fs::create_dir_all(parent) {
-                            eprintln!(
-                                "[ERROR] Failed to create directory {}: {}",
-                                parent.display(),
-                                e
-                            );
-                            return;
-                        }
-                    }
-                }
-                match fs::File::options()
-                    .create(true)
-                    .append(true)
-                    .open(&file_path)
-                {
-                    Ok(f) => entry.insert(f),
-                    Err(e) => {
-                        eprintln!(
-                            "[ERROR] Failed to open file {}: {}",
-                            file_path.display(),
-                            e
-                        );
-                        return;
-                    }
-                }
-            }
-        };
 
-        // Write to the cached file handle
-        if let Err(e) = file_handle.write_all(&event.data) {
-            eprintln!("[ERROR] Error writing to file {}: {}", file.name, e);
+        self.writers
+            .write(&event.id, &file_path, event.data);
+    }
+
+    fn notify_received(&self, event: ReadyToReceiveReceivedEvent) -> bool {
+        // Confirmed to the sender, which may delete the source, once synced
+        self.writers.sync(&event.id, event.modified)
+    }
+
+    fn notify_discarded(&self, event: ReadyToReceiveDiscardedEvent) {
+        let Some(name) = self.files.read().ok().and_then(|files| {
+            files
+                .iter()
+                .find(|f| f.id == event.id)
+                .map(|f| f.name.clone())
+        }) else {
             return;
+        };
+
+        // Not received anymore, so not counted as complete or moved
+        if let Ok(mut received) = self.received.write() {
+            received.remove(&event.id);
+        }
+        if let Some(pb) = self
+            .bars
+            .read()
+            .ok()
+            .and_then(|bars| bars.get(&event.id).cloned())
+        {
+            pb.abandon_with_message(format!("[DISCARDED] {name}"));
         }
-        if let Err(e) = file_handle.flush() {
-            eprintln!("[ERROR] Error flushing file {}: {}", file.name, e);
+        record(
+            &self.journal,
+            JournalEvent::Error {
+                message: format!("Discarded {name}, it failed verification"),
+            },
+        );
+
+        if let Some(dir) = self.session_dir.path()
+            && let Ok(path) = received_file_path(&dir, &name)
+        {
+            self.writers.discard(&event.id, &path);
         }
     }
 
     fn notify_connecting(&self, event: ReadyToReceiveConnectingEvent) {
+        record(
+            &self.journal,
+            JournalEvent::Connected {
+                peer_id: event.sender.id.clone(),
+                peer_name: event.sender.name.clone(),
+                files: event
+                    .files
+                    .iter()
+                    .map(|f| JournalFile {
+                        name: f.name.clone(),
+                        len: f.len,
+                    })
+                    .collect(),
+            },
+        );
+
         let _ = self.mp.println("Connected to sender:");
         let _ = self
             .mp
@@ -1672,6 +2536,13 @@ impl ReadyToReceiveSubscriber for ReadyToReceiveSubscriberImpl {
         let _ = self
             .mp
             .println(format!("   ID: {}", event.sender.id));
+        if let Some(line) = remember_peer(
+            event.sender.node_id.as_deref(),
+            &event.sender.name,
+            event.sender.avatar_b64.as_deref(),
+        ) {
+            let _ = self.mp.println(line);
+        }
         let _ = self
             .mp
             .println(format!("   Files to receive: {}", event.files.len()));
@@ -1680,6 +2551,27 @@ impl ReadyToReceiveSubscriber for ReadyToReceiveSubscriberImpl {
             let _ = self.mp.println(format!("     - {}", f.name));
         }
 
+        match self
+            .session_dir
+            .create(event.sender.node_id.as_deref(), &event.sender.name)
+        {
+            Ok(path) => {
+                let _ = self.mp.println(format!(
+                    "📁 Files will be saved to: {}",
+                    path.display()
+                ));
+            }
+            Err(e) => {
+                eprintln!("[ERROR] {e:#}");
+                record(
+                    &self.journal,
+                    JournalEvent::Error {
+                        message: format!("{e:#}"),
+                    },
+                );
+            }
+        }
+
         match self.files.write() {
             Ok(mut files) => {
                 files.extend(event.files.clone());
@@ -1713,15 +2605,23 @@ struct SendFilesToSubscriberImpl {
     verbose: bool,
     mp: MultiProgress,
     bars: RwLock<HashMap<String, ProgressBar>>,
+    journal: Option<Arc<Journal>>,
+    mover: Option<Arc<SourceMover>>,
 }
 
 impl SendFilesToSubscriberImpl {
-    fn new(verbose: bool) -> Self {
+    fn new(
+        verbose: bool,
+        journal: Option<Arc<Journal>>,
+        mover: Option<Arc<SourceMover>>,
+    ) -> Self {
         Self {
             id: Uuid::new_v4().to_string(),
             verbose,
             mp: MultiProgress::new(),
             bars: RwLock::new(HashMap::new()),
+            journal,
+            mover,
         }
     }
 
@@ -1782,12 +2682,28 @@ impl SendFilesToSubscriber for SendFilesToSubscriberImpl {
 
         if event.remaining == 0 {
             pb.finish_with_message(format!("[DONE] Sent {}", event.name));
+            record(
+                &self.journal,
+                JournalEvent::FileCompleted {
+                    name: event.name.clone(),
+                    len: event.sent,
+                },
+            );
         } else {
             pb.set_message(format!("Sending {}", event.name));
         }
     }
 
     fn notify_connecting(&self, event: SendFilesToConnectingEvent) {
+        record(
+            &self.journal,
+            JournalEvent::Connected {
+                peer_id: event.receiver.id.clone(),
+                peer_name: event.receiver.name.clone(),
+                files: Vec::new(),
+            },
+        );
+
         let _ = self.mp.println("Connected to receiver:");
         let _ = self
             .mp
@@ -1795,6 +2711,47 @@ impl SendFilesToSubscriber for SendFilesToSubscriberImpl {
         let _ = self
             .mp
             .println(format!("   ID: {}", event.receiver.id));
+        if let Some(line) = remember_peer(
+            event.receiver.node_id.as_deref(),
+            &event.receiver.name,
+            event.receiver.avatar_b64.as_deref(),
+        ) {
+            let _ = self.mp.println(line);
+        }
+    }
+
+    fn notify_error(&self, event: SendFilesToErrorEvent) {
+        if let Ok(bars) = self.bars.read()
+            && let Some(pb) = bars.get(&event.name)
+        {
+            pb.abandon_with_message(format!(
+                "[FAILED] {}: {}",
+                event.name, event.error
+            ));
+        }
+        record(
+            &self.journal,
+            JournalEvent::Error {
+                message: format!(
+                    "Failed to send {}: {}",
+                    event.name, event.error
+                ),
+            },
+        );
+    }
+
+    fn notify_received(&self, event: SendFilesToReceivedEvent) {
+        if let Some(line) = self
+            .mover
+            .as_ref()
+            .and_then(|mover| mover.remove(&event.name))
+        {
+            let _ = self.mp.println(line);
+        }
+    }
+
+    fn progress_interval(&self) -> Option<Duration> {
+        Some(PROGRESS_INTERVAL)
     }
 }
 
@@ -1809,6 +2766,8 @@ impl SendFilesToSubscriber for SendFilesToSubscriberImpl {
 /// - profile: The local user profile to present to the sender.
 /// - verbose: Enables transport logs and extra diagnostics.
 /// - save_dir: If true and `output_dir` is Some, saves it as the default.
+/// - max_size: Largest total size in bytes accepted from the sender, the
+///   limit from the config if None.
 ///
 /// Errors:
 /// - If the transfer setup or I/O fails.
@@ -1817,72 +2776,69 @@ pub async fn run_ready_to_receive(
     profile: Profile,
     verbose: bool,
     save_dir: bool,
+    max_size: Option<u64>,
 ) -> Result<()> {
-    // Determine the output directory
-    let final_output_dir = match output_dir {
-        Some(dir) => {
-            let path = PathBuf::from(&dir);
-            if save_dir {
-                set_default_out_dir(path.clone())?;
-                println!("💾 Saved '{}' as default receive directory", dir);
-            }
-            path
-        }
-        None => get_default_out_dir(),
-    };
-
-    // Create output directory if it doesn't exist
-    if !final_output_dir.exists() {
-        fs::create_dir_all(&final_output_dir).with_context(|| {
-            format!(
-                "Failed to create output directory: {}",
-                final_output_dir.display()
-            )
-        })?;
+    // Without an output directory, the sender's one is used
+    let output_dir = output_dir.map(PathBuf::from);
+    if save_dir && let Some(dir) = &output_dir {
+        set_default_out_dir(dir.clone())?;
+        println!("💾 Saved '{}' as default receive directory", dir.display());
     }
 
-    // Create unique subdirectory for this transfer
-    let receiving_path = final_output_dir.join(Uuid::new_v4().to_string());
-    fs::create_dir(&receiving_path).with_context(|| {
-        format!(
-            "Failed to create receiving directory: {}",
-            receiving_path.display()
-        )
-    })?;
+    let journal = start_journal(Direction::Receive, Vec::new());
 
+    let limits = limits();
     let request = ReadyToReceiveRequest {
         profile: ReceiverProfile {
             name: profile.name.clone(),
             avatar_b64: profile.avatar_b64.clone(),
         },
-        config: ReadyToReceiveConfig::default(),
+        config: ReadyToReceiveConfig {
+            endpoint: endpoint_config(),
+            max_rate: limits.max_download_rate,
+            max_total_size: max_size.or(limits.max_receive_size),
+            chunk_size: preset().chunk_size(),
+            parallel_streams: preset().parallel_streams(),
+            ..Default::default()
+        },
     };
 
     let bubble = ready_to_receive(request)
         .await
-        .context("Failed to initiate ready-to-receive")?;
+        .context("Failed to initiate ready-to-receive")
+        .inspect_err(|e| record_failure(&journal, e))?;
 
     let ticket = bubble.get_ticket();
     let confirmation = bubble.get_confirmation();
 
     // Display QR code and session info
     println!("📦 Ready to receive files!");
-    print_ready_to_receive_qr(&ticket, confirmation)?;
-    println!("📁 Files will be saved to: {}", receiving_path.display());
+    print_ready_to_receive_qr(&ticket, confirmation, &profile.name)?;
     println!("⏳ Waiting for sender... (Press Ctrl+C to cancel)");
 
-    let subscriber =
-        ReadyToReceiveSubscriberImpl::new(receiving_path.clone(), verbose);
-    bubble.subscribe(Arc::new(subscriber));
+    let subscriber = Arc::new(ReadyToReceiveSubscriberImpl::new(
+        SessionDir::new(output_dir),
+        verbose,
+        journal.clone(),
+    ));
+    bubble.subscribe(subscriber.clone());
 
     tokio::select! {
         _ = tokio::signal::ctrl_c() => {
             println!("🚫 Cancelling file transfer...");
             let _ = bubble.cancel().await;
+            subscriber.finish_writes();
+            record(&journal, JournalEvent::Finished { cancelled: true });
             println!("✅ Transfer cancelled");
         }
         _ = wait_for_ready_to_receive_completion(&bubble) => {
-            println!("✅ All files received successfully!");
+            subscriber.finish_writes();
+            subscriber.verify();
+            report_end(
+                &journal,
+                bubble.get_end_reason(),
+                "✅ All files received successfully!",
+            );
         }
     }
 
@@ -1900,6 +2856,8 @@ pub async fn run_ready_to_receive(
 /// - confirmation: The numeric confirmation code.
 /// - profile: The local user profile to present to the receiver.
 /// - verbose: Enables transport logs and extra diagnostics.
+/// - move_sources: If true, deletes each file once the receiver confirmed
+///   it got it in full. Files it doesn't confirm are kept.
 ///
 /// Errors:
 /// - If any path is invalid or if the transport fails to initialize.
@@ -1909,6 +2867,7 @@ pub async fn run_send_files_to(
     confirmation: String,
     profile: Profile,
     verbose: bool,
+    move_sources: bool,
 ) -> Result<()> {
     if file_paths.is_empty() {
         return Err(anyhow!("Cannot send an empty list of files"));
@@ -1919,35 +2878,14 @@ pub async fn run_send_files_to(
         .map(PathBuf::from)
         .collect();
 
-    // Validate all files exist before starting
-    for path in &paths {
-        if !path.exists() {
-            return Err(anyhow!("File does not exist: {}", path.display()));
-        }
-        if !path.is_file() {
-            return Err(anyhow!("Path is not a file: {}", path.display()));
-        }
-    }
-
-    let confirmation_code = u8::from_str(&confirmation).with_context(|| {
-        format!("Invalid confirmation code: {}", confirmation)
-    })?;
-
-    // Create sender files
-    let mut files = Vec::new();
-    for path in paths {
-        let name = path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .ok_or_else(|| anyhow!("Invalid file name: {}", path.display()))?
-            .to_string();
+    let named_files = collect_files(&paths)?;
+    let mover = move_sources.then(|| Arc::new(SourceMover::new(&named_files)));
+    let files = create_sender_files(named_files)?;
+    let journal = start_journal(Direction::Send, journal_files(&files));
 
-        let data = FileData::new(path)?;
-        files.push(SenderFile {
-            name,
-            data: Arc::new(data),
-        });
-    }
+    let confirmation_code = u8::from_str(&confirmation)
+        .with_context(|| format!("Invalid confirmation code: {}", confirmation))
+        .inspect_err(|e| record_failure(&journal, e))?;
 
     let request = SendFilesToRequest {
         ticket,
@@ -1957,21 +2895,30 @@ pub async fn run_send_files_to(
             name: profile.name.clone(),
             avatar_b64: profile.avatar_b64.clone(),
         },
-        config: SenderConfig::default(),
+        config: SenderConfig {
+            endpoint: endpoint_config(),
+            max_rate: limits().max_upload_rate,
+            chunk_size: preset().chunk_size(),
+            parallel_streams: preset().parallel_streams(),
+            ..Default::default()
+        },
     };
 
     let bubble = send_files_to(request)
         .await
-        .context("Failed to initiate send-files-to")?;
+        .context("Failed to initiate send-files-to")
+        .inspect_err(|e| record_failure(&journal, e))?;
 
-    let subscriber = SendFilesToSubscriberImpl::new(verbose);
+    let subscriber =
+        SendFilesToSubscriberImpl::new(verbose, journal.clone(), mover.clone());
     bubble.subscribe(Arc::new(subscriber));
 
     println!("Connecting to waiting receiver...");
 
     bubble
         .start()
-        .context("Failed to start send-files-to")?;
+        .context("Failed to start send-files-to")
+        .inspect_err(|e| record_failure(&journal, e))?;
 
     println!("Sending files... (Press Ctrl+C to cancel)");
 
@@ -1979,12 +2926,193 @@ pub async fn run_send_files_to(
         _ = tokio::signal::ctrl_c() => {
             println!("🚫 Cancelling file transfer...");
             let _ = bubble.cancel().await;
+            record(&journal, JournalEvent::Finished { cancelled: true });
             println!("✅ Transfer cancelled");
             Ok(())
         }
         _ = wait_for_send_files_to_completion(&bubble) => {
-            println!("✅ All files sent successfully!");
+            report_end(
+                &journal,
+                bubble.get_end_reason(),
+                "✅ All files sent successfully!",
+            );
+            if let Some(mover) = &mover {
+                mover.report();
+            }
             Ok(())
         }
     }
 }
+
+/// Run a sync listener, receiving the files pushed by paired devices.
+///
+/// Receive sessions run one after the other with the persistent identity of
+/// this device and the sync confirmation code, so the ticket and code shown
+/// at start stay valid for the paired devices. Only trusted peers, see
+/// [`AppConfig::trust_peer`], can connect. Runs until Ctrl+C.
+///
+/// Parameters:
+/// - staging_dir: Directory in which each session gets its own folder.
+/// - profile: The local user profile to present to the senders.
+/// - verbose: Enables transport logs and extra diagnostics.
+/// - on_received: Called with every completed session. Folders of failed
+///   sessions are removed, so partial files never reach the caller.
+///
+/// Errors:
+/// - If no peer is trusted or if the transport fails to initialize.
+pub async fn run_sync_listener(
+    staging_dir: PathBuf,
+    profile: Profile,
+    verbose: bool,
+    mut on_received: impl FnMut(&ReceivedSession),
+) -> Result<()> {
+    let mut config = AppConfig::load()?;
+    if config.sync.trusted_peers.is_empty() {
+        return Err(anyhow!(
+            "No trusted peers, trust the node id of a device first"
+        ));
+    }
+    let confirmation = config.get_sync_confirmation()?;
+    let allowed_senders: Vec<String> = config
+        .sync
+        .trusted_peers
+        .iter()
+        .cloned()
+        .collect();
+    let endpoint = sync_endpoint_config()?;
+
+    let mut shown_ticket = None;
+    loop {
+        let journal = start_journal(Direction::Receive, Vec::new());
+
+        let request = ReadyToReceiveRequest {
+            profile: ReceiverProfile {
+                name: profile.name.clone(),
+                avatar_b64: profile.avatar_b64.clone(),
+            },
+            config: ReadyToReceiveConfig {
+                endpoint: endpoint.clone(),
+                max_rate: limits().max_download_rate,
+                max_total_size: limits().max_receive_size,
+                confirmation: Some(confirmation),
+                allowed_senders: Some(allowed_senders.clone()),
+                chunk_size: preset().chunk_size(),
+                parallel_streams: preset().parallel_streams(),
+                ..Default::default()
+            },
+        };
+
+        let bubble = ready_to_receive(request)
+            .await
+            .context("Failed to start sync listener")
+            .inspect_err(|e| record_failure(&journal, e))?;
+
+        let ticket = bubble.get_ticket();
+        if shown_ticket.as_ref() != Some(&ticket) {
+            println!("🔄 Waiting for paired devices... (Press Ctrl+C to stop)");
+            println!("   Ticket: {ticket}");
+            println!("   Confirmation: {confirmation}");
+            shown_ticket = Some(ticket);
+        }
+
+        let subscriber = Arc::new(ReadyToReceiveSubscriberImpl::new(
+            SessionDir::new(Some(staging_dir.clone())),
+            verbose,
+            journal.clone(),
+        ));
+        bubble.subscribe(subscriber.clone());
+
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {
+                println!("🚫 Stopping sync...");
+                let _ = bubble.cancel().await;
+                record(&journal, JournalEvent::Finished { cancelled: true });
+                return Ok(());
+            }
+            _ = wait_for_ready_to_receive_completion(&bubble) => {
+                subscriber.finish_writes();
+                subscriber.verify();
+                let reason = bubble.get_end_reason();
+                report_end(&journal, reason, "✅ Files synced");
+                if let Some(session) = subscriber.session() {
+                    match reason {
+                        None | Some(EndReason::Completed) => {
+                            on_received(&session)
+                        }
+                        Some(_) => {
+                            let _ = fs::remove_dir_all(&session.dir);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Run a sync push, sending files to the listener of a paired device.
+///
+/// Unlike [`run_send_files_to`], this device connects with its persistent
+/// identity, so the listener recognizes it as trusted, see
+/// [`run_sync_listener`]. Files are sent under their given names, along
+/// with the user data of resources if given, see [`ItemKind::UserData`].
+///
+/// Errors:
+/// - If there is nothing to send or any path is missing, if the transport
+///   fails to initialize, or if the transfer doesn't complete.
+pub async fn run_sync_send(
+    named_files: Vec<(String, PathBuf)>,
+    user_data: Option<Vec<u8>>,
+    ticket: String,
+    confirmation: u8,
+    profile: Profile,
+    verbose: bool,
+) -> Result<()> {
+    let mut files = Vec::new();
+    if !named_files.is_empty() {
+        files = create_sender_files(named_files)?;
+    }
+    files.extend(user_data.map(user_data_sender_file));
+    if files.is_empty() {
+        return Err(anyhow!("Nothing to sync"));
+    }
+    let journal = start_journal(Direction::Send, journal_files(&files));
+
+    let request = SendFilesToRequest {
+        ticket,
+        confirmation,
+        files,
+        profile: SenderProfile {
+            name: profile.name.clone(),
+            avatar_b64: profile.avatar_b64.clone(),
+        },
+        config: SenderConfig {
+            endpoint: sync_endpoint_config()?,
+            max_rate: limits().max_upload_rate,
+            chunk_size: preset().chunk_size(),
+            parallel_streams: preset().parallel_streams(),
+            ..Default::default()
+        },
+    };
+
+    let bubble = send_files_to(request)
+        .await
+        .context("Failed to connect to the paired device")
+        .inspect_err(|e| record_failure(&journal, e))?;
+
+    let subscriber =
+        SendFilesToSubscriberImpl::new(verbose, journal.clone(), None);
+    bubble.subscribe(Arc::new(subscriber));
+
+    bubble
+        .start()
+        .context("Failed to start sync")
+        .inspect_err(|e| record_failure(&journal, e))?;
+
+    wait_for_send_files_to_completion(&bubble).await;
+    let reason = bubble.get_end_reason();
+    report_end(&journal, reason, "✅ Files synced");
+    match reason {
+        None | Some(EndReason::Completed) => Ok(()),
+        Some(reason) => Err(anyhow!("Sync stopped: {reason}")),
+    }
+}
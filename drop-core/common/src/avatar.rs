@@ -0,0 +1,113 @@
+//! Normalization of the avatars sent in handshakes.
+//!
+//! Avatars are sent to the peer in every handshake, so a photo picked from
+//! disk is shrunk to [`MAX_AVATAR_DIMENSION`] and re-encoded until it fits in
+//! [`TARGET_AVATAR_SIZE`]: as PNG if it has transparency and fits, as JPEG of
+//! decreasing quality and dimensions otherwise. Avatars are encoded as plain
+//! Base64, without `data:` URL prefix.
+//!
+//! Received avatars are checked by the handshake against
+//! [`DEFAULT_MAX_AVATAR_SIZE`], [`decode_avatar`] applies the same check.
+use std::{fs, io::Cursor, path::Path};
+
+use anyhow::{Context, Result, anyhow};
+use arkdropx_common::handshake::{
+    DEFAULT_MAX_AVATAR_SIZE, decode_image_avatar,
+};
+use base64::{Engine, engine::general_purpose};
+use image::{
+    DynamicImage, GenericImageView, ImageFormat, codecs::jpeg::JpegEncoder,
+    imageops::FilterType,
+};
+
+/// Largest width and height of a normalized avatar in pixels.
+pub const MAX_AVATAR_DIMENSION: u32 = 256;
+
+/// Size a normalized avatar is shrunk to in bytes, before Base64 encoding.
+///
+/// It's well below [`DEFAULT_MAX_AVATAR_SIZE`], as the avatar is part of
+/// every handshake.
+pub const TARGET_AVATAR_SIZE: usize = 64 * 1024;
+
+/// JPEG qualities tried in turn until the avatar fits.
+const JPEG_QUALITIES: [u8; 4] = [85, 70, 55, 40];
+
+/// Smallest dimension the avatar is shrunk to when it doesn't fit at the
+/// lowest JPEG quality.
+const MIN_AVATAR_DIMENSION: u32 = 32;
+
+/// Reads an image file and returns it as a normalized Base64 avatar.
+///
+/// Errors:
+/// - If the file can't be read, isn't a supported image, or can't be
+///   shrunk to fit.
+pub fn encode_avatar_file(path: &Path) -> Result<String> {
+    let bytes = fs::read(path).with_context(|| {
+        format!("Failed to read avatar file: {}", path.display())
+    })?;
+    let avatar = normalize_avatar(&bytes).with_context(|| {
+        format!("Failed to process avatar file: {}", path.display())
+    })?;
+    Ok(general_purpose::STANDARD.encode(avatar))
+}
+
+/// Resizes and re-encodes image bytes into an avatar within
+/// [`MAX_AVATAR_DIMENSION`] and [`TARGET_AVATAR_SIZE`].
+///
+/// Errors:
+/// - If the bytes aren't a supported image, or it can't be shrunk to fit.
+pub fn normalize_avatar(bytes: &[u8]) -> Result<Vec<u8>> {
+    let image =
+        image::load_from_memory(bytes).context("Unsupported image format")?;
+    let mut image = fit(image, MAX_AVATAR_DIMENSION);
+
+    if image.color().has_alpha() {
+        let mut png = Vec::new();
+        image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+        if png.len() <= TARGET_AVATAR_SIZE {
+            return Ok(png);
+        }
+    }
+
+    loop {
+        let rgb = image.to_rgb8();
+        for quality in JPEG_QUALITIES {
+            let mut jpeg = Vec::new();
+            JpegEncoder::new_with_quality(&mut jpeg, quality)
+                .encode_image(&rgb)?;
+            if jpeg.len() <= TARGET_AVATAR_SIZE {
+                return Ok(jpeg);
+            }
+        }
+
+        let (width, height) = image.dimensions();
+        let dimension = width.max(height) / 2;
+        if dimension < MIN_AVATAR_DIMENSION {
+            return Err(anyhow!(
+                "Avatar doesn't fit in {TARGET_AVATAR_SIZE} bytes"
+            ));
+        }
+        image = fit(image, dimension);
+    }
+}
+
+/// Decodes a Base64 avatar, with or without a `data:` URL prefix, and checks
+/// it the way peers check the avatars of handshakes, see
+/// [`decode_image_avatar`].
+///
+/// Errors:
+/// - If the avatar isn't valid Base64, isn't an image, or exceeds
+///   [`DEFAULT_MAX_AVATAR_SIZE`].
+pub fn decode_avatar(avatar_b64: &str) -> Result<Vec<u8>> {
+    Ok(decode_image_avatar(avatar_b64, DEFAULT_MAX_AVATAR_SIZE)?)
+}
+
+/// Shrinks the image to fit in a square of `dimension`, keeping its aspect
+/// ratio. Smaller images are kept as is.
+fn fit(image: DynamicImage, dimension: u32) -> DynamicImage {
+    let (width, height) = image.dimensions();
+    if width.max(height) <= dimension {
+        return image;
+    }
+    image.resize(dimension, dimension, FilterType::Lanczos3)
+}
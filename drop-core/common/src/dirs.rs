@@ -0,0 +1,101 @@
+//! Directories of the application on each platform.
+//!
+//! Settings, data worth keeping and runtime state live apart, so that
+//! backing up or syncing the configuration doesn't drag along transfer
+//! history, and clearing state doesn't lose settings:
+//!
+//! | | Linux | macOS | Windows |
+//! |---|---|---|---|
+//! | config | `$XDG_CONFIG_HOME/ARK-Drop` | `~/Library/Application Support/ARK-Drop` | `%APPDATA%\ARK-Drop` |
+//! | data | `$XDG_DATA_HOME/ARK-Drop` | `~/Library/Application Support/ARK-Drop` | `%APPDATA%\ARK-Drop` |
+//! | state | `$XDG_STATE_HOME/ARK-Drop` | `~/Library/Application Support/ARK-Drop` | `%LOCALAPPDATA%\ARK-Drop` |
+//!
+//! On Linux, unset XDG variables fall back to `~/.config`, `~/.local/share`
+//! and `~/.local/state`.
+use std::{env, path::PathBuf};
+
+use anyhow::{Result, anyhow};
+
+const APP_DIR: &str = "ARK-Drop";
+
+/// The directories of the application, see the [module docs](self).
+///
+/// Directories are only resolved, callers create them when writing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AppDirs {
+    /// User settings, such as `config.toml`.
+    pub config: PathBuf,
+    /// Data the user would miss, such as peer caches.
+    pub data: PathBuf,
+    /// Runtime state that can be lost, such as the transfer journal, resume
+    /// state and logs.
+    pub state: PathBuf,
+}
+
+impl AppDirs {
+    /// Resolves the directories from the environment.
+    ///
+    /// Errors:
+    /// - If the home directory of the user can't be determined.
+    pub fn resolve() -> Result<Self> {
+        #[cfg(target_os = "windows")]
+        {
+            let config = match env::var("APPDATA") {
+                Ok(appdata) => PathBuf::from(appdata).join(APP_DIR),
+                // Fallback if APPDATA isn't set (rare)
+                Err(_) => home_dir()?.join(".config").join(APP_DIR),
+            };
+            let state = match env::var("LOCALAPPDATA") {
+                Ok(local) => PathBuf::from(local).join(APP_DIR),
+                Err(_) => config.clone(),
+            };
+            return Ok(Self {
+                data: config.clone(),
+                config,
+                state,
+            });
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            let support = home_dir()?
+                .join("Library")
+                .join("Application Support")
+                .join(APP_DIR);
+            return Ok(Self {
+                config: support.clone(),
+                data: support.clone(),
+                state: support,
+            });
+        }
+
+        #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+        {
+            let xdg = |var: &str, fallback: &[&str]| -> Result<PathBuf> {
+                match env::var(var) {
+                    Ok(dir) if !dir.is_empty() => Ok(PathBuf::from(dir)),
+                    _ => Ok(fallback
+                        .iter()
+                        .fold(home_dir()?, |path, segment| path.join(segment))),
+                }
+            };
+            Ok(Self {
+                config: xdg("XDG_CONFIG_HOME", &[".config"])?.join(APP_DIR),
+                data: xdg("XDG_DATA_HOME", &[".local", "share"])?.join(APP_DIR),
+                state: xdg("XDG_STATE_HOME", &[".local", "state"])?
+                    .join(APP_DIR),
+            })
+        }
+    }
+}
+
+pub(crate) fn home_dir() -> Result<PathBuf> {
+    #[cfg(target_os = "windows")]
+    let var = "USERPROFILE";
+    #[cfg(not(target_os = "windows"))]
+    let var = "HOME";
+
+    env::var(var).map(PathBuf::from).map_err(|_| {
+        anyhow!("Unable to determine home directory (missing {var})")
+    })
+}
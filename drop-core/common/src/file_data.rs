@@ -0,0 +1,289 @@
+//! File on disk as a data source for the sender.
+//!
+//! Shared by every front-end sending from the filesystem. Chunks are read at
+//! their claimed offset with positioned reads, so parallel streams never
+//! contend for a file cursor, and the handles used for that are kept in a
+//! small pool instead of being opened for every chunk.
+use std::{
+    fmt::Display,
+    fs::{self, File},
+    io::{self, BufReader, Read},
+    path::PathBuf,
+    sync::{
+        Mutex, OnceLock,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::SystemTime,
+};
+
+use anyhow::{Context, Result};
+use arkdropx_sender::SenderFileData;
+
+/// Buffer size of the sequential reader behind [`SenderFileData::read`].
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Idle handles kept for positioned reads, more are closed after use.
+const MAX_IDLE_HANDLES: usize = 8;
+
+/// File data source for the sender.
+///
+/// This implementation:
+/// - Supports both single-byte reads (`read`), served from a buffered
+///   reader, and ranged chunk reads (`read_chunk`) at atomically claimed
+///   offsets.
+/// - Reuses file handles across chunk reads.
+/// - Reports its total length through `len`, and whether the file changed
+///   on disk through `has_changed`.
+/// - Hashes the file once for both `content_id` and `content_hash`.
+///
+/// Notes:
+/// - Errors are logged and will mark the stream as finished to prevent
+///   stalling.
+pub struct FileData {
+    is_finished: AtomicBool,
+    path: PathBuf,
+    reader: Mutex<Option<BufReader<File>>>,
+    handles: Mutex<Vec<File>>,
+    size: u64,
+    modified: Option<SystemTime>,
+    hash: OnceLock<Option<String>>,
+    bytes_read: AtomicU64,
+}
+
+impl FileData {
+    /// Create a new FileData for the given path, capturing size and
+    /// modification time metadata.
+    ///
+    /// Errors:
+    /// - If the file's metadata cannot be read.
+    pub fn new(path: PathBuf) -> Result<Self> {
+        let metadata = fs::metadata(&path).with_context(|| {
+            format!("Failed to get metadata for file: {}", path.display())
+        })?;
+
+        Ok(Self {
+            is_finished: AtomicBool::new(false),
+            path,
+            reader: Mutex::new(None),
+            handles: Mutex::new(Vec::new()),
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+            hash: OnceLock::new(),
+            bytes_read: AtomicU64::new(0),
+        })
+    }
+
+    /// Takes an idle handle from the pool or opens a new one.
+    fn take_handle(&self) -> io::Result<File> {
+        let idle = self
+            .handles
+            .lock()
+            .ok()
+            .and_then(|mut handles| handles.pop());
+        match idle {
+            Some(file) => Ok(file),
+            None => File::open(&self.path),
+        }
+    }
+
+    /// Returns a handle to the pool, closing it if the pool is full.
+    fn put_handle(&self, file: File) {
+        if let Ok(mut handles) = self.handles.lock()
+            && handles.len() < MAX_IDLE_HANDLES
+        {
+            handles.push(file);
+        }
+    }
+
+    /// Logs the error and marks the stream as finished.
+    fn fail(&self, action: &str, err: impl Display) {
+        eprintln!("[ERROR] Error {action} file {}: {err}", self.path.display());
+        self.is_finished.store(true, Ordering::Release);
+    }
+}
+
+impl SenderFileData for FileData {
+    /// Returns the total file size in bytes.
+    fn len(&self) -> u64 {
+        self.size
+    }
+
+    /// Checks if the data is empty (length is 0).
+    fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Checks if the file's size or modification time differ from the ones
+    /// captured at creation, or if it can't be inspected anymore.
+    fn has_changed(&self) -> bool {
+        match fs::metadata(&self.path) {
+            Ok(metadata) => {
+                metadata.len() != self.size
+                    || metadata.modified().ok() != self.modified
+            }
+            Err(_) => true,
+        }
+    }
+
+    /// Returns the BLAKE3 hash of the file, so hard links and copies of a
+    /// file get the same id.
+    fn content_id(&self) -> Option<String> {
+        self.content_hash()
+    }
+
+    /// Hashes the whole file with BLAKE3 on first use, through its own
+    /// handle so the transfer position is left alone.
+    fn content_hash(&self) -> Option<String> {
+        self.hash
+            .get_or_init(|| {
+                let file = File::open(&self.path).ok()?;
+                let mut hasher = blake3::Hasher::new();
+                hasher.update_reader(file).ok()?;
+                Some(hasher.finalize().to_hex().to_string())
+            })
+            .clone()
+    }
+
+    /// Returns the modification time captured at creation.
+    fn modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
+
+    /// Reads a single byte, falling back to EOF (None) at end of file or on
+    /// errors.
+    fn read(&self) -> Option<u8> {
+        if self.is_finished.load(Ordering::Acquire) {
+            return None;
+        }
+
+        let mut reader = match self.reader.lock() {
+            Ok(guard) => guard,
+            Err(e) => {
+                self.fail("locking reader of", e);
+                return None;
+            }
+        };
+        if reader.is_none() {
+            match File::open(&self.path) {
+                Ok(file) => {
+                    *reader =
+                        Some(BufReader::with_capacity(READ_BUFFER_SIZE, file));
+                }
+                Err(e) => {
+                    self.fail("opening", e);
+                    return None;
+                }
+            }
+        }
+
+        let mut buffer = [0u8; 1];
+        match reader.as_mut()?.read(&mut buffer) {
+            Ok(0) => {
+                *reader = None;
+                self.is_finished.store(true, Ordering::Release);
+                None
+            }
+            Ok(_) => Some(buffer[0]),
+            Err(e) => {
+                *reader = None;
+                self.fail("reading from", e);
+                None
+            }
+        }
+    }
+
+    /// Reads up to `size` bytes as a contiguous chunk starting from the next
+    /// claimed position. Returns an empty Vec when the file is fully consumed
+    /// or on errors.
+    fn read_chunk(&self, size: u64) -> Vec<u8> {
+        if self.is_finished.load(Ordering::Acquire) {
+            return Vec::new();
+        }
+
+        // Atomically claim the next chunk position
+        let position = self.bytes_read.fetch_add(size, Ordering::AcqRel);
+
+        // Check if we've already passed the end of the file
+        if position >= self.size {
+            self.bytes_read
+                .store(self.size, Ordering::Release);
+            self.is_finished.store(true, Ordering::Release);
+            return Vec::new();
+        }
+
+        // Calculate how much to actually read (don't exceed file size)
+        let to_read = size.min(self.size - position) as usize;
+
+        let file = match self.take_handle() {
+            Ok(file) => file,
+            Err(e) => {
+                self.fail("opening", e);
+                return Vec::new();
+            }
+        };
+        let mut buffer = vec![0u8; to_read];
+        let result = read_exact_at(&file, &mut buffer, position);
+        self.put_handle(file);
+
+        match result {
+            Ok(()) => {
+                // Check if we've finished reading the entire file
+                if position + to_read as u64 >= self.size {
+                    self.is_finished.store(true, Ordering::Release);
+                }
+                buffer
+            }
+            Err(e) => {
+                self.fail("reading chunk from", e);
+                Vec::new()
+            }
+        }
+    }
+}
+
+/// Fills `buffer` from `offset` without moving the file cursor.
+#[cfg(unix)]
+fn read_exact_at(
+    file: &File,
+    buffer: &mut [u8],
+    offset: u64,
+) -> io::Result<()> {
+    std::os::unix::fs::FileExt::read_exact_at(file, buffer, offset)
+}
+
+/// Fills `buffer` from `offset`. Windows moves the cursor, which is fine as
+/// each handle is used by one read at a time.
+#[cfg(windows)]
+fn read_exact_at(
+    file: &File,
+    mut buffer: &mut [u8],
+    mut offset: u64,
+) -> io::Result<()> {
+    use std::os::windows::fs::FileExt;
+
+    while !buffer.is_empty() {
+        match file.seek_read(buffer, offset) {
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(read) => {
+                buffer = &mut buffer[read..];
+                offset += read as u64;
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// Fills `buffer` from `offset` by seeking first.
+#[cfg(not(any(unix, windows)))]
+fn read_exact_at(
+    mut file: &File,
+    buffer: &mut [u8],
+    offset: u64,
+) -> io::Result<()> {
+    use std::io::{Seek, SeekFrom};
+
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(buffer)
+}
@@ -0,0 +1,93 @@
+//! Persistent identity of this device.
+//!
+//! Transfers bind endpoints with a fresh identity by default. Sync sessions
+//! instead use a secret key kept in the data directory (see [`crate::dirs`]),
+//! so paired devices recognize each other by node id across sessions.
+//!
+//! The device is also known by the id and name shared with the other ARK
+//! apps, see [`device`]. Its name is shown to peers when no display name is
+//! configured.
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, anyhow};
+use arkdropx_sender::EndpointConfig;
+pub use fs_atomic_versions::app_id::DeviceIdentity;
+
+use crate::{data_dir, dirs::home_dir};
+
+const IDENTITY_FILE: &str = "identity.key";
+
+/// Returns the path of the secret key in the data directory.
+pub fn default_path() -> Result<PathBuf> {
+    Ok(data_dir()?.join(IDENTITY_FILE))
+}
+
+/// Loads the secret key of this device, generating it on first use.
+pub fn secret_key() -> Result<[u8; 32]> {
+    load_or_create_at(&default_path()?)
+}
+
+/// Returns the node id of this device, as seen by its peers.
+pub fn node_id() -> Result<String> {
+    let config = EndpointConfig {
+        secret_key: Some(secret_key()?),
+        ..Default::default()
+    };
+    config
+        .node_id()
+        .ok_or_else(|| anyhow!("Failed to derive node id"))
+}
+
+/// Loads the identity of this device from `~/.ark`, where the other ARK apps
+/// keep it, generating it on first use.
+pub fn device() -> Result<DeviceIdentity> {
+    DeviceIdentity::load_or_create(home_dir()?)
+        .context("Failed to load device identity")
+}
+
+/// Loads the secret key from `path`, or generates and writes a new one if
+/// the file doesn't exist. The file is only readable by the user on Unix.
+///
+/// Errors:
+/// - If the file can't be read or written, or isn't a key.
+pub fn load_or_create_at(path: &Path) -> Result<[u8; 32]> {
+    if path.exists() {
+        let bytes = fs::read(path).with_context(|| {
+            format!("Failed to read identity file: {}", path.display())
+        })?;
+        return bytes
+            .try_into()
+            .map_err(|_| anyhow!("Invalid identity file: {}", path.display()));
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| {
+            format!("Failed to create identity directory: {}", dir.display())
+        })?;
+    }
+    let key: [u8; 32] = rand::random();
+    write_private(path, &key).with_context(|| {
+        format!("Failed to write identity file: {}", path.display())
+    })?;
+    Ok(key)
+}
+
+#[cfg(unix)]
+fn write_private(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    use std::{io::Write, os::unix::fs::OpenOptionsExt};
+
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)?
+        .write_all(bytes)
+}
+
+#[cfg(not(unix))]
+fn write_private(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    fs::write(path, bytes)
+}
@@ -0,0 +1,379 @@
+//! Journal of transfer sessions.
+//!
+//! When enabled, every session appends its lifecycle events to a JSON lines
+//! file in the state directory (see [`crate::dirs`]). Each line is a
+//! [`JournalEntry`] tagged with the session it belongs to, so the file can be
+//! read back to list past transfers, or the lines of one session can be
+//! shared as part of a bug report.
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{data_dir, state_dir};
+
+const JOURNAL_FILE: &str = "journal.jsonl";
+
+/// Whether the session sent or received files.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Send,
+    Receive,
+}
+
+/// A file taking part in a session.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JournalFile {
+    pub name: String,
+    pub len: u64,
+}
+
+/// Something that happened during a session.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum JournalEvent {
+    /// The session was created. Senders list the files they offer.
+    Started {
+        direction: Direction,
+        files: Vec<JournalFile>,
+    },
+    /// The handshake with the peer completed.
+    Connected {
+        peer_id: String,
+        peer_name: String,
+        files: Vec<JournalFile>,
+    },
+    /// All bytes of a file were sent or received.
+    FileCompleted { name: String, len: u64 },
+    /// The size of a received file was checked against the announced one.
+    Verified {
+        name: String,
+        expected_len: u64,
+        received_len: u64,
+    },
+    /// The session failed or a file couldn't be handled.
+    Error { message: String },
+    /// The session is over.
+    Finished { cancelled: bool },
+}
+
+/// A line of the journal.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub session_id: String,
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: JournalEvent,
+}
+
+/// Writer appending the events of one session to the journal.
+pub struct Journal {
+    session_id: String,
+    file: Mutex<File>,
+}
+
+impl Journal {
+    /// Returns the path of the journal file in the state directory.
+    ///
+    /// A journal left in the data directory by earlier versions is moved
+    /// there first, or kept in use if it can't be moved.
+    pub fn default_path() -> Result<PathBuf> {
+        let path = state_dir()?.join(JOURNAL_FILE);
+        let legacy = data_dir()?.join(JOURNAL_FILE);
+        if legacy != path && legacy.exists() && !path.exists() {
+            let moved = path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|()| fs::rename(&legacy, &path));
+            if moved.is_err() {
+                return Ok(legacy);
+            }
+        }
+        Ok(path)
+    }
+
+    /// Starts a new session in the journal at the default path.
+    pub fn start(
+        direction: Direction,
+        files: Vec<JournalFile>,
+    ) -> Result<Self> {
+        Self::start_at(&Self::default_path()?, direction, files)
+    }
+
+    /// Starts a new session in the journal at `path`, creating the file and
+    /// its directory if needed, and records the [`JournalEvent::Started`]
+    /// event.
+    pub fn start_at(
+        path: &Path,
+        direction: Direction,
+        files: Vec<JournalFile>,
+    ) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| {
+                format!("Failed to create journal directory: {}", dir.display())
+            })?;
+        }
+        let file = File::options()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| {
+                format!("Failed to open journal file: {}", path.display())
+            })?;
+
+        let journal = Self {
+            session_id: Uuid::new_v4().to_string(),
+            file: Mutex::new(file),
+        };
+        journal.record(JournalEvent::Started { direction, files })?;
+        Ok(journal)
+    }
+
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    /// Appends an event of this session to the journal.
+    pub fn record(&self, event: JournalEvent) -> Result<()> {
+        let entry = JournalEntry {
+            session_id: self.session_id.clone(),
+            timestamp: Utc::now(),
+            event,
+        };
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+
+        let mut file = self
+            .file
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Journal lock poisoned: {e}"))?;
+        file.write_all(line.as_bytes())
+            .context("Failed to write to journal file")
+    }
+}
+
+/// Reads all entries of the journal at `path`.
+///
+/// A missing file is an empty journal. Lines which can't be parsed, e.g.
+/// one left incomplete by a crash, are skipped.
+pub fn read_journal(path: &Path) -> Result<Vec<JournalEntry>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let file = File::open(path).with_context(|| {
+        format!("Failed to open journal file: {}", path.display())
+    })?;
+
+    let mut entries = Vec::new();
+    for line in BufReader::new(file).lines() {
+        if let Ok(entry) = serde_json::from_str(&line?) {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+/// How a session ended, as far as the journal tells.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SessionStatus {
+    /// No `Finished` event, the session is running or the process died.
+    Incomplete,
+    Completed,
+    Cancelled,
+    Failed,
+}
+
+/// Overview of one session of the journal.
+#[derive(Clone, Debug)]
+pub struct SessionSummary {
+    pub session_id: String,
+    pub started_at: DateTime<Utc>,
+    pub direction: Option<Direction>,
+    pub peer_name: Option<String>,
+    pub files: usize,
+    pub completed_files: usize,
+    pub bytes: u64,
+    pub errors: usize,
+    pub status: SessionStatus,
+}
+
+/// Groups the entries by session, in the order the sessions started.
+///
+/// A session counts as failed when it has errors or a received file doesn't
+/// have the announced size.
+pub fn summarize_sessions(entries: &[JournalEntry]) -> Vec<SessionSummary> {
+    let mut summaries: Vec<SessionSummary> = Vec::new();
+    let mut indices = HashMap::new();
+
+    for entry in entries {
+        let index = *indices
+            .entry(entry.session_id.clone())
+            .or_insert_with(|| {
+                summaries.push(SessionSummary {
+                    session_id: entry.session_id.clone(),
+                    started_at: entry.timestamp,
+                    direction: None,
+                    peer_name: None,
+                    files: 0,
+                    completed_files: 0,
+                    bytes: 0,
+                    errors: 0,
+                    status: SessionStatus::Incomplete,
+                });
+                summaries.len() - 1
+            });
+        let summary = &mut summaries[index];
+
+        match &entry.event {
+            JournalEvent::Started { direction, files } => {
+                summary.direction = Some(*direction);
+                summary.files = files.len();
+            }
+            JournalEvent::Connected {
+                peer_name, files, ..
+            } => {
+                summary.peer_name = Some(peer_name.clone());
+                if !files.is_empty() {
+                    summary.files = files.len();
+                }
+            }
+            JournalEvent::FileCompleted { len, .. } => {
+                summary.completed_files += 1;
+                summary.bytes += len;
+            }
+            JournalEvent::Verified {
+                expected_len,
+                received_len,
+                ..
+            } => {
+                if expected_len != received_len {
+                    summary.errors += 1;
+                }
+            }
+            JournalEvent::Error { .. } => summary.errors += 1,
+            JournalEvent::Finished { cancelled } => {
+                summary.status = if *cancelled {
+                    SessionStatus::Cancelled
+                } else if summary.errors > 0 {
+                    SessionStatus::Failed
+                } else {
+                    SessionStatus::Completed
+                };
+            }
+        }
+    }
+
+    summaries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(name: &str, len: u64) -> JournalFile {
+        JournalFile {
+            name: name.to_string(),
+            len,
+        }
+    }
+
+    #[test]
+    fn round_trips_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state").join(JOURNAL_FILE);
+
+        let journal =
+            Journal::start_at(&path, Direction::Send, vec![file("a.txt", 3)])
+                .unwrap();
+        journal
+            .record(JournalEvent::FileCompleted {
+                name: "a.txt".to_string(),
+                len: 3,
+            })
+            .unwrap();
+        journal
+            .record(JournalEvent::Finished { cancelled: false })
+            .unwrap();
+
+        let entries = read_journal(&path).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert!(
+            entries
+                .iter()
+                .all(|entry| entry.session_id == journal.session_id())
+        );
+
+        let summaries = summarize_sessions(&entries);
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].direction, Some(Direction::Send));
+        assert_eq!(summaries[0].files, 1);
+        assert_eq!(summaries[0].completed_files, 1);
+        assert_eq!(summaries[0].bytes, 3);
+        assert_eq!(summaries[0].status, SessionStatus::Completed);
+    }
+
+    #[test]
+    fn appends_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(JOURNAL_FILE);
+
+        let first = Journal::start_at(&path, Direction::Send, vec![]).unwrap();
+        first
+            .record(JournalEvent::Finished { cancelled: true })
+            .unwrap();
+        let second =
+            Journal::start_at(&path, Direction::Receive, vec![]).unwrap();
+        second
+            .record(JournalEvent::Error {
+                message: "Disk full".to_string(),
+            })
+            .unwrap();
+
+        let summaries = summarize_sessions(&read_journal(&path).unwrap());
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].session_id, first.session_id());
+        assert_eq!(summaries[0].status, SessionStatus::Cancelled);
+        assert_eq!(summaries[1].session_id, second.session_id());
+        assert_eq!(summaries[1].direction, Some(Direction::Receive));
+        assert_eq!(summaries[1].errors, 1);
+        assert_eq!(summaries[1].status, SessionStatus::Incomplete);
+    }
+
+    #[test]
+    fn skips_corrupted_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(JOURNAL_FILE);
+
+        let journal =
+            Journal::start_at(&path, Direction::Send, vec![]).unwrap();
+        // A line left incomplete by a crash
+        let mut file = File::options().append(true).open(&path).unwrap();
+        file.write_all(b"{\"session_id\":\"trunc")
+            .unwrap();
+        file.write_all(b"\n").unwrap();
+        journal
+            .record(JournalEvent::Finished { cancelled: false })
+            .unwrap();
+
+        let entries = read_journal(&path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(matches!(entries[0].event, JournalEvent::Started { .. }));
+        assert!(matches!(entries[1].event, JournalEvent::Finished { .. }));
+    }
+
+    #[test]
+    fn reads_missing_journal_as_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let entries = read_journal(&dir.path().join(JOURNAL_FILE)).unwrap();
+        assert!(entries.is_empty());
+    }
+}
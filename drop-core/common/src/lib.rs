@@ -1,86 +1,179 @@
 //! arkdrop_common library
 //! ```
+pub mod avatar;
+pub mod dirs;
+mod file_data;
+pub mod identity;
+pub mod journal;
+pub mod peers;
+pub mod progress;
+mod sources;
+pub mod writer;
+
 use std::{
+    collections::{BTreeMap, BTreeSet},
     env,
     fs::{self},
-    io::Cursor,
-    path::PathBuf,
-    sync::{RwLock, atomic::AtomicBool},
+    net::IpAddr,
+    path::{Component, Path, PathBuf},
 };
 
 use anyhow::{Context, Result, anyhow};
-use arkdropx_sender::SenderFileData;
-use base64::{Engine, engine::general_purpose};
-use image::ImageFormat;
+use arkdropx_sender::EndpointConfig;
+use dirs::AppDirs;
 use serde::{Deserialize, Serialize};
 
+pub use arkdropx_sender::{
+    DropAction, DropUri, ItemKind, LinkItem, send_files_to::ticket_node_id,
+};
+pub use file_data::FileData;
+pub use sources::{InMemoryData, ReaderData};
+
+/// Progress of a transferred file, formerly its own type.
+#[deprecated(note = "use `progress::FileProgress` instead")]
+pub type TransferFile = progress::FileProgress;
+
 /// Configuration for the application.
 ///
 /// This structure is persisted to TOML and stores user preferences for the app
 /// usage, such as the default directory to save received files.
 ///
-/// Storage location:
-/// - Linux: $XDG_CONFIG_HOME/arkdrop_common/config.toml or
-///   $HOME/.config/arkdrop_common/config.toml
-/// - macOS: $HOME/Library/Application Support/arkdrop_common/config.toml
-/// - Windows: %APPDATA%\arkdrop_common\config.toml
+/// Storage location: `config.toml` in the config directory of [`AppDirs`].
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub struct AppConfig {
     pub out_dir: Option<PathBuf>,
     pub avatar_name: Option<String>,
     pub avatar_file: Option<PathBuf>,
+    /// Whether sessions are recorded in the journal, see [`journal`].
+    #[serde(default)]
+    pub journal: bool,
+    /// Proxy and interface used by transfers.
+    #[serde(default)]
+    pub network: NetworkConfig,
+    /// Bandwidth and size limits of transfers.
+    #[serde(default)]
+    pub limits: LimitsConfig,
+    /// Receive directories of trusted peers, keyed by node id or display
+    /// name, used instead of `out_dir` for files they send.
+    #[serde(default)]
+    pub peer_out_dirs: BTreeMap<String, PathBuf>,
+    /// Continuous sync with paired devices.
+    #[serde(default)]
+    pub sync: SyncConfig,
+    /// Chunk size and parallel streams of transfers.
+    #[serde(default)]
+    pub preset: PerformancePreset,
 }
 
-impl AppConfig {
-    /// Returns the configuration directory path, creating a path under the
-    /// user's platform-appropriate config directory.
-    fn config_dir() -> Result<PathBuf> {
-        let default_path = "ARK-Drop";
-
-        #[cfg(target_os = "windows")]
-        {
-            if let Ok(appdata) = env::var("APPDATA") {
-                return Ok(PathBuf::from(appdata).join(default_path));
-            }
-            // Fallback if APPDATA isn't set (rare)
-            if let Ok(userprofile) = env::var("USERPROFILE") {
-                return Ok(PathBuf::from(userprofile)
-                    .join(".config")
-                    .join("ARK-Drop"));
-            }
-            return Err(anyhow!(
-                "Unable to determine config directory (missing APPDATA/USERPROFILE)"
-            ));
+/// Tuning of transfers, matching the presets of the sender and receiver
+/// configs. Both peers propose theirs, the smaller values are used.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum PerformancePreset {
+    /// 512 KiB chunks, 8 parallel streams.
+    HighPerformance,
+    /// 512 KiB chunks, 4 parallel streams.
+    #[default]
+    Balanced,
+    /// 64 KiB chunks, 2 parallel streams, for constrained networks.
+    LowBandwidth,
+}
+
+impl PerformancePreset {
+    pub const ALL: [Self; 3] =
+        [Self::HighPerformance, Self::Balanced, Self::LowBandwidth];
+
+    /// Name of the preset in the config file.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::HighPerformance => "high_performance",
+            Self::Balanced => "balanced",
+            Self::LowBandwidth => "low_bandwidth",
+        }
+    }
+
+    /// Returns the preset with the given name, see [`Self::name`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|preset| preset.name() == name)
+    }
+
+    /// Target chunk size in bytes.
+    pub fn chunk_size(&self) -> u64 {
+        match self {
+            Self::HighPerformance | Self::Balanced => 512 * 1024,
+            Self::LowBandwidth => 64 * 1024,
         }
+    }
 
-        #[cfg(target_os = "macos")]
-        {
-            if let Ok(home) = env::var("HOME") {
-                return Ok(PathBuf::from(home)
-                    .join("Library")
-                    .join("Application Support")
-                    .join(default_path));
-            }
-            return Err(anyhow!(
-                "Unable to determine config directory (missing HOME)"
-            ));
+    /// Number of streams used in parallel.
+    pub fn parallel_streams(&self) -> u64 {
+        match self {
+            Self::HighPerformance => 8,
+            Self::Balanced => 4,
+            Self::LowBandwidth => 2,
         }
+    }
+}
+
+/// Network settings for users behind restrictive networks, such as
+/// corporate proxies or machines with several interfaces.
+#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+pub struct NetworkConfig {
+    /// HTTP or SOCKS5 proxy URL, e.g. `socks5://127.0.0.1:1080`.
+    pub proxy: Option<String>,
+    /// Address of the local interface to bind, all interfaces if `None`.
+    pub bind_address: Option<IpAddr>,
+}
 
-        #[cfg(not(any(target_os = "windows", target_os = "macos")))]
-        {
-            let config_dir = if let Ok(xdg_config_home) =
-                env::var("XDG_CONFIG_HOME")
-            {
-                PathBuf::from(xdg_config_home)
-            } else if let Ok(home) = env::var("HOME") {
-                PathBuf::from(home).join(".config")
-            } else {
-                return Err(anyhow!(
-                    "Unable to determine config directory (missing XDG_CONFIG_HOME/HOME)"
-                ));
-            };
-            Ok(config_dir.join(default_path))
+impl NetworkConfig {
+    /// Endpoint options applying these settings. An IPv4 bind address
+    /// leaves IPv6 bound on all interfaces, and vice versa.
+    pub fn endpoint_config(&self) -> EndpointConfig {
+        let mut config = EndpointConfig {
+            proxy: self.proxy.clone(),
+            ..Default::default()
+        };
+        match self.bind_address {
+            Some(IpAddr::V4(ip)) => config.bind_ip_v4 = Some(ip),
+            Some(IpAddr::V6(ip)) => config.bind_ip_v6 = Some(ip),
+            None => {}
         }
+        config
+    }
+}
+
+/// Limits of transfers, `None` means unlimited.
+///
+/// Rates are in bytes per second, each shared by all streams of a transfer.
+#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+pub struct LimitsConfig {
+    pub max_upload_rate: Option<u64>,
+    pub max_download_rate: Option<u64>,
+    /// Largest total size in bytes of the files received in a session,
+    /// larger ones are refused before anything is written.
+    pub max_receive_size: Option<u64>,
+}
+
+/// Settings of continuous sync, where paired devices push files to each
+/// other with their persistent identity, see [`identity`].
+#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+pub struct SyncConfig {
+    /// Confirmation code expected from senders when listening, fixed so the
+    /// ticket and code given once to a paired device stay valid.
+    pub confirmation: Option<u8>,
+    /// Node ids of the devices allowed to push files when listening.
+    #[serde(default)]
+    pub trusted_peers: BTreeSet<String>,
+}
+
+impl AppConfig {
+    /// Returns the configuration directory path, see [`AppDirs`].
+    fn config_dir() -> Result<PathBuf> {
+        Ok(AppDirs::resolve()?.config)
     }
 
     /// Returns the full config file path.
@@ -137,9 +230,12 @@ impl AppConfig {
         self.avatar_name.replace(name);
     }
 
+    /// Returns the display name shown to peers: the configured one, else
+    /// the name of this device, see [`identity::device`].
     pub fn get_avatar_name(&self) -> String {
         self.avatar_name
             .clone()
+            .or_else(|| identity::device().ok().map(|device| device.name))
             .unwrap_or("unknown".to_string())
     }
 
@@ -161,6 +257,94 @@ impl AppConfig {
         self.save()
     }
 
+    /// Updates and persists whether sessions are recorded in the journal.
+    pub fn set_journal(&mut self, enabled: bool) -> Result<()> {
+        self.journal = enabled;
+        self.save()
+    }
+
+    /// Updates and persists the network settings.
+    pub fn set_network(&mut self, network: NetworkConfig) -> Result<()> {
+        self.network = network;
+        self.save()
+    }
+
+    /// Updates and persists the limits of transfers.
+    pub fn set_limits(&mut self, limits: LimitsConfig) -> Result<()> {
+        self.limits = limits;
+        self.save()
+    }
+
+    /// Updates and persists the performance preset of transfers.
+    pub fn set_preset(&mut self, preset: PerformancePreset) -> Result<()> {
+        self.preset = preset;
+        self.save()
+    }
+
+    /// Returns the confirmation code of sync sessions, generating and
+    /// persisting one on first use.
+    pub fn get_sync_confirmation(&mut self) -> Result<u8> {
+        if let Some(confirmation) = self.sync.confirmation {
+            return Ok(confirmation);
+        }
+        let confirmation = rand::random_range(0..=99);
+        self.sync.confirmation = Some(confirmation);
+        self.save()?;
+        Ok(confirmation)
+    }
+
+    /// Allows the device with the given node id to push files, and persists
+    /// the change.
+    pub fn trust_peer(&mut self, node_id: String) -> Result<()> {
+        self.sync.trusted_peers.insert(node_id);
+        self.save()
+    }
+
+    /// Stops allowing a device to push files and persists the change.
+    /// Returns whether it was trusted.
+    pub fn untrust_peer(&mut self, node_id: &str) -> Result<bool> {
+        let removed = self.sync.trusted_peers.remove(node_id);
+        if removed {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    /// Updates and persists the receive directory of a peer, given by node
+    /// id or display name.
+    pub fn set_peer_out_dir(
+        &mut self,
+        peer: String,
+        dir: PathBuf,
+    ) -> Result<()> {
+        self.peer_out_dirs.insert(peer, dir);
+        self.save()
+    }
+
+    /// Forgets the receive directory of a peer and persists the change.
+    /// Returns whether the peer had one.
+    pub fn remove_peer_out_dir(&mut self, peer: &str) -> Result<bool> {
+        let removed = self.peer_out_dirs.remove(peer).is_some();
+        if removed {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    /// Returns the receive directory for files from the given sender: the
+    /// one mapped to its node id, else to its name, else the default one.
+    pub fn get_peer_out_dir(
+        &self,
+        node_id: Option<&str>,
+        name: &str,
+    ) -> PathBuf {
+        node_id
+            .and_then(|id| self.peer_out_dirs.get(id))
+            .or_else(|| self.peer_out_dirs.get(name))
+            .cloned()
+            .unwrap_or_else(|| self.get_out_dir())
+    }
+
     /// Returns the saved default receive directory, if any.
     pub fn get_out_dir(&self) -> PathBuf {
         match self.out_dir.clone() {
@@ -170,21 +354,10 @@ impl AppConfig {
     }
 }
 
+/// Reads an image file and returns it as a normalized Base64 avatar, see
+/// [`avatar`].
 pub fn transform_to_base64(path: &PathBuf) -> Result<String> {
-    let img = image::open(path)?;
-
-    let resized = img.resize(64, 64, image::imageops::FilterType::Lanczos3);
-
-    // Convert to JPEG format for smaller size
-    let mut buffer = Vec::new();
-    let mut cursor = Cursor::new(&mut buffer);
-
-    resized.write_to(&mut cursor, ImageFormat::Jpeg)?;
-
-    // Encode to base64
-    let base64_string = general_purpose::STANDARD.encode(&buffer);
-
-    Ok(format!("data:image/jpeg;base64,{}", base64_string))
+    avatar::encode_avatar_file(path)
 }
 
 /// Profile for the application.
@@ -220,18 +393,17 @@ impl Profile {
         Self { name, avatar_b64 }
     }
 
-    /// Load avatar from a file path and encode it as base64.
+    /// Load avatar from a file path, resized and re-encoded to fit in a
+    /// handshake (see [`avatar`]), and encode it as base64.
     ///
     /// Returns an updated Profile on success.
     ///
     /// Errors:
-    /// - If the file cannot be read or encoded.
+    /// - If the file cannot be read, isn't an image, or can't be shrunk to
+    ///   fit.
     pub fn with_avatar_file(mut self, avatar_path: &str) -> Result<Self> {
-        let avatar_data = fs::read(avatar_path).with_context(|| {
-            format!("Failed to read avatar file: {avatar_path}")
-        })?;
-
-        self.avatar_b64 = Some(general_purpose::STANDARD.encode(&avatar_data));
+        self.avatar_b64 =
+            Some(avatar::encode_avatar_file(Path::new(avatar_path))?);
         Ok(self)
     }
 
@@ -243,198 +415,6 @@ impl Profile {
     }
 }
 
-/// In-memory, seek-based file data source for the sender.
-///
-/// This implementation:
-/// - Supports both single-byte reads (`read`) and ranged chunk reads
-///   (`read_chunk`).
-/// - Uses atomic counters to coordinate chunked read offsets safely.
-/// - Reports its total length through `len`.
-///
-/// Notes:
-/// - Errors are logged and will mark the stream as finished to prevent
-///   stalling.
-pub struct FileData {
-    is_finished: AtomicBool,
-    path: PathBuf,
-    reader: RwLock<Option<std::fs::File>>,
-    size: u64,
-    bytes_read: std::sync::atomic::AtomicU64,
-}
-
-impl FileData {
-    /// Create a new FileData for the given path, capturing size metadata.
-    ///
-    /// Errors:
-    /// - If the file's metadata cannot be read.
-    pub fn new(path: PathBuf) -> Result<Self> {
-        let metadata = fs::metadata(&path).with_context(|| {
-            format!("Failed to get metadata for file: {}", path.display())
-        })?;
-
-        Ok(Self {
-            is_finished: AtomicBool::new(false),
-            path,
-            reader: RwLock::new(None),
-            size: metadata.len(),
-            bytes_read: std::sync::atomic::AtomicU64::new(0),
-        })
-    }
-}
-
-impl SenderFileData for FileData {
-    /// Returns the total file size in bytes.
-    fn len(&self) -> u64 {
-        self.size
-    }
-
-    /// Checks if the data is empty (length is 0).
-    fn is_empty(&self) -> bool {
-        self.size == 0
-    }
-
-    /// Reads a single byte, falling back to EOF (None) at end of file or on
-    /// errors.
-    fn read(&self) -> Option<u8> {
-        use std::io::Read;
-
-        if self
-            .is_finished
-            .load(std::sync::atomic::Ordering::Relaxed)
-        {
-            return None;
-        }
-
-        if self.reader.read().unwrap().is_none() {
-            match std::fs::File::open(&self.path) {
-                Ok(file) => {
-                    *self.reader.write().unwrap() = Some(file);
-                }
-                Err(e) => {
-                    eprintln!(
-                        "❌ Error opening file {}: {}",
-                        self.path.display(),
-                        e
-                    );
-                    self.is_finished
-                        .store(true, std::sync::atomic::Ordering::Relaxed);
-                    return None;
-                }
-            }
-        }
-
-        // Read next byte
-        let mut reader = self.reader.write().unwrap();
-        if let Some(file) = reader.as_mut() {
-            let mut buffer = [0u8; 1];
-            match file.read(&mut buffer) {
-                Ok(bytes_read) => {
-                    if bytes_read == 0 {
-                        *reader = None;
-                        self.is_finished
-                            .store(true, std::sync::atomic::Ordering::Relaxed);
-                        None
-                    } else {
-                        Some(buffer[0])
-                    }
-                }
-                Err(e) => {
-                    eprintln!(
-                        "❌ Error reading from file {}: {}",
-                        self.path.display(),
-                        e
-                    );
-                    *reader = None;
-                    self.is_finished
-                        .store(true, std::sync::atomic::Ordering::Relaxed);
-                    None
-                }
-            }
-        } else {
-            None
-        }
-    }
-
-    /// Reads up to `size` bytes as a contiguous chunk starting from the next
-    /// claimed position. Returns an empty Vec when the file is fully consumed
-    /// or on errors.
-    fn read_chunk(&self, size: u64) -> Vec<u8> {
-        use std::{
-            io::{Read, Seek, SeekFrom},
-            sync::atomic::Ordering,
-        };
-
-        if self.is_finished.load(Ordering::Acquire) {
-            return Vec::new();
-        }
-
-        // Atomically claim the next chunk position
-        let current_position =
-            self.bytes_read.fetch_add(size, Ordering::AcqRel);
-
-        // Check if we've already passed the end of the file
-        if current_position >= self.size {
-            // Reset the bytes_read counter and mark as finished
-            self.bytes_read
-                .store(self.size, Ordering::Release);
-            self.is_finished.store(true, Ordering::Release);
-            return Vec::new();
-        }
-
-        // Calculate how much to actually read (don't exceed file size)
-        let remaining = self.size - current_position;
-        let to_read = std::cmp::min(size, remaining) as usize;
-
-        // Open a new file handle for this read operation
-        let mut file = match std::fs::File::open(&self.path) {
-            Ok(file) => file,
-            Err(e) => {
-                eprintln!(
-                    "❌ Error opening file {}: {}",
-                    self.path.display(),
-                    e
-                );
-                self.is_finished.store(true, Ordering::Release);
-                return Vec::new();
-            }
-        };
-
-        // Seek to the claimed position
-        if let Err(e) = file.seek(SeekFrom::Start(current_position)) {
-            eprintln!(
-                "❌ Error seeking to position {} in file {}: {}",
-                current_position,
-                self.path.display(),
-                e
-            );
-            self.is_finished.store(true, Ordering::Release);
-            return Vec::new();
-        }
-
-        // Read the chunk
-        let mut buffer = vec![0u8; to_read];
-        match file.read_exact(&mut buffer) {
-            Ok(()) => {
-                // Check if we've finished reading the entire file
-                if current_position + to_read as u64 >= self.size {
-                    self.is_finished.store(true, Ordering::Release);
-                }
-
-                buffer
-            }
-            Err(e) => {
-                eprintln!(
-                    "❌ Error reading chunk from file {}: {}",
-                    self.path.display(),
-                    e
-                );
-                self.is_finished.store(true, Ordering::Release);
-                Vec::new()
-            }
-        }
-    }
-}
-
 /// Returns the saved default receive directory path, if any, otherwise returns
 /// fallback.
 ///
@@ -449,6 +429,18 @@ pub fn get_default_out_dir() -> PathBuf {
     suggested_default_out_dir()
 }
 
+/// Returns the directory holding the application data, such as peer
+/// caches, see [`AppDirs`].
+pub fn data_dir() -> Result<PathBuf> {
+    Ok(AppDirs::resolve()?.data)
+}
+
+/// Returns the directory holding runtime state, such as the journal, see
+/// [`AppDirs`].
+pub fn state_dir() -> Result<PathBuf> {
+    Ok(AppDirs::resolve()?.state)
+}
+
 /// Returns a suggested default receive directory when no saved default exists:
 /// - Linux/macOS: $HOME/Downloads/ARK-Drop
 /// - Windows: %USERPROFILE%\Downloads\Drop
@@ -498,33 +490,80 @@ pub fn clear_default_out_dir() -> Result<()> {
     config.save()
 }
 
-#[derive(Clone)]
-pub struct TransferFile {
-    pub id: String,
-    pub name: String,
-    pub path: PathBuf,
-    pub len: u64,
-    pub expected_len: u64,
+/// Expands the given paths into the files to send, with their names.
+///
+/// A file is sent under its file name. A directory is walked recursively and
+/// its files are named by their path relative to the directory's parent,
+/// using `/` as separator, e.g. `photos/2024/a.jpg`, so the receiver can
+/// recreate the tree. Files are sorted by name within each directory.
+///
+/// Errors:
+/// - If a path does not exist or a directory cannot be read.
+pub fn collect_files(paths: &[PathBuf]) -> Result<Vec<(String, PathBuf)>> {
+    let mut files = Vec::new();
+    for path in paths {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .ok_or_else(|| anyhow!("Invalid file name: {}", path.display()))?;
+        if path.is_dir() {
+            collect_dir(path, &name, &mut files)?;
+        } else if path.is_file() {
+            files.push((name, path.clone()));
+        } else {
+            return Err(anyhow!("File does not exist: {}", path.display()));
+        }
+    }
+    Ok(files)
 }
-impl TransferFile {
-    pub fn new(
-        id: String,
-        name: String,
-        path: PathBuf,
-        expected_len: u64,
-    ) -> Self {
-        Self {
-            id,
-            name,
-            path,
-            len: 0,
-            expected_len,
+
+fn collect_dir(
+    dir: &Path,
+    prefix: &str,
+    files: &mut Vec<(String, PathBuf)>,
+) -> Result<()> {
+    let mut entries = fs::read_dir(dir)
+        .with_context(|| {
+            format!("Failed to read directory: {}", dir.display())
+        })?
+        .collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let path = entry.path();
+        let name = format!("{prefix}/{}", entry.file_name().to_string_lossy());
+        if path.is_dir() {
+            collect_dir(&path, &name, files)?;
+        } else if path.is_file() {
+            files.push((name, path));
         }
     }
+    Ok(())
+}
 
-    pub fn get_pct(&self) -> f64 {
-        let raw_pct = self.len / self.expected_len;
-        let pct: u32 = raw_pct.try_into().unwrap_or(0);
-        pct.into()
+/// Resolves the name of a received file to a path inside `out_dir`.
+///
+/// Names may contain directories separated by `/`. Empty, `.` and `..`
+/// segments as well as absolute paths are rejected, so a sender can't write
+/// outside of `out_dir`.
+///
+/// Errors:
+/// - If the name doesn't denote a file inside `out_dir`.
+pub fn received_file_path(out_dir: &Path, name: &str) -> Result<PathBuf> {
+    let mut path = out_dir.to_path_buf();
+    for segment in name.split(['/', '\\']) {
+        let component = Path::new(segment);
+        let is_plain = matches!(
+            component
+                .components()
+                .collect::<Vec<_>>()
+                .as_slice(),
+            [Component::Normal(_)]
+        );
+        if !is_plain {
+            return Err(anyhow!("Invalid file name: {name}"));
+        }
+        path.push(segment);
     }
+    Ok(path)
 }
@@ -0,0 +1,205 @@
+//! Address book of the peers met in past sessions.
+//!
+//! Peers are identified by the node id of their endpoint and stored in a
+//! JSON file in the data directory (see [`crate::dirs`]). Their avatars are
+//! decoded once into image files next to it, so front-ends can show who is
+//! connecting, and when they were last seen, without decoding the avatar
+//! sent in every handshake.
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{avatar, data_dir};
+
+const PEERS_FILE: &str = "peers.json";
+const AVATARS_DIR: &str = "avatars";
+
+/// A peer met in a past session.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Peer {
+    pub node_id: String,
+    /// Display name in the last session.
+    pub name: String,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    /// Number of sessions with the peer.
+    pub sessions: u64,
+    /// File name of the decoded avatar in the avatars directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avatar: Option<String>,
+}
+
+/// The peers met so far, backed by a file.
+pub struct PeerBook {
+    path: PathBuf,
+    peers: BTreeMap<String, Peer>,
+}
+
+impl PeerBook {
+    /// Returns the path of the peers file in the data directory.
+    pub fn default_path() -> Result<PathBuf> {
+        Ok(data_dir()?.join(PEERS_FILE))
+    }
+
+    /// Loads the address book from the default path.
+    pub fn load() -> Result<Self> {
+        Self::load_at(&Self::default_path()?)
+    }
+
+    /// Loads the address book from `path`, empty if the file doesn't exist.
+    ///
+    /// Errors:
+    /// - If the file exists but can't be read or parsed.
+    pub fn load_at(path: &Path) -> Result<Self> {
+        let peers = if path.exists() {
+            let content = fs::read_to_string(path).with_context(|| {
+                format!("Failed to read peers file: {}", path.display())
+            })?;
+            serde_json::from_str(&content).with_context(|| {
+                format!("Failed to parse peers file: {}", path.display())
+            })?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            peers,
+        })
+    }
+
+    pub fn get(&self, node_id: &str) -> Option<&Peer> {
+        self.peers.get(node_id)
+    }
+
+    /// Returns the peers, the most recently seen first.
+    pub fn peers(&self) -> Vec<&Peer> {
+        let mut peers: Vec<_> = self.peers.values().collect();
+        peers.sort_by(|a, b| b.last_seen.cmp(&a.last_seen));
+        peers
+    }
+
+    /// Returns the path of the peer's decoded avatar, if any.
+    pub fn avatar_path(&self, peer: &Peer) -> Option<PathBuf> {
+        let file = peer.avatar.as_ref()?;
+        Some(self.avatars_dir().join(file))
+    }
+
+    /// Records a session with the peer and saves the address book.
+    ///
+    /// The avatar, base64 encoded with or without a `data:` URL prefix as
+    /// sent in handshakes, replaces the cached one. An avatar that can't be
+    /// decoded keeps the cached one.
+    ///
+    /// Errors:
+    /// - If the address book or the avatar can't be written.
+    pub fn record(
+        &mut self,
+        node_id: &str,
+        name: &str,
+        avatar_b64: Option<&str>,
+    ) -> Result<&Peer> {
+        let now = Utc::now();
+        let avatar = match avatar_b64.and_then(decode_avatar) {
+            Some((bytes, extension)) => {
+                Some(self.write_avatar(node_id, &bytes, extension)?)
+            }
+            None => None,
+        };
+
+        let peer = self
+            .peers
+            .entry(node_id.to_string())
+            .or_insert_with(|| Peer {
+                node_id: node_id.to_string(),
+                name: name.to_string(),
+                first_seen: now,
+                last_seen: now,
+                sessions: 0,
+                avatar: None,
+            });
+        peer.name = name.to_string();
+        peer.last_seen = now;
+        peer.sessions += 1;
+        if avatar.is_some() {
+            peer.avatar = avatar;
+        }
+
+        self.save()?;
+        Ok(&self.peers[node_id])
+    }
+
+    /// Removes the peer and its avatar. Returns whether it was known.
+    ///
+    /// Errors:
+    /// - If the address book can't be written.
+    pub fn forget(&mut self, node_id: &str) -> Result<bool> {
+        let Some(peer) = self.peers.remove(node_id) else {
+            return Ok(false);
+        };
+        if let Some(path) = self.avatar_path(&peer) {
+            let _ = fs::remove_file(path);
+        }
+        self.save()?;
+        Ok(true)
+    }
+
+    fn avatars_dir(&self) -> PathBuf {
+        self.path
+            .parent()
+            .unwrap_or(Path::new("."))
+            .join(AVATARS_DIR)
+    }
+
+    /// Writes the avatar and returns its file name.
+    fn write_avatar(
+        &self,
+        node_id: &str,
+        bytes: &[u8],
+        extension: &str,
+    ) -> Result<String> {
+        let dir = self.avatars_dir();
+        fs::create_dir_all(&dir).with_context(|| {
+            format!("Failed to create avatars directory: {}", dir.display())
+        })?;
+        let file = format!("{node_id}.{extension}");
+        let path = dir.join(&file);
+        fs::write(&path, bytes).with_context(|| {
+            format!("Failed to write avatar: {}", path.display())
+        })?;
+        Ok(file)
+    }
+
+    /// Writes the address book to a temporary file first, so a crash never
+    /// leaves it truncated.
+    fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).with_context(|| {
+                format!("Failed to create peers directory: {}", dir.display())
+            })?;
+        }
+        let content = serde_json::to_string_pretty(&self.peers)
+            .context("Failed to serialize peers")?;
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, content).with_context(|| {
+            format!("Failed to write peers file: {}", tmp.display())
+        })?;
+        fs::rename(&tmp, &self.path).with_context(|| {
+            format!("Failed to write peers file: {}", self.path.display())
+        })
+    }
+}
+
+/// Decodes a base64 avatar into its bytes and the extension of its image
+/// format, `None` if it isn't a known image.
+fn decode_avatar(avatar_b64: &str) -> Option<(Vec<u8>, &'static str)> {
+    let bytes = avatar::decode_avatar(avatar_b64).ok()?;
+    let format = image::guess_format(&bytes).ok()?;
+    let extension = format.extensions_str().first()?;
+    Some((bytes, extension))
+}
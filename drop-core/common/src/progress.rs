@@ -0,0 +1,341 @@
+//! Progress of a transfer session for front-ends.
+//!
+//! Subscribers feed the bytes they see into a [`TransferProgress`], either as
+//! cumulative counts (the sender's events) or as deltas (the receiver's
+//! chunks). Each [`FileProgress`] smooths its rate with an exponential moving
+//! average, so the speed and ETA shown don't jump with every chunk.
+use std::time::{Duration, Instant};
+
+/// Time constant of the rate average: samples older than this weigh about a
+/// third of a fresh one.
+const RATE_TIME_CONSTANT: Duration = Duration::from_secs(2);
+
+/// Shortest time between two rate samples, chunks arriving faster are summed
+/// into one sample.
+const MIN_SAMPLE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// State of a file in a session.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TransferState {
+    /// Announced, no bytes transferred yet.
+    Waiting,
+    Transferring,
+    Completed,
+    /// Abandoned with the given error.
+    Failed(String),
+}
+
+/// Transfer rate in bytes per second, averaged over time.
+#[derive(Clone, Debug, Default)]
+struct Rate {
+    bytes_per_second: f64,
+    /// Time and byte count of the last sample.
+    sampled: Option<(Instant, u64)>,
+}
+
+impl Rate {
+    fn sample(&mut self, done: u64, now: Instant) {
+        let Some((at, before)) = self.sampled else {
+            self.sampled = Some((now, done));
+            return;
+        };
+        let elapsed = now.saturating_duration_since(at);
+        if elapsed < MIN_SAMPLE_INTERVAL {
+            return;
+        }
+
+        let current =
+            done.saturating_sub(before) as f64 / elapsed.as_secs_f64();
+        self.bytes_per_second = if self.bytes_per_second == 0.0 {
+            current
+        } else {
+            let weight = 1.0
+                - (-elapsed.as_secs_f64() / RATE_TIME_CONSTANT.as_secs_f64())
+                    .exp();
+            self.bytes_per_second + weight * (current - self.bytes_per_second)
+        };
+        self.sampled = Some((now, done));
+    }
+}
+
+/// Progress of a single file.
+#[derive(Clone, Debug)]
+pub struct FileProgress {
+    pub id: String,
+    pub name: String,
+    /// Size of the file in bytes.
+    pub total: u64,
+    /// Bytes transferred so far.
+    pub done: u64,
+    pub state: TransferState,
+    rate: Rate,
+}
+
+impl FileProgress {
+    pub fn new(id: String, name: String, total: u64) -> Self {
+        Self {
+            id,
+            name,
+            total,
+            done: 0,
+            state: TransferState::Waiting,
+            rate: Rate::default(),
+        }
+    }
+
+    /// Records `bytes` more transferred bytes.
+    pub fn advance(&mut self, bytes: u64) {
+        self.set_done(self.done.saturating_add(bytes));
+    }
+
+    /// Records the count of transferred bytes.
+    pub fn set_done(&mut self, done: u64) {
+        if matches!(self.state, TransferState::Failed(_)) {
+            return;
+        }
+        self.done = done;
+        self.rate.sample(done, Instant::now());
+        self.state = if done >= self.total {
+            TransferState::Completed
+        } else {
+            TransferState::Transferring
+        };
+    }
+
+    /// Marks the file as abandoned.
+    pub fn fail(&mut self, error: String) {
+        self.state = TransferState::Failed(error);
+    }
+
+    pub fn remaining(&self) -> u64 {
+        self.total.saturating_sub(self.done)
+    }
+
+    /// Share of the file transferred, from 0 to 1. Empty files count as
+    /// done once completed.
+    pub fn fraction(&self) -> f64 {
+        if self.total == 0 {
+            return if self.state == TransferState::Completed {
+                1.0
+            } else {
+                0.0
+            };
+        }
+        (self.done as f64 / self.total as f64).min(1.0)
+    }
+
+    /// [`Self::fraction`] in percent.
+    pub fn percent(&self) -> f64 {
+        self.fraction() * 100.0
+    }
+
+    /// Average rate while transferring, 0 otherwise.
+    pub fn bytes_per_second(&self) -> f64 {
+        match self.state {
+            TransferState::Transferring => self.rate.bytes_per_second,
+            _ => 0.0,
+        }
+    }
+
+    /// Estimated time until the file is complete, `None` without a rate.
+    pub fn eta(&self) -> Option<Duration> {
+        eta(self.remaining(), self.bytes_per_second())
+    }
+}
+
+/// Progress of all files of a session.
+#[derive(Clone, Debug, Default)]
+pub struct TransferProgress {
+    files: Vec<FileProgress>,
+}
+
+impl TransferProgress {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a file, replacing one with the same ID.
+    pub fn add_file(&mut self, id: String, name: String, total: u64) {
+        let file = FileProgress::new(id, name, total);
+        match self.files.iter_mut().find(|f| f.id == file.id) {
+            Some(existing) => *existing = file,
+            None => self.files.push(file),
+        }
+    }
+
+    pub fn file(&self, id: &str) -> Option<&FileProgress> {
+        self.files.iter().find(|f| f.id == id)
+    }
+
+    pub fn file_mut(&mut self, id: &str) -> Option<&mut FileProgress> {
+        self.files.iter_mut().find(|f| f.id == id)
+    }
+
+    /// Files in the order they were added.
+    pub fn files(&self) -> &[FileProgress] {
+        &self.files
+    }
+
+    pub fn clear(&mut self) {
+        self.files.clear();
+    }
+
+    pub fn total(&self) -> u64 {
+        self.files.iter().map(|f| f.total).sum()
+    }
+
+    pub fn done(&self) -> u64 {
+        self.files.iter().map(|f| f.done).sum()
+    }
+
+    pub fn completed_files(&self) -> usize {
+        self.files
+            .iter()
+            .filter(|f| f.state == TransferState::Completed)
+            .count()
+    }
+
+    /// Share of all bytes transferred, from 0 to 1.
+    pub fn fraction(&self) -> f64 {
+        let total = self.total();
+        if total == 0 {
+            return 0.0;
+        }
+        (self.done() as f64 / total as f64).min(1.0)
+    }
+
+    /// [`Self::fraction`] in percent.
+    pub fn percent(&self) -> f64 {
+        self.fraction() * 100.0
+    }
+
+    /// Sum of the rates of the files being transferred.
+    pub fn bytes_per_second(&self) -> f64 {
+        self.files
+            .iter()
+            .map(FileProgress::bytes_per_second)
+            .sum()
+    }
+
+    /// Estimated time until all files are complete, `None` without a rate.
+    pub fn eta(&self) -> Option<Duration> {
+        let remaining = self
+            .files
+            .iter()
+            .filter(|f| !matches!(f.state, TransferState::Failed(_)))
+            .map(FileProgress::remaining)
+            .sum();
+        eta(remaining, self.bytes_per_second())
+    }
+}
+
+fn eta(remaining: u64, bytes_per_second: f64) -> Option<Duration> {
+    if remaining == 0 {
+        return Some(Duration::ZERO);
+    }
+    if bytes_per_second <= 0.0 {
+        return None;
+    }
+    Duration::try_from_secs_f64(remaining as f64 / bytes_per_second).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seconds(secs: f64) -> Duration {
+        Duration::from_secs_f64(secs)
+    }
+
+    #[test]
+    fn averages_rate_over_samples() {
+        let start = Instant::now();
+        let mut rate = Rate::default();
+
+        rate.sample(0, start);
+        assert_eq!(rate.bytes_per_second, 0.0);
+        rate.sample(1000, start + seconds(1.0));
+        assert_eq!(rate.bytes_per_second, 1000.0);
+
+        // A faster second moves the average part of the way
+        rate.sample(4000, start + seconds(2.0));
+        let weight = 1.0 - (-0.5f64).exp();
+        let expected = 1000.0 + weight * 2000.0;
+        assert!((rate.bytes_per_second - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn ignores_samples_without_elapsed_time() {
+        let start = Instant::now();
+        let mut rate = Rate::default();
+
+        rate.sample(0, start);
+        rate.sample(1000, start);
+        rate.sample(2000, start + MIN_SAMPLE_INTERVAL / 2);
+        assert_eq!(rate.bytes_per_second, 0.0);
+        assert_eq!(rate.sampled, Some((start, 0)));
+
+        // The bytes of skipped samples count in the next one
+        rate.sample(2000, start + seconds(1.0));
+        assert_eq!(rate.bytes_per_second, 2000.0);
+    }
+
+    #[test]
+    fn has_no_rate_without_bytes() {
+        let start = Instant::now();
+        let mut rate = Rate::default();
+        rate.sample(0, start);
+        rate.sample(0, start + seconds(1.0));
+        assert_eq!(rate.bytes_per_second, 0.0);
+
+        let mut file = FileProgress::new("1".into(), "a".into(), 100);
+        file.advance(0);
+        assert_eq!(file.state, TransferState::Transferring);
+        assert_eq!(file.eta(), None);
+    }
+
+    #[test]
+    fn estimates_remaining_time() {
+        assert_eq!(eta(0, 0.0), Some(Duration::ZERO));
+        assert_eq!(eta(100, 0.0), None);
+        assert_eq!(eta(100, 50.0), Some(seconds(2.0)));
+
+        let mut file = FileProgress::new("1".into(), "a".into(), 1000);
+        file.set_done(500);
+        file.rate.bytes_per_second = 100.0;
+        assert_eq!(file.eta(), Some(seconds(5.0)));
+    }
+
+    #[test]
+    fn completed_files_have_no_rate() {
+        let mut file = FileProgress::new("1".into(), "a".into(), 10);
+        file.set_done(10);
+        file.rate.bytes_per_second = 100.0;
+        assert_eq!(file.state, TransferState::Completed);
+        assert_eq!(file.bytes_per_second(), 0.0);
+        assert_eq!(file.eta(), Some(Duration::ZERO));
+        assert_eq!(file.fraction(), 1.0);
+
+        let mut empty = FileProgress::new("2".into(), "b".into(), 0);
+        assert_eq!(empty.fraction(), 0.0);
+        empty.set_done(0);
+        assert_eq!(empty.fraction(), 1.0);
+    }
+
+    #[test]
+    fn leaves_failed_files_out_of_eta() {
+        let mut progress = TransferProgress::new();
+        progress.add_file("1".into(), "a".into(), 10);
+        progress.add_file("2".into(), "b".into(), 100);
+        progress.file_mut("1").unwrap().set_done(10);
+
+        let failed = progress.file_mut("2").unwrap();
+        failed.fail("Disk full".to_string());
+        failed.set_done(50);
+        assert_eq!(failed.done, 0);
+
+        assert_eq!(progress.completed_files(), 1);
+        assert_eq!(progress.eta(), Some(Duration::ZERO));
+        assert_eq!(progress.fraction(), 10.0 / 110.0);
+    }
+}
@@ -0,0 +1,138 @@
+//! Ready-made data sources for the sender, besides [`crate::FileData`].
+//!
+//! [`InMemoryData`] serves a buffer, e.g. a text typed by the user or test
+//! contents. [`ReaderData`] serves any `Read + Seek` source, e.g. a handle
+//! to a content URI an app received from the platform.
+use std::{
+    io::{self, Read, Seek, SeekFrom},
+    sync::Mutex,
+};
+
+use arkdropx_sender::SenderFileData;
+use bytes::Bytes;
+
+/// Data source serving an in-memory buffer.
+///
+/// Accepts anything convertible to [`Bytes`], like `Vec<u8>`, `String` or
+/// `&'static [u8]`. Cloning `Bytes` doesn't copy, so the same buffer can
+/// back several sources.
+pub struct InMemoryData {
+    data: Bytes,
+    position: Mutex<usize>,
+}
+
+impl InMemoryData {
+    pub fn new(data: impl Into<Bytes>) -> Self {
+        Self {
+            data: data.into(),
+            position: Mutex::new(0),
+        }
+    }
+}
+
+impl SenderFileData for InMemoryData {
+    fn len(&self) -> u64 {
+        self.data.len() as u64
+    }
+
+    fn read(&self) -> Option<u8> {
+        let mut position = self.position.lock().unwrap();
+        let byte = self.data.get(*position).copied()?;
+        *position += 1;
+        Some(byte)
+    }
+
+    fn read_chunk(&self, size: u64) -> Vec<u8> {
+        let mut position = self.position.lock().unwrap();
+        let start = *position;
+        let end = start
+            .saturating_add(usize::try_from(size).unwrap_or(usize::MAX))
+            .min(self.data.len());
+        *position = end;
+        self.data[start..end].to_vec()
+    }
+}
+
+/// Data source reading from a `Read + Seek` source.
+///
+/// The length is what remains from the position of the source when the
+/// data is created. Reads are sequential, so wrap sources without their own
+/// buffering in a [`std::io::BufReader`] when using [`SenderFileData::read`].
+///
+/// Notes:
+/// - Errors are logged and end the data early, the receiver then notices
+///   the missing bytes.
+pub struct ReaderData<R> {
+    len: u64,
+    state: Mutex<ReaderState<R>>,
+}
+
+struct ReaderState<R> {
+    reader: R,
+    remaining: u64,
+}
+
+impl<R: Read + Seek + Send> ReaderData<R> {
+    /// Measures the source by seeking to its end and back.
+    ///
+    /// Errors:
+    /// - If the source can't seek.
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let start = reader.stream_position()?;
+        let end = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(start))?;
+
+        let len = end.saturating_sub(start);
+        Ok(Self {
+            len,
+            state: Mutex::new(ReaderState {
+                reader,
+                remaining: len,
+            }),
+        })
+    }
+}
+
+impl<R: Read + Seek + Send> SenderFileData for ReaderData<R> {
+    fn len(&self) -> u64 {
+        self.len
+    }
+
+    fn read(&self) -> Option<u8> {
+        let mut state = self.state.lock().unwrap();
+        if state.remaining == 0 {
+            return None;
+        }
+
+        let mut buffer = [0u8; 1];
+        match state.reader.read_exact(&mut buffer) {
+            Ok(()) => {
+                state.remaining -= 1;
+                Some(buffer[0])
+            }
+            Err(e) => {
+                eprintln!("[ERROR] Error reading from source: {e}");
+                state.remaining = 0;
+                None
+            }
+        }
+    }
+
+    fn read_chunk(&self, size: u64) -> Vec<u8> {
+        let mut state = self.state.lock().unwrap();
+        let to_read = size.min(state.remaining) as usize;
+
+        let mut buffer = vec![0u8; to_read];
+        match state.reader.read_exact(&mut buffer) {
+            Ok(()) => {
+                state.remaining -= to_read as u64;
+                buffer
+            }
+            Err(e) => {
+                eprintln!("[ERROR] Error reading chunk from source: {e}");
+                state.remaining = 0;
+                Vec::new()
+            }
+        }
+    }
+}
@@ -0,0 +1,490 @@
+//! Writing of received files.
+//!
+//! Receivers get chunks in subscriber callbacks, which run on the transfer
+//! tasks. [`FileWriters`] moves the disk work out of them: chunks are handed
+//! to a small pool of threads which append them to buffered per-file
+//! writers, flush those periodically instead of on every chunk, and sync
+//! each file to disk once it is finished.
+//!
+//! The chunks of a file are always handled by the same thread, so they are
+//! written in the order they were received.
+use std::{
+    collections::{HashMap, hash_map::DefaultHasher},
+    fs::{self, File},
+    hash::{Hash, Hasher},
+    io::{self, BufWriter, ErrorKind, Write},
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, SyncSender},
+    },
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// Capacity of the buffer of each file writer.
+const BUFFER_SIZE: usize = 256 * 1024;
+
+/// Chunks queued per thread before callbacks wait for the disk, which
+/// bounds the memory used when the network is faster than the disk.
+const QUEUE_SIZE: usize = 64;
+
+/// Default interval at which buffered data is flushed to the files.
+pub const DEFAULT_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A file which couldn't be written.
+#[derive(Debug)]
+pub struct WriteError {
+    /// Identifier the file was written with.
+    pub id: String,
+    pub path: PathBuf,
+    pub error: io::Error,
+}
+
+impl WriteError {
+    /// Returns true if the file couldn't be written for lack of space.
+    pub fn is_disk_full(&self) -> bool {
+        self.error.kind() == ErrorKind::StorageFull
+    }
+}
+
+impl std::fmt::Display for WriteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Failed to write {}: {}", self.path.display(), self.error)
+    }
+}
+
+impl std::error::Error for WriteError {}
+
+enum Command {
+    Write {
+        id: String,
+        path: PathBuf,
+        data: Vec<u8>,
+    },
+    Finish {
+        id: String,
+    },
+    Sync {
+        id: String,
+        modified: Option<SystemTime>,
+        done: mpsc::Sender<bool>,
+    },
+    Discard {
+        id: String,
+        path: PathBuf,
+    },
+    FinishAll {
+        done: mpsc::Sender<()>,
+    },
+}
+
+/// State shared by the threads of a pool.
+#[derive(Default)]
+struct Shared {
+    errors: Mutex<Vec<WriteError>>,
+    disk_full: AtomicBool,
+}
+
+impl Shared {
+    fn fail(&self, id: &str, path: &Path, error: io::Error) {
+        let error = WriteError {
+            id: id.to_string(),
+            path: path.to_path_buf(),
+            error,
+        };
+        if error.is_disk_full() {
+            self.disk_full.store(true, Ordering::Relaxed);
+        }
+        if let Ok(mut errors) = self.errors.lock() {
+            errors.push(error);
+        }
+    }
+}
+
+/// Pool of threads writing the files of a receive session.
+///
+/// Files are opened in append mode, with their parent directories created,
+/// on their first chunk. Errors are collected rather than returned, see
+/// [`FileWriters::take_errors`]; a file which failed is not written anymore
+/// in the session.
+///
+/// Dropping the pool lets its threads flush and sync the files still open
+/// in the background, use [`FileWriters::finish_all`] to wait for it.
+pub struct FileWriters {
+    workers: Vec<SyncSender<Command>>,
+    shared: Arc<Shared>,
+}
+
+impl Default for FileWriters {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FileWriters {
+    /// Creates a pool with a thread per available core, up to 4, flushing
+    /// every [`DEFAULT_FLUSH_INTERVAL`].
+    pub fn new() -> Self {
+        let threads = thread::available_parallelism()
+            .map_or(1, |n| n.get())
+            .min(4);
+        Self::with_threads(threads, DEFAULT_FLUSH_INTERVAL)
+    }
+
+    /// Creates a pool of `threads` threads, at least one, flushing the
+    /// buffered data every `flush_interval`.
+    pub fn with_threads(threads: usize, flush_interval: Duration) -> Self {
+        let shared = Arc::new(Shared::default());
+        let workers = (0..threads.max(1))
+            .map(|_| {
+                let (tx, rx) = mpsc::sync_channel(QUEUE_SIZE);
+                let shared = shared.clone();
+                thread::spawn(move || run(rx, shared, flush_interval));
+                tx
+            })
+            .collect();
+        Self { workers, shared }
+    }
+
+    /// Queues a chunk to be appended to the file at `path`, identified by
+    /// `id` for the rest of the session.
+    ///
+    /// Only waits when the thread of the file is too far behind.
+    pub fn write(&self, id: &str, path: &Path, data: Vec<u8>) {
+        self.send(
+            id,
+            Command::Write {
+                id: id.to_string(),
+                path: path.to_path_buf(),
+                data,
+            },
+        );
+    }
+
+    /// Queues the file `id` to be flushed, synced to disk and closed, once
+    /// all of its chunks are received.
+    pub fn finish(&self, id: &str) {
+        self.send(id, Command::Finish { id: id.to_string() });
+    }
+
+    /// Flushes, syncs to disk and closes the file `id` once its queued
+    /// chunks are written, and waits for it. The modification time of the
+    /// file is set to `modified` if given, on a best effort basis.
+    ///
+    /// Returns false if the file couldn't be written in full, the error is
+    /// reported like the others, see [`FileWriters::take_errors`], or if
+    /// it isn't open, e.g. it got no chunk or was finished already.
+    pub fn sync(&self, id: &str, modified: Option<SystemTime>) -> bool {
+        let (done, synced) = mpsc::channel();
+        self.send(
+            id,
+            Command::Sync {
+                id: id.to_string(),
+                modified,
+                done,
+            },
+        );
+        synced.recv().unwrap_or(false)
+    }
+
+    /// Queues the file `id` to be closed and deleted from `path`, e.g. when
+    /// its data failed verification.
+    ///
+    /// Chunks queued before are written first, so none of them is left on
+    /// disk.
+    pub fn discard(&self, id: &str, path: &Path) {
+        self.send(
+            id,
+            Command::Discard {
+                id: id.to_string(),
+                path: path.to_path_buf(),
+            },
+        );
+    }
+
+    /// Flushes, syncs and closes all files still open, and waits for it.
+    ///
+    /// Returns the errors of the session which weren't taken yet.
+    pub fn finish_all(&self) -> Vec<WriteError> {
+        let (done, finished) = mpsc::channel();
+        for worker in &self.workers {
+            let _ = worker.send(Command::FinishAll { done: done.clone() });
+        }
+        drop(done);
+        // Ends once every thread dropped its sender
+        while finished.recv().is_ok() {}
+        self.take_errors()
+    }
+
+    /// Returns the errors which happened since the last call.
+    pub fn take_errors(&self) -> Vec<WriteError> {
+        self.shared
+            .errors
+            .lock()
+            .map(|mut errors| std::mem::take(&mut *errors))
+            .unwrap_or_default()
+    }
+
+    /// Returns true once a file couldn't be written for lack of space.
+    pub fn is_disk_full(&self) -> bool {
+        self.shared.disk_full.load(Ordering::Relaxed)
+    }
+
+    fn send(&self, id: &str, command: Command) {
+        let mut hasher = DefaultHasher::new();
+        id.hash(&mut hasher);
+        let worker = hasher.finish() as usize % self.workers.len();
+        // Threads only stop once the pool is dropped
+        let _ = self.workers[worker].send(command);
+    }
+}
+
+/// A file open for writing, `None` once it failed.
+struct OpenFile {
+    id: String,
+    path: PathBuf,
+    writer: Option<BufWriter<File>>,
+}
+
+impl OpenFile {
+    fn open(id: String, path: PathBuf, shared: &Shared) -> Self {
+        let opened = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| {
+                File::options()
+                    .create(true)
+                    .append(true)
+                    .open(&path)
+            });
+        let writer = match opened {
+            Ok(file) => Some(BufWriter::with_capacity(BUFFER_SIZE, file)),
+            Err(e) => {
+                shared.fail(&id, &path, e);
+                None
+            }
+        };
+        Self { id, path, writer }
+    }
+
+    fn write(&mut self, data: &[u8], shared: &Shared) {
+        if let Some(writer) = &mut self.writer
+            && let Err(e) = writer.write_all(data)
+        {
+            self.writer = None;
+            shared.fail(&self.id, &self.path, e);
+        }
+    }
+
+    fn flush(&mut self, shared: &Shared) {
+        if let Some(writer) = &mut self.writer
+            && let Err(e) = writer.flush()
+        {
+            self.writer = None;
+            shared.fail(&self.id, &self.path, e);
+        }
+    }
+
+    /// Returns whether all of the data reached the disk.
+    fn finish(mut self, modified: Option<SystemTime>, shared: &Shared) -> bool {
+        self.flush(shared);
+        let Some(writer) = &self.writer else {
+            return false;
+        };
+        // Only a detail of the file, which is complete without it
+        if let Some(modified) = modified {
+            let _ = writer.get_ref().set_modified(modified);
+        }
+        match writer.get_ref().sync_all() {
+            Ok(()) => true,
+            Err(e) => {
+                shared.fail(&self.id, &self.path, e);
+                false
+            }
+        }
+    }
+}
+
+fn run(rx: Receiver<Command>, shared: Arc<Shared>, flush_interval: Duration) {
+    let mut files: HashMap<String, OpenFile> = HashMap::new();
+    let mut next_flush = Instant::now() + flush_interval;
+
+    loop {
+        let timeout = next_flush.saturating_duration_since(Instant::now());
+        match rx.recv_timeout(timeout) {
+            Ok(Command::Write { id, path, data }) => files
+                .entry(id.clone())
+                .or_insert_with(|| OpenFile::open(id, path, &shared))
+                .write(&data, &shared),
+            Ok(Command::Finish { id }) => {
+                if let Some(file) = files.remove(&id) {
+                    file.finish(None, &shared);
+                }
+            }
+            Ok(Command::Sync { id, modified, done }) => {
+                let synced = files
+                    .remove(&id)
+                    .is_some_and(|file| file.finish(modified, &shared));
+                let _ = done.send(synced);
+            }
+            Ok(Command::Discard { id, path }) => {
+                files.remove(&id);
+                match fs::remove_file(&path) {
+                    Err(e) if e.kind() != ErrorKind::NotFound => {
+                        shared.fail(&id, &path, e)
+                    }
+                    _ => {}
+                }
+            }
+            Ok(Command::FinishAll { done }) => {
+                for (_, file) in files.drain() {
+                    file.finish(None, &shared);
+                }
+                drop(done);
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        // Checked after every command, as a busy queue never times out
+        if Instant::now() >= next_flush {
+            for file in files.values_mut() {
+                file.flush(&shared);
+            }
+            next_flush = Instant::now() + flush_interval;
+        }
+    }
+
+    for (_, file) in files.drain() {
+        file.finish(None, &shared);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_chunks_of_each_file_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let writers = FileWriters::with_threads(2, DEFAULT_FLUSH_INTERVAL);
+        let paths: Vec<PathBuf> = (0..4)
+            .map(|i| dir.path().join(format!("nested/{i}.bin")))
+            .collect();
+
+        for chunk in 0..100u8 {
+            for (i, path) in paths.iter().enumerate() {
+                writers.write(&i.to_string(), path, vec![chunk; 10]);
+            }
+        }
+        assert!(writers.finish_all().is_empty());
+
+        let expected: Vec<u8> =
+            (0..100u8).flat_map(|chunk| [chunk; 10]).collect();
+        for path in &paths {
+            assert_eq!(fs::read(path).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn finish_all_syncs_open_files() {
+        let dir = tempfile::tempdir().unwrap();
+        // Nothing is flushed on time, only when finishing
+        let writers = FileWriters::with_threads(1, Duration::from_secs(3600));
+        let finished = dir.path().join("finished.txt");
+        let open = dir.path().join("open.txt");
+
+        writers.write("1", &finished, b"first".to_vec());
+        writers.finish("1");
+        writers.write("2", &open, b"second".to_vec());
+
+        assert!(writers.finish_all().is_empty());
+        assert_eq!(fs::read(&finished).unwrap(), b"first");
+        assert_eq!(fs::read(&open).unwrap(), b"second");
+    }
+
+    #[test]
+    fn reports_files_which_cant_be_written() {
+        let dir = tempfile::tempdir().unwrap();
+        let blocking = dir.path().join("file");
+        fs::write(&blocking, b"").unwrap();
+        let writers = FileWriters::with_threads(1, DEFAULT_FLUSH_INTERVAL);
+
+        // The parent of the file is a file
+        let path = blocking.join("child.txt");
+        writers.write("1", &path, b"data".to_vec());
+        writers.write("1", &path, b"more".to_vec());
+        writers.write("2", &dir.path().join("ok.txt"), b"ok".to_vec());
+
+        let errors = writers.finish_all();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].id, "1");
+        assert_eq!(errors[0].path, path);
+        assert!(!errors[0].is_disk_full());
+        assert!(!writers.is_disk_full());
+        assert!(writers.take_errors().is_empty());
+        assert_eq!(fs::read(dir.path().join("ok.txt")).unwrap(), b"ok");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn flags_full_disk() {
+        let writers = FileWriters::with_threads(1, DEFAULT_FLUSH_INTERVAL);
+        assert!(!writers.is_disk_full());
+
+        // Writes to /dev/full fail with ENOSPC
+        writers.write("1", Path::new("/dev/full"), vec![0; 1024]);
+
+        let errors = writers.finish_all();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].is_disk_full());
+        assert!(writers.is_disk_full());
+    }
+
+    #[test]
+    fn syncs_files_on_request() {
+        let dir = tempfile::tempdir().unwrap();
+        let writers = FileWriters::with_threads(1, Duration::from_secs(3600));
+        let path = dir.path().join("synced.txt");
+
+        writers.write("1", &path, b"data".to_vec());
+        assert!(writers.sync("1", None));
+        assert_eq!(fs::read(&path).unwrap(), b"data");
+
+        // The parent of the file is a file
+        writers.write("2", &path.join("child.txt"), b"data".to_vec());
+        assert!(!writers.sync("2", None));
+        assert_eq!(writers.take_errors().len(), 1);
+        assert!(!writers.sync("3", None));
+    }
+
+    #[test]
+    fn applies_modification_time_on_sync() {
+        let dir = tempfile::tempdir().unwrap();
+        let writers = FileWriters::with_threads(1, DEFAULT_FLUSH_INTERVAL);
+        let path = dir.path().join("dated.txt");
+        let modified =
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+        writers.write("1", &path, b"data".to_vec());
+        assert!(writers.sync("1", Some(modified)));
+        assert_eq!(fs::metadata(&path).unwrap().modified().unwrap(), modified);
+    }
+
+    #[test]
+    fn discards_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let writers = FileWriters::with_threads(1, DEFAULT_FLUSH_INTERVAL);
+        let path = dir.path().join("corrupted.bin");
+
+        writers.write("1", &path, vec![1; 10]);
+        writers.discard("1", &path);
+        // Files without chunks are discarded as well
+        writers.discard("2", &dir.path().join("missing.bin"));
+
+        assert!(writers.finish_all().is_empty());
+        assert!(!path.exists());
+    }
+}
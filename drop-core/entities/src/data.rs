@@ -13,6 +13,8 @@
 //!   are consuming). If an implementation is non-consuming, document that
 //!   clearly for your type.
 
+use std::time::SystemTime;
+
 /// A thread-safe, sequential source of bytes.
 ///
 /// Implementors must be `Send + Sync` so instances can be shared across
@@ -60,4 +62,24 @@ pub trait Data: Send + Sync {
     /// Implementations should advance their internal cursor by the number
     /// of bytes returned.
     fn read_chunk(&self, size: u64) -> Vec<u8>;
+
+    /// Returns true if the underlying content changed since the object was
+    /// created, so the bytes read so far may not belong to a single version
+    /// of it.
+    ///
+    /// Sources which can't change, like in-memory buffers, keep the default.
+    fn has_changed(&self) -> bool {
+        false
+    }
+
+    /// BLAKE3 hash of the whole content, hex encoded, or `None` if the
+    /// source can't tell. Must not move the cursor.
+    fn content_hash(&self) -> Option<String> {
+        None
+    }
+
+    /// Time the underlying content was last modified, if it has one.
+    fn modified(&self) -> Option<SystemTime> {
+        None
+    }
 }
@@ -0,0 +1,178 @@
+//! Acknowledgements of the files a receiver got in full.
+//!
+//! With [`Capabilities::ACKNOWLEDGEMENTS`] negotiated, the receiver opens a
+//! unidirectional stream of its own once the handshake is done, and writes a
+//! [`FileAck`] on it for every file whose chunks all passed verification and
+//! add up to the announced length, once it stored the file for good, e.g.
+//! synced it to disk. Acknowledgements are framed like chunks:
+//! a 4-byte big-endian length followed by the JSON payload.
+//!
+//! Senders wait for the acknowledgements of the files they sent before
+//! closing the session, so they can act on them, e.g. delete the sources.
+//!
+//! [`Capabilities::ACKNOWLEDGEMENTS`]: crate::handshake::Capabilities::ACKNOWLEDGEMENTS
+
+use std::{
+    collections::{HashMap, HashSet},
+    pin::pin,
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use anyhow::Result;
+use iroh::endpoint::{Connection, ReadExactError, RecvStream, SendStream};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    sync::{Mutex as AsyncMutex, Notify},
+    time,
+};
+
+/// Largest acknowledgement accepted, ids are much shorter.
+const MAX_ACK_LEN: usize = 4 * 1024;
+
+/// Time senders wait for the acknowledgements still missing once all files
+/// were sent. Receivers acknowledge a file once its stream ended and they
+/// stored it.
+pub const ACK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A file received in full and verified.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FileAck {
+    /// Id of the file, see [`crate::handshake::HandshakeFile::id`].
+    pub id: String,
+}
+
+/// Receiver side of the acknowledgements, shared by the tasks receiving the
+/// files of a session.
+pub struct AckWriter {
+    stream: AsyncMutex<SendStream>,
+    /// Announced length of the files, by id.
+    lens: HashMap<String, u64>,
+}
+
+impl AckWriter {
+    /// Opens the stream of acknowledgements for files of the given lengths,
+    /// by id.
+    pub async fn open(
+        connection: &Connection,
+        lens: HashMap<String, u64>,
+    ) -> Result<Self> {
+        let stream = connection.open_uni().await?;
+        Ok(Self {
+            stream: AsyncMutex::new(stream),
+            lens,
+        })
+    }
+
+    /// Acknowledges the files of `ids`, a file and the copies received along
+    /// with it, whose announced length is `received`. Returns whether all
+    /// of them were.
+    pub async fn acknowledge(
+        &self,
+        ids: &[String],
+        received: u64,
+    ) -> Result<bool> {
+        let complete: Vec<_> = ids
+            .iter()
+            .filter(|id| self.lens.get(*id) == Some(&received))
+            .collect();
+        if complete.is_empty() {
+            return Ok(ids.is_empty());
+        }
+        let mut frames = Vec::new();
+        for id in &complete {
+            let payload = serde_json::to_vec(&FileAck { id: id.to_string() })?;
+            frames.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+            frames.extend_from_slice(&payload);
+        }
+        self.stream
+            .lock()
+            .await
+            .write_all(&frames)
+            .await?;
+        Ok(complete.len() == ids.len())
+    }
+}
+
+/// Sender side of the acknowledgements.
+#[derive(Default)]
+pub struct AckReader {
+    received: Mutex<HashSet<String>>,
+    /// Set once the stream of acknowledgements ended.
+    is_closed: AtomicBool,
+    changed: Notify,
+}
+
+impl AckReader {
+    /// Reads the acknowledgements of the receiver until its stream ends or
+    /// fails, calling `on_ack` with the id of each file.
+    pub async fn receive(
+        &self,
+        connection: &Connection,
+        on_ack: impl Fn(&str),
+    ) -> Result<()> {
+        let result = async {
+            let mut stream = connection.accept_uni().await?;
+            while let Some(ack) = read_ack(&mut stream).await? {
+                on_ack(&ack.id);
+                self.received.lock().unwrap().insert(ack.id);
+                self.changed.notify_waiters();
+            }
+            Ok(())
+        }
+        .await;
+        self.is_closed.store(true, Ordering::Release);
+        self.changed.notify_waiters();
+        result
+    }
+
+    /// Waits until all files of `ids` were acknowledged, for `timeout` at
+    /// most. Returns false if the stream of acknowledgements ended before,
+    /// or on timeout.
+    pub async fn wait_for(
+        &self,
+        ids: &HashSet<String>,
+        timeout: Duration,
+    ) -> bool {
+        time::timeout(timeout, self.wait_for_all(ids))
+            .await
+            .unwrap_or(false)
+    }
+
+    async fn wait_for_all(&self, ids: &HashSet<String>) -> bool {
+        loop {
+            // Registered before checking, so no acknowledgement is missed
+            let mut changed = pin!(self.changed.notified());
+            changed.as_mut().enable();
+            if ids.is_subset(&self.received.lock().unwrap()) {
+                return true;
+            }
+            if self.is_closed.load(Ordering::Acquire) {
+                return false;
+            }
+            changed.await;
+        }
+    }
+}
+
+/// Reads the next acknowledgement, `None` once the stream finished.
+async fn read_ack(stream: &mut RecvStream) -> Result<Option<FileAck>> {
+    let mut header = [0u8; 4];
+    match stream.read_exact(&mut header).await {
+        Ok(()) => {}
+        Err(ReadExactError::FinishedEarly(_)) => return Ok(None),
+        Err(ReadExactError::ReadError(e)) => return Err(e.into()),
+    }
+    let len = u32::from_be_bytes(header) as usize;
+    if len > MAX_ACK_LEN {
+        return Err(anyhow::Error::msg(format!(
+            "Acknowledgement of {len} bytes exceeds the limit of {MAX_ACK_LEN} bytes"
+        )));
+    }
+    let mut buffer = vec![0u8; len];
+    stream.read_exact(&mut buffer).await?;
+    Ok(Some(serde_json::from_slice(&buffer)?))
+}
@@ -0,0 +1,137 @@
+//! Networking options of the iroh endpoint bound by a transfer.
+//!
+//! By default an endpoint gets a fresh identity, uses the n0 relays and binds
+//! random ports. Embedders can override these to keep a stable node id across
+//! sessions, use their own relays, open predictable ports in a firewall or
+//! reach the relays through a proxy.
+
+use std::{
+    fmt,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddrV4, SocketAddrV6},
+};
+
+use anyhow::{Context, Result};
+use iroh::{Endpoint, RelayMap, RelayMode, RelayUrl, SecretKey, endpoint};
+use url::Url;
+
+/// Relay servers used to reach peers behind NATs.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum RelayConfig {
+    /// Public relays run by number 0.
+    #[default]
+    Default,
+    /// Only direct connections are attempted.
+    Disabled,
+    /// Relays at the given URLs.
+    Custom(Vec<String>),
+}
+
+/// Options applied to the endpoint before it is bound.
+///
+/// `Default` keeps the previous behavior: random identity, default relays and
+/// ports chosen by the OS.
+#[derive(Clone, Default)]
+pub struct EndpointConfig {
+    /// Secret key of the node. Persisting it keeps the node id, and so the
+    /// tickets, stable across sessions. A new key is generated if `None`.
+    pub secret_key: Option<[u8; 32]>,
+    /// Relay servers to use.
+    pub relay: RelayConfig,
+    /// UDP port bound on all IPv4 interfaces, random if `None`.
+    pub bind_port_v4: Option<u16>,
+    /// UDP port bound on all IPv6 interfaces, random if `None`.
+    pub bind_port_v6: Option<u16>,
+    /// IPv4 address of the interface to bind instead of all of them.
+    pub bind_ip_v4: Option<Ipv4Addr>,
+    /// IPv6 address of the interface to bind instead of all of them.
+    pub bind_ip_v6: Option<Ipv6Addr>,
+    /// HTTP or SOCKS5 proxy, e.g. `socks5://127.0.0.1:1080`, through which
+    /// relays are reached. Direct connections are plain UDP and bypass it,
+    /// so behind a proxy-only network transfers go through the relays.
+    pub proxy: Option<String>,
+    /// Skips publishing and resolving node addresses through n0 DNS. Peers
+    /// are then only reachable with the addresses in their tickets, which is
+    /// what local-only setups and tests want.
+    pub disable_discovery: bool,
+}
+
+impl fmt::Debug for EndpointConfig {
+    /// Leaves the secret key out, configs end up in logs.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EndpointConfig")
+            .field("secret_key", &self.secret_key.map(|_| "<redacted>"))
+            .field("relay", &self.relay)
+            .field("bind_port_v4", &self.bind_port_v4)
+            .field("bind_port_v6", &self.bind_port_v6)
+            .field("bind_ip_v4", &self.bind_ip_v4)
+            .field("bind_ip_v6", &self.bind_ip_v6)
+            .field("proxy", &self.proxy)
+            .field("disable_discovery", &self.disable_discovery)
+            .finish()
+    }
+}
+
+impl EndpointConfig {
+    /// Builder with these options applied.
+    pub fn builder(&self) -> Result<endpoint::Builder> {
+        let mut builder = Endpoint::builder();
+        if !self.disable_discovery {
+            builder = builder.discovery_n0();
+        }
+        if let Some(bytes) = &self.secret_key {
+            builder = builder.secret_key(SecretKey::from_bytes(bytes));
+        }
+        builder = builder.relay_mode(self.relay_mode()?);
+        if self.bind_ip_v4.is_some() || self.bind_port_v4.is_some() {
+            builder = builder.bind_addr_v4(SocketAddrV4::new(
+                self.bind_ip_v4.unwrap_or(Ipv4Addr::UNSPECIFIED),
+                self.bind_port_v4.unwrap_or(0),
+            ));
+        }
+        if self.bind_ip_v6.is_some() || self.bind_port_v6.is_some() {
+            builder = builder.bind_addr_v6(SocketAddrV6::new(
+                self.bind_ip_v6.unwrap_or(Ipv6Addr::UNSPECIFIED),
+                self.bind_port_v6.unwrap_or(0),
+                0,
+                0,
+            ));
+        }
+        if let Some(proxy) = &self.proxy {
+            let url = proxy
+                .parse::<Url>()
+                .with_context(|| format!("Invalid proxy URL {proxy}"))?;
+            builder = builder.proxy_url(url);
+        }
+        Ok(builder)
+    }
+
+    /// Builds and binds the endpoint.
+    pub async fn bind(&self) -> Result<Endpoint> {
+        Ok(self.builder()?.bind().await?)
+    }
+
+    /// Node id of the endpoint, known before binding only if the secret key
+    /// is set.
+    pub fn node_id(&self) -> Option<String> {
+        self.secret_key
+            .as_ref()
+            .map(|bytes| SecretKey::from_bytes(bytes).public().to_string())
+    }
+
+    fn relay_mode(&self) -> Result<RelayMode> {
+        Ok(match &self.relay {
+            RelayConfig::Default => RelayMode::Default,
+            RelayConfig::Disabled => RelayMode::Disabled,
+            RelayConfig::Custom(urls) => {
+                let urls = urls
+                    .iter()
+                    .map(|url| {
+                        url.parse::<RelayUrl>()
+                            .with_context(|| format!("Invalid relay URL {url}"))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                RelayMode::Custom(RelayMap::from_iter(urls))
+            }
+        })
+    }
+}
@@ -10,7 +10,16 @@
 //!
 //! All types are `serde`-serializable for convenient transport.
 
-use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    error, fmt,
+    ops::BitOr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use base64::{Engine, engine::general_purpose};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::IgnoredAny};
 
 /// Identity and display information for a participant in the handshake.
 ///
@@ -33,6 +42,123 @@ pub struct HandshakeProfile {
     pub avatar_b64: Option<String>,
 }
 
+/// Default limit of the decoded size of avatars in bytes.
+pub const DEFAULT_MAX_AVATAR_SIZE: usize = 256 * 1024;
+
+/// Limit of the size of a serialized handshake in bytes, checked before the
+/// handshake is read.
+pub const MAX_HANDSHAKE_LEN: usize = 16 * 1024 * 1024;
+
+/// Reason an avatar received in a handshake was rejected.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AvatarError {
+    /// The decoded avatar is larger than allowed.
+    TooLarge { size: usize, max_size: usize },
+    /// The avatar isn't valid Base64.
+    InvalidBase64,
+    /// The avatar isn't a PNG, JPEG, GIF or WebP image.
+    UnsupportedFormat,
+}
+
+impl fmt::Display for AvatarError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooLarge { size, max_size } => write!(
+                f,
+                "avatar of {size} bytes exceeds the limit of {max_size} bytes"
+            ),
+            Self::InvalidBase64 => f.write_str("avatar is not valid Base64"),
+            Self::UnsupportedFormat => {
+                f.write_str("avatar is not a PNG, JPEG, GIF or WebP image")
+            }
+        }
+    }
+}
+
+impl error::Error for AvatarError {}
+
+/// Decodes a Base64 avatar of at most `max_size` bytes.
+///
+/// Avatars may come as a `data:image/...;base64,` URL, as older versions and
+/// some front-ends send them, or as plain Base64. The size is checked on the
+/// encoded length first, so oversized avatars are rejected without decoding
+/// them.
+pub fn decode_avatar(
+    avatar_b64: &str,
+    max_size: usize,
+) -> Result<Vec<u8>, AvatarError> {
+    let data = match avatar_b64.split_once(";base64,") {
+        Some((prefix, data)) if prefix.starts_with("data:") => data,
+        _ => avatar_b64,
+    };
+    let data = data.trim();
+
+    let estimated_size = data.len() / 4 * 3;
+    if estimated_size > max_size + 2 {
+        return Err(AvatarError::TooLarge {
+            size: estimated_size,
+            max_size,
+        });
+    }
+
+    let avatar = general_purpose::STANDARD
+        .decode(data)
+        .map_err(|_| AvatarError::InvalidBase64)?;
+    if avatar.len() > max_size {
+        return Err(AvatarError::TooLarge {
+            size: avatar.len(),
+            max_size,
+        });
+    }
+    Ok(avatar)
+}
+
+/// Decodes a Base64 avatar of at most `max_size` bytes, see
+/// [`decode_avatar`], and checks that it's a PNG, JPEG, GIF or WebP image.
+///
+/// This is the check peers apply to the avatars of handshakes, front-ends
+/// use it for the avatars they send or store.
+pub fn decode_image_avatar(
+    avatar_b64: &str,
+    max_size: usize,
+) -> Result<Vec<u8>, AvatarError> {
+    let avatar = decode_avatar(avatar_b64, max_size)?;
+    let is_image = avatar.starts_with(b"\x89PNG\r\n\x1a\n")
+        || avatar.starts_with(&[0xFF, 0xD8, 0xFF])
+        || avatar.starts_with(b"GIF87a")
+        || avatar.starts_with(b"GIF89a")
+        || (avatar.starts_with(b"RIFF") && avatar.get(8..12) == Some(b"WEBP"));
+    if !is_image {
+        return Err(AvatarError::UnsupportedFormat);
+    }
+    Ok(avatar)
+}
+
+impl HandshakeProfile {
+    /// Checks that the avatar is a Base64 encoded image of at most
+    /// `max_size` bytes, see [`decode_image_avatar`]. Profiles without
+    /// avatar are valid.
+    pub fn validate_avatar(&self, max_size: usize) -> Result<(), AvatarError> {
+        match &self.avatar_b64 {
+            Some(avatar_b64) => {
+                decode_image_avatar(avatar_b64, max_size).map(|_| ())
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Removes the avatar if it fails [`Self::validate_avatar`], returning
+    /// the reason, so the handshake can go on without it.
+    pub fn strip_invalid_avatar(
+        &mut self,
+        max_size: usize,
+    ) -> Option<AvatarError> {
+        let error = self.validate_avatar(max_size).err()?;
+        self.avatar_b64 = None;
+        Some(error)
+    }
+}
+
 /// Minimal metadata describing a file offered by the sender.
 ///
 /// This is used during discovery/selection prior to any actual data transfer.
@@ -48,6 +174,260 @@ pub struct HandshakeFile {
     pub name: String,
     /// Total byte length of the file.
     pub len: u64,
+    /// What the bytes of the file are. Absent for plain files, so peers
+    /// predating items receive other items as files.
+    #[serde(default, skip_serializing_if = "ItemKind::is_file")]
+    pub kind: ItemKind,
+    /// Id of an earlier file of the list with the same content.
+    ///
+    /// With [`Capabilities::DEDUPLICATION`] the content is only sent for
+    /// the earlier file, and the receiver gets its chunks for this one too.
+    /// Otherwise both files are sent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub copy_of: Option<String>,
+    /// BLAKE3 hash of the content, hex encoded, checked by the receiver once
+    /// the file is received. Absent if the sender couldn't hash the file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    /// Modification time of the source in milliseconds since the UNIX
+    /// epoch, applied to the received file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<u64>,
+}
+
+impl HandshakeFile {
+    /// Returns [`Self::modified`] as a time.
+    pub fn modified_time(&self) -> Option<SystemTime> {
+        self.modified
+            .map(|millis| UNIX_EPOCH + Duration::from_millis(millis))
+    }
+
+    /// Returns false if the content hashed to `hash` isn't the announced
+    /// one. Files without a hash can't be verified and pass.
+    pub fn matches_hash(&self, hash: &str) -> bool {
+        self.hash
+            .as_ref()
+            .is_none_or(|announced| announced.eq_ignore_ascii_case(hash))
+    }
+}
+
+/// Returns `time` in milliseconds since the UNIX epoch, as announced in
+/// [`HandshakeFile::modified`], or `None` for times before the epoch.
+pub fn unix_millis(time: SystemTime) -> Option<u64> {
+    let since_epoch = time.duration_since(UNIX_EPOCH).ok()?;
+    u64::try_from(since_epoch.as_millis()).ok()
+}
+
+/// Kind of an item offered by the sender.
+///
+/// Items other than files are sent as files holding their serialized form,
+/// e.g. a [`crate::item::LinkItem`], which receivers aware of the kind turn
+/// back into the item. Kinds unknown to this version are received as plain
+/// files.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemKind {
+    /// A plain file.
+    #[default]
+    #[serde(other)]
+    File,
+    /// A saved link with its properties and preview.
+    Link,
+    /// Tags, scores and properties of resources, keyed by resource id, to
+    /// merge into the library of the receiver.
+    UserData,
+}
+
+impl ItemKind {
+    pub fn is_file(&self) -> bool {
+        *self == Self::File
+    }
+}
+
+/// Set of optional protocol features supported by a peer.
+///
+/// It is exchanged as a list of feature names. Names unknown to this version
+/// are ignored and a missing list means no features, so peers of different
+/// versions agree on the features both of them know, see
+/// [`NegotiatedConfig::negotiate`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Capabilities(u32);
+
+impl Capabilities {
+    /// File data may be compressed on the wire.
+    pub const COMPRESSION: Self = Self(1 << 0);
+    /// Files come with content hashes to verify them.
+    pub const HASHING: Self = Self(1 << 1);
+    /// Interrupted transfers may be resumed.
+    pub const RESUME: Self = Self(1 << 2);
+    /// File names may contain `/` separated directories.
+    pub const DIRECTORIES: Self = Self(1 << 3);
+    /// Text items may be sent besides files.
+    pub const TEXT_ITEMS: Self = Self(1 << 4);
+    /// Link items may be sent besides files, see [`ItemKind::Link`].
+    pub const LINK_ITEMS: Self = Self(1 << 5);
+    /// User data items may be sent besides files, see
+    /// [`ItemKind::UserData`].
+    pub const USER_DATA_ITEMS: Self = Self(1 << 6);
+    /// Files with the same content may be sent once, see
+    /// [`HandshakeFile::copy_of`].
+    pub const DEDUPLICATION: Self = Self(1 << 7);
+    /// Receivers acknowledge every file received in full, see
+    /// [`crate::ack`].
+    pub const ACKNOWLEDGEMENTS: Self = Self(1 << 8);
+
+    const NAMES: [(Self, &'static str); 9] = [
+        (Self::COMPRESSION, "compression"),
+        (Self::HASHING, "hashing"),
+        (Self::RESUME, "resume"),
+        (Self::DIRECTORIES, "directories"),
+        (Self::TEXT_ITEMS, "text_items"),
+        (Self::LINK_ITEMS, "link_items"),
+        (Self::USER_DATA_ITEMS, "user_data_items"),
+        (Self::DEDUPLICATION, "deduplication"),
+        (Self::ACKNOWLEDGEMENTS, "acknowledgements"),
+    ];
+
+    /// Returns the set without any feature.
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// Returns the features implemented by this version of the exchanges.
+    pub const fn supported() -> Self {
+        Self(
+            Self::DIRECTORIES.0
+                | Self::LINK_ITEMS.0
+                | Self::USER_DATA_ITEMS.0
+                | Self::DEDUPLICATION.0
+                | Self::ACKNOWLEDGEMENTS.0,
+        )
+    }
+
+    /// Returns true if every feature of `other` is in this set.
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns the features present in both sets.
+    pub const fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns the names of the features in this set.
+    pub fn names(self) -> impl Iterator<Item = &'static str> {
+        Self::NAMES
+            .into_iter()
+            .filter(move |(flag, _)| self.contains(*flag))
+            .map(|(_, name)| name)
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::NAMES
+            .into_iter()
+            .find(|(_, known)| *known == name)
+            .map(|(flag, _)| flag)
+    }
+}
+
+impl BitOr for Capabilities {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.names().collect::<Vec<_>>().join(", "))
+    }
+}
+
+impl Serialize for Capabilities {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.names())
+    }
+}
+
+impl<'de> Deserialize<'de> for Capabilities {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        let names = Vec::<String>::deserialize(deserializer)?;
+        Ok(names
+            .iter()
+            .filter_map(|name| Self::from_name(name))
+            .fold(Self::empty(), BitOr::bitor))
+    }
+}
+
+/// Algorithm used to hash file contents for verification.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    Blake3,
+    Sha256,
+}
+
+/// Compression applied to file data on the wire, with its level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "algorithm", rename_all = "lowercase")]
+pub enum Compression {
+    Zstd { level: i32 },
+    Deflate { level: u32 },
+}
+
+impl Compression {
+    /// Returns the same algorithm at the lower level of both, or `None` when
+    /// the algorithms differ.
+    fn agree(self, other: Self) -> Option<Self> {
+        match (self, other) {
+            (Self::Zstd { level }, Self::Zstd { level: other }) => {
+                Some(Self::Zstd {
+                    level: level.min(other),
+                })
+            }
+            (Self::Deflate { level }, Self::Deflate { level: other }) => {
+                Some(Self::Deflate {
+                    level: level.min(other),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Deserializes a list, dropping the items unknown to this version, e.g.
+/// algorithms added by newer peers.
+fn deserialize_known<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Item<T> {
+        Known(T),
+        Unknown(IgnoredAny),
+    }
+
+    Ok(Vec::<Item<T>>::deserialize(deserializer)?
+        .into_iter()
+        .filter_map(|item| match item {
+            Item::Known(value) => Some(value),
+            Item::Unknown(_) => None,
+        })
+        .collect())
 }
 
 /// Transport/configuration preferences advertised by a peer.
@@ -69,6 +449,50 @@ pub struct HandshakeConfig {
     /// During negotiation the effective number of streams is the minimum of
     /// both peers' preferences and never below 1.
     pub parallel_streams: u64,
+    /// Optional features this peer supports.
+    ///
+    /// Absent in handshakes of peers predating capabilities, which then
+    /// support none of them.
+    #[serde(default)]
+    pub capabilities: Capabilities,
+    /// Hash algorithms this peer can use, most preferred first.
+    ///
+    /// Only considered with [`Capabilities::HASHING`].
+    #[serde(default, deserialize_with = "deserialize_known")]
+    pub hash_algorithms: Vec<HashAlgorithm>,
+    /// Compressions this peer can use, most preferred first. The level is the
+    /// highest this peer accepts.
+    ///
+    /// Only considered with [`Capabilities::COMPRESSION`].
+    #[serde(default, deserialize_with = "deserialize_known")]
+    pub compressions: Vec<Compression>,
+}
+
+impl HandshakeConfig {
+    /// Returns the preferences of a peer of this version, advertising
+    /// [`Capabilities::HASHING`] and [`Capabilities::COMPRESSION`] when it
+    /// has algorithms for them.
+    pub fn new(
+        chunk_size: u64,
+        parallel_streams: u64,
+        hash_algorithms: Vec<HashAlgorithm>,
+        compressions: Vec<Compression>,
+    ) -> Self {
+        let mut capabilities = Capabilities::supported();
+        if !hash_algorithms.is_empty() {
+            capabilities = capabilities | Capabilities::HASHING;
+        }
+        if !compressions.is_empty() {
+            capabilities = capabilities | Capabilities::COMPRESSION;
+        }
+        Self {
+            chunk_size,
+            parallel_streams,
+            capabilities,
+            hash_algorithms,
+            compressions,
+        }
+    }
 }
 
 /// Sender's full handshake payload, including their profile, file list, and
@@ -85,6 +509,66 @@ pub struct SenderHandshake {
     pub config: HandshakeConfig,
 }
 
+/// A file announced as a copy of a file it can't be a copy of.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidCopyError {
+    /// Id of the copy.
+    pub id: String,
+    /// Id the copy refers to.
+    pub copy_of: String,
+}
+
+impl fmt::Display for InvalidCopyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "file {} is announced as a copy of {}, which is not an earlier \
+             file of the same length",
+            self.id, self.copy_of
+        )
+    }
+}
+
+impl error::Error for InvalidCopyError {}
+
+impl SenderHandshake {
+    /// Returns the ids of the copies by id of the file they are a copy of,
+    /// see [`HandshakeFile::copy_of`].
+    ///
+    /// Copies must refer to a file listed before them, with the same length,
+    /// which isn't a copy itself. Otherwise the receiver would wait for
+    /// content the sender never sends, so the list is rejected.
+    pub fn copies(
+        &self,
+    ) -> Result<HashMap<String, Vec<String>>, InvalidCopyError> {
+        let mut earlier: HashMap<&str, &HandshakeFile> = HashMap::new();
+        let mut copies: HashMap<String, Vec<String>> = HashMap::new();
+        for file in &self.files {
+            if let Some(original) = &file.copy_of {
+                let is_valid =
+                    earlier
+                        .get(original.as_str())
+                        .is_some_and(|original| {
+                            original.copy_of.is_none()
+                                && original.len == file.len
+                        });
+                if !is_valid {
+                    return Err(InvalidCopyError {
+                        id: file.id.clone(),
+                        copy_of: original.clone(),
+                    });
+                }
+                copies
+                    .entry(original.clone())
+                    .or_default()
+                    .push(file.id.clone());
+            }
+            earlier.insert(&file.id, file);
+        }
+        Ok(copies)
+    }
+}
+
 /// Receiver's handshake payload, including their profile and
 /// transport/configuration preferences.
 ///
@@ -107,6 +591,15 @@ pub struct NegotiatedConfig {
     pub chunk_size: u64,
     /// Effective number of parallel streams (never below 1).
     pub parallel_streams: u64,
+    /// Features supported by both peers, the only ones to be used.
+    #[serde(default)]
+    pub capabilities: Capabilities,
+    /// Hash algorithm to verify files with, if any.
+    #[serde(default)]
+    pub hash_algorithm: Option<HashAlgorithm>,
+    /// Compression of file data, if any.
+    #[serde(default)]
+    pub compression: Option<Compression>,
 }
 
 impl NegotiatedConfig {
@@ -118,26 +611,94 @@ impl NegotiatedConfig {
     ///   side, but clamp to a minimum of 1024 bytes.
     /// - Choose the smaller `parallel_streams` to avoid overloading either
     ///   side, but clamp to a minimum of 1.
+    /// - Keep the `capabilities` supported by both sides.
+    /// - Choose the first of the sender's `hash_algorithms` the receiver also
+    ///   supports, if both support hashing.
+    /// - Choose the first of the sender's `compressions` whose algorithm the
+    ///   receiver also supports, at the lower of both levels, if both support
+    ///   compression.
     ///
-    /// This function is deterministic and symmetric with respect to the chosen
-    /// min() operations.
+    /// Peers without an algorithm in common transfer without hashing or
+    /// compression instead of failing. This function is deterministic and
+    /// symmetric with respect to the chosen min() operations, while algorithms
+    /// follow the sender's preferences.
     ///
     /// Example:
     /// ```
-    /// use arkdropx_common::handshake::{HandshakeConfig, NegotiatedConfig};
+    /// use arkdropx_common::handshake::{
+    ///     Capabilities, Compression, HandshakeConfig, HashAlgorithm,
+    ///     NegotiatedConfig,
+    /// };
     ///
-    /// let sender = HandshakeConfig { chunk_size: 64 * 1024, parallel_streams: 4 };
-    /// let receiver = HandshakeConfig { chunk_size: 32 * 1024, parallel_streams: 8 };
+    /// let sender = HandshakeConfig {
+    ///     chunk_size: 64 * 1024,
+    ///     parallel_streams: 4,
+    ///     capabilities: Capabilities::DIRECTORIES | Capabilities::COMPRESSION,
+    ///     hash_algorithms: vec![HashAlgorithm::Blake3],
+    ///     compressions: vec![
+    ///         Compression::Zstd { level: 9 },
+    ///         Compression::Deflate { level: 6 },
+    ///     ],
+    /// };
+    /// let receiver = HandshakeConfig {
+    ///     chunk_size: 32 * 1024,
+    ///     parallel_streams: 8,
+    ///     capabilities: Capabilities::DIRECTORIES | Capabilities::COMPRESSION,
+    ///     hash_algorithms: vec![HashAlgorithm::Blake3],
+    ///     compressions: vec![Compression::Zstd { level: 3 }],
+    /// };
     ///
     /// let negotiated = NegotiatedConfig::negotiate(&sender, &receiver);
     ///
     /// assert_eq!(negotiated.chunk_size, 32 * 1024);
     /// assert_eq!(negotiated.parallel_streams, 4);
+    /// assert_eq!(
+    ///     negotiated.capabilities,
+    ///     Capabilities::DIRECTORIES | Capabilities::COMPRESSION
+    /// );
+    /// // Hashing isn't supported by both, so there is no hash algorithm.
+    /// assert_eq!(negotiated.hash_algorithm, None);
+    /// assert_eq!(
+    ///     negotiated.compression,
+    ///     Some(Compression::Zstd { level: 3 })
+    /// );
     /// ```
     pub fn negotiate(
         sender_config: &HandshakeConfig,
         receiver_config: &HandshakeConfig,
     ) -> Self {
+        let capabilities = sender_config
+            .capabilities
+            .intersection(receiver_config.capabilities);
+
+        let hash_algorithm = if capabilities.contains(Capabilities::HASHING) {
+            sender_config
+                .hash_algorithms
+                .iter()
+                .find(|algorithm| {
+                    receiver_config
+                        .hash_algorithms
+                        .contains(algorithm)
+                })
+                .copied()
+        } else {
+            None
+        };
+
+        let compression = if capabilities.contains(Capabilities::COMPRESSION) {
+            sender_config
+                .compressions
+                .iter()
+                .find_map(|compression| {
+                    receiver_config
+                        .compressions
+                        .iter()
+                        .find_map(|other| compression.agree(*other))
+                })
+        } else {
+            None
+        };
+
         Self {
             chunk_size: sender_config
                 .chunk_size
@@ -147,6 +708,166 @@ impl NegotiatedConfig {
                 .parallel_streams
                 .min(receiver_config.parallel_streams)
                 .max(1),
+            capabilities,
+            hash_algorithm,
+            compression,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JPEG: &[u8] = &[0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10, b'J', b'F'];
+
+    fn profile(avatar_b64: &str) -> HandshakeProfile {
+        HandshakeProfile {
+            id: "id".to_string(),
+            name: "name".to_string(),
+            avatar_b64: Some(avatar_b64.to_string()),
+        }
+    }
+
+    #[test]
+    fn accepts_plain_and_data_url_avatars() {
+        let encoded = general_purpose::STANDARD.encode(JPEG);
+        assert_eq!(profile(&encoded).validate_avatar(1024), Ok(()));
+
+        // As sent by older versions and the TUI
+        let data_url = format!("data:image/jpeg;base64,{encoded}");
+        assert_eq!(profile(&data_url).validate_avatar(1024), Ok(()));
+        assert_eq!(decode_avatar(&data_url, 1024).unwrap(), JPEG);
+    }
+
+    fn handshake(files: &[(&str, u64, Option<&str>)]) -> SenderHandshake {
+        SenderHandshake {
+            profile: profile("avatar"),
+            files: files
+                .iter()
+                .map(|(id, len, copy_of)| HandshakeFile {
+                    id: id.to_string(),
+                    name: format!("{id}.bin"),
+                    len: *len,
+                    kind: ItemKind::File,
+                    copy_of: copy_of.map(str::to_string),
+                    hash: None,
+                    modified: None,
+                })
+                .collect(),
+            config: HandshakeConfig {
+                chunk_size: 1024,
+                parallel_streams: 1,
+                capabilities: Capabilities::supported(),
+                hash_algorithms: Vec::new(),
+                compressions: Vec::new(),
+            },
         }
     }
+
+    #[test]
+    fn groups_copies_by_original() {
+        let copies = handshake(&[
+            ("a", 3, None),
+            ("b", 3, Some("a")),
+            ("c", 5, None),
+            ("d", 3, Some("a")),
+        ])
+        .copies()
+        .unwrap();
+        assert_eq!(copies.len(), 1);
+        assert_eq!(copies["a"], ["b", "d"]);
+    }
+
+    #[test]
+    fn agrees_on_common_hash_algorithm() {
+        let sender = HandshakeConfig::new(
+            1024,
+            1,
+            vec![HashAlgorithm::Sha256, HashAlgorithm::Blake3],
+            Vec::new(),
+        );
+        let receiver = HandshakeConfig::new(
+            1024,
+            1,
+            vec![HashAlgorithm::Blake3],
+            Vec::new(),
+        );
+        let negotiated = NegotiatedConfig::negotiate(&sender, &receiver);
+        let capabilities = negotiated.capabilities;
+        assert!(capabilities.contains(Capabilities::HASHING));
+        assert!(!capabilities.contains(Capabilities::COMPRESSION));
+        assert_eq!(negotiated.hash_algorithm, Some(HashAlgorithm::Blake3));
+        assert_eq!(negotiated.compression, None);
+
+        // Without algorithms, hashing isn't advertised at all
+        let receiver = HandshakeConfig::new(1024, 1, Vec::new(), Vec::new());
+        let capabilities = receiver.capabilities;
+        assert!(!capabilities.contains(Capabilities::HASHING));
+        let negotiated = NegotiatedConfig::negotiate(&sender, &receiver);
+        assert_eq!(negotiated.hash_algorithm, None);
+    }
+
+    #[test]
+    fn rejects_invalid_copies() {
+        let invalid = [
+            // Unknown file
+            handshake(&[("a", 3, None), ("b", 3, Some("x"))]),
+            // Later file
+            handshake(&[("b", 3, Some("a")), ("a", 3, None)]),
+            // Other length
+            handshake(&[("a", 3, None), ("b", 4, Some("a"))]),
+            // Copy of a copy
+            handshake(&[
+                ("a", 3, None),
+                ("b", 3, Some("a")),
+                ("c", 3, Some("b")),
+            ]),
+            // Copy of itself
+            handshake(&[("a", 3, Some("a"))]),
+        ];
+        for handshake in invalid {
+            assert!(handshake.copies().is_err());
+        }
+    }
+
+    #[test]
+    fn parses_files_without_hash_or_mtime() {
+        // As sent by older versions
+        let file: HandshakeFile =
+            serde_json::from_str(r#"{"id":"a","name":"a.txt","len":3}"#)
+                .unwrap();
+        assert_eq!(file.modified_time(), None);
+        assert!(file.matches_hash("anything"));
+
+        let modified = UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+        let file = HandshakeFile {
+            hash: Some("AB12".to_string()),
+            modified: unix_millis(modified),
+            ..file
+        };
+        let file: HandshakeFile =
+            serde_json::from_str(&serde_json::to_string(&file).unwrap())
+                .unwrap();
+        assert_eq!(file.modified_time(), Some(modified));
+        assert!(file.matches_hash("ab12"));
+        assert!(!file.matches_hash("cd34"));
+    }
+
+    #[test]
+    fn rejects_invalid_avatars() {
+        assert_eq!(
+            profile("not base64!").validate_avatar(1024),
+            Err(AvatarError::InvalidBase64)
+        );
+        assert_eq!(
+            profile(&general_purpose::STANDARD.encode(b"plain text"))
+                .validate_avatar(1024),
+            Err(AvatarError::UnsupportedFormat)
+        );
+        assert!(matches!(
+            profile(&general_purpose::STANDARD.encode(JPEG)).validate_avatar(4),
+            Err(AvatarError::TooLarge { .. })
+        ));
+    }
 }
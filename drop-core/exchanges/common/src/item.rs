@@ -0,0 +1,92 @@
+//! Serialized forms of the items sent besides files.
+//!
+//! An item is offered like a file whose bytes are its JSON form, with its
+//! [`ItemKind`](crate::handshake::ItemKind) in the handshake. Receivers
+//! aware of the kind materialize it, e.g. save a link into their own
+//! library, others keep it as a file.
+
+use anyhow::{Context, Result};
+use base64::{Engine, engine::general_purpose};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A saved link, sent as a
+/// [`ItemKind::Link`](crate::handshake::ItemKind::Link) item.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkItem {
+    pub url: String,
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub desc: Option<String>,
+    /// Preview image of the page, Base64 encoded on the wire.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_b64",
+        deserialize_with = "deserialize_b64"
+    )]
+    pub preview: Option<Vec<u8>>,
+}
+
+impl LinkItem {
+    /// Returns the bytes sent for the item.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        serde_json::to_vec(self).context("Failed to serialize link item")
+    }
+
+    /// Parses the bytes of a received item.
+    ///
+    /// Errors if they aren't a link item.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        serde_json::from_slice(bytes).context("Invalid link item")
+    }
+}
+
+fn serialize_b64<S: Serializer>(
+    bytes: &Option<Vec<u8>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match bytes {
+        Some(bytes) => {
+            serializer.serialize_str(&general_purpose::STANDARD.encode(bytes))
+        }
+        None => serializer.serialize_none(),
+    }
+}
+
+fn deserialize_b64<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Vec<u8>>, D::Error> {
+    let Some(encoded) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    general_purpose::STANDARD
+        .decode(encoded)
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_link_with_preview() {
+        let link = LinkItem {
+            url: "https://example.com/".to_string(),
+            title: "Example".to_string(),
+            desc: None,
+            preview: Some(vec![0x89, b'P', b'N', b'G']),
+        };
+
+        let bytes = link.to_bytes().unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(json["preview"], "iVBORw==");
+        assert!(json.get("desc").is_none());
+        assert_eq!(LinkItem::from_bytes(&bytes).unwrap(), link);
+    }
+
+    #[test]
+    fn rejects_other_bytes() {
+        assert!(LinkItem::from_bytes(b"not a link").is_err());
+    }
+}
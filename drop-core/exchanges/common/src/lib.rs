@@ -2,13 +2,45 @@
 //! projections.
 //!
 //! This crate provides:
+//! - Acknowledgements of the files received in full
 //! - Serializable types to exchange profiles, file lists, and transport
 //!   preferences
 //! - A simple, deterministic negotiation algorithm to derive runtime settings
 //! - A compact file projection type for in-memory data handling
+//! - Networking options of the endpoint bound by a transfer
+//! - The reasons a session ends with, shared with the peer on close
+//! - A retry policy for transient network failures
+//! - Throttling of progress events for slow subscribers
+//! - Bandwidth limiting of file streams
+//! - The `drop://` URI shared between peers, e.g. as a QR code
+//! - Serialized forms of items sent besides files, such as links
+
+/// Per-file acknowledgements sent by receivers.
+pub mod ack;
+
+/// Networking options of the transfer endpoint.
+pub mod endpoint;
 
 /// Handshake data models and negotiation logic.
 pub mod handshake;
 
+/// Serialized forms of typed items, e.g. links.
+pub mod item;
+
 /// Types for working with in-memory file projections.
 pub mod projection;
+
+/// Throttling and coalescing of per-chunk progress events.
+pub mod progress;
+
+/// Rate limiting of the bytes sent or received by a session.
+pub mod rate;
+
+/// Retries of transient connection and stream failures.
+pub mod retry;
+
+/// Final status of a session, sent when closing the connection.
+pub mod status;
+
+/// Serialization and parsing of `drop://` URIs.
+pub mod uri;
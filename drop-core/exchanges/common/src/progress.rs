@@ -0,0 +1,125 @@
+//! Throttling of progress events.
+//!
+//! Handlers report progress once per chunk, more often than slow subscribers
+//! like terminal UIs or FFI bridges can redraw. A subscriber asking for a
+//! minimum interval gets at most one event per file within it.
+//! [`ProgressThrottle`] drops the intermediate events of cumulative counters,
+//! while [`ChunkCoalescer`] merges the chunks received in the meantime so no
+//! bytes are lost. The first and the last event of a file are never held back.
+
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Picks the cumulative progress events of each file that are delivered.
+pub struct ProgressThrottle {
+    interval: Duration,
+    delivered: Mutex<HashMap<String, Instant>>,
+}
+
+impl ProgressThrottle {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            delivered: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether an event of the file is delivered: always the first and the
+    /// last one, others once `interval` passed since the previous delivery.
+    pub fn is_due(&self, id: &str, is_last: bool) -> bool {
+        let now = Instant::now();
+        let mut delivered = self.delivered.lock().unwrap();
+        if is_last {
+            delivered.remove(id);
+            return true;
+        }
+        let is_due = delivered
+            .get(id)
+            .is_none_or(|at| now.duration_since(*at) >= self.interval);
+        if is_due {
+            delivered.insert(id.to_string(), now);
+        }
+        is_due
+    }
+}
+
+/// Merges the chunks of each file received within an interval.
+pub struct ChunkCoalescer {
+    interval: Duration,
+    files: Mutex<HashMap<String, PendingChunks>>,
+}
+
+#[derive(Default)]
+struct PendingChunks {
+    len: Option<u64>,
+    received: u64,
+    data: Vec<u8>,
+    delivered: Option<Instant>,
+}
+
+impl ChunkCoalescer {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            files: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Registers the size of a file, so its last chunk is delivered right
+    /// away instead of when the session ends.
+    pub fn expect(&self, id: String, len: u64) {
+        self.files
+            .lock()
+            .unwrap()
+            .entry(id)
+            .or_default()
+            .len = Some(len);
+    }
+
+    /// Adds a chunk of the file. Returns the data to deliver now, if any.
+    pub fn push(&self, id: &str, data: Vec<u8>) -> Option<Vec<u8>> {
+        let now = Instant::now();
+        let mut files = self.files.lock().unwrap();
+        let file = files.entry(id.to_string()).or_default();
+        file.received += data.len() as u64;
+        if file.data.is_empty() {
+            file.data = data;
+        } else {
+            file.data.extend_from_slice(&data);
+        }
+
+        let is_complete = file.len.is_some_and(|len| file.received >= len);
+        let is_due = is_complete
+            || file
+                .delivered
+                .is_none_or(|at| now.duration_since(at) >= self.interval);
+        if !is_due {
+            return None;
+        }
+        file.delivered = Some(now);
+        Some(std::mem::take(&mut file.data))
+    }
+
+    /// Drops the data of the file that wasn't delivered yet, e.g. because
+    /// it failed verification.
+    pub fn discard(&self, id: &str) {
+        if let Some(file) = self.files.lock().unwrap().get_mut(id) {
+            file.data.clear();
+        }
+    }
+
+    /// Takes the data of all files that wasn't delivered yet, e.g. because
+    /// the session ended.
+    pub fn drain(&self) -> Vec<(String, Vec<u8>)> {
+        self.files
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .filter(|(_, file)| !file.data.is_empty())
+            .map(|(id, file)| (id.clone(), std::mem::take(&mut file.data)))
+            .collect()
+    }
+}
@@ -5,6 +5,14 @@
 
 use serde::{Deserialize, Serialize};
 
+/// Application error code with which a receiver stops a stream after a chunk
+/// failed verification, see [`FileProjection::verify`].
+pub const CHECKSUM_MISMATCH_CODE: u32 = 0xC4C5;
+
+/// Application error code with which a sender resets the stream of a file
+/// that changed on disk while it was being sent.
+pub const SOURCE_CHANGED_CODE: u32 = 0x5C4D;
+
 /// In-memory representation of a file's contents identified by a logical ID.
 ///
 /// This is commonly paired with [`crate::handshake::HandshakeFile`], where
@@ -17,4 +25,26 @@ pub struct FileProjection {
     pub id: String,
     /// Raw file bytes.
     pub data: Vec<u8>,
+    /// CRC-32 of `data`, absent in chunks of peers predating checksums.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<u32>,
+}
+
+impl FileProjection {
+    /// Creates a projection of the data with its checksum.
+    pub fn new(id: String, data: Vec<u8>) -> Self {
+        let checksum = crc32fast::hash(&data);
+        Self {
+            id,
+            data,
+            checksum: Some(checksum),
+        }
+    }
+
+    /// Returns false if the data doesn't match the checksum, i.e. the chunk
+    /// got corrupted. Chunks without a checksum can't be verified and pass.
+    pub fn verify(&self) -> bool {
+        self.checksum
+            .is_none_or(|checksum| checksum == crc32fast::hash(&self.data))
+    }
 }
@@ -0,0 +1,55 @@
+//! Bandwidth limiting of file streams.
+//!
+//! A session shares one [`RateLimiter`] between its parallel streams, so the
+//! limit applies to the whole transfer rather than to each file. Streams ask
+//! for the bytes of a chunk before writing or after reading it, and wait
+//! until the limiter has room for them.
+
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Time worth of bytes a limiter lets through right away after being idle,
+/// so short pauses between files don't lower the average rate.
+const BURST: Duration = Duration::from_millis(250);
+
+/// Paces the bytes of a session to a maximum rate.
+pub struct RateLimiter {
+    bytes_per_second: u64,
+    /// Time at which the bytes acquired so far are paid off.
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    /// Creates a limiter letting through at most `bytes_per_second`, at
+    /// least one byte per second.
+    pub fn new(bytes_per_second: u64) -> Self {
+        Self {
+            bytes_per_second: bytes_per_second.max(1),
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Creates a limiter if a rate is given, `None` means unlimited.
+    pub fn from_rate(bytes_per_second: Option<u64>) -> Option<Self> {
+        bytes_per_second.map(Self::new)
+    }
+
+    /// Waits until `bytes` fit within the rate.
+    pub async fn acquire(&self, bytes: u64) {
+        let wait = {
+            let mut next = self.next.lock().unwrap();
+            let now = Instant::now();
+            let start = (*next).max(now.checked_sub(BURST).unwrap_or(now));
+            *next = start
+                + Duration::from_secs_f64(
+                    bytes as f64 / self.bytes_per_second as f64,
+                );
+            next.saturating_duration_since(now)
+        };
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
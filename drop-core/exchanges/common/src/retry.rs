@@ -0,0 +1,101 @@
+//! Retries of transient network failures.
+//!
+//! Connecting to a peer and opening a stream can fail for reasons which go
+//! away by themselves, like a path that dropped for a moment or a peer that
+//! isn't reachable yet. [`RetryConfig::run`] repeats such operations with an
+//! exponential backoff, while errors retrying can't fix, e.g. a peer that
+//! closed the connection on purpose, fail right away.
+
+use std::{future::Future, time::Duration};
+
+use anyhow::Result;
+use iroh::endpoint::ConnectionError;
+
+use crate::status::{EndReason, connection_error};
+
+/// How often and how fast failed operations are retried before the session
+/// fails.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RetryConfig {
+    /// Attempts in total, including the first one. `1` disables retries.
+    pub max_attempts: u32,
+    /// Wait before the first retry, doubled before each further one.
+    pub initial_backoff: Duration,
+    /// Upper bound of the wait between two attempts.
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryConfig {
+    /// 3 attempts, waiting 250 ms then 500 ms, at most 2 s.
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(2),
+        }
+    }
+}
+
+impl RetryConfig {
+    /// A single attempt, failures aren't retried.
+    pub fn disabled() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Wait before the given retry, counting from 1.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+
+    /// Runs `operation` until it succeeds, fails with an error which isn't
+    /// transient, or used up all attempts. `on_retry` is called with the
+    /// number of the failed attempt and its error before each retry.
+    pub async fn run<T, F, Fut>(
+        &self,
+        mut operation: F,
+        mut on_retry: impl FnMut(u32, &anyhow::Error),
+    ) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match operation().await {
+                Ok(value) => return Ok(value),
+                Err(err)
+                    if attempt < self.max_attempts && is_transient(&err) =>
+                {
+                    on_retry(attempt, &err);
+                    tokio::time::sleep(self.backoff(attempt)).await;
+                    attempt += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+/// Returns false for errors retrying can't fix: the session ended, or either
+/// peer closed the connection on purpose, e.g. because of a wrong
+/// confirmation code.
+pub fn is_transient(err: &anyhow::Error) -> bool {
+    if err.downcast_ref::<EndReason>().is_some() {
+        return false;
+    }
+    !matches!(
+        connection_error(err),
+        Some(
+            ConnectionError::ApplicationClosed(_)
+                | ConnectionError::ConnectionClosed(_)
+                | ConnectionError::LocallyClosed
+                | ConnectionError::VersionMismatch
+        )
+    )
+}
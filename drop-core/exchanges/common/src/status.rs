@@ -0,0 +1,116 @@
+//! Final status of a session.
+//!
+//! Whichever peer ends a session closes the connection with the application
+//! code and reason of an [`EndReason`]. The QUIC close frame is the last thing
+//! the other peer receives, so it learns why the transfer stopped instead of
+//! only seeing a closed connection. The codes of the first versions, 200
+//! `finished` and 0 `cancelled`, keep their meaning.
+
+use std::{error, fmt};
+
+use iroh::endpoint::{ApplicationClose, Connection, ConnectionError, VarInt};
+
+/// Why a session ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EndReason {
+    /// All files were transferred.
+    Completed,
+    /// A user cancelled the transfer.
+    Cancelled,
+    /// The peer stopped responding.
+    Timeout,
+    /// Received data didn't match its checksum.
+    VerificationFailed,
+    /// The receiver ran out of disk space.
+    DiskFull,
+    /// The receiver refused a session larger than it accepts.
+    TooLarge,
+    /// Any other error.
+    Failed,
+}
+
+impl EndReason {
+    /// Application code of the close frame.
+    pub fn code(&self) -> u32 {
+        match self {
+            Self::Completed => 200,
+            Self::Cancelled => 0,
+            Self::Timeout => 408,
+            Self::VerificationFailed => 422,
+            Self::DiskFull => 507,
+            Self::TooLarge => 413,
+            Self::Failed => 500,
+        }
+    }
+
+    /// Reason phrase of the close frame.
+    pub fn phrase(&self) -> &'static str {
+        match self {
+            Self::Completed => "finished",
+            Self::Cancelled => "cancelled",
+            Self::Timeout => "timeout",
+            Self::VerificationFailed => "verification failed",
+            Self::DiskFull => "disk full",
+            Self::TooLarge => "too large",
+            Self::Failed => "failed",
+        }
+    }
+
+    /// Reason of a close frame with the given code. Unknown codes, e.g. of
+    /// newer peers, are [`EndReason::Failed`].
+    pub fn from_code(code: u32) -> Self {
+        match code {
+            200 => Self::Completed,
+            0 => Self::Cancelled,
+            408 => Self::Timeout,
+            422 => Self::VerificationFailed,
+            507 => Self::DiskFull,
+            413 => Self::TooLarge,
+            _ => Self::Failed,
+        }
+    }
+
+    /// Closes the connection with the code and phrase of this reason.
+    pub fn close(&self, connection: &Connection) {
+        connection
+            .close(VarInt::from_u32(self.code()), self.phrase().as_bytes());
+    }
+
+    /// Reason of a session which stopped with `err`.
+    ///
+    /// That is the reason the error was created with, the one sent by the
+    /// peer if it closed the connection, [`EndReason::Timeout`] if the peer
+    /// went silent, and [`EndReason::Failed`] otherwise.
+    pub fn of(err: &anyhow::Error) -> Self {
+        if let Some(reason) = err.downcast_ref::<EndReason>() {
+            return *reason;
+        }
+        match connection_error(err) {
+            Some(ConnectionError::ApplicationClosed(ApplicationClose {
+                error_code,
+                ..
+            })) => Self::from_code(
+                u32::try_from(error_code.into_inner()).unwrap_or(u32::MAX),
+            ),
+            Some(ConnectionError::TimedOut) => Self::Timeout,
+            _ => Self::Failed,
+        }
+    }
+}
+
+impl fmt::Display for EndReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.phrase())
+    }
+}
+
+impl error::Error for EndReason {}
+
+/// The connection error behind a failed stream or connection operation, if
+/// any error of the chain is one.
+pub(crate) fn connection_error(
+    err: &anyhow::Error,
+) -> Option<&ConnectionError> {
+    err.chain()
+        .find_map(|e| e.downcast_ref::<ConnectionError>())
+}
@@ -0,0 +1,303 @@
+//! The `drop://` URI that front-ends share out of band, usually as a QR code.
+//!
+//! The URI names what the scanning peer is expected to do, the ticket to dial
+//! and the confirmation code, and may carry extra metadata such as a display
+//! name:
+//!
+//! ```text
+//! drop://receive?ticket=<ticket>&confirmation=<code>[&<key>=<value>...]
+//! ```
+//!
+//! Known metadata keys are [`NAME_KEY`] and [`SIZE_KEY`], with typed
+//! accessors on [`DropUri`]. They are hints to show before connecting, the
+//! handshake remains the source of truth.
+//!
+//! Its string form is also the QR payload, so every front-end encodes and
+//! scans the same format.
+
+use std::{fmt, str::FromStr};
+
+use anyhow::{Context, Result, anyhow, bail};
+
+/// URI scheme of drop links.
+pub const SCHEME: &str = "drop";
+
+/// Metadata key of the display name of the waiting peer.
+pub const NAME_KEY: &str = "name";
+
+/// Metadata key of the total size in bytes of the files offered by a
+/// waiting sender.
+pub const SIZE_KEY: &str = "size";
+
+/// What the peer opening the URI is expected to do.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DropAction {
+    /// Receive files from a waiting sender.
+    Receive,
+    /// Send files to a waiting receiver.
+    Send,
+}
+
+impl DropAction {
+    fn as_str(&self) -> &'static str {
+        match self {
+            DropAction::Receive => "receive",
+            DropAction::Send => "send",
+        }
+    }
+}
+
+/// Parsed or to-be-serialized `drop://` URI.
+///
+/// Example:
+/// ```
+/// use arkdropx_common::uri::{DropAction, DropUri};
+///
+/// let uri = DropUri::receive("nodeabc", 42).with_metadata("name", "Ann B");
+/// let payload = uri.to_string();
+/// assert_eq!(
+///     payload,
+///     "drop://receive?ticket=nodeabc&confirmation=42&name=Ann%20B"
+/// );
+///
+/// let parsed: DropUri = payload.parse().unwrap();
+/// assert_eq!(parsed.action, DropAction::Receive);
+/// assert_eq!(parsed.metadata("name"), Some("Ann B"));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DropUri {
+    /// What the peer opening the URI is expected to do.
+    pub action: DropAction,
+    /// Ticket of the waiting peer, as returned by its bubble.
+    pub ticket: String,
+    /// Confirmation code of the waiting peer.
+    pub confirmation: u8,
+    /// Extra key/value pairs, in order of appearance. Unknown keys are kept
+    /// so newer front-ends can add metadata without breaking older ones.
+    pub extra: Vec<(String, String)>,
+}
+
+impl DropUri {
+    /// URI asking the scanning peer to receive files from a sender.
+    pub fn receive(ticket: impl Into<String>, confirmation: u8) -> Self {
+        Self::new(DropAction::Receive, ticket, confirmation)
+    }
+
+    /// URI asking the scanning peer to send files to a waiting receiver.
+    pub fn send(ticket: impl Into<String>, confirmation: u8) -> Self {
+        Self::new(DropAction::Send, ticket, confirmation)
+    }
+
+    fn new(
+        action: DropAction,
+        ticket: impl Into<String>,
+        confirmation: u8,
+    ) -> Self {
+        Self {
+            action,
+            ticket: ticket.into(),
+            confirmation,
+            extra: Vec::new(),
+        }
+    }
+
+    /// Adds a metadata pair, replacing an earlier value of the same key.
+    pub fn with_metadata(
+        mut self,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        let key = key.into();
+        self.extra.retain(|(k, _)| *k != key);
+        self.extra.push((key, value.into()));
+        self
+    }
+
+    /// Value of a metadata key, if present.
+    pub fn metadata(&self, key: &str) -> Option<&str> {
+        self.extra
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Adds the display name of the waiting peer.
+    pub fn with_name(self, name: impl Into<String>) -> Self {
+        self.with_metadata(NAME_KEY, name)
+    }
+
+    /// Display name of the waiting peer, if given.
+    pub fn name(&self) -> Option<&str> {
+        self.metadata(NAME_KEY)
+    }
+
+    /// Adds the total size in bytes of the files offered by the sender.
+    pub fn with_expected_size(self, size: u64) -> Self {
+        self.with_metadata(SIZE_KEY, size.to_string())
+    }
+
+    /// Total size in bytes of the offered files, if given and valid.
+    pub fn expected_size(&self) -> Option<u64> {
+        self.metadata(SIZE_KEY)?.parse().ok()
+    }
+}
+
+impl fmt::Display for DropUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{SCHEME}://{}?ticket={}&confirmation={}",
+            self.action.as_str(),
+            encode(&self.ticket),
+            self.confirmation
+        )?;
+        for (key, value) in &self.extra {
+            write!(f, "&{}={}", encode(key), encode(value))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for DropUri {
+    type Err = anyhow::Error;
+
+    fn from_str(uri: &str) -> Result<Self> {
+        let rest = uri
+            .trim()
+            .strip_prefix(SCHEME)
+            .and_then(|rest| rest.strip_prefix("://"))
+            .ok_or_else(|| anyhow!("Not a {SCHEME}:// URI: {uri}"))?;
+        let (action, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let action = match action.trim_end_matches('/') {
+            "receive" => DropAction::Receive,
+            "send" => DropAction::Send,
+            other => bail!("Unknown action {other:?} in {uri}"),
+        };
+
+        let mut ticket = None;
+        let mut confirmation = None;
+        let mut extra = Vec::new();
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let key = decode(key)?;
+            let value = decode(value)?;
+            match key.as_str() {
+                "ticket" => ticket = Some(value),
+                "confirmation" => {
+                    confirmation =
+                        Some(value.parse::<u8>().with_context(|| {
+                            format!("Invalid confirmation code {value:?}")
+                        })?)
+                }
+                _ => extra.push((key, value)),
+            }
+        }
+
+        Ok(Self {
+            action,
+            ticket: ticket
+                .filter(|ticket| !ticket.is_empty())
+                .ok_or_else(|| anyhow!("Missing ticket in {uri}"))?,
+            confirmation: confirmation
+                .ok_or_else(|| anyhow!("Missing confirmation in {uri}"))?,
+            extra,
+        })
+    }
+}
+
+/// Percent-encodes everything but RFC 3986 unreserved characters.
+fn encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z'
+            | b'a'..=b'z'
+            | b'0'..=b'9'
+            | b'-'
+            | b'.'
+            | b'_'
+            | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+fn decode(value: &str) -> Result<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = value
+                    .get(i + 1..i + 3)
+                    .ok_or_else(|| anyhow!("Truncated escape in {value:?}"))?;
+                decoded.push(u8::from_str_radix(hex, 16).with_context(
+                    || format!("Invalid escape %{hex} in {value:?}"),
+                )?);
+                i += 3;
+            }
+            b'+' => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            byte => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded)
+        .with_context(|| format!("Invalid UTF-8 in {value:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_actions() {
+        for uri in [DropUri::receive("nodeabc", 0), DropUri::send("node", 255)]
+        {
+            assert_eq!(uri.to_string().parse::<DropUri>().unwrap(), uri);
+        }
+    }
+
+    #[test]
+    fn round_trips_hints_and_unknown_keys() {
+        let uri = DropUri::receive("node/abc+=", 7)
+            .with_name("Ann & Bob")
+            .with_expected_size(1 << 40)
+            .with_metadata("future", "100% ✓");
+        let parsed: DropUri = uri.to_string().parse().unwrap();
+        assert_eq!(parsed, uri);
+        assert_eq!(parsed.ticket, "node/abc+=");
+        assert_eq!(parsed.name(), Some("Ann & Bob"));
+        assert_eq!(parsed.expected_size(), Some(1 << 40));
+        assert_eq!(parsed.metadata("future"), Some("100% ✓"));
+    }
+
+    #[test]
+    fn ignores_invalid_hints() {
+        let parsed: DropUri = "drop://send?ticket=t&confirmation=1&size=big"
+            .parse()
+            .unwrap();
+        assert_eq!(parsed.expected_size(), None);
+        assert_eq!(parsed.name(), None);
+    }
+
+    #[test]
+    fn rejects_invalid_uris() {
+        for uri in [
+            "http://receive?ticket=t&confirmation=1",
+            "drop://fetch?ticket=t&confirmation=1",
+            "drop://receive?confirmation=1",
+            "drop://receive?ticket=t",
+            "drop://receive?ticket=t&confirmation=256",
+            "drop://receive?ticket=%4&confirmation=1",
+        ] {
+            assert!(uri.parse::<DropUri>().is_err(), "{uri}");
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! Loopback transfer shared by the throughput benchmark and example.
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use anyhow::{Result, anyhow};
+use arkdropx_receiver::{
+    EndpointConfig, ReceiveFilesEvent, ReceiveFilesRequest, ReceiverConfig,
+    ReceiverProfile, RelayConfig, receive_files,
+};
+use arkdropx_sender::{
+    ItemKind, SendFilesRequest, SenderConfig, SenderFile, SenderFileData,
+    SenderProfile, send_files,
+};
+use futures::StreamExt;
+
+/// Shape of a benchmarked transfer.
+#[derive(Clone, Copy, Debug)]
+pub struct Transfer {
+    pub files: usize,
+    pub file_size: usize,
+    pub chunk_size: u64,
+    pub parallel_streams: u64,
+}
+
+impl Transfer {
+    pub fn total_bytes(&self) -> u64 {
+        (self.files * self.file_size) as u64
+    }
+}
+
+/// In-memory file contents served chunk by chunk.
+struct MemoryData {
+    data: Arc<Vec<u8>>,
+    offset: AtomicUsize,
+}
+
+impl SenderFileData for MemoryData {
+    fn len(&self) -> u64 {
+        self.data.len() as u64
+    }
+
+    fn read(&self) -> Option<u8> {
+        let offset = self.offset.fetch_add(1, Ordering::Relaxed);
+        self.data.get(offset).copied()
+    }
+
+    fn read_chunk(&self, size: u64) -> Vec<u8> {
+        let start = self
+            .offset
+            .fetch_add(size as usize, Ordering::Relaxed)
+            .min(self.data.len());
+        let end = (start + size as usize).min(self.data.len());
+        self.data[start..end].to_vec()
+    }
+}
+
+/// Endpoint talking over the local interfaces only.
+fn local_endpoint() -> EndpointConfig {
+    EndpointConfig {
+        relay: RelayConfig::Disabled,
+        disable_discovery: true,
+        ..Default::default()
+    }
+}
+
+/// Sends the files from one endpoint to another in this process and returns
+/// the number of bytes received.
+pub async fn run(transfer: Transfer) -> Result<u64> {
+    let data = Arc::new(vec![0xA5u8; transfer.file_size]);
+    let files = (0..transfer.files)
+        .map(|i| SenderFile {
+            name: format!("{i}.bin"),
+            data: Arc::new(MemoryData {
+                data: data.clone(),
+                offset: AtomicUsize::new(0),
+            }),
+            kind: ItemKind::File,
+        })
+        .collect();
+
+    let sending = send_files(SendFilesRequest {
+        profile: SenderProfile {
+            name: "bench".to_string(),
+            avatar_b64: None,
+        },
+        files,
+        config: SenderConfig {
+            chunk_size: transfer.chunk_size,
+            parallel_streams: transfer.parallel_streams,
+            endpoint: local_endpoint(),
+            ..Default::default()
+        },
+    })
+    .await?;
+
+    let receiving = receive_files(ReceiveFilesRequest {
+        ticket: sending.get_ticket(),
+        confirmation: sending.get_confirmation(),
+        profile: ReceiverProfile {
+            name: "bench".to_string(),
+            avatar_b64: None,
+        },
+        config: Some(ReceiverConfig {
+            chunk_size: transfer.chunk_size,
+            parallel_streams: transfer.parallel_streams,
+            endpoint: local_endpoint(),
+            ..Default::default()
+        }),
+    })
+    .await?;
+
+    let mut events = receiving.events();
+    receiving.start()?;
+
+    let mut received = 0u64;
+    while let Some(event) = events.next().await {
+        match event {
+            ReceiveFilesEvent::Receiving(event) => {
+                received += event.data.len() as u64
+            }
+            ReceiveFilesEvent::Connecting(_)
+            | ReceiveFilesEvent::Discarded(_)
+            | ReceiveFilesEvent::Ended(_) => {}
+            ReceiveFilesEvent::Finished => break,
+        }
+    }
+
+    if received != transfer.total_bytes() {
+        return Err(anyhow!(
+            "Received {received} of {} bytes",
+            transfer.total_bytes()
+        ));
+    }
+    Ok(received)
+}
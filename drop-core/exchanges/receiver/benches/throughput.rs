@@ -0,0 +1,90 @@
+mod support;
+
+use arkdropx_common::projection::FileProjection;
+use criterion::{
+    BenchmarkId, Criterion, Throughput, black_box, criterion_group,
+    criterion_main,
+};
+
+use support::Transfer;
+
+const CHUNK_SIZES: [u64; 3] = [64 * 1024, 512 * 1024, 1024 * 1024];
+
+/// Encoding and decoding of the JSON frames every chunk travels in
+fn frame_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("frame");
+
+    for chunk_size in CHUNK_SIZES {
+        let projection = FileProjection::new(
+            "file".to_string(),
+            vec![0xA5; chunk_size as usize],
+        );
+        group.throughput(Throughput::Bytes(chunk_size));
+        group.bench_with_input(
+            BenchmarkId::new("encode_decode", chunk_size),
+            &projection,
+            |b, projection| {
+                b.iter(|| {
+                    let frame =
+                        serde_json::to_vec(black_box(projection)).unwrap();
+                    let decoded: FileProjection =
+                        serde_json::from_slice(&frame).unwrap();
+                    decoded
+                });
+            },
+        );
+    }
+
+    group.finish();
+}
+
+/// Complete transfers between two endpoints over the local interfaces
+fn transfer_benchmark(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let mut group = c.benchmark_group("transfer");
+    group.sample_size(10);
+
+    let mut transfers = Vec::new();
+    for chunk_size in CHUNK_SIZES {
+        for parallel_streams in [1, 4, 8] {
+            transfers.push(Transfer {
+                files: 8,
+                file_size: 4 * 1024 * 1024,
+                chunk_size,
+                parallel_streams,
+            });
+        }
+    }
+    for file_size in [16 * 1024, 64 * 1024 * 1024] {
+        transfers.push(Transfer {
+            files: (64 * 1024 * 1024 / file_size).min(256),
+            file_size,
+            chunk_size: 512 * 1024,
+            parallel_streams: 4,
+        });
+    }
+
+    for transfer in transfers {
+        let id = format!(
+            "{}x{}B/chunk={}/streams={}",
+            transfer.files,
+            transfer.file_size,
+            transfer.chunk_size,
+            transfer.parallel_streams
+        );
+        group.throughput(Throughput::Bytes(transfer.total_bytes()));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(id),
+            &transfer,
+            |b, transfer| {
+                b.to_async(&runtime)
+                    .iter(|| async { support::run(*transfer).await.unwrap() });
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, frame_benchmark, transfer_benchmark);
+criterion_main!(benches);
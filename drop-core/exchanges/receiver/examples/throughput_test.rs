@@ -0,0 +1,50 @@
+#[path = "../benches/support/mod.rs"]
+mod support;
+
+use std::time::Instant;
+
+use anyhow::Result;
+
+use support::Transfer;
+
+/// Prints the throughput of loopback transfers for every combination of the
+/// chunk sizes and stream counts of the performance presets, as a quick
+/// alternative to the criterion benchmark:
+///
+/// cargo run --release -p arkdropx-receiver --example throughput_test
+#[tokio::main]
+async fn main() -> Result<()> {
+    println!(
+        "{:>10} {:>8} {:>8} {:>10} {:>10}",
+        "chunk", "streams", "files", "file size", "MiB/s"
+    );
+
+    for chunk_size in [64 * 1024, 512 * 1024] {
+        for parallel_streams in [2, 4, 8] {
+            for (files, file_size) in [(256, 64 * 1024), (4, 32 * 1024 * 1024)]
+            {
+                let transfer = Transfer {
+                    files,
+                    file_size,
+                    chunk_size,
+                    parallel_streams,
+                };
+
+                let started = Instant::now();
+                let bytes = support::run(transfer).await?;
+                let seconds = started.elapsed().as_secs_f64();
+
+                println!(
+                    "{:>10} {:>8} {:>8} {:>10} {:>10.1}",
+                    chunk_size,
+                    parallel_streams,
+                    files,
+                    file_size,
+                    bytes as f64 / (1024.0 * 1024.0) / seconds
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
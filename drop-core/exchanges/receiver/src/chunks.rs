@@ -0,0 +1,65 @@
+//! Bounded streams of received chunks.
+//!
+//! Subscribers are called synchronously from the read loop of each file
+//! stream, so a subscriber writing chunks to a slow disk blocks a runtime
+//! worker without the loop ever slowing down. A chunk stream is the
+//! backpressure-aware alternative: once it holds `capacity` chunks the read
+//! loop waits for the consumer, stops reading from QUIC, and flow control
+//! makes the sender wait in turn.
+
+use std::sync::Mutex;
+
+use futures::{Stream, stream};
+use tokio::sync::mpsc;
+
+/// The chunk streams opened on a session.
+pub(crate) struct ChunkStreams<T> {
+    /// `None` once the session is over.
+    senders: Mutex<Option<Vec<mpsc::Sender<T>>>>,
+}
+
+impl<T: Clone + Send + 'static> ChunkStreams<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            senders: Mutex::new(Some(Vec::new())),
+        }
+    }
+
+    /// Opens a stream buffering up to `capacity` chunks. The stream of a
+    /// session that is already over ends right away.
+    pub(crate) fn open(
+        &self,
+        capacity: usize,
+    ) -> impl Stream<Item = T> + Send + 'static {
+        let (sender, receiver) = mpsc::channel(capacity.max(1));
+        if let Some(senders) = self.senders.lock().unwrap().as_mut() {
+            senders.push(sender);
+        }
+        stream::unfold(receiver, |mut receiver| async move {
+            receiver
+                .recv()
+                .await
+                .map(|chunk| (chunk, receiver))
+        })
+    }
+
+    /// Hands the chunk to every open stream, waiting while one is full.
+    /// Streams dropped by their consumer are skipped.
+    pub(crate) async fn send(&self, chunk: T) {
+        let senders = match self.senders.lock().unwrap().as_mut() {
+            Some(senders) => {
+                senders.retain(|sender| !sender.is_closed());
+                senders.clone()
+            }
+            None => return,
+        };
+        for sender in senders {
+            let _ = sender.send(chunk.clone()).await;
+        }
+    }
+
+    /// Ends all streams once their buffered chunks are consumed.
+    pub(crate) fn close(&self) {
+        self.senders.lock().unwrap().take();
+    }
+}
@@ -13,7 +13,8 @@
 //! 1. Build a `ReceiveFilesRequest` with a sender ticket, confirmation code,
 //!    your `ReceiverProfile`, and an optional `ReceiverConfig`.
 //! 2. Call `receive_files::receive_files` to obtain a `ReceiveFilesBubble`.
-//! 3. Subscribe to events to observe connection and progress.
+//! 3. Subscribe to events to observe connection and progress, or poll the
+//!    stream returned by `ReceiveFilesBubble::events()` in async code.
 //! 4. Start the transfer with `ReceiveFilesBubble::start()`.
 //! 5. Optionally cancel with `ReceiveFilesBubble::cancel()`.
 //! 6. When finished, the session is closed and resources cleaned up.
@@ -26,14 +27,38 @@
 //! 4. Subscribe to events to observe when sender connects and file reception.
 //! 5. Optionally cancel with `ReadyToReceiveBubble::cancel()`.
 
+mod chunks;
 pub mod ready_to_receive;
 mod receive_files;
+mod throttle;
 
+#[cfg(test)]
+mod tests;
+
+use arkdropx_common::{
+    handshake::{
+        HandshakeConfig, HandshakeFile, InvalidCopyError, SenderHandshake,
+    },
+    projection::SOURCE_CHANGED_CODE,
+};
+use iroh::endpoint::{ReadError, ReadExactError, VarInt};
 use std::{
+    collections::HashMap,
     io::{BufReader, Bytes, Read},
     sync::{RwLock, atomic::AtomicBool},
+    time::SystemTime,
 };
 
+pub use arkdropx_common::{
+    endpoint::{EndpointConfig, RelayConfig},
+    handshake::{
+        Compression, DEFAULT_MAX_AVATAR_SIZE, HashAlgorithm, ItemKind,
+    },
+    item::LinkItem,
+    retry::RetryConfig,
+    status::EndReason,
+    uri::{DropAction, DropUri},
+};
 pub use receive_files::*;
 
 /// Identity and presentation for the receiving peer.
@@ -64,6 +89,26 @@ pub struct ReceiverConfig {
     pub chunk_size: u64,
     /// Number of unidirectional streams to process concurrently.
     pub parallel_streams: u64,
+    /// Networking options of the endpoint bound for the transfer.
+    pub endpoint: EndpointConfig,
+    /// Largest avatar in bytes accepted from the sender, bigger or invalid
+    /// ones are dropped.
+    pub max_avatar_size: usize,
+    /// Retries of failed connection attempts to the sender.
+    pub retry: RetryConfig,
+    /// Largest download rate in bytes per second, shared by all streams of
+    /// the session. Unlimited if `None`.
+    pub max_rate: Option<u64>,
+    /// Largest total size in bytes of the files of a session. Larger
+    /// sessions are refused with [`EndReason::TooLarge`] before any chunk
+    /// is received. Unlimited if `None`.
+    pub max_total_size: Option<u64>,
+    /// Hash algorithms to verify files with, most preferred first. Hashing
+    /// is disabled if empty.
+    pub hash_algorithms: Vec<HashAlgorithm>,
+    /// Compressions accepted for file data, most preferred first.
+    /// Compression is disabled if empty.
+    pub compressions: Vec<Compression>,
 }
 
 impl Default for ReceiverConfig {
@@ -74,6 +119,13 @@ impl Default for ReceiverConfig {
         Self {
             chunk_size: 1024 * 512, // 512KB chunks
             parallel_streams: 4,    // 4 parallel streams
+            endpoint: EndpointConfig::default(),
+            max_avatar_size: DEFAULT_MAX_AVATAR_SIZE,
+            retry: RetryConfig::default(),
+            max_rate: None,
+            max_total_size: None,
+            hash_algorithms: vec![HashAlgorithm::Blake3],
+            compressions: Vec::new(),
         }
     }
 }
@@ -85,6 +137,7 @@ impl ReceiverConfig {
         Self {
             chunk_size: 1024 * 512, // 512KB chunks
             parallel_streams: 8,    // 8 parallel streams
+            ..Self::default()
         }
     }
 
@@ -100,8 +153,19 @@ impl ReceiverConfig {
         Self {
             chunk_size: 1024 * 64, // 64KB chunks
             parallel_streams: 2,   // 2 parallel streams
+            ..Self::default()
         }
     }
+
+    /// Returns the preferences advertised to the sender in the handshake.
+    pub(crate) fn handshake_config(&self) -> HandshakeConfig {
+        HandshakeConfig::new(
+            self.chunk_size,
+            self.parallel_streams,
+            self.hash_algorithms.clone(),
+            self.compressions.clone(),
+        )
+    }
 }
 
 /// Metadata and data carrier representing a file being received.
@@ -200,3 +264,104 @@ impl ReceiverFileData {
         None
     }
 }
+
+/// Returns true if the stream of a file failed because the sender reset it,
+/// as its source changed on disk. Only that file is lost, the rest of the
+/// session goes on.
+pub(crate) fn is_source_changed(err: &anyhow::Error) -> bool {
+    let code = VarInt::from_u32(SOURCE_CHANGED_CODE);
+    let reset = match err.downcast_ref::<ReadExactError>() {
+        Some(ReadExactError::ReadError(e)) => Some(e),
+        _ => err.downcast_ref::<ReadError>(),
+    };
+    matches!(reset, Some(ReadError::Reset(c)) if *c == code)
+}
+
+/// Files announced by the sender, used to complete and verify each file
+/// once its stream ends.
+#[derive(Default)]
+pub(crate) struct Announced {
+    /// Ids of the copies the sender skips, by id of the file they are a
+    /// copy of. Empty without deduplication.
+    copies: HashMap<String, Vec<String>>,
+    files: HashMap<String, HandshakeFile>,
+    /// Whether both peers agreed on BLAKE3, the hash the files announce.
+    verify: bool,
+}
+
+impl Announced {
+    /// Indexes the files of the handshake, with their copies if the sender
+    /// skips them, verifying their hashes if `verify`.
+    pub(crate) fn new(
+        handshake: &SenderHandshake,
+        deduplicate: bool,
+        verify: bool,
+    ) -> Result<Self, InvalidCopyError> {
+        let copies = if deduplicate {
+            handshake.copies()?
+        } else {
+            HashMap::new()
+        };
+        let files = handshake
+            .files
+            .iter()
+            .map(|file| (file.id.clone(), file.clone()))
+            .collect();
+        Ok(Self {
+            copies,
+            files,
+            verify,
+        })
+    }
+
+    /// Returns the ids of the copies of the file `id` the sender skips.
+    pub(crate) fn copies(&self, id: &str) -> Vec<String> {
+        self.copies.get(id).cloned().unwrap_or_default()
+    }
+
+    /// Returns true if the file `id` is to be verified against the hash
+    /// announced by the sender.
+    pub(crate) fn has_hash(&self, id: &str) -> bool {
+        self.verify
+            && self
+                .files
+                .get(id)
+                .is_some_and(|file| file.hash.is_some())
+    }
+
+    /// Returns false if the content of the file `id` hashed to `hash` isn't
+    /// the announced one.
+    pub(crate) fn matches_hash(&self, id: &str, hash: &blake3::Hash) -> bool {
+        self.files
+            .get(id)
+            .is_none_or(|file| file.matches_hash(&hash.to_hex()))
+    }
+
+    /// Returns the modification time the sender announced for the file
+    /// `id`.
+    pub(crate) fn modified(&self, id: &str) -> Option<SystemTime> {
+        self.files
+            .get(id)
+            .and_then(HandshakeFile::modified_time)
+    }
+}
+
+/// Fails with [`EndReason::TooLarge`] if the files announced by the sender
+/// add up to more than `max_total_size` bytes.
+pub(crate) fn check_total_size(
+    files: &[HandshakeFile],
+    max_total_size: Option<u64>,
+) -> anyhow::Result<()> {
+    let Some(max) = max_total_size else {
+        return Ok(());
+    };
+    let total = files
+        .iter()
+        .fold(0u64, |total, file| total.saturating_add(file.len));
+    if total > max {
+        return Err(anyhow::Error::new(EndReason::TooLarge).context(format!(
+            "Session of {total} bytes exceeds the limit of {max} bytes"
+        )));
+    }
+    Ok(())
+}
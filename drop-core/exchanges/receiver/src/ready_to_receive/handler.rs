@@ -9,13 +9,17 @@
 use anyhow::Result;
 use arkdrop_entities::Profile;
 use arkdropx_common::{
+    ack::AckWriter,
     handshake::{
-        HandshakeConfig, HandshakeProfile, NegotiatedConfig, ReceiverHandshake,
+        Capabilities, HandshakeProfile, HashAlgorithm, ItemKind,
+        MAX_HANDSHAKE_LEN, NegotiatedConfig, ReceiverHandshake,
         SenderHandshake,
     },
-    projection::FileProjection,
+    projection::{CHECKSUM_MISMATCH_CODE, FileProjection},
+    rate::RateLimiter,
+    status::EndReason,
 };
-use futures::Future;
+use futures::{Future, Stream};
 use iroh::{
     endpoint::{Connection, RecvStream, SendStream, VarInt},
     protocol::ProtocolHandler,
@@ -23,11 +27,18 @@ use iroh::{
 use std::{
     collections::HashMap,
     fmt::Debug,
+    iter,
     sync::{Arc, RwLock, atomic::AtomicBool},
+    time::{Duration, SystemTime},
 };
 use tokio::task::JoinSet;
+use tracing::{Instrument, Span, field};
 
 use super::ReadyToReceiveConfig;
+use crate::{
+    Announced, check_total_size, chunks::ChunkStreams, is_source_changed,
+    throttle::throttle_ready_to_receive,
+};
 
 /// Observer interface for transfer logs and progress.
 ///
@@ -38,6 +49,9 @@ pub trait ReadyToReceiveSubscriber: Send + Sync {
     fn get_id(&self) -> String;
 
     /// Receives diagnostic log lines from the transfer pipeline.
+    ///
+    /// The same lines are emitted as `tracing` debug events inside the
+    /// `ready_to_receive` span, which carries the session and peer ids.
     fn log(&self, message: String);
 
     /// Receives chunk data for each file being received.
@@ -47,6 +61,36 @@ pub trait ReadyToReceiveSubscriber: Send + Sync {
 
     /// Notified when a sender connects and completes the handshake.
     fn notify_connecting(&self, event: ReadyToReceiveConnectingEvent);
+
+    /// Notified when a chunk of the file failed verification, before the
+    /// session ends with [`EndReason::VerificationFailed`].
+    ///
+    /// The chunks delivered for the file don't make up the file and must be
+    /// deleted. Copies of the file are discarded along with it.
+    fn notify_discarded(&self, _event: ReadyToReceiveDiscardedEvent) {}
+
+    /// Notified once the sender finished sending a file, or a copy, and all
+    /// of its chunks were delivered.
+    ///
+    /// Returns whether the file is stored for good, e.g. synced to disk;
+    /// the sender may delete its source once every subscriber did, so this
+    /// may wait for the disk. Subscribers not storing files keep the
+    /// default.
+    fn notify_received(&self, _event: ReadyToReceiveReceivedEvent) -> bool {
+        true
+    }
+
+    /// Notified once with the reason the session ended, told to the peer or
+    /// received from it.
+    fn notify_ended(&self, _reason: EndReason) {}
+
+    /// Minimum time between two receiving events of a file, or `None` for
+    /// an event per chunk. Chunks received in between are merged into one
+    /// event, so no data is lost; the last chunk of a file is never held
+    /// back.
+    fn progress_interval(&self) -> Option<Duration> {
+        None
+    }
 }
 
 /// Per-chunk receiving event.
@@ -58,6 +102,23 @@ pub struct ReadyToReceiveReceivingEvent {
     pub data: Vec<u8>,
 }
 
+/// Event emitted when the data received for a file must be discarded.
+#[derive(Clone)]
+pub struct ReadyToReceiveDiscardedEvent {
+    pub id: String,
+}
+
+/// Event emitted once all chunks of a file were delivered.
+#[derive(Clone)]
+pub struct ReadyToReceiveReceivedEvent {
+    pub id: String,
+    /// Number of bytes delivered for the file.
+    pub len: u64,
+    /// Modification time of the source announced by the sender, to apply
+    /// to the stored file.
+    pub modified: Option<SystemTime>,
+}
+
 /// Connection event carrying the sender's profile and files list as reported
 /// during handshake.
 pub struct ReadyToReceiveConnectingEvent {
@@ -71,6 +132,9 @@ pub struct ReadyToReceiveSenderProfile {
     pub id: String,
     pub name: String,
     pub avatar_b64: Option<String>,
+    /// Node id of the sender's endpoint, stable across sessions when the
+    /// sender persists its secret key.
+    pub node_id: Option<String>,
 }
 
 /// File information provided by sender during handshake.
@@ -79,6 +143,7 @@ pub struct ReadyToReceiveFile {
     pub id: String,
     pub name: String,
     pub len: u64,
+    pub kind: ItemKind,
 }
 
 /// Protocol handler responsible for accepting a single sender and receiving
@@ -91,12 +156,16 @@ pub struct ReadyToReceiveFile {
 /// - Receives files over unidirectional streams.
 /// - Emits events to registered subscribers.
 pub struct ReadyToReceiveHandler {
-    is_consumed: AtomicBool,
+    is_consumed: Arc<AtomicBool>,
     is_finished: Arc<AtomicBool>,
+    end_reason: Arc<RwLock<Option<EndReason>>>,
     profile: Profile,
     config: ReadyToReceiveConfig,
+    files: Arc<RwLock<Vec<ReadyToReceiveFile>>>,
     subscribers:
         Arc<RwLock<HashMap<String, Arc<dyn ReadyToReceiveSubscriber>>>>,
+    chunks: Arc<ChunkStreams<ReadyToReceiveReceivingEvent>>,
+    span: Span,
 }
 impl Debug for ReadyToReceiveHandler {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -111,15 +180,30 @@ impl Debug for ReadyToReceiveHandler {
 impl ReadyToReceiveHandler {
     /// Constructs a new handler for the given profile and configuration.
     pub fn new(profile: Profile, config: ReadyToReceiveConfig) -> Self {
+        let span = tracing::info_span!(
+            "ready_to_receive",
+            session_id = %profile.id,
+            peer_id = field::Empty,
+        );
         Self {
-            is_consumed: AtomicBool::new(false),
+            span,
+            is_consumed: Arc::new(AtomicBool::new(false)),
             is_finished: Arc::new(AtomicBool::new(false)),
+            end_reason: Arc::new(RwLock::new(None)),
             profile,
             config,
+            files: Arc::new(RwLock::new(Vec::new())),
             subscribers: Arc::new(RwLock::new(HashMap::new())),
+            chunks: Arc::new(ChunkStreams::new()),
         }
     }
 
+    /// Returns the files announced by the sender, empty until a sender
+    /// connected.
+    pub fn get_files(&self) -> Vec<ReadyToReceiveFile> {
+        self.files.read().unwrap().clone()
+    }
+
     /// Returns true if a connection has already been accepted.
     ///
     /// This handler accepts at most one sender for a bubble.
@@ -141,8 +225,36 @@ impl ReadyToReceiveHandler {
         finished
     }
 
-    /// Broadcasts a log message to all subscribers.
+    /// Returns why the session ended, also told to the peer. `None` while
+    /// the session runs.
+    pub fn get_end_reason(&self) -> Option<EndReason> {
+        *self.end_reason.read().unwrap()
+    }
+
+    /// Records why the session ended unless a reason is already known, and
+    /// notifies subscribers. Used when the bubble is cancelled.
+    pub fn end(&self, reason: EndReason) {
+        self.chunks.close();
+        let mut end_reason = self.end_reason.write().unwrap();
+        if end_reason.is_some() {
+            return;
+        }
+        *end_reason = Some(reason);
+        drop(end_reason);
+
+        self.log(format!("end: Session ended, reason: {reason}"));
+        self.subscribers
+            .read()
+            .unwrap()
+            .iter()
+            .for_each(|(_, subscriber)| {
+                subscriber.notify_ended(reason);
+            });
+    }
+
+    /// Emits a debug event and broadcasts the message to all subscribers.
     pub fn log(&self, message: String) {
+        tracing::debug!(parent: &self.span, "{message}");
         self.subscribers
             .read()
             .unwrap()
@@ -155,6 +267,7 @@ impl ReadyToReceiveHandler {
     /// Registers a new subscriber or replaces an existing one with the same
     /// ID.
     pub fn subscribe(&self, subscriber: Arc<dyn ReadyToReceiveSubscriber>) {
+        let subscriber = throttle_ready_to_receive(subscriber);
         let subscriber_id = subscriber.get_id();
         self.log(format!(
             "Subscribing new subscriber with ID: {subscriber_id}"
@@ -172,6 +285,15 @@ impl ReadyToReceiveHandler {
         ));
     }
 
+    /// Opens a stream of received chunks buffering up to `capacity` of them,
+    /// see [`super::ReadyToReceiveBubble::chunks`].
+    pub fn chunks(
+        &self,
+        capacity: usize,
+    ) -> impl Stream<Item = ReadyToReceiveReceivingEvent> + Send + 'static {
+        self.chunks.open(capacity)
+    }
+
     /// Unregisters a subscriber by its ID.
     pub fn unsubscribe(&self, subscriber: Arc<dyn ReadyToReceiveSubscriber>) {
         let subscriber_id = subscriber.get_id();
@@ -201,22 +323,37 @@ impl ProtocolHandler for ReadyToReceiveHandler {
     > + Send {
         self.log("on_connecting: New connection attempt received".to_string());
 
-        let is_consumed = self
-            .is_consumed
-            .compare_exchange(
-                false,
-                true,
-                std::sync::atomic::Ordering::AcqRel,
-                std::sync::atomic::Ordering::Relaxed,
-            )
-            .unwrap_or(true);
+        let is_consumed = self.is_consumed.clone();
+        let allowed_senders = self.config.allowed_senders.clone();
 
         async move {
-            if is_consumed {
-                return Err(iroh::protocol::AcceptError::NotAllowed {});
-            }
+            let consume = || {
+                is_consumed
+                    .compare_exchange(
+                        false,
+                        true,
+                        std::sync::atomic::Ordering::AcqRel,
+                        std::sync::atomic::Ordering::Relaxed,
+                    )
+                    .unwrap_or(true)
+            };
+
+            let Some(allowed_senders) = allowed_senders else {
+                if consume() {
+                    return Err(iroh::protocol::AcceptError::NotAllowed {});
+                }
+                return Ok(connecting.await?);
+            };
 
+            // Senders are only known once connected, rejected ones must
+            // not consume the session
             let connection = connecting.await?;
+            let is_allowed = connection
+                .remote_node_id()
+                .is_ok_and(|id| allowed_senders.contains(&id.to_string()));
+            if !is_allowed || consume() {
+                return Err(iroh::protocol::AcceptError::NotAllowed {});
+            }
             Ok(connection)
         }
     }
@@ -237,31 +374,43 @@ impl ProtocolHandler for ReadyToReceiveHandler {
         Output = std::result::Result<(), iroh::protocol::AcceptError>,
     > + Send {
         self.log("accept: Creating carrier for file reception".to_string());
+        if let Ok(peer_id) = connection.remote_node_id() {
+            self.span
+                .record("peer_id", field::display(peer_id));
+        }
 
         let carrier = Carrier {
             is_finished: self.is_finished.clone(),
+            end_reason: self.end_reason.clone(),
             config: self.config.clone(),
             negotiated_config: None,
+            announced: Arc::default(),
             profile: self.profile.clone(),
             connection,
+            files: self.files.clone(),
             subscribers: self.subscribers.clone(),
+            chunks: self.chunks.clone(),
+            span: self.span.clone(),
         };
 
         async move {
             let mut carrier = carrier;
             if let Err(e) = carrier.greet().await {
                 carrier.log(format!("accept: Handshake failed: {:?}", e));
+                carrier.end(EndReason::of(&e));
                 return Err(iroh::protocol::AcceptError::NotAllowed {});
             }
 
             if let Err(e) = carrier.receive_files().await {
                 carrier.log(format!("accept: File reception failed: {:?}", e));
+                carrier.finish(EndReason::of(&e));
                 return Err(iroh::protocol::AcceptError::NotAllowed {});
             }
 
-            carrier.finish();
+            carrier.finish(EndReason::Completed);
             Ok(())
         }
+        .instrument(self.span.clone())
     }
 }
 
@@ -270,12 +419,18 @@ impl ProtocolHandler for ReadyToReceiveHandler {
 /// Not exposed publicly; used internally by `ReadyToReceiveHandler`.
 struct Carrier {
     is_finished: Arc<AtomicBool>,
+    end_reason: Arc<RwLock<Option<EndReason>>>,
     config: ReadyToReceiveConfig,
     negotiated_config: Option<NegotiatedConfig>,
+    /// Files announced by the sender, with the copies it skips.
+    announced: Arc<Announced>,
     profile: Profile,
     connection: Connection,
+    files: Arc<RwLock<Vec<ReadyToReceiveFile>>>,
     subscribers:
         Arc<RwLock<HashMap<String, Arc<dyn ReadyToReceiveSubscriber>>>>,
+    chunks: Arc<ChunkStreams<ReadyToReceiveReceivingEvent>>,
+    span: Span,
 }
 impl Carrier {
     /// Performs the bidirectional handshake exchange and notifies subscribers
@@ -300,29 +455,54 @@ impl Carrier {
     ) -> Result<()> {
         let mut header = [0u8; 4];
         bi.1.read_exact(&mut header).await?;
-        let len = u32::from_be_bytes(header);
+        let len = u32::from_be_bytes(header) as usize;
+        if len > MAX_HANDSHAKE_LEN {
+            return Err(anyhow::Error::msg(format!(
+                "Handshake of {len} bytes exceeds the limit of {MAX_HANDSHAKE_LEN} bytes"
+            )));
+        }
 
-        let mut buffer = vec![0u8; len as usize];
+        let mut buffer = vec![0u8; len];
         bi.1.read_exact(&mut buffer).await?;
 
-        let handshake: SenderHandshake = serde_json::from_slice(&buffer)?;
+        let mut handshake: SenderHandshake = serde_json::from_slice(&buffer)?;
+        if let Some(e) = handshake
+            .profile
+            .strip_invalid_avatar(self.config.max_avatar_size)
+        {
+            self.log(format!("receive_handshake: Dropped peer avatar: {e}"));
+        }
+        // Refused before subscribers get to prepare for the files
+        check_total_size(&handshake.files, self.config.max_total_size)?;
 
         // Negotiate configuration
-        let receiver_config = HandshakeConfig {
-            chunk_size: self.config.chunk_size,
-            parallel_streams: self.config.parallel_streams,
-        };
+        let receiver_config = self.config.handshake_config();
 
-        self.negotiated_config = Some(NegotiatedConfig::negotiate(
-            &handshake.config,
-            &receiver_config,
+        let negotiated_config =
+            NegotiatedConfig::negotiate(&handshake.config, &receiver_config);
+        self.log(format!(
+            "receive_handshake: Negotiated capabilities: [{}]",
+            negotiated_config.capabilities
         ));
+        let deduplicate = negotiated_config
+            .capabilities
+            .contains(Capabilities::DEDUPLICATION);
+        let verify =
+            negotiated_config.hash_algorithm == Some(HashAlgorithm::Blake3);
+        self.announced =
+            Arc::new(Announced::new(&handshake, deduplicate, verify)?);
+        self.negotiated_config = Some(negotiated_config);
 
         // Prepare data structures
         let profile = ReadyToReceiveSenderProfile {
             id: handshake.profile.id,
             name: handshake.profile.name,
             avatar_b64: handshake.profile.avatar_b64,
+            node_id: self
+                .connection
+                .remote_node_id()
+                .ok()
+                .map(|id| id.to_string()),
         };
 
         let files: Vec<ReadyToReceiveFile> = handshake
@@ -332,8 +512,10 @@ impl Carrier {
                 id: f.id,
                 name: f.name,
                 len: f.len,
+                kind: f.kind,
             })
             .collect();
+        *self.files.write().unwrap() = files.clone();
 
         // Notify subscribers
         self.subscribers
@@ -361,10 +543,7 @@ impl Carrier {
                 name: self.profile.name.clone(),
                 avatar_b64: self.profile.avatar_b64.clone(),
             },
-            config: HandshakeConfig {
-                chunk_size: self.config.chunk_size,
-                parallel_streams: self.config.parallel_streams,
-            },
+            config: self.config.handshake_config(),
         };
 
         // Pre-allocate vector with estimated capacity
@@ -382,6 +561,27 @@ impl Carrier {
         Ok(())
     }
 
+    /// Opens the stream acknowledging the files received in full, if the
+    /// sender asked for acknowledgements.
+    async fn open_acks(&self) -> Result<Option<Arc<AckWriter>>> {
+        let is_negotiated = self.negotiated_config.as_ref().is_some_and(|c| {
+            c.capabilities
+                .contains(Capabilities::ACKNOWLEDGEMENTS)
+        });
+        if !is_negotiated {
+            return Ok(None);
+        }
+        let lens = self
+            .files
+            .read()
+            .unwrap()
+            .iter()
+            .map(|f| (f.id.clone(), f.len))
+            .collect();
+        let acks = AckWriter::open(&self.connection, lens).await?;
+        Ok(Some(Arc::new(acks)))
+    }
+
     /// Receives all files using unidirectional streams and the negotiated
     /// settings.
     async fn receive_files(&self) -> Result<()> {
@@ -394,6 +594,8 @@ impl Carrier {
             } else {
                 (self.config.chunk_size, self.config.parallel_streams)
             };
+        let rate = RateLimiter::from_rate(self.config.max_rate).map(Arc::new);
+        let acks = self.open_acks().await?;
 
         let expected_close = iroh::endpoint::ConnectionError::ApplicationClosed(
             iroh::endpoint::ApplicationClose {
@@ -405,11 +607,30 @@ impl Carrier {
         'files_iterator: loop {
             let connection = self.connection.clone();
             let subscribers = self.subscribers.clone();
-
-            join_set.spawn(async move {
-                Self::receive_single_file(chunk_size, connection, subscribers)
-                    .await
-            });
+            let chunks = self.chunks.clone();
+            let announced = self.announced.clone();
+            let rate = rate.clone();
+            let acks = acks.clone();
+
+            // The file is only known once its first chunk arrives
+            let span = tracing::debug_span!(
+                parent: &self.span,
+                "receive_file",
+                file_id = field::Empty,
+            );
+            let task = async move {
+                Self::receive_single_file(
+                    chunk_size,
+                    connection,
+                    subscribers,
+                    chunks,
+                    announced,
+                    rate,
+                    acks,
+                )
+                .await
+            };
+            join_set.spawn(task.instrument(span));
 
             // Limit concurrent streams to negotiated number
             while join_set.len() >= parallel_streams as usize {
@@ -423,6 +644,13 @@ impl Carrier {
                     {
                         break 'files_iterator;
                     }
+                    if is_source_changed(&err) {
+                        self.log(
+                            "receive_files: Sender abandoned a changed file"
+                                .to_string(),
+                        );
+                        continue;
+                    }
                     self.log(format!("receive_files: Stream failed: {err}"));
                     return Err(err);
                 }
@@ -438,6 +666,13 @@ impl Carrier {
                 {
                     continue;
                 }
+                if is_source_changed(&err) {
+                    self.log(
+                        "receive_files: Sender abandoned a changed file"
+                            .to_string(),
+                    );
+                    continue;
+                }
                 self.log(format!("receive_single_file: Stream failed: {err}"));
                 return Err(err);
             }
@@ -449,18 +684,25 @@ impl Carrier {
 
     /// Receives a single file in JSON-framed chunks:
     /// - 4-byte big-endian length header
-    /// - JSON payload containing `FileProjection { id, data }`
+    /// - JSON payload containing `FileProjection { id, data, checksum }`
     async fn receive_single_file(
         chunk_size: u64,
         connection: Connection,
         subscribers: Arc<
             RwLock<HashMap<String, Arc<dyn ReadyToReceiveSubscriber>>>,
         >,
+        chunks: Arc<ChunkStreams<ReadyToReceiveReceivingEvent>>,
+        announced: Arc<Announced>,
+        rate: Option<Arc<RateLimiter>>,
+        acks: Option<Arc<AckWriter>>,
     ) -> Result<()> {
         let mut uni = connection.accept_uni().await?;
 
         let mut buffer =
             Vec::with_capacity((chunk_size + 256 * 1024).try_into().unwrap());
+        let mut file_id = None;
+        let mut hasher = None;
+        let mut received = 0;
 
         loop {
             buffer.clear();
@@ -474,25 +716,157 @@ impl Carrier {
             buffer.resize(len, 0);
 
             uni.read_exact(&mut buffer).await?;
+            if let Some(rate) = &rate {
+                rate.acquire(len as u64).await;
+            }
 
             let projection: FileProjection = serde_json::from_slice(&buffer)?;
-
-            // Notify subscribers about received chunk
+            if !projection.verify() {
+                // The chunks delivered so far are an incomplete file
+                let ids: Vec<_> = iter::once(projection.id.clone())
+                    .chain(announced.copies(&projection.id))
+                    .collect();
+                Self::notify_discarded(&subscribers, &ids);
+                uni.stop(CHECKSUM_MISMATCH_CODE.into())?;
+                return Err(anyhow::Error::new(EndReason::VerificationFailed)
+                    .context(format!(
+                        "Checksum mismatch in a chunk of file {}",
+                        projection.id
+                    )));
+            }
+            if file_id.is_none() {
+                Span::current().record("file_id", projection.id.as_str());
+                // Hashed as it arrives, to be verified once complete
+                hasher = announced
+                    .has_hash(&projection.id)
+                    .then(blake3::Hasher::new);
+                file_id = Some(projection.id.clone());
+            }
+            if let Some(hasher) = &mut hasher {
+                hasher.update(&projection.data);
+            }
+            received += projection.data.len() as u64;
+            tracing::trace!(
+                len = projection.data.len(),
+                "receive_single_file: Chunk received"
+            );
+
+            // Copies skipped by the sender get the same chunks
+            let copy_ids = announced.copies(&projection.id);
             let event = ReadyToReceiveReceivingEvent {
                 id: projection.id,
                 data: projection.data,
             };
+            let copy_events: Vec<_> = copy_ids
+                .into_iter()
+                .map(|id| ReadyToReceiveReceivingEvent {
+                    id,
+                    data: event.data.clone(),
+                })
+                .collect();
 
+            // Notify subscribers about received chunk
+            for event in iter::once(event).chain(copy_events) {
+                subscribers
+                    .read()
+                    .unwrap()
+                    .iter()
+                    .for_each(|(_, s)| {
+                        s.notify_receiving(event.clone());
+                    });
+                chunks.send(event).await;
+            }
+        }
+
+        // Copies are complete along with the file they are a copy of
+        let Some(id) = file_id else {
+            return Ok(());
+        };
+        let ids: Vec<_> = iter::once(id.clone())
+            .chain(announced.copies(&id))
+            .collect();
+        if let Some(hasher) = hasher
+            && !announced.matches_hash(&id, &hasher.finalize())
+        {
+            Self::notify_discarded(&subscribers, &ids);
+            return Err(anyhow::Error::new(EndReason::VerificationFailed)
+                .context(format!("Content hash mismatch of file {id}")));
+        }
+        let stored = Self::notify_received(
+            subscribers,
+            announced,
+            ids.clone(),
+            received,
+        )
+        .await?;
+        if stored.len() < ids.len() {
+            tracing::debug!("receive_single_file: File not stored");
+        }
+        // Only files stored for good, the sender may delete the sources
+        if let Some(acks) = &acks
+            && !acks.acknowledge(&stored, received).await?
+        {
+            tracing::debug!(received, "receive_single_file: File incomplete");
+        }
+
+        Ok(())
+    }
+
+    /// Tells the subscribers the files of `ids` were received, on a blocking
+    /// thread as they may wait for the disk, and returns those every
+    /// subscriber stored.
+    async fn notify_received(
+        subscribers: Arc<
+            RwLock<HashMap<String, Arc<dyn ReadyToReceiveSubscriber>>>,
+        >,
+        announced: Arc<Announced>,
+        ids: Vec<String>,
+        len: u64,
+    ) -> Result<Vec<String>> {
+        let stored = tokio::task::spawn_blocking(move || {
+            let subscribers: Vec<_> = subscribers
+                .read()
+                .unwrap()
+                .values()
+                .cloned()
+                .collect();
+            ids.into_iter()
+                .filter(|id| {
+                    // Every subscriber is told, even once one didn't store it
+                    subscribers.iter().fold(true, |stored, s| {
+                        let event = ReadyToReceiveReceivedEvent {
+                            id: id.clone(),
+                            len,
+                            modified: announced.modified(id),
+                        };
+                        s.notify_received(event) && stored
+                    })
+                })
+                .collect()
+        })
+        .await?;
+        Ok(stored)
+    }
+
+    /// Tells the subscribers the data delivered for the files of `ids`
+    /// must be deleted.
+    fn notify_discarded(
+        subscribers: &RwLock<
+            HashMap<String, Arc<dyn ReadyToReceiveSubscriber>>,
+        >,
+        ids: &[String],
+    ) {
+        for id in ids {
             subscribers
                 .read()
                 .unwrap()
                 .iter()
                 .for_each(|(_, s)| {
-                    s.notify_receiving(event.clone());
+                    s.notify_discarded(ReadyToReceiveDiscardedEvent {
+                        id: id.clone(),
+                    });
                 });
         }
-
-        Ok(())
     }
 
     /// Read a 4-byte big-endian length prefix from a unidirectional stream.
@@ -521,24 +895,47 @@ impl Carrier {
         }
     }
 
-    /// Marks the handler as finished and closes the connection with a code and
-    /// reason.
-    fn finish(&self) {
+    /// Records why the session ended and closes the connection with it. A
+    /// reason already known, e.g. of a cancellation, is kept and was already
+    /// notified to subscribers. Ends the chunk streams.
+    fn end(&self, reason: EndReason) {
+        self.chunks.close();
+        let mut end_reason = self.end_reason.write().unwrap();
+        let is_known = end_reason.is_some();
+        let reason = *end_reason.get_or_insert(reason);
+        drop(end_reason);
+
+        self.log(format!("end: Closing connection, reason: {reason}"));
+        reason.close(&self.connection);
+        if is_known {
+            return;
+        }
+
+        self.subscribers
+            .read()
+            .unwrap()
+            .iter()
+            .for_each(|(_, subscriber)| {
+                subscriber.notify_ended(reason);
+            });
+    }
+
+    /// Marks the handler as finished and closes the connection with the code
+    /// of the reason the session ended with.
+    fn finish(&self, reason: EndReason) {
         self.log("finish: Starting transfer finish process".to_string());
+        self.end(reason);
 
         self.is_finished
             .store(true, std::sync::atomic::Ordering::Relaxed);
         self.log("finish: Transfer finished flag set to true".to_string());
 
-        self.log("finish: Connection closed".to_string());
-        self.connection
-            .close(VarInt::from_u32(200), "finished".as_bytes());
-
         self.log("finish: Transfer process completed successfully".to_string());
     }
 
     /// Internal logger that prefixes subscriber IDs.
     fn log(&self, message: String) {
+        tracing::debug!(parent: &self.span, "{message}");
         self.subscribers.read().unwrap().iter().for_each(
             |(_id, subscriber)| {
                 subscriber.log(message.clone());
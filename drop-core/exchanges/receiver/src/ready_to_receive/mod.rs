@@ -9,18 +9,24 @@ mod handler;
 
 use anyhow::Result;
 use arkdrop_entities::Profile;
+use arkdropx_common::handshake::HandshakeConfig;
 use chrono::{DateTime, Utc};
+use futures::Stream;
 use handler::ReadyToReceiveHandler;
-use iroh::{Endpoint, Watcher, protocol::Router};
+use iroh::{Watcher, protocol::Router};
 use iroh_base::ticket::NodeTicket;
 use rand::Rng;
 use std::sync::Arc;
 use uuid::Uuid;
 
-use super::ReceiverProfile;
+use super::{
+    Compression, DEFAULT_MAX_AVATAR_SIZE, EndReason, EndpointConfig,
+    HashAlgorithm, ReceiverProfile,
+};
 
 pub use handler::{
-    ReadyToReceiveConnectingEvent, ReadyToReceiveFile,
+    ReadyToReceiveConnectingEvent, ReadyToReceiveDiscardedEvent,
+    ReadyToReceiveFile, ReadyToReceiveReceivedEvent,
     ReadyToReceiveReceivingEvent, ReadyToReceiveSenderProfile,
     ReadyToReceiveSubscriber,
 };
@@ -44,6 +50,30 @@ pub struct ReadyToReceiveConfig {
     pub chunk_size: u64,
     /// Number of unidirectional streams to process concurrently.
     pub parallel_streams: u64,
+    /// Networking options of the endpoint bound for the transfer.
+    pub endpoint: EndpointConfig,
+    /// Largest avatar in bytes accepted from the sender, bigger or invalid
+    /// ones are dropped.
+    pub max_avatar_size: usize,
+    /// Largest download rate in bytes per second, shared by all streams of
+    /// the session. Unlimited if `None`.
+    pub max_rate: Option<u64>,
+    /// Confirmation code the sender must present, e.g. one agreed on when
+    /// pairing devices. A random one is generated if `None`.
+    pub confirmation: Option<u8>,
+    /// Node ids of the senders allowed to connect, anyone with the ticket
+    /// and confirmation code if `None`.
+    pub allowed_senders: Option<Vec<String>>,
+    /// Largest total size in bytes of the files of a session. Larger
+    /// sessions are refused with [`EndReason::TooLarge`] before any chunk
+    /// is received. Unlimited if `None`.
+    pub max_total_size: Option<u64>,
+    /// Hash algorithms to verify files with, most preferred first. Hashing
+    /// is disabled if empty.
+    pub hash_algorithms: Vec<HashAlgorithm>,
+    /// Compressions accepted for file data, most preferred first.
+    /// Compression is disabled if empty.
+    pub compressions: Vec<Compression>,
 }
 
 impl Default for ReadyToReceiveConfig {
@@ -54,6 +84,14 @@ impl Default for ReadyToReceiveConfig {
         Self {
             chunk_size: 1024 * 512, // 512KB chunks
             parallel_streams: 4,    // 4 parallel streams
+            endpoint: EndpointConfig::default(),
+            max_avatar_size: DEFAULT_MAX_AVATAR_SIZE,
+            max_rate: None,
+            confirmation: None,
+            allowed_senders: None,
+            max_total_size: None,
+            hash_algorithms: vec![HashAlgorithm::Blake3],
+            compressions: Vec::new(),
         }
     }
 }
@@ -66,6 +104,7 @@ impl ReadyToReceiveConfig {
         Self {
             chunk_size: 1024 * 512, // 512KB chunks
             parallel_streams: 8,    // 8 parallel streams
+            ..Self::default()
         }
     }
 
@@ -81,8 +120,19 @@ impl ReadyToReceiveConfig {
         Self {
             chunk_size: 1024 * 64, // 64KB chunks
             parallel_streams: 2,   // 2 parallel streams
+            ..Self::default()
         }
     }
+
+    /// Returns the preferences advertised to the sender in the handshake.
+    pub(crate) fn handshake_config(&self) -> HandshakeConfig {
+        HandshakeConfig::new(
+            self.chunk_size,
+            self.parallel_streams,
+            self.hash_algorithms.clone(),
+            self.compressions.clone(),
+        )
+    }
 }
 
 /// A waiting receive session.
@@ -131,6 +181,7 @@ impl ReadyToReceiveBubble {
     pub async fn cancel(&self) -> Result<()> {
         self.handler
             .log("cancel: Initiating receive wait cancellation".to_string());
+        self.handler.end(EndReason::Cancelled);
         let result = self
             .router
             .shutdown()
@@ -178,6 +229,12 @@ impl ReadyToReceiveBubble {
         is_finished
     }
 
+    /// Returns why the session ended, also told to the sender. `None` while
+    /// the session runs.
+    pub fn get_end_reason(&self) -> Option<EndReason> {
+        self.handler.get_end_reason()
+    }
+
     /// Returns true if a sender has connected and been accepted (i.e.,
     /// the handler has consumed the single allowed connection).
     pub fn is_connected(&self) -> bool {
@@ -202,6 +259,15 @@ impl ReadyToReceiveBubble {
         self.created_at.to_rfc3339()
     }
 
+    /// Returns the files announced by the connected sender, with their sizes.
+    ///
+    /// Empty until a sender completed the handshake, see
+    /// [`ReadyToReceiveSubscriber::notify_connecting`]. Chunk events only
+    /// carry the file ID, this is where to look up the total size of a file.
+    pub fn get_files(&self) -> Vec<ReadyToReceiveFile> {
+        self.handler.get_files()
+    }
+
     /// Register a subscriber to receive logs and chunk notifications.
     ///
     /// Subscribers must be `Send + Sync`. Duplicate IDs will replace previous
@@ -214,6 +280,23 @@ impl ReadyToReceiveBubble {
         self.handler.subscribe(subscriber);
     }
 
+    /// Stream of received chunks which applies backpressure, an alternative
+    /// to [`ReadyToReceiveSubscriber::notify_receiving`] for consumers doing
+    /// slow work like disk writes.
+    ///
+    /// At most `capacity` chunks are buffered; while the stream is full,
+    /// reading from the sender pauses until it is polled again. The stream
+    /// ends once the session is over, use [`Self::get_end_reason`] to learn
+    /// how. A session ending with [`EndReason::VerificationFailed`] leaves a
+    /// file partially delivered, which
+    /// [`ReadyToReceiveSubscriber::notify_discarded`] names.
+    pub fn chunks(
+        &self,
+        capacity: usize,
+    ) -> impl Stream<Item = ReadyToReceiveReceivingEvent> + Send + 'static {
+        self.handler.chunks(capacity)
+    }
+
     /// Remove a previously registered subscriber.
     pub fn unsubscribe(&self, subscriber: Arc<dyn ReadyToReceiveSubscriber>) {
         let subscriber_id = subscriber.get_id();
@@ -228,9 +311,10 @@ impl ReadyToReceiveBubble {
 ///
 /// The function:
 /// - Builds an iroh endpoint with discovery enabled.
-/// - Generates a random human-check confirmation code (0–99).
+/// - Generates a random human-check confirmation code (0–99), unless the
+///   config sets one.
 /// - Spawns a protocol router that accepts exactly one sender matching the
-///   confirmation code.
+///   confirmation code, among the allowed senders if any.
 /// - Returns the ticket and handle used to monitor or cancel the waiting.
 ///
 /// Errors if the endpoint fails to bind or the router cannot be spawned.
@@ -288,11 +372,8 @@ pub async fn ready_to_receive(
         request.config.chunk_size
     ));
 
-    handler.log(
-        "ready_to_receive: Creating endpoint builder with discovery_n0"
-            .to_string(),
-    );
-    let endpoint_builder = Endpoint::builder().discovery_n0();
+    handler.log("ready_to_receive: Creating endpoint builder".to_string());
+    let endpoint_builder = request.config.endpoint.builder()?;
 
     handler.log("ready_to_receive: Binding endpoint".to_string());
     let endpoint = endpoint_builder.bind().await?;
@@ -304,13 +385,20 @@ pub async fn ready_to_receive(
         "ready_to_receive: Node address initialized: {node_addr:?}"
     ));
 
-    handler.log(
-        "ready_to_receive: Generating random confirmation code".to_string(),
-    );
-    let confirmation: u8 = rand::rng().random_range(0..=99);
-    handler.log(format!(
-        "ready_to_receive: Generated confirmation code: {confirmation}"
-    ));
+    let confirmation: u8 = match request.config.confirmation {
+        Some(confirmation) => confirmation,
+        None => {
+            handler.log(
+                "ready_to_receive: Generating random confirmation code"
+                    .to_string(),
+            );
+            let confirmation = rand::rng().random_range(0..=99);
+            handler.log(format!(
+                "ready_to_receive: Generated confirmation code: {confirmation}"
+            ));
+            confirmation
+        }
+    };
 
     handler.log("ready_to_receive: Creating router with handler".to_string());
     let router = Router::builder(endpoint)
@@ -1,12 +1,17 @@
 use anyhow::Result;
 use arkdrop_entities::Profile;
 use arkdropx_common::{
+    ack::AckWriter,
     handshake::{
-        HandshakeConfig, HandshakeProfile, NegotiatedConfig, ReceiverHandshake,
+        Capabilities, HandshakeProfile, HashAlgorithm, ItemKind,
+        MAX_HANDSHAKE_LEN, NegotiatedConfig, ReceiverHandshake,
         SenderHandshake,
     },
-    projection::FileProjection,
+    projection::{CHECKSUM_MISMATCH_CODE, FileProjection},
+    rate::RateLimiter,
+    status::EndReason,
 };
+use futures::{Stream, channel::mpsc};
 use iroh::{
     Endpoint,
     endpoint::{
@@ -17,13 +22,19 @@ use iroh::{
 use iroh_base::ticket::NodeTicket;
 use std::{
     collections::HashMap,
+    iter,
     sync::{Arc, RwLock, atomic::AtomicBool},
+    time::{Duration, SystemTime},
 };
 use tokio::task::JoinSet;
+use tracing::{Instrument, Span, field};
 
 use uuid::Uuid;
 
-use super::{ReceiverConfig, ReceiverProfile};
+use super::{
+    Announced, ReceiverConfig, ReceiverProfile, check_total_size,
+    chunks::ChunkStreams, is_source_changed, throttle::throttle_receive_files,
+};
 
 /// Parameters required to start a receive session.
 ///
@@ -64,7 +75,11 @@ pub struct ReceiveFilesBubble {
     is_consumed: Arc<AtomicBool>,
     is_finished: Arc<AtomicBool>,
     is_cancelled: Arc<AtomicBool>,
+    end_reason: Arc<RwLock<Option<EndReason>>>,
+    files: Arc<RwLock<Vec<ReceiveFilesFile>>>,
     subscribers: Arc<RwLock<HashMap<String, Arc<dyn ReceiveFilesSubscriber>>>>,
+    chunks: Arc<ChunkStreams<ReceiveFilesReceivingEvent>>,
+    span: Span,
 }
 impl ReceiveFilesBubble {
     /// Create a new bubble with the provided endpoint and connection.
@@ -77,7 +92,16 @@ impl ReceiveFilesBubble {
         endpoint: Endpoint,
         connection: Connection,
     ) -> Self {
+        let span = tracing::info_span!(
+            "receive_files",
+            session_id = %profile.id,
+            peer_id = field::Empty,
+        );
+        if let Ok(peer_id) = connection.remote_node_id() {
+            span.record("peer_id", field::display(peer_id));
+        }
         Self {
+            span,
             profile,
             config,
             endpoint,
@@ -86,7 +110,10 @@ impl ReceiveFilesBubble {
             is_consumed: Arc::new(AtomicBool::new(false)),
             is_finished: Arc::new(AtomicBool::new(false)),
             is_cancelled: Arc::new(AtomicBool::new(false)),
+            end_reason: Arc::new(RwLock::new(None)),
+            files: Arc::new(RwLock::new(Vec::new())),
             subscribers: Arc::new(RwLock::new(HashMap::new())),
+            chunks: Arc::new(ChunkStreams::new()),
         }
     }
 
@@ -121,36 +148,50 @@ impl ReceiveFilesBubble {
             profile: self.profile.clone(),
             config: self.config.clone(),
             negotiated_config: None,
+            announced: Arc::default(),
             endpoint: self.endpoint.clone(),
             connection: self.connection.clone(),
             is_running: self.is_running.clone(),
             is_finished: self.is_finished.clone(),
             is_cancelled: self.is_cancelled.clone(),
+            end_reason: self.end_reason.clone(),
+            files: self.files.clone(),
             subscribers: self.subscribers.clone(),
+            chunks: self.chunks.clone(),
+            span: self.span.clone(),
         };
 
         self.log("start: Spawning async task for file reception".to_string());
-        tokio::spawn(async move {
+        let task = async move {
             let mut carrier = carrier;
             if let Err(e) = carrier.greet().await {
                 carrier.log(format!("start: Handshake failed: {e}"));
+                carrier.end(EndReason::of(&e));
+                carrier.notify_finished();
                 return;
             }
 
-            let result = carrier.receive_files().await;
-            if let Err(e) = result {
-                carrier.log(format!("start: File reception failed: {e}"));
-            } else {
-                carrier.log(
-                    "start: File reception completed successfully".to_string(),
-                );
-            }
+            let reason = match carrier.receive_files().await {
+                Ok(()) => {
+                    carrier.log(
+                        "start: File reception completed successfully"
+                            .to_string(),
+                    );
+                    EndReason::Completed
+                }
+                Err(e) => {
+                    carrier.log(format!("start: File reception failed: {e}"));
+                    EndReason::of(&e)
+                }
+            };
 
-            carrier.finish().await;
+            carrier.finish(reason).await;
             carrier
                 .is_running
                 .store(false, std::sync::atomic::Ordering::Relaxed);
-        });
+            carrier.notify_finished();
+        };
+        tokio::spawn(task.instrument(self.span.clone()));
 
         Ok(())
     }
@@ -161,6 +202,12 @@ impl ReceiveFilesBubble {
     /// Cancellation closes the connection with an application code and stops
     /// further processing.
     pub fn cancel(&self) {
+        self.cancel_with(EndReason::Cancelled);
+    }
+
+    /// Request cancellation, telling the sender the given reason, e.g.
+    /// [`EndReason::DiskFull`] when the files can't be written anymore.
+    pub fn cancel_with(&self, reason: EndReason) {
         self.log("cancel: Checking if transfer can be cancelled".to_string());
 
         if !self.is_running() || self.is_finished() {
@@ -172,7 +219,8 @@ impl ReceiveFilesBubble {
             return;
         }
 
-        self.log("cancel: Setting cancelled flag to true".to_string());
+        self.log(format!("cancel: Setting cancelled flag, reason: {reason}"));
+        *self.end_reason.write().unwrap() = Some(reason);
         self.is_cancelled
             .store(true, std::sync::atomic::Ordering::Relaxed);
 
@@ -207,11 +255,28 @@ impl ReceiveFilesBubble {
         cancelled
     }
 
+    /// Returns why the session ended, also told to the peer.
+    ///
+    /// `None` while the session runs, unless a cancellation was requested.
+    pub fn get_end_reason(&self) -> Option<EndReason> {
+        *self.end_reason.read().unwrap()
+    }
+
+    /// Returns the files announced by the sender, with their sizes.
+    ///
+    /// Empty until the handshake completed, see
+    /// [`ReceiveFilesSubscriber::notify_connecting`]. Chunk events only carry
+    /// the file ID, this is where to look up the total size of a file.
+    pub fn get_files(&self) -> Vec<ReceiveFilesFile> {
+        self.files.read().unwrap().clone()
+    }
+
     /// Register a subscriber to receive log and progress events.
     ///
     /// If a subscriber with the same ID is already present, it will be
     /// replaced.
     pub fn subscribe(&self, subscriber: Arc<dyn ReceiveFilesSubscriber>) {
+        let subscriber = throttle_receive_files(subscriber, &self.get_files());
         let subscriber_id = subscriber.get_id();
         self.log(format!(
             "subscribe: Subscribing new subscriber with ID: {subscriber_id}"
@@ -225,6 +290,52 @@ impl ReceiveFilesBubble {
         self.log(format!("subscribe: Subscriber {subscriber_id} successfully subscribed. Total subscribers: {}", self.subscribers.read().unwrap().len()));
     }
 
+    /// Stream of session events, an alternative to [`Self::subscribe`] for
+    /// async front-ends.
+    ///
+    /// Call it before [`Self::start`] to not miss the connecting event. The
+    /// stream yields [`ReceiveFilesEvent::Finished`] as its last item once
+    /// the session is over. Events are buffered until they are polled, so
+    /// the stream should be drained continuously.
+    pub fn events(
+        &self,
+    ) -> impl Stream<Item = ReceiveFilesEvent> + Send + 'static {
+        let (sender, receiver) = mpsc::unbounded();
+        let forwarder = Arc::new(EventForwarder {
+            id: Uuid::new_v4().to_string(),
+            sender,
+            is_ended: AtomicBool::new(false),
+        });
+        // Subscribe before checking, a session finishing in between would
+        // otherwise never end the stream. Events sent twice are ignored.
+        self.subscribe(forwarder.clone());
+        if self.is_finished() {
+            if let Some(reason) = self.get_end_reason() {
+                forwarder.notify_ended(reason);
+            }
+            forwarder.notify_finished();
+            self.unsubscribe(forwarder);
+        }
+        receiver
+    }
+
+    /// Stream of received chunks which applies backpressure, an alternative
+    /// to [`ReceiveFilesSubscriber::notify_receiving`] for consumers doing
+    /// slow work like disk writes.
+    ///
+    /// At most `capacity` chunks are buffered; while the stream is full,
+    /// reading from the sender pauses until it is polled again. Call it
+    /// before [`Self::start`] to not miss chunks. The stream ends once the
+    /// session is over, use [`Self::get_end_reason`] to learn how. A session
+    /// ending with [`EndReason::VerificationFailed`] leaves a file partially
+    /// delivered, which [`ReceiveFilesSubscriber::notify_discarded`] names.
+    pub fn chunks(
+        &self,
+        capacity: usize,
+    ) -> impl Stream<Item = ReceiveFilesReceivingEvent> + Send + 'static {
+        self.chunks.open(capacity)
+    }
+
     /// Remove a previously registered subscriber.
     pub fn unsubscribe(&self, subscriber: Arc<dyn ReceiveFilesSubscriber>) {
         let subscriber_id = subscriber.get_id();
@@ -246,6 +357,7 @@ impl ReceiveFilesBubble {
     }
 
     fn log(&self, message: String) {
+        tracing::debug!(parent: &self.span, "{message}");
         self.subscribers.read().unwrap().iter().for_each(
             |(_id, subscriber)| {
                 subscriber.log(message.clone());
@@ -258,12 +370,18 @@ struct Carrier {
     profile: Profile,
     config: ReceiverConfig,
     negotiated_config: Option<NegotiatedConfig>,
+    /// Files announced by the sender, with the copies it skips.
+    announced: Arc<Announced>,
     endpoint: Endpoint,
     connection: Connection,
     is_running: Arc<AtomicBool>,
     is_finished: Arc<AtomicBool>,
     is_cancelled: Arc<AtomicBool>,
+    end_reason: Arc<RwLock<Option<EndReason>>>,
+    files: Arc<RwLock<Vec<ReceiveFilesFile>>>,
     subscribers: Arc<RwLock<HashMap<String, Arc<dyn ReceiveFilesSubscriber>>>>,
+    chunks: Arc<ChunkStreams<ReceiveFilesReceivingEvent>>,
+    span: Span,
 }
 impl Carrier {
     /// Perform the bidirectional handshake with the sender:
@@ -295,10 +413,7 @@ impl Carrier {
                 name: self.profile.name.clone(),
                 avatar_b64: self.profile.avatar_b64.clone(),
             },
-            config: HandshakeConfig {
-                chunk_size: self.config.chunk_size,
-                parallel_streams: self.config.parallel_streams,
-            },
+            config: self.config.handshake_config(),
         };
 
         let mut buffer = Vec::with_capacity(256);
@@ -324,29 +439,54 @@ impl Carrier {
     ) -> Result<()> {
         let mut header = [0u8; 4];
         bi.1.read_exact(&mut header).await?;
-        let len = u32::from_be_bytes(header);
+        let len = u32::from_be_bytes(header) as usize;
+        if len > MAX_HANDSHAKE_LEN {
+            return Err(anyhow::Error::msg(format!(
+                "Handshake of {len} bytes exceeds the limit of {MAX_HANDSHAKE_LEN} bytes"
+            )));
+        }
 
-        let mut buffer = vec![0u8; len as usize];
+        let mut buffer = vec![0u8; len];
         bi.1.read_exact(&mut buffer).await?;
 
-        let handshake: SenderHandshake = serde_json::from_slice(&buffer)?;
+        let mut handshake: SenderHandshake = serde_json::from_slice(&buffer)?;
+        if let Some(e) = handshake
+            .profile
+            .strip_invalid_avatar(self.config.max_avatar_size)
+        {
+            self.log(format!("receive_handshake: Dropped peer avatar: {e}"));
+        }
+        // Refused before subscribers get to prepare for the files
+        check_total_size(&handshake.files, self.config.max_total_size)?;
 
         // Negotiate configuration
-        let receiver_config = HandshakeConfig {
-            chunk_size: self.config.chunk_size,
-            parallel_streams: self.config.parallel_streams,
-        };
+        let receiver_config = self.config.handshake_config();
 
-        self.negotiated_config = Some(NegotiatedConfig::negotiate(
-            &handshake.config,
-            &receiver_config,
+        let negotiated_config =
+            NegotiatedConfig::negotiate(&handshake.config, &receiver_config);
+        self.log(format!(
+            "receive_handshake: Negotiated capabilities: [{}]",
+            negotiated_config.capabilities
         ));
+        let deduplicate = negotiated_config
+            .capabilities
+            .contains(Capabilities::DEDUPLICATION);
+        let verify =
+            negotiated_config.hash_algorithm == Some(HashAlgorithm::Blake3);
+        self.announced =
+            Arc::new(Announced::new(&handshake, deduplicate, verify)?);
+        self.negotiated_config = Some(negotiated_config);
 
         // Prepare data structures once
         let profile = ReceiveFilesProfile {
             id: handshake.profile.id,
             name: handshake.profile.name,
             avatar_b64: handshake.profile.avatar_b64,
+            node_id: self
+                .connection
+                .remote_node_id()
+                .ok()
+                .map(|id| id.to_string()),
         };
 
         let files: Vec<ReceiveFilesFile> = handshake
@@ -356,8 +496,10 @@ impl Carrier {
                 id: f.id,
                 len: f.len,
                 name: f.name,
+                kind: f.kind,
             })
             .collect();
+        *self.files.write().unwrap() = files.clone();
 
         let event = ReceiveFilesConnectingEvent {
             sender: profile,
@@ -376,6 +518,27 @@ impl Carrier {
         Ok(())
     }
 
+    /// Opens the stream acknowledging the files received in full, if the
+    /// sender asked for acknowledgements.
+    async fn open_acks(&self) -> Result<Option<Arc<AckWriter>>> {
+        let is_negotiated = self.negotiated_config.as_ref().is_some_and(|c| {
+            c.capabilities
+                .contains(Capabilities::ACKNOWLEDGEMENTS)
+        });
+        if !is_negotiated {
+            return Ok(None);
+        }
+        let lens = self
+            .files
+            .read()
+            .unwrap()
+            .iter()
+            .map(|f| (f.id.clone(), f.len))
+            .collect();
+        let acks = AckWriter::open(&self.connection, lens).await?;
+        Ok(Some(Arc::new(acks)))
+    }
+
     /// Receive file projections over one or more unidirectional streams.
     ///
     /// - Spawns up to `parallel_streams` tasks to process incoming streams.
@@ -388,6 +551,8 @@ impl Carrier {
             } else {
                 (self.config.chunk_size, self.config.parallel_streams)
             };
+        let rate = RateLimiter::from_rate(self.config.max_rate).map(Arc::new);
+        let acks = self.open_acks().await?;
 
         let expected_close =
             ConnectionError::ApplicationClosed(ApplicationClose {
@@ -399,20 +564,41 @@ impl Carrier {
 
         'files_iterator: loop {
             if self.is_cancelled() {
-                self.connection
-                    .close(VarInt::from_u32(0), b"cancelled");
-                return Err(anyhow::Error::msg(
-                    "Receive files has been cancelled.",
-                ));
+                let reason = self
+                    .end_reason
+                    .read()
+                    .unwrap()
+                    .unwrap_or(EndReason::Cancelled);
+                return Err(anyhow::Error::new(reason)
+                    .context("Receive files has been cancelled."));
             }
 
             let connection = self.connection.clone();
             let subscribers = self.subscribers.clone();
-
-            join_set.spawn(async move {
-                Self::process_single_file(chunk_size, connection, subscribers)
-                    .await
-            });
+            let chunks = self.chunks.clone();
+            let announced = self.announced.clone();
+            let rate = rate.clone();
+            let acks = acks.clone();
+
+            // The file is only known once its first chunk arrives
+            let span = tracing::debug_span!(
+                parent: &self.span,
+                "receive_file",
+                file_id = field::Empty,
+            );
+            let task = async move {
+                Self::process_single_file(
+                    chunk_size,
+                    connection,
+                    subscribers,
+                    chunks,
+                    announced,
+                    rate,
+                    acks,
+                )
+                .await
+            };
+            join_set.spawn(task.instrument(span));
 
             // Clean up completed tasks periodically
             while join_set.len() >= parallel_streams as usize {
@@ -426,6 +612,13 @@ impl Carrier {
                     {
                         break 'files_iterator;
                     }
+                    if is_source_changed(&err) {
+                        self.log(
+                            "receive_files: Sender abandoned a changed file"
+                                .to_string(),
+                        );
+                        continue;
+                    }
                     return Err(err);
                 }
             }
@@ -440,6 +633,13 @@ impl Carrier {
             {
                 continue;
             }
+            if is_source_changed(&err) {
+                self.log(
+                    "receive_files: Sender abandoned a changed file"
+                        .to_string(),
+                );
+                continue;
+            }
             return Err(err);
         }
 
@@ -447,18 +647,25 @@ impl Carrier {
     }
 
     /// Process a single unidirectional stream and emit receiving events per
-    /// chunk.
+    /// chunk, waiting for room in the chunk streams.
     async fn process_single_file(
         chunk_size: u64,
         connection: Connection,
         subscribers: Arc<
             RwLock<HashMap<String, Arc<dyn ReceiveFilesSubscriber>>>,
         >,
+        chunks: Arc<ChunkStreams<ReceiveFilesReceivingEvent>>,
+        announced: Arc<Announced>,
+        rate: Option<Arc<RateLimiter>>,
+        acks: Option<Arc<AckWriter>>,
     ) -> Result<()> {
         let mut uni = connection.accept_uni().await?;
 
         let mut buffer =
             Vec::with_capacity((chunk_size + 256 * 1024).try_into().unwrap());
+        let mut file_id = None;
+        let mut hasher = None;
+        let mut received = 0;
 
         loop {
             buffer.clear();
@@ -472,28 +679,158 @@ impl Carrier {
             buffer.resize(len, 0);
 
             uni.read_exact(&mut buffer).await?;
+            if let Some(rate) = &rate {
+                rate.acquire(len as u64).await;
+            }
 
             let projection: FileProjection = serde_json::from_slice(&buffer)?;
-
-            // Notify subscribers about received chunk
+            if !projection.verify() {
+                // The chunks delivered so far are an incomplete file
+                let ids: Vec<_> = iter::once(projection.id.clone())
+                    .chain(announced.copies(&projection.id))
+                    .collect();
+                Self::notify_discarded(&subscribers, &ids);
+                uni.stop(CHECKSUM_MISMATCH_CODE.into())?;
+                return Err(anyhow::Error::new(EndReason::VerificationFailed)
+                    .context(format!(
+                        "Checksum mismatch in a chunk of file {}",
+                        projection.id
+                    )));
+            }
+            if file_id.is_none() {
+                Span::current().record("file_id", projection.id.as_str());
+                // Hashed as it arrives, to be verified once complete
+                hasher = announced
+                    .has_hash(&projection.id)
+                    .then(blake3::Hasher::new);
+                file_id = Some(projection.id.clone());
+            }
+            if let Some(hasher) = &mut hasher {
+                hasher.update(&projection.data);
+            }
+            received += projection.data.len() as u64;
+            tracing::trace!(
+                len = projection.data.len(),
+                "process_single_file: Chunk received"
+            );
+
+            // Copies skipped by the sender get the same chunks
+            let copy_ids = announced.copies(&projection.id);
             let event = ReceiveFilesReceivingEvent {
                 id: projection.id,
                 data: projection.data,
             };
+            let copy_events: Vec<_> = copy_ids
+                .into_iter()
+                .map(|id| ReceiveFilesReceivingEvent {
+                    id,
+                    data: event.data.clone(),
+                })
+                .collect();
+
+            // Notify subscribers about received chunk
+            for event in iter::once(event).chain(copy_events) {
+                subscribers
+                    .read()
+                    .unwrap()
+                    .iter()
+                    .for_each(|(_, s)| {
+                        s.notify_receiving(event.clone());
+                    });
+                chunks.send(event).await;
+            }
+        }
+
+        // Copies are complete along with the file they are a copy of
+        let Some(id) = file_id else {
+            return Ok(());
+        };
+        let ids: Vec<_> = iter::once(id.clone())
+            .chain(announced.copies(&id))
+            .collect();
+        if let Some(hasher) = hasher
+            && !announced.matches_hash(&id, &hasher.finalize())
+        {
+            Self::notify_discarded(&subscribers, &ids);
+            return Err(anyhow::Error::new(EndReason::VerificationFailed)
+                .context(format!("Content hash mismatch of file {id}")));
+        }
+        let stored = Self::notify_received(
+            subscribers,
+            announced,
+            ids.clone(),
+            received,
+        )
+        .await?;
+        if stored.len() < ids.len() {
+            tracing::debug!("process_single_file: File not stored");
+        }
+        // Only files stored for good, the sender may delete the sources
+        if let Some(acks) = &acks
+            && !acks.acknowledge(&stored, received).await?
+        {
+            tracing::debug!(received, "process_single_file: File incomplete");
+        }
+
+        // sleep(Duration::from_secs(1)).await;
+        // uni.stop(VarInt::from_u32(0))?;
 
+        Ok(())
+    }
+
+    /// Tells the subscribers the files of `ids` were received, on a blocking
+    /// thread as they may wait for the disk, and returns those every
+    /// subscriber stored.
+    async fn notify_received(
+        subscribers: Arc<
+            RwLock<HashMap<String, Arc<dyn ReceiveFilesSubscriber>>>,
+        >,
+        announced: Arc<Announced>,
+        ids: Vec<String>,
+        len: u64,
+    ) -> Result<Vec<String>> {
+        let stored = tokio::task::spawn_blocking(move || {
+            let subscribers: Vec<_> = subscribers
+                .read()
+                .unwrap()
+                .values()
+                .cloned()
+                .collect();
+            ids.into_iter()
+                .filter(|id| {
+                    // Every subscriber is told, even once one didn't store it
+                    subscribers.iter().fold(true, |stored, s| {
+                        let event = ReceiveFilesReceivedEvent {
+                            id: id.clone(),
+                            len,
+                            modified: announced.modified(id),
+                        };
+                        s.notify_received(event) && stored
+                    })
+                })
+                .collect()
+        })
+        .await?;
+        Ok(stored)
+    }
+
+    /// Tells the subscribers the data delivered for the files of `ids`
+    /// must be deleted.
+    fn notify_discarded(
+        subscribers: &RwLock<HashMap<String, Arc<dyn ReceiveFilesSubscriber>>>,
+        ids: &[String],
+    ) {
+        for id in ids {
             subscribers
                 .read()
                 .unwrap()
                 .iter()
                 .for_each(|(_, s)| {
-                    s.notify_receiving(event.clone());
+                    s.notify_discarded(ReceiveFilesDiscardedEvent {
+                        id: id.clone(),
+                    });
                 });
         }
-
-        // sleep(Duration::from_secs(1)).await;
-        // uni.stop(VarInt::from_u32(0))?;
-
-        Ok(())
     }
 
     /// Returns `true` if a cancellation has been requested.
@@ -506,6 +843,7 @@ impl Carrier {
     }
 
     fn log(&self, message: String) {
+        tracing::debug!(parent: &self.span, "{message}");
         self.subscribers
             .read()
             .unwrap()
@@ -515,18 +853,48 @@ impl Carrier {
             });
     }
 
-    /// Mark the session finished, close the connection with an application
-    /// code, and close the endpoint.
-    async fn finish(&self) {
+    /// Record why the session ended, keeping the reason of a requested
+    /// cancellation, close the connection with it, and tell subscribers.
+    fn end(&self, reason: EndReason) {
+        let reason = *self
+            .end_reason
+            .write()
+            .unwrap()
+            .get_or_insert(reason);
+        self.log(format!("end: Closing connection, reason: {reason}"));
+        reason.close(&self.connection);
+
+        self.subscribers
+            .read()
+            .unwrap()
+            .iter()
+            .for_each(|(_, subscriber)| {
+                subscriber.notify_ended(reason);
+            });
+    }
+
+    /// Tell subscribers that no more events will follow, and end the chunk
+    /// streams.
+    fn notify_finished(&self) {
+        self.chunks.close();
+        self.subscribers
+            .read()
+            .unwrap()
+            .iter()
+            .for_each(|(_, subscriber)| {
+                subscriber.notify_finished();
+            });
+    }
+
+    /// Mark the session finished, close the connection with the code of the
+    /// reason it ended with, and close the endpoint.
+    async fn finish(&self, reason: EndReason) {
         self.log("finish: Starting transfer finish process".to_string());
+        self.end(reason);
 
         self.is_finished
             .store(true, std::sync::atomic::Ordering::Relaxed);
 
-        self.log("finish: Closing connection".to_string());
-        self.connection
-            .close(VarInt::from_u32(200), "finished".as_bytes());
-
         self.log("finish: Closing endpoint".to_string());
         self.endpoint.close().await;
 
@@ -569,13 +937,110 @@ impl Carrier {
 pub trait ReceiveFilesSubscriber: Send + Sync {
     /// Stable identifier for this subscriber (used as a map key).
     fn get_id(&self) -> String;
-    /// Receive diagnostic log messages from the session.
+    /// Receive diagnostic log messages from the session, also emitted as
+    /// `tracing` debug events inside the `receive_files` span.
     fn log(&self, message: String);
     /// Receive a per-chunk event with the file ID and raw bytes of that chunk.
     fn notify_receiving(&self, event: ReceiveFilesReceivingEvent);
     /// Receive a connection event containing the sender profile and all files
     /// to be transferred.
     fn notify_connecting(&self, event: ReceiveFilesConnectingEvent);
+    /// Called when a chunk of the file failed verification, before the
+    /// session ends with [`EndReason::VerificationFailed`]. The chunks
+    /// delivered for the file don't make up the file and must be deleted.
+    /// Copies of the file are discarded along with it.
+    fn notify_discarded(&self, _event: ReceiveFilesDiscardedEvent) {}
+    /// Called once the sender finished sending a file, or a copy, and all
+    /// of its chunks were delivered. Returns whether the file is stored for
+    /// good, e.g. synced to disk; the sender may delete its source once
+    /// every subscriber did, so this may wait for the disk. Subscribers not
+    /// storing files keep the default.
+    fn notify_received(&self, _event: ReceiveFilesReceivedEvent) -> bool {
+        true
+    }
+    /// Called once with the reason the session ended, told to the peer or
+    /// received from it, right before [`Self::notify_finished`].
+    fn notify_ended(&self, _reason: EndReason) {}
+    /// Called once when the session is over, successfully or not.
+    fn notify_finished(&self) {}
+    /// Minimum time between two receiving events of a file, or `None` for
+    /// an event per chunk. Chunks received in between are merged into one
+    /// event, so no data is lost; the last chunk of a file is never held
+    /// back.
+    fn progress_interval(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Item of the stream returned by [`ReceiveFilesBubble::events`].
+#[derive(Clone)]
+pub enum ReceiveFilesEvent {
+    /// Handshake completed, see [`ReceiveFilesSubscriber::notify_connecting`].
+    Connecting(ReceiveFilesConnectingEvent),
+    /// Chunk received, see [`ReceiveFilesSubscriber::notify_receiving`].
+    Receiving(ReceiveFilesReceivingEvent),
+    /// File failed verification, see
+    /// [`ReceiveFilesSubscriber::notify_discarded`].
+    Discarded(ReceiveFilesDiscardedEvent),
+    /// Why the session ended, see [`ReceiveFilesSubscriber::notify_ended`].
+    Ended(EndReason),
+    /// Session is over, this is the last item of the stream.
+    Finished,
+}
+
+/// Subscriber forwarding events into the stream of
+/// [`ReceiveFilesBubble::events`].
+struct EventForwarder {
+    id: String,
+    sender: mpsc::UnboundedSender<ReceiveFilesEvent>,
+    is_ended: AtomicBool,
+}
+
+impl ReceiveFilesSubscriber for EventForwarder {
+    fn get_id(&self) -> String {
+        self.id.clone()
+    }
+
+    fn log(&self, _message: String) {}
+
+    fn notify_receiving(&self, event: ReceiveFilesReceivingEvent) {
+        // Sending fails only if the stream was dropped
+        let _ = self
+            .sender
+            .unbounded_send(ReceiveFilesEvent::Receiving(event));
+    }
+
+    fn notify_connecting(&self, event: ReceiveFilesConnectingEvent) {
+        let _ = self
+            .sender
+            .unbounded_send(ReceiveFilesEvent::Connecting(event));
+    }
+
+    fn notify_discarded(&self, event: ReceiveFilesDiscardedEvent) {
+        let _ = self
+            .sender
+            .unbounded_send(ReceiveFilesEvent::Discarded(event));
+    }
+
+    fn notify_ended(&self, reason: EndReason) {
+        if self
+            .is_ended
+            .swap(true, std::sync::atomic::Ordering::AcqRel)
+        {
+            return;
+        }
+        let _ = self
+            .sender
+            .unbounded_send(ReceiveFilesEvent::Ended(reason));
+    }
+
+    fn notify_finished(&self) {
+        // Sending after the channel was closed is a no-op
+        let _ = self
+            .sender
+            .unbounded_send(ReceiveFilesEvent::Finished);
+        self.sender.close_channel();
+    }
 }
 
 /// Event published for each received projection chunk.
@@ -587,6 +1052,25 @@ pub struct ReceiveFilesReceivingEvent {
     pub data: Vec<u8>,
 }
 
+/// Event published when the data received for a file must be discarded.
+#[derive(Clone)]
+pub struct ReceiveFilesDiscardedEvent {
+    /// Sender-provided identifier of the file.
+    pub id: String,
+}
+
+/// Event published once all chunks of a file were delivered.
+#[derive(Clone)]
+pub struct ReceiveFilesReceivedEvent {
+    /// Sender-provided identifier of the file.
+    pub id: String,
+    /// Number of bytes delivered for the file.
+    pub len: u64,
+    /// Modification time of the source announced by the sender, to apply
+    /// to the stored file.
+    pub modified: Option<SystemTime>,
+}
+
 /// Event published once after handshake with sender profile and files list.
 #[derive(Clone)]
 pub struct ReceiveFilesConnectingEvent {
@@ -605,6 +1089,9 @@ pub struct ReceiveFilesProfile {
     pub name: String,
     /// Optional Base64-encoded avatar image.
     pub avatar_b64: Option<String>,
+    /// Node id of the sender's endpoint. Unlike `id`, it is stable across
+    /// sessions when the sender persists its secret key.
+    pub node_id: Option<String>,
 }
 
 /// Description of a single file to be transferred, as announced by the sender.
@@ -616,6 +1103,9 @@ pub struct ReceiveFilesFile {
     pub name: String,
     /// Total file size in bytes.
     pub len: u64,
+    /// What the file holds, e.g. the serialized form of a
+    /// [`LinkItem`](crate::LinkItem).
+    pub kind: ItemKind,
 }
 
 /// Initialize a receive session and return a controllable bubble.
@@ -670,15 +1160,30 @@ pub async fn receive_files(
 ) -> Result<ReceiveFilesBubble> {
     let ticket: NodeTicket = request.ticket.parse()?;
 
-    let endpoint_builder = Endpoint::builder().discovery_n0();
-
-    let endpoint = endpoint_builder.bind().await?;
-    let connection = endpoint
-        .connect(ticket, &[request.confirmation])
-        .await?;
-
     let config = request.config.unwrap_or_default();
 
+    let endpoint = config.endpoint.bind().await?;
+    let confirmation = request.confirmation;
+    let connection =
+        config
+            .retry
+            .run(
+                || {
+                    let endpoint = endpoint.clone();
+                    let ticket = ticket.clone();
+                    async move {
+                        Ok(endpoint.connect(ticket, &[confirmation]).await?)
+                    }
+                },
+                |attempt, err| {
+                    tracing::debug!(
+                        attempt,
+                        "receive_files: Retrying to connect: {err}"
+                    );
+                },
+            )
+            .await?;
+
     Ok(ReceiveFilesBubble::new(
         Profile {
             id: Uuid::new_v4().to_string(),
@@ -0,0 +1,911 @@
+//! Loopback tests running full transfers between a sender and a receiver in
+//! the same process.
+//!
+//! Both peers bind endpoints with relays and discovery disabled, so they only
+//! talk over the local interfaces using the direct addresses in the ticket.
+//! No external networking is involved, which keeps the tests deterministic
+//! enough for CI.
+
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use anyhow::{Result, anyhow};
+use arkdropx_sender::{
+    Faults, ItemKind, SendFilesConnectingEvent, SendFilesReceivedEvent,
+    SendFilesRequest, SendFilesSendingEvent, SendFilesSubscriber, SenderConfig,
+    SenderFile, SenderFileData, SenderProfile, send_files,
+    send_files_to::{SendFilesToRequest, send_files_to},
+};
+use futures::StreamExt;
+
+use crate::{
+    EndReason, EndpointConfig, LinkItem, ReceiveFilesConnectingEvent,
+    ReceiveFilesEvent, ReceiveFilesReceivedEvent, ReceiveFilesReceivingEvent,
+    ReceiveFilesRequest, ReceiveFilesSubscriber, ReceiverConfig,
+    ReceiverProfile, RelayConfig,
+    ready_to_receive::{
+        ReadyToReceiveConfig, ReadyToReceiveConnectingEvent,
+        ReadyToReceiveReceivingEvent, ReadyToReceiveRequest,
+        ReadyToReceiveSubscriber, ready_to_receive,
+    },
+    receive_files,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// In-memory file contents served chunk by chunk.
+struct MemoryData {
+    data: Vec<u8>,
+    offset: AtomicUsize,
+    /// Reports the contents as changed once this many bytes were read.
+    changes_at: Option<usize>,
+}
+
+impl SenderFileData for MemoryData {
+    fn len(&self) -> u64 {
+        self.data.len() as u64
+    }
+
+    fn read(&self) -> Option<u8> {
+        let offset = self.offset.fetch_add(1, Ordering::Relaxed);
+        self.data.get(offset).copied()
+    }
+
+    fn read_chunk(&self, size: u64) -> Vec<u8> {
+        let start = self
+            .offset
+            .fetch_add(size as usize, Ordering::Relaxed)
+            .min(self.data.len());
+        let end = (start + size as usize).min(self.data.len());
+        self.data[start..end].to_vec()
+    }
+
+    fn has_changed(&self) -> bool {
+        self.changes_at
+            .is_some_and(|at| self.offset.load(Ordering::Relaxed) >= at)
+    }
+
+    fn content_id(&self) -> Option<String> {
+        let mut hasher = DefaultHasher::new();
+        self.data.hash(&mut hasher);
+        Some(format!("{:016x}", hasher.finish()))
+    }
+
+    fn content_hash(&self) -> Option<String> {
+        Some(blake3::hash(&self.data).to_hex().to_string())
+    }
+}
+
+/// In-memory file contents announced with the hash of other contents.
+struct MislabeledData(MemoryData);
+
+impl SenderFileData for MislabeledData {
+    fn len(&self) -> u64 {
+        self.0.len()
+    }
+
+    fn read(&self) -> Option<u8> {
+        self.0.read()
+    }
+
+    fn read_chunk(&self, size: u64) -> Vec<u8> {
+        self.0.read_chunk(size)
+    }
+
+    fn content_hash(&self) -> Option<String> {
+        Some(
+            blake3::hash(b"other contents")
+                .to_hex()
+                .to_string(),
+        )
+    }
+}
+
+fn local_endpoint() -> EndpointConfig {
+    EndpointConfig {
+        relay: RelayConfig::Disabled,
+        disable_discovery: true,
+        ..Default::default()
+    }
+}
+
+fn sender_files(files: &[(&str, Vec<u8>)]) -> Vec<SenderFile> {
+    files
+        .iter()
+        .map(|(name, data)| SenderFile {
+            name: name.to_string(),
+            data: Arc::new(MemoryData {
+                data: data.clone(),
+                offset: AtomicUsize::new(0),
+                changes_at: None,
+            }),
+            kind: ItemKind::File,
+        })
+        .collect()
+}
+
+fn sender_profile() -> SenderProfile {
+    SenderProfile {
+        name: "sender".to_string(),
+        avatar_b64: None,
+    }
+}
+
+fn receiver_profile() -> ReceiverProfile {
+    ReceiverProfile {
+        name: "receiver".to_string(),
+        avatar_b64: None,
+    }
+}
+
+/// Contents of the received files by name.
+#[derive(Default)]
+struct Received {
+    names: HashMap<String, String>,
+    data: HashMap<String, Vec<u8>>,
+}
+
+impl Received {
+    fn connect(&mut self, files: impl IntoIterator<Item = (String, String)>) {
+        self.names.extend(files);
+    }
+
+    fn append(&mut self, id: &str, chunk: &[u8]) {
+        self.data
+            .entry(id.to_string())
+            .or_default()
+            .extend_from_slice(chunk);
+    }
+
+    fn discard(&mut self, id: &str) {
+        self.data.remove(id);
+    }
+
+    fn by_name(mut self) -> HashMap<String, Vec<u8>> {
+        self.names
+            .into_iter()
+            .map(|(id, name)| (name, self.data.remove(&id).unwrap_or_default()))
+            .collect()
+    }
+}
+
+/// Sends the files with `send_files` and receives them with
+/// `receive_files`, using the given chunk size on both sides.
+async fn send_and_receive(
+    files: &[(&str, Vec<u8>)],
+    chunk_size: u64,
+    faults: Faults,
+) -> Result<HashMap<String, Vec<u8>>> {
+    let (received, _) =
+        transfer(sender_files(files), chunk_size, faults).await?;
+    Ok(received)
+}
+
+/// Like [`send_and_receive`], with files of any data source. Also returns
+/// the reason the receiver reported for the end of the session.
+async fn transfer(
+    files: Vec<SenderFile>,
+    chunk_size: u64,
+    faults: Faults,
+) -> Result<(HashMap<String, Vec<u8>>, Option<EndReason>)> {
+    let sending = send_files(SendFilesRequest {
+        profile: sender_profile(),
+        files,
+        config: SenderConfig {
+            chunk_size,
+            endpoint: local_endpoint(),
+            faults,
+            ..Default::default()
+        },
+    })
+    .await?;
+
+    let receiving = receive_files(ReceiveFilesRequest {
+        ticket: sending.get_ticket(),
+        confirmation: sending.get_confirmation(),
+        profile: receiver_profile(),
+        config: Some(ReceiverConfig {
+            chunk_size,
+            endpoint: local_endpoint(),
+            ..Default::default()
+        }),
+    })
+    .await?;
+
+    let mut events = receiving.events();
+    receiving.start()?;
+
+    let mut received = Received::default();
+    let mut reason = None;
+    let collect = async {
+        while let Some(event) = events.next().await {
+            match event {
+                ReceiveFilesEvent::Connecting(event) => received
+                    .connect(event.files.into_iter().map(|f| (f.id, f.name))),
+                ReceiveFilesEvent::Receiving(event) => {
+                    received.append(&event.id, &event.data)
+                }
+                ReceiveFilesEvent::Discarded(event) => {
+                    received.discard(&event.id)
+                }
+                ReceiveFilesEvent::Ended(ended) => reason = Some(ended),
+                ReceiveFilesEvent::Finished => break,
+            }
+        }
+    };
+    tokio::time::timeout(TIMEOUT, collect)
+        .await
+        .map_err(|_| anyhow!("Transfer timed out"))?;
+
+    Ok((received.by_name(), reason))
+}
+
+/// Subscriber collecting the files of a `ready_to_receive` session.
+#[derive(Default)]
+struct Collector {
+    received: Mutex<Received>,
+    /// Progress interval the subscriber asks for.
+    interval: Option<Duration>,
+    /// Receiving events delivered.
+    events: AtomicUsize,
+}
+
+impl ReadyToReceiveSubscriber for Collector {
+    fn get_id(&self) -> String {
+        "collector".to_string()
+    }
+
+    fn log(&self, _message: String) {}
+
+    fn notify_receiving(&self, event: ReadyToReceiveReceivingEvent) {
+        self.events.fetch_add(1, Ordering::Relaxed);
+        self.received
+            .lock()
+            .unwrap()
+            .append(&event.id, &event.data);
+    }
+
+    fn notify_connecting(&self, event: ReadyToReceiveConnectingEvent) {
+        self.received
+            .lock()
+            .unwrap()
+            .connect(event.files.into_iter().map(|f| (f.id, f.name)));
+    }
+
+    fn progress_interval(&self) -> Option<Duration> {
+        self.interval
+    }
+}
+
+/// Sends the files with `send_files_to` to a `ready_to_receive` session
+/// reporting to `collector`.
+async fn send_to_waiting(
+    files: &[(&str, Vec<u8>)],
+    chunk_size: u64,
+    collector: Arc<Collector>,
+) -> Result<HashMap<String, Vec<u8>>> {
+    let waiting = ready_to_receive(ReadyToReceiveRequest {
+        profile: receiver_profile(),
+        config: ReadyToReceiveConfig {
+            chunk_size,
+            endpoint: local_endpoint(),
+            ..Default::default()
+        },
+    })
+    .await?;
+    waiting.subscribe(collector.clone());
+
+    let sending = send_files_to(SendFilesToRequest {
+        ticket: waiting.get_ticket(),
+        confirmation: waiting.get_confirmation(),
+        profile: sender_profile(),
+        files: sender_files(files),
+        config: SenderConfig {
+            chunk_size,
+            endpoint: local_endpoint(),
+            ..Default::default()
+        },
+    })
+    .await?;
+    sending.start()?;
+
+    let wait = async {
+        while !waiting.is_finished() {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    };
+    tokio::time::timeout(TIMEOUT, wait)
+        .await
+        .map_err(|_| anyhow!("Transfer timed out"))?;
+
+    let received = std::mem::take(&mut *collector.received.lock().unwrap());
+    Ok(received.by_name())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn transfers_single_file() -> Result<()> {
+    let data: Vec<u8> = (0..=255).cycle().take(300_000).collect();
+
+    let (received, reason) = transfer(
+        sender_files(&[("a.bin", data.clone())]),
+        64 * 1024,
+        Faults::default(),
+    )
+    .await?;
+
+    assert_eq!(received.len(), 1);
+    assert_eq!(received["a.bin"], data);
+    assert_eq!(reason, Some(EndReason::Completed));
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn transfers_files_in_parallel_with_small_chunks() -> Result<()> {
+    let files: Vec<(String, Vec<u8>)> = (0..6)
+        .map(|i| (format!("dir/{i}.txt"), vec![b'a' + i as u8; 5000 + i]))
+        .collect();
+    let files: Vec<(&str, Vec<u8>)> = files
+        .iter()
+        .map(|(name, data)| (name.as_str(), data.clone()))
+        .collect();
+
+    let received = send_and_receive(&files, 1024, Faults::default()).await?;
+
+    assert_eq!(received.len(), files.len());
+    for (name, data) in &files {
+        assert_eq!(&received[*name], data, "contents of {name}");
+    }
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn streams_chunks_to_slow_consumer() -> Result<()> {
+    let data: Vec<u8> = (0..=255).cycle().take(50_000).collect();
+
+    let sending = send_files(SendFilesRequest {
+        profile: sender_profile(),
+        files: sender_files(&[("slow.bin", data.clone())]),
+        config: SenderConfig {
+            chunk_size: 1024,
+            endpoint: local_endpoint(),
+            ..Default::default()
+        },
+    })
+    .await?;
+    let receiving = receive_files(ReceiveFilesRequest {
+        ticket: sending.get_ticket(),
+        confirmation: sending.get_confirmation(),
+        profile: receiver_profile(),
+        config: Some(ReceiverConfig {
+            chunk_size: 1024,
+            endpoint: local_endpoint(),
+            ..Default::default()
+        }),
+    })
+    .await?;
+
+    let mut chunks = receiving.chunks(1);
+    receiving.start()?;
+
+    let mut received = Vec::new();
+    let collect = async {
+        while let Some(chunk) = chunks.next().await {
+            // A consumer slower than the network
+            tokio::time::sleep(Duration::from_millis(2)).await;
+            received.extend_from_slice(&chunk.data);
+        }
+    };
+    tokio::time::timeout(TIMEOUT, collect)
+        .await
+        .map_err(|_| anyhow!("Transfer timed out"))?;
+
+    assert_eq!(received, data);
+    assert_eq!(receiving.get_end_reason(), Some(EndReason::Completed));
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn survives_delayed_chunks() -> Result<()> {
+    let data = vec![7u8; 10_000];
+    let faults = Faults {
+        chunk_delay: Some(Duration::from_millis(20)),
+        ..Default::default()
+    };
+
+    let received =
+        send_and_receive(&[("slow.bin", data.clone())], 1024, faults).await?;
+
+    assert_eq!(received["slow.bin"], data);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn stops_on_corrupted_frame() -> Result<()> {
+    let data = vec![1u8; 10_000];
+    let faults = Faults {
+        corrupt_chunk: Some(3),
+        ..Default::default()
+    };
+
+    let received =
+        send_and_receive(&[("bad.bin", data.clone())], 1024, faults).await?;
+
+    // Chunks before the corrupted one arrive, nothing after it
+    assert_eq!(received["bad.bin"], data[..3 * 1024]);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn stops_on_checksum_mismatch() -> Result<()> {
+    let data = vec![3u8; 10_000];
+    let faults = Faults {
+        tamper_chunk: Some(3),
+        ..Default::default()
+    };
+
+    let (received, reason) = transfer(
+        sender_files(&[("tampered.bin", data.clone())]),
+        1024,
+        faults,
+    )
+    .await?;
+
+    // The chunks delivered before the tampered one are discarded too
+    assert!(received["tampered.bin"].is_empty());
+    assert_eq!(reason, Some(EndReason::VerificationFailed));
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn discards_file_not_matching_its_hash() -> Result<()> {
+    let data = vec![8u8; 10_000];
+    let files = vec![SenderFile {
+        name: "mislabeled.bin".to_string(),
+        data: Arc::new(MislabeledData(MemoryData {
+            data: data.clone(),
+            offset: AtomicUsize::new(0),
+            changes_at: None,
+        })),
+        kind: ItemKind::File,
+    }];
+
+    let (received, reason) = transfer(files, 1024, Faults::default()).await?;
+
+    // Every chunk passed its checksum, the whole file didn't
+    assert!(received["mislabeled.bin"].is_empty());
+    assert_eq!(reason, Some(EndReason::VerificationFailed));
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn abandons_file_changed_during_send() -> Result<()> {
+    let stable = vec![4u8; 5_000];
+    let edited = vec![5u8; 64 * 1024];
+    let mut files = sender_files(&[("stable.bin", stable.clone())]);
+    files.push(SenderFile {
+        name: "edited.bin".to_string(),
+        data: Arc::new(MemoryData {
+            data: edited.clone(),
+            offset: AtomicUsize::new(0),
+            changes_at: Some(20 * 1024),
+        }),
+        kind: ItemKind::File,
+    });
+
+    let (received, _) = transfer(files, 1024, Faults::default()).await?;
+
+    // The session goes on without the rest of the changed file
+    assert_eq!(received["stable.bin"], stable);
+    assert!(received["edited.bin"].len() < edited.len());
+    assert!(edited.starts_with(&received["edited.bin"]));
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sends_identical_files_once() -> Result<()> {
+    let photo = vec![6u8; 20_000];
+    let other = vec![7u8; 20_000];
+    let copy = Arc::new(MemoryData {
+        data: photo.clone(),
+        offset: AtomicUsize::new(0),
+        changes_at: None,
+    });
+    let mut files = sender_files(&[
+        ("photo.jpg", photo.clone()),
+        ("other.jpg", other.clone()),
+    ]);
+    files.push(SenderFile {
+        name: "copy of photo.jpg".to_string(),
+        data: copy.clone(),
+        kind: ItemKind::File,
+    });
+
+    let (received, reason) = transfer(files, 1024, Faults::default()).await?;
+
+    assert_eq!(received["photo.jpg"], photo);
+    assert_eq!(received["other.jpg"], other);
+    assert_eq!(received["copy of photo.jpg"], photo);
+    assert_eq!(reason, Some(EndReason::Completed));
+    // The copy was received from the chunks of the original
+    assert_eq!(copy.offset.load(Ordering::Relaxed), 0);
+    Ok(())
+}
+
+/// Sender subscriber recording the files the receiver acknowledged.
+#[derive(Default)]
+struct Acknowledged {
+    names: Mutex<Vec<String>>,
+}
+
+impl SendFilesSubscriber for Acknowledged {
+    fn get_id(&self) -> String {
+        "acknowledged".to_string()
+    }
+
+    fn log(&self, _message: String) {}
+
+    fn notify_sending(&self, _event: SendFilesSendingEvent) {}
+
+    fn notify_connecting(&self, _event: SendFilesConnectingEvent) {}
+
+    fn notify_received(&self, event: SendFilesReceivedEvent) {
+        self.names.lock().unwrap().push(event.name);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn acknowledges_files_received_in_full() -> Result<()> {
+    let sending = send_files(SendFilesRequest {
+        profile: sender_profile(),
+        files: sender_files(&[
+            ("first.bin", vec![1u8; 5_000]),
+            ("second.bin", vec![2u8; 3_000]),
+            ("empty.bin", Vec::new()),
+        ]),
+        config: SenderConfig {
+            chunk_size: 1024,
+            endpoint: local_endpoint(),
+            ..Default::default()
+        },
+    })
+    .await?;
+    let acknowledged = Arc::new(Acknowledged::default());
+    sending.subscribe(acknowledged.clone());
+
+    let receiving = receive_files(ReceiveFilesRequest {
+        ticket: sending.get_ticket(),
+        confirmation: sending.get_confirmation(),
+        profile: receiver_profile(),
+        config: Some(ReceiverConfig {
+            chunk_size: 1024,
+            endpoint: local_endpoint(),
+            ..Default::default()
+        }),
+    })
+    .await?;
+
+    let mut events = receiving.events();
+    receiving.start()?;
+
+    let mut reason = None;
+    let collect = async {
+        while let Some(event) = events.next().await {
+            match event {
+                ReceiveFilesEvent::Ended(ended) => reason = Some(ended),
+                ReceiveFilesEvent::Finished => break,
+                _ => {}
+            }
+        }
+    };
+    tokio::time::timeout(TIMEOUT, collect)
+        .await
+        .map_err(|_| anyhow!("Transfer timed out"))?;
+
+    // The sender only closes the session once all files were acknowledged
+    let mut names = acknowledged.names.lock().unwrap().clone();
+    names.sort();
+    assert_eq!(names, ["first.bin", "second.bin"]);
+    assert_eq!(reason, Some(EndReason::Completed));
+    Ok(())
+}
+
+/// Receiver subscriber failing to store the file of the given name.
+struct FailingStore {
+    name: &'static str,
+    names: Mutex<HashMap<String, String>>,
+}
+
+impl ReceiveFilesSubscriber for FailingStore {
+    fn get_id(&self) -> String {
+        "failing_store".to_string()
+    }
+
+    fn log(&self, _message: String) {}
+
+    fn notify_receiving(&self, _event: ReceiveFilesReceivingEvent) {}
+
+    fn notify_connecting(&self, event: ReceiveFilesConnectingEvent) {
+        let names = event.files.into_iter().map(|f| (f.id, f.name));
+        self.names.lock().unwrap().extend(names);
+    }
+
+    fn notify_received(&self, event: ReceiveFilesReceivedEvent) -> bool {
+        self.names.lock().unwrap()[&event.id] != self.name
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn keeps_files_the_receiver_failed_to_store() -> Result<()> {
+    let sending = send_files(SendFilesRequest {
+        profile: sender_profile(),
+        files: sender_files(&[
+            ("stored.bin", vec![1u8; 5_000]),
+            ("failed.bin", vec![2u8; 3_000]),
+        ]),
+        config: SenderConfig {
+            chunk_size: 1024,
+            endpoint: local_endpoint(),
+            ..Default::default()
+        },
+    })
+    .await?;
+    let acknowledged = Arc::new(Acknowledged::default());
+    sending.subscribe(acknowledged.clone());
+
+    let receiving = receive_files(ReceiveFilesRequest {
+        ticket: sending.get_ticket(),
+        confirmation: sending.get_confirmation(),
+        profile: receiver_profile(),
+        config: Some(ReceiverConfig {
+            chunk_size: 1024,
+            endpoint: local_endpoint(),
+            ..Default::default()
+        }),
+    })
+    .await?;
+    receiving.subscribe(Arc::new(FailingStore {
+        name: "failed.bin",
+        names: Mutex::default(),
+    }));
+
+    let mut events = receiving.events();
+    receiving.start()?;
+
+    let collect = async {
+        while let Some(event) = events.next().await {
+            if let ReceiveFilesEvent::Finished = event {
+                break;
+            }
+        }
+    };
+    tokio::time::timeout(TIMEOUT, collect)
+        .await
+        .map_err(|_| anyhow!("Transfer timed out"))?;
+
+    // Received in full but not stored, so the sender must keep the source
+    let names = acknowledged.names.lock().unwrap().clone();
+    assert_eq!(names, ["stored.bin"]);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn refuses_session_larger_than_limit() -> Result<()> {
+    let sending = send_files(SendFilesRequest {
+        profile: sender_profile(),
+        files: sender_files(&[
+            ("first.bin", vec![1u8; 6_000]),
+            ("second.bin", vec![2u8; 6_000]),
+        ]),
+        config: SenderConfig {
+            endpoint: local_endpoint(),
+            ..Default::default()
+        },
+    })
+    .await?;
+
+    let receiving = receive_files(ReceiveFilesRequest {
+        ticket: sending.get_ticket(),
+        confirmation: sending.get_confirmation(),
+        profile: receiver_profile(),
+        config: Some(ReceiverConfig {
+            endpoint: local_endpoint(),
+            max_total_size: Some(10_000),
+            ..Default::default()
+        }),
+    })
+    .await?;
+
+    let mut events = receiving.events();
+    receiving.start()?;
+
+    let mut reason = None;
+    let collect = async {
+        while let Some(event) = events.next().await {
+            match event {
+                ReceiveFilesEvent::Connecting(_) => {
+                    return Err(anyhow!("Refused session connected"));
+                }
+                ReceiveFilesEvent::Receiving(_)
+                | ReceiveFilesEvent::Discarded(_) => {
+                    return Err(anyhow!("Refused session sent chunks"));
+                }
+                ReceiveFilesEvent::Ended(ended) => reason = Some(ended),
+                ReceiveFilesEvent::Finished => break,
+            }
+        }
+        Ok(())
+    };
+    tokio::time::timeout(TIMEOUT, collect)
+        .await
+        .map_err(|_| anyhow!("Transfer timed out"))??;
+
+    assert_eq!(reason, Some(EndReason::TooLarge));
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn stops_on_stream_reset() -> Result<()> {
+    let data = vec![2u8; 10_000];
+    let faults = Faults {
+        reset_after_chunks: Some(2),
+        ..Default::default()
+    };
+
+    let received =
+        send_and_receive(&[("cut.bin", data.clone())], 1024, faults).await?;
+
+    assert!(received["cut.bin"].len() < data.len());
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn transfers_to_waiting_receiver() -> Result<()> {
+    let data = b"hello from the sender".to_vec();
+
+    let received = send_to_waiting(
+        &[("note.txt", data.clone())],
+        ReadyToReceiveConfig::default().chunk_size,
+        Arc::new(Collector::default()),
+    )
+    .await?;
+
+    assert_eq!(received["note.txt"], data);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn announces_kind_of_link_items() -> Result<()> {
+    let link = LinkItem {
+        url: "https://example.com/".to_string(),
+        title: "Example".to_string(),
+        desc: None,
+        preview: None,
+    }
+    .to_bytes()?;
+    let mut files = sender_files(&[("a.bin", vec![1u8; 100])]);
+    files.push(SenderFile {
+        name: "example.link.json".to_string(),
+        data: Arc::new(MemoryData {
+            data: link.clone(),
+            offset: AtomicUsize::new(0),
+            changes_at: None,
+        }),
+        kind: ItemKind::Link,
+    });
+
+    let sending = send_files(SendFilesRequest {
+        profile: sender_profile(),
+        files,
+        config: SenderConfig {
+            endpoint: local_endpoint(),
+            ..Default::default()
+        },
+    })
+    .await?;
+    let receiving = receive_files(ReceiveFilesRequest {
+        ticket: sending.get_ticket(),
+        confirmation: sending.get_confirmation(),
+        profile: receiver_profile(),
+        config: Some(ReceiverConfig {
+            endpoint: local_endpoint(),
+            ..Default::default()
+        }),
+    })
+    .await?;
+
+    let mut events = receiving.events();
+    receiving.start()?;
+    let mut kinds = HashMap::new();
+    let collect = async {
+        while let Some(event) = events.next().await {
+            match event {
+                ReceiveFilesEvent::Connecting(event) => kinds
+                    .extend(event.files.into_iter().map(|f| (f.name, f.kind))),
+                ReceiveFilesEvent::Finished => break,
+                _ => {}
+            }
+        }
+    };
+    tokio::time::timeout(TIMEOUT, collect)
+        .await
+        .map_err(|_| anyhow!("Transfer timed out"))?;
+
+    assert_eq!(kinds["a.bin"], ItemKind::File);
+    assert_eq!(kinds["example.link.json"], ItemKind::Link);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn transfers_from_allowed_sender_with_fixed_confirmation() -> Result<()> {
+    let data = b"hello from a paired sender".to_vec();
+    let sender_endpoint = EndpointConfig {
+        secret_key: Some([7; 32]),
+        ..local_endpoint()
+    };
+    let sender_id = sender_endpoint.node_id().unwrap();
+
+    let collector = Arc::new(Collector::default());
+    let waiting = ready_to_receive(ReadyToReceiveRequest {
+        profile: receiver_profile(),
+        config: ReadyToReceiveConfig {
+            endpoint: local_endpoint(),
+            confirmation: Some(42),
+            allowed_senders: Some(vec![sender_id]),
+            ..Default::default()
+        },
+    })
+    .await?;
+    waiting.subscribe(collector.clone());
+    assert_eq!(waiting.get_confirmation(), 42);
+
+    let sending = send_files_to(SendFilesToRequest {
+        ticket: waiting.get_ticket(),
+        confirmation: 42,
+        profile: sender_profile(),
+        files: sender_files(&[("note.txt", data.clone())]),
+        config: SenderConfig {
+            endpoint: sender_endpoint,
+            ..Default::default()
+        },
+    })
+    .await?;
+    sending.start()?;
+
+    let wait = async {
+        while !waiting.is_finished() {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    };
+    tokio::time::timeout(TIMEOUT, wait)
+        .await
+        .map_err(|_| anyhow!("Transfer timed out"))?;
+
+    let received = std::mem::take(&mut *collector.received.lock().unwrap());
+    assert_eq!(received.by_name()["note.txt"], data);
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn coalesces_chunks_for_throttled_subscriber() -> Result<()> {
+    let data: Vec<u8> = (0..=255).cycle().take(20_000).collect();
+    let collector = Arc::new(Collector {
+        interval: Some(Duration::from_secs(3600)),
+        ..Default::default()
+    });
+
+    let received =
+        send_to_waiting(&[("big.bin", data.clone())], 1024, collector.clone())
+            .await?;
+
+    // The first chunk, then everything else once the file is complete
+    assert_eq!(received["big.bin"], data);
+    assert_eq!(collector.events.load(Ordering::Relaxed), 2);
+    Ok(())
+}
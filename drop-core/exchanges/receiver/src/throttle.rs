@@ -0,0 +1,172 @@
+//! Subscribers that asked for a minimum interval between progress events.
+//!
+//! The handlers keep notifying once per chunk; on subscribe, a subscriber
+//! with a `progress_interval` is wrapped in one of these types, which merge
+//! the chunks of a file received within the interval into one event. Chunks
+//! still held back when the session ends are delivered before the end is.
+
+use std::{sync::Arc, time::Duration};
+
+use arkdropx_common::progress::ChunkCoalescer;
+
+use crate::{
+    EndReason, ReceiveFilesConnectingEvent, ReceiveFilesDiscardedEvent,
+    ReceiveFilesFile, ReceiveFilesReceivedEvent, ReceiveFilesReceivingEvent,
+    ReceiveFilesSubscriber,
+    ready_to_receive::{
+        ReadyToReceiveConnectingEvent, ReadyToReceiveDiscardedEvent,
+        ReadyToReceiveReceivedEvent, ReadyToReceiveReceivingEvent,
+        ReadyToReceiveSubscriber,
+    },
+};
+
+/// Wraps the subscriber if it asked for throttled progress. `files` are the
+/// files already announced, if the handshake completed.
+pub(crate) fn throttle_receive_files(
+    subscriber: Arc<dyn ReceiveFilesSubscriber>,
+    files: &[ReceiveFilesFile],
+) -> Arc<dyn ReceiveFilesSubscriber> {
+    let Some(interval) = subscriber.progress_interval() else {
+        return subscriber;
+    };
+    let chunks = ChunkCoalescer::new(interval);
+    for file in files {
+        chunks.expect(file.id.clone(), file.len);
+    }
+    Arc::new(ThrottledReceiveFiles {
+        inner: subscriber,
+        chunks,
+    })
+}
+
+/// Wraps the subscriber if it asked for throttled progress.
+pub(crate) fn throttle_ready_to_receive(
+    subscriber: Arc<dyn ReadyToReceiveSubscriber>,
+) -> Arc<dyn ReadyToReceiveSubscriber> {
+    match subscriber.progress_interval() {
+        Some(interval) => Arc::new(ThrottledReadyToReceive {
+            inner: subscriber,
+            chunks: ChunkCoalescer::new(interval),
+        }),
+        None => subscriber,
+    }
+}
+
+struct ThrottledReceiveFiles {
+    inner: Arc<dyn ReceiveFilesSubscriber>,
+    chunks: ChunkCoalescer,
+}
+
+impl ThrottledReceiveFiles {
+    fn flush(&self) {
+        for (id, data) in self.chunks.drain() {
+            self.inner
+                .notify_receiving(ReceiveFilesReceivingEvent { id, data });
+        }
+    }
+}
+
+impl ReceiveFilesSubscriber for ThrottledReceiveFiles {
+    fn get_id(&self) -> String {
+        self.inner.get_id()
+    }
+
+    fn log(&self, message: String) {
+        self.inner.log(message);
+    }
+
+    fn notify_receiving(&self, event: ReceiveFilesReceivingEvent) {
+        if let Some(data) = self.chunks.push(&event.id, event.data) {
+            self.inner
+                .notify_receiving(ReceiveFilesReceivingEvent {
+                    id: event.id,
+                    data,
+                });
+        }
+    }
+
+    fn notify_connecting(&self, event: ReceiveFilesConnectingEvent) {
+        for file in &event.files {
+            self.chunks.expect(file.id.clone(), file.len);
+        }
+        self.inner.notify_connecting(event);
+    }
+
+    fn notify_discarded(&self, event: ReceiveFilesDiscardedEvent) {
+        // Held back chunks would be written after the file was deleted
+        self.chunks.discard(&event.id);
+        self.inner.notify_discarded(event);
+    }
+
+    fn notify_received(&self, event: ReceiveFilesReceivedEvent) -> bool {
+        // The last chunk of a file is never held back
+        self.inner.notify_received(event)
+    }
+
+    fn notify_ended(&self, reason: EndReason) {
+        self.flush();
+        self.inner.notify_ended(reason);
+    }
+
+    fn notify_finished(&self) {
+        self.flush();
+        self.inner.notify_finished();
+    }
+
+    fn progress_interval(&self) -> Option<Duration> {
+        self.inner.progress_interval()
+    }
+}
+
+struct ThrottledReadyToReceive {
+    inner: Arc<dyn ReadyToReceiveSubscriber>,
+    chunks: ChunkCoalescer,
+}
+
+impl ReadyToReceiveSubscriber for ThrottledReadyToReceive {
+    fn get_id(&self) -> String {
+        self.inner.get_id()
+    }
+
+    fn log(&self, message: String) {
+        self.inner.log(message);
+    }
+
+    fn notify_receiving(&self, event: ReadyToReceiveReceivingEvent) {
+        if let Some(data) = self.chunks.push(&event.id, event.data) {
+            self.inner
+                .notify_receiving(ReadyToReceiveReceivingEvent {
+                    id: event.id,
+                    data,
+                });
+        }
+    }
+
+    fn notify_connecting(&self, event: ReadyToReceiveConnectingEvent) {
+        for file in &event.files {
+            self.chunks.expect(file.id.clone(), file.len);
+        }
+        self.inner.notify_connecting(event);
+    }
+
+    fn notify_discarded(&self, event: ReadyToReceiveDiscardedEvent) {
+        self.chunks.discard(&event.id);
+        self.inner.notify_discarded(event);
+    }
+
+    fn notify_received(&self, event: ReadyToReceiveReceivedEvent) -> bool {
+        self.inner.notify_received(event)
+    }
+
+    fn notify_ended(&self, reason: EndReason) {
+        for (id, data) in self.chunks.drain() {
+            self.inner
+                .notify_receiving(ReadyToReceiveReceivingEvent { id, data });
+        }
+        self.inner.notify_ended(reason);
+    }
+
+    fn progress_interval(&self) -> Option<Duration> {
+        self.inner.progress_interval()
+    }
+}
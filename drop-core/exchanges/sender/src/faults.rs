@@ -0,0 +1,96 @@
+//! Fault injection for transfer tests.
+//!
+//! With the `fault-injection` feature, `SenderConfig::faults` makes the sender
+//! misbehave on purpose so receivers' error, verification and timeout paths
+//! can be exercised in automated tests. Without the feature `Faults` is an
+//! empty type and the hooks compile to nothing.
+
+#[cfg(feature = "fault-injection")]
+use std::time::Duration;
+
+use anyhow::Result;
+use iroh::endpoint::SendStream;
+
+/// Error code of streams reset by the `reset_after_chunks` fault.
+pub const FAULT_RESET_CODE: u32 = 0xFA17;
+
+/// Faults injected into every file stream of a session.
+#[cfg(feature = "fault-injection")]
+#[derive(Clone, Debug, Default)]
+pub struct Faults {
+    /// Resets the stream once this many chunks of the file were written.
+    pub reset_after_chunks: Option<u64>,
+    /// Waits this long before writing each chunk.
+    pub chunk_delay: Option<Duration>,
+    /// Flips the bytes of the frame of the chunk with this index, so the
+    /// receiver reads a frame that doesn't deserialize.
+    pub corrupt_chunk: Option<u64>,
+    /// Changes a byte of the data of the chunk with this index, keeping its
+    /// frame well-formed, so only the checksum reveals it.
+    pub tamper_chunk: Option<u64>,
+}
+
+/// No faults are injected without the `fault-injection` feature.
+#[cfg(not(feature = "fault-injection"))]
+#[derive(Clone, Debug, Default)]
+pub struct Faults;
+
+impl Faults {
+    /// Applies the faults due before the chunk with the given index is
+    /// written as `frame`. Fails after resetting the stream.
+    #[cfg(feature = "fault-injection")]
+    pub(crate) async fn before_chunk(
+        &self,
+        index: u64,
+        frame: &mut [u8],
+        uni: &mut SendStream,
+    ) -> Result<()> {
+        if let Some(delay) = self.chunk_delay {
+            tokio::time::sleep(delay).await;
+        }
+        if self.reset_after_chunks == Some(index) {
+            uni.reset(FAULT_RESET_CODE.into())?;
+            return Err(anyhow::Error::msg("Stream reset by fault injection"));
+        }
+        if self.corrupt_chunk == Some(index) {
+            frame.iter_mut().for_each(|byte| *byte = !*byte);
+        }
+        if self.tamper_chunk == Some(index) {
+            tamper(frame);
+        }
+        Ok(())
+    }
+
+    #[cfg(not(feature = "fault-injection"))]
+    pub(crate) async fn before_chunk(
+        &self,
+        _index: u64,
+        _frame: &mut [u8],
+        _uni: &mut SendStream,
+    ) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Changes the first digit of the first data byte of a JSON frame.
+#[cfg(feature = "fault-injection")]
+fn tamper(frame: &mut [u8]) {
+    const DATA: &[u8] = b"\"data\":[";
+
+    let Some(start) = frame
+        .windows(DATA.len())
+        .position(|window| window == DATA)
+    else {
+        return;
+    };
+    if let Some(digit) = frame[start + DATA.len()..]
+        .iter_mut()
+        .find(|byte| byte.is_ascii_digit())
+    {
+        *digit = if *digit == b'9' {
+            b'8'
+        } else {
+            *digit + 1
+        };
+    }
+}
@@ -26,12 +26,31 @@
 //!
 //! See `send_files` and `send_files_to` modules for the operational flows.
 
+mod faults;
 mod send_files;
 pub mod send_files_to;
+mod throttle;
 
 use arkdrop_entities::Data;
-use std::sync::Arc;
+use arkdropx_common::handshake::HandshakeConfig;
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    sync::Arc,
+    time::SystemTime,
+};
 
+pub use faults::{FAULT_RESET_CODE, Faults};
+
+pub use arkdropx_common::{
+    endpoint::{EndpointConfig, RelayConfig},
+    handshake::{
+        Compression, DEFAULT_MAX_AVATAR_SIZE, HashAlgorithm, ItemKind,
+    },
+    item::LinkItem,
+    retry::RetryConfig,
+    status::EndReason,
+    uri::{DropAction, DropUri},
+};
 pub use send_files::*;
 
 /// Sender's profile metadata transmitted during the handshake.
@@ -49,12 +68,17 @@ pub struct SenderProfile {
 /// The file contains a human-friendly `name` and a data source implementing
 /// [`SenderFileData`]. The data source is read chunk-by-chunk during transfer.
 pub struct SenderFile {
-    /// File name presented to the receiver.
+    /// File name presented to the receiver. May be a relative path using `/`
+    /// as separator, e.g. `photos/a.jpg`, to place the file in a directory.
     pub name: String,
 
     /// Backing data source. Must be thread-safe, since reads can occur on
     /// background tasks.
     pub data: Arc<dyn SenderFileData>,
+
+    /// What the data is, [`ItemKind::File`] unless it is the serialized form
+    /// of an item such as a [`LinkItem`].
+    pub kind: ItemKind,
 }
 
 /// Trait for a readable file-like data source used by the sender.
@@ -70,6 +94,13 @@ pub struct SenderFile {
 /// - `read` is a single-byte variant primarily to satisfy the
 ///   `arkdrop_entities::Data` trait; it can be implemented in terms of your
 ///   internal reader if needed.
+/// - `has_changed` tells whether the source was modified since it was
+///   created; the file is abandoned when it returns true during a transfer.
+/// - `content_id` identifies the bytes of the source, so identical files of
+///   a session are only sent once.
+/// - `content_hash` and `modified` are announced to the receiver, which
+///   verifies the received file against the hash and restores its
+///   modification time.
 pub trait SenderFileData: Send + Sync {
     /// Total length in bytes.
     fn len(&self) -> u64;
@@ -84,6 +115,93 @@ pub trait SenderFileData: Send + Sync {
 
     /// Read up to `size` bytes. Return an empty vector to indicate EOF.
     fn read_chunk(&self, size: u64) -> Vec<u8>;
+
+    /// Returns true if the source changed since it was created, e.g. a file
+    /// whose size or modification time differs from the ones seen at the
+    /// start. Checked periodically while sending and at EOF.
+    fn has_changed(&self) -> bool {
+        false
+    }
+
+    /// Identifier of the content, e.g. a hash of it, equal for sources with
+    /// the same bytes. Must not move the position of `read_chunk`.
+    ///
+    /// Only asked for sources with the same length as another file of the
+    /// session. `None`, the default, never matches another source.
+    fn content_id(&self) -> Option<String> {
+        None
+    }
+
+    /// BLAKE3 hash of the whole content, hex encoded. Must not move the
+    /// position of `read_chunk`.
+    ///
+    /// Asked for every file before the transfer if BLAKE3 is one of the
+    /// [`SenderConfig::hash_algorithms`]. `None`, the default, sends the file
+    /// without a hash, so it isn't verified as a whole.
+    fn content_hash(&self) -> Option<String> {
+        None
+    }
+
+    /// Time the source was last modified, applied to the received file.
+    fn modified(&self) -> Option<SystemTime> {
+        None
+    }
+}
+
+/// Returns for each file the index of an earlier file with the same content,
+/// see [`SenderFileData::content_id`].
+pub(crate) fn find_copies(files: &[SenderFile]) -> Vec<Option<usize>> {
+    let mut candidates: HashMap<(u64, ItemKind), Vec<usize>> = HashMap::new();
+    for (index, file) in files.iter().enumerate() {
+        let len = file.data.len();
+        if len > 0 {
+            candidates
+                .entry((len, file.kind))
+                .or_default()
+                .push(index);
+        }
+    }
+
+    let mut copies = vec![None; files.len()];
+    for indexes in candidates
+        .into_values()
+        .filter(|indexes| indexes.len() > 1)
+    {
+        let mut originals = HashMap::new();
+        for index in indexes {
+            let Some(content_id) = files[index].data.content_id() else {
+                continue;
+            };
+            match originals.entry(content_id) {
+                Entry::Occupied(original) => {
+                    copies[index] = Some(*original.get())
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(index);
+                }
+            }
+        }
+    }
+    copies
+}
+
+/// Number of chunks sent between two checks of
+/// [`SenderFileData::has_changed`].
+pub(crate) const SOURCE_CHECK_INTERVAL: u64 = 16;
+
+/// Why a file was abandoned while the rest of the session went on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SendFileError {
+    /// The source changed on disk while it was being sent. The stream was
+    /// reset so the receiver doesn't keep a blend of old and new bytes.
+    SourceChanged,
+}
+impl std::fmt::Display for SendFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SourceChanged => write!(f, "source changed during transfer"),
+        }
+    }
 }
 
 /// Internal adapter to bridge `SenderFileData` with `arkdrop_entities::Data`.
@@ -105,6 +223,18 @@ impl Data for SenderFileDataAdapter {
     fn read_chunk(&self, size: u64) -> Vec<u8> {
         self.inner.read_chunk(size)
     }
+
+    fn has_changed(&self) -> bool {
+        self.inner.has_changed()
+    }
+
+    fn content_hash(&self) -> Option<String> {
+        self.inner.content_hash()
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        self.inner.modified()
+    }
 }
 
 /// Tuning knobs for file transfer performance.
@@ -117,6 +247,32 @@ pub struct SenderConfig {
     pub chunk_size: u64,
     /// Maximum number of unidirectional streams used in parallel.
     pub parallel_streams: u64,
+    /// Networking options of the endpoint bound for the transfer.
+    pub endpoint: EndpointConfig,
+    /// Largest avatar in bytes accepted from the receiver, bigger or invalid
+    /// ones are dropped.
+    pub max_avatar_size: usize,
+    /// Retries of failed stream opens and connection attempts.
+    pub retry: RetryConfig,
+    /// Largest upload rate in bytes per second, shared by all streams of
+    /// the session. Unlimited if `None`.
+    pub max_rate: Option<u64>,
+    /// Largest number of files sent simultaneously, each on its own stream.
+    /// One file per negotiated parallel stream if `None`.
+    ///
+    /// Going above `parallel_streams` helps sessions of many small files:
+    /// their streams are opened while the receiver is busy with others, so
+    /// it never waits for the next file to start.
+    pub max_concurrent_files: Option<u64>,
+    /// Hash algorithms to verify files with, most preferred first. Hashing
+    /// is disabled if empty.
+    pub hash_algorithms: Vec<HashAlgorithm>,
+    /// Compressions accepted for file data, most preferred first.
+    /// Compression is disabled if empty.
+    pub compressions: Vec<Compression>,
+    /// Faults injected into the file streams, for tests only.
+    #[cfg(feature = "fault-injection")]
+    pub faults: Faults,
 }
 impl Default for SenderConfig {
     /// Balanced defaults: 512 KiB chunks, 4 parallel streams.
@@ -124,6 +280,15 @@ impl Default for SenderConfig {
         Self {
             chunk_size: 524288,  // 512KB chunks
             parallel_streams: 4, // 4 parallel streams
+            endpoint: EndpointConfig::default(),
+            max_avatar_size: DEFAULT_MAX_AVATAR_SIZE,
+            retry: RetryConfig::default(),
+            max_rate: None,
+            max_concurrent_files: None,
+            hash_algorithms: vec![HashAlgorithm::Blake3],
+            compressions: Vec::new(),
+            #[cfg(feature = "fault-injection")]
+            faults: Faults::default(),
         }
     }
 }
@@ -135,6 +300,7 @@ impl SenderConfig {
         Self {
             chunk_size: 524288,  // 512KB chunks
             parallel_streams: 8, // 8 parallel streams
+            ..Self::default()
         }
     }
 
@@ -150,6 +316,37 @@ impl SenderConfig {
         Self {
             chunk_size: 65536,   // 64KB chunks
             parallel_streams: 2, // 2 parallel streams
+            ..Self::default()
         }
     }
+
+    /// Number of files sent simultaneously given the negotiated number of
+    /// parallel streams.
+    pub(crate) fn concurrent_files(&self, parallel_streams: u64) -> usize {
+        self.max_concurrent_files
+            .unwrap_or(parallel_streams)
+            .max(1) as usize
+    }
+
+    /// Returns the preferences advertised to the receiver in the handshake.
+    pub(crate) fn handshake_config(&self) -> HandshakeConfig {
+        HandshakeConfig::new(
+            self.chunk_size,
+            self.parallel_streams,
+            self.hash_algorithms.clone(),
+            self.compressions.clone(),
+        )
+    }
+
+    /// Faults to inject into the file streams, none without the
+    /// `fault-injection` feature.
+    #[cfg(feature = "fault-injection")]
+    pub(crate) fn faults(&self) -> Faults {
+        self.faults.clone()
+    }
+
+    #[cfg(not(feature = "fault-injection"))]
+    pub(crate) fn faults(&self) -> Faults {
+        Faults
+    }
 }
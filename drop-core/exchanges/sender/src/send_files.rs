@@ -7,19 +7,23 @@
 
 mod handler;
 
-use crate::{SenderConfig, SenderFile, SenderFileDataAdapter, SenderProfile};
+use crate::{
+    EndReason, SenderConfig, SenderFile, SenderFileDataAdapter, SenderProfile,
+    find_copies,
+};
 use anyhow::Result;
 use arkdrop_entities::{File, Profile};
 use chrono::{DateTime, Utc};
 use handler::SendFilesHandler;
-use iroh::{Endpoint, Watcher, protocol::Router};
+use iroh::{Watcher, protocol::Router};
 use iroh_base::ticket::NodeTicket;
 use rand::Rng;
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 use uuid::Uuid;
 
 pub use handler::{
-    SendFilesConnectingEvent, SendFilesSendingEvent, SendFilesSubscriber,
+    SendFilesConnectingEvent, SendFilesErrorEvent, SendFilesReceivedEvent,
+    SendFilesSendingEvent, SendFilesSubscriber,
 };
 
 /// All inputs required to start a file transfer.
@@ -79,6 +83,7 @@ impl SendFilesBubble {
     pub async fn cancel(&self) -> Result<()> {
         self.handler
             .log("cancel: Initiating file transfer cancellation".to_string());
+        self.handler.end(EndReason::Cancelled);
         let result = self
             .router
             .shutdown()
@@ -124,6 +129,12 @@ impl SendFilesBubble {
         is_finished
     }
 
+    /// Returns why the session ended, also told to the receiver. `None`
+    /// while the session runs.
+    pub fn get_end_reason(&self) -> Option<EndReason> {
+        self.handler.get_end_reason()
+    }
+
     /// Returns true if a receiver has connected and been accepted (i.e.,
     /// the handler has consumed the single allowed connection).
     pub fn is_connected(&self) -> bool {
@@ -187,23 +198,38 @@ pub async fn send_files(request: SendFilesRequest) -> Result<SendFilesBubble> {
         avatar_b64: request.profile.avatar_b64.clone(),
     };
 
+    let originals = find_copies(&request.files);
+    let mut kinds = HashMap::new();
     let files: Vec<File> = request
         .files
         .into_iter()
         .map(|f| {
+            let id = Uuid::new_v4().to_string();
+            if !f.kind.is_file() {
+                kinds.insert(id.clone(), f.kind);
+            }
             let data = SenderFileDataAdapter { inner: f.data };
             File {
-                id: Uuid::new_v4().to_string(),
+                id,
                 name: f.name,
                 data: Arc::new(data),
             }
         })
         .collect();
+    let copies: HashMap<String, String> = originals
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, original)| {
+            Some((files[index].id.clone(), files[original?].id.clone()))
+        })
+        .collect();
 
     let files_len = files.len();
     let handler = Arc::new(SendFilesHandler::new(
         profile,
         files,
+        kinds,
+        copies,
         request.config.clone(),
     ));
 
@@ -215,10 +241,8 @@ pub async fn send_files(request: SendFilesRequest) -> Result<SendFilesBubble> {
         request.config.chunk_size
     ));
 
-    handler.log(
-        "send_files: Creating endpoint builder with discovery_n0".to_string(),
-    );
-    let endpoint_builder = Endpoint::builder().discovery_n0();
+    handler.log("send_files: Creating endpoint builder".to_string());
+    let endpoint_builder = request.config.endpoint.builder()?;
 
     handler.log("send_files: Binding endpoint".to_string());
     let endpoint = endpoint_builder.bind().await?;
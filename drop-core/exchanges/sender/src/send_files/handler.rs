@@ -9,25 +9,35 @@
 use anyhow::Result;
 use arkdrop_entities::{File, Profile};
 use arkdropx_common::{
+    ack::{ACK_TIMEOUT, AckReader},
     handshake::{
-        HandshakeConfig, HandshakeFile, HandshakeProfile, NegotiatedConfig,
-        ReceiverHandshake, SenderHandshake,
+        Capabilities, HandshakeFile, HandshakeProfile, HashAlgorithm, ItemKind,
+        MAX_HANDSHAKE_LEN, NegotiatedConfig, ReceiverHandshake,
+        SenderHandshake, unix_millis,
     },
-    projection::FileProjection,
+    projection::{FileProjection, SOURCE_CHANGED_CODE},
+    rate::RateLimiter,
+    status::EndReason,
 };
 use futures::Future;
 use iroh::{
-    endpoint::{Connection, RecvStream, SendStream, VarInt},
+    endpoint::{Connection, RecvStream, SendStream},
     protocol::ProtocolHandler,
 };
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt::Debug,
+    iter,
     sync::{Arc, RwLock, atomic::AtomicBool},
+    time::Duration,
 };
 use tokio::task::JoinSet;
+use tracing::{Instrument, Span, field};
 
-use super::SenderConfig;
+use crate::{
+    Faults, RetryConfig, SOURCE_CHECK_INTERVAL, SendFileError, SenderConfig,
+    throttle::throttle_send_files,
+};
 
 /// Observer interface for transfer logs and progress.
 ///
@@ -38,6 +48,9 @@ pub trait SendFilesSubscriber: Send + Sync {
     fn get_id(&self) -> String;
 
     /// Receives diagnostic log lines from the transfer pipeline.
+    ///
+    /// The same lines are emitted as `tracing` debug events inside the
+    /// `send_files` span, which carries the session and peer ids.
     fn log(&self, message: String);
 
     /// Receives progress updates for each file being sent.
@@ -48,6 +61,26 @@ pub trait SendFilesSubscriber: Send + Sync {
 
     /// Notified when a receiver connects and completes the handshake.
     fn notify_connecting(&self, event: SendFilesConnectingEvent);
+
+    /// Notified when a file is abandoned while the rest of the session goes
+    /// on, e.g. because it changed on disk.
+    fn notify_error(&self, _event: SendFilesErrorEvent) {}
+
+    /// Notified when the receiver confirms it got a file in full and
+    /// verified it. Only receivers supporting acknowledgements do, before
+    /// the session completes.
+    fn notify_received(&self, _event: SendFilesReceivedEvent) {}
+
+    /// Notified once with the reason the session ended, told to the peer or
+    /// received from it.
+    fn notify_ended(&self, _reason: EndReason) {}
+
+    /// Minimum time between two progress events of a file, or `None` for an
+    /// event per chunk. Events due in between are dropped; the first and the
+    /// last event of each file are always delivered.
+    fn progress_interval(&self) -> Option<Duration> {
+        None
+    }
 }
 
 /// Per-file progress event.
@@ -62,6 +95,21 @@ pub struct SendFilesSendingEvent {
     pub remaining: u64,
 }
 
+/// Event of a file that was abandoned, see [`SendFileError`].
+#[derive(Clone)]
+pub struct SendFilesErrorEvent {
+    pub id: String,
+    pub name: String,
+    pub error: SendFileError,
+}
+
+/// Event of a file the receiver confirmed it got in full.
+#[derive(Clone)]
+pub struct SendFilesReceivedEvent {
+    pub id: String,
+    pub name: String,
+}
+
 /// Connection event carrying the receiver's profile as reported during
 /// handshake.
 pub struct SendFilesConnectingEvent {
@@ -74,6 +122,9 @@ pub struct SendFilesProfile {
     pub id: String,
     pub name: String,
     pub avatar_b64: Option<String>,
+    /// Node id of the receiver's endpoint, stable across sessions when the
+    /// receiver persists its secret key.
+    pub node_id: Option<String>,
 }
 
 /// Protocol handler responsible for accepting a single receiver and streaming
@@ -88,10 +139,14 @@ pub struct SendFilesProfile {
 pub struct SendFilesHandler {
     is_consumed: AtomicBool,
     is_finished: Arc<AtomicBool>,
+    end_reason: Arc<RwLock<Option<EndReason>>>,
     profile: Profile,
     files: Vec<File>,
+    kinds: HashMap<String, ItemKind>,
+    copies: HashMap<String, String>,
     config: SenderConfig,
     subscribers: Arc<RwLock<HashMap<String, Arc<dyn SendFilesSubscriber>>>>,
+    span: Span,
 }
 impl Debug for SendFilesHandler {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -110,13 +165,24 @@ impl SendFilesHandler {
     pub fn new(
         profile: Profile,
         files: Vec<File>,
+        kinds: HashMap<String, ItemKind>,
+        copies: HashMap<String, String>,
         config: SenderConfig,
     ) -> Self {
+        let span = tracing::info_span!(
+            "send_files",
+            session_id = %profile.id,
+            peer_id = field::Empty,
+        );
         Self {
             is_consumed: AtomicBool::new(false),
             is_finished: Arc::new(AtomicBool::new(false)),
+            end_reason: Arc::new(RwLock::new(None)),
+            span,
             profile,
             files: files.clone(),
+            kinds,
+            copies,
             config,
             subscribers: Arc::new(RwLock::new(HashMap::new())),
         }
@@ -143,8 +209,35 @@ impl SendFilesHandler {
         finished
     }
 
-    /// Broadcasts a log message to all subscribers.
+    /// Returns why the session ended, also told to the peer. `None` while
+    /// the session runs.
+    pub fn get_end_reason(&self) -> Option<EndReason> {
+        *self.end_reason.read().unwrap()
+    }
+
+    /// Records why the session ended unless a reason is already known, and
+    /// notifies subscribers. Used when the bubble is cancelled.
+    pub fn end(&self, reason: EndReason) {
+        let mut end_reason = self.end_reason.write().unwrap();
+        if end_reason.is_some() {
+            return;
+        }
+        *end_reason = Some(reason);
+        drop(end_reason);
+
+        self.log(format!("end: Session ended, reason: {reason}"));
+        self.subscribers
+            .read()
+            .unwrap()
+            .iter()
+            .for_each(|(_, subscriber)| {
+                subscriber.notify_ended(reason);
+            });
+    }
+
+    /// Emits a debug event and broadcasts the message to all subscribers.
     pub fn log(&self, message: String) {
+        tracing::debug!(parent: &self.span, "{message}");
         self.subscribers
             .read()
             .unwrap()
@@ -156,6 +249,7 @@ impl SendFilesHandler {
 
     /// Registers a new subscriber or replaces an existing one with the same ID.
     pub fn subscribe(&self, subscriber: Arc<dyn SendFilesSubscriber>) {
+        let subscriber = throttle_send_files(subscriber);
         let subscriber_id = subscriber.get_id();
         self.log(format!(
             "Subscribing new subscriber with ID: {subscriber_id}"
@@ -238,30 +332,41 @@ impl ProtocolHandler for SendFilesHandler {
         Output = std::result::Result<(), iroh::protocol::AcceptError>,
     > + Send {
         self.log("accept: Creating carrier for file transfer".to_string());
+        if let Ok(peer_id) = connection.remote_node_id() {
+            self.span
+                .record("peer_id", field::display(peer_id));
+        }
 
         let carrier = Carrier {
             is_finished: self.is_finished.clone(),
+            end_reason: self.end_reason.clone(),
             config: self.config.clone(),
             negotiated_config: None,
             profile: self.profile.clone(),
             connection,
             files: self.files.clone(),
+            kinds: self.kinds.clone(),
+            copies: self.copies.clone(),
             subscribers: self.subscribers.clone(),
+            span: self.span.clone(),
         };
 
         async move {
             let mut carrier = carrier;
-            if (carrier.greet().await).is_err() {
+            if let Err(e) = carrier.greet().await {
+                carrier.end(EndReason::of(&e));
                 return Err(iroh::protocol::AcceptError::NotAllowed {});
             }
 
-            if (carrier.send_files().await).is_err() {
+            if let Err(e) = carrier.send_files().await {
+                carrier.finish(EndReason::of(&e));
                 return Err(iroh::protocol::AcceptError::NotAllowed {});
             }
 
-            carrier.finish();
+            carrier.finish(EndReason::Completed);
             Ok(())
         }
+        .instrument(self.span.clone())
     }
 }
 
@@ -270,12 +375,18 @@ impl ProtocolHandler for SendFilesHandler {
 /// Not exposed publicly; used internally by `SendFilesHandler`.
 struct Carrier {
     is_finished: Arc<AtomicBool>,
+    end_reason: Arc<RwLock<Option<EndReason>>>,
     config: SenderConfig,
     negotiated_config: Option<NegotiatedConfig>,
     profile: Profile,
     connection: Connection,
     files: Vec<File>,
+    /// Kinds of the files that are items, by file id.
+    kinds: HashMap<String, ItemKind>,
+    /// Id of the earlier file with the same content, by id of the copy.
+    copies: HashMap<String, String>,
     subscribers: Arc<RwLock<HashMap<String, Arc<dyn SendFilesSubscriber>>>>,
+    span: Span,
 }
 impl Carrier {
     /// Performs the bidirectional handshake exchange and notifies subscribers
@@ -310,12 +421,18 @@ impl Carrier {
                     id: f.id.clone(),
                     name: f.name.clone(),
                     len: f.data.len(),
+                    kind: self.kinds.get(&f.id).copied().unwrap_or_default(),
+                    copy_of: self.copies.get(&f.id).cloned(),
+                    hash: self
+                        .config
+                        .hash_algorithms
+                        .contains(&HashAlgorithm::Blake3)
+                        .then(|| f.data.content_hash())
+                        .flatten(),
+                    modified: f.data.modified().and_then(unix_millis),
                 })
                 .collect(),
-            config: HandshakeConfig {
-                chunk_size: self.config.chunk_size,
-                parallel_streams: self.config.parallel_streams,
-            },
+            config: self.config.handshake_config(),
         };
 
         // Pre-allocate vector with estimated capacity
@@ -341,29 +458,45 @@ impl Carrier {
     ) -> Result<()> {
         let mut header = [0u8; 4];
         bi.1.read_exact(&mut header).await?;
-        let len = u32::from_be_bytes(header);
+        let len = u32::from_be_bytes(header) as usize;
+        if len > MAX_HANDSHAKE_LEN {
+            return Err(anyhow::Error::msg(format!(
+                "Handshake of {len} bytes exceeds the limit of {MAX_HANDSHAKE_LEN} bytes"
+            )));
+        }
 
-        let mut buffer = vec![0u8; len as usize];
+        let mut buffer = vec![0u8; len];
         bi.1.read_exact(&mut buffer).await?;
 
-        let handshake: ReceiverHandshake = serde_json::from_slice(&buffer)?;
+        let mut handshake: ReceiverHandshake = serde_json::from_slice(&buffer)?;
+        if let Some(e) = handshake
+            .profile
+            .strip_invalid_avatar(self.config.max_avatar_size)
+        {
+            self.log(format!("receive_handshake: Dropped peer avatar: {e}"));
+        }
 
         // Negotiate configuration
-        let sender_config = HandshakeConfig {
-            chunk_size: self.config.chunk_size,
-            parallel_streams: self.config.parallel_streams,
-        };
+        let sender_config = self.config.handshake_config();
 
-        self.negotiated_config = Some(NegotiatedConfig::negotiate(
-            &sender_config,
-            &handshake.config,
+        let negotiated_config =
+            NegotiatedConfig::negotiate(&sender_config, &handshake.config);
+        self.log(format!(
+            "receive_handshake: Negotiated capabilities: [{}]",
+            negotiated_config.capabilities
         ));
+        self.negotiated_config = Some(negotiated_config);
 
         // Notify subscribers
         let profile = SendFilesProfile {
             id: handshake.profile.id,
             name: handshake.profile.name,
             avatar_b64: handshake.profile.avatar_b64,
+            node_id: self
+                .connection
+                .remote_node_id()
+                .ok()
+                .map(|id| id.to_string()),
         };
 
         self.subscribers
@@ -379,6 +512,73 @@ impl Carrier {
         Ok(())
     }
 
+    /// Groups the copies by the file they are a copy of, when both peers
+    /// support [`Capabilities::DEDUPLICATION`]. `None` otherwise, every file
+    /// is sent then.
+    fn copies_by_original(&self) -> Option<HashMap<String, Vec<File>>> {
+        let capabilities = self.negotiated_config.as_ref()?.capabilities;
+        if !capabilities.contains(Capabilities::DEDUPLICATION) {
+            return None;
+        }
+
+        let mut copies: HashMap<String, Vec<File>> = HashMap::new();
+        for file in &self.files {
+            if let Some(original) = self.copies.get(&file.id) {
+                copies
+                    .entry(original.clone())
+                    .or_default()
+                    .push(file.clone());
+            }
+        }
+        Some(copies)
+    }
+
+    /// Starts reading the acknowledgements of the receiver, when both peers
+    /// support [`Capabilities::ACKNOWLEDGEMENTS`], and notifies subscribers
+    /// of every file confirmed.
+    fn receive_acks(&self) -> Option<Arc<AckReader>> {
+        let capabilities = self.negotiated_config.as_ref()?.capabilities;
+        if !capabilities.contains(Capabilities::ACKNOWLEDGEMENTS) {
+            return None;
+        }
+
+        let acks = Arc::new(AckReader::default());
+        let names: HashMap<String, String> = self
+            .files
+            .iter()
+            .map(|f| (f.id.clone(), f.name.clone()))
+            .collect();
+        let reader = acks.clone();
+        let connection = self.connection.clone();
+        let subscribers = self.subscribers.clone();
+        tokio::spawn(
+            async move {
+                let on_ack =
+                    |id: &str| {
+                        let Some(name) = names.get(id) else {
+                            return;
+                        };
+                        let event = SendFilesReceivedEvent {
+                            id: id.to_string(),
+                            name: name.clone(),
+                        };
+                        subscribers.read().unwrap().iter().for_each(
+                            |(_, s)| {
+                                s.notify_received(event.clone());
+                            },
+                        );
+                    };
+                if let Err(e) = reader.receive(&connection, on_ack).await {
+                    tracing::debug!(
+                        "receive_acks: Acknowledgements ended: {e}"
+                    );
+                }
+            }
+            .instrument(self.span.clone()),
+        );
+        Some(acks)
+    }
+
     /// Streams all files using unidirectional streams and the negotiated
     /// settings.
     async fn send_files(&self) -> Result<()> {
@@ -391,76 +591,166 @@ impl Carrier {
             } else {
                 (self.config.chunk_size, self.config.parallel_streams)
             };
+        let rate = RateLimiter::from_rate(self.config.max_rate).map(Arc::new);
+        let concurrent_files = self.config.concurrent_files(parallel_streams);
+        let acks = self.receive_acks();
+        // Files and copies sent in full, to be acknowledged
+        let mut expected = HashSet::new();
 
+        let mut copies = self.copies_by_original();
         for file in self.files.clone() {
+            let file_copies = match &mut copies {
+                // Received along with the file it is a copy of
+                Some(_) if self.copies.contains_key(&file.id) => continue,
+                Some(copies) => copies.remove(&file.id).unwrap_or_default(),
+                None => Vec::new(),
+            };
             let connection = self.connection.clone();
             let subscribers = self.subscribers.clone();
-
-            join_set.spawn(async move {
-                Self::send_single_file(
-                    &file,
-                    chunk_size,
-                    connection,
-                    subscribers,
-                )
-                .await
-            });
-
-            // Limit concurrent streams to negotiated number
-            if join_set.len() >= parallel_streams as usize
+            let faults = self.config.faults();
+            let retry = self.config.retry.clone();
+            let rate = rate.clone();
+            let span = tracing::debug_span!(
+                parent: &self.span,
+                "send_file",
+                file_id = %file.id,
+            );
+
+            join_set.spawn(
+                async move {
+                    Self::send_single_file(
+                        &file,
+                        &file_copies,
+                        chunk_size,
+                        connection,
+                        subscribers,
+                        faults,
+                        retry,
+                        rate,
+                    )
+                    .await
+                }
+                .instrument(span),
+            );
+
+            // Limit the files in flight, each one holds a stream
+            if join_set.len() >= concurrent_files
                 && let Some(result) = join_set.join_next().await
-                && let Err(err) = result?
             {
-                self.log(format!("send_files: Stream failed: {err}"));
-                return Err(err);
+                match result? {
+                    Ok(ids) => expected.extend(ids),
+                    Err(err) => {
+                        self.log(format!("send_files: Stream failed: {err}"));
+                        return Err(err);
+                    }
+                }
             }
         }
 
         // Wait for all remaining streams to complete and update final progress
         while let Some(result) = join_set.join_next().await {
-            if let Err(err) = result? {
-                self.log(format!("send_single_file: Stream failed: {err}"));
-                return Err(err);
+            match result? {
+                Ok(ids) => expected.extend(ids),
+                Err(err) => {
+                    self.log(format!("send_single_file: Stream failed: {err}"));
+                    return Err(err);
+                }
             }
         }
 
+        if let Some(acks) = acks
+            && !acks.wait_for(&expected, ACK_TIMEOUT).await
+        {
+            self.log(
+                "send_files: Not all files were acknowledged by the receiver"
+                    .to_string(),
+            );
+        }
+
         self.log("send_files: All files transferred successfully".to_string());
         Ok(())
     }
 
     /// Streams a single file in JSON-framed chunks:
     /// - 4-byte big-endian length header
-    /// - JSON payload containing `FileProjection { id, data }`
+    /// - JSON payload containing `FileProjection { id, data, checksum }`
+    ///
+    /// Returns the ids of the file and its copies once sent in full, none if
+    /// it was abandoned or is empty.
     async fn send_single_file(
         file: &File,
+        copies: &[File],
         chunk_size: u64,
         connection: Connection,
         subscribers: Arc<RwLock<HashMap<String, Arc<dyn SendFilesSubscriber>>>>,
-    ) -> Result<()> {
+        faults: Faults,
+        retry: RetryConfig,
+        rate: Option<Arc<RateLimiter>>,
+    ) -> Result<Vec<String>> {
         let total_len = file.data.len();
         let mut sent = 0u64;
         let mut remaining = total_len;
         let mut chunk_buffer =
             Vec::with_capacity((chunk_size + 1024).try_into().unwrap());
 
-        let mut uni = connection.open_uni().await?;
+        let mut uni = retry
+            .run(
+                || {
+                    let connection = connection.clone();
+                    async move { Ok(connection.open_uni().await?) }
+                },
+                |attempt, err| {
+                    tracing::debug!(
+                        attempt,
+                        "send_single_file: Retrying to open stream: {err}"
+                    );
+                },
+            )
+            .await?;
 
-        Self::notify_progress(file, sent, remaining, subscribers.clone());
+        Self::notify_progress(
+            file,
+            copies,
+            sent,
+            remaining,
+            subscribers.clone(),
+        );
 
+        let mut index = 0u64;
         loop {
             chunk_buffer.clear();
 
             let chunk_data = file.data.read_chunk(chunk_size);
-            if chunk_data.is_empty() {
+            let is_eof = chunk_data.is_empty();
+            if (is_eof || index % SOURCE_CHECK_INTERVAL == 0)
+                && file.data.has_changed()
+            {
+                uni.reset(SOURCE_CHANGED_CODE.into())?;
+                tracing::debug!(
+                    "send_single_file: Source changed, file abandoned"
+                );
+                Self::notify_error(
+                    file,
+                    copies,
+                    SendFileError::SourceChanged,
+                    subscribers,
+                );
+                return Ok(Vec::new());
+            }
+            if is_eof {
                 break;
             }
-            let projection = FileProjection {
-                id: file.id.clone(),
-                data: chunk_data,
-            };
+            let projection = FileProjection::new(file.id.clone(), chunk_data);
 
             serde_json::to_writer(&mut chunk_buffer, &projection)?;
             let len_bytes = (chunk_buffer.len() as u32).to_be_bytes();
+            faults
+                .before_chunk(index, &mut chunk_buffer, &mut uni)
+                .await?;
+            index += 1;
+            if let Some(rate) = &rate {
+                rate.acquire(chunk_buffer.len() as u64).await;
+            }
 
             // Write header + data
             uni.write_all(&len_bytes).await?;
@@ -469,34 +759,70 @@ impl Carrier {
             let data_len = projection.data.len() as u64;
             sent += data_len;
             remaining = remaining.saturating_sub(data_len);
-
-            Self::notify_progress(file, sent, remaining, subscribers.clone());
+            tracing::trace!(sent, remaining, "send_single_file: Chunk sent");
+
+            Self::notify_progress(
+                file,
+                copies,
+                sent,
+                remaining,
+                subscribers.clone(),
+            );
         }
 
         uni.finish()?;
         uni.stopped().await?;
 
-        Ok(())
+        // The receiver never learns the id of an empty file, it gets no chunk
+        if total_len == 0 {
+            return Ok(Vec::new());
+        }
+        Ok(iter::once(file)
+            .chain(copies)
+            .map(|f| f.id.clone())
+            .collect())
     }
 
-    /// Marks the handler as finished and closes the connection with a code and
-    /// reason.
-    fn finish(&self) {
+    /// Records why the session ended and closes the connection with it. A
+    /// reason already known, e.g. of a cancellation, is kept and was already
+    /// notified to subscribers.
+    fn end(&self, reason: EndReason) {
+        let mut end_reason = self.end_reason.write().unwrap();
+        let is_known = end_reason.is_some();
+        let reason = *end_reason.get_or_insert(reason);
+        drop(end_reason);
+
+        self.log(format!("end: Closing connection, reason: {reason}"));
+        reason.close(&self.connection);
+        if is_known {
+            return;
+        }
+
+        self.subscribers
+            .read()
+            .unwrap()
+            .iter()
+            .for_each(|(_, subscriber)| {
+                subscriber.notify_ended(reason);
+            });
+    }
+
+    /// Marks the handler as finished and closes the connection with the code
+    /// of the reason the session ended with.
+    fn finish(&self, reason: EndReason) {
         self.log("finish: Starting transfer finish process".to_string());
+        self.end(reason);
 
         self.is_finished
             .store(true, std::sync::atomic::Ordering::Relaxed);
         self.log("finish: Transfer finished flag set to true".to_string());
 
-        self.log("finish: Connection closed".to_string());
-        self.connection
-            .close(VarInt::from_u32(200), "finished".as_bytes());
-
         self.log("finish: Transfer process completed successfully".to_string());
     }
 
-    /// Internal logger that prefixes subscriber IDs.
+    /// Emits a debug event and broadcasts the message to all subscribers.
     fn log(&self, message: String) {
+        tracing::debug!(parent: &self.span, "{message}");
         self.subscribers.read().unwrap().iter().for_each(
             |(_id, subscriber)| {
                 subscriber.log(message.clone());
@@ -504,26 +830,46 @@ impl Carrier {
         );
     }
 
-    /// Notifies all subscribers about the current per-file progress.
+    /// Notifies all subscribers about the current per-file progress, which
+    /// is also the progress of the copies of the file.
     fn notify_progress(
         file: &File,
+        copies: &[File],
         sent: u64,
         remaining: u64,
         subscribers: Arc<RwLock<HashMap<String, Arc<dyn SendFilesSubscriber>>>>,
     ) {
-        let event = SendFilesSendingEvent {
-            id: file.id.clone(),
-            name: file.name.clone(),
-            sent,
-            remaining,
-        };
-
-        subscribers
-            .read()
-            .unwrap()
-            .iter()
-            .for_each(|(_, s)| {
+        let subscribers = subscribers.read().unwrap();
+        for file in iter::once(file).chain(copies) {
+            let event = SendFilesSendingEvent {
+                id: file.id.clone(),
+                name: file.name.clone(),
+                sent,
+                remaining,
+            };
+            subscribers.iter().for_each(|(_, s)| {
                 s.notify_sending(event.clone());
             });
+        }
+    }
+
+    /// Notifies all subscribers that the file and its copies were abandoned.
+    fn notify_error(
+        file: &File,
+        copies: &[File],
+        error: SendFileError,
+        subscribers: Arc<RwLock<HashMap<String, Arc<dyn SendFilesSubscriber>>>>,
+    ) {
+        let subscribers = subscribers.read().unwrap();
+        for file in iter::once(file).chain(copies) {
+            let event = SendFilesErrorEvent {
+                id: file.id.clone(),
+                name: file.name.clone(),
+                error,
+            };
+            subscribers.iter().for_each(|(_, s)| {
+                s.notify_error(event.clone());
+            });
+        }
     }
 }
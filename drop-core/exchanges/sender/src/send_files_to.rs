@@ -4,15 +4,23 @@
 //! receiver's ticket (from ready_to_receive) and sends files. This is the
 //! complement to the receiver's ready_to_receive flow.
 
-use crate::{SenderConfig, SenderFile, SenderFileDataAdapter, SenderProfile};
+use crate::{
+    Faults, RetryConfig, SOURCE_CHECK_INTERVAL, SendFileError, SenderConfig,
+    SenderFile, SenderFileDataAdapter, SenderProfile, find_copies,
+    throttle::throttle_send_files_to,
+};
 use anyhow::Result;
 use arkdrop_entities::{File, Profile};
 use arkdropx_common::{
+    ack::{ACK_TIMEOUT, AckReader},
     handshake::{
-        HandshakeConfig, HandshakeFile, HandshakeProfile, NegotiatedConfig,
-        ReceiverHandshake, SenderHandshake,
+        Capabilities, HandshakeFile, HandshakeProfile, HashAlgorithm, ItemKind,
+        MAX_HANDSHAKE_LEN, NegotiatedConfig, ReceiverHandshake,
+        SenderHandshake, unix_millis,
     },
-    projection::FileProjection,
+    projection::{FileProjection, SOURCE_CHANGED_CODE},
+    rate::RateLimiter,
+    status::EndReason,
 };
 use iroh::{
     Endpoint,
@@ -20,10 +28,13 @@ use iroh::{
 };
 use iroh_base::ticket::NodeTicket;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
+    iter,
     sync::{Arc, RwLock, atomic::AtomicBool},
+    time::Duration,
 };
 use tokio::task::JoinSet;
+use tracing::{Instrument, Span, field};
 use uuid::Uuid;
 
 /// All inputs required to send files to a waiting receiver.
@@ -51,10 +62,14 @@ pub struct SendFilesToBubble {
     connection: Connection,
     profile: Profile,
     files: Vec<File>,
+    kinds: HashMap<String, ItemKind>,
+    copies: HashMap<String, String>,
     config: SenderConfig,
     is_running: Arc<AtomicBool>,
     is_finished: Arc<AtomicBool>,
+    end_reason: Arc<RwLock<Option<EndReason>>>,
     subscribers: Arc<RwLock<HashMap<String, Arc<dyn SendFilesToSubscriber>>>>,
+    span: Span,
 }
 
 impl SendFilesToBubble {
@@ -64,16 +79,30 @@ impl SendFilesToBubble {
         connection: Connection,
         profile: Profile,
         files: Vec<File>,
+        kinds: HashMap<String, ItemKind>,
+        copies: HashMap<String, String>,
         config: SenderConfig,
     ) -> Self {
+        let span = tracing::info_span!(
+            "send_files_to",
+            session_id = %profile.id,
+            peer_id = field::Empty,
+        );
+        if let Ok(peer_id) = connection.remote_node_id() {
+            span.record("peer_id", field::display(peer_id));
+        }
         Self {
+            span,
             endpoint,
             connection,
             profile,
             files,
+            kinds,
+            copies,
             config,
             is_running: Arc::new(AtomicBool::new(false)),
             is_finished: Arc::new(AtomicBool::new(false)),
+            end_reason: Arc::new(RwLock::new(None)),
             subscribers: Arc::new(RwLock::new(HashMap::new())),
         }
     }
@@ -110,31 +139,41 @@ impl SendFilesToBubble {
             negotiated_config: None,
             connection: self.connection.clone(),
             files: self.files.clone(),
+            kinds: self.kinds.clone(),
+            copies: self.copies.clone(),
             is_finished: self.is_finished.clone(),
+            end_reason: self.end_reason.clone(),
             subscribers: self.subscribers.clone(),
+            span: self.span.clone(),
         };
 
         self.log("start: Spawning async task for file sending".to_string());
         let endpoint = self.endpoint.clone();
-        tokio::spawn(async move {
+        let task = async move {
             let mut carrier = carrier;
             if let Err(e) = carrier.greet().await {
                 carrier.log(format!("start: Handshake failed: {e}"));
-                carrier.finish(&endpoint).await;
+                carrier.finish(EndReason::of(&e), &endpoint).await;
                 return;
             }
 
-            let result = carrier.send_files().await;
-            if let Err(e) = result {
-                carrier.log(format!("start: File sending failed: {e}"));
-            } else {
-                carrier.log(
-                    "start: File sending completed successfully".to_string(),
-                );
-            }
+            let reason = match carrier.send_files().await {
+                Ok(()) => {
+                    carrier.log(
+                        "start: File sending completed successfully"
+                            .to_string(),
+                    );
+                    EndReason::Completed
+                }
+                Err(e) => {
+                    carrier.log(format!("start: File sending failed: {e}"));
+                    EndReason::of(&e)
+                }
+            };
 
-            carrier.finish(&endpoint).await;
-        });
+            carrier.finish(reason, &endpoint).await;
+        };
+        tokio::spawn(task.instrument(self.span.clone()));
 
         Ok(())
     }
@@ -152,18 +191,44 @@ impl SendFilesToBubble {
     ///
     /// Closes the connection and marks the session as finished.
     pub async fn cancel(&self) -> Result<()> {
-        self.log("cancel: Initiating send-to cancellation".to_string());
+        self.cancel_with(EndReason::Cancelled).await
+    }
+
+    /// Cancel the send-to transfer, telling the receiver the given reason.
+    pub async fn cancel_with(&self, reason: EndReason) -> Result<()> {
+        self.log(format!(
+            "cancel: Initiating send-to cancellation, reason: {reason}"
+        ));
+        let is_known = {
+            let mut end_reason = self.end_reason.write().unwrap();
+            let is_known = end_reason.is_some();
+            end_reason.get_or_insert(reason);
+            is_known
+        };
         self.is_finished
             .store(true, std::sync::atomic::Ordering::Relaxed);
-        self.connection
-            .close(VarInt::from_u32(0), b"cancelled");
+        reason.close(&self.connection);
         self.endpoint.close().await;
+        if !is_known {
+            self.subscribers.read().unwrap().iter().for_each(
+                |(_, subscriber)| {
+                    subscriber.notify_ended(reason);
+                },
+            );
+        }
         self.log("cancel: Send-to cancelled successfully".to_string());
         Ok(())
     }
 
+    /// Returns why the session ended, also told to the receiver. `None`
+    /// while the session runs.
+    pub fn get_end_reason(&self) -> Option<EndReason> {
+        *self.end_reason.read().unwrap()
+    }
+
     /// Register a subscriber to receive log and progress events.
     pub fn subscribe(&self, subscriber: Arc<dyn SendFilesToSubscriber>) {
+        let subscriber = throttle_send_files_to(subscriber);
         let subscriber_id = subscriber.get_id();
         self.log(format!(
             "subscribe: Subscribing new subscriber with ID: {subscriber_id}"
@@ -198,6 +263,7 @@ impl SendFilesToBubble {
     }
 
     fn log(&self, message: String) {
+        tracing::debug!(parent: &self.span, "{message}");
         self.subscribers.read().unwrap().iter().for_each(
             |(_id, subscriber)| {
                 subscriber.log(message.clone());
@@ -210,12 +276,29 @@ impl SendFilesToBubble {
 pub trait SendFilesToSubscriber: Send + Sync {
     /// Stable identifier for this subscriber (used as a map key).
     fn get_id(&self) -> String;
-    /// Receive diagnostic log messages.
+    /// Receive diagnostic log messages, also emitted as `tracing` debug
+    /// events inside the `send_files_to` span.
     fn log(&self, message: String);
     /// Receive progress updates for each file being sent.
     fn notify_sending(&self, event: SendFilesToSendingEvent);
     /// Notified when receiver connection is established.
     fn notify_connecting(&self, event: SendFilesToConnectingEvent);
+    /// Notified when a file is abandoned while the rest of the session goes
+    /// on, e.g. because it changed on disk.
+    fn notify_error(&self, _event: SendFilesToErrorEvent) {}
+    /// Notified when the receiver confirms it got a file in full and
+    /// verified it. Only receivers supporting acknowledgements do, before
+    /// the session completes.
+    fn notify_received(&self, _event: SendFilesToReceivedEvent) {}
+    /// Notified once with the reason the session ended, told to the
+    /// receiver or received from it.
+    fn notify_ended(&self, _reason: EndReason) {}
+    /// Minimum time between two progress events of a file, or `None` for an
+    /// event per chunk. Events due in between are dropped; the first and the
+    /// last event of each file are always delivered.
+    fn progress_interval(&self) -> Option<Duration> {
+        None
+    }
 }
 
 /// Per-file progress event.
@@ -227,6 +310,21 @@ pub struct SendFilesToSendingEvent {
     pub remaining: u64,
 }
 
+/// Event of a file that was abandoned, see [`SendFileError`].
+#[derive(Clone)]
+pub struct SendFilesToErrorEvent {
+    pub id: String,
+    pub name: String,
+    pub error: SendFileError,
+}
+
+/// Event of a file the receiver confirmed it got in full.
+#[derive(Clone)]
+pub struct SendFilesToReceivedEvent {
+    pub id: String,
+    pub name: String,
+}
+
 /// Connection event carrying the receiver's profile.
 pub struct SendFilesToConnectingEvent {
     pub receiver: SendFilesToReceiverProfile,
@@ -238,6 +336,9 @@ pub struct SendFilesToReceiverProfile {
     pub id: String,
     pub name: String,
     pub avatar_b64: Option<String>,
+    /// Node id of the receiver's endpoint, stable across sessions when the
+    /// receiver persists its secret key.
+    pub node_id: Option<String>,
 }
 
 /// Helper that performs handshake, configuration negotiation, and streaming.
@@ -247,8 +348,14 @@ struct Carrier {
     negotiated_config: Option<NegotiatedConfig>,
     connection: Connection,
     files: Vec<File>,
+    /// Kinds of the files that are items, by file id.
+    kinds: HashMap<String, ItemKind>,
+    /// Id of the earlier file with the same content, by id of the copy.
+    copies: HashMap<String, String>,
     is_finished: Arc<AtomicBool>,
+    end_reason: Arc<RwLock<Option<EndReason>>>,
     subscribers: Arc<RwLock<HashMap<String, Arc<dyn SendFilesToSubscriber>>>>,
+    span: Span,
 }
 
 impl Carrier {
@@ -284,12 +391,18 @@ impl Carrier {
                     id: f.id.clone(),
                     name: f.name.clone(),
                     len: f.data.len(),
+                    kind: self.kinds.get(&f.id).copied().unwrap_or_default(),
+                    copy_of: self.copies.get(&f.id).cloned(),
+                    hash: self
+                        .config
+                        .hash_algorithms
+                        .contains(&HashAlgorithm::Blake3)
+                        .then(|| f.data.content_hash())
+                        .flatten(),
+                    modified: f.data.modified().and_then(unix_millis),
                 })
                 .collect(),
-            config: HandshakeConfig {
-                chunk_size: self.config.chunk_size,
-                parallel_streams: self.config.parallel_streams,
-            },
+            config: self.config.handshake_config(),
         };
 
         let mut buffer = Vec::with_capacity(512);
@@ -313,29 +426,45 @@ impl Carrier {
     ) -> Result<()> {
         let mut header = [0u8; 4];
         bi.1.read_exact(&mut header).await?;
-        let len = u32::from_be_bytes(header);
+        let len = u32::from_be_bytes(header) as usize;
+        if len > MAX_HANDSHAKE_LEN {
+            return Err(anyhow::Error::msg(format!(
+                "Handshake of {len} bytes exceeds the limit of {MAX_HANDSHAKE_LEN} bytes"
+            )));
+        }
 
-        let mut buffer = vec![0u8; len as usize];
+        let mut buffer = vec![0u8; len];
         bi.1.read_exact(&mut buffer).await?;
 
-        let handshake: ReceiverHandshake = serde_json::from_slice(&buffer)?;
+        let mut handshake: ReceiverHandshake = serde_json::from_slice(&buffer)?;
+        if let Some(e) = handshake
+            .profile
+            .strip_invalid_avatar(self.config.max_avatar_size)
+        {
+            self.log(format!("receive_handshake: Dropped peer avatar: {e}"));
+        }
 
         // Negotiate configuration
-        let sender_config = HandshakeConfig {
-            chunk_size: self.config.chunk_size,
-            parallel_streams: self.config.parallel_streams,
-        };
+        let sender_config = self.config.handshake_config();
 
-        self.negotiated_config = Some(NegotiatedConfig::negotiate(
-            &sender_config,
-            &handshake.config,
+        let negotiated_config =
+            NegotiatedConfig::negotiate(&sender_config, &handshake.config);
+        self.log(format!(
+            "receive_handshake: Negotiated capabilities: [{}]",
+            negotiated_config.capabilities
         ));
+        self.negotiated_config = Some(negotiated_config);
 
         // Notify subscribers
         let profile = SendFilesToReceiverProfile {
             id: handshake.profile.id,
             name: handshake.profile.name,
             avatar_b64: handshake.profile.avatar_b64,
+            node_id: self
+                .connection
+                .remote_node_id()
+                .ok()
+                .map(|id| id.to_string()),
         };
 
         self.subscribers
@@ -351,6 +480,73 @@ impl Carrier {
         Ok(())
     }
 
+    /// Groups the copies by the file they are a copy of, when both peers
+    /// support [`Capabilities::DEDUPLICATION`]. `None` otherwise, every file
+    /// is sent then.
+    fn copies_by_original(&self) -> Option<HashMap<String, Vec<File>>> {
+        let capabilities = self.negotiated_config.as_ref()?.capabilities;
+        if !capabilities.contains(Capabilities::DEDUPLICATION) {
+            return None;
+        }
+
+        let mut copies: HashMap<String, Vec<File>> = HashMap::new();
+        for file in &self.files {
+            if let Some(original) = self.copies.get(&file.id) {
+                copies
+                    .entry(original.clone())
+                    .or_default()
+                    .push(file.clone());
+            }
+        }
+        Some(copies)
+    }
+
+    /// Starts reading the acknowledgements of the receiver, when both peers
+    /// support [`Capabilities::ACKNOWLEDGEMENTS`], and notifies subscribers
+    /// of every file confirmed.
+    fn receive_acks(&self) -> Option<Arc<AckReader>> {
+        let capabilities = self.negotiated_config.as_ref()?.capabilities;
+        if !capabilities.contains(Capabilities::ACKNOWLEDGEMENTS) {
+            return None;
+        }
+
+        let acks = Arc::new(AckReader::default());
+        let names: HashMap<String, String> = self
+            .files
+            .iter()
+            .map(|f| (f.id.clone(), f.name.clone()))
+            .collect();
+        let reader = acks.clone();
+        let connection = self.connection.clone();
+        let subscribers = self.subscribers.clone();
+        tokio::spawn(
+            async move {
+                let on_ack =
+                    |id: &str| {
+                        let Some(name) = names.get(id) else {
+                            return;
+                        };
+                        let event = SendFilesToReceivedEvent {
+                            id: id.to_string(),
+                            name: name.clone(),
+                        };
+                        subscribers.read().unwrap().iter().for_each(
+                            |(_, s)| {
+                                s.notify_received(event.clone());
+                            },
+                        );
+                    };
+                if let Err(e) = reader.receive(&connection, on_ack).await {
+                    tracing::debug!(
+                        "receive_acks: Acknowledgements ended: {e}"
+                    );
+                }
+            }
+            .instrument(self.span.clone()),
+        );
+        Some(acks)
+    }
+
     /// Streams all files using unidirectional streams.
     async fn send_files(&self) -> Result<()> {
         let mut join_set = JoinSet::new();
@@ -361,74 +557,163 @@ impl Carrier {
             } else {
                 (self.config.chunk_size, self.config.parallel_streams)
             };
+        let rate = RateLimiter::from_rate(self.config.max_rate).map(Arc::new);
+        let concurrent_files = self.config.concurrent_files(parallel_streams);
+        let acks = self.receive_acks();
+        // Files and copies sent in full, to be acknowledged
+        let mut expected = HashSet::new();
 
+        let mut copies = self.copies_by_original();
         for file in self.files.clone() {
+            let file_copies = match &mut copies {
+                // Received along with the file it is a copy of
+                Some(_) if self.copies.contains_key(&file.id) => continue,
+                Some(copies) => copies.remove(&file.id).unwrap_or_default(),
+                None => Vec::new(),
+            };
             let connection = self.connection.clone();
             let subscribers = self.subscribers.clone();
+            let faults = self.config.faults();
+            let retry = self.config.retry.clone();
+            let rate = rate.clone();
+            let span = tracing::debug_span!(
+                parent: &self.span,
+                "send_file",
+                file_id = %file.id,
+            );
 
-            join_set.spawn(async move {
-                Self::send_single_file(
-                    &file,
-                    chunk_size,
-                    connection,
-                    subscribers,
-                )
-                .await
-            });
+            join_set.spawn(
+                async move {
+                    Self::send_single_file(
+                        &file,
+                        &file_copies,
+                        chunk_size,
+                        connection,
+                        subscribers,
+                        faults,
+                        retry,
+                        rate,
+                    )
+                    .await
+                }
+                .instrument(span),
+            );
 
-            if join_set.len() >= parallel_streams as usize
+            if join_set.len() >= concurrent_files
                 && let Some(result) = join_set.join_next().await
-                && let Err(err) = result?
             {
-                self.log(format!("send_files: Stream failed: {err}"));
-                return Err(err);
+                match result? {
+                    Ok(ids) => expected.extend(ids),
+                    Err(err) => {
+                        self.log(format!("send_files: Stream failed: {err}"));
+                        return Err(err);
+                    }
+                }
             }
         }
 
         while let Some(result) = join_set.join_next().await {
-            if let Err(err) = result? {
-                self.log(format!("send_single_file: Stream failed: {err}"));
-                return Err(err);
+            match result? {
+                Ok(ids) => expected.extend(ids),
+                Err(err) => {
+                    self.log(format!("send_single_file: Stream failed: {err}"));
+                    return Err(err);
+                }
             }
         }
 
+        if let Some(acks) = acks
+            && !acks.wait_for(&expected, ACK_TIMEOUT).await
+        {
+            self.log(
+                "send_files: Not all files were acknowledged by the receiver"
+                    .to_string(),
+            );
+        }
+
         self.log("send_files: All files transferred successfully".to_string());
         Ok(())
     }
 
-    /// Streams a single file in JSON-framed chunks.
+    /// Streams a single file in JSON-framed chunks. Returns the ids of the
+    /// file and its copies once sent in full, none if it was abandoned or is
+    /// empty.
     async fn send_single_file(
         file: &File,
+        copies: &[File],
         chunk_size: u64,
         connection: Connection,
         subscribers: Arc<
             RwLock<HashMap<String, Arc<dyn SendFilesToSubscriber>>>,
         >,
-    ) -> Result<()> {
+        faults: Faults,
+        retry: RetryConfig,
+        rate: Option<Arc<RateLimiter>>,
+    ) -> Result<Vec<String>> {
         let total_len = file.data.len();
         let mut sent = 0u64;
         let mut remaining = total_len;
         let mut chunk_buffer =
             Vec::with_capacity((chunk_size + 1024).try_into().unwrap());
 
-        let mut uni = connection.open_uni().await?;
+        let mut uni = retry
+            .run(
+                || {
+                    let connection = connection.clone();
+                    async move { Ok(connection.open_uni().await?) }
+                },
+                |attempt, err| {
+                    tracing::debug!(
+                        attempt,
+                        "send_single_file: Retrying to open stream: {err}"
+                    );
+                },
+            )
+            .await?;
 
-        Self::notify_progress(file, sent, remaining, subscribers.clone());
+        Self::notify_progress(
+            file,
+            copies,
+            sent,
+            remaining,
+            subscribers.clone(),
+        );
 
+        let mut index = 0u64;
         loop {
             chunk_buffer.clear();
 
             let chunk_data = file.data.read_chunk(chunk_size);
-            if chunk_data.is_empty() {
+            let is_eof = chunk_data.is_empty();
+            if (is_eof || index % SOURCE_CHECK_INTERVAL == 0)
+                && file.data.has_changed()
+            {
+                uni.reset(SOURCE_CHANGED_CODE.into())?;
+                tracing::debug!(
+                    "send_single_file: Source changed, file abandoned"
+                );
+                Self::notify_error(
+                    file,
+                    copies,
+                    SendFileError::SourceChanged,
+                    subscribers,
+                );
+                return Ok(Vec::new());
+            }
+            if is_eof {
                 break;
             }
-            let projection = FileProjection {
-                id: file.id.clone(),
-                data: chunk_data,
-            };
+            let projection = FileProjection::new(file.id.clone(), chunk_data);
 
             serde_json::to_writer(&mut chunk_buffer, &projection)?;
             let len_bytes = (chunk_buffer.len() as u32).to_be_bytes();
+            faults
+                .before_chunk(index, &mut chunk_buffer, &mut uni)
+                .await?;
+            index += 1;
+            if let Some(rate) = &rate {
+                rate.acquire(chunk_buffer.len() as u64).await;
+            }
 
             uni.write_all(&len_bytes).await?;
             uni.write_all(&chunk_buffer).await?;
@@ -436,28 +721,64 @@ impl Carrier {
             let data_len = projection.data.len() as u64;
             sent += data_len;
             remaining = remaining.saturating_sub(data_len);
-
-            Self::notify_progress(file, sent, remaining, subscribers.clone());
+            tracing::trace!(sent, remaining, "send_single_file: Chunk sent");
+
+            Self::notify_progress(
+                file,
+                copies,
+                sent,
+                remaining,
+                subscribers.clone(),
+            );
         }
 
         uni.finish()?;
         uni.stopped().await?;
 
-        Ok(())
+        // The receiver never learns the id of an empty file, it gets no chunk
+        if total_len == 0 {
+            return Ok(Vec::new());
+        }
+        Ok(iter::once(file)
+            .chain(copies)
+            .map(|f| f.id.clone())
+            .collect())
+    }
+
+    /// Records why the session ended and closes the connection with it. A
+    /// reason already known, e.g. of a cancellation, is kept and was already
+    /// notified to subscribers.
+    fn end(&self, reason: EndReason) {
+        let mut end_reason = self.end_reason.write().unwrap();
+        let is_known = end_reason.is_some();
+        let reason = *end_reason.get_or_insert(reason);
+        drop(end_reason);
+
+        self.log(format!("end: Closing connection, reason: {reason}"));
+        reason.close(&self.connection);
+        if is_known {
+            return;
+        }
+
+        self.subscribers
+            .read()
+            .unwrap()
+            .iter()
+            .for_each(|(_, subscriber)| {
+                subscriber.notify_ended(reason);
+            });
     }
 
-    /// Marks the transfer as finished and closes the connection and endpoint.
-    async fn finish(&self, endpoint: &Endpoint) {
+    /// Marks the transfer as finished and closes the connection, with the
+    /// code of the reason the session ended with, and the endpoint.
+    async fn finish(&self, reason: EndReason, endpoint: &Endpoint) {
         self.log("finish: Starting transfer finish process".to_string());
+        self.end(reason);
 
         self.is_finished
             .store(true, std::sync::atomic::Ordering::Relaxed);
         self.log("finish: Transfer finished flag set to true".to_string());
 
-        self.log("finish: Closing connection".to_string());
-        self.connection
-            .close(VarInt::from_u32(200), "finished".as_bytes());
-
         self.log("finish: Closing endpoint".to_string());
         endpoint.close().await;
 
@@ -465,6 +786,7 @@ impl Carrier {
     }
 
     fn log(&self, message: String) {
+        tracing::debug!(parent: &self.span, "{message}");
         self.subscribers.read().unwrap().iter().for_each(
             |(_id, subscriber)| {
                 subscriber.log(message.clone());
@@ -474,26 +796,47 @@ impl Carrier {
 
     fn notify_progress(
         file: &File,
+        copies: &[File],
         sent: u64,
         remaining: u64,
         subscribers: Arc<
             RwLock<HashMap<String, Arc<dyn SendFilesToSubscriber>>>,
         >,
     ) {
-        let event = SendFilesToSendingEvent {
-            id: file.id.clone(),
-            name: file.name.clone(),
-            sent,
-            remaining,
-        };
-
-        subscribers
-            .read()
-            .unwrap()
-            .iter()
-            .for_each(|(_, s)| {
+        let subscribers = subscribers.read().unwrap();
+        for file in iter::once(file).chain(copies) {
+            let event = SendFilesToSendingEvent {
+                id: file.id.clone(),
+                name: file.name.clone(),
+                sent,
+                remaining,
+            };
+            subscribers.iter().for_each(|(_, s)| {
                 s.notify_sending(event.clone());
             });
+        }
+    }
+
+    /// Notifies all subscribers that the file and its copies were abandoned.
+    fn notify_error(
+        file: &File,
+        copies: &[File],
+        error: SendFileError,
+        subscribers: Arc<
+            RwLock<HashMap<String, Arc<dyn SendFilesToSubscriber>>>,
+        >,
+    ) {
+        let subscribers = subscribers.read().unwrap();
+        for file in iter::once(file).chain(copies) {
+            let event = SendFilesToErrorEvent {
+                id: file.id.clone(),
+                name: file.name.clone(),
+                error,
+            };
+            subscribers.iter().for_each(|(_, s)| {
+                s.notify_error(event.clone());
+            });
+        }
     }
 }
 
@@ -546,11 +889,28 @@ pub async fn send_files_to(
 ) -> Result<SendFilesToBubble> {
     let ticket: NodeTicket = request.ticket.parse()?;
 
-    let endpoint_builder = Endpoint::builder().discovery_n0();
-    let endpoint = endpoint_builder.bind().await?;
-    let connection = endpoint
-        .connect(ticket, &[request.confirmation])
-        .await?;
+    let endpoint = request.config.endpoint.bind().await?;
+    let confirmation = request.confirmation;
+    let connection =
+        request
+            .config
+            .retry
+            .run(
+                || {
+                    let endpoint = endpoint.clone();
+                    let ticket = ticket.clone();
+                    async move {
+                        Ok(endpoint.connect(ticket, &[confirmation]).await?)
+                    }
+                },
+                |attempt, err| {
+                    tracing::debug!(
+                        attempt,
+                        "send_files_to: Retrying to connect: {err}"
+                    );
+                },
+            )
+            .await?;
 
     let profile = Profile {
         id: Uuid::new_v4().to_string(),
@@ -558,24 +918,48 @@ pub async fn send_files_to(
         avatar_b64: request.profile.avatar_b64,
     };
 
+    let originals = find_copies(&request.files);
+    let mut kinds = HashMap::new();
     let files: Vec<File> = request
         .files
         .into_iter()
         .map(|f| {
+            let id = Uuid::new_v4().to_string();
+            if !f.kind.is_file() {
+                kinds.insert(id.clone(), f.kind);
+            }
             let data = SenderFileDataAdapter { inner: f.data };
             File {
-                id: Uuid::new_v4().to_string(),
+                id,
                 name: f.name,
                 data: Arc::new(data),
             }
         })
         .collect();
+    let copies: HashMap<String, String> = originals
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, original)| {
+            Some((files[index].id.clone(), files[original?].id.clone()))
+        })
+        .collect();
 
     Ok(SendFilesToBubble::new(
         endpoint,
         connection,
         profile,
         files,
+        kinds,
+        copies,
         request.config,
     ))
 }
+
+/// Returns the node id of the receiver a ticket points to, e.g. to recognize
+/// the same receiver across sessions.
+///
+/// Errors if the ticket can't be parsed.
+pub fn ticket_node_id(ticket: &str) -> Result<String> {
+    let ticket: NodeTicket = ticket.parse()?;
+    Ok(ticket.node_addr().node_id.to_string())
+}
@@ -0,0 +1,133 @@
+//! Subscribers that asked for a minimum interval between progress events.
+//!
+//! The handlers keep notifying once per chunk; on subscribe, a subscriber
+//! with a `progress_interval` is wrapped in one of these types, which drop the
+//! sending events due before the interval passed and forward everything else.
+
+use std::{sync::Arc, time::Duration};
+
+use arkdropx_common::progress::ProgressThrottle;
+
+use crate::{
+    EndReason, SendFilesConnectingEvent, SendFilesErrorEvent,
+    SendFilesReceivedEvent, SendFilesSendingEvent, SendFilesSubscriber,
+    send_files_to::{
+        SendFilesToConnectingEvent, SendFilesToErrorEvent,
+        SendFilesToReceivedEvent, SendFilesToSendingEvent,
+        SendFilesToSubscriber,
+    },
+};
+
+/// Wraps the subscriber if it asked for throttled progress.
+pub(crate) fn throttle_send_files(
+    subscriber: Arc<dyn SendFilesSubscriber>,
+) -> Arc<dyn SendFilesSubscriber> {
+    match subscriber.progress_interval() {
+        Some(interval) => Arc::new(ThrottledSendFiles {
+            inner: subscriber,
+            throttle: ProgressThrottle::new(interval),
+        }),
+        None => subscriber,
+    }
+}
+
+/// Wraps the subscriber if it asked for throttled progress.
+pub(crate) fn throttle_send_files_to(
+    subscriber: Arc<dyn SendFilesToSubscriber>,
+) -> Arc<dyn SendFilesToSubscriber> {
+    match subscriber.progress_interval() {
+        Some(interval) => Arc::new(ThrottledSendFilesTo {
+            inner: subscriber,
+            throttle: ProgressThrottle::new(interval),
+        }),
+        None => subscriber,
+    }
+}
+
+struct ThrottledSendFiles {
+    inner: Arc<dyn SendFilesSubscriber>,
+    throttle: ProgressThrottle,
+}
+
+impl SendFilesSubscriber for ThrottledSendFiles {
+    fn get_id(&self) -> String {
+        self.inner.get_id()
+    }
+
+    fn log(&self, message: String) {
+        self.inner.log(message);
+    }
+
+    fn notify_sending(&self, event: SendFilesSendingEvent) {
+        if self
+            .throttle
+            .is_due(&event.id, event.remaining == 0)
+        {
+            self.inner.notify_sending(event);
+        }
+    }
+
+    fn notify_connecting(&self, event: SendFilesConnectingEvent) {
+        self.inner.notify_connecting(event);
+    }
+
+    fn notify_error(&self, event: SendFilesErrorEvent) {
+        self.inner.notify_error(event);
+    }
+
+    fn notify_received(&self, event: SendFilesReceivedEvent) {
+        self.inner.notify_received(event);
+    }
+
+    fn notify_ended(&self, reason: EndReason) {
+        self.inner.notify_ended(reason);
+    }
+
+    fn progress_interval(&self) -> Option<Duration> {
+        self.inner.progress_interval()
+    }
+}
+
+struct ThrottledSendFilesTo {
+    inner: Arc<dyn SendFilesToSubscriber>,
+    throttle: ProgressThrottle,
+}
+
+impl SendFilesToSubscriber for ThrottledSendFilesTo {
+    fn get_id(&self) -> String {
+        self.inner.get_id()
+    }
+
+    fn log(&self, message: String) {
+        self.inner.log(message);
+    }
+
+    fn notify_sending(&self, event: SendFilesToSendingEvent) {
+        if self
+            .throttle
+            .is_due(&event.id, event.remaining == 0)
+        {
+            self.inner.notify_sending(event);
+        }
+    }
+
+    fn notify_connecting(&self, event: SendFilesToConnectingEvent) {
+        self.inner.notify_connecting(event);
+    }
+
+    fn notify_error(&self, event: SendFilesToErrorEvent) {
+        self.inner.notify_error(event);
+    }
+
+    fn notify_received(&self, event: SendFilesToReceivedEvent) {
+        self.inner.notify_received(event);
+    }
+
+    fn notify_ended(&self, reason: EndReason) {
+        self.inner.notify_ended(reason);
+    }
+
+    fn progress_interval(&self) -> Option<Duration> {
+        self.inner.progress_interval()
+    }
+}
@@ -579,6 +579,7 @@ impl ConfigApp {
             avatar_name,
             avatar_file,
             out_dir,
+            ..AppConfig::load().unwrap_or_default()
         };
 
         match config.save() {
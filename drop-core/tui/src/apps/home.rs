@@ -259,21 +259,20 @@ impl HomeApp {
         };
 
         let config = self.b.get_config();
-        let profile = ReceiverProfile {
-            name: config
-                .avatar_name
-                .unwrap_or("Receiver".to_string()),
-            avatar_b64: None,
-        };
-
-        let request = ReadyToReceiveRequest {
-            profile,
-            config: ReadyToReceiveConfig::balanced(),
-        };
-
         self.b
             .get_ready_to_receive_manager()
-            .ready_to_receive(request);
+            .ready_to_receive(Arc::new(move || {
+                Some(ReadyToReceiveRequest {
+                    profile: ReceiverProfile {
+                        name: config
+                            .avatar_name
+                            .clone()
+                            .unwrap_or("Receiver".to_string()),
+                        avatar_b64: None,
+                    },
+                    config: ReadyToReceiveConfig::balanced(),
+                })
+            }));
         self.set_status_message("Starting Ready to Receive...");
         self.b
             .get_navigation()
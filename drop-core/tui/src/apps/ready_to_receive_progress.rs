@@ -1,18 +1,30 @@
 use std::{
-    collections::HashMap,
     fs,
-    io::Write,
-    sync::{Arc, RwLock, atomic::AtomicU32},
+    path::PathBuf,
+    sync::{Arc, RwLock},
     time::Instant,
 };
 
 use crate::{
-    App, AppBackend, ControlCapture,
-    utilities::{clipboard::copy_to_clipboard, qr_renderer::QrCodeRenderer},
+    App, AppBackend, ControlCapture, Page,
+    utilities::{
+        clipboard::copy_to_clipboard,
+        failure::{FailureAction, TransferFailure},
+        qr_renderer::QrCodeRenderer,
+    },
 };
-use arkdropx_receiver::ready_to_receive::{
-    ReadyToReceiveConnectingEvent, ReadyToReceiveReceivingEvent,
-    ReadyToReceiveSubscriber,
+use arkdrop_common::{
+    progress::{FileProgress, TransferProgress, TransferState},
+    received_file_path,
+    writer::{FileWriters, WriteError},
+};
+use arkdropx_receiver::{
+    DropUri, EndReason,
+    ready_to_receive::{
+        ReadyToReceiveConnectingEvent, ReadyToReceiveDiscardedEvent,
+        ReadyToReceiveReceivedEvent, ReadyToReceiveReceivingEvent,
+        ReadyToReceiveSubscriber,
+    },
 };
 use crossterm::event::KeyModifiers;
 use ratatui::{
@@ -26,42 +38,21 @@ use ratatui::{
 };
 use uuid::Uuid;
 
-#[derive(Clone)]
-struct ProgressFile {
-    id: String,
-    name: String,
-    len: u64,
-    received: u64,
-    last_update: Instant,
-    bytes_per_second: f64,
-    status: FileTransferStatus,
-}
-
-#[derive(Clone, PartialEq)]
-enum FileTransferStatus {
-    Waiting,
-    Receiving,
-    Completed,
-    Error(String),
-}
-
-impl FileTransferStatus {
-    fn icon(&self) -> &'static str {
-        match self {
-            FileTransferStatus::Waiting => "⏳",
-            FileTransferStatus::Receiving => "📥",
-            FileTransferStatus::Completed => "✅",
-            FileTransferStatus::Error(_) => "❌",
-        }
+fn state_icon(state: &TransferState) -> &'static str {
+    match state {
+        TransferState::Waiting => "⏳",
+        TransferState::Transferring => "📥",
+        TransferState::Completed => "✅",
+        TransferState::Failed(_) => "❌",
     }
+}
 
-    fn color(&self) -> Color {
-        match self {
-            FileTransferStatus::Waiting => Color::Gray,
-            FileTransferStatus::Receiving => Color::Cyan,
-            FileTransferStatus::Completed => Color::Green,
-            FileTransferStatus::Error(_) => Color::Red,
-        }
+fn state_color(state: &TransferState) -> Color {
+    match state {
+        TransferState::Waiting => Color::Gray,
+        TransferState::Transferring => Color::Cyan,
+        TransferState::Completed => Color::Green,
+        TransferState::Failed(_) => Color::Red,
     }
 }
 
@@ -69,7 +60,6 @@ pub struct ReadyToReceiveProgressApp {
     id: String,
     b: Arc<dyn AppBackend>,
 
-    progress_pct: AtomicU32,
     operation_start_time: RwLock<Option<Instant>>,
 
     title_text: RwLock<String>,
@@ -78,17 +68,28 @@ pub struct ReadyToReceiveProgressApp {
     log_text: RwLock<String>,
     error_message: RwLock<Option<String>>,
 
-    files: RwLock<HashMap<String, ProgressFile>>,
-    total_transfer_speed: RwLock<f64>,
+    files: RwLock<TransferProgress>,
     sender_name: RwLock<String>,
+    // Receive directory of the connected sender
+    out_dir: RwLock<Option<PathBuf>>,
     total_chunks_received: RwLock<u64>,
+    writers: FileWriters,
 
     // Copy feedback for T/Y clipboard shortcuts
     copy_feedback: RwLock<Option<(String, Instant)>>,
+
+    // Failure of the session, with its log
+    failure: TransferFailure,
 }
 
 impl App for ReadyToReceiveProgressApp {
     fn draw(&self, f: &mut Frame, area: ratatui::prelude::Rect) {
+        if self.failure.is_failed() {
+            self.failure
+                .draw(f, area, "Retry with a new ticket");
+            return;
+        }
+
         if self.has_transfer_started() {
             self.draw_receiving_mode(f, area);
         } else {
@@ -100,6 +101,10 @@ impl App for ReadyToReceiveProgressApp {
         &self,
         ev: &ratatui::crossterm::event::Event,
     ) -> Option<ControlCapture> {
+        if self.failure.is_failed() {
+            return self.handle_failure_control(ev);
+        }
+
         if let Event::Key(key) = ev {
             let has_ctrl = key.modifiers == KeyModifiers::CONTROL;
 
@@ -145,15 +150,14 @@ impl ReadyToReceiveSubscriber for ReadyToReceiveProgressApp {
     }
 
     fn log(&self, message: String) {
+        self.failure.log(&message);
         self.set_log_text(message.as_str());
     }
 
     fn notify_receiving(&self, event: ReadyToReceiveReceivingEvent) {
         self.increment_chunk_count();
         self.update_file(&event);
-        self.refresh_total_transfer_speed();
-        self.refresh_overall_progress();
-        self.write_file_to_fs(&event);
+        self.write_file_to_fs(event);
     }
 
     fn notify_connecting(&self, event: ReadyToReceiveConnectingEvent) {
@@ -167,6 +171,41 @@ impl ReadyToReceiveSubscriber for ReadyToReceiveProgressApp {
             format!("Receiving Files from {}", event.sender.name).as_str(),
         );
         *self.sender_name.write().unwrap() = event.sender.name.clone();
+        *self.out_dir.write().unwrap() =
+            Some(self.b.get_config().get_peer_out_dir(
+                event.sender.node_id.as_deref(),
+                &event.sender.name,
+            ));
+    }
+
+    fn notify_received(&self, event: ReadyToReceiveReceivedEvent) -> bool {
+        // Confirmed to the sender, which may delete the source, once synced
+        let synced = self.writers.sync(&event.id, event.modified);
+        self.report_write_errors(self.writers.take_errors());
+        synced
+    }
+
+    fn notify_discarded(&self, event: ReadyToReceiveDiscardedEvent) {
+        let name = self
+            .files
+            .read()
+            .unwrap()
+            .file(&event.id)
+            .map(|file| file.name.clone());
+        if let Some(name) = name
+            && let Ok(path) = received_file_path(&self.get_out_dir(), &name)
+        {
+            self.writers.discard(&event.id, &path);
+        }
+        self.set_file_error(
+            &event.id,
+            "Failed verification, discarded".to_string(),
+        );
+    }
+
+    fn notify_ended(&self, reason: EndReason) {
+        self.report_write_errors(self.writers.finish_all());
+        self.failure.end(reason);
     }
 }
 
@@ -176,7 +215,6 @@ impl ReadyToReceiveProgressApp {
             id: Uuid::new_v4().to_string(),
             b,
 
-            progress_pct: AtomicU32::new(0),
             operation_start_time: RwLock::new(None),
 
             title_text: RwLock::new("📥 Waiting for Sender".to_string()),
@@ -185,17 +223,18 @@ impl ReadyToReceiveProgressApp {
             log_text: RwLock::new("Initializing...".to_string()),
             error_message: RwLock::new(None),
 
-            files: RwLock::new(HashMap::new()),
-            total_transfer_speed: RwLock::new(0.0),
+            files: RwLock::new(TransferProgress::new()),
             sender_name: RwLock::new(String::new()),
+            out_dir: RwLock::new(None),
             total_chunks_received: RwLock::new(0),
+            writers: FileWriters::new(),
             copy_feedback: RwLock::new(None),
+
+            failure: TransferFailure::new(),
         }
     }
 
     fn reset(&self) {
-        self.progress_pct
-            .store(0, std::sync::atomic::Ordering::Relaxed);
         *self.operation_start_time.write().unwrap() = None;
         *self.title_text.write().unwrap() = "📥 Waiting for Sender".to_string();
         *self.block_title_text.write().unwrap() =
@@ -205,10 +244,64 @@ impl ReadyToReceiveProgressApp {
         *self.log_text.write().unwrap() = "Initializing...".to_string();
         *self.error_message.write().unwrap() = None;
         self.files.write().unwrap().clear();
-        *self.total_transfer_speed.write().unwrap() = 0.0;
         *self.sender_name.write().unwrap() = String::new();
+        *self.out_dir.write().unwrap() = None;
         *self.total_chunks_received.write().unwrap() = 0;
         *self.copy_feedback.write().unwrap() = None;
+        self.failure.reset();
+    }
+
+    fn handle_failure_control(&self, ev: &Event) -> Option<ControlCapture> {
+        let Event::Key(key) = ev else {
+            return None;
+        };
+        if key.modifiers == KeyModifiers::CONTROL {
+            return None;
+        }
+
+        match FailureAction::from_key(key.code)? {
+            FailureAction::Retry => {
+                self.discard_received_files();
+                self.reset();
+                self.b.get_ready_to_receive_manager().retry();
+            }
+            FailureAction::Reconfigure => {
+                self.reset();
+                self.b.get_navigation().replace_with(Page::Config);
+            }
+            FailureAction::ToggleLogs => self.failure.toggle_logs(),
+            FailureAction::Home => {
+                self.reset();
+                self.b
+                    .get_navigation()
+                    .navigate_fresh_to(Page::Home);
+            }
+        }
+
+        Some(ControlCapture::new(ev))
+    }
+
+    /// Removes the files written by the failed session, as a retried
+    /// session receives them again from the start.
+    fn discard_received_files(&self) {
+        let out_dir = self.get_out_dir();
+        for file in self
+            .get_files()
+            .iter()
+            .filter(|file| file.done > 0)
+        {
+            if let Ok(path) = received_file_path(&out_dir, &file.name) {
+                let _ = fs::remove_file(path);
+            }
+        }
+    }
+
+    fn get_out_dir(&self) -> PathBuf {
+        self.out_dir
+            .read()
+            .unwrap()
+            .clone()
+            .unwrap_or_else(|| self.b.get_config().get_out_dir())
     }
 
     fn has_transfer_started(&self) -> bool {
@@ -251,23 +344,15 @@ impl ReadyToReceiveProgressApp {
     }
 
     fn get_progress_pct(&self) -> f64 {
-        let v = self
-            .progress_pct
-            .load(std::sync::atomic::Ordering::Relaxed);
-        f64::from(v)
+        self.files.read().unwrap().percent()
     }
 
-    fn get_files(&self) -> Vec<ProgressFile> {
-        self.files
-            .read()
-            .unwrap()
-            .values()
-            .cloned()
-            .collect()
+    fn get_files(&self) -> Vec<FileProgress> {
+        self.files.read().unwrap().files().to_vec()
     }
 
     fn get_total_transfer_speed(&self) -> f64 {
-        *self.total_transfer_speed.read().unwrap()
+        self.files.read().unwrap().bytes_per_second()
     }
 
     fn increment_chunk_count(&self) {
@@ -276,25 +361,8 @@ impl ReadyToReceiveProgressApp {
     }
 
     fn update_file(&self, event: &ReadyToReceiveReceivingEvent) {
-        let now = Instant::now();
-        let mut files = self.files.write().unwrap();
-
-        if let Some(file) = files.get_mut(&event.id) {
-            let time_diff = now.duration_since(file.last_update).as_secs_f64();
-            let bytes_received = event.data.len() as u64;
-
-            if time_diff > 0.0 {
-                file.bytes_per_second = bytes_received as f64 / time_diff;
-            }
-
-            file.received += bytes_received;
-            file.last_update = now;
-
-            if file.received >= file.len {
-                file.status = FileTransferStatus::Completed;
-            } else {
-                file.status = FileTransferStatus::Receiving;
-            }
+        if let Some(file) = self.files.write().unwrap().file_mut(&event.id) {
+            file.advance(event.data.len() as u64);
         }
     }
 
@@ -303,94 +371,46 @@ impl ReadyToReceiveProgressApp {
         files.clear();
 
         for file in &event.files {
-            files.insert(
-                file.id.clone(),
-                ProgressFile {
-                    id: file.id.clone(),
-                    name: file.name.clone(),
-                    len: file.len,
-                    received: 0,
-                    last_update: Instant::now(),
-                    bytes_per_second: 0.0,
-                    status: FileTransferStatus::Waiting,
-                },
-            );
+            files.add_file(file.id.clone(), file.name.clone(), file.len);
         }
     }
 
-    fn refresh_total_transfer_speed(&self) {
-        let files = self.files.read().unwrap();
-        let total_speed: f64 = files
-            .values()
-            .filter(|f| f.status == FileTransferStatus::Receiving)
-            .map(|f| f.bytes_per_second)
-            .sum();
-        *self.total_transfer_speed.write().unwrap() = total_speed;
-    }
-
-    fn refresh_overall_progress(&self) {
-        let files = self.files.read().unwrap();
-        let total_size: u64 = files.values().map(|f| f.len).sum();
-        let total_received: u64 = files.values().map(|f| f.received).sum();
+    fn write_file_to_fs(&self, event: ReadyToReceiveReceivingEvent) {
+        let out_dir = self.get_out_dir();
+        let name = self
+            .files
+            .read()
+            .unwrap()
+            .file(&event.id)
+            .map(|f| f.name.clone());
 
-        let progress_pct = if total_size > 0 {
-            ((total_received as f64 / total_size as f64) * 100.0).min(100.0)
-        } else {
-            0.0
-        };
+        if let Some(name) = name {
+            let file_path = match received_file_path(&out_dir, &name) {
+                Ok(path) => path,
+                Err(e) => {
+                    self.set_file_error(&event.id, e.to_string());
+                    return;
+                }
+            };
 
-        self.progress_pct
-            .store(progress_pct as u32, std::sync::atomic::Ordering::Relaxed);
+            self.writers
+                .write(&event.id, &file_path, event.data);
+            self.report_write_errors(self.writers.take_errors());
+        }
     }
 
-    fn write_file_to_fs(&self, event: &ReadyToReceiveReceivingEvent) {
-        let out_dir = self.b.get_config().get_out_dir();
-        let file_name = {
-            let files = self.files.read().unwrap();
-            files.get(&event.id).map(|f| f.name.clone())
-        };
-
-        if let Some(name) = file_name {
-            let file_path = out_dir.join(&name);
-
-            // Create parent directories if needed
-            if let Some(parent) = file_path.parent()
-                && let Err(e) = fs::create_dir_all(parent)
-            {
-                self.set_file_error(
-                    &event.id,
-                    format!("Failed to create directory: {}", e),
-                );
-                return;
-            }
-
-            let mut options = fs::OpenOptions::new();
-            options.create(true).append(true);
-
-            match options.open(&file_path) {
-                Ok(mut file) => {
-                    if let Err(e) = file.write_all(&event.data) {
-                        self.set_file_error(
-                            &event.id,
-                            format!("Failed to write data: {}", e),
-                        );
-                    }
-                }
-                Err(e) => {
-                    self.set_file_error(
-                        &event.id,
-                        format!("Failed to open file: {}", e),
-                    );
-                }
-            }
+    fn report_write_errors(&self, errors: Vec<WriteError>) {
+        for e in errors {
+            self.log(format!("[ERROR] {e}"));
+            self.set_file_error(&e.id, e.to_string());
         }
     }
 
     fn set_file_error(&self, file_id: &str, error: String) {
         // Update the file's status to Error
         let mut files = self.files.write().unwrap();
-        if let Some(file) = files.get_mut(file_id) {
-            file.status = FileTransferStatus::Error(error.clone());
+        if let Some(file) = files.file_mut(file_id) {
+            file.fail(error.clone());
         }
         // Also set global error message for UI display
         *self.error_message.write().unwrap() = Some(error);
@@ -525,13 +545,17 @@ impl ReadyToReceiveProgressApp {
             .get_ready_to_receive_manager()
             .get_ready_to_receive_bubble()
         {
-            let qr_data = format!(
-                "drop://send?ticket={}&confirmation={}",
-                bubble.get_ticket(),
-                bubble.get_confirmation()
-            );
+            let qr_data =
+                DropUri::send(bubble.get_ticket(), bubble.get_confirmation())
+                    .to_string();
 
-            QrCodeRenderer::render_qr_code(f, area, qr_block, &qr_data);
+            QrCodeRenderer::render_qr_code(
+                f,
+                area,
+                qr_block,
+                &qr_data,
+                &bubble.get_ticket(),
+            );
         } else {
             QrCodeRenderer::render_waiting(
                 f,
@@ -674,7 +698,7 @@ impl ReadyToReceiveProgressApp {
         let files = self.get_files();
         let completed_files = files
             .iter()
-            .filter(|f| f.status == FileTransferStatus::Completed)
+            .filter(|f| f.state == TransferState::Completed)
             .count();
         let total_files = files.len();
 
@@ -728,8 +752,8 @@ impl ReadyToReceiveProgressApp {
     ) {
         let progress_pct = self.get_progress_pct();
         let files = self.get_files();
-        let total_size: u64 = files.iter().map(|f| f.len).sum();
-        let total_received: u64 = files.iter().map(|f| f.received).sum();
+        let total_size: u64 = files.iter().map(|f| f.total).sum();
+        let total_received: u64 = files.iter().map(|f| f.done).sum();
         let transfer_speed = self.get_total_transfer_speed();
 
         let chunks = Layout::default()
@@ -804,22 +828,18 @@ impl ReadyToReceiveProgressApp {
         let file_items: Vec<ListItem> = files
             .iter()
             .map(|file| {
-                let progress_pct = if file.len > 0 {
-                    (file.received as f64 / file.len as f64) * 100.0
-                } else {
-                    0.0
-                };
+                let progress_pct = file.percent();
 
-                let name_color = match &file.status {
-                    FileTransferStatus::Completed => Color::Green,
-                    FileTransferStatus::Error(_) => Color::Red,
+                let name_color = match &file.state {
+                    TransferState::Completed => Color::Green,
+                    TransferState::Failed(_) => Color::Red,
                     _ => Color::White,
                 };
 
                 let status_line = Line::from(vec![
                     Span::styled(
-                        format!("{} ", file.status.icon()),
-                        Style::default().fg(file.status.color()),
+                        format!("{} ", state_icon(&file.state)),
+                        Style::default().fg(state_color(&file.state)),
                     ),
                     Span::styled(
                         file.name.clone(),
@@ -831,30 +851,30 @@ impl ReadyToReceiveProgressApp {
                     ),
                 ]);
 
-                let detail_text = match &file.status {
-                    FileTransferStatus::Receiving => format!(
+                let detail_text = match &file.state {
+                    TransferState::Transferring => format!(
                         "{} / {} • {}",
-                        self.format_bytes(file.received),
-                        self.format_bytes(file.len),
-                        self.format_speed(file.bytes_per_second)
+                        self.format_bytes(file.done),
+                        self.format_bytes(file.total),
+                        self.format_speed(file.bytes_per_second())
                     ),
-                    FileTransferStatus::Completed => format!(
+                    TransferState::Completed => format!(
                         "{} / {} • Complete",
-                        self.format_bytes(file.received),
-                        self.format_bytes(file.len)
+                        self.format_bytes(file.done),
+                        self.format_bytes(file.total)
                     ),
-                    FileTransferStatus::Error(err) => {
+                    TransferState::Failed(err) => {
                         format!("Error: {}", truncate_string(err, 40))
                     }
-                    FileTransferStatus::Waiting => format!(
+                    TransferState::Waiting => format!(
                         "{} / {} • --",
-                        self.format_bytes(file.received),
-                        self.format_bytes(file.len)
+                        self.format_bytes(file.done),
+                        self.format_bytes(file.total)
                     ),
                 };
 
-                let detail_color = match &file.status {
-                    FileTransferStatus::Error(_) => Color::Red,
+                let detail_color = match &file.state {
+                    TransferState::Failed(_) => Color::Red,
                     _ => Color::Gray,
                 };
 
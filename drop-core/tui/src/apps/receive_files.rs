@@ -2,6 +2,7 @@ use crate::{
     App, AppBackend, BrowserMode, ControlCapture, OpenFileBrowserRequest, Page,
     SortMode,
 };
+use arkdrop_common::AppConfig;
 use arkdropx_receiver::{ReceiveFilesRequest, ReceiverProfile};
 use ratatui::{
     Frame,
@@ -558,37 +559,25 @@ impl ReceiveFilesApp {
     }
 
     fn receive_files(&self) {
-        if let Some(req) = self.make_receive_files_request() {
-            self.set_status_message("Starting file reception...");
-            self.b
-                .get_receive_files_manager()
-                .receive_files(req);
-            self.b
-                .get_navigation()
-                .navigate_to(Page::ReceiveFilesProgress);
-        } else {
+        if !self.can_receive() {
             self.set_status_message(
                 "Missing required information - check ticket and confirmation",
             );
-        }
-    }
-
-    fn make_receive_files_request(&self) -> Option<ReceiveFilesRequest> {
-        if !self.can_receive() {
-            return None;
+            return;
         }
 
+        let ticket = self.get_ticket_in();
+        let confirmation = self.get_confirmation_in();
         let config = self.b.get_config();
-
-        Some(ReceiveFilesRequest {
-            ticket: self.get_ticket_in(),
-            confirmation: self.get_confirmation_in().parse().unwrap(),
-            profile: ReceiverProfile {
-                name: config.get_avatar_name(),
-                avatar_b64: config.get_avatar_base64(),
-            },
-            config: None,
-        })
+        self.set_status_message("Starting file reception...");
+        self.b
+            .get_receive_files_manager()
+            .receive_files(Arc::new(move || {
+                make_receive_files_request(&ticket, &confirmation, &config)
+            }));
+        self.b
+            .get_navigation()
+            .navigate_to(Page::ReceiveFilesProgress);
     }
 
     fn set_status_message(&self, message: &str) {
@@ -1270,3 +1259,19 @@ impl ReceiveFilesApp {
         f.render_widget(receive_button, area);
     }
 }
+
+fn make_receive_files_request(
+    ticket: &str,
+    confirmation: &str,
+    config: &AppConfig,
+) -> Option<ReceiveFilesRequest> {
+    Some(ReceiveFilesRequest {
+        ticket: ticket.to_string(),
+        confirmation: confirmation.parse().ok()?,
+        profile: ReceiverProfile {
+            name: config.get_avatar_name(),
+            avatar_b64: config.get_avatar_base64(),
+        },
+        config: None,
+    })
+}
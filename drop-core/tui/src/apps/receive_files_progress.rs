@@ -1,13 +1,23 @@
 use std::{
-    collections::HashMap,
     fs,
-    io::Write,
-    sync::{Arc, RwLock, atomic::AtomicU32},
+    path::PathBuf,
+    sync::{Arc, RwLock},
     time::Instant,
 };
 
-use crate::{App, AppBackend, ControlCapture};
-use arkdropx_receiver::{ReceiveFilesConnectingEvent, ReceiveFilesSubscriber};
+use crate::{
+    App, AppBackend, ControlCapture, Page,
+    utilities::failure::{FailureAction, TransferFailure},
+};
+use arkdrop_common::{
+    progress::{FileProgress, TransferProgress, TransferState},
+    received_file_path,
+    writer::{FileWriters, WriteError},
+};
+use arkdropx_receiver::{
+    EndReason, ReceiveFilesConnectingEvent, ReceiveFilesDiscardedEvent,
+    ReceiveFilesReceivedEvent, ReceiveFilesSubscriber,
+};
 use crossterm::event::KeyModifiers;
 use ratatui::{
     Frame,
@@ -20,38 +30,21 @@ use ratatui::{
 };
 use uuid::Uuid;
 
-#[derive(Clone)]
-struct ProgressFile {
-    name: String,
-    len: u64,
-    received: u64,
-    last_update: Instant,
-    bytes_per_second: f64,
-    status: FileTransferStatus,
-}
-
-#[derive(Clone, PartialEq)]
-enum FileTransferStatus {
-    Waiting,
-    Receiving,
-    Completed,
-}
-
-impl FileTransferStatus {
-    fn icon(&self) -> &'static str {
-        match self {
-            FileTransferStatus::Waiting => "⏳",
-            FileTransferStatus::Receiving => "📥",
-            FileTransferStatus::Completed => "✅",
-        }
+fn state_icon(state: &TransferState) -> &'static str {
+    match state {
+        TransferState::Waiting => "⏳",
+        TransferState::Transferring => "📥",
+        TransferState::Completed => "✅",
+        TransferState::Failed(_) => "❌",
     }
+}
 
-    fn color(&self) -> Color {
-        match self {
-            FileTransferStatus::Waiting => Color::Gray,
-            FileTransferStatus::Receiving => Color::Blue,
-            FileTransferStatus::Completed => Color::Green,
-        }
+fn state_color(state: &TransferState) -> Color {
+    match state {
+        TransferState::Waiting => Color::Gray,
+        TransferState::Transferring => Color::Blue,
+        TransferState::Completed => Color::Green,
+        TransferState::Failed(_) => Color::Red,
     }
 }
 
@@ -59,7 +52,6 @@ pub struct ReceiveFilesProgressApp {
     id: String,
     b: Arc<dyn AppBackend>,
 
-    progress_pct: AtomicU32,
     operation_start_time: RwLock<Option<Instant>>,
 
     title_text: RwLock<String>,
@@ -67,14 +59,25 @@ pub struct ReceiveFilesProgressApp {
     status_text: RwLock<String>,
     log_text: RwLock<String>,
 
-    files: RwLock<HashMap<String, ProgressFile>>,
-    total_transfer_speed: RwLock<f64>,
+    files: RwLock<TransferProgress>,
     sender_name: RwLock<String>,
+    // Receive directory of the connected sender
+    out_dir: RwLock<Option<PathBuf>>,
     total_chunks_received: RwLock<u64>,
+    writers: FileWriters,
+
+    // Failure of the session, with its log
+    failure: TransferFailure,
 }
 
 impl App for ReceiveFilesProgressApp {
     fn draw(&self, f: &mut Frame, area: ratatui::prelude::Rect) {
+        if self.failure.is_failed() {
+            self.failure
+                .draw(f, area, "Retry with same ticket");
+            return;
+        }
+
         let blocks = Layout::default()
             .direction(Direction::Vertical)
             .margin(1)
@@ -96,6 +99,10 @@ impl App for ReceiveFilesProgressApp {
         &self,
         ev: &ratatui::crossterm::event::Event,
     ) -> Option<ControlCapture> {
+        if self.failure.is_failed() {
+            return self.handle_failure_control(ev);
+        }
+
         if let Event::Key(key) = ev {
             let has_ctrl = key.modifiers == KeyModifiers::CONTROL;
 
@@ -130,6 +137,7 @@ impl ReceiveFilesSubscriber for ReceiveFilesProgressApp {
     }
 
     fn log(&self, message: String) {
+        self.failure.log(&message);
         self.set_log_text(message.as_str());
     }
 
@@ -139,9 +147,7 @@ impl ReceiveFilesSubscriber for ReceiveFilesProgressApp {
     ) {
         self.increment_chunk_count();
         self.update_file(&event);
-        self.refresh_total_transfer_speed();
-        self.refresh_overall_progress();
-        self.write_file_to_fs(&event);
+        self.write_file_to_fs(event);
     }
 
     fn notify_connecting(&self, event: ReceiveFilesConnectingEvent) {
@@ -155,6 +161,38 @@ impl ReceiveFilesSubscriber for ReceiveFilesProgressApp {
             format!("Receiving Files from {}", &event.sender.name).as_str(),
         );
         self.set_sender_name(event.sender.name.as_str());
+        *self.out_dir.write().unwrap() =
+            Some(self.b.get_config().get_peer_out_dir(
+                event.sender.node_id.as_deref(),
+                &event.sender.name,
+            ));
+    }
+
+    fn notify_received(&self, event: ReceiveFilesReceivedEvent) -> bool {
+        // Confirmed to the sender, which may delete the source, once synced
+        let synced = self.writers.sync(&event.id, event.modified);
+        self.report_write_errors(self.writers.take_errors());
+        synced
+    }
+
+    fn notify_discarded(&self, event: ReceiveFilesDiscardedEvent) {
+        let name = self
+            .files
+            .read()
+            .unwrap()
+            .file(&event.id)
+            .map(|file| file.name.clone());
+        if let Some(name) = name
+            && let Ok(path) = received_file_path(&self.get_out_dir(), &name)
+        {
+            self.writers.discard(&event.id, &path);
+        }
+        self.fail_file(&event.id, "Failed verification, discarded".to_string());
+    }
+
+    fn notify_ended(&self, reason: EndReason) {
+        self.report_write_errors(self.writers.finish_all());
+        self.failure.end(reason);
     }
 }
 
@@ -164,7 +202,6 @@ impl ReceiveFilesProgressApp {
             id: Uuid::new_v4().to_string(),
             b,
 
-            progress_pct: AtomicU32::new(0),
             operation_start_time: RwLock::new(None),
 
             title_text: RwLock::new("📥 Receiving Files".to_string()),
@@ -172,10 +209,13 @@ impl ReceiveFilesProgressApp {
             status_text: RwLock::new("Waiting for Sender".to_string()),
             log_text: RwLock::new("Initializing transfer...".to_string()),
 
-            files: RwLock::new(HashMap::new()),
-            total_transfer_speed: RwLock::new(0.0),
+            files: RwLock::new(TransferProgress::new()),
             sender_name: RwLock::new("Unknown".to_string()),
+            out_dir: RwLock::new(None),
             total_chunks_received: RwLock::new(0),
+            writers: FileWriters::new(),
+
+            failure: TransferFailure::new(),
         }
     }
 
@@ -185,17 +225,7 @@ impl ReceiveFilesProgressApp {
         files.clear();
 
         ev.files.iter().for_each(|f| {
-            files.insert(
-                f.id.clone(),
-                ProgressFile {
-                    name: f.name.clone(),
-                    status: FileTransferStatus::Waiting,
-                    len: f.len,
-                    received: 0,
-                    last_update: Instant::now(),
-                    bytes_per_second: 0.0,
-                },
-            );
+            files.add_file(f.id.clone(), f.name.clone(), f.len);
         });
     }
 
@@ -234,32 +264,32 @@ impl ReceiveFilesProgressApp {
         self.block_title_text.read().unwrap().clone()
     }
 
+    fn get_out_dir(&self) -> PathBuf {
+        self.out_dir
+            .read()
+            .unwrap()
+            .clone()
+            .unwrap_or_else(|| self.b.get_config().get_out_dir())
+    }
+
     fn get_sender_name(&self) -> String {
         self.sender_name.read().unwrap().clone()
     }
 
     fn get_progress_pct(&self) -> f64 {
-        let v = self
-            .progress_pct
-            .load(std::sync::atomic::Ordering::Relaxed);
-        f64::from(v)
+        self.files.read().unwrap().percent()
     }
 
     fn get_operation_start_time(&self) -> Option<Instant> {
         *self.operation_start_time.read().unwrap()
     }
 
-    fn get_files(&self) -> Vec<ProgressFile> {
-        self.files
-            .read()
-            .unwrap()
-            .values()
-            .cloned()
-            .collect()
+    fn get_files(&self) -> Vec<FileProgress> {
+        self.files.read().unwrap().files().to_vec()
     }
 
     fn get_total_transfer_speed(&self) -> f64 {
-        *self.total_transfer_speed.read().unwrap()
+        self.files.read().unwrap().bytes_per_second()
     }
 
     fn get_total_chunks_received(&self) -> u64 {
@@ -295,7 +325,7 @@ impl ReceiveFilesProgressApp {
         let files = self.get_files();
         let completed_files = files
             .iter()
-            .filter(|f| f.status == FileTransferStatus::Completed)
+            .filter(|f| f.state == TransferState::Completed)
             .count();
         let total_files = files.len();
         let total_chunks = self.get_total_chunks_received();
@@ -350,8 +380,8 @@ impl ReceiveFilesProgressApp {
     ) {
         let progress_pct = self.get_progress_pct();
         let files = self.get_files();
-        let total_size: u64 = files.iter().map(|f| f.len).sum();
-        let total_received: u64 = files.iter().map(|f| f.received).sum();
+        let total_size: u64 = files.iter().map(|f| f.total).sum();
+        let total_received: u64 = files.iter().map(|f| f.done).sum();
         let transfer_speed = self.get_total_transfer_speed();
 
         let chunks = Layout::default()
@@ -415,20 +445,11 @@ impl ReceiveFilesProgressApp {
             "00:00".to_string()
         };
 
-        let estimated_remaining = if progress_pct > 0.0
-            && progress_pct < 100.0
-            && transfer_speed > 0.0
-            && total_size > 0
-        {
-            let remaining_bytes = total_size.saturating_sub(total_received);
-            let remaining_secs = remaining_bytes as f64 / transfer_speed;
-            format!(
-                "{}:{:02}",
-                (remaining_secs as u64) / 60,
-                (remaining_secs as u64) % 60
-            )
-        } else {
-            "--:--".to_string()
+        let estimated_remaining = match self.files.read().unwrap().eta() {
+            Some(eta) if progress_pct > 0.0 && progress_pct < 100.0 => {
+                format!("{}:{:02}", eta.as_secs() / 60, eta.as_secs() % 60)
+            }
+            _ => "--:--".to_string(),
         };
 
         let stats_content = vec![
@@ -500,8 +521,7 @@ impl ReceiveFilesProgressApp {
         let file_items: Vec<ListItem> = files
             .iter()
             .map(|file| {
-                let progress_pct =
-                    (file.received as f64 / file.len as f64) * 100.0;
+                let progress_pct = file.percent();
 
                 // Create a mini progress bar using Unicode blocks
                 let progress_width = 20.0;
@@ -520,8 +540,8 @@ impl ReceiveFilesProgressApp {
 
                 let status_line = Line::from(vec![
                     Span::styled(
-                        format!("{} ", file.status.icon()),
-                        Style::default().fg(file.status.color()),
+                        format!("{} ", state_icon(&file.state)),
+                        Style::default().fg(state_color(&file.state)),
                     ),
                     Span::styled(
                         format!("{:<25}", file_name),
@@ -541,36 +561,25 @@ impl ReceiveFilesProgressApp {
                     ),
                 ]);
 
-                let detail_text = if file.len > 0 {
+                let rate = match file.state {
+                    TransferState::Transferring => {
+                        self.format_speed(file.bytes_per_second())
+                    }
+                    TransferState::Completed => "Complete".to_string(),
+                    _ => "--".to_string(),
+                };
+                let detail_text = if file.total > 0 {
                     format!(
                         "{} / {} • {}",
-                        self.format_bytes(file.received),
-                        self.format_bytes(file.len),
-                        if file.status == FileTransferStatus::Receiving {
-                            self.format_speed(file.bytes_per_second)
-                        } else {
-                            match file.status {
-                                FileTransferStatus::Completed => {
-                                    "Complete".to_string()
-                                }
-                                _ => "--".to_string(),
-                            }
-                        }
+                        self.format_bytes(file.done),
+                        self.format_bytes(file.total),
+                        rate
                     )
                 } else {
                     format!(
                         "{} received • {}",
-                        self.format_bytes(file.received),
-                        if file.status == FileTransferStatus::Receiving {
-                            self.format_speed(file.bytes_per_second)
-                        } else {
-                            match file.status {
-                                FileTransferStatus::Completed => {
-                                    "Complete".to_string()
-                                }
-                                _ => "--".to_string(),
-                            }
-                        }
+                        self.format_bytes(file.done),
+                        rate
                     )
                 };
 
@@ -664,97 +673,100 @@ impl ReceiveFilesProgressApp {
         &self,
         event: &arkdropx_receiver::ReceiveFilesReceivingEvent,
     ) {
-        let now = Instant::now();
-        let mut files = self.files.write().unwrap();
-
-        if let Some(file) = files.get_mut(&event.id) {
-            let chunk_size = event.data.len() as u64;
-
-            // Update existing file progress
-            let time_diff = now.duration_since(file.last_update).as_secs_f64();
-
-            // Calculate transfer speed based on chunk size and time difference
-            if time_diff > 0.0 {
-                // Use exponential moving average for smoother speed calculation
-                let instant_speed = chunk_size as f64 / time_diff;
-                file.bytes_per_second = if file.bytes_per_second == 0.0 {
-                    instant_speed
-                } else {
-                    // 70% old speed + 30% new speed for smoothing
-                    file.bytes_per_second * 0.7 + instant_speed * 0.3
-                };
-            }
-
-            file.received += chunk_size;
-            file.last_update = now;
-
-            // Update status based on progress
-            if file.received >= file.len {
-                file.status = FileTransferStatus::Completed;
-            } else {
-                file.status = FileTransferStatus::Receiving;
-            }
+        if let Some(file) = self.files.write().unwrap().file_mut(&event.id) {
+            file.advance(event.data.len() as u64);
         }
     }
 
-    fn refresh_total_transfer_speed(&self) {
-        let total_speed: f64 = self
+    fn write_file_to_fs(
+        &self,
+        event: arkdropx_receiver::ReceiveFilesReceivingEvent,
+    ) {
+        let out_dir = self.get_out_dir();
+        let Some(name) = self
             .files
             .read()
             .unwrap()
-            .values()
-            .filter(|f| f.status == FileTransferStatus::Receiving)
-            .map(|f| f.bytes_per_second)
-            .sum();
-        *self.total_transfer_speed.write().unwrap() = total_speed;
+            .file(&event.id)
+            .map(|file| file.name.clone())
+        else {
+            return;
+        };
+
+        let file_path = match received_file_path(&out_dir, &name) {
+            Ok(path) => path,
+            Err(e) => {
+                self.fail_file(&event.id, e.to_string());
+                return;
+            }
+        };
+        self.writers
+            .write(&event.id, &file_path, event.data);
+        self.report_write_errors(self.writers.take_errors());
     }
 
-    fn refresh_overall_progress(&self) {
-        let files = self.files.read().unwrap();
-        let total_files_size: u64 = files.values().map(|f| f.len).sum();
-        let total_received_size: u64 = files.values().map(|f| f.received).sum();
+    fn report_write_errors(&self, errors: Vec<WriteError>) {
+        for e in errors {
+            self.fail_file(&e.id, e.to_string());
+        }
+    }
 
-        let progress_pct =
-            ((total_received_size as f64 / total_files_size as f64) * 100.0)
-                .min(100.0);
+    fn fail_file(&self, file_id: &str, error: String) {
+        self.log(format!("[ERROR] {error}"));
+        if let Some(file) = self.files.write().unwrap().file_mut(file_id) {
+            file.fail(error);
+        }
+    }
 
-        self.progress_pct
-            .store(progress_pct as u32, std::sync::atomic::Ordering::Relaxed);
+    fn reset(&self) {
+        *self.operation_start_time.write().unwrap() = None;
+        self.files.write().unwrap().clear();
+        *self.out_dir.write().unwrap() = None;
+        self.failure.reset();
     }
 
-    fn write_file_to_fs(
-        &self,
-        event: &arkdropx_receiver::ReceiveFilesReceivingEvent,
-    ) {
-        let config = self.b.get_config();
-        let files = self.files.read().unwrap();
-
-        if let Some(file) = files.get(&event.id) {
-            let file_path = config.get_out_dir().join(&file.name);
-
-            match fs::File::options()
-                .create(true)
-                .append(true)
-                .open(&file_path)
-            {
-                Ok(mut file_stream) => {
-                    if let Err(e) = file_stream.write_all(&event.data) {
-                        // TODO: error handling
-                        return;
-                    }
-                    if let Err(e) = file_stream.flush() {
-                        // TODO: error handling
-                    }
-                }
-                Err(e) => {
-                    // TODO: error handling
-                }
+    fn handle_failure_control(&self, ev: &Event) -> Option<ControlCapture> {
+        let Event::Key(key) = ev else {
+            return None;
+        };
+        if key.modifiers == KeyModifiers::CONTROL {
+            return None;
+        }
+
+        match FailureAction::from_key(key.code)? {
+            FailureAction::Retry => {
+                self.discard_received_files();
+                self.reset();
+                self.b.get_receive_files_manager().retry();
+            }
+            FailureAction::Reconfigure => {
+                self.reset();
+                self.b.get_navigation().go_back();
+            }
+            FailureAction::ToggleLogs => self.failure.toggle_logs(),
+            FailureAction::Home => {
+                self.reset();
+                self.b
+                    .get_navigation()
+                    .navigate_fresh_to(Page::Home);
             }
         }
+
+        Some(ControlCapture::new(ev))
     }
 
-    fn reset(&self) {
-        *self.operation_start_time.write().unwrap() = None;
-        *self.files.write().unwrap() = HashMap::new();
+    /// Removes the files written by the failed session, as a retried
+    /// session receives them again from the start.
+    fn discard_received_files(&self) {
+        let out_dir = self.get_out_dir();
+        for file in self
+            .get_files()
+            .iter()
+            .filter(|file| file.done > 0)
+        {
+            if let Ok(path) = received_file_path(&out_dir, &file.name) {
+                let _ = fs::remove_file(path);
+            }
+        }
     }
 }
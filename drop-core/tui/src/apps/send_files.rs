@@ -2,9 +2,9 @@ use crate::{
     App, AppBackend, AppFileBrowserSaveEvent, AppFileBrowserSubscriber,
     BrowserMode, ControlCapture, OpenFileBrowserRequest, Page, SortMode,
 };
-use arkdrop_common::FileData;
+use arkdrop_common::{AppConfig, FileData};
 use arkdropx_sender::{
-    SendFilesRequest, SenderConfig, SenderFile, SenderProfile,
+    ItemKind, SendFilesRequest, SenderConfig, SenderFile, SenderProfile,
 };
 use ratatui::{
     Frame,
@@ -555,60 +555,22 @@ impl SendFilesApp {
     }
 
     fn send_files(&self) {
-        if let Some(req) = self.make_send_files_request() {
-            self.set_status_message("Starting file transfer...");
-            self.b.get_send_files_manager().send_files(req);
-            self.b
-                .get_navigation()
-                .navigate_to(Page::SendFilesProgress);
-        } else {
+        let paths = self.selected_files_in.read().unwrap().clone();
+        if paths.is_empty() {
             self.set_status_message("No files selected to send");
+            return;
         }
-    }
-
-    fn make_send_files_request(&self) -> Option<SendFilesRequest> {
-        let files = self.get_sender_files();
-
-        if files.is_empty() {
-            return None;
-        }
-
-        let profile = self.b.get_config();
-
-        Some(SendFilesRequest {
-            files,
-            profile: SenderProfile {
-                name: profile.get_avatar_name(),
-                avatar_b64: profile.get_avatar_base64(),
-            },
-            config: SenderConfig::default(),
-        })
-    }
-
-    fn get_sender_files(&self) -> Vec<SenderFile> {
-        let selected_files_in = self.selected_files_in.read().unwrap();
-
-        if selected_files_in.is_empty() {
-            return Vec::new();
-        }
-
-        selected_files_in
-            .iter()
-            .filter_map(|f| {
-                if let Some(name) = f.file_name()
-                    && let Ok(data) = FileData::new(f.clone())
-                {
-                    let name = name.to_string_lossy().to_string();
-
-                    return Some(SenderFile {
-                        name,
-                        data: Arc::new(data),
-                    });
-                }
 
-                None
-            })
-            .collect()
+        let config = self.b.get_config();
+        self.set_status_message("Starting file transfer...");
+        self.b
+            .get_send_files_manager()
+            .send_files(Arc::new(move || {
+                make_send_files_request(&paths, &config)
+            }));
+        self.b
+            .get_navigation()
+            .navigate_to(Page::SendFilesProgress);
     }
 
     fn set_status_message(&self, message: &str) {
@@ -1264,3 +1226,44 @@ impl SendFilesApp {
         f.render_widget(send_button, area);
     }
 }
+
+fn make_send_files_request(
+    paths: &[PathBuf],
+    config: &AppConfig,
+) -> Option<SendFilesRequest> {
+    let files = sender_files(paths);
+
+    if files.is_empty() {
+        return None;
+    }
+
+    Some(SendFilesRequest {
+        files,
+        profile: SenderProfile {
+            name: config.get_avatar_name(),
+            avatar_b64: config.get_avatar_base64(),
+        },
+        config: SenderConfig::default(),
+    })
+}
+
+fn sender_files(paths: &[PathBuf]) -> Vec<SenderFile> {
+    paths
+        .iter()
+        .filter_map(|f| {
+            if let Some(name) = f.file_name()
+                && let Ok(data) = FileData::new(f.clone())
+            {
+                let name = name.to_string_lossy().to_string();
+
+                return Some(SenderFile {
+                    name,
+                    data: Arc::new(data),
+                    kind: ItemKind::File,
+                });
+            }
+
+            None
+        })
+        .collect()
+}
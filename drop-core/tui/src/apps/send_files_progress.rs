@@ -1,14 +1,19 @@
 use std::{
-    sync::{Arc, RwLock, atomic::AtomicU32},
+    sync::{Arc, RwLock},
     time::Instant,
 };
 
 use crate::{
-    App, AppBackend, ControlCapture, utilities::clipboard::copy_to_clipboard,
+    App, AppBackend, ControlCapture, Page,
+    utilities::{
+        clipboard::copy_to_clipboard,
+        failure::{FailureAction, TransferFailure},
+        qr_renderer::QrCodeRenderer,
+    },
 };
-use arkdropx_sender::SendFilesSubscriber;
+use arkdrop_common::progress::{FileProgress, TransferProgress, TransferState};
+use arkdropx_sender::{DropUri, EndReason, SendFilesSubscriber};
 use crossterm::event::KeyModifiers;
-use qrcode::QrCode;
 use ratatui::{
     Frame,
     crossterm::event::{Event, KeyCode},
@@ -20,36 +25,21 @@ use ratatui::{
 };
 use uuid::Uuid;
 
-#[derive(Clone)]
-struct ProgressFile {
-    id: String,
-    name: String,
-    total_size: u64,
-    sent: u64,
-    status: FileTransferStatus,
-    transfer_speed: f64, // bytes per second
-    last_update: Instant,
-}
-
-#[derive(Clone, PartialEq)]
-enum FileTransferStatus {
-    Transferring,
-    Completed,
-}
-
-impl FileTransferStatus {
-    fn icon(&self) -> &'static str {
-        match self {
-            FileTransferStatus::Transferring => "📤",
-            FileTransferStatus::Completed => "✅",
-        }
+fn state_icon(state: &TransferState) -> &'static str {
+    match state {
+        TransferState::Waiting => "⏳",
+        TransferState::Transferring => "📤",
+        TransferState::Completed => "✅",
+        TransferState::Failed(_) => "❌",
     }
+}
 
-    fn color(&self) -> Color {
-        match self {
-            FileTransferStatus::Transferring => Color::Blue,
-            FileTransferStatus::Completed => Color::Green,
-        }
+fn state_color(state: &TransferState) -> Color {
+    match state {
+        TransferState::Waiting => Color::Gray,
+        TransferState::Transferring => Color::Blue,
+        TransferState::Completed => Color::Green,
+        TransferState::Failed(_) => Color::Red,
     }
 }
 
@@ -57,7 +47,6 @@ pub struct SendFilesProgressApp {
     id: String,
     b: Arc<dyn AppBackend>,
 
-    progress_pct: AtomicU32,
     operation_start_time: RwLock<Option<Instant>>,
 
     title_text: RwLock<String>,
@@ -67,15 +56,23 @@ pub struct SendFilesProgressApp {
 
     log_text: RwLock<String>, // TODO: info | display log text on UI
 
-    files: RwLock<Vec<ProgressFile>>,
-    total_transfer_speed: RwLock<f64>,
+    files: RwLock<TransferProgress>,
 
     // Copy feedback for T/Y clipboard shortcuts
     copy_feedback: RwLock<Option<(String, Instant)>>,
+
+    // Failure of the session, with its log
+    failure: TransferFailure,
 }
 
 impl App for SendFilesProgressApp {
     fn draw(&self, f: &mut Frame, area: ratatui::prelude::Rect) {
+        if self.failure.is_failed() {
+            self.failure
+                .draw(f, area, "Retry with a new ticket");
+            return;
+        }
+
         let blocks = Layout::default()
             .direction(Direction::Vertical)
             .margin(1)
@@ -97,6 +94,10 @@ impl App for SendFilesProgressApp {
         &self,
         ev: &ratatui::crossterm::event::Event,
     ) -> Option<ControlCapture> {
+        if self.failure.is_failed() {
+            return self.handle_failure_control(ev);
+        }
+
         if let Event::Key(key) = ev {
             let has_ctrl = key.modifiers == KeyModifiers::CONTROL;
 
@@ -143,6 +144,7 @@ impl SendFilesSubscriber for SendFilesProgressApp {
     }
 
     fn log(&self, message: String) {
+        self.failure.log(&message);
         self.set_log_text(message.as_str());
     }
 
@@ -151,63 +153,14 @@ impl SendFilesSubscriber for SendFilesProgressApp {
         let name = event.name;
         let remaining = event.remaining;
         let sent = event.sent;
-        let total_size = sent + remaining;
-        let now = Instant::now();
 
-        // Try to find and update existing file or add new file
         let mut files = self.files.write().unwrap();
-        if let Some(file) = files.iter_mut().find(|f| f.id == id) {
-            // Calculate transfer speed
-            let time_diff = now.duration_since(file.last_update).as_secs_f64();
-            let bytes_diff = sent.saturating_sub(file.sent);
-
-            if time_diff > 0.0 && bytes_diff > 0 {
-                file.transfer_speed = bytes_diff as f64 / time_diff;
-            }
-
-            file.sent = sent;
-            file.status = if remaining == 0 {
-                FileTransferStatus::Completed
-            } else {
-                FileTransferStatus::Transferring
-            };
-            file.last_update = now;
-        } else {
-            files.push(ProgressFile {
-                id: id.clone(),
-                name: name.clone(),
-                total_size,
-                sent,
-                status: if remaining == 0 {
-                    FileTransferStatus::Completed
-                } else {
-                    FileTransferStatus::Transferring
-                },
-                transfer_speed: 0.0,
-                last_update: now,
-            });
+        if files.file(&id).is_none() {
+            files.add_file(id.clone(), name, sent + remaining);
+        }
+        if let Some(file) = files.file_mut(&id) {
+            file.set_done(sent);
         }
-
-        // Calculate total transfer speed
-        let total_speed: f64 = files
-            .iter()
-            .filter(|f| f.status == FileTransferStatus::Transferring)
-            .map(|f| f.transfer_speed)
-            .sum();
-        *self.total_transfer_speed.write().unwrap() = total_speed;
-
-        // Recalculate total progress
-        let total_files_size: u64 = files.iter().map(|f| f.total_size).sum();
-        let total_sent_size: u64 = files.iter().map(|f| f.sent).sum();
-        let progress_pct = if total_files_size > 0 {
-            ((total_sent_size as f64 / total_files_size as f64) * 100.0)
-                .min(100.0)
-        } else {
-            0.0
-        };
-
-        self.progress_pct
-            .store(progress_pct as u32, std::sync::atomic::Ordering::Relaxed);
     }
 
     fn notify_connecting(
@@ -222,6 +175,10 @@ impl SendFilesSubscriber for SendFilesProgressApp {
         self.set_block_title_text(format!("Connected to {name}").as_str());
         self.set_status_text(format!("Sending Files to {name}").as_str());
     }
+
+    fn notify_ended(&self, reason: EndReason) {
+        self.failure.end(reason);
+    }
 }
 
 impl SendFilesProgressApp {
@@ -230,7 +187,6 @@ impl SendFilesProgressApp {
             id: Uuid::new_v4().to_string(),
             b,
 
-            progress_pct: AtomicU32::new(0),
             operation_start_time: RwLock::new(None),
 
             title_text: RwLock::new("📤 Sending Files".to_string()),
@@ -240,10 +196,11 @@ impl SendFilesProgressApp {
 
             log_text: RwLock::new("Initializing transfer...".to_string()),
 
-            files: RwLock::new(Vec::new()),
-            total_transfer_speed: RwLock::new(0.0),
+            files: RwLock::new(TransferProgress::new()),
 
             copy_feedback: RwLock::new(None),
+
+            failure: TransferFailure::new(),
         }
     }
 
@@ -283,22 +240,19 @@ impl SendFilesProgressApp {
     }
 
     fn get_progress_pct(&self) -> f64 {
-        let v = self
-            .progress_pct
-            .load(std::sync::atomic::Ordering::Relaxed);
-        f64::from(v)
+        self.files.read().unwrap().percent()
     }
 
     fn get_operation_start_time(&self) -> Option<Instant> {
         *self.operation_start_time.read().unwrap()
     }
 
-    fn get_files(&self) -> Vec<ProgressFile> {
-        self.files.read().unwrap().clone()
+    fn get_files(&self) -> Vec<FileProgress> {
+        self.files.read().unwrap().files().to_vec()
     }
 
     fn get_total_transfer_speed(&self) -> f64 {
-        *self.total_transfer_speed.read().unwrap()
+        self.files.read().unwrap().bytes_per_second()
     }
 
     fn copy_ticket_to_clipboard(&self) {
@@ -372,7 +326,7 @@ impl SendFilesProgressApp {
         let files = self.get_files();
         let completed_files = files
             .iter()
-            .filter(|f| f.status == FileTransferStatus::Completed)
+            .filter(|f| f.state == TransferState::Completed)
             .count();
         let total_files = files.len();
 
@@ -426,8 +380,8 @@ impl SendFilesProgressApp {
     ) {
         let progress_pct = self.get_progress_pct();
         let files = self.get_files();
-        let total_size: u64 = files.iter().map(|f| f.total_size).sum();
-        let total_sent: u64 = files.iter().map(|f| f.sent).sum();
+        let total_size: u64 = files.iter().map(|f| f.total).sum();
+        let total_sent: u64 = files.iter().map(|f| f.done).sum();
         let transfer_speed = self.get_total_transfer_speed();
 
         let chunks = Layout::default()
@@ -481,19 +435,11 @@ impl SendFilesProgressApp {
             "00:00".to_string()
         };
 
-        let estimated_remaining = if progress_pct > 0.0
-            && progress_pct < 100.0
-            && transfer_speed > 0.0
-        {
-            let remaining_bytes = total_size.saturating_sub(total_sent);
-            let remaining_secs = remaining_bytes as f64 / transfer_speed;
-            format!(
-                "{}:{:02}",
-                (remaining_secs as u64) / 60,
-                (remaining_secs as u64) % 60
-            )
-        } else {
-            "--:--".to_string()
+        let estimated_remaining = match self.files.read().unwrap().eta() {
+            Some(eta) if progress_pct > 0.0 && progress_pct < 100.0 => {
+                format!("{}:{:02}", eta.as_secs() / 60, eta.as_secs() % 60)
+            }
+            _ => "--:--".to_string(),
         };
 
         let stats_content = vec![
@@ -573,11 +519,7 @@ impl SendFilesProgressApp {
         let file_items: Vec<ListItem> = files
             .iter()
             .map(|file| {
-                let progress_pct = if file.total_size > 0 {
-                    (file.sent as f64 / file.total_size as f64) * 100.0
-                } else {
-                    0.0
-                };
+                let progress_pct = file.percent();
 
                 // Create a mini progress bar using Unicode blocks
                 let progress_width = 20.0;
@@ -596,8 +538,8 @@ impl SendFilesProgressApp {
 
                 let status_line = Line::from(vec![
                     Span::styled(
-                        format!("{} ", file.status.icon()),
-                        Style::default().fg(file.status.color()),
+                        format!("{} ", state_icon(&file.state)),
+                        Style::default().fg(state_color(&file.state)),
                     ),
                     Span::styled(
                         format!("{:<25}", file_name),
@@ -622,17 +564,15 @@ impl SendFilesProgressApp {
                     Span::styled(
                         format!(
                             "{} / {} • {}",
-                            self.format_bytes(file.sent),
-                            self.format_bytes(file.total_size),
-                            if file.status == FileTransferStatus::Transferring {
-                                self.format_speed(file.transfer_speed)
-                            } else {
-                                match file.status {
-                                    FileTransferStatus::Completed => {
-                                        "Complete".to_string()
-                                    }
-                                    _ => "--".to_string(),
+                            self.format_bytes(file.done),
+                            self.format_bytes(file.total),
+                            match file.state {
+                                TransferState::Transferring =>
+                                    self.format_speed(file.bytes_per_second()),
+                                TransferState::Completed => {
+                                    "Complete".to_string()
                                 }
+                                _ => "--".to_string(),
                             }
                         ),
                         Style::default().fg(Color::Gray),
@@ -658,117 +598,37 @@ impl SendFilesProgressApp {
     }
 
     fn draw_qr_code(&self, f: &mut Frame, area: ratatui::prelude::Rect) {
-        let qr_block = Block::default()
-            .borders(Borders::ALL)
-            .border_set(border::ROUNDED)
-            .border_style(Style::default().fg(Color::Blue))
-            .title(" Scan to Connect ")
-            .title_style(Style::default().fg(Color::White).bold());
+        let qr_block =
+            QrCodeRenderer::create_qr_block("Scan to Connect", Color::Blue);
 
         if let Some(bubble) = self
             .b
             .get_send_files_manager()
             .get_send_files_bubble()
         {
-            let qr_data = format!(
-                "drop://receive?ticket={}&confirmation={}",
+            let qr_data = DropUri::receive(
                 bubble.get_ticket(),
-                bubble.get_confirmation()
+                bubble.get_confirmation(),
+            )
+            .to_string();
+
+            QrCodeRenderer::render_qr_code(
+                f,
+                area,
+                qr_block,
+                &qr_data,
+                &bubble.get_ticket(),
             );
-
-            let qr_code = match QrCode::new(&qr_data) {
-                Ok(code) => code,
-                Err(_) => {
-                    self.draw_qr_error(f, area, qr_block);
-                    return;
-                }
-            };
-
-            let qr_matrix = qr_code
-                .render::<char>()
-                .quiet_zone(false)
-                .module_dimensions(1, 1)
-                .build();
-
-            // Split QR code into lines for display
-            let qr_lines: Vec<Line> = qr_matrix
-                .lines()
-                .map(|line| {
-                    Line::from(vec![Span::styled(
-                        line.replace('█', "██").replace(' ', "  "), // Make blocks wider for better visibility
-                        Style::default().fg(Color::White).bg(Color::Black),
-                    )])
-                })
-                .collect();
-
-            let qr_widget = Paragraph::new(qr_lines)
-                .block(qr_block)
-                .alignment(Alignment::Center);
-
-            f.render_widget(qr_widget, area);
         } else {
-            self.draw_qr_waiting(f, area, qr_block);
+            QrCodeRenderer::render_waiting(
+                f,
+                area,
+                qr_block,
+                "Preparing connection...",
+            );
         }
     }
 
-    fn draw_qr_error(
-        &self,
-        f: &mut Frame,
-        area: ratatui::prelude::Rect,
-        block: Block,
-    ) {
-        let error_content = vec![
-            Line::from(""),
-            Line::from(vec![
-                Span::styled("❌ ", Style::default().fg(Color::Red)),
-                Span::styled(
-                    "Failed to generate QR code",
-                    Style::default().fg(Color::Red).bold(),
-                ),
-            ]),
-            Line::from(""),
-            Line::from(vec![Span::styled(
-                "Please check connection details",
-                Style::default().fg(Color::Gray),
-            )]),
-        ];
-
-        let error_widget = Paragraph::new(error_content)
-            .block(block)
-            .alignment(Alignment::Center);
-
-        f.render_widget(error_widget, area);
-    }
-
-    fn draw_qr_waiting(
-        &self,
-        f: &mut Frame,
-        area: ratatui::prelude::Rect,
-        block: Block,
-    ) {
-        let waiting_content = vec![
-            Line::from(""),
-            Line::from(vec![
-                Span::styled("⏳ ", Style::default().fg(Color::Yellow)),
-                Span::styled(
-                    "Preparing connection...",
-                    Style::default().fg(Color::Yellow).bold(),
-                ),
-            ]),
-            Line::from(""),
-            Line::from(vec![Span::styled(
-                "QR code will appear when ready",
-                Style::default().fg(Color::Gray),
-            )]),
-        ];
-
-        let waiting_widget = Paragraph::new(waiting_content)
-            .block(block)
-            .alignment(Alignment::Center);
-
-        f.render_widget(waiting_widget, area);
-    }
-
     fn draw_footer(&self, f: &mut Frame, area: ratatui::prelude::Rect) {
         let progress_pct = self.get_progress_pct();
         let copy_feedback = self.get_copy_feedback();
@@ -867,7 +727,37 @@ impl SendFilesProgressApp {
 
     fn reset(&self) {
         *self.operation_start_time.write().unwrap() = None;
-        *self.files.write().unwrap() = Vec::new();
+        self.files.write().unwrap().clear();
         *self.copy_feedback.write().unwrap() = None;
+        self.failure.reset();
+    }
+
+    fn handle_failure_control(&self, ev: &Event) -> Option<ControlCapture> {
+        let Event::Key(key) = ev else {
+            return None;
+        };
+        if key.modifiers == KeyModifiers::CONTROL {
+            return None;
+        }
+
+        match FailureAction::from_key(key.code)? {
+            FailureAction::Retry => {
+                self.reset();
+                self.b.get_send_files_manager().retry();
+            }
+            FailureAction::Reconfigure => {
+                self.reset();
+                self.b.get_navigation().go_back();
+            }
+            FailureAction::ToggleLogs => self.failure.toggle_logs(),
+            FailureAction::Home => {
+                self.reset();
+                self.b
+                    .get_navigation()
+                    .navigate_fresh_to(Page::Home);
+            }
+        }
+
+        Some(ControlCapture::new(ev))
     }
 }
@@ -2,9 +2,10 @@ use crate::{
     App, AppBackend, AppFileBrowserSaveEvent, AppFileBrowserSubscriber,
     BrowserMode, ControlCapture, OpenFileBrowserRequest, Page, SortMode,
 };
-use arkdrop_common::FileData;
+use arkdrop_common::{AppConfig, FileData};
 use arkdropx_sender::{
-    SenderConfig, SenderFile, SenderProfile, send_files_to::SendFilesToRequest,
+    ItemKind, SenderConfig, SenderFile, SenderProfile,
+    send_files_to::SendFilesToRequest,
 };
 use ratatui::{
     Frame,
@@ -559,68 +560,31 @@ impl SendFilesToApp {
     // ─── Send Operation ────────────────────────────────────────────────────
 
     fn send_files_to(&self) {
-        if let Some(req) = self.make_send_files_to_request() {
-            self.set_status_message("Connecting to receiver...");
-            self.b
-                .get_send_files_to_manager()
-                .send_files_to(req);
-            self.b
-                .get_navigation()
-                .navigate_to(Page::SendFilesToProgress);
-        } else {
+        if !self.can_send() {
             self.set_status_message(
                 "Missing required information - check ticket, confirmation, and files",
             );
-        }
-    }
-
-    fn make_send_files_to_request(&self) -> Option<SendFilesToRequest> {
-        if !self.can_send() {
-            return None;
-        }
-
-        let files = self.get_sender_files();
-        if files.is_empty() {
-            return None;
+            return;
         }
 
+        let ticket = self.get_ticket_in();
+        let confirmation = self.get_confirmation_in();
+        let paths = self.selected_files_in.read().unwrap().clone();
         let config = self.b.get_config();
-        let confirmation: u8 = self.get_confirmation_in().parse().ok()?;
-
-        Some(SendFilesToRequest {
-            ticket: self.get_ticket_in(),
-            confirmation,
-            files,
-            profile: SenderProfile {
-                name: config.get_avatar_name(),
-                avatar_b64: config.get_avatar_base64(),
-            },
-            config: SenderConfig::default(),
-        })
-    }
-
-    fn get_sender_files(&self) -> Vec<SenderFile> {
-        let selected_files_in = self.selected_files_in.read().unwrap();
-
-        if selected_files_in.is_empty() {
-            return Vec::new();
-        }
-
-        selected_files_in
-            .iter()
-            .filter_map(|f| {
-                if let Some(name) = f.file_name()
-                    && let Ok(data) = FileData::new(f.clone())
-                {
-                    let name = name.to_string_lossy().to_string();
-                    return Some(SenderFile {
-                        name,
-                        data: Arc::new(data),
-                    });
-                }
-                None
-            })
-            .collect()
+        self.set_status_message("Connecting to receiver...");
+        self.b
+            .get_send_files_to_manager()
+            .send_files_to(Arc::new(move || {
+                make_send_files_to_request(
+                    &ticket,
+                    &confirmation,
+                    &paths,
+                    &config,
+                )
+            }));
+        self.b
+            .get_navigation()
+            .navigate_to(Page::SendFilesToProgress);
     }
 
     fn can_send(&self) -> bool {
@@ -1165,3 +1129,47 @@ fn truncate_string(s: &str, max_len: usize) -> String {
         format!("{}...", &s[..max_len.saturating_sub(3)])
     }
 }
+
+fn make_send_files_to_request(
+    ticket: &str,
+    confirmation: &str,
+    paths: &[PathBuf],
+    config: &AppConfig,
+) -> Option<SendFilesToRequest> {
+    let files = sender_files(paths);
+    if files.is_empty() {
+        return None;
+    }
+
+    let confirmation: u8 = confirmation.parse().ok()?;
+
+    Some(SendFilesToRequest {
+        ticket: ticket.to_string(),
+        confirmation,
+        files,
+        profile: SenderProfile {
+            name: config.get_avatar_name(),
+            avatar_b64: config.get_avatar_base64(),
+        },
+        config: SenderConfig::default(),
+    })
+}
+
+fn sender_files(paths: &[PathBuf]) -> Vec<SenderFile> {
+    paths
+        .iter()
+        .filter_map(|f| {
+            if let Some(name) = f.file_name()
+                && let Ok(data) = FileData::new(f.clone())
+            {
+                let name = name.to_string_lossy().to_string();
+                return Some(SenderFile {
+                    name,
+                    data: Arc::new(data),
+                    kind: ItemKind::File,
+                });
+            }
+            None
+        })
+        .collect()
+}
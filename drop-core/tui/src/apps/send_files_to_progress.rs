@@ -1,11 +1,19 @@
 use std::{
-    sync::{Arc, RwLock, atomic::AtomicU32},
+    sync::{Arc, RwLock},
     time::Instant,
 };
 
-use crate::{App, AppBackend, ControlCapture};
-use arkdropx_sender::send_files_to::{
-    SendFilesToConnectingEvent, SendFilesToSendingEvent, SendFilesToSubscriber,
+use crate::{
+    App, AppBackend, ControlCapture, Page,
+    utilities::failure::{FailureAction, TransferFailure},
+};
+use arkdrop_common::progress::{FileProgress, TransferProgress, TransferState};
+use arkdropx_sender::{
+    EndReason,
+    send_files_to::{
+        SendFilesToConnectingEvent, SendFilesToSendingEvent,
+        SendFilesToSubscriber,
+    },
 };
 use crossterm::event::KeyModifiers;
 use ratatui::{
@@ -19,39 +27,21 @@ use ratatui::{
 };
 use uuid::Uuid;
 
-#[derive(Clone)]
-struct ProgressFile {
-    id: String,
-    name: String,
-    total_size: u64,
-    sent: u64,
-    status: FileTransferStatus,
-    transfer_speed: f64,
-    last_update: Instant,
-}
-
-#[derive(Clone, PartialEq)]
-enum FileTransferStatus {
-    Waiting,
-    Transferring,
-    Completed,
-}
-
-impl FileTransferStatus {
-    fn icon(&self) -> &'static str {
-        match self {
-            FileTransferStatus::Waiting => "⏳",
-            FileTransferStatus::Transferring => "📤",
-            FileTransferStatus::Completed => "✅",
-        }
+fn state_icon(state: &TransferState) -> &'static str {
+    match state {
+        TransferState::Waiting => "⏳",
+        TransferState::Transferring => "📤",
+        TransferState::Completed => "✅",
+        TransferState::Failed(_) => "❌",
     }
+}
 
-    fn color(&self) -> Color {
-        match self {
-            FileTransferStatus::Waiting => Color::Gray,
-            FileTransferStatus::Transferring => Color::Blue,
-            FileTransferStatus::Completed => Color::Green,
-        }
+fn state_color(state: &TransferState) -> Color {
+    match state {
+        TransferState::Waiting => Color::Gray,
+        TransferState::Transferring => Color::Blue,
+        TransferState::Completed => Color::Green,
+        TransferState::Failed(_) => Color::Red,
     }
 }
 
@@ -59,7 +49,6 @@ pub struct SendFilesToProgressApp {
     id: String,
     b: Arc<dyn AppBackend>,
 
-    progress_pct: AtomicU32,
     operation_start_time: RwLock<Option<Instant>>,
 
     title_text: RwLock<String>,
@@ -67,13 +56,21 @@ pub struct SendFilesToProgressApp {
     status_text: RwLock<String>,
     log_text: RwLock<String>,
 
-    files: RwLock<Vec<ProgressFile>>,
-    total_transfer_speed: RwLock<f64>,
+    files: RwLock<TransferProgress>,
     receiver_name: RwLock<String>,
+
+    // Failure of the session, with its log
+    failure: TransferFailure,
 }
 
 impl App for SendFilesToProgressApp {
     fn draw(&self, f: &mut Frame, area: ratatui::prelude::Rect) {
+        if self.failure.is_failed() {
+            self.failure
+                .draw(f, area, "Retry with same ticket");
+            return;
+        }
+
         let blocks = Layout::default()
             .direction(Direction::Vertical)
             .margin(1)
@@ -95,6 +92,10 @@ impl App for SendFilesToProgressApp {
         &self,
         ev: &ratatui::crossterm::event::Event,
     ) -> Option<ControlCapture> {
+        if self.failure.is_failed() {
+            return self.handle_failure_control(ev);
+        }
+
         if let Event::Key(key) = ev {
             let has_ctrl = key.modifiers == KeyModifiers::CONTROL;
 
@@ -129,6 +130,7 @@ impl SendFilesToSubscriber for SendFilesToProgressApp {
     }
 
     fn log(&self, message: String) {
+        self.failure.log(&message);
         self.set_log_text(message.as_str());
     }
 
@@ -137,59 +139,14 @@ impl SendFilesToSubscriber for SendFilesToProgressApp {
         let name = event.name;
         let remaining = event.remaining;
         let sent = event.sent;
-        let total_size = sent + remaining;
-        let now = Instant::now();
 
         let mut files = self.files.write().unwrap();
-        if let Some(file) = files.iter_mut().find(|f| f.id == id) {
-            let time_diff = now.duration_since(file.last_update).as_secs_f64();
-            let bytes_diff = sent.saturating_sub(file.sent);
-
-            if time_diff > 0.0 && bytes_diff > 0 {
-                file.transfer_speed = bytes_diff as f64 / time_diff;
-            }
-
-            file.sent = sent;
-            file.status = if remaining == 0 {
-                FileTransferStatus::Completed
-            } else {
-                FileTransferStatus::Transferring
-            };
-            file.last_update = now;
-        } else {
-            files.push(ProgressFile {
-                id: id.clone(),
-                name: name.clone(),
-                total_size,
-                sent,
-                status: if remaining == 0 {
-                    FileTransferStatus::Completed
-                } else {
-                    FileTransferStatus::Transferring
-                },
-                transfer_speed: 0.0,
-                last_update: now,
-            });
+        if files.file(&id).is_none() {
+            files.add_file(id.clone(), name, sent + remaining);
+        }
+        if let Some(file) = files.file_mut(&id) {
+            file.set_done(sent);
         }
-
-        let total_speed: f64 = files
-            .iter()
-            .filter(|f| f.status == FileTransferStatus::Transferring)
-            .map(|f| f.transfer_speed)
-            .sum();
-        *self.total_transfer_speed.write().unwrap() = total_speed;
-
-        let total_files_size: u64 = files.iter().map(|f| f.total_size).sum();
-        let total_sent_size: u64 = files.iter().map(|f| f.sent).sum();
-        let progress_pct = if total_files_size > 0 {
-            ((total_sent_size as f64 / total_files_size as f64) * 100.0)
-                .min(100.0)
-        } else {
-            0.0
-        };
-
-        self.progress_pct
-            .store(progress_pct as u32, std::sync::atomic::Ordering::Relaxed);
     }
 
     fn notify_connecting(&self, event: SendFilesToConnectingEvent) {
@@ -202,6 +159,10 @@ impl SendFilesToSubscriber for SendFilesToProgressApp {
         self.set_block_title_text(format!("Connected to {name}").as_str());
         self.set_status_text(format!("Sending Files to {name}").as_str());
     }
+
+    fn notify_ended(&self, reason: EndReason) {
+        self.failure.end(reason);
+    }
 }
 
 impl SendFilesToProgressApp {
@@ -210,7 +171,6 @@ impl SendFilesToProgressApp {
             id: Uuid::new_v4().to_string(),
             b,
 
-            progress_pct: AtomicU32::new(0),
             operation_start_time: RwLock::new(None),
 
             title_text: RwLock::new("📤 Sending Files".to_string()),
@@ -218,15 +178,14 @@ impl SendFilesToProgressApp {
             status_text: RwLock::new("Establishing Connection".to_string()),
             log_text: RwLock::new("Initializing transfer...".to_string()),
 
-            files: RwLock::new(Vec::new()),
-            total_transfer_speed: RwLock::new(0.0),
+            files: RwLock::new(TransferProgress::new()),
             receiver_name: RwLock::new(String::new()),
+
+            failure: TransferFailure::new(),
         }
     }
 
     fn reset(&self) {
-        self.progress_pct
-            .store(0, std::sync::atomic::Ordering::Relaxed);
         *self.operation_start_time.write().unwrap() = None;
         *self.title_text.write().unwrap() = "📤 Sending Files".to_string();
         *self.block_title_text.write().unwrap() =
@@ -236,8 +195,37 @@ impl SendFilesToProgressApp {
         *self.log_text.write().unwrap() =
             "Initializing transfer...".to_string();
         self.files.write().unwrap().clear();
-        *self.total_transfer_speed.write().unwrap() = 0.0;
         *self.receiver_name.write().unwrap() = String::new();
+        self.failure.reset();
+    }
+
+    fn handle_failure_control(&self, ev: &Event) -> Option<ControlCapture> {
+        let Event::Key(key) = ev else {
+            return None;
+        };
+        if key.modifiers == KeyModifiers::CONTROL {
+            return None;
+        }
+
+        match FailureAction::from_key(key.code)? {
+            FailureAction::Retry => {
+                self.reset();
+                self.b.get_send_files_to_manager().retry();
+            }
+            FailureAction::Reconfigure => {
+                self.reset();
+                self.b.get_navigation().go_back();
+            }
+            FailureAction::ToggleLogs => self.failure.toggle_logs(),
+            FailureAction::Home => {
+                self.reset();
+                self.b
+                    .get_navigation()
+                    .navigate_fresh_to(Page::Home);
+            }
+        }
+
+        Some(ControlCapture::new(ev))
     }
 
     fn set_title_text(&self, text: &str) {
@@ -272,18 +260,15 @@ impl SendFilesToProgressApp {
     }
 
     fn get_progress_pct(&self) -> f64 {
-        let v = self
-            .progress_pct
-            .load(std::sync::atomic::Ordering::Relaxed);
-        f64::from(v)
+        self.files.read().unwrap().percent()
     }
 
-    fn get_files(&self) -> Vec<ProgressFile> {
-        self.files.read().unwrap().clone()
+    fn get_files(&self) -> Vec<FileProgress> {
+        self.files.read().unwrap().files().to_vec()
     }
 
     fn get_total_transfer_speed(&self) -> f64 {
-        *self.total_transfer_speed.read().unwrap()
+        self.files.read().unwrap().bytes_per_second()
     }
 
     fn format_bytes(&self, bytes: u64) -> String {
@@ -315,7 +300,7 @@ impl SendFilesToProgressApp {
         let files = self.get_files();
         let completed_files = files
             .iter()
-            .filter(|f| f.status == FileTransferStatus::Completed)
+            .filter(|f| f.state == TransferState::Completed)
             .count();
         let total_files = files.len();
 
@@ -369,8 +354,8 @@ impl SendFilesToProgressApp {
     ) {
         let progress_pct = self.get_progress_pct();
         let files = self.get_files();
-        let total_size: u64 = files.iter().map(|f| f.total_size).sum();
-        let total_sent: u64 = files.iter().map(|f| f.sent).sum();
+        let total_size: u64 = files.iter().map(|f| f.total).sum();
+        let total_sent: u64 = files.iter().map(|f| f.done).sum();
         let transfer_speed = self.get_total_transfer_speed();
 
         let chunks = Layout::default()
@@ -445,21 +430,17 @@ impl SendFilesToProgressApp {
         let file_items: Vec<ListItem> = files
             .iter()
             .map(|file| {
-                let progress_pct = if file.total_size > 0 {
-                    (file.sent as f64 / file.total_size as f64) * 100.0
-                } else {
-                    0.0
-                };
+                let progress_pct = file.percent();
 
                 let status_line = Line::from(vec![
                     Span::styled(
-                        format!("{} ", file.status.icon()),
-                        Style::default().fg(file.status.color()),
+                        format!("{} ", state_icon(&file.state)),
+                        Style::default().fg(state_color(&file.state)),
                     ),
                     Span::styled(
                         file.name.clone(),
                         Style::default().fg(
-                            if file.status == FileTransferStatus::Completed {
+                            if file.state == TransferState::Completed {
                                 Color::Green
                             } else {
                                 Color::White
@@ -477,17 +458,15 @@ impl SendFilesToProgressApp {
                     Span::styled(
                         format!(
                             "{} / {} • {}",
-                            self.format_bytes(file.sent),
-                            self.format_bytes(file.total_size),
-                            if file.status == FileTransferStatus::Transferring {
-                                self.format_speed(file.transfer_speed)
-                            } else {
-                                match file.status {
-                                    FileTransferStatus::Completed => {
-                                        "Complete".to_string()
-                                    }
-                                    _ => "--".to_string(),
+                            self.format_bytes(file.done),
+                            self.format_bytes(file.total),
+                            match file.state {
+                                TransferState::Transferring =>
+                                    self.format_speed(file.bytes_per_second()),
+                                TransferState::Completed => {
+                                    "Complete".to_string()
                                 }
+                                _ => "--".to_string(),
                             }
                         ),
                         Style::default().fg(Color::Gray),
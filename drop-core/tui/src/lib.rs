@@ -114,15 +114,26 @@ pub trait AppNavigation: Send + Sync {
     fn go_back(&self);
 }
 
+/// Builds the request of a transfer session.
+///
+/// Managers keep it once the session started, to build the request again if
+/// the session fails and is retried. Requests themselves can't be reused as
+/// the data sources of their files are consumed while sending.
+pub type RequestBuilder<R> = Arc<dyn Fn() -> Option<R> + Send + Sync>;
+
 pub trait AppSendFilesManager: Send + Sync {
     fn cancel(&self);
-    fn send_files(&self, req: SendFilesRequest);
+    fn send_files(&self, make_req: RequestBuilder<SendFilesRequest>);
+    /// Starts the last session again, returns false if there is none.
+    fn retry(&self) -> bool;
     fn get_send_files_bubble(&self) -> Option<Arc<SendFilesBubble>>;
 }
 
 pub trait AppReceiveFilesManager: Send + Sync {
     fn cancel(&self);
-    fn receive_files(&self, req: ReceiveFilesRequest);
+    fn receive_files(&self, make_req: RequestBuilder<ReceiveFilesRequest>);
+    /// Starts the last session again, returns false if there is none.
+    fn retry(&self) -> bool;
     fn get_receive_files_bubble(&self) -> Option<Arc<ReceiveFilesBubble>>;
 }
 
@@ -132,13 +143,17 @@ pub trait AppFileBrowserManager: Send + Sync {
 
 pub trait AppSendFilesToManager: Send + Sync {
     fn cancel(&self);
-    fn send_files_to(&self, req: SendFilesToRequest);
+    fn send_files_to(&self, make_req: RequestBuilder<SendFilesToRequest>);
+    /// Starts the last session again, returns false if there is none.
+    fn retry(&self) -> bool;
     fn get_send_files_to_bubble(&self) -> Option<Arc<SendFilesToBubble>>;
 }
 
 pub trait AppReadyToReceiveManager: Send + Sync {
     fn cancel(&self);
-    fn ready_to_receive(&self, req: ReadyToReceiveRequest);
+    fn ready_to_receive(&self, make_req: RequestBuilder<ReadyToReceiveRequest>);
+    /// Starts the last session again, returns false if there is none.
+    fn retry(&self) -> bool;
     fn get_ready_to_receive_bubble(&self) -> Option<Arc<ReadyToReceiveBubble>>;
 }
 
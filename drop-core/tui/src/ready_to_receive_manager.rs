@@ -1,15 +1,20 @@
 use std::sync::{Arc, RwLock};
 
-use arkdropx_receiver::ready_to_receive::{
-    ReadyToReceiveBubble, ReadyToReceiveRequest, ReadyToReceiveSubscriber,
-    ready_to_receive,
+use arkdropx_receiver::{
+    EndReason,
+    ready_to_receive::{
+        ReadyToReceiveBubble, ReadyToReceiveRequest, ReadyToReceiveSubscriber,
+        ready_to_receive,
+    },
 };
 
-use crate::AppReadyToReceiveManager;
+use crate::{AppReadyToReceiveManager, RequestBuilder};
 
 pub struct MainAppReadyToReceiveManager {
     bubble: Arc<RwLock<Option<Arc<ReadyToReceiveBubble>>>>,
     sub: Arc<RwLock<Option<Arc<dyn ReadyToReceiveSubscriber>>>>,
+    // Kept after the session ends, to retry it
+    make_req: RwLock<Option<RequestBuilder<ReadyToReceiveRequest>>>,
 }
 
 impl AppReadyToReceiveManager for MainAppReadyToReceiveManager {
@@ -24,32 +29,26 @@ impl AppReadyToReceiveManager for MainAppReadyToReceiveManager {
         });
     }
 
-    fn ready_to_receive(&self, req: ReadyToReceiveRequest) {
-        let curr_sub = self.sub.clone();
-        let curr_bubble = self.bubble.clone();
-
-        tokio::spawn(async move {
-            let bubble = ready_to_receive(req).await;
-            match bubble {
-                Ok(bub) => {
-                    let bub = Arc::new(bub);
-
-                    if let Some(sub) = curr_sub.read().unwrap().clone() {
-                        bub.subscribe(sub);
-                    }
+    fn ready_to_receive(
+        &self,
+        make_req: RequestBuilder<ReadyToReceiveRequest>,
+    ) {
+        self.make_req
+            .write()
+            .unwrap()
+            .replace(make_req.clone());
+        self.start(make_req);
+    }
 
-                    // No explicit start needed - the bubble starts waiting
-                    // immediately
-                    curr_bubble.write().unwrap().replace(bub);
-                }
-                Err(e) => {
-                    // Log error to subscriber if available
-                    if let Some(sub) = curr_sub.read().unwrap().clone() {
-                        sub.log(format!("[ERROR] Failed to start: {}", e));
-                    }
-                }
-            }
-        });
+    fn retry(&self) -> bool {
+        let Some(make_req) = self.make_req.read().unwrap().clone() else {
+            return false;
+        };
+        // The bubble of the failed session is dropped, not to show its
+        // ticket until the new one is ready
+        self.bubble.write().unwrap().take();
+        self.start(make_req);
+        true
     }
 
     fn get_ready_to_receive_bubble(&self) -> Option<Arc<ReadyToReceiveBubble>> {
@@ -69,6 +68,7 @@ impl MainAppReadyToReceiveManager {
         Self {
             bubble: Arc::new(RwLock::new(None)),
             sub: Arc::new(RwLock::new(None)),
+            make_req: RwLock::new(None),
         }
     }
 
@@ -78,4 +78,37 @@ impl MainAppReadyToReceiveManager {
     ) {
         self.sub.write().unwrap().replace(sub);
     }
+
+    fn start(&self, make_req: RequestBuilder<ReadyToReceiveRequest>) {
+        let curr_sub = self.sub.clone();
+        let curr_bubble = self.bubble.clone();
+
+        tokio::spawn(async move {
+            let Some(req) = make_req() else {
+                return;
+            };
+
+            let bubble = ready_to_receive(req).await;
+            match bubble {
+                Ok(bub) => {
+                    let bub = Arc::new(bub);
+
+                    if let Some(sub) = curr_sub.read().unwrap().clone() {
+                        bub.subscribe(sub);
+                    }
+
+                    // No explicit start needed - the bubble starts waiting
+                    // immediately
+                    curr_bubble.write().unwrap().replace(bub);
+                }
+                Err(e) => {
+                    // Log error to subscriber if available
+                    if let Some(sub) = curr_sub.read().unwrap().clone() {
+                        sub.log(format!("[ERROR] Failed to start: {}", e));
+                        sub.notify_ended(EndReason::of(&e));
+                    }
+                }
+            }
+        });
+    }
 }
@@ -1,14 +1,17 @@
 use std::sync::{Arc, RwLock};
 
 use arkdropx_receiver::{
-    ReceiveFilesBubble, ReceiveFilesSubscriber, receive_files,
+    EndReason, ReceiveFilesBubble, ReceiveFilesRequest, ReceiveFilesSubscriber,
+    receive_files,
 };
 
-use crate::AppReceiveFilesManager;
+use crate::{AppReceiveFilesManager, RequestBuilder};
 
 pub struct MainAppReceiveFilesManager {
     bubble: Arc<RwLock<Option<Arc<ReceiveFilesBubble>>>>,
     sub: Arc<RwLock<Option<Arc<dyn ReceiveFilesSubscriber>>>>,
+    // Kept after the session ends, to retry it
+    make_req: RwLock<Option<RequestBuilder<ReceiveFilesRequest>>>,
 }
 
 impl AppReceiveFilesManager for MainAppReceiveFilesManager {
@@ -20,27 +23,23 @@ impl AppReceiveFilesManager for MainAppReceiveFilesManager {
         }
     }
 
-    fn receive_files(&self, req: arkdropx_receiver::ReceiveFilesRequest) {
-        let curr_sub = self.sub.clone();
-        let curr_bubble = self.bubble.clone();
-
-        tokio::spawn(async move {
-            let bubble = receive_files(req).await;
-            match bubble {
-                Ok(bub) => {
-                    let bub = Arc::new(bub);
-
-                    if let Some(sub) = curr_sub.read().unwrap().clone() {
-                        bub.subscribe(sub);
-                    }
-
-                    let _ = bub.start();
+    fn receive_files(&self, make_req: RequestBuilder<ReceiveFilesRequest>) {
+        self.make_req
+            .write()
+            .unwrap()
+            .replace(make_req.clone());
+        self.start(make_req);
+    }
 
-                    curr_bubble.write().unwrap().replace(bub);
-                }
-                Err(_) => todo!(),
-            }
-        });
+    fn retry(&self) -> bool {
+        let Some(make_req) = self.make_req.read().unwrap().clone() else {
+            return false;
+        };
+        // The bubble of the failed session is dropped, not to show its
+        // ticket until the new one is ready
+        self.bubble.write().unwrap().take();
+        self.start(make_req);
+        true
     }
 
     fn get_receive_files_bubble(
@@ -56,6 +55,7 @@ impl MainAppReceiveFilesManager {
         Self {
             bubble: Arc::new(RwLock::new(None)),
             sub: Arc::new(RwLock::new(None)),
+            make_req: RwLock::new(None),
         }
     }
 
@@ -65,4 +65,42 @@ impl MainAppReceiveFilesManager {
     ) {
         self.sub.write().unwrap().replace(sub);
     }
+
+    fn start(&self, make_req: RequestBuilder<ReceiveFilesRequest>) {
+        let curr_sub = self.sub.clone();
+        let curr_bubble = self.bubble.clone();
+
+        tokio::spawn(async move {
+            let Some(req) = make_req() else {
+                if let Some(sub) = curr_sub.read().unwrap().clone() {
+                    sub.log(
+                        "[ERROR] Invalid ticket or confirmation".to_string(),
+                    );
+                    sub.notify_ended(EndReason::Failed);
+                }
+                return;
+            };
+
+            let bubble = receive_files(req).await;
+            match bubble {
+                Ok(bub) => {
+                    let bub = Arc::new(bub);
+
+                    if let Some(sub) = curr_sub.read().unwrap().clone() {
+                        bub.subscribe(sub);
+                    }
+
+                    let _ = bub.start();
+
+                    curr_bubble.write().unwrap().replace(bub);
+                }
+                Err(e) => {
+                    if let Some(sub) = curr_sub.read().unwrap().clone() {
+                        sub.log(format!("[ERROR] Failed to connect: {}", e));
+                        sub.notify_ended(EndReason::of(&e));
+                    }
+                }
+            }
+        });
+    }
 }
@@ -1,12 +1,17 @@
 use std::sync::{Arc, RwLock};
 
-use arkdropx_sender::{SendFilesBubble, SendFilesSubscriber, send_files};
+use arkdropx_sender::{
+    EndReason, SendFilesBubble, SendFilesRequest, SendFilesSubscriber,
+    send_files,
+};
 
-use crate::AppSendFilesManager;
+use crate::{AppSendFilesManager, RequestBuilder};
 
 pub struct MainAppSendFilesManager {
     bubble: Arc<RwLock<Option<Arc<SendFilesBubble>>>>,
     sub: Arc<RwLock<Option<Arc<dyn SendFilesSubscriber>>>>,
+    // Kept after the session ends, to retry it
+    make_req: RwLock<Option<RequestBuilder<SendFilesRequest>>>,
 }
 
 impl AppSendFilesManager for MainAppSendFilesManager {
@@ -21,25 +26,23 @@ impl AppSendFilesManager for MainAppSendFilesManager {
         });
     }
 
-    fn send_files(&self, req: arkdropx_sender::SendFilesRequest) {
-        let curr_sub = self.sub.clone();
-        let curr_bubble = self.bubble.clone();
-
-        tokio::spawn(async move {
-            let bubble = send_files(req).await;
-            match bubble {
-                Ok(bub) => {
-                    let bub = Arc::new(bub);
-
-                    if let Some(sub) = curr_sub.read().unwrap().clone() {
-                        bub.subscribe(sub);
-                    }
+    fn send_files(&self, make_req: RequestBuilder<SendFilesRequest>) {
+        self.make_req
+            .write()
+            .unwrap()
+            .replace(make_req.clone());
+        self.start(make_req);
+    }
 
-                    curr_bubble.write().unwrap().replace(bub)
-                }
-                Err(_) => todo!(),
-            }
-        });
+    fn retry(&self) -> bool {
+        let Some(make_req) = self.make_req.read().unwrap().clone() else {
+            return false;
+        };
+        // The bubble of the failed session is dropped, not to show its
+        // ticket until the new one is ready
+        self.bubble.write().unwrap().take();
+        self.start(make_req);
+        true
     }
 
     fn get_send_files_bubble(
@@ -55,10 +58,45 @@ impl MainAppSendFilesManager {
         Self {
             bubble: Arc::new(RwLock::new(None)),
             sub: Arc::new(RwLock::new(None)),
+            make_req: RwLock::new(None),
         }
     }
 
     pub fn set_send_files_subscriber(&self, sub: Arc<dyn SendFilesSubscriber>) {
         self.sub.write().unwrap().replace(sub);
     }
+
+    fn start(&self, make_req: RequestBuilder<SendFilesRequest>) {
+        let curr_sub = self.sub.clone();
+        let curr_bubble = self.bubble.clone();
+
+        tokio::spawn(async move {
+            let Some(req) = make_req() else {
+                if let Some(sub) = curr_sub.read().unwrap().clone() {
+                    sub.log("[ERROR] Selected files can't be read".to_string());
+                    sub.notify_ended(EndReason::Failed);
+                }
+                return;
+            };
+
+            let bubble = send_files(req).await;
+            match bubble {
+                Ok(bub) => {
+                    let bub = Arc::new(bub);
+
+                    if let Some(sub) = curr_sub.read().unwrap().clone() {
+                        bub.subscribe(sub);
+                    }
+
+                    curr_bubble.write().unwrap().replace(bub);
+                }
+                Err(e) => {
+                    if let Some(sub) = curr_sub.read().unwrap().clone() {
+                        sub.log(format!("[ERROR] Failed to start: {}", e));
+                        sub.notify_ended(EndReason::of(&e));
+                    }
+                }
+            }
+        });
+    }
 }
@@ -1,14 +1,20 @@
 use std::sync::{Arc, RwLock};
 
-use arkdropx_sender::send_files_to::{
-    SendFilesToBubble, SendFilesToRequest, SendFilesToSubscriber, send_files_to,
+use arkdropx_sender::{
+    EndReason,
+    send_files_to::{
+        SendFilesToBubble, SendFilesToRequest, SendFilesToSubscriber,
+        send_files_to,
+    },
 };
 
-use crate::AppSendFilesToManager;
+use crate::{AppSendFilesToManager, RequestBuilder};
 
 pub struct MainAppSendFilesToManager {
     bubble: Arc<RwLock<Option<Arc<SendFilesToBubble>>>>,
     sub: Arc<RwLock<Option<Arc<dyn SendFilesToSubscriber>>>>,
+    // Kept after the session ends, to retry it
+    make_req: RwLock<Option<RequestBuilder<SendFilesToRequest>>>,
 }
 
 impl AppSendFilesToManager for MainAppSendFilesToManager {
@@ -23,11 +29,66 @@ impl AppSendFilesToManager for MainAppSendFilesToManager {
         });
     }
 
-    fn send_files_to(&self, req: SendFilesToRequest) {
+    fn send_files_to(&self, make_req: RequestBuilder<SendFilesToRequest>) {
+        self.make_req
+            .write()
+            .unwrap()
+            .replace(make_req.clone());
+        self.start(make_req);
+    }
+
+    fn retry(&self) -> bool {
+        let Some(make_req) = self.make_req.read().unwrap().clone() else {
+            return false;
+        };
+        // The bubble of the failed session is dropped, not to show its
+        // ticket until the new one is ready
+        self.bubble.write().unwrap().take();
+        self.start(make_req);
+        true
+    }
+
+    fn get_send_files_to_bubble(&self) -> Option<Arc<SendFilesToBubble>> {
+        let bubble = self.bubble.read().unwrap();
+        bubble.clone()
+    }
+}
+
+impl Default for MainAppSendFilesToManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MainAppSendFilesToManager {
+    pub fn new() -> Self {
+        Self {
+            bubble: Arc::new(RwLock::new(None)),
+            sub: Arc::new(RwLock::new(None)),
+            make_req: RwLock::new(None),
+        }
+    }
+
+    pub fn set_send_files_to_subscriber(
+        &self,
+        sub: Arc<dyn SendFilesToSubscriber>,
+    ) {
+        self.sub.write().unwrap().replace(sub);
+    }
+
+    fn start(&self, make_req: RequestBuilder<SendFilesToRequest>) {
         let curr_sub = self.sub.clone();
         let curr_bubble = self.bubble.clone();
 
         tokio::spawn(async move {
+            let Some(req) = make_req() else {
+                if let Some(sub) = curr_sub.read().unwrap().clone() {
+                    sub.log("[ERROR] Selected files can't be read".to_string());
+                    sub.notify_ended(EndReason::Failed);
+                }
+                return;
+            };
+
             let bubble = send_files_to(req).await;
             match bubble {
                 Ok(bub) => {
@@ -42,6 +103,7 @@ impl AppSendFilesToManager for MainAppSendFilesToManager {
                                 "[ERROR] Failed to start transfer: {}",
                                 e
                             ));
+                            sub.notify_ended(EndReason::of(&e));
                         }
                     }
 
@@ -51,36 +113,10 @@ impl AppSendFilesToManager for MainAppSendFilesToManager {
                     // Log error to subscriber if available
                     if let Some(sub) = curr_sub.read().unwrap().clone() {
                         sub.log(format!("[ERROR] Failed to connect: {}", e));
+                        sub.notify_ended(EndReason::of(&e));
                     }
                 }
             }
         });
     }
-
-    fn get_send_files_to_bubble(&self) -> Option<Arc<SendFilesToBubble>> {
-        let bubble = self.bubble.read().unwrap();
-        bubble.clone()
-    }
-}
-
-impl Default for MainAppSendFilesToManager {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-impl MainAppSendFilesToManager {
-    pub fn new() -> Self {
-        Self {
-            bubble: Arc::new(RwLock::new(None)),
-            sub: Arc::new(RwLock::new(None)),
-        }
-    }
-
-    pub fn set_send_files_to_subscriber(
-        &self,
-        sub: Arc<dyn SendFilesToSubscriber>,
-    ) {
-        self.sub.write().unwrap().replace(sub);
-    }
 }
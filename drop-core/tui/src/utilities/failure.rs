@@ -0,0 +1,231 @@
+use std::sync::RwLock;
+
+use arkdropx_sender::EndReason;
+use ratatui::{
+    Frame,
+    crossterm::event::KeyCode,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Style, Stylize},
+    symbols::border,
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
+};
+
+/// Log lines kept per session, older ones are dropped first.
+const MAX_LOG_LINES: usize = 500;
+
+/// Actions offered on a failed transfer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureAction {
+    /// Start the same session again.
+    Retry,
+    /// Go back to the page the session was configured on.
+    Reconfigure,
+    /// Show or hide the log of the failed session.
+    ToggleLogs,
+    /// Leave the failed session for the home page.
+    Home,
+}
+
+impl FailureAction {
+    pub fn from_key(code: KeyCode) -> Option<Self> {
+        match code {
+            KeyCode::Char('r') | KeyCode::Char('R') => Some(Self::Retry),
+            KeyCode::Char('e') | KeyCode::Char('E') => Some(Self::Reconfigure),
+            KeyCode::Char('l') | KeyCode::Char('L') => Some(Self::ToggleLogs),
+            KeyCode::Char('h') | KeyCode::Char('H') | KeyCode::Esc => {
+                Some(Self::Home)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Failure state of a transfer progress page.
+///
+/// Keeps the log of the session so it can be inspected once the session
+/// failed, instead of the page dead-ending on the last log line.
+pub struct TransferFailure {
+    reason: RwLock<Option<String>>,
+    logs: RwLock<Vec<String>>,
+    show_logs: RwLock<bool>,
+}
+
+impl Default for TransferFailure {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TransferFailure {
+    pub fn new() -> Self {
+        Self {
+            reason: RwLock::new(None),
+            logs: RwLock::new(Vec::new()),
+            show_logs: RwLock::new(false),
+        }
+    }
+
+    pub fn log(&self, message: &str) {
+        let mut logs = self.logs.write().unwrap();
+        if logs.len() == MAX_LOG_LINES {
+            logs.remove(0);
+        }
+        logs.push(message.to_string());
+    }
+
+    pub fn fail(&self, reason: &str) {
+        self.reason
+            .write()
+            .unwrap()
+            .replace(reason.to_string());
+    }
+
+    /// Records the end of a session, which failed unless it completed or
+    /// was cancelled by a user.
+    pub fn end(&self, reason: EndReason) {
+        match reason {
+            EndReason::Completed | EndReason::Cancelled => {}
+            reason => self.fail(&format!("Transfer {}", reason.phrase())),
+        }
+    }
+
+    pub fn is_failed(&self) -> bool {
+        self.reason.read().unwrap().is_some()
+    }
+
+    pub fn toggle_logs(&self) {
+        let mut show_logs = self.show_logs.write().unwrap();
+        *show_logs = !*show_logs;
+    }
+
+    pub fn reset(&self) {
+        *self.reason.write().unwrap() = None;
+        self.logs.write().unwrap().clear();
+        *self.show_logs.write().unwrap() = false;
+    }
+
+    /// Draws the failure with its actions, or the log of the session when
+    /// toggled.
+    pub fn draw(&self, f: &mut Frame, area: Rect, retry_label: &str) {
+        let blocks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([
+                Constraint::Length(5), // Reason
+                Constraint::Min(6),    // Actions or logs
+            ])
+            .split(area);
+
+        self.draw_reason(f, blocks[0]);
+        if *self.show_logs.read().unwrap() {
+            self.draw_logs(f, blocks[1]);
+        } else {
+            draw_actions(f, blocks[1], retry_label);
+        }
+    }
+
+    fn draw_reason(&self, f: &mut Frame, area: Rect) {
+        let reason = self
+            .reason
+            .read()
+            .unwrap()
+            .clone()
+            .unwrap_or_default();
+        let last_log = self
+            .logs
+            .read()
+            .unwrap()
+            .last()
+            .cloned()
+            .unwrap_or_default();
+
+        let reason_content = vec![
+            Line::from(vec![
+                Span::styled("❌ ", Style::default().fg(Color::Red)),
+                Span::styled(reason, Style::default().fg(Color::Red).bold()),
+            ]),
+            Line::from(vec![Span::styled(
+                last_log,
+                Style::default().fg(Color::Gray),
+            )]),
+        ];
+
+        let reason_block = Block::default()
+            .borders(Borders::ALL)
+            .border_set(border::ROUNDED)
+            .border_style(Style::default().fg(Color::Red))
+            .title(" Transfer Failed ")
+            .title_style(Style::default().fg(Color::White).bold());
+
+        let reason_widget = Paragraph::new(reason_content)
+            .block(reason_block)
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+
+        f.render_widget(reason_widget, area);
+    }
+
+    fn draw_logs(&self, f: &mut Frame, area: Rect) {
+        let logs = self.logs.read().unwrap();
+        // Only the latest lines fit, the borders take two rows
+        let visible = (area.height as usize).saturating_sub(2);
+        let log_items: Vec<ListItem> = logs
+            .iter()
+            .skip(logs.len().saturating_sub(visible))
+            .map(|line| {
+                let color = if line.starts_with("[ERROR]") {
+                    Color::Red
+                } else {
+                    Color::Gray
+                };
+                ListItem::new(Line::from(Span::styled(
+                    line.clone(),
+                    Style::default().fg(color),
+                )))
+            })
+            .collect();
+
+        let logs_block = Block::default()
+            .borders(Borders::ALL)
+            .border_set(border::ROUNDED)
+            .border_style(Style::default().fg(Color::Yellow))
+            .title(format!(" Logs ({}) • L to close ", logs.len()))
+            .title_style(Style::default().fg(Color::White).bold());
+
+        f.render_widget(List::new(log_items).block(logs_block), area);
+    }
+}
+
+fn draw_actions(f: &mut Frame, area: Rect, retry_label: &str) {
+    let action = |key: &'static str, label: String, color: Color| {
+        Line::from(vec![
+            Span::styled(
+                format!("[{}] ", key),
+                Style::default().fg(color).bold(),
+            ),
+            Span::styled(label, Style::default().fg(Color::White)),
+        ])
+    };
+
+    let actions_content = vec![
+        Line::from(""),
+        action("R", retry_label.to_string(), Color::Green),
+        action("E", "Reconfigure and retry".to_string(), Color::Cyan),
+        action("L", "Open logs".to_string(), Color::Yellow),
+        action("H", "Return home".to_string(), Color::Magenta),
+    ];
+
+    let actions_block = Block::default()
+        .borders(Borders::ALL)
+        .border_set(border::ROUNDED)
+        .border_style(Style::default().fg(Color::White))
+        .title(" What's Next? ")
+        .title_style(Style::default().fg(Color::White).bold());
+
+    let actions_widget = Paragraph::new(actions_content)
+        .block(actions_block)
+        .alignment(Alignment::Center);
+
+    f.render_widget(actions_widget, area);
+}
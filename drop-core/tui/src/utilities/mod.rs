@@ -1,3 +1,4 @@
 pub mod clipboard;
+pub mod failure;
 pub mod helper_footer;
 pub mod qr_renderer;
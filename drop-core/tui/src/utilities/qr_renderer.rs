@@ -1,11 +1,11 @@
-use qrcode::QrCode;
+use qrcode::{Color as Module, QrCode};
 use ratatui::{
     Frame,
     layout::Alignment,
     style::{Color, Style, Stylize},
     symbols::border,
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph},
+    widgets::{Block, Borders, Paragraph, Wrap},
 };
 
 /// Error type for QR code rendering failures.
@@ -22,37 +22,103 @@ impl std::fmt::Display for QrRenderError {
 
 impl std::error::Error for QrRenderError {}
 
+/// How QR code modules are mapped to terminal cells.
+///
+/// Both scales keep modules roughly square, as terminal cells are about
+/// twice as high as they are wide.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QrScale {
+    /// Each module takes two cells of a single line.
+    Full,
+    /// Each module takes half of a cell, two lines of modules being drawn
+    /// per line with half blocks.
+    Compact,
+}
+
+impl QrScale {
+    /// Scales from the most to the least readable one.
+    pub const ALL: [QrScale; 2] = [QrScale::Full, QrScale::Compact];
+
+    /// Width and height in cells of a QR code of `modules` modules per side.
+    pub fn size(&self, modules: usize) -> (usize, usize) {
+        match self {
+            QrScale::Full => (modules * 2, modules),
+            QrScale::Compact => (modules, modules.div_ceil(2)),
+        }
+    }
+}
+
 /// Reusable QR code rendering utilities for TUI applications.
 pub struct QrCodeRenderer;
 
 impl QrCodeRenderer {
-    /// Generates QR code as a vector of styled lines for display.
+    /// Generates QR code as a vector of styled lines for display, at the
+    /// most readable scale fitting in `width` x `height` cells.
     ///
-    /// The QR code uses doubled-width blocks for better terminal visibility.
+    /// Returns `None` if the QR code doesn't fit even when downscaled.
     pub fn render_qr_lines(
         data: &str,
-    ) -> Result<Vec<Line<'static>>, QrRenderError> {
-        let qr_code = QrCode::new(data).map_err(|e| QrRenderError {
+        width: u16,
+        height: u16,
+    ) -> Result<Option<Vec<Line<'static>>>, QrRenderError> {
+        let qr_code = Self::encode(data)?;
+        let scale = QrScale::ALL.into_iter().find(|scale| {
+            let (w, h) = scale.size(qr_code.width());
+            w <= width as usize && h <= height as usize
+        });
+
+        Ok(scale.map(|scale| Self::scaled_lines(&qr_code, scale)))
+    }
+
+    fn encode(data: &str) -> Result<QrCode, QrRenderError> {
+        QrCode::new(data).map_err(|e| QrRenderError {
             message: e.to_string(),
-        })?;
-
-        let qr_matrix = qr_code
-            .render::<char>()
-            .quiet_zone(false)
-            .module_dimensions(1, 1)
-            .build();
-
-        let lines: Vec<Line<'static>> = qr_matrix
-            .lines()
-            .map(|line| {
-                Line::from(vec![Span::styled(
-                    line.replace('█', "██").replace(' ', "  "),
-                    Style::default().fg(Color::White).bg(Color::Black),
-                )])
-            })
-            .collect();
-
-        Ok(lines)
+        })
+    }
+
+    fn scaled_lines(qr_code: &QrCode, scale: QrScale) -> Vec<Line<'static>> {
+        let modules = qr_code.width();
+        let colors = qr_code.to_colors();
+        let rows: Vec<&[Module]> = colors.chunks(modules).collect();
+        let style = Style::default().fg(Color::White).bg(Color::Black);
+
+        let lines: Vec<String> = match scale {
+            QrScale::Full => rows
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|module| match module {
+                            Module::Dark => "██",
+                            Module::Light => "  ",
+                        })
+                        .collect()
+                })
+                .collect(),
+            QrScale::Compact => rows
+                .chunks(2)
+                .map(|pair| {
+                    (0..modules)
+                        .map(|x| {
+                            let top = pair[0][x] == Module::Dark;
+                            let bottom = pair
+                                .get(1)
+                                .is_some_and(|row| row[x] == Module::Dark);
+                            match (top, bottom) {
+                                (true, true) => '█',
+                                (true, false) => '▀',
+                                (false, true) => '▄',
+                                (false, false) => ' ',
+                            }
+                        })
+                        .collect()
+                })
+                .collect(),
+        };
+
+        lines
+            .into_iter()
+            .map(|line| Line::from(vec![Span::styled(line, style)]))
+            .collect()
     }
 
     /// Creates a styled block for QR code display.
@@ -125,24 +191,64 @@ impl QrCodeRenderer {
         f.render_widget(error_widget, area);
     }
 
+    /// Renders the plain ticket when the QR code doesn't fit the terminal.
+    pub fn render_fallback(
+        f: &mut Frame,
+        area: ratatui::prelude::Rect,
+        block: Block,
+        ticket: &str,
+    ) {
+        let fallback_content = vec![
+            Line::from(vec![
+                Span::styled("📐 ", Style::default().fg(Color::Yellow)),
+                Span::styled(
+                    "Terminal too small for QR code",
+                    Style::default().fg(Color::Yellow).bold(),
+                ),
+            ]),
+            Line::from(vec![Span::styled(
+                "Enlarge the window to scan, or share the ticket:",
+                Style::default().fg(Color::Gray),
+            )]),
+            Line::from(""),
+            Line::from(vec![Span::styled(
+                ticket.to_string(),
+                Style::default().fg(Color::White),
+            )]),
+        ];
+
+        let fallback_widget = Paragraph::new(fallback_content)
+            .block(block)
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: false });
+
+        f.render_widget(fallback_widget, area);
+    }
+
     /// Renders a complete QR code with the given data.
     ///
     /// This is a convenience method that handles all rendering states:
-    /// - Shows the QR code if generation succeeds
+    /// - Shows the QR code, downscaled if needed to fit the area
+    /// - Shows the plain `ticket` if the QR code doesn't fit at all
     /// - Shows an error message if generation fails
     pub fn render_qr_code(
         f: &mut Frame,
         area: ratatui::prelude::Rect,
         block: Block,
         data: &str,
+        ticket: &str,
     ) {
-        match Self::render_qr_lines(data) {
-            Ok(qr_lines) => {
+        let inner = block.inner(area);
+        match Self::render_qr_lines(data, inner.width, inner.height) {
+            Ok(Some(qr_lines)) => {
                 let qr_widget = Paragraph::new(qr_lines)
                     .block(block)
                     .alignment(Alignment::Center);
                 f.render_widget(qr_widget, area);
             }
+            Ok(None) => {
+                Self::render_fallback(f, area, block, ticket);
+            }
             Err(e) => {
                 Self::render_error(f, area, block, &e.message);
             }
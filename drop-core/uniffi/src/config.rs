@@ -0,0 +1,127 @@
+//! Shared application configuration exposed to foreign bindings.
+//!
+//! Apps read and update the same `config.toml` as the CLI and TUI, through
+//! `arkdrop_common::AppConfig`. Settings without a counterpart here, such as
+//! network settings and limits, are kept as they are when saving.
+
+use std::path::PathBuf;
+
+use crate::{DropError, ReceiverConfig, SenderConfig};
+
+/// Tuning of transfers, see `arkdrop_common::PerformancePreset`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PerformancePreset {
+    HighPerformance,
+    Balanced,
+    LowBandwidth,
+}
+
+impl From<arkdrop_common::PerformancePreset> for PerformancePreset {
+    fn from(preset: arkdrop_common::PerformancePreset) -> Self {
+        use arkdrop_common::PerformancePreset as Preset;
+        match preset {
+            Preset::HighPerformance => Self::HighPerformance,
+            Preset::Balanced => Self::Balanced,
+            Preset::LowBandwidth => Self::LowBandwidth,
+        }
+    }
+}
+
+impl From<PerformancePreset> for arkdrop_common::PerformancePreset {
+    fn from(preset: PerformancePreset) -> Self {
+        match preset {
+            PerformancePreset::HighPerformance => Self::HighPerformance,
+            PerformancePreset::Balanced => Self::Balanced,
+            PerformancePreset::LowBandwidth => Self::LowBandwidth,
+        }
+    }
+}
+
+/// Settings shared by the apps, the CLI and the TUI.
+pub struct AppConfig {
+    /// Default receive directory, a suggested one is used if `None`.
+    pub out_dir: Option<String>,
+    /// Display name shown to peers.
+    pub profile_name: Option<String>,
+    /// Image file of the avatar shown to peers.
+    pub avatar_file: Option<String>,
+    /// Chunk size and parallel streams of transfers.
+    pub preset: PerformancePreset,
+    /// Node ids of the devices allowed to push files in sync sessions.
+    pub trusted_peers: Vec<String>,
+}
+
+/// Loads the configuration, the default one if it was never saved.
+pub fn load_app_config() -> Result<AppConfig, DropError> {
+    let config = load()?;
+    Ok(AppConfig {
+        out_dir: config
+            .out_dir
+            .map(|dir| dir.to_string_lossy().into_owned()),
+        profile_name: config.avatar_name,
+        avatar_file: config
+            .avatar_file
+            .map(|file| file.to_string_lossy().into_owned()),
+        preset: config.preset.into(),
+        trusted_peers: config.sync.trusted_peers.into_iter().collect(),
+    })
+}
+
+/// Persists the given settings, keeping the others of the configuration.
+pub fn save_app_config(config: AppConfig) -> Result<(), DropError> {
+    let mut current = load()?;
+    current.out_dir = config.out_dir.map(PathBuf::from);
+    current.avatar_name = config.profile_name;
+    current.avatar_file = config.avatar_file.map(PathBuf::from);
+    current.preset = config.preset.into();
+    current.sync.trusted_peers = config.trusted_peers.into_iter().collect();
+    current
+        .save()
+        .map_err(|e| DropError::TODO(e.to_string()))
+}
+
+/// Returns the directory received files are saved to by default: the
+/// configured one, else a suggested one.
+pub fn get_default_out_dir() -> String {
+    arkdrop_common::get_default_out_dir()
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Allows the device with the given node id to push files in sync sessions.
+pub fn trust_peer(node_id: String) -> Result<(), DropError> {
+    load()?
+        .trust_peer(node_id)
+        .map_err(|e| DropError::TODO(e.to_string()))
+}
+
+/// Stops allowing a device to push files. Returns whether it was trusted.
+pub fn untrust_peer(node_id: String) -> Result<bool, DropError> {
+    load()?
+        .untrust_peer(&node_id)
+        .map_err(|e| DropError::TODO(e.to_string()))
+}
+
+/// Sender settings of a preset.
+pub fn preset_sender_config(preset: PerformancePreset) -> SenderConfig {
+    let preset = arkdrop_common::PerformancePreset::from(preset);
+    SenderConfig {
+        chunk_size: preset.chunk_size(),
+        parallel_streams: preset.parallel_streams(),
+        max_concurrent_files: None,
+    }
+}
+
+/// Receiver settings of a preset.
+pub fn preset_receiver_config(preset: PerformancePreset) -> ReceiverConfig {
+    let preset = arkdrop_common::PerformancePreset::from(preset);
+    ReceiverConfig {
+        chunk_size: preset.chunk_size(),
+        parallel_streams: preset.parallel_streams(),
+    }
+}
+
+fn load() -> Result<arkdrop_common::AppConfig, DropError> {
+    arkdrop_common::AppConfig::load()
+        .map_err(|e| DropError::TODO(e.to_string()))
+}
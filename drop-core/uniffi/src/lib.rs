@@ -4,9 +4,11 @@
 //! DropError type surfaced to foreign languages. Bindings are generated via
 //! `uniffi::include_scaffolding!`.
 
+mod config;
 mod receiver;
 mod sender;
 
+pub use config::*;
 pub use receiver::*;
 pub use sender::*;
 
@@ -23,6 +25,33 @@ pub enum DropError {
     TODO(String),
 }
 
+/// Why a session ended, as told to or received from the peer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EndReason {
+    Completed,
+    Cancelled,
+    Timeout,
+    VerificationFailed,
+    DiskFull,
+    TooLarge,
+    Failed,
+}
+
+impl From<arkdropx_sender::EndReason> for EndReason {
+    fn from(reason: arkdropx_sender::EndReason) -> Self {
+        use arkdropx_sender::EndReason as Reason;
+        match reason {
+            Reason::Completed => Self::Completed,
+            Reason::Cancelled => Self::Cancelled,
+            Reason::Timeout => Self::Timeout,
+            Reason::VerificationFailed => Self::VerificationFailed,
+            Reason::DiskFull => Self::DiskFull,
+            Reason::TooLarge => Self::TooLarge,
+            Reason::Failed => Self::Failed,
+        }
+    }
+}
+
 // UniFFI picks up the UDL and generates the FFI scaffolding.
 // Keep this at crate root to expose all exported items.
 uniffi::include_scaffolding!("drop");
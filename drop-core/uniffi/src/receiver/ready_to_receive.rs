@@ -7,7 +7,7 @@
 use std::sync::Arc;
 
 use super::{ReceiverConfig, ReceiverProfile};
-use crate::DropError;
+use crate::{DropError, EndReason};
 
 /// Request to start waiting for a sender.
 ///
@@ -53,6 +53,11 @@ impl ReadyToReceiveBubble {
         self.inner.is_finished()
     }
 
+    /// Why the session ended, `None` while it runs.
+    pub fn get_end_reason(&self) -> Option<EndReason> {
+        self.inner.get_end_reason().map(EndReason::from)
+    }
+
     /// True once a sender has connected and handshake has completed.
     pub fn is_connected(&self) -> bool {
         self.inner.is_connected()
@@ -63,6 +68,20 @@ impl ReadyToReceiveBubble {
         self.inner.get_created_at()
     }
 
+    /// Files announced by the connected sender, empty until a sender
+    /// completed the handshake.
+    pub fn get_files(&self) -> Vec<ReadyToReceiveFile> {
+        self.inner
+            .get_files()
+            .into_iter()
+            .map(|f| ReadyToReceiveFile {
+                id: f.id,
+                name: f.name,
+                len: f.len,
+            })
+            .collect()
+    }
+
     /// Register an observer for logs, chunk payloads, and connection events.
     ///
     /// The subscriber is adapted and passed to the underlying transport.
@@ -116,6 +135,7 @@ pub struct ReadyToReceiveSenderProfile {
     pub id: String,
     pub name: String,
     pub avatar_b64: Option<String>,
+    pub node_id: Option<String>,
 }
 
 /// Information about a single file to be received.
@@ -162,6 +182,7 @@ impl arkdropx_receiver::ready_to_receive::ReadyToReceiveSubscriber
                     id: event.sender.id,
                     name: event.sender.name,
                     avatar_b64: event.sender.avatar_b64,
+                    node_id: event.sender.node_id,
                 },
                 files: event
                     .files
@@ -215,6 +236,7 @@ fn create_adapted_request(
             arkdropx_receiver::ready_to_receive::ReadyToReceiveConfig {
                 chunk_size: config.chunk_size,
                 parallel_streams: config.parallel_streams,
+                ..Default::default()
             }
         }
         None => {
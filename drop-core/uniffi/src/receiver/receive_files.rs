@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use crate::DropError;
+use crate::{DropError, EndReason};
 
 use super::{ReceiverConfig, ReceiverProfile};
 
@@ -45,11 +45,29 @@ impl ReceiveFilesBubble {
         self.inner.is_finished()
     }
 
+    /// Why the session ended, `None` while it runs.
+    pub fn get_end_reason(&self) -> Option<EndReason> {
+        self.inner.get_end_reason().map(EndReason::from)
+    }
+
     /// True if the session has been explicitly canceled.
     pub fn is_cancelled(&self) -> bool {
         self.inner.is_cancelled()
     }
 
+    /// Files announced by the sender, empty until the handshake completed.
+    pub fn get_files(&self) -> Vec<ReceiveFilesFile> {
+        self.inner
+            .get_files()
+            .into_iter()
+            .map(|f| ReceiveFilesFile {
+                id: f.id,
+                name: f.name,
+                len: f.len,
+            })
+            .collect()
+    }
+
     /// Register an observer for logs, chunk payloads, and connection events.
     pub fn subscribe(&self, subscriber: Arc<dyn ReceiveFilesSubscriber>) {
         let adapted_subscriber =
@@ -101,6 +119,7 @@ pub struct ReceiveFilesProfile {
     pub id: String,
     pub name: String,
     pub avatar_b64: Option<String>,
+    pub node_id: Option<String>,
 }
 
 /// Information about a single file to be received.
@@ -147,6 +166,7 @@ impl arkdropx_receiver::ReceiveFilesSubscriber
                     id: event.sender.id,
                     name: event.sender.name,
                     avatar_b64: event.sender.avatar_b64,
+                    node_id: event.sender.node_id,
                 },
                 files: event
                     .files
@@ -199,6 +219,7 @@ fn create_adapted_request(
         .map(|c| arkdropx_receiver::ReceiverConfig {
             chunk_size: c.chunk_size,
             parallel_streams: c.parallel_streams,
+            ..Default::default()
         });
     arkdropx_receiver::ReceiveFilesRequest {
         profile,
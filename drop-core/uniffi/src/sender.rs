@@ -1,12 +1,17 @@
 //! Public sender-facing types re-exported to foreign bindings.
 //!
 //! These types form the high-level API that foreign bindings use. The concrete
-//! file data is provided by the embedding app via the `SenderFileData` trait.
+//! file data is provided by the embedding app via the `SenderFileData` trait,
+//! or read from a path with [`file_data_from_path`].
 
 mod send_files;
 mod send_files_to;
 
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc};
+
+use arkdropx_sender::SenderFileData as _;
+
+use crate::DropError;
 
 pub use send_files::*;
 pub use send_files_to::*;
@@ -68,11 +73,46 @@ impl arkdropx_sender::SenderFileData for SenderFileDataAdapter {
     }
 }
 
+/// File on disk read by the shared `arkdrop_common::FileData`.
+struct PathFileData {
+    inner: arkdrop_common::FileData,
+}
+impl SenderFileData for PathFileData {
+    fn len(&self) -> u64 {
+        self.inner.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    fn read(&self) -> Option<u8> {
+        self.inner.read()
+    }
+
+    fn read_chunk(&self, size: i32) -> Vec<u8> {
+        self.inner.read_chunk(size.max(0) as u64)
+    }
+}
+
+/// Data source reading the file at `path`, for apps with direct filesystem
+/// access that don't need their own `SenderFileData` implementation.
+pub fn file_data_from_path(
+    path: String,
+) -> Result<Arc<dyn SenderFileData>, DropError> {
+    let inner = arkdrop_common::FileData::new(PathBuf::from(path))
+        .map_err(|e| DropError::TODO(e.to_string()))?;
+    Ok(Arc::new(PathFileData { inner }))
+}
+
 /// Tuning parameters for the send pipeline.
 ///
 /// - `chunk_size`: bytes per chunk when streaming.
 /// - `parallel_streams`: number of concurrent channels used by the transport.
+/// - `max_concurrent_files`: files sent simultaneously, one per parallel
+///   stream if `None`.
 pub struct SenderConfig {
     pub chunk_size: u64,
     pub parallel_streams: u64,
+    pub max_concurrent_files: Option<u64>,
 }
@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use super::{SenderConfig, SenderFile, SenderFileDataAdapter, SenderProfile};
-use crate::DropError;
+use crate::{DropError, EndReason};
 
 /// Request to start a send session.
 ///
@@ -50,6 +50,11 @@ impl SendFilesBubble {
         self.inner.is_finished()
     }
 
+    /// Why the session ended, `None` while it runs.
+    pub fn get_end_reason(&self) -> Option<EndReason> {
+        self.inner.get_end_reason().map(EndReason::from)
+    }
+
     /// True once a receiver has connected and handshake has completed.
     pub fn is_connected(&self) -> bool {
         self.inner.is_connected()
@@ -112,6 +117,7 @@ pub struct SendFilesProfile {
     pub id: String,
     pub name: String,
     pub avatar_b64: Option<String>,
+    pub node_id: Option<String>,
 }
 
 /// Adapter bridging this crate's subscriber trait to the lower-level one.
@@ -147,6 +153,7 @@ impl arkdropx_sender::SendFilesSubscriber for SendFilesSubscriberAdapter {
                     id: event.receiver.id,
                     name: event.receiver.name,
                     avatar_b64: event.receiver.avatar_b64,
+                    node_id: event.receiver.node_id,
                 },
             })
     }
@@ -194,6 +201,7 @@ fn create_adapted_request(
             arkdropx_sender::SenderFile {
                 name: f.name,
                 data: Arc::new(data),
+                kind: arkdropx_sender::ItemKind::File,
             }
         })
         .collect();
@@ -201,6 +209,8 @@ fn create_adapted_request(
         Some(config) => arkdropx_sender::SenderConfig {
             chunk_size: config.chunk_size,
             parallel_streams: config.parallel_streams,
+            max_concurrent_files: config.max_concurrent_files,
+            ..Default::default()
         },
         None => arkdropx_sender::SenderConfig::default(),
     };
@@ -6,7 +6,7 @@
 use std::sync::Arc;
 
 use super::{SenderConfig, SenderFile, SenderFileDataAdapter, SenderProfile};
-use crate::DropError;
+use crate::{DropError, EndReason};
 
 /// Request to start a send-to session.
 ///
@@ -53,6 +53,11 @@ impl SendFilesToBubble {
         self.inner.is_finished()
     }
 
+    /// Why the session ended, `None` while it runs.
+    pub fn get_end_reason(&self) -> Option<EndReason> {
+        self.inner.get_end_reason().map(EndReason::from)
+    }
+
     /// Register an observer for logs and progress/connect events.
     ///
     /// The subscriber is adapted and passed to the underlying transport.
@@ -105,6 +110,7 @@ pub struct SendFilesToReceiverProfile {
     pub id: String,
     pub name: String,
     pub avatar_b64: Option<String>,
+    pub node_id: Option<String>,
 }
 
 /// Adapter bridging this crate's subscriber trait to the lower-level one.
@@ -146,6 +152,7 @@ impl arkdropx_sender::send_files_to::SendFilesToSubscriber
                     id: event.receiver.id,
                     name: event.receiver.name,
                     avatar_b64: event.receiver.avatar_b64,
+                    node_id: event.receiver.node_id,
                 },
             })
     }
@@ -189,6 +196,7 @@ fn create_adapted_request(
             arkdropx_sender::SenderFile {
                 name: f.name,
                 data: Arc::new(data),
+                kind: arkdropx_sender::ItemKind::File,
             }
         })
         .collect();
@@ -196,6 +204,8 @@ fn create_adapted_request(
         Some(config) => arkdropx_sender::SenderConfig {
             chunk_size: config.chunk_size,
             parallel_streams: config.parallel_streams,
+            max_concurrent_files: config.max_concurrent_files,
+            ..Default::default()
         },
         None => arkdropx_sender::SenderConfig::default(),
     };
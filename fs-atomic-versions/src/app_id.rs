@@ -1,45 +1,195 @@
-use std::{fs, path::Path};
+//! Identity of the device running the app.
+//!
+//! A device is identified by a random UUID, which tells apart the versions
+//! written by different devices in synced folders, see [`crate::atomic`], and
+//! by a human-readable name shown to users, e.g. to peers when sharing files.
+//!
+//! The identity is stored as JSON in `<root>/.ark/device`, see
+//! [`load_identity`]. Ids generated by older versions, stored in an `app_id`
+//! file, are kept. [`load`] still stores a bare id in `<root>/app_id`.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
 
 use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
 
 use data_error::{ArklibError, Result};
 
-use crate::{APP_ID_FILE, APP_ID_PATH};
+use crate::{APP_ID_FILE, APP_ID_PATH, ARK_FOLDER, DEVICE_FILE};
 
-fn generate<P: AsRef<Path>>(app_id_path: P) -> Result<String> {
-    let id = uuid::Uuid::new_v4().to_string();
-    fs::write(app_id_path, &id)?;
-    Ok(id)
+/// Identity of a device
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceIdentity {
+    /// Random UUID, stable until rotated
+    pub id: String,
+    /// Human-readable name, the host name by default
+    pub name: String,
 }
 
-pub fn read() -> Result<String> {
+impl DeviceIdentity {
+    /// Generate a new identity, named after the host
+    pub fn generate() -> Self {
+        let id = uuid::Uuid::new_v4().to_string();
+        let name = default_name(&id);
+        Self { id, name }
+    }
+
+    /// Return the path of the identity stored under `root`
+    pub fn path<P: AsRef<Path>>(root_path: P) -> PathBuf {
+        root_path
+            .as_ref()
+            .join(ARK_FOLDER)
+            .join(DEVICE_FILE)
+    }
+
+    /// Load the identity stored under `root`, or generate and store one if
+    /// there is none
+    ///
+    /// The id stored by older versions in `<root>/.ark/app_id` or
+    /// `<root>/app_id` is kept if there is one.
+    pub fn load_or_create<P: AsRef<Path>>(root_path: P) -> Result<Self> {
+        let root_path = root_path.as_ref();
+        let path = Self::path(root_path);
+        if path.exists() {
+            return Self::read_from(&path);
+        }
+
+        let mut identity = Self::generate();
+        let legacy_paths = [
+            root_path.join(ARK_FOLDER).join(APP_ID_FILE),
+            root_path.join(APP_ID_FILE),
+        ];
+        if let Some(id) = legacy_paths
+            .iter()
+            .find_map(|path| fs::read_to_string(path).ok())
+        {
+            log::info!("Keeping the app id of an older version");
+            identity.id = id.trim().to_owned();
+        }
+        identity.write_to(&path)?;
+        Ok(identity)
+    }
+
+    /// Read an identity from a file
+    pub fn read_from<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Write the identity to a file, creating its folder if needed
+    pub fn write_to<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Replace the id with a new one, keeping the name
+    pub fn rotate(&mut self) {
+        self.id = uuid::Uuid::new_v4().to_string();
+    }
+}
+
+/// Host name from the environment, or a name derived from the id
+fn default_name(id: &str) -> String {
+    ["HOSTNAME", "COMPUTERNAME"]
+        .iter()
+        .find_map(|var| env::var(var).ok())
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_owned())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| format!("Device {}", &id[..8]))
+}
+
+fn loaded_path() -> Result<PathBuf> {
     let app_id_path = APP_ID_PATH.read().map_err(|_| {
         ArklibError::Other(anyhow!("Could not lock app id path"))
     })?;
 
-    if let Some(app_id_path) = &*app_id_path {
-        Ok(fs::read_to_string(app_id_path)?)
-    } else {
-        Err(ArklibError::Other(anyhow!("Device id path is not set")))
-    }
+    app_id_path
+        .clone()
+        .ok_or_else(|| ArklibError::Other(anyhow!("Device id path is not set")))
+}
+
+/// Return the identity loaded with [`load_identity`]
+pub fn identity() -> Result<DeviceIdentity> {
+    DeviceIdentity::read_from(loaded_path()?)
+}
+
+fn set_loaded_path(path: PathBuf) -> Result<()> {
+    let mut app_id = APP_ID_PATH.write().map_err(|_| {
+        ArklibError::Other(anyhow!("Could not lock app id path"))
+    })?;
+    *app_id = Some(path);
+    Ok(())
+}
+
+/// Return the id loaded with [`load_identity`] or [`load`]
+pub fn read() -> Result<String> {
+    let content = fs::read_to_string(loaded_path()?)?;
+    // Ids loaded with `load` are stored bare
+    Ok(serde_json::from_str::<DeviceIdentity>(&content)
+        .map_or(content, |identity| identity.id))
 }
 
+/// Load the identity stored under `root`, generating it on first use, and
+/// use it for the rest of the process
+pub fn load_identity<P: AsRef<Path>>(root_path: P) -> Result<DeviceIdentity> {
+    let identity = DeviceIdentity::load_or_create(&root_path)?;
+    set_loaded_path(DeviceIdentity::path(root_path))?;
+    Ok(identity)
+}
+
+/// Load the id stored in `<root>/app_id`, generating it on first use, and
+/// use it for the rest of the process
+///
+/// The id has no name and can't be rotated or renamed, use
+/// [`load_identity`] for that.
 pub fn load<P: AsRef<Path>>(root_path: P) -> Result<String> {
     let app_id_path = root_path.as_ref().join(APP_ID_FILE);
 
     let id = if app_id_path.exists() {
         fs::read_to_string(&app_id_path)?
     } else {
-        generate(&app_id_path)?
+        let id = uuid::Uuid::new_v4().to_string();
+        fs::write(&app_id_path, &id)?;
+        id
     };
 
-    let mut app_id = APP_ID_PATH.write().map_err(|_| {
-        ArklibError::Other(anyhow!("Could not lock app id path"))
-    })?;
-    *app_id = Some(app_id_path);
+    set_loaded_path(app_id_path)?;
     Ok(id)
 }
 
+/// Replace the id of the loaded identity with a new one and store it
+///
+/// Files written with the previous id are seen as written by another device
+/// from then on.
+pub fn rotate() -> Result<DeviceIdentity> {
+    let path = loaded_path()?;
+    let mut identity = DeviceIdentity::read_from(&path)?;
+    identity.rotate();
+    identity.write_to(&path)?;
+    Ok(identity)
+}
+
+/// Rename the loaded identity and store it
+pub fn rename(name: &str) -> Result<DeviceIdentity> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(ArklibError::Other(anyhow!("Device name is empty")));
+    }
+    let path = loaded_path()?;
+    let mut identity = DeviceIdentity::read_from(&path)?;
+    identity.name = name.to_owned();
+    identity.write_to(&path)?;
+    Ok(identity)
+}
+
 pub fn remove() -> Result<()> {
     let app_id_path = APP_ID_PATH.read().map_err(|_| {
         ArklibError::Other(anyhow!("Could not lock app id path"))
@@ -51,3 +201,37 @@ pub fn remove() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn load_or_create_identity() {
+        let dir = TempDir::new("device_identity").unwrap();
+        let root = dir.path();
+
+        let identity = DeviceIdentity::load_or_create(root).unwrap();
+        assert!(DeviceIdentity::path(root).exists());
+        assert!(!identity.name.is_empty());
+        assert_eq!(DeviceIdentity::load_or_create(root).unwrap(), identity);
+
+        let mut rotated = identity.clone();
+        rotated.rotate();
+        assert_ne!(rotated.id, identity.id);
+        assert_eq!(rotated.name, identity.name);
+    }
+
+    #[test]
+    fn keep_legacy_app_id() {
+        let dir = TempDir::new("legacy_app_id").unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join(ARK_FOLDER)).unwrap();
+        fs::write(root.join(ARK_FOLDER).join(APP_ID_FILE), "legacy-id\n")
+            .unwrap();
+
+        let identity = DeviceIdentity::load_or_create(root).unwrap();
+        assert_eq!(identity.id, "legacy-id");
+    }
+}
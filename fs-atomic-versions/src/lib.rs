@@ -9,7 +9,13 @@ pub mod atomic;
 
 pub static INIT: Once = Once::new();
 
+/// File of the bare device id, see `app_id::load`
 pub const APP_ID_FILE: &str = "app_id";
+/// File of the device identity, in the `.ark` folder
+pub const DEVICE_FILE: &str = "device";
+/// Same as `fs_storage::ARK_FOLDER`, which can't be used here as
+/// `fs-storage` depends on this crate
+const ARK_FOLDER: &str = ".ark";
 
 lazy_static! {
     pub static ref APP_ID_PATH: RwLock<Option<PathBuf>> = RwLock::new(None);
@@ -1,18 +1,18 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeSet, HashMap, HashSet},
     fs,
     hash::Hash,
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
     time::{Duration, SystemTime},
 };
 
 use serde::{Deserialize, Serialize};
 
-use data_error::Result;
+use data_error::{ArklibError, Result};
 use data_resource::ResourceId;
 use fs_storage::{ARK_FOLDER, INDEX_PATH};
 
-use crate::utils::{discover_paths, scan_entries};
+use crate::utils::{discover_paths, scan_entries, PathRules};
 
 /// The threshold for considering a resource updated
 pub const RESOURCE_UPDATED_THRESHOLD: Duration = Duration::from_millis(1);
@@ -92,6 +92,10 @@ type IndexedPaths = HashSet<Timestamped<PathBuf>>;
 ///   ID.
 /// - [`ResourceIndex::get_resource_by_path`]: Query a resource from the index
 ///   by its path.
+/// - [`ResourceIndex::resources_modified_between`]: Query resources modified
+///   in a time range.
+/// - [`ResourceIndex::resources_larger_than`]: Query resources larger than a
+///   size.
 ///
 /// #### Selective API
 /// - [`ResourceIndex::update_one`]: An experimental method to manually update a
@@ -99,6 +103,14 @@ type IndexedPaths = HashSet<Timestamped<PathBuf>>;
 ///   control but is less dynamic than the reactive `update_all()`. The reactive
 ///   API is typically preferred for broader updates.
 ///
+/// #### Rules API
+/// - [`ResourceIndex::exclude`]: Stop tracking a path, e.g. a scratch folder.
+/// - [`ResourceIndex::include`]: Track a path which would be ignored
+///   otherwise, e.g. a hidden file.
+/// - [`ResourceIndex::reset_rules`]: Remove the rule of a path.
+///
+/// The rules are stored with the index.
+///
 ///
 /// ## Examples
 /// ```no_run
@@ -139,6 +151,14 @@ where
     pub(crate) id_to_paths: HashMap<Id, HashSet<PathBuf>>,
     /// A map from resource paths to resources
     pub(crate) path_to_id: HashMap<PathBuf, Timestamped<Id>>,
+    /// A map from resource paths to their sizes in bytes
+    pub(crate) path_to_size: HashMap<PathBuf, u64>,
+    /// Resource paths ordered by their last modified time
+    pub(crate) modified_to_paths: BTreeSet<(SystemTime, PathBuf)>,
+    /// Resource paths ordered by their size
+    pub(crate) size_to_paths: BTreeSet<(u64, PathBuf)>,
+    /// Paths excluded from or included in the index by the user
+    pub(crate) rules: PathRules,
 }
 
 /// Represents the result of an update operation on the ResourceIndex
@@ -222,6 +242,51 @@ impl<Id: ResourceId> ResourceIndex<Id> {
             .sum()
     }
 
+    /// Return the size in bytes of a resource, as it was when the resource
+    /// was indexed
+    ///
+    /// **Note**: The path should be relative to the root path
+    pub fn get_size_by_path<P: AsRef<Path>>(&self, path: P) -> Option<u64> {
+        self.path_to_size.get(path.as_ref()).copied()
+    }
+
+    /// Return the resources modified between `from` (inclusive) and `to`
+    /// (exclusive), ordered by their last modified time
+    ///
+    /// Resources are looked up in an ordered set, without scanning the whole
+    /// index.
+    pub fn resources_modified_between(
+        &self,
+        from: SystemTime,
+        to: SystemTime,
+    ) -> Vec<IndexedResource<Id>> {
+        if from >= to {
+            return vec![];
+        }
+        // An empty path is lower than any other path
+        self.modified_to_paths
+            .range((from, PathBuf::new())..(to, PathBuf::new()))
+            .filter_map(|(_, path)| self.get_resource_by_path(path))
+            .collect()
+    }
+
+    /// Return the resources larger than `bytes`, ordered by their size
+    ///
+    /// Resources are looked up in an ordered set, without scanning the whole
+    /// index.
+    pub fn resources_larger_than(
+        &self,
+        bytes: u64,
+    ) -> Vec<IndexedResource<Id>> {
+        let Some(min) = bytes.checked_add(1) else {
+            return vec![];
+        };
+        self.size_to_paths
+            .range((min, PathBuf::new())..)
+            .filter_map(|(_, path)| self.get_resource_by_path(path))
+            .collect()
+    }
+
     /// Save the index to the file system (as a JSON file in
     /// <root_path>/ARK_FOLDER/INDEX_PATH)
     pub fn store(&self) -> Result<()> {
@@ -290,9 +355,10 @@ impl<Id: ResourceId> ResourceIndex<Id> {
 
         let mut id_to_paths: HashMap<Id, HashSet<PathBuf>> = HashMap::new();
         let mut path_to_resource = HashMap::new();
+        let rules = PathRules::default();
 
         // Discover paths in the root directory
-        let paths = discover_paths(&root_path)?;
+        let paths = discover_paths(&root_path, Path::new(""), &rules)?;
         let entries: HashMap<PathBuf, Timestamped<Id>> =
             scan_entries(&root_path, paths);
 
@@ -313,11 +379,19 @@ impl<Id: ResourceId> ResourceIndex<Id> {
         // Update the path to resource map
         path_to_resource.extend(entries.clone());
 
-        let index = ResourceIndex {
+        let mut index = ResourceIndex {
             root: root_path.to_path_buf(),
             id_to_paths,
             path_to_id: path_to_resource,
+            path_to_size: HashMap::new(),
+            modified_to_paths: BTreeSet::new(),
+            size_to_paths: BTreeSet::new(),
+            rules,
         };
+        for (path, id) in entries {
+            let size = index.file_size(&path);
+            index.track(path, id.last_modified, size);
+        }
         Ok(index)
     }
 
@@ -329,7 +403,8 @@ impl<Id: ResourceId> ResourceIndex<Id> {
         let mut added: HashMap<Id, IndexedPaths> = HashMap::new();
         let mut removed: HashSet<Id> = HashSet::new();
 
-        let current_paths = discover_paths(&self.root)?;
+        let current_paths =
+            discover_paths(&self.root, Path::new(""), &self.rules)?;
 
         // Assuming that collection manipulation
         // is faster than repeated lookups
@@ -443,6 +518,7 @@ impl<Id: ResourceId> ResourceIndex<Id> {
                 id.last_modified
             );
 
+            self.untrack(&path);
             self.path_to_id.remove(&path);
             self.id_to_paths
                 .get_mut(&id.item)
@@ -464,9 +540,12 @@ impl<Id: ResourceId> ResourceIndex<Id> {
 
         for (path, id) in added_entries {
             log::trace!("Resource added: {:?}", path);
+            self.untrack(&path);
             self.path_to_id.insert(path.clone(), id.clone());
 
             let last_modified = id.last_modified;
+            let size = self.file_size(&path);
+            self.track(path.clone(), last_modified, size);
             let id = id.item.clone();
             self.id_to_paths
                 .entry(id.clone())
@@ -512,9 +591,16 @@ impl<Id: ResourceId> ResourceIndex<Id> {
             removed: HashSet::new(),
         };
 
+        let is_excluded = self.rules.pin(path) == Some(false);
+        if is_excluded && !self.path_to_id.contains_key(path) {
+            log::trace!("Ignoring excluded resource: {:?}", path);
+            return Ok(result);
+        }
+
         // Check if the entry exists in the file system
-        if !entry_path.exists() {
-            // If the entry does not exist in the file system, it's a removal
+        if !entry_path.exists() || is_excluded {
+            // If the entry does not exist in the file system or was
+            // excluded, it's a removal
 
             // Remove the resource from the path to ID map
             debug_assert!(
@@ -522,6 +608,7 @@ impl<Id: ResourceId> ResourceIndex<Id> {
                 "Caller must ensure that the resource exists in the index: {:?}",
                 path
             );
+            self.untrack(path);
             let id = self.path_to_id.remove(path).ok_or_else(
                 || anyhow::anyhow!("Resource with path {} is neither in the index nor in the file system. Make sure the index is up-to-date.", path.display())
             )?;
@@ -558,8 +645,10 @@ impl<Id: ResourceId> ResourceIndex<Id> {
             }
 
             // Update the path to resource map
+            self.untrack(path);
             self.path_to_id
                 .insert(path.to_path_buf(), resource_path);
+            self.track(path.to_path_buf(), last_modified, metadata.len());
             // Update the ID to paths map
             self.id_to_paths
                 .entry(id.clone())
@@ -578,4 +667,205 @@ impl<Id: ResourceId> ResourceIndex<Id> {
 
         Ok(result)
     }
+
+    /// Update the index with the latest information from the file system
+    /// for the resources under a directory
+    ///
+    /// This method accepts the relative path of a directory and detects the
+    /// resources added, removed, or modified under it, without walking the
+    /// rest of the root. Only new and modified files are hashed.
+    ///
+    /// It is meant for callers that know where the file system changed, e.g.
+    /// after writing files into a folder of the root. The path of a single
+    /// file is accepted too.
+    pub fn update_subtree<P: AsRef<Path>>(
+        &mut self,
+        relative_dir: P,
+    ) -> Result<IndexUpdate<Id>> {
+        let dir = relative_dir.as_ref();
+        log::debug!("Updating index subtree: {:?}", dir);
+
+        let mut result = IndexUpdate {
+            added: HashMap::new(),
+            removed: HashSet::new(),
+        };
+
+        let mut present = HashSet::new();
+        let mut changed = Vec::new();
+        for entry in discover_paths(&self.root, dir, &self.rules)? {
+            let path = entry
+                .path()
+                .strip_prefix(&self.root)
+                .expect("Failed to strip prefix")
+                .to_path_buf();
+            let last_modified = fs::metadata(entry.path())?.modified()?;
+            let is_changed = match self.path_to_id.get(&path) {
+                None => true,
+                Some(prev) => last_modified
+                    .duration_since(prev.last_modified)
+                    .is_ok_and(|elapsed| elapsed >= RESOURCE_UPDATED_THRESHOLD),
+            };
+            if is_changed {
+                changed.push(path.clone());
+            }
+            present.insert(path);
+        }
+
+        let removed: Vec<PathBuf> = self
+            .path_to_id
+            .keys()
+            .filter(|path| path.starts_with(dir) && !present.contains(*path))
+            .cloned()
+            .collect();
+
+        for path in removed.iter().chain(&changed) {
+            let update = self.update_one(path)?;
+            result.removed.extend(update.removed);
+            for (id, paths) in update.added {
+                result.added.entry(id).or_default().extend(paths);
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Return the paths excluded from the index, relative to the root path
+    pub fn excluded(&self) -> &BTreeSet<PathBuf> {
+        &self.rules.excluded
+    }
+
+    /// Return the paths included in the index even if they would be ignored
+    /// otherwise, relative to the root path
+    pub fn included(&self) -> &BTreeSet<PathBuf> {
+        &self.rules.included
+    }
+
+    /// Exclude a path from the index, with everything under it
+    ///
+    /// The resources already indexed under the path are removed from the
+    /// index, and the path is skipped by later updates. Paths included under
+    /// it with [`ResourceIndex::include`] are still indexed.
+    ///
+    /// The path can be relative to the root path or absolute. Call
+    /// [`ResourceIndex::store`] to persist the rule.
+    pub fn exclude<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+    ) -> Result<IndexUpdate<Id>> {
+        let path = self.rule_path(path.as_ref())?;
+        log::debug!("Excluding path from the index: {:?}", path);
+
+        self.rules.included.remove(&path);
+        self.rules.excluded.insert(path.clone());
+        self.update_subtree(path)
+    }
+
+    /// Include a path in the index, with everything under it, even if it
+    /// would be ignored otherwise, e.g. because it is hidden or under an
+    /// excluded folder
+    ///
+    /// The resources under the path are indexed right away. Empty files are
+    /// still ignored, and so is the `.ark` folder, which can't be included.
+    ///
+    /// The path can be relative to the root path or absolute. Call
+    /// [`ResourceIndex::store`] to persist the rule.
+    pub fn include<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+    ) -> Result<IndexUpdate<Id>> {
+        let path = self.rule_path(path.as_ref())?;
+        if path.starts_with(ARK_FOLDER) {
+            return Err(ArklibError::Path(format!(
+                "Can't include {} in the index",
+                path.display()
+            )));
+        }
+        log::debug!("Including path in the index: {:?}", path);
+
+        self.rules.excluded.remove(&path);
+        self.rules.included.insert(path.clone());
+        self.update_subtree(path)
+    }
+
+    /// Remove the rule set for a path with [`ResourceIndex::exclude`] or
+    /// [`ResourceIndex::include`], so it is indexed as any other path again
+    ///
+    /// Return an empty update if there was no rule for the path.
+    pub fn reset_rules<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+    ) -> Result<IndexUpdate<Id>> {
+        let path = self.rule_path(path.as_ref())?;
+        let was_excluded = self.rules.excluded.remove(&path);
+        let was_included = self.rules.included.remove(&path);
+        if !was_excluded && !was_included {
+            return Ok(IndexUpdate {
+                added: HashMap::new(),
+                removed: HashSet::new(),
+            });
+        }
+        log::debug!("Resetting the rule of path: {:?}", path);
+
+        self.update_subtree(path)
+    }
+
+    /// Normalize a path given to a rule: make it relative to the root path
+    /// and reject the root path itself and paths out of it
+    fn rule_path(&self, path: &Path) -> Result<PathBuf> {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        let mut normalized = PathBuf::new();
+        for component in relative.components() {
+            match component {
+                Component::Normal(name) => normalized.push(name),
+                Component::CurDir => {}
+                _ => {
+                    return Err(ArklibError::Path(format!(
+                        "Path {} is not under the root of the index",
+                        path.display()
+                    )))
+                }
+            }
+        }
+        if normalized.as_os_str().is_empty() {
+            return Err(ArklibError::Path(
+                "Rules can't apply to the root of the index".to_owned(),
+            ));
+        }
+        Ok(normalized)
+    }
+
+    /// Record a resource in the ordered sets used by time and size queries
+    pub(crate) fn track(
+        &mut self,
+        path: PathBuf,
+        last_modified: SystemTime,
+        size: u64,
+    ) {
+        self.modified_to_paths
+            .insert((last_modified, path.clone()));
+        self.size_to_paths.insert((size, path.clone()));
+        self.path_to_size.insert(path, size);
+    }
+
+    /// Remove a resource from the ordered sets used by time and size queries
+    ///
+    /// Must be called before the resource is removed from or replaced in
+    /// `path_to_id`, so that its previous last modified time is known.
+    fn untrack(&mut self, path: &Path) {
+        if let Some(size) = self.path_to_size.remove(path) {
+            self.size_to_paths
+                .remove(&(size, path.to_path_buf()));
+        }
+        if let Some(id) = self.path_to_id.get(path) {
+            self.modified_to_paths
+                .remove(&(id.last_modified, path.to_path_buf()));
+        }
+    }
+
+    /// Size of a file of the root, 0 if it can't be read anymore
+    fn file_size(&self, path: &Path) -> u64 {
+        fs::metadata(self.root.join(path))
+            .map(|metadata| metadata.len())
+            .unwrap_or(0)
+    }
 }
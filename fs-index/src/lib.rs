@@ -1,5 +1,7 @@
 mod index;
 mod serde;
+#[cfg(feature = "sidecar")]
+pub mod sidecar;
 mod utils;
 #[cfg(feature = "watch")]
 mod watch;
@@ -1,5 +1,6 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeSet, HashMap, HashSet},
+    fs,
     path::PathBuf,
     time::SystemTime,
 };
@@ -12,25 +13,35 @@ use serde::{
 
 use data_resource::ResourceId;
 
-use crate::{index::Timestamped, ResourceIndex};
+use crate::{index::Timestamped, utils::PathRules, ResourceIndex};
 
 /// Data structure for serializing and deserializing the index
 #[derive(Serialize, Deserialize)]
 struct ResourceIndexData<Id> {
     root: PathBuf,
     resources: HashMap<PathBuf, IndexedResourceData<Id>>,
+    /// Missing in indexes without rules or stored by older versions
+    #[serde(default)]
+    excluded: BTreeSet<PathBuf>,
+    #[serde(default)]
+    included: BTreeSet<PathBuf>,
 }
 
 #[derive(Serialize, Deserialize)]
 struct IndexedResourceData<Id> {
     id: Id,
     last_modified: u64,
+    /// Missing in indexes stored by older versions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
 }
 
 /// Custom implementation of [`Serialize`] for [`ResourceIndex`]
 ///
 /// To avoid writing a large repetitive index file with double maps,
-/// we are only serializing the root path, and path_to_resource.
+/// we are only serializing the root path, and path_to_resource with the
+/// sizes of the resources. The rules of the index are serialized only if
+/// there are any.
 ///
 /// Other fields can be reconstructed from the path_to_resource map.
 impl<Id> Serialize for ResourceIndex<Id>
@@ -41,7 +52,11 @@ where
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("ResourceIndex", 2)?;
+        let rules = &self.rules;
+        let len = 2
+            + usize::from(!rules.excluded.is_empty())
+            + usize::from(!rules.included.is_empty());
+        let mut state = serializer.serialize_struct("ResourceIndex", len)?;
         state.serialize_field("root", &self.root)?;
 
         let mut resources = HashMap::new();
@@ -60,11 +75,18 @@ where
             let resource_data = IndexedResourceData {
                 id: id.item.clone(),
                 last_modified,
+                size: self.path_to_size.get(path).copied(),
             };
             resources.insert(path.clone(), resource_data);
         }
 
         state.serialize_field("resources", &resources)?;
+        if !rules.excluded.is_empty() {
+            state.serialize_field("excluded", &rules.excluded)?;
+        }
+        if !rules.included.is_empty() {
+            state.serialize_field("included", &rules.included)?;
+        }
         state.end()
     }
 }
@@ -84,8 +106,18 @@ where
         let index_data: ResourceIndexData<Id> =
             ResourceIndexData::deserialize(deserializer)?;
 
-        let mut path_to_resource = HashMap::new();
-        let mut id_to_paths = HashMap::new();
+        let mut index = ResourceIndex {
+            root: index_data.root,
+            id_to_paths: HashMap::new(),
+            path_to_id: HashMap::new(),
+            path_to_size: HashMap::new(),
+            modified_to_paths: BTreeSet::new(),
+            size_to_paths: BTreeSet::new(),
+            rules: PathRules {
+                excluded: index_data.excluded,
+                included: index_data.included,
+            },
+        };
         for (path, resource_data) in index_data.resources {
             let last_modified = SystemTime::UNIX_EPOCH
                 + std::time::Duration::from_nanos(resource_data.last_modified);
@@ -94,26 +126,31 @@ where
                 item: resource_data.id,
                 last_modified,
             };
-            path_to_resource.insert(path.clone(), id.clone());
-            id_to_paths
+            // Sizes of resources indexed by older versions are read once,
+            // they are stored with the index from then on
+            let size = resource_data.size.unwrap_or_else(|| {
+                fs::metadata(index.root.join(&path))
+                    .map(|metadata| metadata.len())
+                    .unwrap_or(0)
+            });
+            index.track(path.clone(), last_modified, size);
+            index.path_to_id.insert(path.clone(), id.clone());
+            index
+                .id_to_paths
                 .entry(id.item.clone())
                 .or_insert_with(HashSet::new)
                 .insert(path);
         }
 
-        Ok(ResourceIndex {
-            root: index_data.root,
-            id_to_paths,
-            path_to_id: path_to_resource,
-        })
+        Ok(index)
     }
 }
 
 /// Custom implementation of [`PartialEq`] for [`ResourceIndex`]
 ///
 /// The order of items in hashmaps is not relevant.
-/// we just need to compare [`ResourceIndex::resources`] to check if the two
-/// indexes are equal.
+/// we just need to compare [`ResourceIndex::resources`] and the rules to
+/// check if the two indexes are equal.
 impl<Id> PartialEq for ResourceIndex<Id>
 where
     Id: ResourceId,
@@ -124,6 +161,8 @@ where
         resources1.sort_by(|a, b| a.path().cmp(b.path()));
         resources2.sort_by(|a, b| a.path().cmp(b.path()));
 
-        resources1 == resources2 && self.root == other.root
+        resources1 == resources2
+            && self.root == other.root
+            && self.rules == other.rules
     }
 }
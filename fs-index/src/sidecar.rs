@@ -0,0 +1,193 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use data_error::Result;
+use data_resource::ResourceId;
+use fs_metadata::store_external_metadata;
+
+use crate::{IndexUpdate, ResourceIndex};
+
+/// Extensions of sidecar files which are read along with a resource
+pub const SIDECAR_EXTENSIONS: [&str; 2] = ["xmp", "json"];
+
+/// Namespaces of extended attributes managed by the system,
+/// which are not authored by users or apps
+#[cfg(unix)]
+const SYSTEM_XATTR_NAMESPACES: [&str; 3] = ["security.", "system.", "trusted."];
+
+/// Metadata of a resource authored outside of ark
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExternalMetadata {
+    /// Extended attributes of the file with UTF-8 values, by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub xattrs: BTreeMap<String, String>,
+    /// Contents of sidecar files, by extension. JSON sidecars are stored as
+    /// they are, others as strings.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub sidecars: BTreeMap<String, serde_json::Value>,
+}
+
+impl ExternalMetadata {
+    pub fn is_empty(&self) -> bool {
+        self.xattrs.is_empty() && self.sidecars.is_empty()
+    }
+}
+
+/// Read extended attributes and sidecar files of the file at `path`.
+///
+/// Sidecars are looked up both with the extension appended to the file name
+/// (`photo.jpg.xmp`) and in place of the extension (`photo.xmp`), the former
+/// taking precedence. Returns `None` if nothing was found.
+pub fn read_external_metadata<P: AsRef<Path>>(
+    path: P,
+) -> Result<Option<ExternalMetadata>> {
+    let path = path.as_ref();
+    // Not all file systems support extended attributes
+    let xattrs = read_xattrs(path).unwrap_or_else(|e| {
+        log::debug!("Couldn't read xattrs of {}: {}", path.display(), e);
+        BTreeMap::new()
+    });
+    let mut metadata = ExternalMetadata {
+        xattrs,
+        sidecars: BTreeMap::new(),
+    };
+
+    for extension in SIDECAR_EXTENSIONS {
+        // Sidecars are resources too, they don't have sidecars of their own
+        if has_extension(path, extension) {
+            continue;
+        }
+        let Some(sidecar) = sidecar_paths(path, extension)
+            .into_iter()
+            .find(|sidecar| sidecar.is_file())
+        else {
+            continue;
+        };
+        let content = fs::read_to_string(&sidecar)?;
+        let value = if extension == "json" {
+            match serde_json::from_str(&content) {
+                Ok(value) => value,
+                Err(e) => {
+                    log::warn!(
+                        "Ignoring invalid sidecar {}: {}",
+                        sidecar.display(),
+                        e
+                    );
+                    continue;
+                }
+            }
+        } else {
+            serde_json::Value::String(content)
+        };
+        metadata
+            .sidecars
+            .insert(extension.to_owned(), value);
+    }
+
+    Ok((!metadata.is_empty()).then_some(metadata))
+}
+
+/// Read external metadata of all resources of the index and store it
+/// with [`fs_metadata::store_external_metadata`].
+///
+/// Returns the number of resources which have external metadata. Resources
+/// whose metadata can't be read are logged and skipped.
+pub fn ingest_external_metadata<Id: ResourceId>(
+    index: &ResourceIndex<Id>,
+) -> Result<usize> {
+    let resources = index
+        .resources()
+        .into_iter()
+        .map(|resource| (resource.id().clone(), resource.path().to_path_buf()));
+    ingest(index.root(), resources)
+}
+
+/// Like [`ingest_external_metadata`], but only for the resources added by
+/// an update of the index
+pub fn ingest_update<Id: ResourceId>(
+    index: &ResourceIndex<Id>,
+    update: &IndexUpdate<Id>,
+) -> Result<usize> {
+    let resources = update.added().iter().flat_map(|(id, paths)| {
+        paths
+            .iter()
+            .map(move |path| (id.clone(), path.item().clone()))
+    });
+    ingest(index.root(), resources)
+}
+
+fn ingest<Id: ResourceId>(
+    root: &Path,
+    resources: impl Iterator<Item = (Id, PathBuf)>,
+) -> Result<usize> {
+    let mut count = 0;
+    for (id, path) in resources {
+        let metadata = match read_external_metadata(root.join(&path)) {
+            Ok(Some(metadata)) => metadata,
+            Ok(None) => continue,
+            Err(e) => {
+                log::warn!(
+                    "Couldn't read external metadata of {}: {}",
+                    path.display(),
+                    e
+                );
+                continue;
+            }
+        };
+        store_external_metadata(root, id, &metadata)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+}
+
+fn sidecar_paths(path: &Path, extension: &str) -> Vec<PathBuf> {
+    let mut appended = path.as_os_str().to_owned();
+    appended.push(".");
+    appended.push(extension);
+    let mut paths = vec![PathBuf::from(appended)];
+    if path.extension().is_some() {
+        paths.push(path.with_extension(extension));
+    }
+    paths
+}
+
+#[cfg(unix)]
+fn read_xattrs(path: &Path) -> Result<BTreeMap<String, String>> {
+    let mut xattrs = BTreeMap::new();
+    if !xattr::SUPPORTED_PLATFORM {
+        return Ok(xattrs);
+    }
+    for name in xattr::list(path)? {
+        let Some(name) = name.to_str() else {
+            continue;
+        };
+        if SYSTEM_XATTR_NAMESPACES
+            .iter()
+            .any(|namespace| name.starts_with(namespace))
+        {
+            continue;
+        }
+        // Binary values, e.g. property lists on macOS, are skipped
+        if let Some(value) = xattr::get(path, name)?
+            .and_then(|value| String::from_utf8(value).ok())
+        {
+            xattrs.insert(name.to_owned(), value);
+        }
+    }
+    Ok(xattrs)
+}
+
+#[cfg(not(unix))]
+fn read_xattrs(_path: &Path) -> Result<BTreeMap<String, String>> {
+    Ok(BTreeMap::new())
+}
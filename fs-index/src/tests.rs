@@ -28,7 +28,11 @@
 //!    desired hash type.
 
 use dev_hash::{Blake3, Crc32};
-use std::{fs, path::Path};
+use std::{
+    fs,
+    path::Path,
+    time::{Duration, SystemTime},
+};
 
 use anyhow::{anyhow, Result};
 use tempfile::TempDir;
@@ -956,3 +960,311 @@ fn test_track_move_to_subdirectory() {
         assert_eq!(*resource_by_path.id(), file_id);
     });
 }
+
+/// Test updating the resources under a directory only.
+///
+/// ## Test scenario:
+/// - Create a file at the root and a file in a subdirectory.
+/// - Build a resource index in the temporary directory.
+/// - Add and remove files in the subdirectory, and add a file at the root.
+/// - Update the subdirectory.
+/// - Assert that the changes in the subdirectory are indexed, and the new file
+///   at the root is not.
+#[test]
+fn test_update_subtree() {
+    for_each_type!(Crc32, Blake3 => {
+        let temp_dir = TempDir::with_prefix("ark_test_update_subtree")
+            .expect("Failed to create temp dir");
+        let root_path = temp_dir.path();
+
+        let subdirectory_path = root_path.join("subdirectory");
+        fs::create_dir(&subdirectory_path).expect("Failed to create subdirectory");
+        fs::write(root_path.join("file.txt"), "file content")
+            .expect("Failed to write to file");
+        let removed_path = subdirectory_path.join("removed.txt");
+        fs::write(&removed_path, "removed content")
+            .expect("Failed to write to file");
+
+        let mut index: ResourceIndex<Id> =
+            ResourceIndex::build(root_path).expect("Failed to build index");
+
+        fs::remove_file(&removed_path).expect("Failed to remove file");
+        fs::write(subdirectory_path.join("added.txt"), "added content")
+            .expect("Failed to write to file");
+        fs::write(root_path.join("outside.txt"), "outside content")
+            .expect("Failed to write to file");
+
+        let update_result = index
+            .update_subtree("subdirectory")
+            .expect("Failed to update index");
+        assert_eq!(update_result.added().len(), 1, "{:?}", update_result);
+        assert_eq!(update_result.removed().len(), 1, "{:?}", update_result);
+
+        assert_eq!(index.len(), 2, "{:?}", index);
+        assert!(index.get_resource_by_path("subdirectory/added.txt").is_some());
+        assert!(index.get_resource_by_path("subdirectory/removed.txt").is_none());
+        assert!(index.get_resource_by_path("outside.txt").is_none());
+    });
+}
+
+/// Test excluding and including paths in the index.
+///
+/// ## Test scenario:
+/// - Create files in the root, in a scratch folder and a hidden file.
+/// - Build a resource index in the temporary directory.
+/// - Exclude the scratch folder and assert that its files are removed.
+/// - Include a file of the scratch folder and the hidden file, and assert
+///   that they are added.
+/// - Write a new file in the scratch folder and update the index.
+/// - Assert that the new file is not indexed.
+/// - Store and load the index, and assert that the rules were kept.
+/// - Reset the rule of the scratch folder and assert that its files are
+///   indexed again.
+#[test]
+fn test_exclude_and_include() {
+    for_each_type!(Crc32, Blake3 => {
+        let temp_dir = TempDir::with_prefix("ark_test_exclude_and_include")
+            .expect("Failed to create temp dir");
+        let root_path = temp_dir.path();
+
+        let scratch_path = root_path.join("scratch");
+        fs::create_dir(&scratch_path).expect("Failed to create scratch");
+        fs::write(root_path.join("file.txt"), "file content")
+            .expect("Failed to write to file");
+        fs::write(root_path.join(".hidden.txt"), "hidden content")
+            .expect("Failed to write to file");
+        fs::write(scratch_path.join("draft.txt"), "draft content")
+            .expect("Failed to write to file");
+        fs::write(scratch_path.join("keep.txt"), "keep content")
+            .expect("Failed to write to file");
+
+        let mut index: ResourceIndex<Id> =
+            ResourceIndex::build(root_path).expect("Failed to build index");
+        assert_eq!(index.len(), 3, "{:?}", index);
+
+        let update_result =
+            index.exclude("scratch").expect("Failed to exclude path");
+        assert_eq!(update_result.removed().len(), 2, "{:?}", update_result);
+        assert_eq!(index.len(), 1, "{:?}", index);
+
+        let update_result = index
+            .include("scratch/keep.txt")
+            .expect("Failed to include path");
+        assert_eq!(update_result.added().len(), 1, "{:?}", update_result);
+        let update_result =
+            index.include(".hidden.txt").expect("Failed to include path");
+        assert_eq!(update_result.added().len(), 1, "{:?}", update_result);
+        assert_eq!(index.len(), 3, "{:?}", index);
+        assert!(index.get_resource_by_path("scratch/keep.txt").is_some());
+        assert!(index.get_resource_by_path(".hidden.txt").is_some());
+
+        fs::write(scratch_path.join("new.txt"), "new content")
+            .expect("Failed to write to file");
+        let update_result = index.update_all().expect("Failed to update index");
+        assert!(update_result.added().is_empty(), "{:?}", update_result);
+        assert!(update_result.removed().is_empty(), "{:?}", update_result);
+
+        index.store().expect("Failed to store index");
+        let loaded_index: ResourceIndex<Id> =
+            load_or_build_index(root_path, false).expect("Failed to load index");
+        assert_eq!(index, loaded_index, "{:?} != {:?}", index, loaded_index);
+        assert!(loaded_index.excluded().contains(Path::new("scratch")));
+        assert_eq!(loaded_index.included().len(), 2, "{:?}", loaded_index);
+
+        let update_result =
+            index.reset_rules("scratch").expect("Failed to reset rules");
+        assert_eq!(update_result.added().len(), 2, "{:?}", update_result);
+        assert!(index.excluded().is_empty(), "{:?}", index);
+        assert_eq!(index.len(), 5, "{:?}", index);
+    });
+}
+
+/// Test that rules can't apply to the root, paths out of it or the `.ark`
+/// folder.
+#[test]
+fn test_invalid_rules() {
+    for_each_type!(Crc32, Blake3 => {
+        let temp_dir = TempDir::with_prefix("ark_test_invalid_rules")
+            .expect("Failed to create temp dir");
+        let root_path = temp_dir.path();
+
+        let mut index: ResourceIndex<Id> =
+            ResourceIndex::build(root_path).expect("Failed to build index");
+
+        assert!(index.exclude("").is_err());
+        assert!(index.exclude("../outside").is_err());
+        assert!(index.include(".ark/index").is_err());
+        assert!(index.excluded().is_empty() && index.included().is_empty());
+    });
+}
+
+/// Test querying resources by their last modified time.
+///
+/// ## Test scenario:
+/// - Create three files modified at different times.
+/// - Build a resource index in the temporary directory.
+/// - Assert that only the files modified in the queried range are returned,
+///   ordered by time.
+/// - Modify a file and update the index.
+/// - Assert that the file is returned for its new time only.
+#[test]
+fn test_resources_modified_between() {
+    for_each_type!(Crc32, Blake3 => {
+        let temp_dir = TempDir::with_prefix("ark_test_modified_between")
+            .expect("Failed to create temp dir");
+        let root_path = temp_dir.path();
+
+        let epoch = SystemTime::UNIX_EPOCH;
+        let files = [("old.txt", 1000), ("mid.txt", 2000), ("new.txt", 3000)];
+        for (name, secs) in files {
+            let path = root_path.join(name);
+            fs::write(&path, name).expect("Failed to write to file");
+            let modified = epoch + Duration::from_secs(secs);
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .and_then(|file| file.set_modified(modified))
+                .expect("Failed to set modified time");
+        }
+
+        let mut index: ResourceIndex<Id> =
+            ResourceIndex::build(root_path).expect("Failed to build index");
+
+        let resources = index.resources_modified_between(
+            epoch + Duration::from_secs(1500),
+            epoch + Duration::from_secs(3000),
+        );
+        let paths: Vec<&Path> = resources.iter().map(|r| r.path()).collect();
+        assert_eq!(paths, vec![Path::new("mid.txt")]);
+
+        let resources = index.resources_modified_between(
+            epoch,
+            epoch + Duration::from_secs(3001),
+        );
+        let paths: Vec<&Path> = resources.iter().map(|r| r.path()).collect();
+        assert_eq!(
+            paths,
+            vec![Path::new("old.txt"), Path::new("mid.txt"), Path::new("new.txt")]
+        );
+
+        fs::write(root_path.join("old.txt"), "modified content")
+            .expect("Failed to write to file");
+        index.update_all().expect("Failed to update index");
+
+        let resources = index.resources_modified_between(
+            epoch,
+            epoch + Duration::from_secs(1500),
+        );
+        assert!(resources.is_empty(), "{:?}", resources);
+        let resources = index.resources_modified_between(
+            epoch + Duration::from_secs(3001),
+            SystemTime::now() + Duration::from_secs(60),
+        );
+        assert_eq!(resources.len(), 1, "{:?}", resources);
+        assert_eq!(resources[0].path(), Path::new("old.txt"));
+    });
+}
+
+/// Test querying resources by their size.
+///
+/// ## Test scenario:
+/// - Create files of different sizes.
+/// - Build a resource index in the temporary directory.
+/// - Assert that only the files larger than the queried size are returned,
+///   ordered by size.
+/// - Remove a file, update the index and assert that it isn't returned.
+/// - Store and load the index, and assert that sizes are preserved.
+#[test]
+fn test_resources_larger_than() {
+    for_each_type!(Crc32, Blake3 => {
+        let temp_dir = TempDir::with_prefix("ark_test_larger_than")
+            .expect("Failed to create temp dir");
+        let root_path = temp_dir.path();
+
+        fs::write(root_path.join("small.txt"), "a").expect("Failed to write to file");
+        fs::write(root_path.join("medium.txt"), "a".repeat(10))
+            .expect("Failed to write to file");
+        fs::write(root_path.join("large.txt"), "a".repeat(100))
+            .expect("Failed to write to file");
+
+        let mut index: ResourceIndex<Id> =
+            ResourceIndex::build(root_path).expect("Failed to build index");
+        assert_eq!(index.get_size_by_path("medium.txt"), Some(10));
+
+        let resources = index.resources_larger_than(1);
+        let paths: Vec<&Path> = resources.iter().map(|r| r.path()).collect();
+        assert_eq!(paths, vec![Path::new("medium.txt"), Path::new("large.txt")]);
+        assert!(index.resources_larger_than(100).is_empty());
+        assert!(index.resources_larger_than(u64::MAX).is_empty());
+
+        fs::remove_file(root_path.join("large.txt")).expect("Failed to remove file");
+        index.update_all().expect("Failed to update index");
+        assert_eq!(index.resources_larger_than(1).len(), 1);
+
+        index.store().expect("Failed to store index");
+        let loaded_index: ResourceIndex<Id> =
+            load_or_build_index(root_path, false).expect("Failed to load index");
+        assert_eq!(loaded_index.get_size_by_path("medium.txt"), Some(10));
+        assert_eq!(loaded_index.resources_larger_than(1).len(), 1);
+    });
+}
+
+/// Test ingesting sidecar files of the resources into external metadata.
+///
+/// ## Test scenario:
+/// - Create a photo with an XMP sidecar next to it, a document with a JSON
+///   sidecar and a file without sidecars.
+/// - Build a resource index in the temporary directory.
+/// - Ingest external metadata of the index.
+/// - Assert that the sidecars are stored for the photo and the document only.
+#[cfg(feature = "sidecar")]
+#[test]
+fn test_ingest_sidecars() {
+    use crate::sidecar::{ingest_external_metadata, ExternalMetadata};
+    use fs_metadata::load_external_metadata;
+
+    fs_atomic_versions::initialize();
+
+    for_each_type!(Crc32, Blake3 => {
+        let temp_dir = TempDir::with_prefix("ark_test_ingest_sidecars")
+            .expect("Failed to create temp dir");
+        let root_path = temp_dir.path();
+
+        fs::write(root_path.join("photo.jpg"), "photo content")
+            .expect("Failed to write to file");
+        fs::write(root_path.join("photo.jpg.xmp"), "<x:xmpmeta/>")
+            .expect("Failed to write to file");
+        fs::write(root_path.join("document.pdf"), "document content")
+            .expect("Failed to write to file");
+        fs::write(root_path.join("document.json"), r#"{"title": "Report"}"#)
+            .expect("Failed to write to file");
+        fs::write(root_path.join("plain.txt"), "plain content")
+            .expect("Failed to write to file");
+
+        let index: ResourceIndex<Id> =
+            ResourceIndex::build(root_path).expect("Failed to build index");
+        let count = ingest_external_metadata(&index)
+            .expect("Failed to ingest metadata");
+        assert_eq!(count, 2);
+
+        let photo = index.get_resource_by_path("photo.jpg").unwrap();
+        let metadata: ExternalMetadata =
+            load_external_metadata(index.root(), photo.id().clone())
+                .expect("Failed to load metadata")
+                .expect("Metadata not stored");
+        assert_eq!(metadata.sidecars["xmp"], "<x:xmpmeta/>");
+
+        let document = index.get_resource_by_path("document.pdf").unwrap();
+        let metadata: ExternalMetadata =
+            load_external_metadata(index.root(), document.id().clone())
+                .expect("Failed to load metadata")
+                .expect("Metadata not stored");
+        assert_eq!(metadata.sidecars["json"]["title"], "Report");
+
+        let plain = index.get_resource_by_path("plain.txt").unwrap();
+        let metadata: Option<ExternalMetadata> =
+            load_external_metadata(index.root(), plain.id().clone())
+                .expect("Failed to load metadata");
+        assert!(metadata.is_none());
+    });
+}
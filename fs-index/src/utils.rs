@@ -1,5 +1,5 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeSet, HashMap},
     fs,
     io::BufReader,
     path::{Path, PathBuf},
@@ -67,22 +67,74 @@ pub fn load_or_build_index<P: AsRef<Path>, Id: ResourceId>(
     }
 }
 
+/// Paths excluded from or included in the index by the user, relative to
+/// the root path of the index
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct PathRules {
+    /// Paths never indexed, with everything under them
+    pub(crate) excluded: BTreeSet<PathBuf>,
+    /// Paths indexed even if they would be ignored otherwise, with
+    /// everything under them
+    pub(crate) included: BTreeSet<PathBuf>,
+}
+
+impl PathRules {
+    /// Return whether the path is included (`Some(true)`) or excluded
+    /// (`Some(false)`) by a rule, `None` if no rule applies to it
+    ///
+    /// The rule of the closest ancestor wins, so a path can be included
+    /// inside an excluded folder and the other way around.
+    pub(crate) fn pin(&self, path: &Path) -> Option<bool> {
+        path.ancestors().find_map(|ancestor| {
+            if self.included.contains(ancestor) {
+                Some(true)
+            } else if self.excluded.contains(ancestor) {
+                Some(false)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Return true if nothing under the directory can be indexed
+    fn prunes(&self, dir: &Path) -> bool {
+        self.pin(dir) == Some(false)
+            && !self
+                .included
+                .iter()
+                .any(|path| path.starts_with(dir))
+    }
+}
+
 /// A helper function to discover paths in a directory
 ///
-/// This function walks the directory tree starting from the root path and
-/// returns a list of file paths.
+/// This function walks the directory tree starting from `root_path` joined
+/// with `dir`, and returns a list of file paths. `dir` can also be the path
+/// of a single file.
 ///
-/// Ignore hidden files and empty files.
+/// Ignore hidden files and empty files, and apply the rules of the index.
 pub(crate) fn discover_paths<P: AsRef<Path>>(
     root_path: P,
+    dir: &Path,
+    rules: &PathRules,
 ) -> Result<Vec<DirEntry>> {
-    log::debug!("Discovering paths at root path: {:?}", root_path.as_ref());
-
-    let paths = WalkDir::new(root_path)
-        .min_depth(1)
+    let root_path = root_path.as_ref();
+    log::debug!("Discovering paths at root path: {:?}", root_path.join(dir));
+
+    let relative = |entry: &DirEntry| {
+        entry
+            .path()
+            .strip_prefix(root_path)
+            .expect("Failed to strip prefix")
+            .to_path_buf()
+    };
+    let paths = WalkDir::new(root_path.join(dir))
         .into_iter()
+        .filter_entry(|entry| {
+            !entry.file_type().is_dir() || !rules.prunes(&relative(entry))
+        })
         .filter_map(|e| e.ok())
-        .filter(should_index)
+        .filter(|entry| should_index(entry, rules.pin(&relative(entry))))
         .collect();
 
     Ok(paths)
@@ -125,14 +177,23 @@ pub(crate) fn scan_entry<Id: ResourceId>(entry: DirEntry) -> Timestamped<Id> {
     }
 }
 
-/// A helper function to check if the entry should be indexed (not hidden or
-/// empty)
-fn should_index(entry: &walkdir::DirEntry) -> bool {
-    // Check if the entry is hidden
-    if entry
-        .file_name()
-        .to_string_lossy()
-        .starts_with('.')
+/// A helper function to check if the entry should be indexed (not excluded,
+/// hidden or empty)
+///
+/// `pin` is the rule applying to the entry, see [`PathRules::pin`].
+fn should_index(entry: &walkdir::DirEntry, pin: Option<bool>) -> bool {
+    // Check if the entry is excluded
+    if pin == Some(false) {
+        log::trace!("Ignoring excluded path: {:?}", entry.path());
+        return false;
+    }
+
+    // Check if the entry is hidden, unless it was included
+    if pin.is_none()
+        && entry
+            .file_name()
+            .to_string_lossy()
+            .starts_with('.')
     {
         log::trace!("Ignoring hidden file: {:?}", entry.path());
         return false;
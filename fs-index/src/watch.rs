@@ -9,7 +9,7 @@ use tokio::sync::mpsc;
 use data_resource::ResourceId;
 use fs_storage::ARK_FOLDER;
 
-use crate::{IndexUpdate, ResourceIndex};
+use crate::{load_or_build_index, IndexUpdate, ResourceIndex};
 
 /// Represents the different kinds of events that can occur when watching the
 /// resource index.
@@ -38,9 +38,9 @@ pub fn watch_index<P: AsRef<Path>, Id: ResourceId + 'static>(
     );
 
     let root_path = fs::canonicalize(root_path.as_ref()).unwrap();
+    // Loaded rather than rebuilt, to keep the rules of a stored index
     let mut index: ResourceIndex<Id> =
-        ResourceIndex::build(&root_path).unwrap();
-    index.store().unwrap();
+        load_or_build_index(&root_path, true).unwrap();
 
     let (tx, mut rx) = mpsc::channel(100);
     let ark_folder = root_path.join(ARK_FOLDER);
@@ -1,12 +1,45 @@
 use data_error::Result;
 use fs_atomic_versions::atomic::{modify_json, AtomicFile};
 use serde::{de::DeserializeOwned, Serialize};
-use std::{fmt::Debug, io::Read, path::Path};
+use std::{
+    fmt::Debug,
+    io::Read,
+    path::{Path, PathBuf},
+};
 
 use data_resource::ResourceId;
 use fs_storage::ARK_FOLDER;
 
 pub const METADATA_STORAGE_FOLDER: &str = "cache/metadata";
+pub const METADATA_GENERATIONS_FOLDER: &str = "cache/generations";
+pub const EXTERNAL_METADATA_STORAGE_FOLDER: &str = "cache/external";
+
+fn metadata_path<P: AsRef<Path>, Id: ResourceId>(root: P, id: &Id) -> PathBuf {
+    root.as_ref()
+        .join(ARK_FOLDER)
+        .join(METADATA_STORAGE_FOLDER)
+        .join(id.to_string())
+}
+
+fn external_metadata_path<P: AsRef<Path>, Id: ResourceId>(
+    root: P,
+    id: &Id,
+) -> PathBuf {
+    root.as_ref()
+        .join(ARK_FOLDER)
+        .join(EXTERNAL_METADATA_STORAGE_FOLDER)
+        .join(id.to_string())
+}
+
+fn generation_path<P: AsRef<Path>, Id: ResourceId>(
+    root: P,
+    id: &Id,
+) -> PathBuf {
+    root.as_ref()
+        .join(ARK_FOLDER)
+        .join(METADATA_GENERATIONS_FOLDER)
+        .join(id.to_string())
+}
 
 pub fn store_metadata<
     S: Serialize + DeserializeOwned + Clone + Debug,
@@ -17,12 +50,7 @@ pub fn store_metadata<
     id: Id,
     metadata: &S,
 ) -> Result<()> {
-    let file = AtomicFile::new(
-        root.as_ref()
-            .join(ARK_FOLDER)
-            .join(METADATA_STORAGE_FOLDER)
-            .join(id.to_string()),
-    )?;
+    let file = AtomicFile::new(metadata_path(root, &id))?;
     modify_json(&file, |current_meta: &mut Option<S>| {
         let new_meta = metadata.clone();
         match current_meta {
@@ -45,12 +73,7 @@ pub fn load_raw_metadata<P: AsRef<Path>, Id: ResourceId>(
     root: P,
     id: Id,
 ) -> Result<Vec<u8>> {
-    let storage = root
-        .as_ref()
-        .join(ARK_FOLDER)
-        .join(METADATA_STORAGE_FOLDER)
-        .join(id.to_string());
-    let file = AtomicFile::new(storage)?;
+    let file = AtomicFile::new(metadata_path(root, &id))?;
     let read_file = file.load()?;
     if let Some(mut real_file) = read_file.open()? {
         let mut content = vec![];
@@ -64,6 +87,129 @@ pub fn load_raw_metadata<P: AsRef<Path>, Id: ResourceId>(
     }
 }
 
+/// Remove cached metadata of the resource together with its generation
+/// stamp, so that it is generated again on next access.
+pub fn invalidate_metadata<P: AsRef<Path>, Id: ResourceId>(
+    root: P,
+    id: Id,
+) -> Result<()> {
+    for path in [metadata_path(&root, &id), generation_path(&root, &id)] {
+        if path.exists() {
+            std::fs::remove_dir_all(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Regenerate metadata of the resource using `generator` and stamp it
+/// with `generation`, the version of the algorithm which produced it.
+pub fn refresh_metadata<
+    S: Serialize + DeserializeOwned + Clone + Debug,
+    P: AsRef<Path>,
+    Id: ResourceId,
+    F: FnOnce() -> Result<S>,
+>(
+    root: P,
+    id: Id,
+    generation: u32,
+    generator: F,
+) -> Result<S> {
+    let metadata = generator()?;
+    store_metadata(&root, id.clone(), &metadata)?;
+
+    let file = AtomicFile::new(generation_path(&root, &id))?;
+    modify_json(&file, |current: &mut Option<u32>| {
+        *current = Some(generation)
+    })?;
+    Ok(metadata)
+}
+
+/// Generation stamp of the cached metadata, `None` if the metadata
+/// was stored without a stamp or hasn't been generated yet.
+pub fn metadata_generation<P: AsRef<Path>, Id: ResourceId>(
+    root: P,
+    id: Id,
+) -> Result<Option<u32>> {
+    let path = generation_path(&root, &id);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let file = AtomicFile::new(path)?;
+    match file.load()?.open()? {
+        Some(real_file) => Ok(serde_json::from_reader(real_file)?),
+        None => Ok(None),
+    }
+}
+
+/// Check if the cached metadata was produced by a generation
+/// older than `generation` and must be refreshed.
+pub fn is_metadata_stale<P: AsRef<Path>, Id: ResourceId>(
+    root: P,
+    id: Id,
+    generation: u32,
+) -> Result<bool> {
+    Ok(metadata_generation(root, id)?.map_or(true, |g| g < generation))
+}
+
+/// Store metadata authored outside of ark, e.g. extended attributes or
+/// sidecar files of the resource. It is kept apart from generated metadata,
+/// so neither of them overwrites the other.
+///
+/// Nothing is written if the stored metadata is the same, so that re-reading
+/// unchanged files doesn't create new versions.
+pub fn store_external_metadata<
+    S: Serialize + DeserializeOwned + Clone + Debug + PartialEq,
+    P: AsRef<Path>,
+    Id: ResourceId,
+>(
+    root: P,
+    id: Id,
+    metadata: &S,
+) -> Result<()> {
+    if load_external_metadata::<S, _, _>(&root, id.clone())?.as_ref()
+        == Some(metadata)
+    {
+        return Ok(());
+    }
+    let file = AtomicFile::new(external_metadata_path(root, &id))?;
+    modify_json(&file, |current: &mut Option<S>| {
+        *current = Some(metadata.clone())
+    })?;
+    Ok(())
+}
+
+/// Load metadata authored outside of ark, `None` if none was stored
+pub fn load_external_metadata<
+    S: DeserializeOwned,
+    P: AsRef<Path>,
+    Id: ResourceId,
+>(
+    root: P,
+    id: Id,
+) -> Result<Option<S>> {
+    let path = external_metadata_path(&root, &id);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let file = AtomicFile::new(path)?;
+    match file.load()?.open()? {
+        Some(real_file) => Ok(serde_json::from_reader(real_file)?),
+        None => Ok(None),
+    }
+}
+
+/// Remove metadata authored outside of ark, e.g. after its source is gone
+pub fn remove_external_metadata<P: AsRef<Path>, Id: ResourceId>(
+    root: P,
+    id: Id,
+) -> Result<()> {
+    let path = external_metadata_path(&root, &id);
+    if path.exists() {
+        std::fs::remove_dir_all(path)?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use fs_atomic_versions::initialize;
@@ -96,4 +242,63 @@ mod tests {
         let prop2: TestMetadata = serde_json::from_slice(&bytes).unwrap();
         assert_eq!(meta, prop2);
     }
+
+    #[test]
+    fn test_refresh_and_invalidate() {
+        initialize();
+
+        let dir = TempDir::new("arklib_test").unwrap();
+        let root = dir.path();
+
+        let id = Crc32(0x342a3d4a);
+        assert!(is_metadata_stale(root, id.clone(), 1).unwrap());
+
+        let mut meta = TestMetadata::new();
+        meta.insert("abc".to_string(), "def".to_string());
+        refresh_metadata(root, id.clone(), 1, || Ok(meta.clone())).unwrap();
+
+        assert_eq!(metadata_generation(root, id.clone()).unwrap(), Some(1));
+        assert!(!is_metadata_stale(root, id.clone(), 1).unwrap());
+        assert!(is_metadata_stale(root, id.clone(), 2).unwrap());
+
+        let bytes = load_raw_metadata(root, id.clone()).unwrap();
+        let loaded: TestMetadata = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(meta, loaded);
+
+        invalidate_metadata(root, id.clone()).unwrap();
+        assert_eq!(metadata_generation(root, id.clone()).unwrap(), None);
+        assert!(load_raw_metadata(root, id).is_err());
+    }
+
+    #[test]
+    fn test_external_metadata() {
+        initialize();
+
+        let dir = TempDir::new("arklib_test").unwrap();
+        let root = dir.path();
+
+        let id = Crc32(0x342a3d4a);
+        let loaded: Option<TestMetadata> =
+            load_external_metadata(root, id.clone()).unwrap();
+        assert_eq!(loaded, None);
+
+        let mut meta = TestMetadata::new();
+        meta.insert("user.xdg.comment".to_string(), "cat".to_string());
+        store_external_metadata(root, id.clone(), &meta).unwrap();
+        // Storing the same metadata again doesn't create a new version
+        store_external_metadata(root, id.clone(), &meta).unwrap();
+        let file = AtomicFile::new(external_metadata_path(root, &id)).unwrap();
+        assert_eq!(file.load().unwrap().version, 1);
+
+        // Generated metadata is kept apart
+        assert!(load_raw_metadata(root, id.clone()).is_err());
+        let loaded: Option<TestMetadata> =
+            load_external_metadata(root, id.clone()).unwrap();
+        assert_eq!(loaded, Some(meta));
+
+        remove_external_metadata(root, id.clone()).unwrap();
+        let loaded: Option<TestMetadata> =
+            load_external_metadata(root, id).unwrap();
+        assert_eq!(loaded, None);
+    }
 }
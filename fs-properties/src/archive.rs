@@ -0,0 +1,291 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+};
+
+use data_error::Result;
+use data_json::merge;
+use fs_atomic_versions::atomic::{modify, modify_json, AtomicFile};
+use fs_storage::{ARK_FOLDER, SCORE_STORAGE_FILE, TAG_STORAGE_FILE};
+
+use crate::PROPERTIES_STORAGE_FOLDER;
+
+pub const ARCHIVE_VERSION: i32 = 1;
+
+/// Portable snapshot of all user-defined data of a root:
+/// tags, scores and properties, keyed by resource id.
+///
+/// Unlike raw `.ark` folders, the archive doesn't depend on
+/// app ids or versioned file names, so it can be moved
+/// between machines and imported into another root.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UserDataArchive {
+    pub version: i32,
+    pub tags: BTreeMap<String, BTreeSet<String>>,
+    pub scores: BTreeMap<String, i32>,
+    pub properties: BTreeMap<String, Value>,
+}
+
+impl Default for UserDataArchive {
+    fn default() -> Self {
+        Self {
+            version: ARCHIVE_VERSION,
+            tags: BTreeMap::new(),
+            scores: BTreeMap::new(),
+            properties: BTreeMap::new(),
+        }
+    }
+}
+
+impl UserDataArchive {
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+            && self.scores.is_empty()
+            && self.properties.is_empty()
+    }
+
+    /// Keep only the data of the given resources, e.g. to send
+    /// the data of a selection of resources to another device
+    pub fn retain_resources(&mut self, ids: &BTreeSet<String>) {
+        self.tags.retain(|id, _| ids.contains(id));
+        self.scores.retain(|id, _| ids.contains(id));
+        self.properties.retain(|id, _| ids.contains(id));
+    }
+}
+
+/// Collect tags, scores and properties of the root into an archive
+pub fn export_user_data<P: AsRef<Path>>(root: P) -> Result<UserDataArchive> {
+    let ark = root.as_ref().join(ARK_FOLDER);
+    let mut archive = UserDataArchive::default();
+
+    for (id, value) in read_lines(&ark.join(TAG_STORAGE_FILE))? {
+        archive
+            .tags
+            .entry(id)
+            .or_default()
+            .extend(parse_tags(&value));
+    }
+
+    // the latest appended score of a resource wins
+    for (id, value) in read_lines(&ark.join(SCORE_STORAGE_FILE))? {
+        if let Ok(score) = value.trim().parse::<i32>() {
+            archive.scores.insert(id, score);
+        }
+    }
+
+    let properties = ark.join(PROPERTIES_STORAGE_FOLDER);
+    if properties.exists() {
+        for entry in fs::read_dir(&properties)?.flatten() {
+            if !entry.path().is_dir() {
+                continue;
+            }
+            let id = entry.file_name().to_string_lossy().to_string();
+            let current = AtomicFile::new(entry.path())?.load()?;
+            if let Some(file) = current.open()? {
+                let value: Value = serde_json::from_reader(file)?;
+                archive.properties.insert(id, value);
+            }
+        }
+    }
+
+    Ok(archive)
+}
+
+/// Merge an archive into the user data of the root.
+///
+/// Tags are united, imported scores replace the current ones
+/// and properties are merged the same way `store_properties` does.
+pub fn import_user_data<P: AsRef<Path>>(
+    root: P,
+    archive: &UserDataArchive,
+) -> Result<()> {
+    if archive.version > ARCHIVE_VERSION {
+        return Err(data_error::ArklibError::Parse);
+    }
+    let ark = root.as_ref().join(ARK_FOLDER);
+
+    if !archive.tags.is_empty() {
+        let file = AtomicFile::new(ark.join(TAG_STORAGE_FILE))?;
+        modify(&file, |current| {
+            let mut tags: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+            for (id, value) in parse_lines(current) {
+                tags.entry(id)
+                    .or_default()
+                    .extend(parse_tags(&value));
+            }
+            for (id, imported) in &archive.tags {
+                tags.entry(id.clone())
+                    .or_default()
+                    .extend(imported.iter().cloned());
+            }
+            tags.iter()
+                .map(|(id, tags)| {
+                    let tags: Vec<&str> =
+                        tags.iter().map(String::as_str).collect();
+                    format!("{}:{}\n", id, tags.join(","))
+                })
+                .collect::<String>()
+                .into_bytes()
+        })?;
+    }
+
+    if !archive.scores.is_empty() {
+        let file = AtomicFile::new(ark.join(SCORE_STORAGE_FILE))?;
+        modify(&file, |current| {
+            let mut scores: BTreeMap<String, i32> = BTreeMap::new();
+            for (id, value) in parse_lines(current) {
+                if let Ok(score) = value.trim().parse::<i32>() {
+                    scores.insert(id, score);
+                }
+            }
+            scores.extend(
+                archive
+                    .scores
+                    .iter()
+                    .map(|(id, score)| (id.clone(), *score)),
+            );
+            scores
+                .iter()
+                .map(|(id, score)| format!("{}:{}\n", id, score))
+                .collect::<String>()
+                .into_bytes()
+        })?;
+    }
+
+    for (id, imported) in &archive.properties {
+        let file =
+            AtomicFile::new(ark.join(PROPERTIES_STORAGE_FOLDER).join(id))?;
+        modify_json(&file, |current: &mut Option<Value>| {
+            let new_value = imported.clone();
+            *current = Some(match current.take() {
+                Some(old_value) => merge(old_value, new_value),
+                None => new_value,
+            });
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Write the archive of the root into a single JSON file
+pub fn export_user_data_to<P: AsRef<Path>, Q: AsRef<Path>>(
+    root: P,
+    destination: Q,
+) -> Result<()> {
+    let archive = export_user_data(root)?;
+    let content = serde_json::to_string_pretty(&archive)?;
+    fs::write(destination, content)?;
+    Ok(())
+}
+
+/// Read an archive from a JSON file and merge it into the root
+pub fn import_user_data_from<P: AsRef<Path>, Q: AsRef<Path>>(
+    root: P,
+    source: Q,
+) -> Result<()> {
+    let content = fs::read(source)?;
+    let archive: UserDataArchive = serde_json::from_slice(&content)?;
+    import_user_data(root, &archive)
+}
+
+fn read_lines(path: &Path) -> Result<Vec<(String, String)>> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let current = AtomicFile::new(path)?.load()?;
+    if current.version == 0 {
+        return Ok(vec![]);
+    }
+    Ok(parse_lines(&current.read_content()?))
+}
+
+fn parse_lines(content: &[u8]) -> Vec<(String, String)> {
+    String::from_utf8_lossy(content)
+        .lines()
+        .filter_map(|line| {
+            let (id, value) = line.split_once(':')?;
+            Some((id.trim().to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+fn parse_tags(value: &str) -> impl Iterator<Item = String> + '_ {
+    value
+        .split(',')
+        .map(|tag| tag.trim())
+        .filter(|tag| !tag.is_empty())
+        .map(|tag| tag.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use fs_atomic_versions::initialize;
+
+    use super::*;
+    use tempdir::TempDir;
+
+    use dev_hash::Crc32;
+
+    use crate::store_properties;
+
+    #[test]
+    fn test_export_and_import() {
+        initialize();
+
+        let source = TempDir::new("arklib_test").unwrap();
+        let target = TempDir::new("arklib_test").unwrap();
+
+        let id = Crc32(0x342a3d4a);
+        let mut prop = BTreeMap::new();
+        prop.insert("abc".to_string(), "def".to_string());
+        store_properties(source.path(), id.clone(), &prop).unwrap();
+
+        let mut archive = export_user_data(source.path()).unwrap();
+        assert_eq!(archive.properties.len(), 1);
+
+        archive
+            .tags
+            .insert(id.to_string(), ["work".to_string()].into());
+        archive.scores.insert(id.to_string(), 5);
+        import_user_data(target.path(), &archive).unwrap();
+
+        let mut other = UserDataArchive::default();
+        other
+            .tags
+            .insert(id.to_string(), ["home".to_string()].into());
+        other.scores.insert(id.to_string(), 3);
+        import_user_data(target.path(), &other).unwrap();
+
+        let imported = export_user_data(target.path()).unwrap();
+        assert_eq!(imported.properties, archive.properties);
+        assert_eq!(imported.scores[&id.to_string()], 3);
+        assert_eq!(
+            imported.tags[&id.to_string()],
+            ["home".to_string(), "work".to_string()].into()
+        );
+    }
+
+    #[test]
+    fn test_retain_resources() {
+        let mut archive = UserDataArchive::default();
+        archive
+            .tags
+            .insert("1".to_string(), ["work".to_string()].into());
+        archive.scores.insert("1".to_string(), 5);
+        archive.scores.insert("2".to_string(), 3);
+        archive
+            .properties
+            .insert("2".to_string(), serde_json::json!({"abc": "def"}));
+
+        archive.retain_resources(&["1".to_string()].into());
+        assert_eq!(archive.tags.len(), 1);
+        assert_eq!(archive.scores.len(), 1);
+        assert!(archive.properties.is_empty());
+
+        archive.retain_resources(&BTreeSet::new());
+        assert!(archive.is_empty());
+    }
+}
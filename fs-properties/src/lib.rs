@@ -3,11 +3,13 @@ use serde_json::Value;
 use std::{fmt::Debug, io::Read, path::Path};
 
 use data_error::Result;
-use data_json::merge;
+use data_json::{merge, merge_at, MergeOptions};
 use data_resource::ResourceId;
 use fs_atomic_versions::atomic::{modify_json, AtomicFile};
 use fs_storage::ARK_FOLDER;
 
+pub mod archive;
+
 pub const PROPERTIES_STORAGE_FOLDER: &str = "user/properties";
 
 pub fn store_properties<
@@ -40,6 +42,33 @@ pub fn store_properties<
     Ok(())
 }
 
+/// Merge `value` only into the part of the properties addressed by
+/// the JSON Pointer, so that updates of different fields don't interfere
+pub fn store_properties_at<S: Serialize, P: AsRef<Path>, Id: ResourceId>(
+    root: P,
+    id: Id,
+    pointer: &str,
+    value: &S,
+    options: &MergeOptions,
+) -> Result<()> {
+    let file = AtomicFile::new(
+        root.as_ref()
+            .join(ARK_FOLDER)
+            .join(PROPERTIES_STORAGE_FOLDER)
+            .join(id.to_string()),
+    )?;
+    let value = serde_json::to_value(value)?;
+    let mut result = Ok(());
+    modify_json(&file, |current_data: &mut Option<Value>| {
+        let mut properties = current_data.clone().unwrap_or(Value::Null);
+        result = merge_at(&mut properties, pointer, value.clone(), options);
+        if result.is_ok() {
+            *current_data = Some(properties);
+        }
+    })?;
+    result.map_err(|e| data_error::ArklibError::Other(e.into()))
+}
+
 /// The file must exist if this method is called
 pub fn load_raw_properties<P: AsRef<Path>, Id: ResourceId>(
     root: P,
@@ -96,4 +125,31 @@ mod tests {
         let prop2: TestProperties = serde_json::from_slice(&bytes).unwrap();
         assert_eq!(prop, prop2);
     }
+
+    #[test]
+    fn test_store_at_pointer() {
+        initialize();
+
+        let dir = TempDir::new("arklib_test").unwrap();
+        let root = dir.path();
+        let id = Crc32(0x342a3d4a);
+        let options = MergeOptions {
+            conflict: data_json::Conflict::NewWins,
+            ..Default::default()
+        };
+
+        let prop = serde_json::json!({"a": {"b": 1}, "c": 1});
+        store_properties(root, id.clone(), &prop).unwrap();
+        store_properties_at(root, id.clone(), "/a/b", &2, &options).unwrap();
+        store_properties_at(root, id.clone(), "/d", &"x", &options).unwrap();
+
+        let bytes = load_raw_properties(root, id.clone()).unwrap();
+        let stored: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(
+            stored,
+            serde_json::json!({"a": {"b": 2}, "c": 1, "d": "x"})
+        );
+
+        assert!(store_properties_at(root, id, "/c/x", &0, &options).is_err());
+    }
 }
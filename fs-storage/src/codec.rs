@@ -0,0 +1,100 @@
+use std::{
+    fs::File,
+    io::{ErrorKind, Read},
+    path::Path,
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use data_error::{ArklibError, Result};
+
+/// CBOR self-describe tag (RFC 8949, section 3.4.6), written at the start of
+/// CBOR files so that they can be told apart from JSON ones
+const CBOR_MAGIC: [u8; 3] = [0xd9, 0xd9, 0xf7];
+
+/// Serialization format of the data persisted by a storage
+///
+/// The format of existing files is detected when they are loaded, so storages
+/// can switch codecs without losing data.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Codec {
+    /// Pretty-printed JSON, readable by humans and older versions
+    #[default]
+    Json,
+    /// Binary CBOR, faster to parse and smaller for large maps
+    Cbor,
+}
+
+impl Codec {
+    /// Serialize the value in this format
+    pub fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>> {
+        match self {
+            Codec::Json => Ok(serde_json::to_vec_pretty(value)?),
+            Codec::Cbor => {
+                let mut bytes = CBOR_MAGIC.to_vec();
+                ciborium::into_writer(value, &mut bytes).map_err(|err| {
+                    ArklibError::from(err.to_string().as_str())
+                })?;
+                Ok(bytes)
+            }
+        }
+    }
+
+    /// Deserialize a value from bytes in this format
+    pub fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T> {
+        match self {
+            // Errors keep their message, to be reported with the storage label
+            Codec::Json => serde_json::from_slice(bytes)
+                .map_err(|err| ArklibError::from(err.to_string().as_str())),
+            Codec::Cbor => {
+                let bytes = bytes.strip_prefix(&CBOR_MAGIC).unwrap_or(bytes);
+                ciborium::from_reader(bytes)
+                    .map_err(|err| ArklibError::from(err.to_string().as_str()))
+            }
+        }
+    }
+
+    /// Detect the format of encoded bytes, JSON is assumed unless they start
+    /// with the CBOR magic
+    pub fn detect(bytes: &[u8]) -> Self {
+        if bytes.starts_with(&CBOR_MAGIC) {
+            Codec::Cbor
+        } else {
+            Codec::Json
+        }
+    }
+
+    /// Detect the format of a file by reading its first bytes only
+    pub fn detect_file(path: &Path) -> Result<Self> {
+        let mut magic = [0; CBOR_MAGIC.len()];
+        match File::open(path)?.read_exact(&mut magic) {
+            Ok(()) => Ok(Self::detect(&magic)),
+            // Too short to be CBOR
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => {
+                Ok(Codec::Json)
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_codec_round_trip() {
+        let mut map = BTreeMap::new();
+        map.insert("key1".to_string(), vec![1, 2, 3]);
+        map.insert("key2".to_string(), vec![]);
+
+        for codec in [Codec::Json, Codec::Cbor] {
+            let bytes = codec.encode(&map).unwrap();
+            assert_eq!(Codec::detect(&bytes), codec);
+            let decoded: BTreeMap<String, Vec<i32>> =
+                codec.decode(&bytes).unwrap();
+            assert_eq!(decoded, map);
+        }
+    }
+}
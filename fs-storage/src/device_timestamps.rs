@@ -0,0 +1,93 @@
+use std::{collections::BTreeMap, time::SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+use data_error::Result;
+use fs_atomic_versions::app_id;
+
+use crate::monoid::Monoid;
+
+/// Last time each device changed a value, by device id
+///
+/// Stored along with synced values, it tells which device changed them last
+/// without relying on the clock of a single device. Storages merge these
+/// timestamps by keeping the latest one of each device.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceTimestamps(BTreeMap<String, SystemTime>);
+
+impl DeviceTimestamps {
+    /// Record a change made now by this device, as identified by
+    /// [`app_id::read`]
+    pub fn touch(&mut self) -> Result<()> {
+        let device = app_id::read()?;
+        self.record(device, SystemTime::now());
+        Ok(())
+    }
+
+    /// Record a change made by a device at the given time, unless a later
+    /// one was recorded already
+    pub fn record(&mut self, device: String, time: SystemTime) {
+        let entry = self.0.entry(device).or_insert(time);
+        *entry = (*entry).max(time);
+    }
+
+    /// Return the time of the last change made by a device
+    pub fn get(&self, device: &str) -> Option<SystemTime> {
+        self.0.get(device).copied()
+    }
+
+    /// Return the device which made the last change, with its time
+    pub fn latest(&self) -> Option<(&str, SystemTime)> {
+        self.0
+            .iter()
+            .max_by_key(|(_, time)| **time)
+            .map(|(device, time)| (device.as_str(), *time))
+    }
+
+    /// Iterate over the devices and the times of their last changes
+    pub fn iter(&self) -> impl Iterator<Item = (&str, SystemTime)> {
+        self.0
+            .iter()
+            .map(|(device, time)| (device.as_str(), *time))
+    }
+}
+
+impl Monoid<DeviceTimestamps> for DeviceTimestamps {
+    fn neutral() -> DeviceTimestamps {
+        DeviceTimestamps::default()
+    }
+
+    fn combine(a: &DeviceTimestamps, b: &DeviceTimestamps) -> DeviceTimestamps {
+        let mut result = a.clone();
+        for (device, time) in b.iter() {
+            result.record(device.to_owned(), time);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monoid;
+    use std::time::Duration;
+
+    #[test]
+    fn test_combine_device_timestamps() {
+        let earlier = SystemTime::UNIX_EPOCH + Duration::from_secs(10);
+        let later = earlier + Duration::from_secs(10);
+
+        let mut a = DeviceTimestamps::default();
+        a.record("laptop".to_owned(), later);
+        a.record("phone".to_owned(), earlier);
+        let mut b = DeviceTimestamps::default();
+        b.record("phone".to_owned(), later);
+        b.record("laptop".to_owned(), earlier);
+
+        let merged = monoid::combine(&a, &b).unwrap();
+        assert_eq!(merged.get("laptop"), Some(later));
+        assert_eq!(merged.get("phone"), Some(later));
+        assert_eq!(merged, monoid::combine(&b, &a).unwrap());
+        assert_eq!(a.latest(), Some(("laptop", later)));
+    }
+}
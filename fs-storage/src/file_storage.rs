@@ -4,12 +4,14 @@ use std::{
     fs::{self, File},
     io::Write,
     path::{Path, PathBuf},
+    sync::Arc,
     time::SystemTime,
 };
 
 use crate::{
     base_storage::{BaseStorage, SyncStatus},
-    monoid::Monoid,
+    codec::Codec,
+    monoid::{self, CombineFn, Monoid},
     utils::read_version_2_fs,
 };
 use data_error::{ArklibError, Result};
@@ -20,7 +22,8 @@ Note on `FileStorage` Versioning:
 `FileStorage` is a basic key-value storage system that persists data to disk.
 
 In version 2, `FileStorage` stored data in a plaintext format.
-Starting from version 3, data is stored in JSON format.
+Starting from version 3, data is stored in JSON format, or in CBOR format
+if requested with `FileStorage::with_codec`. The format is detected on load.
 
 For backward compatibility, we provide a helper function `read_version_2_fs` to read version 2 format.
 */
@@ -41,7 +44,11 @@ where
     /// Last time the data was written to disk. This becomes equal to
     /// `modified` only when data is written or read from disk.
     written_to_disk: SystemTime,
+    /// Format the data is written in
+    codec: Codec,
     data: FileStorageData<K, V>,
+    /// Combines the values of a key when merging
+    combine: CombineFn<V>,
 }
 
 /// A struct that represents the data stored in a [`FileStorage`] instance.
@@ -77,33 +84,79 @@ where
         + serde::Serialize
         + serde::de::DeserializeOwned
         + std::str::FromStr
-        + Monoid<V>,
+        + Monoid<V>
+        + 'static,
 {
     /// Create a new file storage with a diagnostic label and file path
     /// The storage will be initialized using the disk data, if the path exists
     ///
     /// Note: if the file storage already exists, the data will be read from the
-    /// file without overwriting it.
+    /// file without overwriting it. Data is then written in the format of the
+    /// file, JSON for new storages.
     pub fn new(label: String, path: &Path) -> Result<Self> {
+        let combine: CombineFn<V> = Arc::new(V::combine);
+        Self::open(label, path, combine)
+    }
+}
+
+impl<K, V> FileStorage<K, V>
+where
+    K: Ord
+        + Clone
+        + serde::Serialize
+        + serde::de::DeserializeOwned
+        + std::str::FromStr,
+    V: Clone
+        + serde::Serialize
+        + serde::de::DeserializeOwned
+        + std::str::FromStr
+        + 'static,
+{
+    /// Create a new file storage like [`FileStorage::new`], for values which
+    /// aren't a [`Monoid`], combined with the function registered for their
+    /// type, see [`monoid::register_combine`]. Fails if there is none.
+    pub fn new_registered(label: String, path: &Path) -> Result<Self> {
+        let combine = monoid::combine_fn::<V>().ok_or_else(|| {
+            ArklibError::Storage(label.clone(), monoid::missing_combine::<V>())
+        })?;
+        Self::open(label, path, combine)
+    }
+
+    fn open(label: String, path: &Path, combine: CombineFn<V>) -> Result<Self> {
         let time = SystemTime::now();
         let mut storage = Self {
             label,
             path: PathBuf::from(path),
             modified: time,
             written_to_disk: time,
+            codec: Codec::default(),
             data: FileStorageData {
                 version: STORAGE_VERSION,
                 entries: BTreeMap::new(),
             },
+            combine,
         };
 
         if Path::exists(path) {
+            storage.codec = Codec::detect_file(path)?;
             storage.read_fs()?;
         }
 
         Ok(storage)
     }
 
+    /// Write the data in the given format from now on, e.g. CBOR for large
+    /// maps. The file is converted on next write.
+    pub fn with_codec(mut self, codec: Codec) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    /// Format the data is written in
+    pub fn codec(&self) -> Codec {
+        self.codec
+    }
+
     /// Load mapping from file
     fn load_fs_data(&self) -> Result<FileStorageData<K, V>> {
         if !self.path.exists() {
@@ -114,8 +167,8 @@ where
         }
 
         // First check if the file starts with "version: 2"
-        let file_content = std::fs::read_to_string(&self.path)?;
-        if file_content.starts_with("version: 2") {
+        let file_content = fs::read(&self.path)?;
+        if file_content.starts_with(b"version: 2") {
             // Attempt to parse the file using the legacy version 2 storage
             // format of FileStorage.
             match read_version_2_fs(&self.path) {
@@ -140,8 +193,8 @@ where
             };
         }
 
-        let file = fs::File::open(&self.path)?;
-        let data: FileStorageData<K, V> = serde_json::from_reader(file)
+        let data: FileStorageData<K, V> = Codec::detect(&file_content)
+            .decode(&file_content)
             .map_err(|err| {
                 ArklibError::Storage(self.label.clone(), err.to_string())
             })?;
@@ -171,7 +224,7 @@ where
         + serde::Serialize
         + serde::de::DeserializeOwned
         + std::str::FromStr
-        + Monoid<V>,
+        + 'static,
 {
     /// Set a key-value pair in the internal mapping
     fn set(&mut self, key: K, value: V) {
@@ -263,7 +316,7 @@ where
         })?;
         fs::create_dir_all(parent_dir)?;
         let mut file = File::create(&self.path)?;
-        file.write_all(serde_json::to_string_pretty(&self.data)?.as_bytes())?;
+        file.write_all(&self.codec.encode(&self.data)?)?;
         file.flush()?;
 
         let new_timestamp = SystemTime::now();
@@ -289,14 +342,11 @@ where
     }
 
     /// Merge the data from another storage instance into this storage instance
-    fn merge_from(&mut self, other: impl AsRef<BTreeMap<K, V>>) -> Result<()>
-    where
-        V: Monoid<V>,
-    {
+    fn merge_from(&mut self, other: impl AsRef<BTreeMap<K, V>>) -> Result<()> {
         let other_entries = other.as_ref();
         for (key, value) in other_entries {
             if let Some(existing_value) = self.data.entries.get(key) {
-                let resolved_value = V::combine(existing_value, value);
+                let resolved_value = (self.combine)(existing_value, value);
                 self.set(key.clone(), resolved_value);
             } else {
                 self.set(key.clone(), value.clone())
@@ -323,6 +373,7 @@ mod tests {
 
     use crate::{
         base_storage::{BaseStorage, SyncStatus},
+        codec::Codec,
         file_storage::FileStorage,
     };
 
@@ -469,4 +520,40 @@ mod tests {
         assert_eq!(file_storage_1.as_ref().get("key2"), Some(&6));
         assert_eq!(file_storage_1.as_ref().get("key3"), Some(&9));
     }
+
+    #[test]
+    fn test_file_storage_codec() {
+        let temp_dir =
+            TempDir::new("tmp").expect("Failed to create temporary directory");
+        let storage_path = temp_dir.path().join("teststorage.txt");
+
+        let mut file_storage =
+            FileStorage::new("TestStorage".to_string(), &storage_path).unwrap();
+        file_storage.set("key1".to_string(), "value1".to_string());
+        file_storage.write_fs().unwrap();
+        assert!(fs::read_to_string(&storage_path)
+            .unwrap()
+            .starts_with('{'));
+
+        // JSON storage is converted on next write
+        let mut file_storage: FileStorage<String, String> =
+            FileStorage::new("TestStorage".to_string(), &storage_path)
+                .unwrap()
+                .with_codec(Codec::Cbor);
+        file_storage.set("key2".to_string(), "value2".to_string());
+        file_storage.write_fs().unwrap();
+        assert_eq!(Codec::detect_file(&storage_path).unwrap(), Codec::Cbor);
+
+        // The format is detected on load and kept for writes
+        let file_storage: FileStorage<String, String> =
+            FileStorage::new("TestStorage".to_string(), &storage_path).unwrap();
+        assert_eq!(file_storage.codec(), Codec::Cbor);
+        assert_eq!(file_storage.as_ref().len(), 2);
+        assert_eq!(
+            file_storage
+                .get(&"key1".to_string())
+                .map(|v| v.as_str()),
+            Some("value1")
+        );
+    }
 }
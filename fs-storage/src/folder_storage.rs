@@ -3,12 +3,13 @@ use std::{
     fs::{self, File},
     io::Write,
     path::{Path, PathBuf},
+    sync::Arc,
     time::SystemTime,
 };
 
 use crate::{
     base_storage::{BaseStorage, SyncStatus},
-    monoid::Monoid,
+    monoid::{self, CombineFn, Monoid},
 };
 use data_error::{ArklibError, Result};
 
@@ -29,6 +30,8 @@ pub struct FolderStorage<K, V> {
     data: BTreeMap<K, V>,
     /// Temporary store for deleted keys until storage is synced
     deleted_keys: BTreeSet<K>,
+    /// Combines the values of a key which diverged
+    combine: CombineFn<V>,
 }
 
 impl<K, V> AsRef<BTreeMap<K, V>> for FolderStorage<K, V>
@@ -48,18 +51,49 @@ where
         + serde::de::DeserializeOwned
         + std::str::FromStr
         + std::fmt::Display,
-    V: Clone + serde::Serialize + serde::de::DeserializeOwned + Monoid<V>,
+    V: Clone
+        + serde::Serialize
+        + serde::de::DeserializeOwned
+        + Monoid<V>
+        + 'static,
 {
     /// Create a new folder storage with a diagnostic label and directory path
     /// Note: if the folder storage already exists, the data will be read from
     /// the folder without overwriting it.
     pub fn new(label: String, path: &Path) -> Result<Self> {
+        let combine: CombineFn<V> = Arc::new(V::combine);
+        Self::open(label, path, combine)
+    }
+}
+
+impl<K, V> FolderStorage<K, V>
+where
+    K: Ord
+        + Clone
+        + serde::Serialize
+        + serde::de::DeserializeOwned
+        + std::str::FromStr
+        + std::fmt::Display,
+    V: Clone + serde::Serialize + serde::de::DeserializeOwned + 'static,
+{
+    /// Create a new folder storage like [`FolderStorage::new`], for values
+    /// which aren't a [`Monoid`], combined with the function registered for
+    /// their type, see [`monoid::register_combine`]. Fails if there is none.
+    pub fn new_registered(label: String, path: &Path) -> Result<Self> {
+        let combine = monoid::combine_fn::<V>().ok_or_else(|| {
+            ArklibError::Storage(label.clone(), monoid::missing_combine::<V>())
+        })?;
+        Self::open(label, path, combine)
+    }
+
+    fn open(label: String, path: &Path, combine: CombineFn<V>) -> Result<Self> {
         let mut storage = Self {
             label,
             path: PathBuf::from(path),
             timestamps: BTreeMap::new(),
             data: BTreeMap::new(),
             deleted_keys: BTreeSet::new(),
+            combine,
         };
 
         if Path::exists(path) {
@@ -133,7 +167,8 @@ where
     /// or overwriting values based on which version is more recent,
     /// ensuring consistency.
     fn resolve_divergence(&mut self) -> Result<()> {
-        let new_data = FolderStorage::new("new_data".into(), &self.path)?;
+        let new_data =
+            Self::open("new_data".into(), &self.path, self.combine.clone())?;
 
         for (key, new_value) in new_data.data.iter() {
             if let Some(existing_value) = self.data.get(key) {
@@ -146,7 +181,8 @@ where
                 // Use monoid to combine value for the given key
                 // if the memory and disk have diverged
                 if existing_value_updated {
-                    let resolved_value = V::combine(existing_value, new_value);
+                    let resolved_value =
+                        (self.combine)(existing_value, new_value);
                     self.data.insert(key.clone(), resolved_value);
                 } else {
                     self.data.insert(key.clone(), new_value.clone());
@@ -181,7 +217,7 @@ where
         + serde::de::DeserializeOwned
         + std::str::FromStr
         + std::fmt::Display,
-    V: Clone + serde::Serialize + serde::de::DeserializeOwned + Monoid<V>,
+    V: Clone + serde::Serialize + serde::de::DeserializeOwned + 'static,
 {
     /// Set a key-value pair in the internal mapping
     fn set(&mut self, key: K, value: V) {
@@ -395,14 +431,11 @@ where
 
     /// Merge the data from another folder storage instance into this folder
     /// storage instance
-    fn merge_from(&mut self, other: impl AsRef<BTreeMap<K, V>>) -> Result<()>
-    where
-        V: Monoid<V>,
-    {
+    fn merge_from(&mut self, other: impl AsRef<BTreeMap<K, V>>) -> Result<()> {
         let other_entries = other.as_ref();
         for (key, value) in other_entries {
             if let Some(existing_value) = self.data.get(key) {
-                let resolved_value = V::combine(existing_value, value);
+                let resolved_value = (self.combine)(existing_value, value);
                 self.set(key.clone(), resolved_value);
             } else {
                 self.set(key.clone(), value.clone())
@@ -450,7 +483,7 @@ mod tests {
     use crate::{
         base_storage::{BaseStorage, SyncStatus},
         folder_storage::FolderStorage,
-        monoid::Monoid,
+        monoid::{self, Monoid},
     };
     use std::{
         collections::BTreeSet,
@@ -590,6 +623,45 @@ mod tests {
         assert_eq!(storage1.as_ref().get("key3"), Some(&9));
     }
 
+    #[test]
+    fn test_registered_combine() {
+        // Vector clocks, merged by taking the latest counter of each device
+        type Clock = BTreeMap<String, u64>;
+        let temp_dir =
+            TempDir::new("tmp").expect("Failed to create temporary directory");
+        assert!(FolderStorage::<String, Clock>::new_registered(
+            "test".to_owned(),
+            temp_dir.path()
+        )
+        .is_err());
+
+        monoid::register_combine(|a: &Clock, b: &Clock| {
+            let mut clock = a.clone();
+            for (device, counter) in b {
+                let entry = clock.entry(device.clone()).or_default();
+                *entry = (*entry).max(*counter);
+            }
+            clock
+        });
+        let mut storage1: FolderStorage<String, Clock> =
+            FolderStorage::new_registered("test".to_owned(), temp_dir.path())
+                .unwrap();
+        let mut storage2: FolderStorage<String, Clock> =
+            FolderStorage::new_registered("test".to_owned(), temp_dir.path())
+                .unwrap();
+
+        storage1.set("key".to_string(), Clock::from([("a".to_string(), 2)]));
+        storage2.set(
+            "key".to_string(),
+            Clock::from([("a".to_string(), 1), ("b".to_string(), 4)]),
+        );
+        storage1.merge_from(&storage2).unwrap();
+        assert_eq!(
+            storage1.as_ref().get("key"),
+            Some(&Clock::from([("a".to_string(), 2), ("b".to_string(), 4)]))
+        );
+    }
+
     use quickcheck::{Arbitrary, Gen};
     use std::collections::{BTreeMap, HashSet};
 
@@ -1,5 +1,7 @@
 pub mod base_storage;
 pub mod btreemap_iter;
+pub mod codec;
+pub mod device_timestamps;
 pub mod file_storage;
 pub mod folder_storage;
 #[cfg(feature = "jni-bindings")]
@@ -21,3 +23,5 @@ pub const SCORE_STORAGE_FILE: &str = "user/scores";
 pub const INDEX_PATH: &str = "index";
 pub const PREVIEWS_STORAGE_FOLDER: &str = "cache/previews";
 pub const THUMBNAILS_STORAGE_FOLDER: &str = "cache/thumbnails";
+pub const FAVICONS_STORAGE_FOLDER: &str = "cache/favicons";
+pub const SNAPSHOTS_STORAGE_FOLDER: &str = "cache/snapshots";
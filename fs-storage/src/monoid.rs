@@ -3,6 +3,20 @@
 // so only a special function for integers is needed.
 // CRDTs can be considered later when we need to add structures that require
 // more powerful combine semantics.
+//
+// Applications merging their own value types, e.g. vector clocks or weighted
+// scores, register a combine function instead of implementing the trait,
+// which isn't possible for types defined outside of their crate. Storages of
+// such types are created with `new_registered` and resolve conflicts with the
+// function registered for their value type.
+
+use std::{
+    any::{type_name, Any, TypeId},
+    collections::HashMap,
+    sync::{Arc, OnceLock, RwLock},
+};
+
+use crate::device_timestamps::DeviceTimestamps;
 
 // Trait defining a Monoid, which represents a mathematical structure with an
 // identity element and an associative binary operation.
@@ -48,3 +62,89 @@ impl Monoid<String> for String {
         result
     }
 }
+
+/// Function combining two values of a type, see [`register_combine`].
+pub type CombineFn<V> = Arc<dyn Fn(&V, &V) -> V + Send + Sync>;
+
+/// Combine functions by value type, holding a [`CombineFn`] of the type
+type Registry = RwLock<HashMap<TypeId, Arc<dyn Any + Send + Sync>>>;
+
+fn registry() -> &'static Registry {
+    static REGISTRY: OnceLock<Registry> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let mut functions: HashMap<TypeId, Arc<dyn Any + Send + Sync>> =
+            HashMap::new();
+        functions.insert(TypeId::of::<i32>(), monoid_fn::<i32>());
+        functions.insert(TypeId::of::<String>(), monoid_fn::<String>());
+        functions.insert(
+            TypeId::of::<DeviceTimestamps>(),
+            monoid_fn::<DeviceTimestamps>(),
+        );
+        RwLock::new(functions)
+    })
+}
+
+fn monoid_fn<V: Monoid<V> + 'static>() -> Arc<dyn Any + Send + Sync> {
+    let combine: CombineFn<V> = Arc::new(V::combine);
+    Arc::new(combine)
+}
+
+/// Registers the function used by storages to combine two values of type
+/// `V` when merging, replacing the one registered before, if any.
+///
+/// The function should be associative, so that merges give the same result
+/// in any order.
+pub fn register_combine<V: 'static>(
+    combine: impl Fn(&V, &V) -> V + Send + Sync + 'static,
+) {
+    let combine: CombineFn<V> = Arc::new(combine);
+    registry()
+        .write()
+        .unwrap_or_else(|err| err.into_inner())
+        .insert(TypeId::of::<V>(), Arc::new(combine));
+}
+
+/// Registers [`Monoid::combine`] of `V` as its combine function.
+/// Functions of the monoids of this crate are registered by default.
+pub fn register_monoid<V: Monoid<V> + 'static>() {
+    register_combine::<V>(V::combine);
+}
+
+/// Returns the combine function registered for `V`
+pub fn combine_fn<V: 'static>() -> Option<CombineFn<V>> {
+    let functions = registry()
+        .read()
+        .unwrap_or_else(|err| err.into_inner());
+    functions
+        .get(&TypeId::of::<V>())?
+        .downcast_ref::<CombineFn<V>>()
+        .cloned()
+}
+
+/// Combines two values with the function registered for their type
+pub fn combine<V: 'static>(a: &V, b: &V) -> Option<V> {
+    combine_fn::<V>().map(|combine| combine(a, b))
+}
+
+/// Error message of storages merging values of a type without a combine
+/// function
+pub(crate) fn missing_combine<V>() -> String {
+    format!("No combine function registered for {}", type_name::<V>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Weighted(u32);
+
+    #[test]
+    fn test_registered_combine() {
+        assert_eq!(combine(&3, &5), Some(5));
+        assert_eq!(combine(&Weighted(1), &Weighted(2)), None);
+
+        register_combine(|a: &Weighted, b: &Weighted| Weighted(a.0 + b.0));
+        assert_eq!(combine(&Weighted(1), &Weighted(2)), Some(Weighted(3)));
+    }
+}